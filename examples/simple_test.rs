@@ -37,7 +37,7 @@ fn main() {
 
     // Test 4: Calculate weights
     println!("\n4. Testing Weight Calculation:");
-    let participants = engine.calculate_weights(&djs);
+    let participants = engine.calculate_weights(&djs, None);
     for participant in &participants {
         println!("   {} - Weight: {:.2}, Probability: {:.1}%", 
             participant.dj.name, 
@@ -48,7 +48,7 @@ fn main() {
 
     // Test 5: Run lottery
     println!("\n5. Testing Lottery Draw:");
-    if let Some(draw) = engine.draw_winner(&djs) {
+    if let Some(draw) = engine.draw_winner(&djs, None) {
         println!("   🎉 Winner: {}", draw.winner.name);
         println!("   🎲 Algorithm: {}", draw.algorithm_used);
         println!("   📊 Total participants: {}", draw.participants.len());
@@ -60,7 +60,7 @@ fn main() {
     println!("\n6. Testing Fairness (10 draws):");
     let mut winners = std::collections::HashMap::new();
     for i in 1..=10 {
-        if let Some(draw) = engine.draw_winner(&djs) {
+        if let Some(draw) = engine.draw_winner(&djs, None) {
             *winners.entry(draw.winner.name.clone()).or_insert(0) += 1;
         }
     }
@@ -1,5 +1,6 @@
 // Test example for Session-Recorder Integration
 
+use session_recorder_addon::models::MatchOutcome;
 use session_recorder_addon::services::{SessionRecorderService, SessionRecorderConfig};
 
 #[tokio::main]
@@ -14,6 +15,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         minio_secret_key: "password123".to_string(),
         bucket_name: "session-recorder".to_string(),
         public_endpoint: "http://localhost:9000".to_string(),
+        local_dir: None,
     };
 
     println!("\n1. Connecting to MinIO at {}", config.minio_endpoint);
@@ -85,12 +87,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Test 4: Demonstrate session matching
     println!("\n4. Testing session matching:");
     let test_time = chrono::Utc::now() - chrono::Duration::minutes(10);
-    match session_recorder.find_matching_session(test_time, 15).await {
-        Ok(Some(matching_session)) => {
-            println!("   🎯 Found matching session: {} ({})", 
-                matching_session.name, matching_session.id);
+    match session_recorder.find_matching_session(test_time, None, 15).await {
+        Ok(MatchOutcome::Matched { candidate }) => {
+            println!("   🎯 Found matching session: {}/{} ({:.0}% coverage)", 
+                candidate.recorder_id, candidate.recorder_session_id, candidate.coverage * 100.0);
         }
-        Ok(None) => {
+        Ok(MatchOutcome::Ambiguous { candidates }) => {
+            println!("   ⚖️  {} recorder sessions match equally well", candidates.len());
+        }
+        Ok(MatchOutcome::NoMatch) => {
             println!("   📝 No matching session found for test time");
         }
         Err(e) => {
@@ -0,0 +1,2 @@
+-- Per-event late arrival penalty curve (JSON). NULL falls back to the step cutoff.
+ALTER TABLE event_sessions ADD COLUMN late_penalty_curve TEXT;
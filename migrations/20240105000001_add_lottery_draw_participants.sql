@@ -0,0 +1,16 @@
+-- Record every participant of every draw with the factors behind their weight
+
+ALTER TABLE lottery_draws ADD COLUMN event_id TEXT REFERENCES event_sessions(id);
+
+CREATE TABLE lottery_draw_participants (
+    draw_id TEXT NOT NULL REFERENCES lottery_draws(id),
+    dj_id TEXT NOT NULL, -- No foreign key: history outlives removed DJs
+    calculated_weight REAL NOT NULL,
+    selection_probability REAL NOT NULL,
+    weight_components TEXT NOT NULL, -- JSON array of weight components
+    was_winner BOOLEAN NOT NULL DEFAULT FALSE,
+    PRIMARY KEY (draw_id, dj_id)
+);
+
+CREATE INDEX idx_lottery_draws_event_id ON lottery_draws(event_id);
+CREATE INDEX idx_lottery_draw_participants_dj_id ON lottery_draw_participants(dj_id);
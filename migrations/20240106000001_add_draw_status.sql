@@ -0,0 +1,6 @@
+-- Draws can be voided instead of deleted so the audit trail stays complete
+
+ALTER TABLE lottery_draws ADD COLUMN status TEXT NOT NULL DEFAULT 'completed';
+ALTER TABLE lottery_draws ADD COLUMN voided_at DATETIME;
+ALTER TABLE lottery_draws ADD COLUMN void_reason TEXT;
+ALTER TABLE lottery_draws ADD COLUMN replaces_draw_id TEXT REFERENCES lottery_draws(id);
@@ -0,0 +1,11 @@
+-- Queue positions booked for specific DJs (warm-up, headliner, closing); the lottery fills the rest
+
+CREATE TABLE reserved_slots (
+    id TEXT PRIMARY KEY,
+    dj_id TEXT NOT NULL UNIQUE REFERENCES djs(id),
+    position INTEGER NOT NULL UNIQUE,
+    kind TEXT NOT NULL,
+    label TEXT,
+    starts_at DATETIME,
+    created_at DATETIME NOT NULL
+);
@@ -0,0 +1,36 @@
+-- Local index of the session recorder's bucket, kept current by the background sync
+
+CREATE TABLE recorder_sessions (
+    recorder_id TEXT NOT NULL,
+    id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    start_time DATETIME NOT NULL,
+    end_time DATETIME,
+    duration_seconds INTEGER,
+    is_closed BOOLEAN NOT NULL DEFAULT FALSE,
+    keep BOOLEAN NOT NULL DEFAULT FALSE,
+    ogg_url TEXT,
+    flac_url TEXT,
+    waveform_url TEXT,
+    overview_png_url TEXT,
+    metadata_url TEXT,
+    synced_at DATETIME NOT NULL,
+    PRIMARY KEY (recorder_id, id)
+);
+
+CREATE INDEX idx_recorder_sessions_start_time ON recorder_sessions(start_time);
+
+-- Single row describing the last sync run
+CREATE TABLE recorder_sync_status (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    running BOOLEAN NOT NULL DEFAULT FALSE,
+    last_started_at DATETIME,
+    last_finished_at DATETIME,
+    last_error TEXT,
+    recorders INTEGER NOT NULL DEFAULT 0,
+    sessions_added INTEGER NOT NULL DEFAULT 0,
+    sessions_updated INTEGER NOT NULL DEFAULT 0,
+    sessions_removed INTEGER NOT NULL DEFAULT 0
+);
+
+INSERT INTO recorder_sync_status (id) VALUES (1);
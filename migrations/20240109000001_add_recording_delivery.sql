@@ -0,0 +1,2 @@
+-- When the DJ was sent the recorder's recording; keeps webhook retries from delivering twice
+ALTER TABLE sessions ADD COLUMN recording_delivered_at DATETIME;
@@ -0,0 +1,17 @@
+-- Recordings started on the session recorder for DJ slots
+
+ALTER TABLE djs ADD COLUMN keep_recording BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE TABLE slot_recordings (
+    id TEXT PRIMARY KEY,
+    event_id TEXT NOT NULL REFERENCES event_sessions(id),
+    dj_id TEXT NOT NULL REFERENCES djs(id),
+    recording_id TEXT, -- The recorder's id; NULL if starting failed
+    name TEXT NOT NULL,
+    keep BOOLEAN NOT NULL DEFAULT FALSE,
+    started_at DATETIME NOT NULL,
+    stopped_at DATETIME,
+    error TEXT
+);
+
+CREATE INDEX idx_slot_recordings_open ON slot_recordings(stopped_at);
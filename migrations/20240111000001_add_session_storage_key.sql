@@ -0,0 +1,2 @@
+-- Where the session's published file lives in the configured storage backend
+ALTER TABLE sessions ADD COLUMN storage_key TEXT;
@@ -0,0 +1,28 @@
+-- Download links handed to DJs; served by GET /dl/:token
+CREATE TABLE download_tokens (
+    token TEXT PRIMARY KEY,
+    session_id TEXT NOT NULL,
+    dj_id TEXT NOT NULL,
+    created_at DATETIME NOT NULL,
+    expires_at DATETIME NOT NULL,
+    max_downloads INTEGER,               -- NULL for unlimited
+    download_count INTEGER NOT NULL DEFAULT 0,
+    revoked_at DATETIME,
+    FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE,
+    FOREIGN KEY (dj_id) REFERENCES djs(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_download_tokens_session ON download_tokens(session_id);
+
+-- Every request for a token, including refused ones
+CREATE TABLE download_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    token TEXT NOT NULL,
+    accessed_at DATETIME NOT NULL,
+    status INTEGER NOT NULL,             -- HTTP status answered
+    range_header TEXT,
+    client_ip TEXT,
+    user_agent TEXT
+);
+
+CREATE INDEX idx_download_log_token ON download_log(token);
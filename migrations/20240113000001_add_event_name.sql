@@ -0,0 +1,2 @@
+-- Optional event name; used as the album of delivered recordings
+ALTER TABLE event_sessions ADD COLUMN name TEXT;
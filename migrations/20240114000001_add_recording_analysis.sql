@@ -0,0 +1,17 @@
+-- Loudness and silence analysis of each session's set
+CREATE TABLE recording_analyses (
+    session_id TEXT PRIMARY KEY,
+    analyzed_at DATETIME NOT NULL,
+    duration_seconds REAL NOT NULL,
+    integrated_lufs REAL,                -- NULL for a silent set
+    true_peak_dbtp REAL,
+    clipping_ratio REAL NOT NULL,
+    silent_seconds REAL NOT NULL,
+    silence TEXT NOT NULL,               -- JSON list of silent stretches
+    flags TEXT NOT NULL,                 -- JSON list of failed thresholds
+    flagged BOOLEAN NOT NULL,
+    FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
+);
+
+-- Set when a flagged recording's download email is held back for an admin
+ALTER TABLE sessions ADD COLUMN delivery_held_at DATETIME;
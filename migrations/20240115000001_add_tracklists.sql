@@ -0,0 +1,13 @@
+-- Tracks a DJ played in a session, in order
+CREATE TABLE tracklist_tracks (
+    session_id TEXT NOT NULL,
+    position INTEGER NOT NULL,           -- 1-based
+    offset_seconds REAL,                 -- From the start of the set; NULL if unknown
+    artist TEXT,
+    title TEXT NOT NULL,
+    PRIMARY KEY (session_id, position),
+    FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
+);
+
+-- Set whenever a session's tracklist is replaced
+ALTER TABLE sessions ADD COLUMN tracklist_updated_at DATETIME;
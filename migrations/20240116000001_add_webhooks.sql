@@ -0,0 +1,30 @@
+-- Outgoing webhook subscriptions registered by admins
+CREATE TABLE webhook_subscriptions (
+    id TEXT PRIMARY KEY,
+    url TEXT NOT NULL,
+    secret TEXT NOT NULL,                -- HMAC-SHA256 key for the signature header
+    event_types TEXT NOT NULL,           -- JSON array of event types; empty for all
+    description TEXT,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at DATETIME NOT NULL
+);
+
+-- One row per event per subscription, kept as the delivery log
+CREATE TABLE webhook_deliveries (
+    id TEXT PRIMARY KEY,
+    subscription_id TEXT NOT NULL,
+    event_type TEXT NOT NULL,
+    payload TEXT NOT NULL,               -- JSON body exactly as signed and sent
+    status TEXT NOT NULL DEFAULT 'pending', -- pending, delivered or failed
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at DATETIME,            -- NULL once delivered or given up
+    last_attempt_at DATETIME,
+    response_status INTEGER,             -- HTTP status of the last attempt
+    last_error TEXT,
+    created_at DATETIME NOT NULL,
+    delivered_at DATETIME,
+    FOREIGN KEY (subscription_id) REFERENCES webhook_subscriptions(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_webhook_deliveries_due ON webhook_deliveries(status, next_attempt_at);
+CREATE INDEX idx_webhook_deliveries_subscription ON webhook_deliveries(subscription_id, created_at);
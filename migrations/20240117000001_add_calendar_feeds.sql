@@ -0,0 +1,4 @@
+-- Secret part of each DJ's personal iCalendar feed URL, issued on first request
+ALTER TABLE djs ADD COLUMN calendar_token TEXT;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_djs_calendar_token ON djs(calendar_token);
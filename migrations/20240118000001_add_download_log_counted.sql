@@ -0,0 +1,5 @@
+-- Whether a request used up one of the link's downloads; later ranges from the same
+-- client only continue a download if one was counted shortly before
+ALTER TABLE download_log ADD COLUMN counted BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE INDEX IF NOT EXISTS idx_download_log_counted ON download_log(token, client_ip, accessed_at) WHERE counted;
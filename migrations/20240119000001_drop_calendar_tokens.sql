@@ -0,0 +1,3 @@
+-- DJ calendar feeds are served by DJ ID; their slots are public in the event feed anyway
+DROP INDEX IF EXISTS idx_djs_calendar_token;
+ALTER TABLE djs DROP COLUMN calendar_token;
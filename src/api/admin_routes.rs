@@ -1,12 +1,14 @@
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
-    response::Json,
+    body::Body,
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Json},
     routing::{get, post, put, delete},
     Router,
 };
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+use tokio_util::io::ReaderStream;
 
 use crate::{
     models::{
@@ -14,8 +16,14 @@ use crate::{
         dj::{DjResponse, UpdateDjRequest},
         session::B2BSessionRequest,
         lottery::LotteryStatistics,
+        SimulationError, SimulationRequest,
+        CreateReservedSlotRequest, ReservedSlotError, ReservedSlotKind, ReservedSlotResponse,
+        ArchiveError, ArchiveFormat,
+        AnalysisError, RecordingAnalysisResponse,
+        HookResult, HookTrigger, OutputHook,
+        CreateWebhookRequest, UpdateWebhookRequest, WebhookError,
     },
-    services::{DjService, LotteryService, SessionService},
+    services::{AnalysisService, ArchiveService, DjService, LotteryService, OutputService, ReservedSlotService, SessionService, WebhookService},
 };
 
 pub fn router() -> Router<Arc<AppState>> {
@@ -29,6 +37,18 @@ pub fn router() -> Router<Arc<AppState>> {
         .route("/sessions/b2b", post(create_b2b_session))
         .route("/statistics", get(get_admin_statistics))
         .route("/timetable", get(get_timetable))
+        .route("/lottery/simulate", post(simulate_lottery))
+        .route("/reserved-slots", get(get_reserved_slots).post(create_reserved_slot))
+        .route("/reserved-slots/:id", delete(delete_reserved_slot))
+        .route("/events/:id/archive", get(download_event_archive))
+        .route("/analyses", get(get_analyses))
+        .route("/sessions/:id/release", post(release_delivery))
+        .route("/outputs", get(get_output_hooks))
+        .route("/outputs/test", post(test_output_hooks))
+        .route("/webhooks", get(get_webhooks).post(create_webhook))
+        .route("/webhooks/:id", get(get_webhook).put(update_webhook).delete(delete_webhook))
+        .route("/webhooks/:id/deliveries", get(get_webhook_deliveries))
+        .route("/webhooks/:id/ping", post(ping_webhook))
 }
 
 #[derive(Deserialize)]
@@ -36,6 +56,27 @@ struct MovePositionRequest {
     new_position: i32,
 }
 
+#[derive(Deserialize)]
+struct ArchiveQuery {
+    format: Option<ArchiveFormat>,
+}
+
+#[derive(Deserialize)]
+struct AnalysesQuery {
+    #[serde(default)]
+    flagged: bool,
+}
+
+#[derive(Deserialize)]
+struct OutputTestQuery {
+    trigger: HookTrigger,
+}
+
+#[derive(Serialize)]
+struct ReleaseResponse {
+    emailed: bool, // False if the DJ left no address
+}
+
 #[derive(Serialize)]
 struct AdminQueueResponse {
     lottery_pool: Vec<DjResponse>,
@@ -58,6 +99,7 @@ struct TimetableEntry {
     dj: DjResponse,
     estimated_start_time: Option<chrono::DateTime<chrono::Utc>>,
     session_type: String,
+    reserved_kind: Option<ReservedSlotKind>,
 }
 
 async fn get_all_djs_admin(
@@ -191,9 +233,11 @@ async fn get_timetable(
     State(app_state): State<Arc<AppState>>,
 ) -> Result<Json<Vec<TimetableEntry>>, StatusCode> {
     let lottery_service = LotteryService::new(app_state.clone());
+    let reserved_slot_service = ReservedSlotService::new(app_state.clone());
     let session_service = SessionService::new(app_state);
     
     let current_queue = lottery_service.get_current_queue().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let reserved_slots = reserved_slot_service.get_reserved_slots().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     
     let mut timetable = Vec::new();
     let mut estimated_time = chrono::Utc::now();
@@ -204,17 +248,253 @@ async fn get_timetable(
         estimated_time = estimated_time + chrono::Duration::minutes(60); // Assume 60 min default
     }
     
+    let mut previous_position = 0;
     for (i, dj) in current_queue.iter().enumerate() {
+        let position = dj.position_in_queue.unwrap_or((i + 1) as i32);
+
+        // Open lottery slots before a reserved one still take time
+        if previous_position > 0 {
+            estimated_time += chrono::Duration::minutes(60 * (position - previous_position) as i64);
+        }
+        previous_position = position;
+
         timetable.push(TimetableEntry {
-            position: (i + 1) as i32,
+            position,
             dj: dj.clone(),
             estimated_start_time: Some(estimated_time),
             session_type: "solo".to_string(),
+            reserved_kind: reserved_slots.iter().find(|slot| slot.dj_id == dj.id).map(|slot| slot.kind),
         });
-        
-        // Add estimated duration for next DJ (60 minutes default)
-        estimated_time = estimated_time + chrono::Duration::minutes(60);
     }
 
     Ok(Json(timetable))
-}
\ No newline at end of file
+}
+
+async fn simulate_lottery(
+    State(app_state): State<Arc<AppState>>,
+    Json(request): Json<SimulationRequest>,
+) -> impl IntoResponse {
+    let lottery_service = LotteryService::new(app_state);
+
+    match lottery_service.get_fairness_report(request).await {
+        Ok(report) => (StatusCode::OK, Json(report)).into_response(),
+        Err(e) => {
+            let status = match e.downcast_ref::<SimulationError>() {
+                Some(_) => StatusCode::BAD_REQUEST,
+                None => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            (status, e.to_string()).into_response()
+        }
+    }
+}
+
+async fn get_reserved_slots(
+    State(app_state): State<Arc<AppState>>,
+) -> Result<Json<Vec<ReservedSlotResponse>>, StatusCode> {
+    let reserved_slot_service = ReservedSlotService::new(app_state);
+
+    match reserved_slot_service.get_reserved_slots().await {
+        Ok(slots) => Ok(Json(slots)),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+async fn create_reserved_slot(
+    State(app_state): State<Arc<AppState>>,
+    Json(request): Json<CreateReservedSlotRequest>,
+) -> impl IntoResponse {
+    let reserved_slot_service = ReservedSlotService::new(app_state);
+
+    match reserved_slot_service.create_reserved_slot(request).await {
+        Ok(slot) => (StatusCode::CREATED, Json(slot)).into_response(),
+        Err(e) => reserved_slot_error_response(e),
+    }
+}
+
+async fn delete_reserved_slot(
+    State(app_state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    let reserved_slot_service = ReservedSlotService::new(app_state);
+
+    match reserved_slot_service.delete_reserved_slot(&id).await {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => reserved_slot_error_response(e),
+    }
+}
+
+fn reserved_slot_error_response(e: anyhow::Error) -> axum::response::Response {
+    let status = match e.downcast_ref::<ReservedSlotError>() {
+        Some(ReservedSlotError::DjNotFound) | Some(ReservedSlotError::NotFound) => StatusCode::NOT_FOUND,
+        Some(ReservedSlotError::PositionTaken(_)) | Some(ReservedSlotError::AlreadyReserved) => StatusCode::CONFLICT,
+        Some(_) => StatusCode::BAD_REQUEST,
+        None => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    (status, e.to_string()).into_response()
+}
+
+/// Streams the archive as it is written, so recordings never sit in memory whole
+async fn download_event_archive(
+    State(app_state): State<Arc<AppState>>,
+    Path(event_id): Path<String>,
+    Query(query): Query<ArchiveQuery>,
+) -> impl IntoResponse {
+    let format = query.format.unwrap_or_default();
+    let archive = match ArchiveService::new(app_state).prepare(&event_id).await {
+        Ok(archive) => archive,
+        Err(e) => {
+            let status = match e.downcast_ref::<ArchiveError>() {
+                Some(ArchiveError::EventNotFound) => StatusCode::NOT_FOUND,
+                Some(_) => StatusCode::BAD_REQUEST,
+                None => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            return (status, e.to_string()).into_response();
+        }
+    };
+
+    let disposition = format!("attachment; filename=\"{}\"", archive.filename(format).replace('"', ""));
+    let (writer, reader) = tokio::io::duplex(64 * 1024);
+    tokio::spawn(async move {
+        // A failure here cuts the response short; the client sees a truncated archive
+        if let Err(e) = archive.write(format, writer).await {
+            tracing::error!("Writing archive of event {} failed: {}", archive.event.id, e);
+        }
+    });
+
+    (
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        Body::from_stream(ReaderStream::new(reader)),
+    )
+        .into_response()
+}
+
+async fn get_analyses(
+    State(app_state): State<Arc<AppState>>,
+    Query(query): Query<AnalysesQuery>,
+) -> Result<Json<Vec<RecordingAnalysisResponse>>, StatusCode> {
+    let analysis_service = AnalysisService::new(app_state);
+
+    match analysis_service.list_analyses(query.flagged).await {
+        Ok(analyses) => Ok(Json(analyses)),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+/// Sends the download email a flagged recording was held back from
+async fn release_delivery(
+    State(app_state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    let analysis_service = AnalysisService::new(app_state);
+
+    match analysis_service.release_delivery(&id).await {
+        Ok(emailed) => Json(ReleaseResponse { emailed }).into_response(),
+        Err(e) => {
+            let status = match e.downcast_ref::<AnalysisError>() {
+                Some(AnalysisError::NotHeld) => StatusCode::CONFLICT,
+                Some(_) => StatusCode::NOT_FOUND,
+                None => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            (status, e.to_string()).into_response()
+        }
+    }
+}
+
+async fn get_output_hooks(State(app_state): State<Arc<AppState>>) -> Json<Vec<OutputHook>> {
+    Json(app_state.config.output_config.hooks.clone())
+}
+
+/// Fires the hooks of a trigger now, so the lighting and VJ desks can be checked before doors
+async fn test_output_hooks(
+    State(app_state): State<Arc<AppState>>,
+    Query(query): Query<OutputTestQuery>,
+) -> Result<Json<Vec<HookResult>>, (StatusCode, String)> {
+    let output_service = OutputService::new(app_state);
+
+    match output_service.test_hooks(query.trigger).await {
+        Ok(results) => Ok(Json(results)),
+        Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string())),
+    }
+}
+
+fn webhook_error_response(e: anyhow::Error) -> axum::response::Response {
+    let status = match e.downcast_ref::<WebhookError>() {
+        Some(WebhookError::NotFound) => StatusCode::NOT_FOUND,
+        Some(_) => StatusCode::BAD_REQUEST,
+        None => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    (status, e.to_string()).into_response()
+}
+
+async fn get_webhooks(State(app_state): State<Arc<AppState>>) -> impl IntoResponse {
+    match WebhookService::new(app_state).list_subscriptions().await {
+        Ok(subscriptions) => Json(subscriptions).into_response(),
+        Err(e) => webhook_error_response(e),
+    }
+}
+
+/// The answer carries the signing secret; it isn't shown again
+async fn create_webhook(
+    State(app_state): State<Arc<AppState>>,
+    Json(request): Json<CreateWebhookRequest>,
+) -> impl IntoResponse {
+    match WebhookService::new(app_state).create_subscription(request).await {
+        Ok(subscription) => (StatusCode::CREATED, Json(subscription)).into_response(),
+        Err(e) => webhook_error_response(e),
+    }
+}
+
+async fn get_webhook(
+    State(app_state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    match WebhookService::new(app_state).get_subscription(&id).await {
+        Ok(subscription) => Json(subscription).into_response(),
+        Err(e) => webhook_error_response(e),
+    }
+}
+
+async fn update_webhook(
+    State(app_state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(request): Json<UpdateWebhookRequest>,
+) -> impl IntoResponse {
+    match WebhookService::new(app_state).update_subscription(&id, request).await {
+        Ok(subscription) => Json(subscription).into_response(),
+        Err(e) => webhook_error_response(e),
+    }
+}
+
+async fn delete_webhook(
+    State(app_state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    match WebhookService::new(app_state).delete_subscription(&id).await {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => webhook_error_response(e),
+    }
+}
+
+async fn get_webhook_deliveries(
+    State(app_state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    match WebhookService::new(app_state).get_deliveries(&id).await {
+        Ok(deliveries) => Json(deliveries).into_response(),
+        Err(e) => webhook_error_response(e),
+    }
+}
+
+/// Sends a `ping` to the subscription right away and answers with the delivery
+async fn ping_webhook(
+    State(app_state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    match WebhookService::new(app_state).ping(&id).await {
+        Ok(delivery) => Json(delivery).into_response(),
+        Err(e) => webhook_error_response(e),
+    }
+}
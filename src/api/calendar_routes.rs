@@ -0,0 +1,48 @@
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use std::sync::Arc;
+
+use crate::{
+    models::{AppState, CalendarError},
+    services::CalendarService,
+};
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new().route("/djs/:feed", get(get_dj_feed))
+}
+
+/// A DJ's slots, `/api/calendar/djs/<dj id>.ics`; public like the event feed it is cut from
+async fn get_dj_feed(
+    State(app_state): State<Arc<AppState>>,
+    Path(feed): Path<String>,
+) -> Response {
+    let dj_id = feed.strip_suffix(".ics").unwrap_or(&feed);
+    calendar_response(CalendarService::new(app_state).dj_feed(dj_id).await, "slots.ics")
+}
+
+/// `text/calendar` answer, or the error mapped to its status
+pub(super) fn calendar_response(result: anyhow::Result<String>, filename: &str) -> Response {
+    match result {
+        Ok(calendar) => (
+            [
+                (header::CONTENT_TYPE, "text/calendar; charset=utf-8".to_string()),
+                (header::CONTENT_DISPOSITION, format!("inline; filename=\"{}\"", filename)),
+                (header::CACHE_CONTROL, "no-cache".to_string()),
+            ],
+            calendar,
+        )
+            .into_response(),
+        Err(e) => {
+            let status = match e.downcast_ref::<CalendarError>() {
+                Some(_) => StatusCode::NOT_FOUND,
+                None => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            (status, e.to_string()).into_response()
+        }
+    }
+}
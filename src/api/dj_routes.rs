@@ -11,8 +11,10 @@ use crate::{
     models::{
         AppState,
         dj::{CreateDjRequest, UpdateDjRequest, DjResponse, DjPool, GuestRequest},
+        lottery::DjOdds,
+        CalendarError, CalendarFeedResponse,
     },
-    services::DjService,
+    services::{CalendarService, DjService, LotteryService},
 };
 
 pub fn router() -> Router<Arc<AppState>> {
@@ -24,6 +26,8 @@ pub fn router() -> Router<Arc<AppState>> {
         .route("/:id", put(update_dj))
         .route("/:id", delete(remove_dj))
         .route("/:id/request", post(submit_guest_request))
+        .route("/:id/odds", get(get_dj_odds))
+        .route("/:id/calendar", get(get_dj_calendar))
 }
 
 async fn get_all_djs(
@@ -111,4 +115,31 @@ async fn submit_guest_request(
         Ok(()) => Ok(StatusCode::CREATED),
         Err(_) => Err(StatusCode::BAD_REQUEST),
     }
-}
\ No newline at end of file
+}
+
+async fn get_dj_odds(
+    State(app_state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<DjOdds>, StatusCode> {
+    let lottery_service = LotteryService::new(app_state);
+    
+    match lottery_service.get_dj_odds(&id).await {
+        Ok(Some(odds)) => Ok(Json(odds)),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+/// URL of the DJ's calendar feed with their slots
+async fn get_dj_calendar(
+    State(app_state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<CalendarFeedResponse>, StatusCode> {
+    let calendar_service = CalendarService::new(app_state);
+
+    match calendar_service.dj_feed_url(&id).await {
+        Ok(feed) => Ok(Json(feed)),
+        Err(e) if e.downcast_ref::<CalendarError>().is_some() => Err(StatusCode::NOT_FOUND),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
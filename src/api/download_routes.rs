@@ -0,0 +1,127 @@
+use axum::{
+    body::Body,
+    extract::{ConnectInfo, Path, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
+    routing::get,
+    Router,
+};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio_util::io::ReaderStream;
+
+use crate::{
+    models::{
+        download::{DownloadClient, DownloadLogEntry},
+        storage::StorageError,
+        AppState,
+    },
+    services::{download_status, DownloadService},
+};
+
+/// Admin endpoints, mounted at `/api/downloads`
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/:token", get(get_download_token).delete(revoke_download_token))
+        .route("/:token/log", get(get_download_log))
+}
+
+/// The links handed to DJs, mounted at `/dl`
+pub fn public_router() -> Router<Arc<AppState>> {
+    Router::new().route("/:token", get(download))
+}
+
+async fn download(
+    State(app_state): State<Arc<AppState>>,
+    Path(token): Path<String>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    headers: HeaderMap,
+) -> Response {
+    let header_value = |name: header::HeaderName| headers.get(name).and_then(|value| value.to_str().ok());
+
+    // Behind a reverse proxy the peer address is the proxy's
+    let client = DownloadClient {
+        ip: DownloadClient::resolve_ip(
+            connect_info.map(|ConnectInfo(addr)| addr.ip()),
+            header_value(header::HeaderName::from_static("x-forwarded-for")),
+            &app_state.config.download_config.trusted_proxies,
+        ),
+        user_agent: header_value(header::USER_AGENT).map(str::to_string),
+    };
+    let download_service = DownloadService::new(app_state);
+
+    match download_service.open_download(&token, header_value(header::RANGE), &client).await {
+        Ok(file) => {
+            let range = file.stream.range.clone();
+            let mut response = Response::builder()
+                .status(if file.partial { StatusCode::PARTIAL_CONTENT } else { StatusCode::OK })
+                .header(header::CONTENT_TYPE, file.content_type)
+                .header(header::CONTENT_LENGTH, range.end - range.start)
+                .header(header::ACCEPT_RANGES, "bytes")
+                .header(
+                    header::CONTENT_DISPOSITION,
+                    format!("attachment; filename=\"{}\"", file.filename.replace('"', "")),
+                );
+            if file.partial {
+                response = response.header(
+                    header::CONTENT_RANGE,
+                    format!("bytes {}-{}/{}", range.start, range.end - 1, file.stream.size),
+                );
+            }
+
+            response
+                .body(Body::from_stream(ReaderStream::new(file.stream.body)))
+                .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())
+        }
+        Err(e) => {
+            if let Some(StorageError::RangeNotSatisfiable { size }) = e.downcast_ref::<StorageError>() {
+                return (
+                    StatusCode::RANGE_NOT_SATISFIABLE,
+                    [(header::CONTENT_RANGE, format!("bytes */{}", size))],
+                    e.to_string(),
+                )
+                    .into_response();
+            }
+            let status = StatusCode::from_u16(download_status(&e)).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+            (status, e.to_string()).into_response()
+        }
+    }
+}
+
+async fn get_download_token(
+    State(app_state): State<Arc<AppState>>,
+    Path(token): Path<String>,
+) -> Response {
+    let download_service = DownloadService::new(app_state);
+
+    match download_service.get_token(&token).await {
+        Ok(Some(token)) => Json(download_service.to_response(token)).into_response(),
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+async fn revoke_download_token(
+    State(app_state): State<Arc<AppState>>,
+    Path(token): Path<String>,
+) -> StatusCode {
+    let download_service = DownloadService::new(app_state);
+
+    match download_service.revoke_token(&token).await {
+        Ok(true) => StatusCode::NO_CONTENT,
+        Ok(false) => StatusCode::NOT_FOUND,
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+async fn get_download_log(
+    State(app_state): State<Arc<AppState>>,
+    Path(token): Path<String>,
+) -> Result<Json<Vec<DownloadLogEntry>>, StatusCode> {
+    let download_service = DownloadService::new(app_state);
+
+    match download_service.get_access_log(&token).await {
+        Ok(entries) => Ok(Json(entries)),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
@@ -1,13 +1,29 @@
-use crate::models::{AppState, event_session::StartEventRequest};
-use crate::services::EventService;
+use crate::models::{AppState, NowPlaying, ShareImageError, ShareImageRequest, TimetableExportError, TimetableFormat, event_session::StartEventRequest, late_penalty::{PenaltyCurveError, PenaltyPreviewRequest}};
+use crate::services::{CalendarService, EventService, NowPlayingService, ShareImageService, TimetableExportService};
+use super::calendar_routes::calendar_response;
 use axum::{
-    extract::State,
-    http::StatusCode,
-    response::IntoResponse,
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse,
+    },
     routing::{get, post},
     Json, Router,
 };
+use futures_util::stream::{self, Stream};
+use serde::Deserialize;
+use std::convert::Infallible;
 use std::sync::Arc;
+use std::time::Duration;
+
+/// How often the live stream looks for a new set or track
+const LIVE_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Deserialize)]
+struct TimetableExportQuery {
+    format: Option<TimetableFormat>,
+}
 
 pub fn router() -> Router<Arc<AppState>> {
     Router::new()
@@ -15,6 +31,13 @@ pub fn router() -> Router<Arc<AppState>> {
         .route("/current", get(get_current_event))
         .route("/end", post(end_event))
         .route("/timetable", get(get_timetable))
+        .route("/recordings", get(get_slot_recordings))
+        .route("/penalty-preview", get(get_penalty_preview).post(preview_penalty_curve))
+        .route("/now-playing", get(get_now_playing))
+        .route("/live", get(live_events))
+        .route("/:id/timetable", get(export_timetable))
+        .route("/:id/timetable.ics", get(get_timetable_calendar))
+        .route("/:id/poster.png", get(get_poster))
 }
 
 async fn start_event(
@@ -57,3 +80,146 @@ async fn get_timetable(State(app_state): State<Arc<AppState>>) -> impl IntoRespo
         Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
     }
 }
+
+/// Any event's timetable as CSV, JSON (default), Markdown or a printable HTML page
+async fn export_timetable(
+    State(app_state): State<Arc<AppState>>,
+    Path(event_id): Path<String>,
+    Query(query): Query<TimetableExportQuery>,
+) -> impl IntoResponse {
+    let service = TimetableExportService::new(app_state);
+
+    match service.export(&event_id, query.format.unwrap_or_default()).await {
+        Ok(export) => (
+            [
+                (header::CONTENT_TYPE, export.content_type.to_string()),
+                (header::CONTENT_DISPOSITION, format!("inline; filename=\"{}\"", export.filename.replace('"', ""))),
+            ],
+            export.body,
+        )
+            .into_response(),
+        Err(e) => {
+            let status = match e.downcast_ref::<TimetableExportError>() {
+                Some(TimetableExportError::EventNotFound) => StatusCode::NOT_FOUND,
+                Some(_) => StatusCode::BAD_REQUEST,
+                None => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            (status, e.to_string()).into_response()
+        }
+    }
+}
+
+/// The event's timetable as an iCalendar feed, for the venue's calendar
+async fn get_timetable_calendar(
+    State(app_state): State<Arc<AppState>>,
+    Path(event_id): Path<String>,
+) -> impl IntoResponse {
+    calendar_response(CalendarService::new(app_state).event_feed(&event_id).await, "timetable.ics")
+}
+
+/// Share image of the event: the lineup poster, the now playing story or a DJ's set online card
+async fn get_poster(
+    State(app_state): State<Arc<AppState>>,
+    Path(event_id): Path<String>,
+    Query(request): Query<ShareImageRequest>,
+) -> impl IntoResponse {
+    let service = ShareImageService::new(app_state);
+
+    match service.render(&event_id, request).await {
+        Ok(png) => (
+            [
+                (header::CONTENT_TYPE, "image/png"),
+                // The lineup and the set playing change during the night
+                (header::CACHE_CONTROL, "no-cache"),
+            ],
+            png,
+        )
+            .into_response(),
+        Err(e) => {
+            let status = match e.downcast_ref::<ShareImageError>() {
+                Some(ShareImageError::TokenRequired) => StatusCode::BAD_REQUEST,
+                Some(_) => StatusCode::NOT_FOUND,
+                None => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            (status, e.to_string()).into_response()
+        }
+    }
+}
+
+async fn get_slot_recordings(State(app_state): State<Arc<AppState>>) -> impl IntoResponse {
+    let service = EventService::new(app_state);
+
+    match service.get_slot_recordings().await {
+        Ok(recordings) => (StatusCode::OK, Json(recordings)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+async fn get_penalty_preview(State(app_state): State<Arc<AppState>>) -> impl IntoResponse {
+    let service = EventService::new(app_state);
+
+    match service.preview_late_penalty(PenaltyPreviewRequest::default()).await {
+        Ok(preview) => (StatusCode::OK, Json(preview)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+async fn preview_penalty_curve(
+    State(app_state): State<Arc<AppState>>,
+    Json(request): Json<PenaltyPreviewRequest>,
+) -> impl IntoResponse {
+    let service = EventService::new(app_state);
+
+    match service.preview_late_penalty(request).await {
+        Ok(preview) => (StatusCode::OK, Json(preview)).into_response(),
+        Err(e) => {
+            let status = match e.downcast_ref::<PenaltyCurveError>() {
+                Some(_) => StatusCode::BAD_REQUEST,
+                None => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            (status, e.to_string()).into_response()
+        }
+    }
+}
+
+async fn get_now_playing(State(app_state): State<Arc<AppState>>) -> impl IntoResponse {
+    let service = NowPlayingService::new(app_state);
+
+    match service.now_playing().await {
+        Ok(Some(now_playing)) => (StatusCode::OK, Json(now_playing)).into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, "No session in progress").into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// Server-sent events: a `now_playing` event on connect and whenever the set or track
+/// changes, with `null` between sets
+async fn live_events(State(app_state): State<Arc<AppState>>) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let service = NowPlayingService::new(app_state);
+    let events = stream::unfold((service, None::<Option<NowPlaying>>), |(service, mut sent)| async move {
+        loop {
+            if sent.is_some() {
+                tokio::time::sleep(LIVE_POLL_INTERVAL).await;
+            }
+            let now_playing = match service.now_playing().await {
+                Ok(now_playing) => now_playing,
+                Err(e) => {
+                    tracing::error!("Error reading now playing for live stream: {}", e);
+                    sent.get_or_insert(None);
+                    continue;
+                }
+            };
+            if sent.as_ref() == Some(&now_playing) {
+                continue;
+            }
+            let event = Event::default()
+                .event("now_playing")
+                .json_data(&now_playing)
+                .unwrap_or_else(|_| Event::default().event("now_playing").data("null"));
+            sent = Some(now_playing);
+            return Some((Ok(event), (service, sent)));
+        }
+    });
+
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
@@ -1,7 +1,7 @@
 use axum::{
-    extract::State,
+    extract::{Path, State},
     http::StatusCode,
-    response::Json,
+    response::{IntoResponse, Json},
     routing::{get, post},
     Router,
 };
@@ -10,7 +10,7 @@ use std::sync::Arc;
 use crate::{
     models::{
         AppState,
-        lottery::{LotteryDraw, LotteryStatistics},
+        lottery::{LotteryDraw, LotteryStatistics, VoidDrawError, VoidDrawRequest},
         dj::DjResponse,
     },
     services::LotteryService,
@@ -23,6 +23,7 @@ pub fn router() -> Router<Arc<AppState>> {
         .route("/next", get(get_next_dj))
         .route("/statistics", get(get_lottery_statistics))
         .route("/reset", post(reset_lottery))
+        .route("/draws/:id/void", post(void_draw))
 }
 
 async fn draw_next_dj(
@@ -78,4 +79,25 @@ async fn reset_lottery(
         Ok(()) => Ok(StatusCode::OK),
         Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
     }
-}
\ No newline at end of file
+}
+
+async fn void_draw(
+    State(app_state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(request): Json<VoidDrawRequest>,
+) -> impl IntoResponse {
+    let lottery_service = LotteryService::new(app_state);
+
+    match lottery_service.void_draw(&id, request).await {
+        Ok(response) => (StatusCode::OK, Json(response)).into_response(),
+        Err(e) => {
+            let status = match e.downcast_ref::<VoidDrawError>() {
+                Some(VoidDrawError::MissingReason) => StatusCode::BAD_REQUEST,
+                Some(VoidDrawError::NotFound) => StatusCode::NOT_FOUND,
+                Some(VoidDrawError::AlreadyVoided) | Some(VoidDrawError::WinnerAlreadyPlayed) => StatusCode::CONFLICT,
+                None => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            (status, e.to_string()).into_response()
+        }
+    }
+}
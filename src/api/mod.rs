@@ -9,6 +9,8 @@ mod lottery_routes;
 mod admin_routes;
 mod session_recorder_routes;
 mod event_routes;
+mod calendar_routes;
+pub mod download_routes;
 
 pub fn router() -> Router<Arc<AppState>> {
     Router::new()
@@ -18,4 +20,6 @@ pub fn router() -> Router<Arc<AppState>> {
         .nest("/admin", admin_routes::router())
         .nest("/session-recorder", session_recorder_routes::router())
         .nest("/event", event_routes::router())
+        .nest("/calendar", calendar_routes::router())
+        .nest("/downloads", download_routes::router())
 }
\ No newline at end of file
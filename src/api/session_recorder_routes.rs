@@ -1,7 +1,8 @@
 use axum::{
+    body::Bytes,
     extract::{Path, Query, State},
-    http::StatusCode,
-    response::Json,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Json},
     routing::{get, post},
     Router,
 };
@@ -9,8 +10,11 @@ use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
 use crate::{
-    models::AppState,
-    services::{SessionService, RecorderSession},
+    models::{
+        AppState, EventLinkReport, MatchOutcome, RecorderSyncError, RecorderSyncRequest, RecorderSyncStatus,
+        RecorderWebhookError, SegmentRecordingRequest, SegmentationError, SessionLinkResult, WEBHOOK_SIGNATURE_HEADER,
+    },
+    services::{RecorderIndexService, RecorderWebhookService, SegmentationService, SessionService, RecorderSession},
 };
 
 pub fn router() -> Router<Arc<AppState>> {
@@ -18,8 +22,13 @@ pub fn router() -> Router<Arc<AppState>> {
         .route("/available-sessions", get(get_available_recorder_sessions))
         .route("/link/:session_id/:recorder_id/:recorder_session_id", post(link_session_to_recorder))
         .route("/auto-link/:session_id", post(auto_link_session))
+        .route("/auto-link/event/:event_id", post(auto_link_event))
         .route("/download/:session_id/:format", get(get_recording_download_url))
         .route("/session/:session_id", get(get_session_with_recorder_info))
+        .route("/segment", post(segment_recording))
+        .route("/sync", post(trigger_recorder_sync))
+        .route("/sync/status", get(get_recorder_sync_status))
+        .route("/webhook", post(recorder_webhook))
 }
 
 #[derive(Deserialize)]
@@ -30,11 +39,8 @@ struct AutoLinkQuery {
 async fn get_available_recorder_sessions(
     State(app_state): State<Arc<AppState>>,
 ) -> Result<Json<Vec<RecorderSession>>, StatusCode> {
-    let session_service = match SessionService::new_with_recorder(app_state).await {
-        Ok(service) => service,
-        Err(_) => return Err(StatusCode::SERVICE_UNAVAILABLE),
-    };
-    
+    let session_service = SessionService::new(app_state);
+
     match session_service.get_available_recorder_sessions().await {
         Ok(sessions) => Ok(Json(sessions)),
         Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
@@ -61,31 +67,36 @@ async fn auto_link_session(
     Path(session_id): Path<String>,
     Query(params): Query<AutoLinkQuery>,
 ) -> Result<Json<AutoLinkResponse>, StatusCode> {
-    let session_service = match SessionService::new_with_recorder(app_state.clone()).await {
-        Ok(service) => service,
-        Err(_) => return Err(StatusCode::SERVICE_UNAVAILABLE),
-    };
+    let session_service = SessionService::new(app_state);
 
-    // Get the session start time
-    let session = match session_service.get_session_by_id(&session_id).await {
-        Ok(Some(session)) => session,
-        Ok(None) => return Err(StatusCode::NOT_FOUND),
-        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
-    };
+    match session_service.auto_link_recorder_session(&session_id, params.tolerance_minutes).await {
+        Ok(Some(result)) => {
+            let (success, message) = match &result.outcome {
+                MatchOutcome::Matched { .. } => (true, "Session successfully auto-linked to recorder session"),
+                MatchOutcome::Ambiguous { .. } => (false, "Several recorder sessions match; link one manually"),
+                MatchOutcome::NoMatch => (false, "No matching recorder session found"),
+            };
+            Ok(Json(AutoLinkResponse {
+                success,
+                message: message.to_string(),
+                result,
+            }))
+        }
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
 
-    let session_start = chrono::DateTime::parse_from_rfc3339(&session.started_at.to_rfc3339())
-        .unwrap()
-        .with_timezone(&chrono::Utc);
-
-    match session_service.auto_link_recorder_session(&session_id, session_start).await {
-        Ok(linked) => Ok(Json(AutoLinkResponse { 
-            success: linked,
-            message: if linked { 
-                "Session successfully auto-linked to recorder session".to_string() 
-            } else { 
-                "No matching recorder session found".to_string() 
-            }
-        })),
+async fn auto_link_event(
+    State(app_state): State<Arc<AppState>>,
+    Path(event_id): Path<String>,
+    Query(params): Query<AutoLinkQuery>,
+) -> Result<Json<EventLinkReport>, StatusCode> {
+    let session_service = SessionService::new(app_state);
+
+    match session_service.auto_link_event(&event_id, params.tolerance_minutes).await {
+        Ok(Some(report)) => Ok(Json(report)),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
         Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
     }
 }
@@ -144,10 +155,90 @@ async fn get_session_with_recorder_info(
     }
 }
 
+async fn segment_recording(
+    State(app_state): State<Arc<AppState>>,
+    Json(request): Json<SegmentRecordingRequest>,
+) -> impl IntoResponse {
+    let segmentation_service = SegmentationService::new(app_state);
+
+    match segmentation_service.segment_recording(request).await {
+        Ok(report) => (StatusCode::OK, Json(report)).into_response(),
+        Err(e) => {
+            let status = match e.downcast_ref::<SegmentationError>() {
+                Some(SegmentationError::MissingSource) | Some(SegmentationError::MissingStartTime) => StatusCode::BAD_REQUEST,
+                Some(SegmentationError::FileNotFound) | Some(SegmentationError::NoRecording) => StatusCode::NOT_FOUND,
+                Some(SegmentationError::RecorderUnavailable) => StatusCode::SERVICE_UNAVAILABLE,
+                None => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            (status, e.to_string()).into_response()
+        }
+    }
+}
+
+async fn trigger_recorder_sync(
+    State(app_state): State<Arc<AppState>>,
+    Query(request): Query<RecorderSyncRequest>,
+) -> impl IntoResponse {
+    let index_service = RecorderIndexService::new(app_state);
+
+    match index_service.trigger_sync(request.full).await {
+        Ok(()) => StatusCode::ACCEPTED.into_response(),
+        Err(e) => {
+            let status = match e.downcast_ref::<RecorderSyncError>() {
+                Some(RecorderSyncError::AlreadyRunning) => StatusCode::CONFLICT,
+                Some(RecorderSyncError::Disabled) => StatusCode::SERVICE_UNAVAILABLE,
+                None => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            (status, e.to_string()).into_response()
+        }
+    }
+}
+
+async fn get_recorder_sync_status(
+    State(app_state): State<Arc<AppState>>,
+) -> Result<Json<RecorderSyncStatus>, StatusCode> {
+    let index_service = RecorderIndexService::new(app_state);
+
+    match index_service.get_sync_status().await {
+        Ok(status) => Ok(Json(status)),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+async fn recorder_webhook(
+    State(app_state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> impl IntoResponse {
+    let webhook_service = RecorderWebhookService::new(app_state);
+    let signature = headers.get(WEBHOOK_SIGNATURE_HEADER).and_then(|value| value.to_str().ok());
+
+    match webhook_service.handle_webhook(&body, signature).await {
+        Ok(report) => (StatusCode::OK, Json(report)).into_response(),
+        Err(e) => {
+            let status = if let Some(error) = e.downcast_ref::<RecorderWebhookError>() {
+                match error {
+                    RecorderWebhookError::NotConfigured => StatusCode::SERVICE_UNAVAILABLE,
+                    RecorderWebhookError::InvalidSignature => StatusCode::UNAUTHORIZED,
+                    RecorderWebhookError::InvalidPayload(_) => StatusCode::BAD_REQUEST,
+                }
+            } else if e.downcast_ref::<RecorderSyncError>().is_some() {
+                StatusCode::SERVICE_UNAVAILABLE
+            } else {
+                tracing::error!("Recorder webhook failed: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            };
+            (status, e.to_string()).into_response()
+        }
+    }
+}
+
 #[derive(Serialize)]
 struct AutoLinkResponse {
     success: bool,
     message: String,
+    #[serde(flatten)]
+    result: SessionLinkResult,
 }
 
 #[derive(Serialize)]
@@ -1,18 +1,24 @@
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
-    response::Json,
+    body::Bytes,
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Json, Response},
     routing::{get, post},
     Router,
 };
+use serde::Deserialize;
 use std::sync::Arc;
 
 use crate::{
     models::{
         AppState,
         session::{SessionResponse, StartSessionRequest, EndSessionRequest, SessionStats},
+        download::{CreateDownloadTokenRequest, DownloadError, DownloadTokenResponse},
+        waveform::{WaveformError, DEFAULT_WAVEFORM_POINTS},
+        analysis::{AnalysisError, RecordingAnalysisResponse},
+        tracklist::{SubmitTracklistRequest, TracklistError, TracklistFormat},
     },
-    services::SessionService,
+    services::{AnalysisService, DownloadService, SessionService, TracklistService, WaveformService},
 };
 
 pub fn router() -> Router<Arc<AppState>> {
@@ -23,9 +29,32 @@ pub fn router() -> Router<Arc<AppState>> {
         .route("/end", post(end_session))
         .route("/:id", get(get_session))
         .route("/:id/download", get(get_download_link))
+        .route(
+            "/:id/download-tokens",
+            get(get_download_tokens).post(issue_download_token).delete(revoke_download_tokens),
+        )
+        .route("/:id/waveform", get(get_session_waveform))
+        .route("/:id/analysis", get(get_session_analysis).post(analyze_session))
+        .route(
+            "/:id/tracklist",
+            get(get_tracklist).put(submit_tracklist).delete(delete_tracklist),
+        )
+        .route("/:id/tracklist/import", post(import_tracklist))
+        .route("/:id/tracklist/cue", get(get_tracklist_cue))
+        .route("/:id/tracklist/text", get(get_tracklist_text))
         .route("/statistics", get(get_session_statistics))
 }
 
+#[derive(Deserialize)]
+struct WaveformQuery {
+    points: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct TracklistImportQuery {
+    format: Option<TracklistFormat>,
+}
+
 async fn get_all_sessions(
     State(app_state): State<Arc<AppState>>,
 ) -> Result<Json<Vec<SessionResponse>>, StatusCode> {
@@ -108,4 +137,185 @@ async fn get_session_statistics(
         Ok(stats) => Ok(Json(stats)),
         Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
     }
-}
\ No newline at end of file
+}
+
+async fn issue_download_token(
+    State(app_state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(request): Json<CreateDownloadTokenRequest>,
+) -> Response {
+    let download_service = DownloadService::new(app_state);
+
+    match download_service.issue_token(&id, request).await {
+        Ok(token) => (StatusCode::CREATED, Json(token)).into_response(),
+        Err(e) => {
+            let status = match e.downcast_ref::<DownloadError>() {
+                Some(DownloadError::SessionNotFound) => StatusCode::NOT_FOUND,
+                Some(DownloadError::InvalidExpiry) => StatusCode::BAD_REQUEST,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            (status, e.to_string()).into_response()
+        }
+    }
+}
+
+async fn get_download_tokens(
+    State(app_state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<DownloadTokenResponse>>, StatusCode> {
+    let download_service = DownloadService::new(app_state);
+
+    match download_service.get_session_tokens(&id).await {
+        Ok(tokens) => Ok(Json(tokens)),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+async fn revoke_download_tokens(
+    State(app_state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<u64>, StatusCode> {
+    let download_service = DownloadService::new(app_state);
+
+    match download_service.revoke_session_tokens(&id).await {
+        Ok(revoked) => Ok(Json(revoked)),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+async fn get_session_waveform(
+    State(app_state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Query(query): Query<WaveformQuery>,
+) -> Response {
+    let waveform_service = WaveformService::new(app_state);
+
+    match waveform_service.session_waveform(&id, query.points.unwrap_or(DEFAULT_WAVEFORM_POINTS)).await {
+        Ok(waveform) => Json(waveform).into_response(),
+        Err(e) => {
+            let status = match e.downcast_ref::<WaveformError>() {
+                Some(WaveformError::Invalid(_)) => StatusCode::UNPROCESSABLE_ENTITY,
+                Some(_) => StatusCode::NOT_FOUND,
+                None => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            (status, e.to_string()).into_response()
+        }
+    }
+}
+
+async fn get_session_analysis(
+    State(app_state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<RecordingAnalysisResponse>, StatusCode> {
+    let analysis_service = AnalysisService::new(app_state);
+
+    match analysis_service.get_analysis(&id).await {
+        Ok(Some(analysis)) => Ok(Json(analysis)),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+async fn analyze_session(
+    State(app_state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Response {
+    let analysis_service = AnalysisService::new(app_state);
+
+    match analysis_service.analyze_session(&id).await {
+        Ok(analysis) => Json(analysis).into_response(),
+        Err(e) => {
+            let status = match e.downcast_ref::<AnalysisError>() {
+                Some(_) => StatusCode::NOT_FOUND,
+                None => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            (status, e.to_string()).into_response()
+        }
+    }
+}
+
+fn tracklist_error(e: anyhow::Error) -> Response {
+    let status = match e.downcast_ref::<TracklistError>() {
+        Some(TracklistError::SessionNotFound | TracklistError::NoTracklist) => StatusCode::NOT_FOUND,
+        Some(TracklistError::NoStartTimes | TracklistError::Invalid(_)) => StatusCode::UNPROCESSABLE_ENTITY,
+        Some(TracklistError::UnknownFormat(_)) => StatusCode::BAD_REQUEST,
+        None => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    (status, e.to_string()).into_response()
+}
+
+async fn get_tracklist(
+    State(app_state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Response {
+    match TracklistService::new(app_state).get_tracklist(&id).await {
+        Ok(tracklist) => Json(tracklist).into_response(),
+        Err(e) => tracklist_error(e),
+    }
+}
+
+async fn submit_tracklist(
+    State(app_state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(request): Json<SubmitTracklistRequest>,
+) -> Response {
+    match TracklistService::new(app_state).set_tracklist(&id, request).await {
+        Ok(tracklist) => Json(tracklist).into_response(),
+        Err(e) => tracklist_error(e),
+    }
+}
+
+/// Takes an exported file as the request body
+async fn import_tracklist(
+    State(app_state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Query(query): Query<TracklistImportQuery>,
+    body: Bytes,
+) -> Response {
+    let format = query.format.unwrap_or_default();
+    match TracklistService::new(app_state).import_tracklist(&id, &body, format).await {
+        Ok(tracklist) => Json(tracklist).into_response(),
+        Err(e) => tracklist_error(e),
+    }
+}
+
+async fn delete_tracklist(
+    State(app_state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Response {
+    match TracklistService::new(app_state).delete_tracklist(&id).await {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => tracklist_error(e),
+    }
+}
+
+async fn get_tracklist_cue(
+    State(app_state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Response {
+    match TracklistService::new(app_state).cue_sheet(&id).await {
+        Ok(cue) => {
+            let disposition = format!("attachment; filename=\"{}\"", cue.filename.replace('"', ""));
+            (
+                [
+                    (header::CONTENT_TYPE, "application/x-cue; charset=utf-8".to_string()),
+                    (header::CONTENT_DISPOSITION, disposition),
+                ],
+                cue.content,
+            )
+                .into_response()
+        }
+        Err(e) => tracklist_error(e),
+    }
+}
+
+async fn get_tracklist_text(
+    State(app_state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Response {
+    match TracklistService::new(app_state).formatted_tracklist(&id).await {
+        Ok(Some(text)) => ([(header::CONTENT_TYPE, "text/plain; charset=utf-8")], text).into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, TracklistError::NoTracklist.to_string()).into_response(),
+        Err(e) => tracklist_error(e),
+    }
+}
@@ -0,0 +1,122 @@
+use anyhow::{anyhow, Result};
+use std::fs::File;
+use std::path::Path;
+use symphonia::core::{
+    audio::SampleBuffer,
+    codecs::{Decoder, DecoderOptions, CODEC_TYPE_NULL},
+    errors::Error as SymphoniaError,
+    formats::{FormatOptions, FormatReader},
+    io::MediaSourceStream,
+    meta::MetadataOptions,
+    probe::Hint,
+};
+
+use super::AudioSpec;
+
+/// Lossy sources (OGG/Vorbis) have no native bit depth; they are re-quantised to this
+const DEFAULT_BITS_PER_SAMPLE: u32 = 16;
+/// Deepest resolution we pass on; keeps predictor residuals well inside `i32`
+const MAX_BITS_PER_SAMPLE: u32 = 24;
+
+/// Streaming decoder for recorder files (FLAC or OGG), yielding interleaved PCM chunks
+pub struct AudioDecoder {
+    format: Box<dyn FormatReader>,
+    decoder: Box<dyn Decoder>,
+    track_id: u32,
+    spec: AudioSpec,
+    frames: Option<u64>,
+    buffer: Option<SampleBuffer<i32>>,
+}
+
+impl AudioDecoder {
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = File::open(path)?;
+        let stream = MediaSourceStream::new(Box::new(file), Default::default());
+
+        let mut hint = Hint::new();
+        if let Some(extension) = path.extension().and_then(|e| e.to_str()) {
+            hint.with_extension(extension);
+        }
+
+        let probed = symphonia::default::get_probe()
+            .format(&hint, stream, &FormatOptions::default(), &MetadataOptions::default())
+            .map_err(|e| anyhow!("Unsupported audio file {:?}: {}", path, e))?;
+        let format = probed.format;
+
+        let track = format
+            .tracks()
+            .iter()
+            .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+            .ok_or_else(|| anyhow!("No audio track in {:?}", path))?;
+
+        let params = &track.codec_params;
+        let spec = AudioSpec {
+            sample_rate: params.sample_rate.ok_or_else(|| anyhow!("Unknown sample rate in {:?}", path))?,
+            channels: params.channels.map(|c| c.count() as u16).ok_or_else(|| anyhow!("Unknown channel layout in {:?}", path))?,
+            bits_per_sample: params
+                .bits_per_sample
+                .unwrap_or(DEFAULT_BITS_PER_SAMPLE)
+                .min(MAX_BITS_PER_SAMPLE),
+        };
+        let track_id = track.id;
+        let frames = params.n_frames;
+
+        let decoder = symphonia::default::get_codecs().make(params, &DecoderOptions::default())?;
+
+        Ok(Self {
+            format,
+            decoder,
+            track_id,
+            spec,
+            frames,
+            buffer: None,
+        })
+    }
+
+    pub fn spec(&self) -> AudioSpec {
+        self.spec
+    }
+
+    /// Length of the recording if the container states it
+    pub fn frames(&self) -> Option<u64> {
+        self.frames
+    }
+
+    /// Next chunk of interleaved samples, or `None` at the end of the stream
+    pub fn next_chunk(&mut self) -> Result<Option<Vec<i32>>> {
+        let shift = 32 - self.spec.bits_per_sample;
+
+        loop {
+            let packet = match self.format.next_packet() {
+                Ok(packet) => packet,
+                Err(SymphoniaError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+                Err(e) => return Err(e.into()),
+            };
+            if packet.track_id() != self.track_id {
+                continue;
+            }
+
+            let decoded = match self.decoder.decode(&packet) {
+                Ok(decoded) => decoded,
+                // A corrupt packet costs a few milliseconds of audio, not the whole night
+                Err(SymphoniaError::DecodeError(e)) => {
+                    tracing::warn!("Skipping undecodable packet: {}", e);
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            };
+            if decoded.frames() == 0 {
+                continue;
+            }
+
+            let needed = decoded.capacity() as u64;
+            let buffer = match &mut self.buffer {
+                Some(buffer) if buffer.capacity() as u64 >= needed * self.spec.channels as u64 => buffer,
+                slot => slot.insert(SampleBuffer::new(needed, *decoded.spec())),
+            };
+            buffer.copy_interleaved_ref(decoded);
+
+            return Ok(Some(buffer.samples().iter().map(|s| s >> shift).collect()));
+        }
+    }
+}
@@ -0,0 +1,385 @@
+use anyhow::{anyhow, Result};
+use std::io::{Seek, SeekFrom, Write};
+
+use super::AudioSpec;
+
+/// Frames per FLAC block; the reference encoder's default
+pub const FLAC_BLOCK_SIZE: usize = 4096;
+
+const STREAMINFO_OFFSET: u64 = 8; // "fLaC" + metadata block header
+const MAX_FIXED_ORDER: usize = 4;
+const MAX_RICE_PARAMETER: u32 = 14; // 15 is the escape code
+
+/// Streaming FLAC encoder using fixed predictors and Rice-coded residuals.
+///
+/// Not as tight as libFLAC's LPC search, but lossless and small enough to live here.
+/// STREAMINFO is written as a placeholder and patched in `finish`, so the writer
+/// has to be seekable.
+pub struct FlacEncoder<W: Write + Seek> {
+    writer: W,
+    spec: AudioSpec,
+    pending: Vec<i32>,
+    frame_number: u64,
+    total_frames: u64,
+    min_frame_size: u32,
+    max_frame_size: u32,
+}
+
+impl<W: Write + Seek> FlacEncoder<W> {
+    pub fn new(mut writer: W, spec: AudioSpec) -> Result<Self> {
+        if !(1..=8).contains(&spec.channels) {
+            return Err(anyhow!("FLAC supports 1 to 8 channels, got {}", spec.channels));
+        }
+        if !(4..=24).contains(&spec.bits_per_sample) {
+            return Err(anyhow!("Unsupported bit depth {}", spec.bits_per_sample));
+        }
+        if spec.sample_rate == 0 || spec.sample_rate >= 1 << 20 {
+            return Err(anyhow!("Unsupported sample rate {}", spec.sample_rate));
+        }
+
+        writer.write_all(b"fLaC")?;
+        // Last-metadata-block flag + STREAMINFO type, 34 byte body
+        writer.write_all(&[0x80, 0x00, 0x00, 34])?;
+        writer.write_all(&[0u8; 34])?;
+
+        Ok(Self {
+            writer,
+            spec,
+            pending: Vec::with_capacity(FLAC_BLOCK_SIZE * spec.channels as usize),
+            frame_number: 0,
+            total_frames: 0,
+            min_frame_size: u32::MAX,
+            max_frame_size: 0,
+        })
+    }
+
+    /// Append interleaved samples; a trailing partial frame is not allowed
+    pub fn write_interleaved(&mut self, samples: &[i32]) -> Result<()> {
+        let channels = self.spec.channels as usize;
+        if !samples.len().is_multiple_of(channels) {
+            return Err(anyhow!("Sample count {} is not a multiple of {} channels", samples.len(), channels));
+        }
+
+        let block_samples = FLAC_BLOCK_SIZE * channels;
+        let mut rest = samples;
+        while !rest.is_empty() {
+            let take = (block_samples - self.pending.len()).min(rest.len());
+            self.pending.extend_from_slice(&rest[..take]);
+            rest = &rest[take..];
+
+            if self.pending.len() == block_samples {
+                self.flush_block()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn frames_written(&self) -> u64 {
+        self.total_frames + (self.pending.len() / self.spec.channels as usize) as u64
+    }
+
+    /// Encode the final partial block, patch STREAMINFO and hand the writer back
+    pub fn finish(mut self) -> Result<W> {
+        if !self.pending.is_empty() {
+            self.flush_block()?;
+        }
+
+        let streaminfo = self.streaminfo();
+        self.writer.seek(SeekFrom::Start(STREAMINFO_OFFSET))?;
+        self.writer.write_all(&streaminfo)?;
+        self.writer.seek(SeekFrom::End(0))?;
+        self.writer.flush()?;
+
+        Ok(self.writer)
+    }
+
+    fn streaminfo(&self) -> Vec<u8> {
+        let mut bits = BitWriter::new();
+        bits.write(FLAC_BLOCK_SIZE as u64, 16);
+        bits.write(FLAC_BLOCK_SIZE as u64, 16);
+        if self.max_frame_size == 0 {
+            bits.write(0, 24);
+            bits.write(0, 24);
+        } else {
+            bits.write(self.min_frame_size as u64, 24);
+            bits.write(self.max_frame_size as u64, 24);
+        }
+        bits.write(self.spec.sample_rate as u64, 20);
+        bits.write(self.spec.channels as u64 - 1, 3);
+        bits.write(self.spec.bits_per_sample as u64 - 1, 5);
+        bits.write(self.total_frames, 36);
+        // MD5 left as zero, meaning "not computed"
+        bits.write(0, 64);
+        bits.write(0, 64);
+        bits.into_bytes()
+    }
+
+    fn flush_block(&mut self) -> Result<()> {
+        let channels = self.spec.channels as usize;
+        let block_size = self.pending.len() / channels;
+        let bps = self.spec.bits_per_sample;
+
+        let mut bits = BitWriter::new();
+
+        // Frame header: sync code, fixed blocking strategy
+        bits.write(0x3ffe, 14);
+        bits.write(0, 1);
+        bits.write(0, 1);
+        bits.write(0b0111, 4); // block size as 16 bit value at end of header
+        bits.write(sample_rate_code(self.spec.sample_rate), 4);
+        bits.write(channels as u64 - 1, 4); // independent channels
+        bits.write(sample_size_code(bps), 3);
+        bits.write(0, 1);
+        for byte in utf8_number(self.frame_number) {
+            bits.write(byte as u64, 8);
+        }
+        bits.write(block_size as u64 - 1, 16);
+        let crc = crc8(bits.bytes());
+        bits.write(crc as u64, 8);
+
+        let mut channel = Vec::with_capacity(block_size);
+        for c in 0..channels {
+            channel.clear();
+            channel.extend(self.pending.iter().skip(c).step_by(channels).map(|&s| s as i64));
+            write_subframe(&mut bits, &channel, bps);
+        }
+
+        bits.align();
+        let crc = crc16(bits.bytes());
+        bits.write(crc as u64, 16);
+
+        let frame = bits.into_bytes();
+        self.writer.write_all(&frame)?;
+
+        self.min_frame_size = self.min_frame_size.min(frame.len() as u32);
+        self.max_frame_size = self.max_frame_size.max(frame.len() as u32);
+        self.frame_number += 1;
+        self.total_frames += block_size as u64;
+        self.pending.clear();
+
+        Ok(())
+    }
+}
+
+fn write_subframe(bits: &mut BitWriter, samples: &[i64], bps: u32) {
+    if samples.iter().all(|&s| s == samples[0]) {
+        bits.write(0b0000_0000, 8); // CONSTANT
+        bits.write_signed(samples[0], bps);
+        return;
+    }
+
+    let max_order = MAX_FIXED_ORDER.min(samples.len() - 1);
+    let (order, residuals) = (0..=max_order)
+        .map(|order| (order, fixed_residuals(samples, order)))
+        .min_by_key(|(_, residuals)| residuals.iter().map(|r| r.unsigned_abs()).sum::<u64>())
+        .expect("at least order 0 is available");
+
+    let parameter = rice_parameter(&residuals);
+    let rice_bits: u64 = residuals
+        .iter()
+        .map(|&r| (zigzag(r) >> parameter) + 1 + parameter as u64)
+        .sum();
+
+    if rice_bits > samples.len() as u64 * bps as u64 {
+        bits.write(0b0000_0010, 8); // VERBATIM
+        for &sample in samples {
+            bits.write_signed(sample, bps);
+        }
+        return;
+    }
+
+    bits.write(0b0001_0000 | ((order as u64) << 1), 8); // FIXED, no wasted bits
+    for &sample in &samples[..order] {
+        bits.write_signed(sample, bps);
+    }
+    bits.write(0b00, 2); // Rice coding with 4 bit parameters
+    bits.write(0, 4); // single partition
+    bits.write(parameter as u64, 4);
+    for &residual in &residuals {
+        let folded = zigzag(residual);
+        bits.write_unary(folded >> parameter);
+        bits.write(folded & ((1 << parameter) - 1), parameter);
+    }
+}
+
+fn fixed_residuals(samples: &[i64], order: usize) -> Vec<i64> {
+    (order..samples.len())
+        .map(|i| {
+            let s = |k: usize| samples[i - k];
+            match order {
+                0 => s(0),
+                1 => s(0) - s(1),
+                2 => s(0) - 2 * s(1) + s(2),
+                3 => s(0) - 3 * s(1) + 3 * s(2) - s(3),
+                _ => s(0) - 4 * s(1) + 6 * s(2) - 4 * s(3) + s(4),
+            }
+        })
+        .collect()
+}
+
+fn rice_parameter(residuals: &[i64]) -> u32 {
+    if residuals.is_empty() {
+        return 0;
+    }
+    let mean = residuals.iter().map(|&r| zigzag(r)).sum::<u64>() / residuals.len() as u64;
+    if mean == 0 {
+        0
+    } else {
+        (63 - mean.leading_zeros()).min(MAX_RICE_PARAMETER)
+    }
+}
+
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+fn sample_rate_code(sample_rate: u32) -> u64 {
+    match sample_rate {
+        88_200 => 0b0001,
+        176_400 => 0b0010,
+        192_000 => 0b0011,
+        8_000 => 0b0100,
+        16_000 => 0b0101,
+        22_050 => 0b0110,
+        24_000 => 0b0111,
+        32_000 => 0b1000,
+        44_100 => 0b1001,
+        48_000 => 0b1010,
+        96_000 => 0b1011,
+        _ => 0b0000, // taken from STREAMINFO
+    }
+}
+
+fn sample_size_code(bps: u32) -> u64 {
+    match bps {
+        8 => 0b001,
+        12 => 0b010,
+        16 => 0b100,
+        20 => 0b101,
+        24 => 0b110,
+        _ => 0b000, // taken from STREAMINFO
+    }
+}
+
+/// FLAC's UTF-8-style variable length frame number
+fn utf8_number(value: u64) -> Vec<u8> {
+    if value < 0x80 {
+        return vec![value as u8];
+    }
+
+    let mut bytes = Vec::new();
+    let mut rest = value;
+    let mut first_payload_bits = 6;
+    while rest >= 1 << first_payload_bits {
+        bytes.push(0x80 | (rest & 0x3f) as u8);
+        rest >>= 6;
+        first_payload_bits -= 1;
+    }
+    let length = bytes.len() + 1;
+    let marker = !(0xffu8 >> length);
+    bytes.push(marker | rest as u8);
+    bytes.reverse();
+    bytes
+}
+
+fn crc8(data: &[u8]) -> u8 {
+    data.iter().fold(0u8, |mut crc, &byte| {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 { (crc << 1) ^ 0x07 } else { crc << 1 };
+        }
+        crc
+    })
+}
+
+fn crc16(data: &[u8]) -> u16 {
+    data.iter().fold(0u16, |mut crc, &byte| {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 { (crc << 1) ^ 0x8005 } else { crc << 1 };
+        }
+        crc
+    })
+}
+
+/// MSB-first bit packer
+struct BitWriter {
+    bytes: Vec<u8>,
+    accumulator: u64,
+    pending_bits: u32,
+}
+
+impl BitWriter {
+    fn new() -> Self {
+        Self {
+            bytes: Vec::new(),
+            accumulator: 0,
+            pending_bits: 0,
+        }
+    }
+
+    fn write(&mut self, value: u64, bits: u32) {
+        let mut remaining = bits;
+        while remaining > 0 {
+            let take = remaining.min(32);
+            remaining -= take;
+            let chunk = (value >> remaining) & ((1u64 << take) - 1);
+            self.accumulator = (self.accumulator << take) | chunk;
+            self.pending_bits += take;
+            while self.pending_bits >= 8 {
+                self.pending_bits -= 8;
+                self.bytes.push((self.accumulator >> self.pending_bits) as u8);
+            }
+            self.accumulator &= (1u64 << self.pending_bits) - 1;
+        }
+    }
+
+    fn write_signed(&mut self, value: i64, bits: u32) {
+        self.write(value as u64 & ((1u64 << bits) - 1), bits);
+    }
+
+    fn write_unary(&mut self, zeros: u64) {
+        let mut zeros = zeros;
+        while zeros >= 32 {
+            self.write(0, 32);
+            zeros -= 32;
+        }
+        self.write(1, zeros as u32 + 1);
+    }
+
+    fn align(&mut self) {
+        if self.pending_bits > 0 {
+            self.write(0, 8 - self.pending_bits);
+        }
+    }
+
+    /// Whole bytes written so far
+    fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    fn into_bytes(mut self) -> Vec<u8> {
+        self.align();
+        self.bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_utf8_frame_numbers() {
+        assert_eq!(utf8_number(0x7f), vec![0x7f]);
+        assert_eq!(utf8_number(0x80), vec![0xc2, 0x80]);
+        assert_eq!(utf8_number(0x800), vec![0xe0, 0xa0, 0x80]);
+    }
+
+    #[test]
+    fn test_crcs() {
+        // Check values for "123456789"
+        assert_eq!(crc8(b"123456789"), 0xf4);
+        assert_eq!(crc16(b"123456789"), 0xfee8);
+    }
+}
@@ -0,0 +1,376 @@
+use anyhow::Result;
+use std::f64::consts::PI;
+use std::path::Path;
+
+use super::{AudioDecoder, AudioSpec};
+
+/// Gating block length and step of ITU-R BS.1770: 400 ms blocks overlapping by 75 %
+const SUBBLOCKS_PER_BLOCK: usize = 4;
+const SUBBLOCK_MS: u64 = 100;
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+const RELATIVE_GATE_LU: f64 = -10.0;
+
+/// True peak is measured on a 4x oversampled signal with this many taps per phase
+const OVERSAMPLING: usize = 4;
+const TAPS_PER_PHASE: usize = 12;
+
+/// What counts as silence: stretches quieter than `threshold_dbfs` for at least `min_seconds`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SilenceSettings {
+    pub threshold_dbfs: f64,
+    pub min_seconds: f64,
+}
+
+impl Default for SilenceSettings {
+    fn default() -> Self {
+        Self {
+            threshold_dbfs: -60.0,
+            min_seconds: 10.0,
+        }
+    }
+}
+
+/// Silent stretch, in seconds from the start of the analysed audio
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SilentSpan {
+    pub start_seconds: f64,
+    pub end_seconds: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoudnessReport {
+    pub duration_seconds: f64,
+    pub integrated_lufs: Option<f64>, // None when nothing passes the absolute gate
+    pub true_peak_dbtp: Option<f64>,  // None for digital silence
+    pub clipped_samples: u64,
+    pub clipping_ratio: f64, // Share of samples at full scale
+    pub silence: Vec<SilentSpan>,
+}
+
+impl LoudnessReport {
+    pub fn silent_seconds(&self) -> f64 {
+        self.silence.iter().map(|span| span.end_seconds - span.start_seconds).sum()
+    }
+}
+
+/// Second order IIR section in direct form I
+#[derive(Debug, Clone, Copy)]
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+}
+
+impl Biquad {
+    /// Pre-filter (high shelf) of the K-weighting, recomputed for `sample_rate`
+    fn k_shelf(sample_rate: f64) -> Self {
+        let f0 = 1681.974450955533;
+        let gain_db = 3.999843853973347;
+        let q = 0.7071752369554196;
+        let k = (PI * f0 / sample_rate).tan();
+        let vh = 10f64.powf(gain_db / 20.0);
+        let vb = vh.powf(0.4996667741545416);
+        let a0 = 1.0 + k / q + k * k;
+        Self {
+            b: [(vh + vb * k / q + k * k) / a0, 2.0 * (k * k - vh) / a0, (vh - vb * k / q + k * k) / a0],
+            a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        }
+    }
+
+    /// RLB high-pass of the K-weighting
+    fn k_highpass(sample_rate: f64) -> Self {
+        let f0 = 38.13547087602444;
+        let q = 0.5003270373238773;
+        let k = (PI * f0 / sample_rate).tan();
+        let a0 = 1.0 + k / q + k * k;
+        Self {
+            b: [1.0, -2.0, 1.0],
+            a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct BiquadState {
+    x: [f64; 2],
+    y: [f64; 2],
+}
+
+impl BiquadState {
+    fn process(&mut self, filter: &Biquad, x: f64) -> f64 {
+        let y = filter.b[0] * x + filter.b[1] * self.x[0] + filter.b[2] * self.x[1] - filter.a[0] * self.y[0] - filter.a[1] * self.y[1];
+        self.x = [x, self.x[0]];
+        self.y = [y, self.y[0]];
+        y
+    }
+}
+
+/// BS.1770 channel weights; the LFE of a 5.1 layout is left out and surrounds count more
+fn channel_weights(channels: usize) -> Vec<f64> {
+    match channels {
+        6 => vec![1.0, 1.0, 1.0, 0.0, 1.41, 1.41],
+        _ => vec![1.0; channels],
+    }
+}
+
+/// Windowed-sinc interpolation filter, one row of taps per oversampled phase after the
+/// original sample
+fn oversampling_phases() -> Vec<[f64; TAPS_PER_PHASE]> {
+    (1..OVERSAMPLING)
+        .map(|phase| {
+            let mut taps = [0.0; TAPS_PER_PHASE];
+            for (tap, value) in taps.iter_mut().enumerate() {
+                // Distance from the interpolated point to the input sample, in input samples
+                let t = (TAPS_PER_PHASE / 2) as f64 - 1.0 - tap as f64 + phase as f64 / OVERSAMPLING as f64;
+                let sinc = if t == 0.0 { 1.0 } else { (PI * t).sin() / (PI * t) };
+                let window = 0.5 + 0.5 * (PI * t / (TAPS_PER_PHASE / 2) as f64).cos();
+                *value = sinc * window;
+            }
+            taps
+        })
+        .collect()
+}
+
+/// Streaming EBU R128 loudness, true peak, clipping and silence measurement over
+/// interleaved samples
+pub struct LoudnessMeter {
+    spec: AudioSpec,
+    weights: Vec<f64>,
+    shelf: Biquad,
+    highpass: Biquad,
+    filter_state: Vec<(BiquadState, BiquadState)>,
+    history: Vec<[f64; TAPS_PER_PHASE]>, // Newest sample last, per channel
+    phases: Vec<[f64; TAPS_PER_PHASE]>,
+    full_scale: f64,
+    clip_level: i32,
+    silence: SilenceSettings,
+
+    subblock_frames: u64,
+    subblock_position: u64,
+    weighted_sum: f64, // K-weighted, channel weighted energy of the current subblock
+    plain_sum: f64,    // Unweighted energy, for the silence detector
+    subblocks: Vec<f64>,
+
+    frames: u64,
+    samples: u64,
+    clipped: u64,
+    peak: f64,
+    silent_since: Option<u64>, // Subblock index where the current quiet stretch began
+    spans: Vec<SilentSpan>,
+}
+
+impl LoudnessMeter {
+    pub fn new(spec: AudioSpec, silence: SilenceSettings) -> Self {
+        let channels = spec.channels as usize;
+        let sample_rate = spec.sample_rate as f64;
+        Self {
+            spec,
+            weights: channel_weights(channels),
+            shelf: Biquad::k_shelf(sample_rate),
+            highpass: Biquad::k_highpass(sample_rate),
+            filter_state: vec![Default::default(); channels],
+            history: vec![[0.0; TAPS_PER_PHASE]; channels],
+            phases: oversampling_phases(),
+            full_scale: (1i64 << (spec.bits_per_sample - 1)) as f64,
+            clip_level: ((1i64 << (spec.bits_per_sample - 1)) - 1) as i32,
+            silence,
+            subblock_frames: spec.millis_to_frames(SUBBLOCK_MS).max(1),
+            subblock_position: 0,
+            weighted_sum: 0.0,
+            plain_sum: 0.0,
+            subblocks: Vec::new(),
+            frames: 0,
+            samples: 0,
+            clipped: 0,
+            peak: 0.0,
+            silent_since: None,
+            spans: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, interleaved: &[i32]) {
+        let channels = self.spec.channels as usize;
+        for frame in interleaved.chunks_exact(channels) {
+            for (channel, &sample) in frame.iter().enumerate() {
+                if sample >= self.clip_level || sample <= -self.clip_level {
+                    self.clipped += 1;
+                }
+                let x = sample as f64 / self.full_scale;
+                self.plain_sum += x * x;
+
+                let (shelf_state, highpass_state) = &mut self.filter_state[channel];
+                let shelved = shelf_state.process(&self.shelf, x);
+                let weighted = highpass_state.process(&self.highpass, shelved);
+                self.weighted_sum += self.weights[channel] * weighted * weighted;
+
+                let history = &mut self.history[channel];
+                history.copy_within(1.., 0);
+                history[TAPS_PER_PHASE - 1] = x;
+                let mut peak = x.abs();
+                for taps in &self.phases {
+                    let value: f64 = taps.iter().zip(history.iter()).map(|(t, h)| t * h).sum();
+                    peak = peak.max(value.abs());
+                }
+                self.peak = self.peak.max(peak);
+            }
+            self.samples += channels as u64;
+            self.frames += 1;
+
+            self.subblock_position += 1;
+            if self.subblock_position == self.subblock_frames {
+                self.close_subblock();
+            }
+        }
+    }
+
+    fn close_subblock(&mut self) {
+        let frames = self.subblock_position as f64;
+        self.subblocks.push(self.weighted_sum / frames);
+
+        let mean_square = self.plain_sum / (frames * self.spec.channels as f64);
+        let quiet = mean_square <= 0.0 || 10.0 * mean_square.log10() < self.silence.threshold_dbfs;
+        let index = self.subblocks.len() as u64 - 1;
+        match (quiet, self.silent_since) {
+            (true, None) => self.silent_since = Some(index),
+            (false, Some(since)) => {
+                self.end_silence(since, index);
+                self.silent_since = None;
+            }
+            _ => {}
+        }
+
+        self.subblock_position = 0;
+        self.weighted_sum = 0.0;
+        self.plain_sum = 0.0;
+    }
+
+    fn end_silence(&mut self, since: u64, until: u64) {
+        let seconds_per_subblock = self.spec.frames_to_seconds(self.subblock_frames);
+        let start_seconds = since as f64 * seconds_per_subblock;
+        let end_seconds = (until as f64 * seconds_per_subblock).min(self.spec.frames_to_seconds(self.frames));
+        if end_seconds - start_seconds >= self.silence.min_seconds {
+            self.spans.push(SilentSpan { start_seconds, end_seconds });
+        }
+    }
+
+    pub fn finish(mut self) -> LoudnessReport {
+        if self.subblock_position > 0 {
+            self.close_subblock();
+        }
+        if let Some(since) = self.silent_since.take() {
+            self.end_silence(since, self.subblocks.len() as u64);
+        }
+
+        let blocks: Vec<f64> = self
+            .subblocks
+            .windows(SUBBLOCKS_PER_BLOCK)
+            .map(|window| window.iter().sum::<f64>() / SUBBLOCKS_PER_BLOCK as f64)
+            .collect();
+        let loudness = |energy: f64| -0.691 + 10.0 * energy.log10();
+        let mean_of = |blocks: &[f64]| blocks.iter().sum::<f64>() / blocks.len() as f64;
+
+        let above_absolute: Vec<f64> = blocks.into_iter().filter(|&z| z > 0.0 && loudness(z) > ABSOLUTE_GATE_LUFS).collect();
+        let integrated_lufs = if above_absolute.is_empty() {
+            None
+        } else {
+            let relative_gate = loudness(mean_of(&above_absolute)) + RELATIVE_GATE_LU;
+            let gated: Vec<f64> = above_absolute.iter().copied().filter(|&z| loudness(z) > relative_gate).collect();
+            Some(loudness(mean_of(&gated)))
+        };
+
+        LoudnessReport {
+            duration_seconds: self.spec.frames_to_seconds(self.frames),
+            integrated_lufs,
+            true_peak_dbtp: (self.peak > 0.0).then(|| 20.0 * self.peak.log10()),
+            clipped_samples: self.clipped,
+            clipping_ratio: if self.samples == 0 { 0.0 } else { self.clipped as f64 / self.samples as f64 },
+            silence: self.spans,
+        }
+    }
+}
+
+/// Measure `from_ms..to_ms` of a recording (all of it when open) in one decoding pass
+pub fn analyze_recording(input: &Path, from_ms: u64, to_ms: Option<u64>, silence: SilenceSettings) -> Result<LoudnessReport> {
+    let mut decoder = AudioDecoder::open(input)?;
+    let spec = decoder.spec();
+    let channels = spec.channels as usize;
+    let start = spec.millis_to_frames(from_ms);
+    let end = to_ms.map(|ms| spec.millis_to_frames(ms)).unwrap_or(u64::MAX).max(start);
+
+    let mut meter = LoudnessMeter::new(spec, silence);
+    let mut position = 0u64;
+    while let Some(chunk) = decoder.next_chunk()? {
+        let chunk_end = position + (chunk.len() / channels) as u64;
+        let from = start.clamp(position, chunk_end) - position;
+        let to = end.clamp(position, chunk_end) - position;
+        if from < to {
+            meter.push(&chunk[from as usize * channels..to as usize * channels]);
+        }
+        if chunk_end >= end {
+            break;
+        }
+        position = chunk_end;
+    }
+
+    Ok(meter.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SPEC: AudioSpec = AudioSpec {
+        sample_rate: 48_000,
+        channels: 2,
+        bits_per_sample: 16,
+    };
+
+    fn sine(frequency: f64, amplitude: f64, seconds: f64) -> Vec<i32> {
+        let frames = (seconds * SPEC.sample_rate as f64) as usize;
+        (0..frames)
+            .flat_map(|i| {
+                let value = (amplitude * 32767.0 * (2.0 * PI * frequency * i as f64 / SPEC.sample_rate as f64).sin()).round() as i32;
+                [value, value]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_full_scale_stereo_sine_reads_zero_lufs() {
+        // BS.1770 calibration: a 0 dBFS 997 Hz sine measures -3.01 LKFS in one channel,
+        // so the same tone in both channels reads 0
+        let mut meter = LoudnessMeter::new(SPEC, SilenceSettings::default());
+        meter.push(&sine(997.0, 1.0, 5.0));
+        let report = meter.finish();
+
+        let lufs = report.integrated_lufs.unwrap();
+        assert!((lufs - 0.0).abs() < 0.1, "{}", lufs);
+        assert!(report.true_peak_dbtp.unwrap().abs() < 0.1);
+        assert!(report.clipping_ratio > 0.0);
+        assert!(report.silence.is_empty());
+        assert_eq!(report.duration_seconds, 5.0);
+    }
+
+    #[test]
+    fn test_silence_is_reported_and_gated() {
+        let mut samples = sine(997.0, 0.25, 3.0);
+        samples.extend(vec![0; 12 * SPEC.sample_rate as usize * 2]);
+        samples.extend(sine(997.0, 0.25, 3.0));
+
+        let mut meter = LoudnessMeter::new(SPEC, SilenceSettings { threshold_dbfs: -60.0, min_seconds: 10.0 });
+        meter.push(&samples);
+        let report = meter.finish();
+
+        assert_eq!(report.silence, vec![SilentSpan { start_seconds: 3.0, end_seconds: 15.0 }]);
+        assert_eq!(report.clipped_samples, 0);
+        // The gap is gated out, so loudness matches the tone alone: -12 dB below full scale
+        let lufs = report.integrated_lufs.unwrap();
+        assert!((lufs + 12.04).abs() < 0.3, "{}", lufs);
+
+        let mut meter = LoudnessMeter::new(SPEC, SilenceSettings::default());
+        meter.push(&vec![0; SPEC.sample_rate as usize * 2 * 11]);
+        let report = meter.finish();
+        assert_eq!(report.integrated_lufs, None);
+        assert_eq!(report.true_peak_dbtp, None);
+        assert_eq!(report.silence, vec![SilentSpan { start_seconds: 0.0, end_seconds: 11.0 }]);
+    }
+}
@@ -0,0 +1,32 @@
+pub mod decoder;
+pub mod flac;
+pub mod loudness;
+pub mod segmenter;
+pub mod tags;
+pub mod waveform;
+
+pub use decoder::*;
+pub use flac::*;
+pub use loudness::*;
+pub use segmenter::*;
+pub use tags::*;
+pub use waveform::*;
+
+/// Sample layout shared by the decoder and encoders. Samples are passed around as
+/// interleaved `i32` values at `bits_per_sample` resolution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AudioSpec {
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub bits_per_sample: u32,
+}
+
+impl AudioSpec {
+    pub fn frames_to_seconds(&self, frames: u64) -> f64 {
+        frames as f64 / self.sample_rate as f64
+    }
+
+    pub fn millis_to_frames(&self, millis: u64) -> u64 {
+        millis * self.sample_rate as u64 / 1000
+    }
+}
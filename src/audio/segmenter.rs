@@ -0,0 +1,125 @@
+use anyhow::Result;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+use super::{AudioDecoder, AudioSpec, FlacEncoder};
+
+/// A stretch of the recording to cut out, relative to the start of the recording
+#[derive(Debug, Clone)]
+pub struct SegmentRange {
+    pub id: String,
+    pub start_ms: u64,
+    pub end_ms: Option<u64>, // Runs to the end of the recording when open
+    pub output_path: PathBuf,
+}
+
+/// A segment that was written; ranges that fall entirely after the recording produce none
+#[derive(Debug, Clone)]
+pub struct WrittenSegment {
+    pub id: String,
+    pub output_path: PathBuf,
+    pub start_frame: u64,
+    pub frames: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct SplitResult {
+    pub spec: AudioSpec,
+    pub total_frames: u64,
+    pub segments: Vec<WrittenSegment>,
+}
+
+struct OpenSegment {
+    index: usize,
+    start_frame: u64,
+    encoder: FlacEncoder<BufWriter<File>>,
+}
+
+/// Cut one long recording into FLAC files in a single decoding pass.
+///
+/// Ranges may overlap (pre/post roll around a changeover); every segment gets its own
+/// encoder and receives the decoded samples that fall into its range.
+pub fn split_recording(input: &Path, ranges: &[SegmentRange]) -> Result<SplitResult> {
+    let mut decoder = AudioDecoder::open(input)?;
+    let spec = decoder.spec();
+    let channels = spec.channels as usize;
+
+    let bounds: Vec<(u64, u64)> = ranges
+        .iter()
+        .map(|range| {
+            let start = spec.millis_to_frames(range.start_ms);
+            let end = range.end_ms.map(|end| spec.millis_to_frames(end)).unwrap_or(u64::MAX);
+            (start, end.max(start))
+        })
+        .collect();
+
+    let mut open: Vec<OpenSegment> = Vec::new();
+    let mut started = vec![false; ranges.len()];
+    let mut written = Vec::new();
+    let mut position = 0u64;
+
+    while let Some(chunk) = decoder.next_chunk()? {
+        let chunk_frames = (chunk.len() / channels) as u64;
+        let chunk_end = position + chunk_frames;
+
+        for (index, &(start, end)) in bounds.iter().enumerate() {
+            if !started[index] && start < chunk_end && end > position {
+                started[index] = true;
+                if let Some(parent) = ranges[index].output_path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                let file = BufWriter::new(File::create(&ranges[index].output_path)?);
+                open.push(OpenSegment {
+                    index,
+                    start_frame: start.max(position),
+                    encoder: FlacEncoder::new(file, spec)?,
+                });
+            }
+        }
+
+        let mut finished = Vec::new();
+        for (slot, segment) in open.iter_mut().enumerate() {
+            let (start, end) = bounds[segment.index];
+            let from = start.max(position) - position;
+            let to = end.min(chunk_end) - position;
+            if from < to {
+                segment
+                    .encoder
+                    .write_interleaved(&chunk[from as usize * channels..to as usize * channels])?;
+            }
+            if end <= chunk_end {
+                finished.push(slot);
+            }
+        }
+        for slot in finished.into_iter().rev() {
+            written.push(close_segment(open.swap_remove(slot), ranges)?);
+        }
+
+        position = chunk_end;
+    }
+
+    for segment in open {
+        written.push(close_segment(segment, ranges)?);
+    }
+    written.sort_by_key(|segment| segment.start_frame);
+
+    Ok(SplitResult {
+        spec,
+        total_frames: position,
+        segments: written,
+    })
+}
+
+fn close_segment(segment: OpenSegment, ranges: &[SegmentRange]) -> Result<WrittenSegment> {
+    let frames = segment.encoder.frames_written();
+    segment.encoder.finish()?;
+
+    let range = &ranges[segment.index];
+    Ok(WrittenSegment {
+        id: range.id.clone(),
+        output_path: range.output_path.clone(),
+        start_frame: segment.start_frame,
+        frames,
+    })
+}
@@ -0,0 +1,719 @@
+use anyhow::{anyhow, Result};
+use base64::Engine;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+const VENDOR: &str = "Slotify";
+
+const FLAC_PADDING: u8 = 1;
+const FLAC_VORBIS_COMMENT: u8 = 4;
+const FLAC_PICTURE: u8 = 6;
+const FLAC_MAX_BLOCK_LENGTH: usize = (1 << 24) - 1;
+
+const PICTURE_FRONT_COVER: u32 = 3;
+
+/// Metadata written into a delivered recording
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RecordingTags {
+    pub artist: String,
+    pub album: String,
+    pub date: String, // YYYY-MM-DD
+    pub comment: Option<String>,
+    pub cover: Option<CoverArt>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoverArt {
+    pub mime_type: String,
+    pub data: Vec<u8>,
+}
+
+impl CoverArt {
+    /// Load an image; the MIME type follows the file extension
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase();
+        let mime_type = match extension.as_str() {
+            "jpg" | "jpeg" => "image/jpeg",
+            "png" => "image/png",
+            other => return Err(anyhow!("Unsupported cover art format '{}'", other)),
+        };
+        Ok(Self {
+            mime_type: mime_type.to_string(),
+            data: std::fs::read(path)?,
+        })
+    }
+}
+
+/// File formats tags can be written to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaggedFormat {
+    Flac,
+    Ogg,
+    Mp3,
+}
+
+impl TaggedFormat {
+    /// Recognise a file by its first bytes
+    pub fn detect(header: &[u8]) -> Option<Self> {
+        if header.starts_with(b"fLaC") {
+            Some(Self::Flac)
+        } else if header.starts_with(b"OggS") {
+            Some(Self::Ogg)
+        } else if header.starts_with(b"ID3") || (header.len() >= 2 && header[0] == 0xFF && header[1] & 0xE0 == 0xE0) {
+            Some(Self::Mp3)
+        } else {
+            None
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Flac => "flac",
+            Self::Ogg => "ogg",
+            Self::Mp3 => "mp3",
+        }
+    }
+}
+
+fn detect_file(reader: &mut BufReader<File>) -> Result<TaggedFormat> {
+    let mut header = [0u8; 4];
+    reader.read_exact(&mut header)?;
+    reader.seek(SeekFrom::Start(0))?;
+    TaggedFormat::detect(&header).ok_or_else(|| anyhow!("Not a FLAC, Ogg or MP3 file"))
+}
+
+/// Copy `input` to `output` with `tags` written in: Vorbis comments for FLAC and Ogg,
+/// ID3v2.4 for MP3. Audio data is copied unchanged. Existing Vorbis comment fields
+/// other than the ones set here are kept.
+pub fn write_tags(input: &Path, output: &Path, tags: &RecordingTags) -> Result<TaggedFormat> {
+    let mut reader = BufReader::new(File::open(input)?);
+    let format = detect_file(&mut reader)?;
+    let mut writer = BufWriter::new(File::create(output)?);
+
+    match format {
+        TaggedFormat::Flac => write_flac_tags(&mut reader, &mut writer, tags)?,
+        TaggedFormat::Ogg => write_ogg_tags(&mut reader, &mut writer, tags)?,
+        TaggedFormat::Mp3 => write_id3_tags(&mut reader, &mut writer, tags)?,
+    }
+
+    writer.flush()?;
+    Ok(format)
+}
+
+/// Read back the tags `write_tags` writes
+pub fn read_tags(path: &Path) -> Result<RecordingTags> {
+    let mut reader = BufReader::new(File::open(path)?);
+    match detect_file(&mut reader)? {
+        TaggedFormat::Flac => read_flac_tags(&mut reader),
+        TaggedFormat::Ogg => read_ogg_tags(&mut reader),
+        TaggedFormat::Mp3 => read_id3_tags(&mut reader),
+    }
+}
+
+// Vorbis comments, shared by FLAC and Ogg
+
+type Comments = Vec<(String, String)>;
+
+impl RecordingTags {
+    fn vorbis_fields(&self) -> Comments {
+        let mut fields = vec![
+            ("ARTIST".to_string(), self.artist.clone()),
+            ("ALBUM".to_string(), self.album.clone()),
+            ("DATE".to_string(), self.date.clone()),
+        ];
+        if let Some(comment) = &self.comment {
+            fields.push(("COMMENT".to_string(), comment.clone()));
+        }
+        fields
+    }
+
+    fn from_vorbis_fields(comments: &Comments) -> Self {
+        let field = |name: &str| {
+            comments
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.clone())
+        };
+        Self {
+            artist: field("ARTIST").unwrap_or_default(),
+            album: field("ALBUM").unwrap_or_default(),
+            date: field("DATE").unwrap_or_default(),
+            comment: field("COMMENT"),
+            cover: None,
+        }
+    }
+}
+
+/// Existing comments minus the fields we set, then ours
+fn merge_comments(existing: Comments, ours: Comments, replace_picture: bool) -> Comments {
+    let mut merged: Comments = existing
+        .into_iter()
+        .filter(|(key, _)| {
+            let replaced = ours.iter().any(|(ours, _)| ours.eq_ignore_ascii_case(key))
+                || (replace_picture && key.eq_ignore_ascii_case("METADATA_BLOCK_PICTURE"));
+            !replaced
+        })
+        .collect();
+    merged.extend(ours);
+    merged
+}
+
+fn encode_vorbis_comment(vendor: &str, comments: &Comments) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
+    out.extend_from_slice(vendor.as_bytes());
+    out.extend_from_slice(&(comments.len() as u32).to_le_bytes());
+    for (key, value) in comments {
+        let entry = format!("{}={}", key, value);
+        out.extend_from_slice(&(entry.len() as u32).to_le_bytes());
+        out.extend_from_slice(entry.as_bytes());
+    }
+    out
+}
+
+fn decode_vorbis_comment(data: &[u8]) -> Result<(String, Comments)> {
+    let mut cursor = ByteCursor::new(data);
+    let vendor_length = cursor.u32_le()? as usize;
+    let vendor = String::from_utf8_lossy(cursor.take(vendor_length)?).to_string();
+    let count = cursor.u32_le()?;
+
+    let mut comments = Vec::new();
+    for _ in 0..count {
+        let length = cursor.u32_le()? as usize;
+        let entry = String::from_utf8_lossy(cursor.take(length)?).to_string();
+        if let Some((key, value)) = entry.split_once('=') {
+            comments.push((key.to_string(), value.to_string()));
+        }
+    }
+    Ok((vendor, comments))
+}
+
+/// FLAC PICTURE block body; Ogg carries the same bytes base64-encoded
+fn encode_picture(cover: &CoverArt) -> Vec<u8> {
+    // Dimensions are informational; leave them zero if the image can't be read
+    let (width, height) = image::ImageReader::new(std::io::Cursor::new(&cover.data))
+        .with_guessed_format()
+        .ok()
+        .and_then(|reader| reader.into_dimensions().ok())
+        .unwrap_or((0, 0));
+
+    let mut out = Vec::new();
+    out.extend_from_slice(&PICTURE_FRONT_COVER.to_be_bytes());
+    out.extend_from_slice(&(cover.mime_type.len() as u32).to_be_bytes());
+    out.extend_from_slice(cover.mime_type.as_bytes());
+    out.extend_from_slice(&0u32.to_be_bytes()); // No description
+    out.extend_from_slice(&width.to_be_bytes());
+    out.extend_from_slice(&height.to_be_bytes());
+    out.extend_from_slice(&(if width > 0 { 24u32 } else { 0 }).to_be_bytes());
+    out.extend_from_slice(&0u32.to_be_bytes()); // Not indexed
+    out.extend_from_slice(&(cover.data.len() as u32).to_be_bytes());
+    out.extend_from_slice(&cover.data);
+    out
+}
+
+fn decode_picture(data: &[u8]) -> Result<CoverArt> {
+    let mut cursor = ByteCursor::new(data);
+    cursor.u32_be()?; // Picture type
+    let mime_length = cursor.u32_be()? as usize;
+    let mime_type = String::from_utf8_lossy(cursor.take(mime_length)?).to_string();
+    let description_length = cursor.u32_be()? as usize;
+    cursor.take(description_length + 16)?; // Description, width, height, depth, colours
+    let data_length = cursor.u32_be()? as usize;
+    Ok(CoverArt {
+        mime_type,
+        data: cursor.take(data_length)?.to_vec(),
+    })
+}
+
+// FLAC: replace the VORBIS_COMMENT (and PICTURE, if we have a cover) blocks
+
+struct FlacBlock {
+    block_type: u8,
+    body: Vec<u8>,
+}
+
+fn read_flac_blocks(reader: &mut impl Read) -> Result<Vec<FlacBlock>> {
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic)?;
+
+    let mut blocks = Vec::new();
+    loop {
+        let mut header = [0u8; 4];
+        reader.read_exact(&mut header)?;
+        let length = u32::from_be_bytes([0, header[1], header[2], header[3]]) as usize;
+        let mut body = vec![0u8; length];
+        reader.read_exact(&mut body)?;
+        blocks.push(FlacBlock { block_type: header[0] & 0x7F, body });
+
+        if header[0] & 0x80 != 0 {
+            return Ok(blocks);
+        }
+    }
+}
+
+fn write_flac_tags(reader: &mut impl Read, writer: &mut impl Write, tags: &RecordingTags) -> Result<()> {
+    let mut vendor = VENDOR.to_string();
+    let mut existing = Vec::new();
+    let mut blocks = Vec::new();
+    for block in read_flac_blocks(reader)? {
+        match block.block_type {
+            FLAC_VORBIS_COMMENT => (vendor, existing) = decode_vorbis_comment(&block.body)?,
+            FLAC_PICTURE if tags.cover.is_some() => {}
+            FLAC_PADDING => {}
+            _ => blocks.push(block),
+        }
+    }
+
+    let comments = merge_comments(existing, tags.vorbis_fields(), false);
+    blocks.push(FlacBlock {
+        block_type: FLAC_VORBIS_COMMENT,
+        body: encode_vorbis_comment(&vendor, &comments),
+    });
+    if let Some(cover) = &tags.cover {
+        blocks.push(FlacBlock {
+            block_type: FLAC_PICTURE,
+            body: encode_picture(cover),
+        });
+    }
+
+    writer.write_all(b"fLaC")?;
+    for (index, block) in blocks.iter().enumerate() {
+        if block.body.len() > FLAC_MAX_BLOCK_LENGTH {
+            return Err(anyhow!("FLAC metadata block of {} bytes is too large (cover art?)", block.body.len()));
+        }
+        let last = if index + 1 == blocks.len() { 0x80 } else { 0 };
+        let length = (block.body.len() as u32).to_be_bytes();
+        writer.write_all(&[last | block.block_type, length[1], length[2], length[3]])?;
+        writer.write_all(&block.body)?;
+    }
+
+    std::io::copy(reader, writer)?;
+    Ok(())
+}
+
+fn read_flac_tags(reader: &mut impl Read) -> Result<RecordingTags> {
+    let mut tags = RecordingTags::default();
+    for block in read_flac_blocks(reader)? {
+        match block.block_type {
+            FLAC_VORBIS_COMMENT => {
+                let cover = tags.cover.take();
+                tags = RecordingTags::from_vorbis_fields(&decode_vorbis_comment(&block.body)?.1);
+                tags.cover = cover;
+            }
+            FLAC_PICTURE => tags.cover = Some(decode_picture(&block.body)?),
+            _ => {}
+        }
+    }
+    Ok(tags)
+}
+
+// Ogg: rewrite the comment header packet and renumber the pages after it
+
+const OGG_CONTINUED: u8 = 0x01;
+
+struct OggPage {
+    header_type: u8,
+    granule: u64,
+    serial: u32,
+    sequence: u32,
+    lacing: Vec<u8>,
+    data: Vec<u8>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum OggCodec {
+    Vorbis,
+    Opus,
+}
+
+impl OggCodec {
+    fn comment_prefix(&self) -> &'static [u8] {
+        match self {
+            OggCodec::Vorbis => b"\x03vorbis",
+            OggCodec::Opus => b"OpusTags",
+        }
+    }
+
+    /// Header packets after the identification header: comments, plus setup for Vorbis
+    fn remaining_headers(&self) -> usize {
+        match self {
+            OggCodec::Vorbis => 2,
+            OggCodec::Opus => 1,
+        }
+    }
+}
+
+const OGG_CRC_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = (i as u32) << 24;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 0x8000_0000 != 0 { (crc << 1) ^ 0x04C1_1DB7 } else { crc << 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+fn ogg_crc(data: &[u8]) -> u32 {
+    data.iter()
+        .fold(0u32, |crc, &byte| (crc << 8) ^ OGG_CRC_TABLE[((crc >> 24) as u8 ^ byte) as usize])
+}
+
+fn read_ogg_page(reader: &mut impl Read) -> Result<Option<OggPage>> {
+    let mut header = [0u8; 27];
+    match reader.read_exact(&mut header) {
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        result => result?,
+    }
+    if &header[..4] != b"OggS" {
+        return Err(anyhow!("Lost Ogg page sync"));
+    }
+
+    let mut lacing = vec![0u8; header[26] as usize];
+    reader.read_exact(&mut lacing)?;
+    let mut data = vec![0u8; lacing.iter().map(|&l| l as usize).sum()];
+    reader.read_exact(&mut data)?;
+
+    Ok(Some(OggPage {
+        header_type: header[5],
+        granule: u64::from_le_bytes(header[6..14].try_into()?),
+        serial: u32::from_le_bytes(header[14..18].try_into()?),
+        sequence: u32::from_le_bytes(header[18..22].try_into()?),
+        lacing,
+        data,
+    }))
+}
+
+fn write_ogg_page(writer: &mut impl Write, page: &OggPage) -> Result<()> {
+    let mut bytes = Vec::with_capacity(27 + page.lacing.len() + page.data.len());
+    bytes.extend_from_slice(b"OggS");
+    bytes.push(0);
+    bytes.push(page.header_type);
+    bytes.extend_from_slice(&page.granule.to_le_bytes());
+    bytes.extend_from_slice(&page.serial.to_le_bytes());
+    bytes.extend_from_slice(&page.sequence.to_le_bytes());
+    bytes.extend_from_slice(&[0; 4]);
+    bytes.push(page.lacing.len() as u8);
+    bytes.extend_from_slice(&page.lacing);
+    bytes.extend_from_slice(&page.data);
+
+    let crc = ogg_crc(&bytes);
+    bytes[22..26].copy_from_slice(&crc.to_le_bytes());
+    writer.write_all(&bytes)?;
+    Ok(())
+}
+
+/// Add a page's packet data to `partial`; returns the packets completed on it
+fn collect_packets(page: &OggPage, partial: &mut Vec<u8>) -> Vec<Vec<u8>> {
+    let mut packets = Vec::new();
+    let mut offset = 0;
+    for &length in &page.lacing {
+        partial.extend_from_slice(&page.data[offset..offset + length as usize]);
+        offset += length as usize;
+        if length < 255 {
+            packets.push(std::mem::take(partial));
+        }
+    }
+    packets
+}
+
+/// Pages carrying one header packet, starting at `sequence`
+fn header_pages(packet: &[u8], serial: u32, sequence: &mut u32) -> Vec<OggPage> {
+    let mut lacing = vec![255u8; packet.len() / 255];
+    lacing.push((packet.len() % 255) as u8);
+
+    let mut pages = Vec::new();
+    let mut offset = 0;
+    let mut continued = false;
+    for chunk in lacing.chunks(255) {
+        let length: usize = chunk.iter().map(|&l| l as usize).sum();
+        let ends_packet = chunk.last().is_some_and(|&l| l < 255);
+        pages.push(OggPage {
+            header_type: if continued { OGG_CONTINUED } else { 0 },
+            granule: if ends_packet { 0 } else { u64::MAX }, // No packet ends here
+            serial,
+            sequence: *sequence,
+            lacing: chunk.to_vec(),
+            data: packet[offset..offset + length].to_vec(),
+        });
+        *sequence += 1;
+        offset += length;
+        continued = !ends_packet;
+    }
+    pages
+}
+
+/// The first logical stream's codec, its first page and the header packets after the
+/// identification header. Pages of other streams seen on the way are passed to `other`.
+fn read_ogg_headers(
+    reader: &mut impl Read,
+    mut other: impl FnMut(&OggPage) -> Result<()>,
+) -> Result<(OggCodec, OggPage, Vec<Vec<u8>>, u32)> {
+    let first = read_ogg_page(reader)?.ok_or_else(|| anyhow!("Empty Ogg file"))?;
+    let mut partial = Vec::new();
+    let identification = collect_packets(&first, &mut partial);
+    let codec = match identification.as_slice() {
+        [packet] if packet.starts_with(b"\x01vorbis") => OggCodec::Vorbis,
+        [packet] if packet.starts_with(b"OpusHead") => OggCodec::Opus,
+        _ => return Err(anyhow!("Unsupported Ogg stream: expected Vorbis or Opus")),
+    };
+
+    let mut packets = Vec::new();
+    let mut pages = 0;
+    while packets.len() < codec.remaining_headers() {
+        let page = read_ogg_page(reader)?.ok_or_else(|| anyhow!("Ogg file ends inside its headers"))?;
+        if page.serial != first.serial {
+            other(&page)?;
+            continue;
+        }
+        pages += 1;
+        packets.extend(collect_packets(&page, &mut partial));
+    }
+    // Audio starts on a fresh page in well-formed files
+    if packets.len() > codec.remaining_headers() || !partial.is_empty() {
+        return Err(anyhow!("Ogg headers share a page with audio"));
+    }
+
+    Ok((codec, first, packets, pages))
+}
+
+fn write_ogg_tags(reader: &mut impl Read, writer: &mut impl Write, tags: &RecordingTags) -> Result<()> {
+    let mut passed_through = Vec::new();
+    let (codec, first, mut packets, old_page_count) = read_ogg_headers(reader, |page| {
+        write_ogg_page(&mut passed_through, page)
+    })?;
+    write_ogg_page(writer, &first)?;
+    writer.write_all(&passed_through)?;
+
+    let prefix = codec.comment_prefix();
+    let comment_body = packets[0]
+        .strip_prefix(prefix)
+        .ok_or_else(|| anyhow!("Ogg comment header is missing"))?;
+    let (vendor, existing) = decode_vorbis_comment(comment_body)?;
+
+    let mut ours = tags.vorbis_fields();
+    if let Some(cover) = &tags.cover {
+        ours.push((
+            "METADATA_BLOCK_PICTURE".to_string(),
+            base64::engine::general_purpose::STANDARD.encode(encode_picture(cover)),
+        ));
+    }
+    let mut comment_packet = prefix.to_vec();
+    comment_packet.extend(encode_vorbis_comment(&vendor, &merge_comments(existing, ours, tags.cover.is_some())));
+    if codec == OggCodec::Vorbis {
+        comment_packet.push(1); // Framing bit
+    }
+    packets[0] = comment_packet;
+
+    let serial = first.serial;
+    let mut sequence = first.sequence + 1;
+    for packet in &packets {
+        for page in header_pages(packet, serial, &mut sequence) {
+            write_ogg_page(writer, &page)?;
+        }
+    }
+
+    // The rest of the stream keeps its pages, renumbered after the new headers
+    let shift = i64::from(sequence) - i64::from(first.sequence + 1 + old_page_count);
+    while let Some(mut page) = read_ogg_page(reader)? {
+        if page.serial == serial {
+            page.sequence = (i64::from(page.sequence) + shift) as u32;
+        }
+        write_ogg_page(writer, &page)?;
+    }
+    Ok(())
+}
+
+fn read_ogg_tags(reader: &mut impl Read) -> Result<RecordingTags> {
+    let (codec, _, packets, _) = read_ogg_headers(reader, |_| Ok(()))?;
+    let comment_body = packets[0]
+        .strip_prefix(codec.comment_prefix())
+        .ok_or_else(|| anyhow!("Ogg comment header is missing"))?;
+    let (_, comments) = decode_vorbis_comment(comment_body)?;
+
+    let mut tags = RecordingTags::from_vorbis_fields(&comments);
+    if let Some((_, picture)) = comments.iter().find(|(key, _)| key.eq_ignore_ascii_case("METADATA_BLOCK_PICTURE")) {
+        tags.cover = Some(decode_picture(&base64::engine::general_purpose::STANDARD.decode(picture)?)?);
+    }
+    Ok(tags)
+}
+
+// MP3: replace any leading ID3v2 tag with an ID3v2.4 one
+
+const ID3_UTF8: u8 = 3;
+
+fn syncsafe(value: usize) -> Result<[u8; 4]> {
+    if value >= 1 << 28 {
+        return Err(anyhow!("ID3 tag of {} bytes is too large", value));
+    }
+    Ok([(value >> 21) as u8 & 0x7F, (value >> 14) as u8 & 0x7F, (value >> 7) as u8 & 0x7F, value as u8 & 0x7F])
+}
+
+fn from_syncsafe(bytes: &[u8]) -> usize {
+    bytes.iter().fold(0, |value, &byte| (value << 7) | (byte & 0x7F) as usize)
+}
+
+fn id3_frame(id: &[u8; 4], body: &[u8]) -> Result<Vec<u8>> {
+    let mut frame = id.to_vec();
+    frame.extend_from_slice(&syncsafe(body.len())?);
+    frame.extend_from_slice(&[0, 0]);
+    frame.extend_from_slice(body);
+    Ok(frame)
+}
+
+fn id3_text_frame(id: &[u8; 4], text: &str) -> Result<Vec<u8>> {
+    let mut body = vec![ID3_UTF8];
+    body.extend_from_slice(text.as_bytes());
+    id3_frame(id, &body)
+}
+
+/// Size of the ID3v2 tag at the start of `header` (10 bytes), if there is one
+fn id3_tag_size(header: &[u8; 10]) -> Option<usize> {
+    if &header[..3] != b"ID3" {
+        return None;
+    }
+    let footer = if header[5] & 0x10 != 0 { 10 } else { 0 };
+    Some(10 + from_syncsafe(&header[6..10]) + footer)
+}
+
+fn write_id3_tags(reader: &mut (impl Read + Seek), writer: &mut impl Write, tags: &RecordingTags) -> Result<()> {
+    let mut header = [0u8; 10];
+    reader.read_exact(&mut header)?;
+    reader.seek(SeekFrom::Start(id3_tag_size(&header).unwrap_or(0) as u64))?;
+
+    let mut frames = Vec::new();
+    frames.extend(id3_text_frame(b"TPE1", &tags.artist)?);
+    frames.extend(id3_text_frame(b"TALB", &tags.album)?);
+    frames.extend(id3_text_frame(b"TDRC", &tags.date)?);
+    if let Some(comment) = &tags.comment {
+        let mut body = vec![ID3_UTF8];
+        body.extend_from_slice(b"eng\0"); // Language, empty description
+        body.extend_from_slice(comment.as_bytes());
+        frames.extend(id3_frame(b"COMM", &body)?);
+    }
+    if let Some(cover) = &tags.cover {
+        let mut body = vec![ID3_UTF8];
+        body.extend_from_slice(cover.mime_type.as_bytes());
+        body.push(0);
+        body.push(PICTURE_FRONT_COVER as u8);
+        body.push(0); // Empty description
+        body.extend_from_slice(&cover.data);
+        frames.extend(id3_frame(b"APIC", &body)?);
+    }
+
+    writer.write_all(b"ID3\x04\x00\x00")?;
+    writer.write_all(&syncsafe(frames.len())?)?;
+    writer.write_all(&frames)?;
+    std::io::copy(reader, writer)?;
+    Ok(())
+}
+
+fn decode_id3_text(encoding: u8, bytes: &[u8]) -> String {
+    let bytes = bytes.strip_suffix(&[0]).unwrap_or(bytes);
+    match encoding {
+        0 => bytes.iter().map(|&b| b as char).collect(), // ISO-8859-1
+        _ => String::from_utf8_lossy(bytes).to_string(),
+    }
+}
+
+fn read_id3_tags(reader: &mut impl Read) -> Result<RecordingTags> {
+    let mut header = [0u8; 10];
+    reader.read_exact(&mut header)?;
+    let mut tags = RecordingTags::default();
+    let Some(size) = id3_tag_size(&header) else {
+        return Ok(tags);
+    };
+    let version = header[3];
+    let mut body = vec![0u8; size - 10];
+    reader.read_exact(&mut body)?;
+
+    let mut cursor = ByteCursor::new(&body);
+    while let Ok(frame_header) = cursor.take(10) {
+        if frame_header[0] == 0 {
+            break; // Padding
+        }
+        // ID3v2.3 frame sizes are plain integers, v2.4 ones syncsafe
+        let length = if version >= 4 {
+            from_syncsafe(&frame_header[4..8])
+        } else {
+            u32::from_be_bytes(frame_header[4..8].try_into()?) as usize
+        };
+        let frame = cursor.take(length)?;
+        let Some((&encoding, content)) = frame.split_first() else {
+            continue;
+        };
+
+        match &frame_header[..4] {
+            b"TPE1" => tags.artist = decode_id3_text(encoding, content),
+            b"TALB" => tags.album = decode_id3_text(encoding, content),
+            b"TDRC" | b"TYER" => tags.date = decode_id3_text(encoding, content),
+            b"COMM" if content.len() >= 3 => {
+                let text = &content[3..];
+                let description_end = text.iter().position(|&b| b == 0).map_or(0, |end| end + 1);
+                tags.comment = Some(decode_id3_text(encoding, &text[description_end..]));
+            }
+            b"APIC" => {
+                let mime_end = content.iter().position(|&b| b == 0).unwrap_or(content.len());
+                let rest = content.get(mime_end + 2..).unwrap_or_default(); // NUL, picture type
+                let description_end = rest.iter().position(|&b| b == 0).map_or(0, |end| end + 1);
+                tags.cover = Some(CoverArt {
+                    mime_type: String::from_utf8_lossy(&content[..mime_end]).to_string(),
+                    data: rest[description_end..].to_vec(),
+                });
+            }
+            _ => {}
+        }
+    }
+    Ok(tags)
+}
+
+struct ByteCursor<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> ByteCursor<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    fn take(&mut self, length: usize) -> Result<&'a [u8]> {
+        if length > self.data.len() {
+            return Err(anyhow!("Truncated tag data"));
+        }
+        let (taken, rest) = self.data.split_at(length);
+        self.data = rest;
+        Ok(taken)
+    }
+
+    fn u32_le(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into()?))
+    }
+
+    fn u32_be(&mut self) -> Result<u32> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ogg_crc() {
+        // CRC-32/POSIX check value without the final inversion
+        assert_eq!(ogg_crc(b"123456789"), 0x765E_7680 ^ 0xFFFF_FFFF);
+    }
+
+    #[test]
+    fn test_syncsafe_round_trip() {
+        assert_eq!(syncsafe(0x0FFF_FFFF).unwrap(), [0x7F; 4]);
+        assert_eq!(from_syncsafe(&syncsafe(300_000).unwrap()), 300_000);
+        assert!(syncsafe(1 << 28).is_err());
+    }
+}
@@ -0,0 +1,154 @@
+use anyhow::{bail, Result};
+use std::ops::Range;
+
+/// Peak data in audiowaveform's binary `.dat` format, which the session recorder writes
+/// next to each recording as `waveform.dat`.
+///
+/// Version 1 files are mono; version 2 adds a channel count and stores a min/max pair per
+/// channel for every pixel. Channels are merged on parsing since only the outline is drawn.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WaveformData {
+    pub sample_rate: u32,
+    pub samples_per_pixel: u32,
+    pub bits: u8, // 8 or 16
+    pub channels: u32,
+    min: Vec<i16>,
+    max: Vec<i16>,
+}
+
+const HEADER_V1: usize = 20;
+const HEADER_V2: usize = 24;
+const FLAG_8_BIT: u32 = 1;
+
+impl WaveformData {
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        if data.len() < HEADER_V1 {
+            bail!("Waveform data too short for a header");
+        }
+        let field = |at: usize| u32::from_le_bytes([data[at], data[at + 1], data[at + 2], data[at + 3]]);
+
+        let version = field(0);
+        let bits = if field(4) & FLAG_8_BIT != 0 { 8 } else { 16 };
+        let sample_rate = field(8);
+        let samples_per_pixel = field(12);
+        let length = field(16) as usize;
+        let (channels, header) = match version {
+            1 => (1, HEADER_V1),
+            2 if data.len() >= HEADER_V2 => (field(20), HEADER_V2),
+            2 => bail!("Waveform data too short for a version 2 header"),
+            other => bail!("Unsupported waveform version {}", other),
+        };
+        if sample_rate == 0 || samples_per_pixel == 0 {
+            bail!("Waveform has a zero sample rate or scale");
+        }
+        if !(1..=24).contains(&channels) {
+            bail!("Waveform has {} channels", channels);
+        }
+
+        let value_size = bits as usize / 8;
+        let pixel_size = 2 * channels as usize * value_size;
+        let available = (data.len() - header) / pixel_size;
+        if available < length {
+            bail!("Waveform data holds {} of its {} points", available, length);
+        }
+
+        let value = |at: usize| -> i16 {
+            if bits == 8 {
+                // Scaled up so both resolutions share one range
+                (data[at] as i8 as i16) << 8
+            } else {
+                i16::from_le_bytes([data[at], data[at + 1]])
+            }
+        };
+        let mut min = Vec::with_capacity(length);
+        let mut max = Vec::with_capacity(length);
+        for pixel in 0..length {
+            let start = header + pixel * pixel_size;
+            let (mut low, mut high) = (i16::MAX, i16::MIN);
+            for channel in 0..channels as usize {
+                let at = start + channel * 2 * value_size;
+                low = low.min(value(at));
+                high = high.max(value(at + value_size));
+            }
+            min.push(low);
+            max.push(high);
+        }
+
+        Ok(Self {
+            sample_rate,
+            samples_per_pixel,
+            bits,
+            channels,
+            min,
+            max,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.min.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.is_empty()
+    }
+
+    pub fn seconds_per_pixel(&self) -> f64 {
+        self.samples_per_pixel as f64 / self.sample_rate as f64
+    }
+
+    pub fn duration_seconds(&self) -> f64 {
+        self.len() as f64 * self.seconds_per_pixel()
+    }
+
+    /// Pixels covering `start..end` seconds, clamped to the data
+    pub fn pixels_between(&self, start_seconds: f64, end_seconds: f64) -> Range<usize> {
+        let to_pixel = |seconds: f64| ((seconds.max(0.0) / self.seconds_per_pixel()).round() as usize).min(self.len());
+        let start = to_pixel(start_seconds);
+        start..to_pixel(end_seconds).max(start)
+    }
+
+    /// Min/max pairs scaled to -1.0..=1.0, reduced to at most `points` by keeping the
+    /// extremes of each bucket so short peaks survive
+    pub fn peaks(&self, pixels: Range<usize>, points: usize) -> Vec<(f32, f32)> {
+        let pixels = pixels.start.min(self.len())..pixels.end.min(self.len());
+        let count = pixels.len();
+        if count == 0 || points == 0 {
+            return Vec::new();
+        }
+        let buckets = count.min(points);
+        let scale = |v: i16| v as f32 / 32768.0;
+
+        (0..buckets)
+            .map(|bucket| {
+                let from = pixels.start + bucket * count / buckets;
+                let to = pixels.start + (bucket + 1) * count / buckets;
+                let low = self.min[from..to].iter().copied().min().unwrap_or(0);
+                let high = self.max[from..to].iter().copied().max().unwrap_or(0);
+                (scale(low), scale(high))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_peaks_keep_bucket_extremes() {
+        let waveform = WaveformData {
+            sample_rate: 100,
+            samples_per_pixel: 10,
+            bits: 16,
+            channels: 1,
+            min: vec![-100, -16384, -50, -10],
+            max: vec![100, 50, 32767, 10],
+        };
+        assert_eq!(waveform.duration_seconds(), 0.4);
+        assert_eq!(waveform.peaks(0..4, 2), vec![(-0.5, 100.0 / 32768.0), (-50.0 / 32768.0, 32767.0 / 32768.0)]);
+        assert_eq!(waveform.peaks(0..4, 10).len(), 4);
+        assert_eq!(waveform.pixels_between(0.1, 0.25), 1..3);
+        assert_eq!(waveform.pixels_between(0.3, 9.0), 3..4);
+        assert!(waveform.peaks(4..4, 10).is_empty());
+    }
+}
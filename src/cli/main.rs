@@ -0,0 +1,235 @@
+use anyhow::{anyhow, Result};
+use std::sync::Arc;
+
+use session_recorder_addon::{
+    models::{
+        simulation::SimulationRequest, AppState, ArchiveFormat, FairnessReport, SegmentRecordingRequest,
+        TimetableFormat,
+    },
+    services::{ArchiveService, LotteryService, SegmentationService, TimetableExportService},
+};
+
+const USAGE: &str = "\
+Usage: cli <command> [options]
+
+Commands:
+  simulate    Monte Carlo simulation of the current pool and fairness report
+      --runs <n>        Number of virtual events (default 10000)
+      --seed <n>        Random seed, reported back for reproducible runs
+      --slots <n>       Slots per virtual event (default: six hours of slots)
+      --event <id>      Restrict the historical comparison to one event
+      --json            Print the full report as JSON
+  archive     Bundle an event's recordings, timetable, cue sheet and README
+      --event <id>      Event to archive
+      --format <fmt>    zip (default) or tar
+      --output <path>   File to write (default: <event name>_<date>.<fmt>)
+  segment     Split a continuous recording into one file per DJ session
+      --file <path>     Local recording file
+      --started-at <t>  Wall-clock start of the recording (RFC 3339), required with --file
+      --recorder <id>   Recorder to download from instead of --file
+      --session <id>    Recorder session to download
+      --pre-roll <s>    Seconds to keep before each set (default SEGMENTATION_PRE_ROLL_SECONDS)
+      --post-roll <s>   Seconds to keep after each set (default SEGMENTATION_POST_ROLL_SECONDS)
+  timetable   Export an event's timetable
+      --event <id>      Event to export
+      --format <fmt>    json (default), csv, markdown or html
+      --output <path>   File to write (default: standard output)
+";
+
+/// Minimal `--flag value` parser; the CLI is small enough not to need a framework
+struct Args {
+    command: Option<String>,
+    options: Vec<(String, Option<String>)>,
+}
+
+impl Args {
+    fn parse() -> Result<Self> {
+        let mut raw = std::env::args().skip(1).peekable();
+        let command = raw.next();
+        let mut options = Vec::new();
+
+        while let Some(arg) = raw.next() {
+            let name = arg
+                .strip_prefix("--")
+                .ok_or_else(|| anyhow!("Unexpected argument '{}'", arg))?
+                .to_string();
+            let value = match raw.peek() {
+                Some(next) if !next.starts_with("--") => raw.next(),
+                _ => None,
+            };
+            options.push((name, value));
+        }
+
+        Ok(Self { command, options })
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.options.iter().any(|(n, _)| n == name)
+    }
+
+    fn value<T: std::str::FromStr>(&self, name: &str) -> Result<Option<T>> {
+        match self.options.iter().find(|(n, _)| n == name) {
+            Some((_, Some(value))) => value
+                .parse()
+                .map(Some)
+                .map_err(|_| anyhow!("Invalid value '{}' for --{}", value, name)),
+            Some((_, None)) => Err(anyhow!("--{} needs a value", name)),
+            None => Ok(None),
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    dotenv::dotenv().ok();
+
+    let args = Args::parse()?;
+
+    match args.command.as_deref() {
+        Some("simulate") => simulate(&args).await,
+        Some("archive") => archive(&args).await,
+        Some("segment") => segment(&args).await,
+        Some("timetable") => timetable(&args).await,
+        Some("help") | Some("--help") | None => {
+            print!("{}", USAGE);
+            Ok(())
+        }
+        Some(other) => Err(anyhow!("Unknown command '{}'\n\n{}", other, USAGE)),
+    }
+}
+
+async fn simulate(args: &Args) -> Result<()> {
+    let request = SimulationRequest {
+        runs: args.value("runs")?,
+        seed: args.value("seed")?,
+        slots: args.value("slots")?,
+        event_id: args.value("event")?,
+    };
+
+    let app_state = Arc::new(AppState::new().await?);
+    let report = LotteryService::new(app_state).get_fairness_report(request).await?;
+
+    if args.flag("json") {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print_report(&report);
+    }
+
+    Ok(())
+}
+
+async fn archive(args: &Args) -> Result<()> {
+    let event_id: String = args.value("event")?.ok_or_else(|| anyhow!("--event is required\n\n{}", USAGE))?;
+    let format: ArchiveFormat = args.value("format")?.unwrap_or_default();
+
+    let app_state = Arc::new(AppState::new().await?);
+    let archive = ArchiveService::new(app_state).prepare(&event_id).await?;
+    let output: String = args.value("output")?.unwrap_or_else(|| archive.filename(format));
+
+    let file = tokio::fs::File::create(&output).await?;
+    let mut file = archive.write(format, tokio::io::BufWriter::new(file)).await?;
+    tokio::io::AsyncWriteExt::shutdown(&mut file).await?;
+
+    let recorded = archive.timetable.iter().filter(|entry| entry.recording.is_some()).count();
+    println!(
+        "Wrote {} ({} sets, {} recorded)",
+        output,
+        archive.timetable.len(),
+        recorded
+    );
+    Ok(())
+}
+
+async fn segment(args: &Args) -> Result<()> {
+    let request = SegmentRecordingRequest {
+        recorder_id: args.value("recorder")?,
+        recorder_session_id: args.value("session")?,
+        file_path: args.value("file")?,
+        recording_started_at: args.value("started-at")?,
+        pre_roll_seconds: args.value("pre-roll")?,
+        post_roll_seconds: args.value("post-roll")?,
+    };
+
+    let app_state = Arc::new(AppState::new().await?);
+    let report = SegmentationService::new(app_state).segment_recording(request).await?;
+
+    for segment in &report.segments {
+        println!(
+            "{:<24} {:>9.0}s {:>9.0}s  {}",
+            segment.dj_name, segment.offset_seconds, segment.duration_seconds, segment.file_path
+        );
+    }
+    println!("Wrote {} segments", report.segments.len());
+    Ok(())
+}
+
+async fn timetable(args: &Args) -> Result<()> {
+    let event_id: String = args.value("event")?.ok_or_else(|| anyhow!("--event is required\n\n{}", USAGE))?;
+    let format: TimetableFormat = args.value("format")?.unwrap_or_default();
+
+    let app_state = Arc::new(AppState::new().await?);
+    let export = TimetableExportService::new(app_state).export(&event_id, format).await?;
+
+    match args.value::<String>("output")? {
+        Some(output) => {
+            tokio::fs::write(&output, &export.body).await?;
+            println!("Wrote {}", output);
+        }
+        None => print!("{}", export.body),
+    }
+    Ok(())
+}
+
+fn print_report(report: &FairnessReport) {
+    let simulation = &report.simulation;
+
+    println!(
+        "Simulated {} events of {} slots ({} min) with {} DJs in the pool, seed {}",
+        simulation.runs,
+        simulation.slots,
+        simulation.slot_duration_minutes,
+        simulation.pool_size,
+        simulation.seed
+    );
+    println!();
+    println!("{:<24} {:>8} {:>10} {:>12}", "DJ", "Weight", "P(play)", "Wait (min)");
+    for dj in &simulation.djs {
+        let wait = dj
+            .expected_wait_minutes
+            .map(|w| format!("{:.0}", w))
+            .unwrap_or_else(|| "-".to_string());
+        println!(
+            "{:<24} {:>8.2} {:>9.1}% {:>12}",
+            dj.dj_name,
+            dj.current_weight,
+            dj.play_probability * 100.0,
+            wait
+        );
+    }
+    println!();
+    println!("Gini coefficient: {:.3}", simulation.gini_coefficient);
+
+    let historical = &report.historical;
+    println!();
+    println!("Historical draws: {}", historical.total_draws);
+    if historical.total_draws > 0 {
+        println!("{:<24} {:>8} {:>10} {:>10}", "DJ", "Entered", "Won", "Expected");
+        for dj in &historical.djs {
+            println!(
+                "{:<24} {:>8} {:>10} {:>10.2}",
+                dj.dj_name.as_deref().unwrap_or(&dj.dj_id),
+                dj.draws_entered,
+                dj.observed_wins,
+                dj.expected_wins
+            );
+        }
+        println!();
+    }
+    match historical.p_value {
+        Some(p_value) => println!(
+            "Chi-square: {:.3} ({} degrees of freedom), p = {:.4}",
+            historical.chi_square, historical.degrees_of_freedom, p_value
+        ),
+        None => println!("Chi-square: not enough historical data"),
+    }
+}
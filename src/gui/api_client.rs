@@ -73,6 +73,21 @@ pub struct StartEventRequest {
     pub slot_duration_minutes: Option<i32>,
     pub late_arrival_cutoff_hours: Option<i32>,
     pub started_at: Option<String>, // ISO 8601 datetime string
+    pub late_penalty_curve: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PenaltyPreviewPoint {
+    pub minutes_after_start: i64,
+    pub multiplier: f64,
+    pub weight: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PenaltyPreviewResponse {
+    pub curve: Value,
+    pub base_weight: f64,
+    pub points: Vec<PenaltyPreviewPoint>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -84,6 +99,10 @@ pub struct TimetableEntry {
     pub ended_at: Option<String>,
     pub duration_minutes: Option<i32>,
     pub status: String,
+    #[serde(default)]
+    pub reserved_kind: Option<String>,
+    #[serde(default)]
+    pub reserved_label: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -95,6 +114,101 @@ pub struct Timetable {
     pub completed_sets: usize,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WeightComponent {
+    pub kind: String,
+    pub factor: f64,
+    pub description: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OddsParticipant {
+    pub calculated_weight: f64,
+    pub selection_probability: f64,
+    pub weight_components: Vec<WeightComponent>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DrawHistoryEntry {
+    pub draw_id: String,
+    pub drawn_at: String,
+    #[serde(default)]
+    pub status: String,
+    pub winner_name: Option<String>,
+    pub was_winner: bool,
+    pub participant_count: i64,
+    pub calculated_weight: f64,
+    pub selection_probability: f64,
+    pub weight_components: Vec<WeightComponent>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DjOddsResponse {
+    pub lottery_status: Value,
+    pub current: Option<OddsParticipant>,
+    pub pool_size: usize,
+    pub history: Vec<DrawHistoryEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WaveformPeak {
+    pub min: f32,
+    pub max: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WaveformSlot {
+    pub session_id: String,
+    pub dj_name: String,
+    pub start_seconds: f64,
+    pub end_seconds: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionWaveform {
+    pub session_id: String,
+    pub recording_started_at: String,
+    pub duration_seconds: f64,
+    pub seconds_per_peak: f64,
+    pub peaks: Vec<WaveformPeak>,
+    pub slots: Vec<WaveformSlot>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingAnalysis {
+    pub session_id: String,
+    pub integrated_lufs: Option<f64>,
+    pub true_peak_dbtp: Option<f64>,
+    pub clipping_ratio: f64,
+    pub silent_seconds: f64,
+    pub flags: Vec<String>,
+    pub flagged: bool,
+    pub delivery_held: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TracklistTrack {
+    pub position: i64,
+    pub offset_seconds: Option<f64>,
+    pub artist: Option<String>,
+    pub title: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tracklist {
+    pub session_id: String,
+    pub updated_at: Option<String>,
+    pub tracks: Vec<TracklistTrack>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NowPlaying {
+    pub session_id: String,
+    pub dj_name: String,
+    pub session_started_at: String,
+    pub track: Option<TracklistTrack>,
+}
+
 pub struct ApiClient {
     base_url: String,
     client: reqwest::blocking::Client,
@@ -146,6 +260,17 @@ impl ApiClient {
         Ok(())
     }
 
+    pub fn get_dj_odds(&self, dj_id: &str) -> Result<DjOddsResponse, String> {
+        let url = format!("{}/djs/{}/odds", self.base_url, dj_id);
+
+        self.client
+            .get(&url)
+            .send()
+            .map_err(|e| format!("Request failed: {}", e))?
+            .json::<DjOddsResponse>()
+            .map_err(|e| format!("Failed to parse response: {}", e))
+    }
+
     pub fn get_dj_pool(&self) -> Result<DjPoolResponse, String> {
         let url = format!("{}/djs/pool", self.base_url);
 
@@ -283,6 +408,102 @@ impl ApiClient {
             .map_err(|e| format!("Failed to parse response: {}", e))
     }
 
+    /// `None` if the session has no recording with a waveform
+    pub fn get_session_waveform(&self, session_id: &str, points: usize) -> Result<Option<SessionWaveform>, String> {
+        let url = format!("{}/sessions/{}/waveform?points={}", self.base_url, session_id, points);
+
+        let response = self.client
+            .get(&url)
+            .send()
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !response.status().is_success() {
+            let message = response.text().unwrap_or_default();
+            return Err(format!("Waveform unavailable: {}", message));
+        }
+
+        response.json::<SessionWaveform>()
+            .map(Some)
+            .map_err(|e| format!("Failed to parse response: {}", e))
+    }
+
+    pub fn get_analyses(&self) -> Result<Vec<RecordingAnalysis>, String> {
+        let url = format!("{}/admin/analyses", self.base_url);
+
+        self.client
+            .get(&url)
+            .send()
+            .map_err(|e| format!("Request failed: {}", e))?
+            .json::<Vec<RecordingAnalysis>>()
+            .map_err(|e| format!("Failed to parse response: {}", e))
+    }
+
+    pub fn release_delivery(&self, session_id: &str) -> Result<(), String> {
+        let url = format!("{}/admin/sessions/{}/release", self.base_url, session_id);
+
+        let response = self.client
+            .post(&url)
+            .send()
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        if !response.status().is_success() {
+            let message = response.text().unwrap_or_default();
+            return Err(message);
+        }
+        Ok(())
+    }
+
+    pub fn get_tracklist(&self, session_id: &str) -> Result<Tracklist, String> {
+        let url = format!("{}/sessions/{}/tracklist", self.base_url, session_id);
+
+        self.client
+            .get(&url)
+            .send()
+            .map_err(|e| format!("Request failed: {}", e))?
+            .json::<Tracklist>()
+            .map_err(|e| format!("Failed to parse response: {}", e))
+    }
+
+    /// Pasted tracklist; `format` is one of auto, text, cue, m3u or rekordbox
+    pub fn submit_tracklist_text(&self, session_id: &str, text: String, format: &str) -> Result<Tracklist, String> {
+        let url = format!("{}/sessions/{}/tracklist", self.base_url, session_id);
+        let request = serde_json::json!({ "text": text, "format": format });
+
+        let response = self.client
+            .put(&url)
+            .json(&request)
+            .send()
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        if !response.status().is_success() {
+            let message = response.text().unwrap_or_default();
+            return Err(message);
+        }
+        response.json::<Tracklist>()
+            .map_err(|e| format!("Failed to parse response: {}", e))
+    }
+
+    /// Exported file as it is on disk, e.g. a UTF-16 rekordbox TXT
+    pub fn import_tracklist(&self, session_id: &str, data: Vec<u8>, format: &str) -> Result<Tracklist, String> {
+        let url = format!("{}/sessions/{}/tracklist/import?format={}", self.base_url, session_id, format);
+
+        let response = self.client
+            .post(&url)
+            .body(data)
+            .send()
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        if !response.status().is_success() {
+            let message = response.text().unwrap_or_default();
+            return Err(message);
+        }
+        response.json::<Tracklist>()
+            .map_err(|e| format!("Failed to parse response: {}", e))
+    }
+
     pub fn get_session_statistics(&self) -> Result<Value, String> {
         let url = format!("{}/sessions/statistics", self.base_url);
 
@@ -295,12 +516,13 @@ impl ApiClient {
     }
 
     // Event Endpoints
-    pub fn start_event(&self, slot_duration: Option<i32>, late_arrival_cutoff: Option<i32>, started_at: Option<String>) -> Result<EventSessionResponse, String> {
+    pub fn start_event(&self, slot_duration: Option<i32>, late_arrival_cutoff: Option<i32>, started_at: Option<String>, late_penalty_curve: Option<Value>) -> Result<EventSessionResponse, String> {
         let url = format!("{}/event/start", self.base_url);
         let request = StartEventRequest {
             slot_duration_minutes: slot_duration,
             late_arrival_cutoff_hours: late_arrival_cutoff,
             started_at,
+            late_penalty_curve,
         };
 
         self.client
@@ -312,6 +534,44 @@ impl ApiClient {
             .map_err(|e| format!("Failed to parse response: {}", e))
     }
 
+    pub fn preview_penalty_curve(&self, curve: Value) -> Result<PenaltyPreviewResponse, String> {
+        let url = format!("{}/event/penalty-preview", self.base_url);
+        let request = serde_json::json!({ "curve": curve, "until_minutes": 360, "step_minutes": 10 });
+
+        let response = self.client
+            .post(&url)
+            .json(&request)
+            .send()
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        if !response.status().is_success() {
+            let message = response.text().unwrap_or_default();
+            return Err(format!("Invalid penalty curve: {}", message));
+        }
+
+        response.json::<PenaltyPreviewResponse>()
+            .map_err(|e| format!("Failed to parse response: {}", e))
+    }
+
+    /// The set in progress and its latest track; `None` between sets
+    pub fn get_now_playing(&self) -> Result<Option<NowPlaying>, String> {
+        let url = format!("{}/event/now-playing", self.base_url);
+
+        let response = self.client
+            .get(&url)
+            .send()
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        response
+            .json::<NowPlaying>()
+            .map(Some)
+            .map_err(|e| format!("Failed to parse response: {}", e))
+    }
+
     pub fn get_current_event(&self) -> Result<Option<EventSessionResponse>, String> {
         let url = format!("{}/event/current", self.base_url);
 
@@ -82,4 +82,93 @@ impl StatusIndicator {
             ui.colored_label(color, status);
         });
     }
-}
\ No newline at end of file
+}
+pub struct PenaltyCurvePlot;
+
+impl PenaltyCurvePlot {
+    /// Draws multiplier (0..1) against minutes after event start
+    pub fn show(ui: &mut egui::Ui, points: &[(f32, f32)], max_minutes: f32) {
+        let size = egui::vec2(ui.available_width().min(400.0), 120.0);
+        let (rect, _) = ui.allocate_exact_size(size, egui::Sense::hover());
+        let painter = ui.painter();
+
+        painter.rect_filled(rect, egui::Rounding::same(3.0), egui::Color32::from_gray(30));
+
+        // Reference line at 50% weight
+        let half_y = rect.bottom() - rect.height() * 0.5;
+        painter.line_segment(
+            [egui::pos2(rect.left(), half_y), egui::pos2(rect.right(), half_y)],
+            egui::Stroke::new(1.0, egui::Color32::from_gray(70)),
+        );
+
+        let to_screen = |(minutes, multiplier): (f32, f32)| {
+            egui::pos2(
+                rect.left() + rect.width() * (minutes / max_minutes.max(1.0)),
+                rect.bottom() - rect.height() * multiplier.clamp(0.0, 1.0),
+            )
+        };
+
+        let line: Vec<egui::Pos2> = points.iter().copied().map(to_screen).collect();
+        painter.add(egui::Shape::line(line, egui::Stroke::new(2.0, egui::Color32::LIGHT_GREEN)));
+    }
+}
+
+pub struct WaveformView;
+
+impl WaveformView {
+    /// Draws min/max peaks (-1..1) over `duration_seconds`, with set boundaries as
+    /// `(start, end, label)` in seconds. The set at `highlight` is shaded.
+    pub fn show(
+        ui: &mut egui::Ui,
+        peaks: &[(f32, f32)],
+        duration_seconds: f32,
+        slots: &[(f32, Option<f32>, &str)],
+        highlight: Option<usize>,
+    ) {
+        let size = egui::vec2(ui.available_width(), 80.0);
+        let (rect, response) = ui.allocate_exact_size(size, egui::Sense::hover());
+        let painter = ui.painter();
+
+        painter.rect_filled(rect, egui::Rounding::same(3.0), egui::Color32::from_gray(30));
+
+        let duration = duration_seconds.max(1.0);
+        let to_x = |seconds: f32| rect.left() + rect.width() * (seconds / duration).clamp(0.0, 1.0);
+
+        if let Some((start, end, _)) = highlight.and_then(|index| slots.get(index)) {
+            let shaded = egui::Rect::from_x_y_ranges(to_x(*start)..=to_x(end.unwrap_or(duration)), rect.y_range());
+            painter.rect_filled(shaded, egui::Rounding::ZERO, egui::Color32::from_rgba_unmultiplied(80, 140, 220, 50));
+        }
+
+        // One vertical line per peak, so the outline stays readable at any width
+        let column = rect.width() / peaks.len().max(1) as f32;
+        let center = rect.center().y;
+        let half_height = rect.height() * 0.5;
+        for (index, (min, max)) in peaks.iter().enumerate() {
+            let x = rect.left() + (index as f32 + 0.5) * column;
+            painter.line_segment(
+                [egui::pos2(x, center - max.clamp(-1.0, 1.0) * half_height), egui::pos2(x, center - min.clamp(-1.0, 1.0) * half_height)],
+                egui::Stroke::new(column.max(1.0), egui::Color32::LIGHT_GREEN),
+            );
+        }
+
+        for (start, _, label) in slots {
+            let x = to_x(*start);
+            painter.line_segment(
+                [egui::pos2(x, rect.top()), egui::pos2(x, rect.bottom())],
+                egui::Stroke::new(1.5, egui::Color32::YELLOW),
+            );
+            painter.text(
+                egui::pos2(x + 3.0, rect.top() + 2.0),
+                egui::Align2::LEFT_TOP,
+                *label,
+                egui::FontId::proportional(11.0),
+                egui::Color32::WHITE,
+            );
+        }
+
+        if let Some(pointer) = response.hover_pos() {
+            let seconds = (pointer.x - rect.left()) / rect.width() * duration;
+            response.on_hover_text(format!("{}:{:02}:{:02}", seconds as u32 / 3600, seconds as u32 / 60 % 60, seconds as u32 % 60));
+        }
+    }
+}
@@ -1,8 +1,32 @@
 use eframe::egui;
+use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::runtime::Runtime;
 use serde_json::Value;
-use crate::api_client::{ApiClient, DjResponse, EventSessionResponse, Timetable};
+use crate::api_client::{ApiClient, DjResponse, EventSessionResponse, PenaltyPreviewResponse, RecordingAnalysis, SessionResponse, SessionWaveform, Timetable};
+use crate::components::{PenaltyCurvePlot, WaveformView};
+
+/// Peaks requested per waveform; about one per pixel of the admin window
+const WAVEFORM_POINTS: usize = 800;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PenaltyCurveKind {
+    Step,
+    Linear,
+    Exponential,
+    Table,
+}
+
+impl PenaltyCurveKind {
+    fn label(&self) -> &'static str {
+        match self {
+            PenaltyCurveKind::Step => "Step (hard cutoff)",
+            PenaltyCurveKind::Linear => "Linear decay",
+            PenaltyCurveKind::Exponential => "Exponential decay",
+            PenaltyCurveKind::Table => "Custom breakpoints",
+        }
+    }
+}
 
 pub struct AdminMode {
     rt: Arc<Runtime>,
@@ -21,7 +45,16 @@ pub struct AdminMode {
     event_slot_duration: String,
     event_late_cutoff: String,
     event_start_time: String, // Format: HH:MM (e.g., "20:00")
+    penalty_curve_kind: PenaltyCurveKind,
+    penalty_multiplier: String, // Step multiplier or decay floor
+    penalty_decay_minutes: String, // Linear decay span or exponential half-life
+    penalty_breakpoints: String, // Format: "minutes:multiplier, ..." (e.g., "0:1.0, 120:0.5")
+    penalty_preview: Option<PenaltyPreviewResponse>,
     lottery_stats: Option<Value>,
+    sessions: Vec<SessionResponse>,
+    analyses: HashMap<String, RecordingAnalysis>,
+    waveforms: HashMap<String, Option<SessionWaveform>>, // None: session has no waveform
+    expanded_session: Option<String>,
     error_message: Option<String>,
     success_message: Option<String>,
     show_stop_confirmation: bool,
@@ -49,7 +82,16 @@ impl AdminMode {
             event_slot_duration: "60".to_string(),
             event_late_cutoff: "2".to_string(),
             event_start_time: String::new(), // Empty = start immediately
+            penalty_curve_kind: PenaltyCurveKind::Step,
+            penalty_multiplier: "0.5".to_string(),
+            penalty_decay_minutes: "120".to_string(),
+            penalty_breakpoints: "0:1.0, 120:0.75, 240:0.5".to_string(),
+            penalty_preview: None,
             lottery_stats: None,
+            sessions: Vec::new(),
+            analyses: HashMap::new(),
+            waveforms: HashMap::new(),
+            expanded_session: None,
             error_message: None,
             success_message: None,
             show_stop_confirmation: false,
@@ -160,6 +202,10 @@ impl AdminMode {
 
         // Statistics and controls
         self.render_statistics_panel(ui);
+
+        ui.add_space(20.0);
+
+        self.render_session_list(ui);
     }
 
     fn render_dj_pool(&mut self, ui: &mut egui::Ui) {
@@ -300,6 +346,14 @@ impl AdminMode {
                         .to_string());
                 });
 
+                ui.horizontal(|ui| {
+                    ui.label("Voided Draws:");
+                    ui.label(stats.get("voided_draws")
+                        .and_then(|v| v.as_u64())
+                        .unwrap_or(0)
+                        .to_string());
+                });
+
                 ui.horizontal(|ui| {
                     ui.label("Unique Winners:");
                     ui.label(stats.get("unique_winners")
@@ -333,6 +387,120 @@ impl AdminMode {
         });
     }
 
+    fn render_session_list(&mut self, ui: &mut egui::Ui) {
+        ui.group(|ui| {
+            ui.heading("🎧 Recorded Sessions");
+            ui.add_space(10.0);
+
+            if self.sessions.is_empty() {
+                ui.label("No sessions yet");
+                return;
+            }
+
+            let mut toggled = None;
+            let mut released = None;
+            for session in &self.sessions {
+                let expanded = self.expanded_session.as_deref() == Some(session.id.as_str());
+                ui.horizontal(|ui| {
+                    let arrow = if expanded { "🔽" } else { "▶" };
+                    if ui.button(arrow).clicked() {
+                        toggled = Some(session.id.clone());
+                    }
+                    ui.label(format!(
+                        "{} – {} ({})",
+                        session.started_at.get(11..16).unwrap_or(&session.started_at),
+                        session.dj_name,
+                        session.duration_minutes.map(|m| format!("{} min", m)).unwrap_or_else(|| "playing".to_string())
+                    ));
+
+                    let Some(analysis) = self.analyses.get(&session.id) else {
+                        return;
+                    };
+                    let loudness = analysis.integrated_lufs
+                        .map(|lufs| format!("{:.1} LUFS", lufs))
+                        .unwrap_or_else(|| "silent".to_string());
+                    let peak = analysis.true_peak_dbtp
+                        .map(|peak| format!("{:+.1} dBTP", peak))
+                        .unwrap_or_default();
+                    if analysis.flagged {
+                        ui.colored_label(egui::Color32::RED, format!("⚠ {} {} ({})", loudness, peak, analysis.flags.join(", ")))
+                            .on_hover_text(format!(
+                                "{:.3}% clipped, {:.0} s silent",
+                                analysis.clipping_ratio * 100.0,
+                                analysis.silent_seconds
+                            ));
+                    } else {
+                        ui.colored_label(egui::Color32::GRAY, format!("{} {}", loudness, peak));
+                    }
+                    if analysis.delivery_held && ui.button("📧 Send anyway").clicked() {
+                        released = Some(session.id.clone());
+                    }
+                });
+
+                if !expanded {
+                    continue;
+                }
+                // Loaded before the session is expanded, see `toggle_session_waveform`
+                if let Some(Some(waveform)) = self.waveforms.get(&session.id) {
+                    let peaks: Vec<(f32, f32)> = waveform.peaks.iter().map(|p| (p.min, p.max)).collect();
+                    let slots: Vec<(f32, Option<f32>, &str)> = waveform.slots.iter()
+                        .map(|slot| (slot.start_seconds as f32, slot.end_seconds.map(|e| e as f32), slot.dj_name.as_str()))
+                        .collect();
+                    let highlight = waveform.slots.iter().position(|slot| slot.session_id == session.id);
+                    WaveformView::show(ui, &peaks, waveform.duration_seconds as f32, &slots, highlight);
+                } else {
+                    ui.colored_label(egui::Color32::GRAY, "No recording waveform for this set");
+                }
+            }
+
+            if let Some(session_id) = toggled {
+                self.toggle_session_waveform(session_id);
+            }
+            if let Some(session_id) = released {
+                self.release_delivery(&session_id);
+            }
+        });
+    }
+
+    fn release_delivery(&mut self, session_id: &str) {
+        self.error_message = None;
+        self.success_message = None;
+
+        match self.api_client.release_delivery(session_id) {
+            Ok(()) => {
+                if let Some(analysis) = self.analyses.get_mut(session_id) {
+                    analysis.delivery_held = false;
+                }
+                self.success_message = Some("Download link sent to the DJ".to_string());
+            }
+            Err(e) => {
+                self.error_message = Some(format!("Failed to release recording: {}", e));
+            }
+        }
+    }
+
+    fn toggle_session_waveform(&mut self, session_id: String) {
+        if self.expanded_session.as_deref() == Some(session_id.as_str()) {
+            // Reloaded on the next expand, in case the recording has grown
+            self.waveforms.remove(&session_id);
+            self.expanded_session = None;
+            return;
+        }
+
+        if !self.waveforms.contains_key(&session_id) {
+            match self.api_client.get_session_waveform(&session_id, WAVEFORM_POINTS) {
+                Ok(waveform) => {
+                    self.waveforms.insert(session_id.clone(), waveform);
+                }
+                Err(e) => {
+                    self.error_message = Some(format!("Failed to load waveform: {}", e));
+                    return;
+                }
+            }
+        }
+        self.expanded_session = Some(session_id);
+    }
+
     fn load_admin_data(&mut self) {
         self.error_message = None;
         self.success_message = None;
@@ -360,6 +528,26 @@ impl AdminMode {
             }
         }
 
+        match self.api_client.get_all_sessions() {
+            Ok(sessions) => {
+                self.sessions = sessions;
+            }
+            Err(e) => {
+                self.error_message = Some(format!("Failed to load sessions: {}", e));
+            }
+        }
+
+        match self.api_client.get_analyses() {
+            Ok(analyses) => {
+                self.analyses = analyses.into_iter()
+                    .map(|analysis| (analysis.session_id.clone(), analysis))
+                    .collect();
+            }
+            Err(e) => {
+                self.error_message = Some(format!("Failed to load recording analyses: {}", e));
+            }
+        }
+
         self.load_lottery_stats();
     }
 
@@ -616,6 +804,9 @@ impl AdminMode {
                 });
                 ui.label("💡 Examples: '20:00' for 8 PM today, or leave empty to start immediately");
 
+                ui.add_space(10.0);
+                self.render_penalty_curve_settings(ui);
+
                 ui.add_space(10.0);
 
                 if ui.button("🎉 Start Event").clicked() {
@@ -695,7 +886,15 @@ impl AdminMode {
             None
         };
 
-        match self.api_client.start_event(slot_duration, late_cutoff, started_at) {
+        let late_penalty_curve = match self.build_penalty_curve() {
+            Ok(curve) => Some(curve),
+            Err(e) => {
+                self.error_message = Some(e);
+                return;
+            }
+        };
+
+        match self.api_client.start_event(slot_duration, late_cutoff, started_at, late_penalty_curve) {
             Ok(event) => {
                 self.current_event = Some(event);
                 self.success_message = Some("Event started successfully!".to_string());
@@ -706,6 +905,131 @@ impl AdminMode {
         }
     }
 
+    fn render_penalty_curve_settings(&mut self, ui: &mut egui::Ui) {
+        ui.group(|ui| {
+            ui.label("⚖️ Late arrival penalty");
+
+            egui::ComboBox::from_label("Curve")
+                .selected_text(self.penalty_curve_kind.label())
+                .show_ui(ui, |ui| {
+                    for kind in [
+                        PenaltyCurveKind::Step,
+                        PenaltyCurveKind::Linear,
+                        PenaltyCurveKind::Exponential,
+                        PenaltyCurveKind::Table,
+                    ] {
+                        ui.selectable_value(&mut self.penalty_curve_kind, kind, kind.label());
+                    }
+                });
+
+            match self.penalty_curve_kind {
+                PenaltyCurveKind::Step => {
+                    ui.horizontal(|ui| {
+                        ui.label("Weight after cutoff:");
+                        ui.text_edit_singleline(&mut self.penalty_multiplier);
+                    });
+                }
+                PenaltyCurveKind::Linear | PenaltyCurveKind::Exponential => {
+                    ui.horizontal(|ui| {
+                        ui.label("Minimum weight:");
+                        ui.text_edit_singleline(&mut self.penalty_multiplier);
+                    });
+                    ui.horizontal(|ui| {
+                        let label = if self.penalty_curve_kind == PenaltyCurveKind::Linear {
+                            "Decay over (minutes):"
+                        } else {
+                            "Half-life (minutes):"
+                        };
+                        ui.label(label);
+                        ui.text_edit_singleline(&mut self.penalty_decay_minutes);
+                    });
+                }
+                PenaltyCurveKind::Table => {
+                    ui.horizontal(|ui| {
+                        ui.label("Breakpoints (min:weight):");
+                        ui.text_edit_singleline(&mut self.penalty_breakpoints);
+                    });
+                }
+            }
+
+            if ui.button("📈 Preview").clicked() {
+                self.load_penalty_preview();
+            }
+
+            if let Some(preview) = &self.penalty_preview {
+                let points: Vec<(f32, f32)> = preview.points.iter()
+                    .map(|p| (p.minutes_after_start as f32, p.multiplier as f32))
+                    .collect();
+                let max_minutes = points.last().map(|(m, _)| *m).unwrap_or(0.0);
+                PenaltyCurvePlot::show(ui, &points, max_minutes);
+                ui.label(format!("Weight vs. minutes after start (0 – {} min)", max_minutes));
+            }
+        });
+    }
+
+    fn build_penalty_curve(&self) -> Result<Value, String> {
+        let grace_minutes = self.event_late_cutoff.parse::<i64>()
+            .map_err(|_| "Late penalty cutoff must be a whole number of hours".to_string())? * 60;
+        let multiplier = self.penalty_multiplier.parse::<f64>()
+            .map_err(|_| "Penalty weight must be a number between 0 and 1".to_string())?;
+        let decay_minutes = self.penalty_decay_minutes.parse::<i64>()
+            .map_err(|_| "Decay must be a whole number of minutes".to_string())?;
+
+        let curve = match self.penalty_curve_kind {
+            PenaltyCurveKind::Step => serde_json::json!({
+                "type": "step",
+                "cutoff_minutes": grace_minutes,
+                "multiplier": multiplier,
+            }),
+            PenaltyCurveKind::Linear => serde_json::json!({
+                "type": "linear",
+                "grace_minutes": grace_minutes,
+                "decay_minutes": decay_minutes,
+                "floor": multiplier,
+            }),
+            PenaltyCurveKind::Exponential => serde_json::json!({
+                "type": "exponential",
+                "grace_minutes": grace_minutes,
+                "half_life_minutes": decay_minutes as f64,
+                "floor": multiplier,
+            }),
+            PenaltyCurveKind::Table => {
+                let mut breakpoints = Vec::new();
+                for entry in self.penalty_breakpoints.split(',') {
+                    let (minutes, weight) = entry.trim().split_once(':')
+                        .ok_or_else(|| format!("Invalid breakpoint '{}', use minutes:weight", entry.trim()))?;
+                    let minutes = minutes.trim().parse::<i64>()
+                        .map_err(|_| format!("Invalid minutes in breakpoint '{}'", entry.trim()))?;
+                    let weight = weight.trim().parse::<f64>()
+                        .map_err(|_| format!("Invalid weight in breakpoint '{}'", entry.trim()))?;
+                    breakpoints.push(serde_json::json!({ "from_minutes": minutes, "multiplier": weight }));
+                }
+                serde_json::json!({ "type": "table", "breakpoints": breakpoints })
+            }
+        };
+
+        Ok(curve)
+    }
+
+    fn load_penalty_preview(&mut self) {
+        let curve = match self.build_penalty_curve() {
+            Ok(curve) => curve,
+            Err(e) => {
+                self.error_message = Some(e);
+                return;
+            }
+        };
+
+        match self.api_client.preview_penalty_curve(curve) {
+            Ok(preview) => {
+                self.penalty_preview = Some(preview);
+            }
+            Err(e) => {
+                self.error_message = Some(format!("Failed to preview penalty: {}", e));
+            }
+        }
+    }
+
     fn end_event(&mut self) {
         self.error_message = None;
         self.success_message = None;
@@ -756,6 +1080,10 @@ impl AdminMode {
                 self.dj_pool.clear();
                 self.current_queue.clear();
                 self.lottery_stats = None;
+                self.sessions.clear();
+                self.analyses.clear();
+                self.waveforms.clear();
+                self.expanded_session = None;
                 self.success_message = Some("All data cleared successfully!".to_string());
             }
             Err(e) => {
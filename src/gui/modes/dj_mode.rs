@@ -1,7 +1,16 @@
 use eframe::egui;
 use std::sync::Arc;
 use tokio::runtime::Runtime;
-use crate::api_client::{ApiClient, DjResponse, EventSessionResponse};
+use crate::api_client::{ApiClient, DjOddsResponse, DjResponse, EventSessionResponse, SessionResponse, Tracklist, WeightComponent};
+
+/// Formats the server reads a tracklist in, with their labels
+const TRACKLIST_FORMATS: [(&str, &str); 5] = [
+    ("auto", "Detect"),
+    ("text", "Text"),
+    ("cue", "CUE"),
+    ("m3u", "M3U"),
+    ("rekordbox", "rekordbox TXT"),
+];
 
 pub struct DjMode {
     rt: Arc<Runtime>,
@@ -13,6 +22,12 @@ pub struct DjMode {
     current_queue: Vec<DjResponse>,
     next_dj: Option<DjResponse>,
     current_event: Option<EventSessionResponse>,
+    odds: Option<DjOddsResponse>,
+    my_sessions: Vec<SessionResponse>,
+    tracklist_session: Option<String>,
+    tracklist_format: usize, // Index into TRACKLIST_FORMATS
+    tracklist_text: String,
+    tracklist: Option<Tracklist>,
     error_message: Option<String>,
     success_message: Option<String>,
     last_refresh: std::time::Instant,
@@ -39,6 +54,12 @@ impl DjMode {
             current_queue: Vec::new(),
             next_dj: None,
             current_event: None,
+            odds: None,
+            my_sessions: Vec::new(),
+            tracklist_session: None,
+            tracklist_format: 0,
+            tracklist_text: String::new(),
+            tracklist: None,
             error_message: None,
             success_message: None,
             last_refresh: std::time::Instant::now(),
@@ -188,6 +209,10 @@ impl DjMode {
                 if ui.button("➕ Register Another DJ").clicked() {
                     // Reset to registration form but keep current DJ registered
                     self.registration_status = RegistrationStatus::NotRegistered;
+                    self.odds = None;
+                    self.my_sessions.clear();
+                    self.tracklist_session = None;
+                    self.tracklist = None;
                     self.dj_name.clear();
                     self.dj_email.clear();
                     self.error_message = None;
@@ -208,6 +233,219 @@ impl DjMode {
                 ui.label("⏳ Waiting in lottery pool...");
             }
         });
+
+        ui.add_space(10.0);
+        self.render_odds(ui);
+        ui.add_space(10.0);
+        self.render_tracklist(ui);
+    }
+
+    fn render_odds(&mut self, ui: &mut egui::Ui) {
+        ui.group(|ui| {
+            ui.heading("🎲 Your Odds");
+            ui.add_space(5.0);
+
+            let odds = match &self.odds {
+                Some(odds) => odds,
+                None => {
+                    ui.label("Loading odds...");
+                    return;
+                }
+            };
+
+            if let Some(current) = &odds.current {
+                ui.horizontal(|ui| {
+                    ui.strong(format!("{:.1}%", current.selection_probability * 100.0));
+                    ui.label(format!("chance in the next draw ({} DJs in the pool)", odds.pool_size));
+                });
+                Self::render_weight_components(ui, &current.weight_components);
+            } else if let Some(position) = odds.lottery_status.get("position").and_then(|p| p.as_i64()) {
+                ui.colored_label(egui::Color32::GREEN, format!("🎉 Drawn! Queue position #{}", position));
+            } else {
+                ui.label("Not taking part in the next draw");
+            }
+
+            if !odds.history.is_empty() {
+                ui.add_space(5.0);
+                ui.label(format!("📜 {} earlier draws", odds.history.len()));
+
+                egui::ScrollArea::vertical()
+                    .id_salt("odds_history")
+                    .max_height(150.0)
+                    .show(ui, |ui| {
+                        for entry in &odds.history {
+                            let time = chrono::DateTime::parse_from_rfc3339(&entry.drawn_at)
+                                .map(|dt| dt.format("%H:%M").to_string())
+                                .unwrap_or_else(|_| "--:--".to_string());
+                            let outcome = if entry.status == "voided" {
+                                "🚫 voided".to_string()
+                            } else if entry.was_winner {
+                                "✅ drawn".to_string()
+                            } else {
+                                format!("❌ {} drawn", entry.winner_name.as_deref().unwrap_or("another DJ"))
+                            };
+
+                            ui.collapsing(
+                                format!("{} | {:.1}% of {} | {}", time, entry.selection_probability * 100.0,
+                                    entry.participant_count, outcome),
+                                |ui| Self::render_weight_components(ui, &entry.weight_components),
+                            );
+                        }
+                    });
+            }
+        });
+    }
+
+    fn render_tracklist(&mut self, ui: &mut egui::Ui) {
+        ui.group(|ui| {
+            ui.heading("🎶 Tracklist");
+            ui.add_space(5.0);
+
+            if self.my_sessions.is_empty() {
+                ui.label("You can add your tracklist once your set has started");
+                return;
+            }
+
+            let session_label = |session: &SessionResponse| {
+                chrono::DateTime::parse_from_rfc3339(&session.started_at)
+                    .map(|dt| format!("Set at {}", dt.format("%Y-%m-%d %H:%M")))
+                    .unwrap_or_else(|_| session.started_at.clone())
+            };
+            let selected_label = self
+                .tracklist_session
+                .as_ref()
+                .and_then(|id| self.my_sessions.iter().find(|session| &session.id == id))
+                .map(session_label)
+                .unwrap_or_else(|| "Choose a set".to_string());
+
+            let mut chosen = None;
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_id_salt("tracklist_session")
+                    .selected_text(selected_label)
+                    .show_ui(ui, |ui| {
+                        for session in &self.my_sessions {
+                            let selected = self.tracklist_session.as_deref() == Some(session.id.as_str());
+                            if ui.selectable_label(selected, session_label(session)).clicked() {
+                                chosen = Some(session.id.clone());
+                            }
+                        }
+                    });
+                egui::ComboBox::from_id_salt("tracklist_format")
+                    .selected_text(TRACKLIST_FORMATS[self.tracklist_format].1)
+                    .show_ui(ui, |ui| {
+                        for (index, (_, label)) in TRACKLIST_FORMATS.iter().enumerate() {
+                            ui.selectable_value(&mut self.tracklist_format, index, *label);
+                        }
+                    });
+            });
+            if let Some(session_id) = chosen {
+                self.select_tracklist_session(session_id);
+            }
+            let Some(session_id) = self.tracklist_session.clone() else {
+                return;
+            };
+
+            ui.add(
+                egui::TextEdit::multiline(&mut self.tracklist_text)
+                    .hint_text("0:00 Artist - Title\n4:32 Artist - Title")
+                    .desired_rows(6)
+                    .desired_width(f32::INFINITY),
+            );
+            ui.label("Paste your tracklist, or drop a CUE, M3U or rekordbox TXT export onto this window");
+
+            let can_save = !self.tracklist_text.trim().is_empty();
+            if ui.add_enabled(can_save, egui::Button::new("💾 Save Tracklist")).clicked() {
+                let text = self.tracklist_text.clone();
+                let result = self.api_client.submit_tracklist_text(&session_id, text, TRACKLIST_FORMATS[self.tracklist_format].0);
+                self.tracklist_saved(result);
+            }
+
+            let dropped = ui.ctx().input(|input| input.raw.dropped_files.clone());
+            for file in dropped {
+                self.import_dropped_tracklist(&session_id, file);
+            }
+
+            if let Some(tracklist) = &self.tracklist {
+                if !tracklist.tracks.is_empty() {
+                    ui.add_space(5.0);
+                    ui.label(format!("📜 {} tracks saved", tracklist.tracks.len()));
+                    egui::ScrollArea::vertical()
+                        .id_salt("tracklist_tracks")
+                        .max_height(150.0)
+                        .show(ui, |ui| {
+                            for track in &tracklist.tracks {
+                                let time = track
+                                    .offset_seconds
+                                    .map(|offset| format!("{}:{:02}", offset as u64 / 60, offset as u64 % 60))
+                                    .unwrap_or_else(|| "--:--".to_string());
+                                let name = match &track.artist {
+                                    Some(artist) => format!("{} - {}", artist, track.title),
+                                    None => track.title.clone(),
+                                };
+                                ui.label(format!("{}. [{}] {}", track.position, time, name));
+                            }
+                        });
+                }
+            }
+        });
+    }
+
+    fn select_tracklist_session(&mut self, session_id: String) {
+        self.tracklist = self.api_client.get_tracklist(&session_id).ok();
+        self.tracklist_session = Some(session_id);
+    }
+
+    fn import_dropped_tracklist(&mut self, session_id: &str, file: egui::DroppedFile) {
+        let data = match (&file.bytes, &file.path) {
+            (Some(bytes), _) => bytes.to_vec(),
+            (None, Some(path)) => match std::fs::read(path) {
+                Ok(data) => data,
+                Err(e) => {
+                    self.error_message = Some(format!("Failed to read {}: {}", path.display(), e));
+                    return;
+                }
+            },
+            (None, None) => return,
+        };
+        let name = file.path.as_ref().map(|path| path.to_string_lossy().to_string()).unwrap_or(file.name);
+        let format = match name.rsplit_once('.').map(|(_, extension)| extension.to_lowercase()).as_deref() {
+            Some("cue") => "cue",
+            Some("m3u") | Some("m3u8") => "m3u",
+            _ => TRACKLIST_FORMATS[self.tracklist_format].0,
+        };
+
+        let result = self.api_client.import_tracklist(session_id, data, format);
+        self.tracklist_saved(result);
+    }
+
+    fn tracklist_saved(&mut self, result: Result<Tracklist, String>) {
+        match result {
+            Ok(tracklist) => {
+                self.success_message = Some(format!("Tracklist saved with {} tracks", tracklist.tracks.len()));
+                self.error_message = None;
+                self.tracklist_text.clear();
+                self.tracklist = Some(tracklist);
+            }
+            Err(e) => {
+                self.error_message = Some(format!("Failed to save tracklist: {}", e));
+            }
+        }
+    }
+
+    fn render_weight_components(ui: &mut egui::Ui, components: &[WeightComponent]) {
+        for component in components {
+            ui.horizontal(|ui| {
+                let color = if component.factor > 1.0 {
+                    egui::Color32::GREEN
+                } else if component.factor < 1.0 {
+                    egui::Color32::YELLOW
+                } else {
+                    egui::Color32::GRAY
+                };
+                ui.colored_label(color, format!("×{:.2}", component.factor));
+                ui.label(&component.description);
+            });
+        }
     }
 
     fn render_current_queue(&mut self, ui: &mut egui::Ui) {
@@ -288,6 +526,10 @@ impl DjMode {
         match self.api_client.delete_dj(dj_id) {
             Ok(_) => {
                 self.registration_status = RegistrationStatus::NotRegistered;
+                self.odds = None;
+                self.my_sessions.clear();
+                self.tracklist_session = None;
+                self.tracklist = None;
                 let name = self.dj_name.clone();
                 self.dj_name.clear();
                 self.dj_email.clear();
@@ -318,6 +560,12 @@ impl DjMode {
                         self.next_dj = None;
                     }
                 }
+
+                if let RegistrationStatus::Registered(dj_id) = &self.registration_status {
+                    self.odds = self.api_client.get_dj_odds(dj_id).ok();
+                    let dj_id = dj_id.clone();
+                    self.refresh_my_sessions(&dj_id);
+                }
             }
             Err(e) => {
                 self.error_message = Some(format!("Failed to load DJs: {}", e));
@@ -325,6 +573,18 @@ impl DjMode {
         }
     }
 
+    /// The DJ's sets, newest first; the newest is picked for the tracklist until another is chosen
+    fn refresh_my_sessions(&mut self, dj_id: &str) {
+        if let Ok(sessions) = self.api_client.get_all_sessions() {
+            self.my_sessions = sessions.into_iter().filter(|session| session.dj_id == dj_id).collect();
+        }
+        if self.tracklist_session.is_none() {
+            if let Some(newest) = self.my_sessions.first().map(|session| session.id.clone()) {
+                self.select_tracklist_session(newest);
+            }
+        }
+    }
+
     fn refresh_event_status(&mut self) {
         match self.api_client.get_current_event() {
             Ok(event) => {
@@ -2,7 +2,7 @@ use eframe::egui;
 use std::sync::Arc;
 use tokio::runtime::Runtime;
 use serde_json::Value;
-use crate::api_client::{ApiClient, EventSessionResponse, Timetable};
+use crate::api_client::{ApiClient, EventSessionResponse, NowPlaying, Timetable};
 
 pub struct GuestMode {
     rt: Arc<Runtime>,
@@ -11,7 +11,7 @@ pub struct GuestMode {
     guest_name: String,
     guest_email: String,
     message: String,
-    current_dj: Option<Value>,
+    now_playing: Option<NowPlaying>,
     previous_dj: Option<Value>,
     request_status: RequestStatus,
     qr_code_visible: bool,
@@ -38,7 +38,7 @@ impl GuestMode {
             guest_name: String::new(),
             guest_email: String::new(),
             message: String::new(),
-            current_dj: None,
+            now_playing: None,
             previous_dj: None,
             request_status: RequestStatus::None,
             qr_code_visible: false,
@@ -53,12 +53,15 @@ impl GuestMode {
         if self.last_refresh.elapsed() > std::time::Duration::from_secs(2) {
             self.refresh_event_status();
             self.refresh_timetable();
+            self.refresh_now_playing();
             self.last_refresh = std::time::Instant::now();
         }
 
         // Two-column layout (no main heading)
         ui.columns(2, |columns| {
-            // LEFT COLUMN: Timetable (no heading, more space)
+            // LEFT COLUMN: Now playing and timetable
+            self.render_current_dj_info(&mut columns[0]);
+            columns[0].add_space(10.0);
             self.render_timetable(&mut columns[0]);
 
             // RIGHT COLUMN: Guest Request Features (no heading)
@@ -80,19 +83,29 @@ impl GuestMode {
             ui.heading("🎵 Now Playing");
             ui.add_space(10.0);
 
-            if let Some(current_dj) = &self.current_dj {
+            if let Some(now_playing) = &self.now_playing {
                 ui.horizontal(|ui| {
                     ui.strong("Current DJ:");
-                    ui.label(current_dj.get("name")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("Unknown"));
+                    ui.label(&now_playing.dj_name);
                 });
 
-                // Show session duration if available
-                ui.horizontal(|ui| {
-                    ui.label("⏱️ Playing for:");
-                    ui.label("45:30"); // TODO: Calculate actual duration
-                });
+                if let Ok(started_at) = chrono::DateTime::parse_from_rfc3339(&now_playing.session_started_at) {
+                    let playing_for = chrono::Utc::now().signed_duration_since(started_at).num_seconds().max(0);
+                    ui.horizontal(|ui| {
+                        ui.label("⏱️ Playing for:");
+                        ui.label(format!("{}:{:02}", playing_for / 60, playing_for % 60));
+                    });
+                }
+
+                if let Some(track) = &now_playing.track {
+                    ui.horizontal(|ui| {
+                        ui.label("💿");
+                        match &track.artist {
+                            Some(artist) => ui.label(format!("{} - {}", artist, track.title)),
+                            None => ui.label(&track.title),
+                        };
+                    });
+                }
             } else {
                 ui.label("No DJ currently playing");
             }
@@ -337,6 +350,12 @@ impl GuestMode {
                                 ui.label(start_time);
                                 ui.label("|");
                                 ui.label(&entry.dj_name);
+                                if entry.reserved_kind.is_some() {
+                                    let label = entry.reserved_label.clone()
+                                        .or_else(|| entry.reserved_kind.as_ref().map(|kind| kind.replace('_', " ")))
+                                        .unwrap_or_default();
+                                    ui.colored_label(egui::Color32::GOLD, format!("⭐ {}", label));
+                                }
                             });
                         }
                     });
@@ -373,4 +392,15 @@ impl GuestMode {
             }
         }
     }
+
+    fn refresh_now_playing(&mut self) {
+        match self.api_client.get_now_playing() {
+            Ok(now_playing) => {
+                self.now_playing = now_playing;
+            }
+            Err(_) => {
+                self.now_playing = None;
+            }
+        }
+    }
 }
\ No newline at end of file
@@ -1,3 +1,4 @@
+pub mod audio;
 pub mod models;
 pub mod services;
 pub mod utils;
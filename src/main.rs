@@ -6,19 +6,22 @@ use axum::{
     Router,
 };
 use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
 use std::sync::Arc;
-use tokio::net::TcpListener;
+use tokio::net::{TcpListener, UdpSocket};
 use tower_http::cors::CorsLayer;
 use tracing::{info, level_filters::LevelFilter};
 use tracing_subscriber;
 
 mod api;
+mod audio;
 mod models;
 mod services;
 mod utils;
 
 use models::AppState;
-use services::{EventService, LotteryService};
+use models::RecorderSyncError;
+use services::{EventService, LotteryService, OutputService, RecorderIndexService};
 
 #[derive(Serialize)]
 struct HealthResponse {
@@ -67,6 +70,40 @@ async fn auto_draw_background_task(app_state: Arc<AppState>) {
     }
 }
 
+async fn recorder_sync_background_task(app_state: Arc<AppState>, interval_seconds: u64) {
+    info!("Starting recorder index sync background task (every {}s)", interval_seconds);
+    let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(interval_seconds));
+
+    loop {
+        interval.tick().await;
+
+        let index_service = RecorderIndexService::new(app_state.clone());
+
+        if let Err(e) = index_service.sync_recorder_index(false).await {
+            match e.downcast_ref::<RecorderSyncError>() {
+                // A manual sync is in progress; the next tick picks up from there
+                Some(RecorderSyncError::AlreadyRunning) => {}
+                _ => tracing::error!("Error syncing recorder index: {}", e),
+            }
+        }
+    }
+}
+
+async fn slot_ending_background_task(app_state: Arc<AppState>) {
+    info!("Starting slot ending output hook task");
+    let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(10));
+    let mut warned_slot = None;
+
+    loop {
+        interval.tick().await;
+
+        let output_service = OutputService::new(app_state.clone());
+        if let Err(e) = output_service.check_slot_ending(&mut warned_slot).await {
+            tracing::error!("Error checking for ending slots: {}", e);
+        }
+    }
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     // Initialize tracing
@@ -86,10 +123,56 @@ async fn main() -> anyhow::Result<()> {
         auto_draw_background_task(app_state_clone).await;
     });
 
+    // Spawn background task keeping the recorder session index current
+    let recorder_config = &app_state.config.session_recorder_config;
+    if recorder_config.enabled && recorder_config.sync_interval_seconds > 0 {
+        let app_state_clone = app_state.clone();
+        let interval_seconds = recorder_config.sync_interval_seconds;
+        tokio::spawn(async move {
+            recorder_sync_background_task(app_state_clone, interval_seconds).await;
+        });
+    }
+
+    // Spawn background task warning outputs before a slot runs out
+    let output_config = &app_state.config.output_config;
+    if output_config.hooks.iter().any(|hook| hook.triggers.contains(&models::HookTrigger::SlotEnding)) {
+        let app_state_clone = app_state.clone();
+        tokio::spawn(async move {
+            slot_ending_background_task(app_state_clone).await;
+        });
+    }
+
+    // Spawn background task keeping stream titles on the current set
+    let stream_config = &app_state.config.stream_metadata_config;
+    if stream_config.mounts.iter().any(|mount| mount.enabled) {
+        let app_state_clone = app_state.clone();
+        let interval_seconds = stream_config.interval_seconds;
+        tokio::spawn(async move {
+            services::run_stream_metadata_sync(app_state_clone, interval_seconds).await;
+        });
+    }
+
+    // Spawn background task retrying webhook deliveries that failed
+    let app_state_clone = app_state.clone();
+    tokio::spawn(async move {
+        services::run_webhook_retries(app_state_clone, 10).await;
+    });
+
+    // Listen for the tracks DJ software reports over OSC
+    if let Some(osc_addr) = app_state.config.osc_config.listen_addr {
+        let socket = UdpSocket::bind(osc_addr).await?;
+        info!("Listening for OSC track messages on {}", osc_addr);
+        let app_state_clone = app_state.clone();
+        tokio::spawn(async move {
+            services::run_osc_listener(app_state_clone, socket).await;
+        });
+    }
+
     // Build the router
     let app = Router::new()
         .route("/health", get(health))
         .nest("/api", api::router())
+        .nest("/dl", api::download_routes::public_router())
         .layer(CorsLayer::permissive())
         .with_state(app_state);
 
@@ -97,7 +180,8 @@ async fn main() -> anyhow::Result<()> {
     let listener = TcpListener::bind("0.0.0.0:3000").await?;
     info!("DJ Session Recorder API listening on 0.0.0.0:3000");
 
-    axum::serve(listener, app).await?;
+    // Peer addresses go into the download access log
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await?;
 
     Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,155 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use crate::audio::{LoudnessReport, SilentSpan};
+
+/// Stored result of analysing one session's set
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct RecordingAnalysis {
+    pub session_id: String,
+    pub analyzed_at: DateTime<Utc>,
+    pub duration_seconds: f64,
+    pub integrated_lufs: Option<f64>, // NULL when the set never rises above -70 LUFS
+    pub true_peak_dbtp: Option<f64>,
+    pub clipping_ratio: f64,
+    pub silent_seconds: f64,
+    pub silence: String, // JSON Vec<SilenceSegment>
+    pub flags: String,   // JSON Vec<AnalysisFlag>
+    pub flagged: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SilenceSegment {
+    pub start_seconds: f64,
+    pub end_seconds: f64,
+}
+
+impl From<SilentSpan> for SilenceSegment {
+    fn from(span: SilentSpan) -> Self {
+        Self {
+            start_seconds: span.start_seconds,
+            end_seconds: span.end_seconds,
+        }
+    }
+}
+
+/// A threshold from `AnalysisConfig` the set failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnalysisFlag {
+    Silent,      // Nothing above the absolute gate, e.g. a muted mixer output
+    TooQuiet,    // Integrated loudness below the minimum
+    Clipping,    // Too many samples at full scale
+    TruePeak,    // True peak above the maximum
+    LongSilence, // More silent time than allowed
+}
+
+impl AnalysisFlag {
+    pub fn description(&self) -> &'static str {
+        match self {
+            AnalysisFlag::Silent => "Recording is silent",
+            AnalysisFlag::TooQuiet => "Recording is very quiet",
+            AnalysisFlag::Clipping => "Recording is clipped",
+            AnalysisFlag::TruePeak => "True peak is too high",
+            AnalysisFlag::LongSilence => "Recording has long silent stretches",
+        }
+    }
+}
+
+/// Thresholds a set has to meet before its download email goes out
+#[derive(Clone, Debug)]
+pub struct AnalysisConfig {
+    pub enabled: bool,
+    pub min_integrated_lufs: f64,
+    pub max_true_peak_dbtp: f64,
+    pub max_clipping_ratio: f64,
+    pub max_silent_seconds: f64,
+    pub silence_threshold_dbfs: f64,
+    pub min_silence_seconds: f64, // Shorter quiet stretches, e.g. breaks between tracks, don't count
+    pub hold_flagged: bool,       // Flagged sets wait for an admin before the DJ is emailed
+}
+
+impl Default for AnalysisConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min_integrated_lufs: -40.0,
+            max_true_peak_dbtp: 2.0,
+            max_clipping_ratio: 0.001,
+            max_silent_seconds: 120.0,
+            silence_threshold_dbfs: -60.0,
+            min_silence_seconds: 10.0,
+            hold_flagged: true,
+        }
+    }
+}
+
+impl AnalysisConfig {
+    /// Every threshold the report fails, in a fixed order
+    pub fn flags(&self, report: &LoudnessReport) -> Vec<AnalysisFlag> {
+        let mut flags = Vec::new();
+        match report.integrated_lufs {
+            None => flags.push(AnalysisFlag::Silent),
+            Some(lufs) if lufs < self.min_integrated_lufs => flags.push(AnalysisFlag::TooQuiet),
+            Some(_) => {}
+        }
+        if report.clipping_ratio > self.max_clipping_ratio {
+            flags.push(AnalysisFlag::Clipping);
+        }
+        if report.true_peak_dbtp.is_some_and(|peak| peak > self.max_true_peak_dbtp) {
+            flags.push(AnalysisFlag::TruePeak);
+        }
+        if report.silent_seconds() > self.max_silent_seconds {
+            flags.push(AnalysisFlag::LongSilence);
+        }
+        flags
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingAnalysisResponse {
+    pub session_id: String,
+    pub dj_name: String,
+    pub session_started_at: DateTime<Utc>,
+    pub analyzed_at: DateTime<Utc>,
+    pub duration_seconds: f64,
+    pub integrated_lufs: Option<f64>,
+    pub true_peak_dbtp: Option<f64>,
+    pub clipping_ratio: f64,
+    pub silent_seconds: f64,
+    pub silence: Vec<SilenceSegment>,
+    pub flags: Vec<AnalysisFlag>,
+    pub flagged: bool,
+    pub delivery_held: bool, // Download email waits for `POST /api/admin/sessions/:id/release`
+}
+
+impl RecordingAnalysis {
+    pub fn to_response(&self, dj_name: String, session_started_at: DateTime<Utc>, delivery_held: bool) -> RecordingAnalysisResponse {
+        RecordingAnalysisResponse {
+            session_id: self.session_id.clone(),
+            dj_name,
+            session_started_at,
+            analyzed_at: self.analyzed_at,
+            duration_seconds: self.duration_seconds,
+            integrated_lufs: self.integrated_lufs,
+            true_peak_dbtp: self.true_peak_dbtp,
+            clipping_ratio: self.clipping_ratio,
+            silent_seconds: self.silent_seconds,
+            silence: serde_json::from_str(&self.silence).unwrap_or_default(),
+            flags: serde_json::from_str(&self.flags).unwrap_or_default(),
+            flagged: self.flagged,
+            delivery_held,
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum AnalysisError {
+    #[error("Session not found")]
+    SessionNotFound,
+    #[error("Session has no recording to analyse")]
+    NoRecording,
+    #[error("Recording delivery is not held")]
+    NotHeld,
+}
@@ -0,0 +1,68 @@
+use crate::utils::ArchiveKind;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Container of an event archive, `?format=` on `GET /api/admin/events/:id/archive`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ArchiveFormat {
+    #[default]
+    Zip,
+    Tar,
+}
+
+impl ArchiveFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Zip => "zip",
+            Self::Tar => "tar",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Zip => "application/zip",
+            Self::Tar => "application/x-tar",
+        }
+    }
+
+    pub fn kind(&self) -> ArchiveKind {
+        match self {
+            Self::Zip => ArchiveKind::Zip,
+            Self::Tar => ArchiveKind::Tar,
+        }
+    }
+}
+
+impl std::str::FromStr for ArchiveFormat {
+    type Err = ArchiveError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "zip" => Ok(Self::Zip),
+            "tar" => Ok(Self::Tar),
+            _ => Err(ArchiveError::UnknownFormat(s.to_string())),
+        }
+    }
+}
+
+/// One set in an archive's `timetable.json` / `timetable.csv`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArchiveTimetableEntry {
+    pub position: usize,
+    pub session_id: String,
+    pub dj_name: String,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: Option<DateTime<Utc>>,
+    pub duration_minutes: Option<i32>,
+    pub recording: Option<String>, // Path inside the archive, None if the set wasn't recorded
+    pub recording_offset_seconds: Option<f64>, // Where the set starts in `recording`
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ArchiveError {
+    #[error("Event not found")]
+    EventNotFound,
+    #[error("Unknown archive format '{0}', expected zip or tar")]
+    UnknownFormat(String),
+}
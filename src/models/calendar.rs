@@ -0,0 +1,24 @@
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+/// How the iCalendar feeds present the night
+#[derive(Clone, Debug, Default)]
+pub struct CalendarConfig {
+    pub timezone: Option<Tz>, // Venue time zone, e.g. Europe/Berlin; UTC when unset
+    pub location: Option<String>, // Venue shown on every calendar entry
+}
+
+/// Answer of `GET /api/djs/:id/calendar`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalendarFeedResponse {
+    pub url: String,
+    pub webcal_url: String, // Same feed; opens the subscription dialog on phones
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum CalendarError {
+    #[error("Event not found")]
+    EventNotFound,
+    #[error("DJ not found")]
+    DjNotFound,
+}
@@ -1,8 +1,10 @@
-use chrono::{DateTime, Utc, Timelike};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
 
+use crate::models::event_session::EventSession;
+
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Dj {
     pub id: String,
@@ -12,6 +14,9 @@ pub struct Dj {
     pub weight: f64,
     pub is_active: bool,
     pub position_in_queue: Option<i32>,
+    #[serde(default)]
+    #[sqlx(default)]
+    pub keep_recording: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -27,6 +32,8 @@ pub struct UpdateDjRequest {
     pub weight: Option<f64>,
     pub is_active: Option<bool>,
     pub position_in_queue: Option<i32>,
+    #[serde(default)]
+    pub keep_recording: Option<bool>, // Ask the recorder to keep this DJ's sets
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -39,6 +46,8 @@ pub struct DjResponse {
     pub is_active: bool,
     pub position_in_queue: Option<i32>,
     pub estimated_time: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub keep_recording: bool, // The recorder keeps this DJ's sets from cleanup
 }
 
 impl From<Dj> for DjResponse {
@@ -52,6 +61,7 @@ impl From<Dj> for DjResponse {
             is_active: dj.is_active,
             position_in_queue: dj.position_in_queue,
             estimated_time: None, // This will be calculated based on current queue
+            keep_recording: dj.keep_recording,
         }
     }
 }
@@ -66,23 +76,12 @@ impl Dj {
             weight: 1.0,
             is_active: true,
             position_in_queue: None,
+            keep_recording: false,
         }
     }
 
-    pub fn calculate_weight(&self, late_arrival_penalty: f64) -> f64 {
-        let hours_since_registration = Utc::now()
-            .signed_duration_since(self.registered_at)
-            .num_hours() as f64;
-        
-        // Apply penalty for late arrivals (after midnight or specific time)
-        let current_hour = Utc::now().hour();
-        let penalty = if current_hour >= 24 || hours_since_registration > 4.0 {
-            late_arrival_penalty
-        } else {
-            1.0
-        };
-
-        self.weight * penalty
+    pub fn calculate_weight(&self, event: &EventSession, default_penalty: f64) -> f64 {
+        self.weight * event.late_penalty_multiplier(self.registered_at, default_penalty)
     }
 }
 
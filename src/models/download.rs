@@ -0,0 +1,114 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use std::net::IpAddr;
+
+/// A DJ's link to one session's recording
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct DownloadToken {
+    pub token: String,
+    pub session_id: String,
+    pub dj_id: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub max_downloads: Option<i64>, // None for unlimited
+    pub download_count: i64,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl DownloadToken {
+    /// Why the token can't be used right now, if it can't
+    pub fn check_usable(&self, now: DateTime<Utc>) -> Result<(), DownloadError> {
+        if self.revoked_at.is_some() {
+            return Err(DownloadError::Revoked);
+        }
+        if now >= self.expires_at {
+            return Err(DownloadError::Expired);
+        }
+        if self.max_downloads.is_some_and(|max| self.download_count >= max) {
+            return Err(DownloadError::LimitReached);
+        }
+        Ok(())
+    }
+}
+
+/// Longest a download link may stay valid
+pub const MAX_LINK_TTL_DAYS: i64 = 365;
+/// How long after a counted download the same client may fetch later ranges without
+/// using up another one, e.g. when seeking in a player
+pub const DOWNLOAD_CONTINUATION_MINUTES: i64 = 10;
+
+/// Body of `POST /api/sessions/:id/download-tokens`; omitted fields use the configured defaults
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CreateDownloadTokenRequest {
+    pub expires_in_days: Option<i64>,
+    pub max_downloads: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DownloadTokenResponse {
+    #[serde(flatten)]
+    pub token: DownloadToken,
+    pub url: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct DownloadLogEntry {
+    pub id: i64,
+    pub token: String,
+    pub accessed_at: DateTime<Utc>,
+    pub status: i64,
+    pub range_header: Option<String>,
+    pub client_ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub counted: bool, // Used up one of the link's downloads
+}
+
+/// Who asked for a download, for the access log
+#[derive(Debug, Clone, Default)]
+pub struct DownloadClient {
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+impl DownloadClient {
+    /// The client's address given the connecting peer and its `X-Forwarded-For` header.
+    /// The header is only believed when the peer is a trusted proxy; the client is then the
+    /// last address in it that is not one of the proxies, since earlier ones can be forged.
+    pub fn resolve_ip(peer: Option<IpAddr>, forwarded_for: Option<&str>, trusted_proxies: &[IpAddr]) -> Option<String> {
+        let peer = peer?;
+        if !trusted_proxies.contains(&peer) {
+            return Some(peer.to_string());
+        }
+
+        let mut client = peer;
+        for entry in forwarded_for.into_iter().flat_map(|header| header.split(',').rev()) {
+            match entry.trim().parse() {
+                Ok(ip) => client = ip,
+                Err(_) => break,
+            }
+            if !trusted_proxies.contains(&client) {
+                break;
+            }
+        }
+        Some(client.to_string())
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum DownloadError {
+    #[error("Download link not found")]
+    NotFound,
+    #[error("Download link has expired")]
+    Expired,
+    #[error("Download link has been revoked")]
+    Revoked,
+    #[error("Download limit reached for this link")]
+    LimitReached,
+    #[error("Recording is not available yet")]
+    NoFile,
+    #[error("Session not found")]
+    SessionNotFound,
+    #[error("expires_in_days must be between 1 and {}", MAX_LINK_TTL_DAYS)]
+    InvalidExpiry,
+}
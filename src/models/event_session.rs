@@ -3,6 +3,12 @@ use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
 
+use crate::models::late_penalty::LatePenaltyCurve;
+use crate::models::reserved_slot::ReservedSlotKind;
+
+/// Name of events that were started without one
+pub const DEFAULT_EVENT_NAME: &str = "Slotify";
+
 /// Represents the overall event session (the entire DJ night)
 /// This is different from individual DJ sessions - it manages the whole event flow
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
@@ -16,6 +22,8 @@ pub struct EventSession {
     pub current_dj_id: Option<String>,
     pub current_slot_started_at: Option<DateTime<Utc>>,
     pub next_draw_at: Option<DateTime<Utc>>,
+    pub late_penalty_curve: Option<String>, // JSON LatePenaltyCurve, NULL = step at cutoff
+    pub name: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -23,15 +31,19 @@ pub struct StartEventRequest {
     pub slot_duration_minutes: Option<i32>, // Default to 60 if not provided
     pub late_arrival_cutoff_hours: Option<i32>, // Default to 2 if not provided
     pub started_at: Option<DateTime<Utc>>, // Optional custom start time
+    pub late_penalty_curve: Option<LatePenaltyCurve>, // Defaults to a step at the cutoff
+    pub name: Option<String>, // e.g. "Friday Night Sessions"; tagged into delivered recordings
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct EventSessionResponse {
     pub id: String,
+    pub name: Option<String>,
     pub started_at: DateTime<Utc>,
     pub ended_at: Option<DateTime<Utc>>,
     pub slot_duration_minutes: i32,
     pub late_arrival_cutoff_hours: i32,
+    pub late_penalty_curve: LatePenaltyCurve,
     pub is_active: bool,
     pub current_dj_id: Option<String>,
     pub current_dj_name: Option<String>,
@@ -41,7 +53,7 @@ pub struct EventSessionResponse {
     pub current_slot_progress_percent: Option<f32>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TimetableEntry {
     pub position: i32,
     pub dj_id: String,
@@ -50,9 +62,11 @@ pub struct TimetableEntry {
     pub ended_at: Option<DateTime<Utc>>,
     pub duration_minutes: Option<i32>,
     pub status: TimetableEntryStatus,
+    pub reserved_kind: Option<ReservedSlotKind>, // Set for booked slots, None for lottery slots
+    pub reserved_label: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum TimetableEntryStatus {
     Completed,
     InProgress,
@@ -62,7 +76,9 @@ pub enum TimetableEntryStatus {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Timetable {
     pub event_id: String,
+    pub event_name: Option<String>,
     pub event_started_at: DateTime<Utc>,
+    pub slot_duration_minutes: i32,
     pub entries: Vec<TimetableEntry>,
     pub total_djs: usize,
     pub completed_sets: usize,
@@ -80,6 +96,8 @@ impl EventSession {
             current_dj_id: None,
             current_slot_started_at: None,
             next_draw_at: None,
+            late_penalty_curve: None,
+            name: None,
         }
     }
 
@@ -93,11 +111,19 @@ impl EventSession {
             .num_minutes() as i32
     }
 
-    pub fn should_apply_late_penalty(&self, registration_time: DateTime<Utc>) -> bool {
-        let hours_since_start = registration_time
+    /// The penalty curve configured for this event, or a step at the cutoff if none was set
+    pub fn penalty_curve(&self, default_penalty: f64) -> LatePenaltyCurve {
+        self.late_penalty_curve
+            .as_deref()
+            .and_then(|json| serde_json::from_str(json).ok())
+            .unwrap_or_else(|| LatePenaltyCurve::step_after_hours(self.late_arrival_cutoff_hours, default_penalty))
+    }
+
+    pub fn late_penalty_multiplier(&self, registration_time: DateTime<Utc>, default_penalty: f64) -> f64 {
+        let minutes_since_start = registration_time
             .signed_duration_since(self.started_at)
-            .num_hours();
-        hours_since_start > self.late_arrival_cutoff_hours as i64
+            .num_minutes();
+        self.penalty_curve(default_penalty).multiplier(minutes_since_start)
     }
 
     pub fn current_slot_progress_percent(&self) -> Option<f32> {
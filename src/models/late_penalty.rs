@@ -0,0 +1,239 @@
+use serde::{Deserialize, Serialize};
+
+/// How much a DJ's lottery weight is reduced depending on how late they registered.
+///
+/// All curves are evaluated against the number of minutes between the event start and the
+/// DJ's registration. Registrations before the event started count as minute 0. Every curve
+/// yields a multiplier between 0.0 and 1.0 that is applied to the DJ's weight.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LatePenaltyCurve {
+    /// Full weight until `cutoff_minutes`, then a flat `multiplier`
+    Step {
+        cutoff_minutes: i64,
+        multiplier: f64,
+    },
+    /// Full weight during the grace period, then falls linearly to `floor` over `decay_minutes`
+    Linear {
+        grace_minutes: i64,
+        decay_minutes: i64,
+        floor: f64,
+    },
+    /// Full weight during the grace period, then halves every `half_life_minutes` towards `floor`
+    Exponential {
+        grace_minutes: i64,
+        half_life_minutes: f64,
+        floor: f64,
+    },
+    /// Custom table of breakpoints; optionally interpolated linearly between them
+    Table {
+        breakpoints: Vec<PenaltyBreakpoint>,
+        #[serde(default)]
+        interpolate: bool,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PenaltyBreakpoint {
+    /// Minutes after event start from which this multiplier applies
+    pub from_minutes: i64,
+    pub multiplier: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PenaltyPreviewPoint {
+    pub minutes_after_start: i64,
+    pub multiplier: f64,
+    pub weight: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PenaltyPreview {
+    pub curve: LatePenaltyCurve,
+    pub base_weight: f64,
+    pub points: Vec<PenaltyPreviewPoint>,
+}
+
+/// Longest span a preview samples, a full day after the event start
+pub const MAX_PREVIEW_MINUTES: i64 = 24 * 60;
+/// Most points a preview returns
+pub const MAX_PREVIEW_POINTS: i64 = 500;
+
+#[derive(Debug, thiserror::Error)]
+pub enum PenaltyCurveError {
+    #[error("{0} must be between 0.0 and 1.0, got {1}")]
+    MultiplierOutOfRange(&'static str, f64),
+    #[error("{0} must not be negative, got {1}")]
+    NegativeMinutes(&'static str, i64),
+    #[error("{0} must be positive")]
+    NotPositive(&'static str),
+    #[error("A breakpoint table needs at least one entry")]
+    EmptyTable,
+    #[error("Breakpoints must be sorted by strictly increasing from_minutes")]
+    UnsortedBreakpoints,
+    #[error("until_minutes must be between 0 and {max}, got {0}", max = MAX_PREVIEW_MINUTES)]
+    PreviewTooLong(i64),
+    #[error("A preview has at most {max} points; use a larger step_minutes", max = MAX_PREVIEW_POINTS)]
+    TooManyPoints,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PenaltyPreviewRequest {
+    pub curve: Option<LatePenaltyCurve>,
+    pub base_weight: Option<f64>,
+    pub until_minutes: Option<i64>,
+    pub step_minutes: Option<i64>,
+}
+
+impl PenaltyPreviewRequest {
+    /// `until_minutes` and `step_minutes`, defaulting to six hours in steps of 15 minutes
+    pub fn range(&self) -> Result<(i64, i64), PenaltyCurveError> {
+        let until_minutes = self.until_minutes.unwrap_or(6 * 60);
+        let step_minutes = self.step_minutes.unwrap_or(15);
+        if !(0..=MAX_PREVIEW_MINUTES).contains(&until_minutes) {
+            return Err(PenaltyCurveError::PreviewTooLong(until_minutes));
+        }
+        if step_minutes <= 0 {
+            return Err(PenaltyCurveError::NotPositive("step_minutes"));
+        }
+        if until_minutes / step_minutes + 1 > MAX_PREVIEW_POINTS {
+            return Err(PenaltyCurveError::TooManyPoints);
+        }
+        Ok((until_minutes, step_minutes))
+    }
+}
+
+impl LatePenaltyCurve {
+    /// The historical behaviour: a hard cutoff after a number of hours
+    pub fn step_after_hours(cutoff_hours: i32, multiplier: f64) -> Self {
+        Self::Step {
+            cutoff_minutes: cutoff_hours as i64 * 60,
+            multiplier,
+        }
+    }
+
+    pub fn multiplier(&self, minutes_after_start: i64) -> f64 {
+        let t = minutes_after_start.max(0);
+
+        let multiplier = match self {
+            Self::Step { cutoff_minutes, multiplier } => {
+                if t > *cutoff_minutes {
+                    *multiplier
+                } else {
+                    1.0
+                }
+            }
+            Self::Linear { grace_minutes, decay_minutes, floor } => {
+                if t <= *grace_minutes {
+                    1.0
+                } else {
+                    let progress = (t - grace_minutes) as f64 / (*decay_minutes).max(1) as f64;
+                    1.0 - (1.0 - floor) * progress.min(1.0)
+                }
+            }
+            Self::Exponential { grace_minutes, half_life_minutes, floor } => {
+                if t <= *grace_minutes {
+                    1.0
+                } else {
+                    let half_lives = (t - grace_minutes) as f64 / half_life_minutes;
+                    floor + (1.0 - floor) * 0.5_f64.powf(half_lives)
+                }
+            }
+            Self::Table { breakpoints, interpolate } => {
+                Self::table_multiplier(breakpoints, *interpolate, t)
+            }
+        };
+
+        multiplier.clamp(0.0, 1.0)
+    }
+
+    fn table_multiplier(breakpoints: &[PenaltyBreakpoint], interpolate: bool, t: i64) -> f64 {
+        let next_index = breakpoints.iter().position(|b| b.from_minutes > t);
+
+        let (previous, next) = match next_index {
+            Some(0) => return 1.0,
+            Some(i) => (&breakpoints[i - 1], Some(&breakpoints[i])),
+            None => match breakpoints.last() {
+                Some(last) => (last, None),
+                None => return 1.0,
+            },
+        };
+
+        match next {
+            Some(next) if interpolate => {
+                let span = (next.from_minutes - previous.from_minutes) as f64;
+                let progress = (t - previous.from_minutes) as f64 / span;
+                previous.multiplier + (next.multiplier - previous.multiplier) * progress
+            }
+            _ => previous.multiplier,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), PenaltyCurveError> {
+        let check_multiplier = |name: &'static str, value: f64| {
+            if !(0.0..=1.0).contains(&value) {
+                return Err(PenaltyCurveError::MultiplierOutOfRange(name, value));
+            }
+            Ok(())
+        };
+        let check_minutes = |name: &'static str, value: i64| {
+            if value < 0 {
+                return Err(PenaltyCurveError::NegativeMinutes(name, value));
+            }
+            Ok(())
+        };
+
+        match self {
+            Self::Step { cutoff_minutes, multiplier } => {
+                check_minutes("cutoff_minutes", *cutoff_minutes)?;
+                check_multiplier("multiplier", *multiplier)
+            }
+            Self::Linear { grace_minutes, decay_minutes, floor } => {
+                check_minutes("grace_minutes", *grace_minutes)?;
+                if *decay_minutes <= 0 {
+                    return Err(PenaltyCurveError::NotPositive("decay_minutes"));
+                }
+                check_multiplier("floor", *floor)
+            }
+            Self::Exponential { grace_minutes, half_life_minutes, floor } => {
+                check_minutes("grace_minutes", *grace_minutes)?;
+                if !half_life_minutes.is_finite() || *half_life_minutes <= 0.0 {
+                    return Err(PenaltyCurveError::NotPositive("half_life_minutes"));
+                }
+                check_multiplier("floor", *floor)
+            }
+            Self::Table { breakpoints, .. } => {
+                if breakpoints.is_empty() {
+                    return Err(PenaltyCurveError::EmptyTable);
+                }
+                for breakpoint in breakpoints {
+                    check_minutes("from_minutes", breakpoint.from_minutes)?;
+                    check_multiplier("multiplier", breakpoint.multiplier)?;
+                }
+                if breakpoints.windows(2).any(|w| w[0].from_minutes >= w[1].from_minutes) {
+                    return Err(PenaltyCurveError::UnsortedBreakpoints);
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Sample the curve so organisers can see how weight changes with arrival time
+    pub fn preview(&self, base_weight: f64, until_minutes: i64, step_minutes: i64) -> Vec<PenaltyPreviewPoint> {
+        let step = step_minutes.max(1);
+        let mut points = Vec::new();
+        let mut minutes = 0;
+
+        while minutes <= until_minutes && points.len() < MAX_PREVIEW_POINTS as usize {
+            let multiplier = self.multiplier(minutes);
+            points.push(PenaltyPreviewPoint {
+                minutes_after_start: minutes,
+                multiplier,
+                weight: base_weight * multiplier,
+            });
+            minutes += step;
+        }
+
+        points
+    }
+}
@@ -1,10 +1,17 @@
 use chrono::{DateTime, Utc, Timelike};
+use rand::Rng;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
 use crate::models::dj::{Dj, DjResponse};
 use crate::models::event_session::EventSession;
 
+/// Weight below which no participant can fall
+pub const MINIMUM_WEIGHT: f64 = 0.1;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct LotteryDraw {
+    pub id: String,
     pub winner: DjResponse,
     pub participants: Vec<LotteryParticipant>,
     pub drawn_at: DateTime<Utc>,
@@ -16,14 +23,36 @@ pub struct LotteryParticipant {
     pub dj: DjResponse,
     pub calculated_weight: f64,
     pub selection_probability: f64,
+    #[serde(default)]
+    pub weight_components: Vec<WeightComponent>, // Factors whose product is calculated_weight
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WeightComponentKind {
+    BaseWeight,
+    ManualAdjustment,
+    LatePenalty,
+    RegistrationBonus,
+    CarryOver,
+    MinimumWeight,
+}
+
+/// One multiplicative factor of a participant's weight
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WeightComponent {
+    pub kind: WeightComponentKind,
+    pub factor: f64,
+    pub description: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LotteryConfig {
     pub base_weight: f64,
     pub late_arrival_penalty: f64,
     pub time_block_hours: u32,
     pub enable_time_blocking: bool,
+    pub carry_over_bonus: f64, // Extra weight per draw lost earlier in the event
 }
 
 impl Default for LotteryConfig {
@@ -33,18 +62,104 @@ impl Default for LotteryConfig {
             late_arrival_penalty: 0.5,
             time_block_hours: 2,
             enable_time_blocking: true,
+            carry_over_bonus: 0.0,
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "draw_status", rename_all = "lowercase")]
+#[serde(rename_all = "snake_case")]
+pub enum DrawStatus {
+    Completed,
+    Voided,
+}
+
+/// A draw as stored, including its void state
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DrawRecord {
+    pub id: String,
+    pub winner_dj_id: String,
+    pub winner_name: Option<String>,
+    pub drawn_at: DateTime<Utc>,
+    pub event_id: Option<String>,
+    pub status: DrawStatus,
+    pub voided_at: Option<DateTime<Utc>>,
+    pub void_reason: Option<String>,
+    pub replaces_draw_id: Option<String>, // Set on draws made to replace a voided one
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VoidDrawRequest {
+    pub reason: String,
+    #[serde(default)]
+    pub redraw: bool, // Draw a replacement for the freed queue position
+    #[serde(default)]
+    pub include_voided_winner: bool, // Let the redraw pick the DJ whose draw was voided
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VoidDrawResponse {
+    pub voided: DrawRecord,
+    pub freed_position: Option<i32>,
+    pub redraw: Option<LotteryDraw>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum VoidDrawError {
+    #[error("A reason is required to void a draw")]
+    MissingReason,
+    #[error("Draw not found")]
+    NotFound,
+    #[error("Draw has already been voided")]
+    AlreadyVoided,
+    #[error("The winner has already played since this draw")]
+    WinnerAlreadyPlayed,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct LotteryStatistics {
-    pub total_draws: usize,
+    pub total_draws: usize, // Completed draws only
+    pub voided_draws: usize,
     pub unique_winners: usize,
     pub average_weight: f64,
     pub fairness_score: f64, // 0-1, where 1 is perfectly fair
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum DjLotteryStatus {
+    Queued { position: i32 },
+    InPool,
+    Inactive,
+}
+
+/// A DJ's participation in one past draw
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DjDrawHistoryEntry {
+    pub draw_id: String,
+    pub drawn_at: DateTime<Utc>,
+    pub status: DrawStatus,
+    pub winner_dj_id: String,
+    pub winner_name: Option<String>,
+    pub was_winner: bool,
+    pub participant_count: i64,
+    pub calculated_weight: f64,
+    pub selection_probability: f64,
+    pub weight_components: Vec<WeightComponent>,
+}
+
+/// Answers "why was I (not) drawn": current odds plus every draw the DJ took part in
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DjOdds {
+    pub dj: DjResponse,
+    pub lottery_status: DjLotteryStatus,
+    pub current: Option<LotteryParticipant>, // Odds if the next draw happened now
+    pub pool_size: usize,
+    pub history: Vec<DjDrawHistoryEntry>,
+}
+
+#[derive(Clone)]
 pub struct LotteryEngine {
     config: LotteryConfig,
 }
@@ -55,15 +170,31 @@ impl LotteryEngine {
     }
 
     pub fn calculate_weights(&self, djs: &[Dj], event: Option<&EventSession>) -> Vec<LotteryParticipant> {
-        let total_participants = djs.len() as f64;
+        self.calculate_weights_with_carry_over(djs, event, &HashMap::new())
+    }
+
+    /// `lost_draws` maps DJ ids to the number of draws they lost earlier in the event
+    pub fn calculate_weights_with_carry_over(
+        &self,
+        djs: &[Dj],
+        event: Option<&EventSession>,
+        lost_draws: &HashMap<String, u32>,
+    ) -> Vec<LotteryParticipant> {
         let mut participants = Vec::new();
 
         for dj in djs {
-            let calculated_weight = self.calculate_individual_weight(dj, event);
+            let lost = lost_draws.get(&dj.id).copied().unwrap_or(0);
+            let weight_components = self.weight_components(dj, event, lost);
+            let calculated_weight = weight_components
+                .iter()
+                .map(|c| c.factor)
+                .product::<f64>()
+                .max(MINIMUM_WEIGHT); // Ensure minimum weight
             participants.push(LotteryParticipant {
                 dj: dj.clone().into(),
                 calculated_weight,
                 selection_probability: 0.0, // Will be calculated after all weights are known
+                weight_components,
             });
         }
 
@@ -76,68 +207,129 @@ impl LotteryEngine {
         participants
     }
 
-    fn calculate_individual_weight(&self, dj: &Dj, event: Option<&EventSession>) -> f64 {
-        let mut weight = dj.weight * self.config.base_weight;
+    fn weight_components(&self, dj: &Dj, event: Option<&EventSession>, lost_draws: u32) -> Vec<WeightComponent> {
+        let mut components = vec![
+            WeightComponent {
+                kind: WeightComponentKind::BaseWeight,
+                factor: self.config.base_weight,
+                description: "Configured base weight".to_string(),
+            },
+            WeightComponent {
+                kind: WeightComponentKind::ManualAdjustment,
+                factor: dj.weight,
+                description: if dj.weight == 1.0 {
+                    "No manual adjustment".to_string()
+                } else {
+                    format!("Weight set to {:.2} by an admin", dj.weight)
+                },
+            },
+        ];
 
-        // Apply late arrival penalty based on event start time
-        if let Some(event) = event {
-            if event.should_apply_late_penalty(dj.registered_at) {
-                weight *= self.config.late_arrival_penalty;
-            }
-        }
+        // Apply the event's late arrival penalty curve
+        let late_penalty = event
+            .map(|e| e.late_penalty_multiplier(dj.registered_at, self.config.late_arrival_penalty))
+            .unwrap_or(1.0);
+        components.push(WeightComponent {
+            kind: WeightComponentKind::LatePenalty,
+            factor: late_penalty,
+            description: match event {
+                Some(e) => {
+                    let minutes = dj.registered_at.signed_duration_since(e.started_at).num_minutes().max(0);
+                    format!("Registered {} minutes after event start", minutes)
+                }
+                None => "No active event".to_string(),
+            },
+        });
 
         // Apply time-based fairness (earlier arrivals get slightly higher weight)
         let hours_registered = Utc::now()
             .signed_duration_since(dj.registered_at)
             .num_hours() as f64;
 
-        if hours_registered > 0.0 {
-            // Small bonus for being registered longer (max 20% bonus)
-            let time_bonus = (hours_registered / 10.0).min(0.2);
-            weight *= 1.0 + time_bonus;
-        }
+        // Small bonus for being registered longer (max 20% bonus)
+        let time_bonus = if hours_registered > 0.0 {
+            (hours_registered / 10.0).min(0.2)
+        } else {
+            0.0
+        };
+        components.push(WeightComponent {
+            kind: WeightComponentKind::RegistrationBonus,
+            factor: 1.0 + time_bonus,
+            description: format!("Registered for {} hours", hours_registered),
+        });
 
-        weight.max(0.1) // Ensure minimum weight
-    }
+        components.push(WeightComponent {
+            kind: WeightComponentKind::CarryOver,
+            factor: self.carry_over_factor(lost_draws),
+            description: format!("Lost {} earlier draws this event", lost_draws),
+        });
 
-    pub fn draw_winner(&self, djs: &[Dj], event: Option<&EventSession>) -> Option<LotteryDraw> {
-        if djs.is_empty() {
-            return None;
+        // Ensure minimum weight. A zero weight cannot be scaled up, so the floor replaces it.
+        let weight: f64 = components.iter().map(|c| c.factor).product();
+        if weight < MINIMUM_WEIGHT {
+            components.push(WeightComponent {
+                kind: WeightComponentKind::MinimumWeight,
+                factor: if weight > 0.0 { MINIMUM_WEIGHT / weight } else { MINIMUM_WEIGHT },
+                description: if weight > 0.0 {
+                    format!("Raised to the minimum weight of {}", MINIMUM_WEIGHT)
+                } else {
+                    format!("Weight of 0 replaced by the minimum weight of {}", MINIMUM_WEIGHT)
+                },
+            });
         }
 
-        let participants = self.calculate_weights(djs, event);
-        let total_weight: f64 = participants.iter().map(|p| p.calculated_weight).sum();
+        components
+    }
+
+    pub fn carry_over_factor(&self, lost_draws: u32) -> f64 {
+        1.0 + self.config.carry_over_bonus * lost_draws as f64
+    }
 
-        if total_weight <= 0.0 {
+    /// Weighted random pick; returns the index of the selected weight
+    pub fn pick_weighted<R: Rng + ?Sized>(weights: &[f64], rng: &mut R) -> Option<usize> {
+        let total_weight: f64 = weights.iter().sum();
+        if weights.is_empty() || total_weight <= 0.0 {
             return None;
         }
 
-        // Use rand to generate random number
-        let random_value = rand::random::<f64>() * total_weight;
+        let random_value = rng.gen::<f64>() * total_weight;
         let mut cumulative_weight = 0.0;
 
-        for participant in &participants {
-            cumulative_weight += participant.calculated_weight;
+        for (index, weight) in weights.iter().enumerate() {
+            cumulative_weight += weight;
             if random_value <= cumulative_weight {
-                return Some(LotteryDraw {
-                    winner: participant.dj.clone(),
-                    participants,
-                    drawn_at: Utc::now(),
-                    algorithm_used: "weighted_random".to_string(),
-                });
+                return Some(index);
             }
         }
 
-        // Fallback: return last participant (should never happen)
-        if let Some(last) = participants.last() {
-            Some(LotteryDraw {
-                winner: last.dj.clone(),
-                participants,
-                drawn_at: Utc::now(),
-                algorithm_used: "weighted_random_fallback".to_string(),
-            })
-        } else {
-            None
+        // Rounding can leave random_value just above the final sum
+        Some(weights.len() - 1)
+    }
+
+    pub fn draw_winner(&self, djs: &[Dj], event: Option<&EventSession>) -> Option<LotteryDraw> {
+        self.draw_winner_with_carry_over(djs, event, &HashMap::new())
+    }
+
+    pub fn draw_winner_with_carry_over(
+        &self,
+        djs: &[Dj],
+        event: Option<&EventSession>,
+        lost_draws: &HashMap<String, u32>,
+    ) -> Option<LotteryDraw> {
+        if djs.is_empty() {
+            return None;
         }
+
+        let participants = self.calculate_weights_with_carry_over(djs, event, lost_draws);
+        let weights: Vec<f64> = participants.iter().map(|p| p.calculated_weight).collect();
+        let winner_index = Self::pick_weighted(&weights, &mut rand::thread_rng())?;
+
+        Some(LotteryDraw {
+            id: Uuid::new_v4().to_string(),
+            winner: participants[winner_index].dj.clone(),
+            participants,
+            drawn_at: Utc::now(),
+            algorithm_used: "weighted_random".to_string(),
+        })
     }
-}
\ No newline at end of file
+}
@@ -8,11 +8,53 @@ pub mod dj;
 pub mod session;
 pub mod lottery;
 pub mod event_session;
+pub mod late_penalty;
+pub mod simulation;
+pub mod reserved_slot;
+pub mod segmentation;
+pub mod recorder_match;
+pub mod recorder_index;
+pub mod recorder_webhook;
+pub mod slot_recording;
+pub mod storage;
+pub mod download;
+pub mod archive;
+pub mod waveform;
+pub mod analysis;
+pub mod tracklist;
+pub mod now_playing;
+pub mod output_hook;
+pub mod stream_metadata;
+pub mod webhook;
+pub mod calendar;
+pub mod timetable_export;
+pub mod share_image;
 
 pub use dj::*;
 pub use session::*;
 pub use lottery::*;
 pub use event_session::*;
+pub use late_penalty::*;
+pub use simulation::*;
+pub use reserved_slot::*;
+pub use segmentation::*;
+pub use recorder_match::*;
+pub use recorder_index::*;
+pub use recorder_webhook::*;
+pub use slot_recording::*;
+pub use storage::*;
+pub use download::*;
+pub use archive::*;
+pub use waveform::*;
+pub use analysis::*;
+pub use tracklist::*;
+pub use now_playing::*;
+pub use output_hook::*;
+pub use stream_metadata::*;
+pub use webhook::*;
+pub use calendar::*;
+pub use timetable_export::*;
+pub use share_image::*;
 
 #[derive(Clone)]
 pub struct AppState {
@@ -23,10 +65,20 @@ pub struct AppState {
 #[derive(Clone, Debug)]
 pub struct AppConfig {
     pub database_url: String,
-    pub cloud_storage_url: Option<String>,
+    pub storage_config: StorageConfig,
     pub email_config: EmailConfig,
     pub lottery_config: LotteryConfig,
     pub session_recorder_config: SessionRecorderIntegrationConfig,
+    pub segmentation_config: SegmentationConfig,
+    pub download_config: DownloadConfig,
+    pub tagging_config: TaggingConfig,
+    pub analysis_config: AnalysisConfig,
+    pub osc_config: OscConfig,
+    pub output_config: OutputConfig,
+    pub stream_metadata_config: StreamMetadataConfig,
+    pub webhook_config: WebhookConfig,
+    pub calendar_config: CalendarConfig,
+    pub branding_config: BrandingConfig,
 }
 
 #[derive(Clone, Debug)]
@@ -38,6 +90,63 @@ pub struct SessionRecorderIntegrationConfig {
     pub bucket_name: String,
     pub public_endpoint: String,
     pub auto_link_tolerance_minutes: i64,
+    pub sync_interval_seconds: u64, // Background index sync; 0 disables it
+    pub webhook_secret: Option<String>, // Shared HMAC secret; the webhook is off without one
+    pub control_url: Option<String>, // Recorder's HTTP control API; slots are not recorded without it
+    pub control_token: Option<String>,
+    pub local_dir: Option<std::path::PathBuf>, // Recorder output on this machine; MinIO is not used then
+    pub preferred_recorder_id: Option<String>, // Auto-link picks this recorder when several cover a set equally
+}
+
+#[derive(Clone, Debug)]
+pub struct SegmentationConfig {
+    pub pre_roll_seconds: u32,
+    pub post_roll_seconds: u32,
+    pub output_dir: std::path::PathBuf,
+}
+
+impl Default for SegmentationConfig {
+    fn default() -> Self {
+        Self {
+            pre_roll_seconds: 30,
+            post_roll_seconds: 30,
+            output_dir: crate::utils::get_recordings_directory().join("segments"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct DownloadConfig {
+    pub base_url: String, // Where this server is reachable; links are `{base_url}/dl/{token}`
+    pub link_ttl_days: i64,
+    pub max_downloads: Option<i64>, // Per link; None for unlimited
+    pub trusted_proxies: Vec<std::net::IpAddr>, // Peers whose `X-Forwarded-For` is believed
+}
+
+impl Default for DownloadConfig {
+    fn default() -> Self {
+        Self {
+            base_url: "http://localhost:3000".to_string(),
+            link_ttl_days: 30,
+            max_downloads: None,
+            trusted_proxies: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct TaggingConfig {
+    pub enabled: bool, // Off: delivered files are renamed but left untouched
+    pub cover_art_path: Option<std::path::PathBuf>, // JPEG or PNG embedded as front cover
+}
+
+impl Default for TaggingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            cover_art_path: None,
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -55,6 +164,7 @@ pub struct LotteryConfig {
     pub late_arrival_penalty: f64,
     pub time_block_hours: u32,
     pub max_session_duration_minutes: u32,
+    pub carry_over_bonus: f64, // Extra weight per draw lost earlier in the event, e.g. 0.25 for +25%
 }
 
 impl Default for LotteryConfig {
@@ -64,6 +174,7 @@ impl Default for LotteryConfig {
             late_arrival_penalty: 0.5,
             time_block_hours: 2,
             max_session_duration_minutes: 60,
+            carry_over_bonus: 0.0,
         }
     }
 }
@@ -91,7 +202,7 @@ impl AppConfig {
         Ok(Self {
             database_url: std::env::var("DATABASE_URL")
                 .unwrap_or_else(|_| "sqlite:///tmp/dj_system.db".to_string()),
-            cloud_storage_url: std::env::var("CLOUD_STORAGE_URL").ok(),
+            storage_config: StorageConfig::from_env()?,
             email_config: EmailConfig {
                 smtp_server: std::env::var("EMAIL_SMTP_SERVER")
                     .unwrap_or_else(|_| "localhost".to_string()),
@@ -105,7 +216,14 @@ impl AppConfig {
                 from_address: std::env::var("EMAIL_FROM")
                     .unwrap_or_else(|_| "noreply@dj-system.local".to_string()),
             },
-            lottery_config: LotteryConfig::default(),
+            lottery_config: LotteryConfig {
+                carry_over_bonus: std::env::var("LOTTERY_CARRY_OVER_BONUS")
+                    .ok()
+                    .and_then(|bonus| bonus.parse::<f64>().ok())
+                    .filter(|bonus| bonus.is_finite() && *bonus >= 0.0)
+                    .unwrap_or(0.0),
+                ..LotteryConfig::default()
+            },
             session_recorder_config: SessionRecorderIntegrationConfig {
                 enabled: std::env::var("SESSION_RECORDER_ENABLED")
                     .unwrap_or_else(|_| "true".to_string())
@@ -125,6 +243,161 @@ impl AppConfig {
                     .unwrap_or_else(|_| "5".to_string())
                     .parse()
                     .unwrap_or(5),
+                sync_interval_seconds: std::env::var("SESSION_RECORDER_SYNC_INTERVAL")
+                    .unwrap_or_else(|_| "300".to_string())
+                    .parse()
+                    .unwrap_or(300),
+                webhook_secret: std::env::var("SESSION_RECORDER_WEBHOOK_SECRET")
+                    .ok()
+                    .filter(|secret| !secret.is_empty()),
+                control_url: std::env::var("SESSION_RECORDER_CONTROL_URL")
+                    .ok()
+                    .filter(|url| !url.is_empty()),
+                control_token: std::env::var("SESSION_RECORDER_CONTROL_TOKEN")
+                    .ok()
+                    .filter(|token| !token.is_empty()),
+                local_dir: std::env::var("SESSION_RECORDER_LOCAL_DIR")
+                    .ok()
+                    .filter(|dir| !dir.is_empty())
+                    .map(std::path::PathBuf::from),
+                preferred_recorder_id: std::env::var("SESSION_RECORDER_PREFERRED_ID")
+                    .ok()
+                    .filter(|id| !id.is_empty()),
+            },
+            segmentation_config: SegmentationConfig {
+                pre_roll_seconds: std::env::var("SEGMENTATION_PRE_ROLL_SECONDS")
+                    .unwrap_or_else(|_| "30".to_string())
+                    .parse()
+                    .unwrap_or(30),
+                post_roll_seconds: std::env::var("SEGMENTATION_POST_ROLL_SECONDS")
+                    .unwrap_or_else(|_| "30".to_string())
+                    .parse()
+                    .unwrap_or(30),
+                output_dir: std::env::var("SEGMENTATION_OUTPUT_DIR")
+                    .map(std::path::PathBuf::from)
+                    .unwrap_or_else(|_| crate::utils::get_recordings_directory().join("segments")),
+            },
+            download_config: DownloadConfig {
+                base_url: std::env::var("DOWNLOAD_BASE_URL")
+                    .map(|url| url.trim_end_matches('/').to_string())
+                    .unwrap_or_else(|_| "http://localhost:3000".to_string()),
+                link_ttl_days: std::env::var("DOWNLOAD_LINK_TTL_DAYS")
+                    .ok()
+                    .and_then(|days| days.parse().ok())
+                    .filter(|days| (1..=download::MAX_LINK_TTL_DAYS).contains(days))
+                    .unwrap_or(30),
+                max_downloads: std::env::var("DOWNLOAD_MAX_COUNT")
+                    .ok()
+                    .and_then(|count| count.parse().ok())
+                    .filter(|count| *count > 0),
+                trusted_proxies: std::env::var("DOWNLOAD_TRUSTED_PROXIES")
+                    .map(|proxies| proxies.split(',').filter_map(|ip| ip.trim().parse().ok()).collect())
+                    .unwrap_or_default(),
+            },
+            tagging_config: TaggingConfig {
+                enabled: std::env::var("TAG_RECORDINGS")
+                    .unwrap_or_else(|_| "true".to_string())
+                    .parse()
+                    .unwrap_or(true),
+                // Defaults to the logo the GUI shows, if there is one
+                cover_art_path: std::env::var("EVENT_LOGO_PATH")
+                    .ok()
+                    .filter(|path| !path.is_empty())
+                    .map(std::path::PathBuf::from)
+                    .or_else(|| Some(std::path::PathBuf::from("assets/logo.jpg")).filter(|path| path.is_file())),
+            },
+            analysis_config: {
+                let defaults = AnalysisConfig::default();
+                let number = |name: &str, default: f64| {
+                    std::env::var(name).ok().and_then(|value| value.parse().ok()).unwrap_or(default)
+                };
+                AnalysisConfig {
+                    enabled: std::env::var("ANALYSIS_ENABLED")
+                        .unwrap_or_else(|_| "true".to_string())
+                        .parse()
+                        .unwrap_or(true),
+                    min_integrated_lufs: number("ANALYSIS_MIN_LUFS", defaults.min_integrated_lufs),
+                    max_true_peak_dbtp: number("ANALYSIS_MAX_TRUE_PEAK_DBTP", defaults.max_true_peak_dbtp),
+                    max_clipping_ratio: number("ANALYSIS_MAX_CLIPPING_RATIO", defaults.max_clipping_ratio),
+                    max_silent_seconds: number("ANALYSIS_MAX_SILENT_SECONDS", defaults.max_silent_seconds),
+                    silence_threshold_dbfs: number("ANALYSIS_SILENCE_THRESHOLD_DBFS", defaults.silence_threshold_dbfs),
+                    min_silence_seconds: number("ANALYSIS_MIN_SILENCE_SECONDS", defaults.min_silence_seconds),
+                    hold_flagged: std::env::var("ANALYSIS_HOLD_FLAGGED")
+                        .unwrap_or_else(|_| "true".to_string())
+                        .parse()
+                        .unwrap_or(true),
+                }
+            },
+            osc_config: OscConfig {
+                listen_addr: std::env::var("OSC_LISTEN_ADDR")
+                    .ok()
+                    .filter(|addr| !addr.is_empty())
+                    .map(|addr| addr.parse())
+                    .transpose()?,
+                track_address: std::env::var("OSC_TRACK_ADDRESS")
+                    .ok()
+                    .filter(|address| address.starts_with('/'))
+                    .unwrap_or_else(|| OscConfig::default().track_address),
+            },
+            output_config: OutputConfig {
+                hooks: match std::env::var("OUTPUT_HOOKS_FILE").ok().filter(|path| !path.is_empty()) {
+                    Some(path) => OutputConfig::load_hooks(std::path::Path::new(&path))?,
+                    None => Vec::new(),
+                },
+                slot_warning_minutes: std::env::var("OUTPUT_SLOT_WARNING_MINUTES")
+                    .unwrap_or_else(|_| "10".to_string())
+                    .parse()
+                    .unwrap_or(10),
+                timeout_seconds: std::env::var("OUTPUT_TIMEOUT_SECONDS")
+                    .unwrap_or_else(|_| "5".to_string())
+                    .parse()
+                    .unwrap_or(5),
+            },
+            stream_metadata_config: StreamMetadataConfig {
+                mounts: match std::env::var("STREAM_METADATA_FILE").ok().filter(|path| !path.is_empty()) {
+                    Some(path) => StreamMetadataConfig::load_mounts(std::path::Path::new(&path))?,
+                    None => Vec::new(),
+                },
+                interval_seconds: std::env::var("STREAM_METADATA_INTERVAL_SECONDS")
+                    .unwrap_or_else(|_| "2".to_string())
+                    .parse()
+                    .unwrap_or(2),
+            },
+            webhook_config: WebhookConfig {
+                max_attempts: std::env::var("WEBHOOK_MAX_ATTEMPTS")
+                    .unwrap_or_else(|_| "6".to_string())
+                    .parse()
+                    .unwrap_or(6),
+                retry_seconds: std::env::var("WEBHOOK_RETRY_SECONDS")
+                    .unwrap_or_else(|_| "30".to_string())
+                    .parse()
+                    .unwrap_or(30),
+                timeout_seconds: std::env::var("WEBHOOK_TIMEOUT_SECONDS")
+                    .unwrap_or_else(|_| "10".to_string())
+                    .parse()
+                    .unwrap_or(10),
+            },
+            calendar_config: CalendarConfig {
+                timezone: std::env::var("CALENDAR_TIMEZONE")
+                    .ok()
+                    .filter(|timezone| !timezone.is_empty())
+                    .map(|timezone| timezone.parse::<chrono_tz::Tz>())
+                    .transpose()?,
+                location: std::env::var("CALENDAR_LOCATION").ok().filter(|location| !location.is_empty()),
+            },
+            branding_config: BrandingConfig {
+                name: std::env::var("BRANDING_NAME")
+                    .ok()
+                    .filter(|name| !name.is_empty())
+                    .unwrap_or_else(|| BrandingConfig::default().name),
+                accent_color: std::env::var("BRANDING_ACCENT_COLOR")
+                    .ok()
+                    .and_then(|color| BrandingConfig::parse_color(&color))
+                    .unwrap_or_else(|| BrandingConfig::default().accent_color),
+                logo_url: std::env::var("BRANDING_LOGO_URL").ok().filter(|url| !url.is_empty()),
+                footer: std::env::var("BRANDING_FOOTER").ok().filter(|footer| !footer.is_empty()),
+                font_path: std::env::var("BRANDING_FONT").ok().filter(|path| !path.is_empty()).map(std::path::PathBuf::from),
+                registration_url: std::env::var("BRANDING_REGISTRATION_URL").ok().filter(|url| !url.is_empty()),
             },
         })
     }
@@ -0,0 +1,30 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+
+use super::Track;
+
+/// Where DJ software or a bridge sends the tracks it plays, as OSC over UDP
+#[derive(Clone, Debug)]
+pub struct OscConfig {
+    pub listen_addr: Option<SocketAddr>, // None: no listener is started
+    pub track_address: String,           // OSC address of track messages
+}
+
+impl Default for OscConfig {
+    fn default() -> Self {
+        Self {
+            listen_addr: None,
+            track_address: "/slotify/track".to_string(),
+        }
+    }
+}
+
+/// The set being played and its latest reported track
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NowPlaying {
+    pub session_id: String,
+    pub dj_name: String,
+    pub session_started_at: DateTime<Utc>,
+    pub track: Option<Track>, // None until the DJ software reports a track
+}
@@ -0,0 +1,183 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Moments of the night that output hooks react to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookTrigger {
+    SlotStart,  // A DJ's session started
+    Draw,       // The lottery drew a DJ into the queue
+    SlotEnding, // The current slot is about to run out
+    EventEnd,
+}
+
+impl HookTrigger {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::SlotStart => "slot_start",
+            Self::Draw => "draw",
+            Self::SlotEnding => "slot_ending",
+            Self::EventEnd => "event_end",
+        }
+    }
+}
+
+/// Where a hook sends its message. Strings may contain `{placeholders}` from `HookContext`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HookTarget {
+    /// OSC message over UDP, e.g. to a lighting desk or Resolume
+    Osc {
+        host: String, // "host:port"
+        address: String,
+        #[serde(default)]
+        args: Vec<serde_json::Value>, // Strings are templated; whole numbers are sent as int32, others as float32
+    },
+    /// HTTP request; without a body template the context is sent as JSON
+    Http {
+        url: String,
+        #[serde(default = "default_http_method")]
+        method: String,
+        #[serde(default, skip_serializing)]
+        headers: HashMap<String, String>, // May carry credentials, so never sent back out
+        body: Option<String>,
+    },
+    /// A line written to a file or named pipe, e.g. a text source of the streaming software
+    File {
+        path: PathBuf,
+        template: String,
+        #[serde(default)]
+        append: bool, // Otherwise the file is replaced, so it always holds the latest line
+    },
+}
+
+fn default_http_method() -> String {
+    "POST".to_string()
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutputHook {
+    pub name: String,
+    pub triggers: Vec<HookTrigger>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(flatten)]
+    pub target: HookTarget,
+}
+
+/// Output hooks read from `OUTPUT_HOOKS_FILE`, a JSON array of `OutputHook`
+#[derive(Clone, Debug)]
+pub struct OutputConfig {
+    pub hooks: Vec<OutputHook>,
+    pub slot_warning_minutes: i64, // `slot_ending` fires this long before the slot runs out
+    pub timeout_seconds: u64,      // Per hook; a stuck receiver never holds up the others
+}
+
+impl Default for OutputConfig {
+    fn default() -> Self {
+        Self {
+            hooks: Vec::new(),
+            slot_warning_minutes: 10,
+            timeout_seconds: 5,
+        }
+    }
+}
+
+impl OutputConfig {
+    pub fn load_hooks(path: &std::path::Path) -> anyhow::Result<Vec<OutputHook>> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Cannot read output hooks from {}: {}", path.display(), e))?;
+        serde_json::from_str(&json)
+            .map_err(|e| anyhow::anyhow!("Invalid output hooks in {}: {}", path.display(), e))
+    }
+}
+
+/// What a hook's templates can refer to, as `{field}`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct HookContext {
+    pub trigger: String,
+    pub event_name: String,
+    pub dj_name: String,      // The DJ the trigger is about; the drawn DJ for `draw`
+    pub next_dj_name: String, // Next in the queue after the current DJ
+    pub slot_start: Option<DateTime<Utc>>,
+    pub slot_end: Option<DateTime<Utc>>,
+    pub minutes_left: Option<i64>, // Until the end of the current slot
+}
+
+impl HookContext {
+    /// Replace `{field}` placeholders; times render as HH:MM, `{countdown}` as mm:ss to the slot end
+    pub fn render(&self, template: &str) -> String {
+        self.render_at(template, Utc::now())
+    }
+
+    pub fn render_at(&self, template: &str, now: DateTime<Utc>) -> String {
+        self.render_with(template, now, str::to_string)
+    }
+
+    /// `render` for a JSON body template: values are escaped to go inside JSON strings
+    pub fn render_json(&self, template: &str) -> String {
+        self.render_with(template, Utc::now(), |value| {
+            let quoted = serde_json::Value::from(value).to_string();
+            quoted[1..quoted.len() - 1].to_string()
+        })
+    }
+
+    /// Replace placeholders in one pass over the template, so values that look like
+    /// placeholders themselves (a DJ called "{countdown}") are left as they are
+    fn render_with(&self, template: &str, now: DateTime<Utc>, escape: fn(&str) -> String) -> String {
+        let time = |at: Option<DateTime<Utc>>| at.map(|at| at.format("%H:%M").to_string()).unwrap_or_default();
+        let countdown = self
+            .slot_end
+            .map(|end| {
+                let seconds = (end - now).num_seconds().max(0);
+                format!("{}:{:02}", seconds / 60, seconds % 60)
+            })
+            .unwrap_or_default();
+        let values = [
+            ("trigger", self.trigger.clone()),
+            ("event_name", self.event_name.clone()),
+            ("dj_name", self.dj_name.clone()),
+            ("next_dj_name", self.next_dj_name.clone()),
+            ("slot_start", time(self.slot_start)),
+            ("slot_end", time(self.slot_end)),
+            ("minutes_left", self.minutes_left.map(|minutes| minutes.to_string()).unwrap_or_default()),
+            ("countdown", countdown),
+        ];
+
+        let mut rendered = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(open) = rest.find('{') {
+            rendered.push_str(&rest[..open]);
+            rest = &rest[open..];
+            let value = rest[1..]
+                .split_once('}')
+                .and_then(|(name, _)| values.iter().find(|(field, _)| *field == name));
+            match value {
+                Some((name, value)) => {
+                    rendered.push_str(&escape(value));
+                    rest = &rest[name.len() + 2..];
+                }
+                None => {
+                    rendered.push('{');
+                    rest = &rest[1..];
+                }
+            }
+        }
+        rendered.push_str(rest);
+        rendered
+    }
+}
+
+/// Outcome of one hook, as reported by the test endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HookResult {
+    pub name: String,
+    pub ok: bool,
+    pub error: Option<String>,
+}
@@ -0,0 +1,42 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A sync that has not finished after this long is assumed to have died with the process
+pub const STALE_SYNC_MINUTES: i64 = 30;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecorderSyncStatus {
+    pub running: bool,
+    pub last_started_at: Option<DateTime<Utc>>,
+    pub last_finished_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    pub recorders: i64,
+    pub indexed_sessions: i64,
+    pub sessions_added: i64, // Counts of the last successful run
+    pub sessions_updated: i64,
+    pub sessions_removed: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RecorderSyncSummary {
+    pub recorders: i64,
+    pub sessions_added: i64,
+    pub sessions_updated: i64,
+    pub sessions_removed: i64,
+    pub sessions_unchanged: i64,
+    pub sessions_failed: i64, // Listed, but their details could not be fetched
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct RecorderSyncRequest {
+    #[serde(default)]
+    pub full: bool, // Re-fetch closed sessions too
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RecorderSyncError {
+    #[error("A recorder sync is already running")]
+    AlreadyRunning,
+    #[error("Session recorder integration is disabled")]
+    Disabled,
+}
@@ -0,0 +1,143 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Share of the DJ session a recording has to cover to be considered at all
+pub const DEFAULT_MIN_COVERAGE: f64 = 0.5;
+/// Candidates whose coverage is within this margin of the best one make the match ambiguous
+pub const DEFAULT_AMBIGUITY_MARGIN: f64 = 0.1;
+
+/// Time span of one recorder session, as seen by the matcher
+#[derive(Debug, Clone)]
+pub struct RecordingWindow {
+    pub recorder_id: String,
+    pub recorder_session_id: String,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: Option<DateTime<Utc>>, // Still recording when open
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MatchCandidate {
+    pub recorder_id: String,
+    pub recorder_session_id: String,
+    pub overlap_seconds: i64,
+    pub coverage: f64, // Share of the DJ session inside the recording, 0..1
+    pub split_required: bool, // The recording also holds other sets; see segmentation
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum MatchOutcome {
+    Matched { candidate: MatchCandidate },
+    /// Several recordings cover the session about equally well; left for an admin to pick
+    Ambiguous { candidates: Vec<MatchCandidate> },
+    NoMatch,
+}
+
+/// Scores recorder sessions by how much of a DJ session's interval they cover.
+///
+/// Recording bounds are widened by the tolerance to absorb clock drift between the
+/// recorder and this server.
+#[derive(Debug, Clone)]
+pub struct RecorderMatcher {
+    pub tolerance: Duration,
+    pub min_coverage: f64,
+    pub ambiguity_margin: f64,
+    pub preferred_recorder_id: Option<String>, // Wins a tie, e.g. the main recorder over a backup
+}
+
+impl RecorderMatcher {
+    pub fn new(tolerance_minutes: i64) -> Self {
+        Self {
+            tolerance: Duration::minutes(tolerance_minutes.max(0)),
+            min_coverage: DEFAULT_MIN_COVERAGE,
+            ambiguity_margin: DEFAULT_AMBIGUITY_MARGIN,
+            preferred_recorder_id: None,
+        }
+    }
+
+    pub fn with_preferred_recorder(mut self, recorder_id: Option<String>) -> Self {
+        self.preferred_recorder_id = recorder_id;
+        self
+    }
+
+    /// Match one DJ session. Open intervals (session or recording still running) end at `now`.
+    pub fn match_session(
+        &self,
+        session_start: DateTime<Utc>,
+        session_end: Option<DateTime<Utc>>,
+        recordings: &[RecordingWindow],
+        now: DateTime<Utc>,
+    ) -> MatchOutcome {
+        // A session that has only just started still needs a non-empty interval
+        let session_end = session_end.unwrap_or(now).max(session_start + Duration::seconds(1));
+        let session_seconds = (session_end - session_start).num_seconds();
+
+        let mut candidates: Vec<MatchCandidate> = recordings
+            .iter()
+            .filter_map(|recording| {
+                let recording_end = recording.ended_at.unwrap_or(now).max(recording.started_at);
+                let widened_start = recording.started_at - self.tolerance;
+                let widened_end = recording_end + self.tolerance;
+
+                let overlap_seconds = (session_end.min(widened_end) - session_start.max(widened_start)).num_seconds();
+                let coverage = overlap_seconds as f64 / session_seconds as f64;
+                if overlap_seconds <= 0 || coverage < self.min_coverage {
+                    return None;
+                }
+
+                Some(MatchCandidate {
+                    recorder_id: recording.recorder_id.clone(),
+                    recorder_session_id: recording.recorder_session_id.clone(),
+                    overlap_seconds,
+                    coverage,
+                    split_required: recording.started_at < session_start - self.tolerance
+                        || recording_end > session_end + self.tolerance,
+                })
+            })
+            .collect();
+
+        candidates.sort_by(|a, b| b.coverage.total_cmp(&a.coverage).then(b.overlap_seconds.cmp(&a.overlap_seconds)));
+
+        let Some(best) = candidates.first().map(|c| c.coverage) else {
+            return MatchOutcome::NoMatch;
+        };
+        candidates.retain(|c| best - c.coverage <= self.ambiguity_margin);
+
+        // A tie is settled by the preferred recorder if exactly one of its recordings is among them
+        if let Some(preferred) = &self.preferred_recorder_id {
+            if candidates.iter().filter(|c| &c.recorder_id == preferred).count() == 1 {
+                candidates.retain(|c| &c.recorder_id == preferred);
+            }
+        }
+        // Otherwise a recording of just this set beats one that would have to be split
+        if candidates.iter().filter(|c| !c.split_required).count() == 1 {
+            candidates.retain(|c| !c.split_required);
+        }
+
+        if candidates.len() == 1 {
+            MatchOutcome::Matched { candidate: candidates.remove(0) }
+        } else {
+            MatchOutcome::Ambiguous { candidates }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionLinkResult {
+    pub session_id: String,
+    pub dj_name: String,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: Option<DateTime<Utc>>,
+    #[serde(flatten)]
+    pub outcome: MatchOutcome,
+}
+
+/// Result of auto-linking every DJ session of an event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventLinkReport {
+    pub event_id: String,
+    pub linked: Vec<SessionLinkResult>,
+    pub ambiguous: Vec<SessionLinkResult>, // Needs an admin to link manually
+    pub unmatched: Vec<SessionLinkResult>,
+    pub already_linked: Vec<String>, // Session ids left untouched
+}
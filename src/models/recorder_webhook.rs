@@ -0,0 +1,112 @@
+use percent_encoding::percent_decode_str;
+use serde::{Deserialize, Serialize};
+
+use super::recorder_match::SessionLinkResult;
+
+/// Header carrying `sha256=<hex HMAC of the raw body>`
+pub const WEBHOOK_SIGNATURE_HEADER: &str = "x-slotify-signature";
+
+/// Body of `POST /api/session-recorder/webhook`: either the recorder's own event or a
+/// MinIO bucket notification for objects under `{recorder}/sessions/{id}/`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum RecorderWebhookPayload {
+    Recorder(RecorderEvent),
+    BucketNotification(BucketNotification),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RecorderEvent {
+    pub event: RecorderEventKind,
+    pub recorder_id: String,
+    pub session_id: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecorderEventKind {
+    SessionStarted,
+    SessionClosed,
+    FileUploaded,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BucketNotification {
+    #[serde(rename = "Records")]
+    pub records: Vec<BucketRecord>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BucketRecord {
+    pub s3: BucketRecordS3,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BucketRecordS3 {
+    pub object: BucketObject,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BucketObject {
+    pub key: String, // URL-encoded
+}
+
+impl RecorderWebhookPayload {
+    /// `(recorder_id, session_id)` of every recorder session the notification touches, in
+    /// order and without repeats. Objects outside a session folder are ignored.
+    pub fn affected_sessions(&self) -> Vec<(String, String)> {
+        let mut sessions: Vec<(String, String)> = Vec::new();
+        let mut push = |session: (String, String)| {
+            if !sessions.contains(&session) {
+                sessions.push(session);
+            }
+        };
+
+        match self {
+            Self::Recorder(event) => push((event.recorder_id.clone(), event.session_id.clone())),
+            Self::BucketNotification(notification) => {
+                for record in &notification.records {
+                    if let Some(session) = session_from_object_key(&record.s3.object.key) {
+                        push(session);
+                    }
+                }
+            }
+        }
+
+        sessions
+    }
+}
+
+/// `recorder/sessions/session-id/data.flac` -> `("recorder", "session-id")`
+fn session_from_object_key(key: &str) -> Option<(String, String)> {
+    let key = key.replace('+', " ");
+    let key = percent_decode_str(&key).decode_utf8().ok()?;
+    let mut parts = key.split('/');
+
+    match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(recorder_id), Some("sessions"), Some(session_id), Some(_))
+            if !recorder_id.is_empty() && !session_id.is_empty() =>
+        {
+            Some((recorder_id.to_string(), session_id.to_string()))
+        }
+        _ => None,
+    }
+}
+
+/// What one webhook call changed
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RecorderWebhookReport {
+    pub indexed: Vec<String>, // "recorder_id/session_id"
+    pub linked: Vec<SessionLinkResult>, // DJ sessions newly linked by this notification
+    pub deliveries_started: Vec<String>, // DJ session ids
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RecorderWebhookError {
+    #[error("Recorder webhook is not configured; set SESSION_RECORDER_WEBHOOK_SECRET")]
+    NotConfigured,
+    #[error("Missing or invalid webhook signature")]
+    InvalidSignature,
+    #[error("Unrecognised webhook payload: {0}")]
+    InvalidPayload(String),
+}
@@ -0,0 +1,121 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "reserved_slot_kind", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ReservedSlotKind {
+    WarmUp,
+    Headliner,
+    Closing,
+    Other,
+}
+
+impl ReservedSlotKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::WarmUp => "Warm-up",
+            Self::Headliner => "Headliner",
+            Self::Closing => "Closing",
+            Self::Other => "Booked",
+        }
+    }
+}
+
+/// A queue position booked for a specific DJ; the lottery only fills the positions around it
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ReservedSlot {
+    pub id: String,
+    pub dj_id: String,
+    pub position: i32,
+    pub kind: ReservedSlotKind,
+    pub label: Option<String>,
+    pub starts_at: Option<DateTime<Utc>>, // Set when the slot was booked by time rather than position
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateReservedSlotRequest {
+    pub dj_id: Option<String>,
+    pub dj_name: Option<String>, // Registers a new DJ when no dj_id is given
+    pub position: Option<i32>,
+    pub starts_at: Option<DateTime<Utc>>, // Alternative to position, needs a running event
+    pub kind: ReservedSlotKind,
+    pub label: Option<String>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ReservedSlotError {
+    #[error("Either dj_id or dj_name is required")]
+    MissingDj,
+    #[error("DJ not found")]
+    DjNotFound,
+    #[error("Either position or starts_at is required")]
+    MissingPosition,
+    #[error("Booking by time needs a running event")]
+    NoActiveEvent,
+    #[error("Position must be 1 or higher")]
+    InvalidPosition,
+    #[error("Position {0} is already reserved")]
+    PositionTaken(i32),
+    #[error("DJ already has a reserved slot")]
+    AlreadyReserved,
+    #[error("Position {0} has already been played")]
+    PositionPassed(i32),
+    #[error("Reserved slot not found")]
+    NotFound,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReservedSlotResponse {
+    pub id: String,
+    pub dj_id: String,
+    pub dj_name: Option<String>,
+    pub position: i32,
+    pub kind: ReservedSlotKind,
+    pub label: Option<String>,
+    pub starts_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ReservedSlot {
+    pub fn new(dj_id: String, position: i32, kind: ReservedSlotKind, label: Option<String>, starts_at: Option<DateTime<Utc>>) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            dj_id,
+            position,
+            kind,
+            label,
+            starts_at,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Queue position of the slot starting at `starts_at`, counting from position 1 at event start
+    pub fn position_for_time(event_started_at: DateTime<Utc>, slot_duration_minutes: i32, starts_at: DateTime<Utc>) -> i32 {
+        let minutes = starts_at.signed_duration_since(event_started_at).num_minutes().max(0);
+        (minutes / slot_duration_minutes.max(1) as i64) as i32 + 1
+    }
+}
+
+/// Queue positions for DJs in queue order, keeping reserved DJs on their booked positions.
+///
+/// `floating` are the lottery DJs in their current order, `reserved` the (dj_id, position)
+/// pairs of the bookings. Lottery DJs take the lowest positions not booked by anyone.
+pub fn assign_queue_positions(floating: &[String], reserved: &[(String, i32)]) -> Vec<(String, i32)> {
+    let booked: std::collections::HashSet<i32> = reserved.iter().map(|(_, position)| *position).collect();
+    let mut positions: Vec<(String, i32)> = reserved.to_vec();
+
+    let mut next = 1;
+    for dj_id in floating {
+        while booked.contains(&next) {
+            next += 1;
+        }
+        positions.push((dj_id.clone(), next));
+        next += 1;
+    }
+
+    positions.sort_by_key(|(_, position)| *position);
+    positions
+}
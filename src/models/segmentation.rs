@@ -0,0 +1,55 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Cut a continuous recorder recording into one file per DJ session.
+///
+/// The source is either a recorder session (downloaded from the recorder's storage)
+/// or a local file, in which case the wall-clock start of the recording is required.
+/// Local files can only be given from the CLI; the HTTP API ignores `file_path`, so
+/// it cannot be used to read arbitrary files on the server.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SegmentRecordingRequest {
+    pub recorder_id: Option<String>,
+    pub recorder_session_id: Option<String>,
+    #[serde(skip)]
+    pub file_path: Option<String>,
+    pub recording_started_at: Option<DateTime<Utc>>, // Overrides the recorder's start time
+    pub pre_roll_seconds: Option<u32>,
+    pub post_roll_seconds: Option<u32>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SegmentationError {
+    #[error("recorder_id and recorder_session_id, or a local file, are required")]
+    MissingSource,
+    #[error("recording_started_at is required for local files")]
+    MissingStartTime,
+    #[error("Recording file not found")]
+    FileNotFound,
+    #[error("Session recorder is not available")]
+    RecorderUnavailable,
+    #[error("Recorder session has no FLAC or OGG recording")]
+    NoRecording,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionSegment {
+    pub session_id: String,
+    pub dj_id: String,
+    pub dj_name: String,
+    pub file_path: String,
+    pub offset_seconds: f64, // Into the source recording, including pre-roll
+    pub duration_seconds: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SegmentationReport {
+    pub recording_started_at: DateTime<Utc>,
+    pub recording_duration_seconds: f64,
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub pre_roll_seconds: u32,
+    pub post_roll_seconds: u32,
+    pub segments: Vec<SessionSegment>,
+    pub skipped_session_ids: Vec<String>, // Sessions in the recording's time span its audio doesn't reach
+}
@@ -32,7 +32,7 @@ pub enum SessionUploadStatus {
     Failed,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::Type)]
 #[sqlx(type_name = "session_type", rename_all = "lowercase")]
 pub enum SessionType {
     Solo,
@@ -40,7 +40,7 @@ pub enum SessionType {
     Special,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StartSessionRequest {
     pub dj_id: String,
     pub session_type: Option<SessionType>,
@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+
+/// Templates of `GET /api/event/:id/poster.png`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShareTemplate {
+    #[default]
+    Lineup, // Poster of the night's timetable
+    NowPlaying, // Story card of the set being played
+    SetOnline, // Card of a DJ's published recording, opened with the set's download token
+}
+
+impl ShareTemplate {
+    /// Width and height in pixels, sized for feed posts and stories
+    pub fn size(&self) -> (u32, u32) {
+        match self {
+            Self::Lineup => (1080, 1350),
+            Self::NowPlaying => (1080, 1920),
+            Self::SetOnline => (1080, 1080),
+        }
+    }
+}
+
+/// Query of `GET /api/event/:id/poster.png`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ShareImageRequest {
+    #[serde(default)]
+    pub template: ShareTemplate,
+    pub token: Option<String>, // Download token of the set, for `set_online`
+    pub qr: Option<bool>, // QR code to the registration or download page; on by default
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ShareImageError {
+    #[error("Event not found")]
+    EventNotFound,
+    #[error("No set is playing at this event")]
+    NothingPlaying,
+    #[error("The set_online template needs the download token of the set")]
+    TokenRequired,
+    #[error("Unknown download token for this event")]
+    UnknownToken,
+}
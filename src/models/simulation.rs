@@ -0,0 +1,346 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::dj::Dj;
+use super::event_session::EventSession;
+use super::lottery::{LotteryEngine, WeightComponentKind, MINIMUM_WEIGHT};
+
+pub const DEFAULT_SIMULATION_RUNS: u32 = 10_000;
+pub const MAX_SIMULATION_RUNS: u32 = 100_000;
+/// Most weights one simulation evaluates, runs × slots drawn × pool size
+pub const MAX_SIMULATION_WEIGHTS: u64 = 50_000_000;
+/// Length of a virtual event when no slot count is given
+pub const DEFAULT_SIMULATED_EVENT_MINUTES: i64 = 360;
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct SimulationRequest {
+    /// Number of virtual events, defaults to 10 000
+    pub runs: Option<u32>,
+    /// Seed for the random generator; a random seed is picked (and reported) when omitted
+    pub seed: Option<u64>,
+    /// Slots per virtual event, defaults to a six hour night at the event's slot length
+    pub slots: Option<usize>,
+    /// Restrict the historical comparison to one event
+    pub event_id: Option<String>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SimulationError {
+    #[error("runs must be between 1 and {}", MAX_SIMULATION_RUNS)]
+    InvalidRuns,
+    #[error("runs × slots × pool size must be at most {}; use fewer runs or slots", MAX_SIMULATION_WEIGHTS)]
+    TooLarge,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DjSimulationResult {
+    pub dj_id: String,
+    pub dj_name: String,
+    pub current_weight: f64,
+    /// Share of virtual events in which the DJ got a slot
+    pub play_probability: f64,
+    /// Average number of slots played before the DJ's own, over the events they played in
+    pub expected_wait_slots: Option<f64>,
+    pub expected_wait_minutes: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimulationResult {
+    pub runs: u32,
+    pub seed: u64,
+    pub slots: usize,
+    pub slot_duration_minutes: i64,
+    pub pool_size: usize,
+    /// Inequality of play probabilities across the pool: 0 = everyone equal, 1 = one DJ plays every time
+    pub gini_coefficient: f64,
+    pub djs: Vec<DjSimulationResult>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoricalDjFairness {
+    pub dj_id: String,
+    pub dj_name: Option<String>,
+    pub draws_entered: i64,
+    pub observed_wins: i64,
+    /// Sum of the selection probabilities recorded at draw time
+    pub expected_wins: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoricalFairness {
+    pub total_draws: i64,
+    /// Pearson statistic of observed wins against recorded probabilities
+    pub chi_square: f64,
+    pub degrees_of_freedom: usize,
+    /// Probability of a deviation at least this large if the draws were fair; None without enough data
+    pub p_value: Option<f64>,
+    pub djs: Vec<HistoricalDjFairness>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FairnessReport {
+    pub generated_at: DateTime<Utc>,
+    pub simulation: SimulationResult,
+    pub historical: HistoricalFairness,
+}
+
+/// Replays the lottery engine against a fixed pool many times over
+pub struct LotterySimulator<'a> {
+    engine: &'a LotteryEngine,
+}
+
+impl<'a> LotterySimulator<'a> {
+    pub fn new(engine: &'a LotteryEngine) -> Self {
+        Self { engine }
+    }
+
+    /// Run `runs` virtual events of `slots` draws each, starting from the given carry-over state
+    pub fn run(
+        &self,
+        djs: &[Dj],
+        event: Option<&EventSession>,
+        lost_draws: &HashMap<String, u32>,
+        runs: u32,
+        slots: usize,
+        seed: u64,
+    ) -> Result<SimulationResult> {
+        if runs == 0 || runs > MAX_SIMULATION_RUNS {
+            return Err(SimulationError::InvalidRuns.into());
+        }
+        let pool_size = djs.len();
+        let draws_per_run = slots.min(pool_size);
+        if runs as u64 * draws_per_run as u64 * pool_size as u64 > MAX_SIMULATION_WEIGHTS {
+            return Err(SimulationError::TooLarge.into());
+        }
+
+        let slot_duration_minutes = event
+            .map(|e| e.slot_duration_minutes as i64)
+            .unwrap_or(60);
+
+        // Everything except carry-over is fixed for the duration of a virtual event,
+        // so the unfloored product is computed once and carry-over applied per draw
+        let participants = self.engine.calculate_weights(djs, event);
+        let static_weights: Vec<f64> = participants
+            .iter()
+            .map(|p| {
+                p.weight_components
+                    .iter()
+                    .filter(|c| {
+                        c.kind != WeightComponentKind::MinimumWeight
+                            && c.kind != WeightComponentKind::CarryOver
+                    })
+                    .map(|c| c.factor)
+                    .product()
+            })
+            .collect();
+        let initial_lost: Vec<u32> = djs
+            .iter()
+            .map(|dj| lost_draws.get(&dj.id).copied().unwrap_or(0))
+            .collect();
+
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let mut plays = vec![0u32; pool_size];
+        let mut wait_totals = vec![0u64; pool_size];
+
+        let mut remaining: Vec<usize> = Vec::with_capacity(pool_size);
+        let mut lost = initial_lost.clone();
+        let mut weights: Vec<f64> = Vec::with_capacity(pool_size);
+
+        for _ in 0..runs {
+            remaining.clear();
+            remaining.extend(0..pool_size);
+            lost.copy_from_slice(&initial_lost);
+
+            for slot in 0..draws_per_run {
+                weights.clear();
+                weights.extend(remaining.iter().map(|&i| {
+                    (static_weights[i] * self.engine.carry_over_factor(lost[i])).max(MINIMUM_WEIGHT)
+                }));
+
+                let picked = match LotteryEngine::pick_weighted(&weights, &mut rng) {
+                    Some(index) => remaining.swap_remove(index),
+                    None => break,
+                };
+
+                plays[picked] += 1;
+                wait_totals[picked] += slot as u64;
+                for &i in &remaining {
+                    lost[i] += 1;
+                }
+            }
+        }
+
+        let djs: Vec<DjSimulationResult> = participants
+            .iter()
+            .enumerate()
+            .map(|(i, p)| {
+                let expected_wait_slots = if plays[i] > 0 {
+                    Some(wait_totals[i] as f64 / plays[i] as f64)
+                } else {
+                    None
+                };
+
+                DjSimulationResult {
+                    dj_id: p.dj.id.clone(),
+                    dj_name: p.dj.name.clone(),
+                    current_weight: p.calculated_weight,
+                    play_probability: plays[i] as f64 / runs as f64,
+                    expected_wait_slots,
+                    expected_wait_minutes: expected_wait_slots.map(|w| w * slot_duration_minutes as f64),
+                }
+            })
+            .collect();
+
+        let probabilities: Vec<f64> = djs.iter().map(|d| d.play_probability).collect();
+
+        Ok(SimulationResult {
+            runs,
+            seed,
+            slots,
+            slot_duration_minutes,
+            pool_size,
+            gini_coefficient: gini_coefficient(&probabilities),
+            djs,
+        })
+    }
+}
+
+impl HistoricalFairness {
+    /// Pearson chi-square over DJs, comparing wins with the sum of their recorded probabilities
+    pub fn from_djs(total_draws: i64, djs: Vec<HistoricalDjFairness>) -> Self {
+        let tested: Vec<&HistoricalDjFairness> = djs.iter().filter(|d| d.expected_wins > 0.0).collect();
+
+        let chi_square: f64 = tested
+            .iter()
+            .map(|d| {
+                let difference = d.observed_wins as f64 - d.expected_wins;
+                difference * difference / d.expected_wins
+            })
+            .sum();
+        let degrees_of_freedom = tested.len().saturating_sub(1);
+        let p_value = if degrees_of_freedom > 0 {
+            Some(chi_square_p_value(chi_square, degrees_of_freedom))
+        } else {
+            None
+        };
+
+        Self {
+            total_draws,
+            chi_square,
+            degrees_of_freedom,
+            p_value,
+            djs,
+        }
+    }
+}
+
+/// Gini coefficient of non-negative values; 0.0 for empty or all-zero input
+pub fn gini_coefficient(values: &[f64]) -> f64 {
+    let n = values.len() as f64;
+    let total: f64 = values.iter().sum();
+    if values.is_empty() || total <= 0.0 {
+        return 0.0;
+    }
+
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+
+    let weighted_sum: f64 = sorted
+        .iter()
+        .enumerate()
+        .map(|(i, value)| (2.0 * (i as f64 + 1.0) - n - 1.0) * value)
+        .sum();
+
+    weighted_sum / (n * total)
+}
+
+/// Upper tail probability of the chi-square distribution
+pub fn chi_square_p_value(statistic: f64, degrees_of_freedom: usize) -> f64 {
+    if statistic <= 0.0 || degrees_of_freedom == 0 {
+        return 1.0;
+    }
+    regularized_gamma_q(degrees_of_freedom as f64 / 2.0, statistic / 2.0)
+}
+
+/// Q(a, x) = Γ(a, x) / Γ(a), via the series for small x and a continued fraction otherwise
+fn regularized_gamma_q(a: f64, x: f64) -> f64 {
+    const EPSILON: f64 = 1e-14;
+    const MAX_ITERATIONS: usize = 500;
+
+    let log_prefactor = -x + a * x.ln() - ln_gamma(a);
+
+    if x < a + 1.0 {
+        let mut term = 1.0 / a;
+        let mut sum = term;
+        let mut denominator = a;
+        for _ in 0..MAX_ITERATIONS {
+            denominator += 1.0;
+            term *= x / denominator;
+            sum += term;
+            if term.abs() < sum.abs() * EPSILON {
+                break;
+            }
+        }
+        (1.0 - sum * log_prefactor.exp()).clamp(0.0, 1.0)
+    } else {
+        // Lentz's method
+        let tiny = f64::MIN_POSITIVE / EPSILON;
+        let mut b = x + 1.0 - a;
+        let mut c = 1.0 / tiny;
+        let mut d = 1.0 / b;
+        let mut h = d;
+        for i in 1..=MAX_ITERATIONS {
+            let an = -(i as f64) * (i as f64 - a);
+            b += 2.0;
+            d = an * d + b;
+            if d.abs() < tiny {
+                d = tiny;
+            }
+            c = b + an / c;
+            if c.abs() < tiny {
+                c = tiny;
+            }
+            d = 1.0 / d;
+            let delta = d * c;
+            h *= delta;
+            if (delta - 1.0).abs() < EPSILON {
+                break;
+            }
+        }
+        (h * log_prefactor.exp()).clamp(0.0, 1.0)
+    }
+}
+
+/// Lanczos approximation of ln Γ(x) for x > 0
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+
+    if x < 0.5 {
+        // Reflection formula
+        let pi = std::f64::consts::PI;
+        return (pi / (pi * x).sin()).ln() - ln_gamma(1.0 - x);
+    }
+
+    let x = x - 1.0;
+    let t = x + 7.5;
+    let series = COEFFICIENTS[1..]
+        .iter()
+        .enumerate()
+        .fold(COEFFICIENTS[0], |acc, (i, c)| acc + c / (x + i as f64 + 1.0));
+
+    0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + series.ln()
+}
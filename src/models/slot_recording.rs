@@ -0,0 +1,39 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// A recording started on the session recorder for one DJ slot
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct SlotRecording {
+    pub id: String,
+    pub event_id: String,
+    pub dj_id: String,
+    pub recording_id: Option<String>, // The recorder's id; None if starting failed
+    pub name: String,
+    pub keep: bool,
+    pub started_at: DateTime<Utc>,
+    pub stopped_at: Option<DateTime<Utc>>,
+    pub error: Option<String>,
+}
+
+/// What the recorder is asked to record
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StartRecordingRequest {
+    pub name: String,
+    pub keep: bool, // Exempt from the recorder's cleanup
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StartRecordingResponse {
+    pub id: String,
+}
+
+/// "Alice @ Friday Night Sessions 2024-01-01", or "Alice @ 2024-01-01" for an unnamed event;
+/// the name the set shows up under on the recorder
+pub fn recording_name(dj_name: &str, event_name: Option<&str>, event_started_at: DateTime<Utc>) -> String {
+    let date = event_started_at.format("%Y-%m-%d");
+    match event_name.map(str::trim).filter(|name| !name.is_empty()) {
+        Some(event_name) => format!("{} @ {} {}", dj_name, event_name, date),
+        None => format!("{} @ {}", dj_name, date),
+    }
+}
@@ -0,0 +1,139 @@
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use std::path::PathBuf;
+use thiserror::Error;
+
+/// Where Slotify keeps the files it hands out (published sets, archives)
+#[derive(Clone, Debug)]
+pub enum StorageConfig {
+    /// A directory on this machine; `public_url` is where a web server exposes it, if anywhere
+    Local { root: PathBuf, public_url: Option<String> },
+    S3(S3StorageConfig),
+}
+
+#[derive(Clone, Debug)]
+pub struct S3StorageConfig {
+    pub endpoint: String,
+    pub region: String,
+    pub access_key: String,
+    pub secret_key: String,
+    pub bucket: String,
+    pub public_endpoint: String, // Object URLs are `{public_endpoint}/{key}`
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self::Local {
+            root: crate::utils::get_recordings_directory(),
+            public_url: None,
+        }
+    }
+}
+
+impl StorageConfig {
+    /// `STORAGE_BACKEND=local` (default) or `s3`
+    pub fn from_env() -> anyhow::Result<Self> {
+        let public_url = std::env::var("STORAGE_PUBLIC_URL").ok().filter(|url| !url.is_empty());
+
+        match std::env::var("STORAGE_BACKEND").unwrap_or_else(|_| "local".to_string()).as_str() {
+            "local" => Ok(Self::Local {
+                root: std::env::var("STORAGE_LOCAL_DIR")
+                    .map(PathBuf::from)
+                    .unwrap_or_else(|_| crate::utils::get_recordings_directory()),
+                public_url,
+            }),
+            "s3" => {
+                let endpoint = std::env::var("STORAGE_S3_ENDPOINT")
+                    .unwrap_or_else(|_| "http://localhost:9000".to_string());
+                let bucket = std::env::var("STORAGE_S3_BUCKET")
+                    .unwrap_or_else(|_| "slotify".to_string());
+                Ok(Self::S3(S3StorageConfig {
+                    public_endpoint: public_url
+                        .unwrap_or_else(|| format!("{}/{}", endpoint.trim_end_matches('/'), bucket)),
+                    region: std::env::var("STORAGE_S3_REGION")
+                        .unwrap_or_else(|_| "us-east-1".to_string()),
+                    access_key: std::env::var("STORAGE_S3_ACCESS_KEY")
+                        .unwrap_or_else(|_| "admin".to_string()),
+                    secret_key: std::env::var("STORAGE_S3_SECRET_KEY")
+                        .unwrap_or_else(|_| "password123".to_string()),
+                    endpoint,
+                    bucket,
+                }))
+            }
+            other => Err(anyhow::anyhow!("Unknown STORAGE_BACKEND '{}', expected 'local' or 's3'", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StorageObject {
+    pub key: String,
+    pub size: u64,
+}
+
+impl StorageObject {
+    /// Last path segment of the key
+    pub fn name(&self) -> &str {
+        self.key.rsplit('/').next().unwrap_or(&self.key)
+    }
+}
+
+/// One level of a storage "directory": sub-directory names and the objects directly in it
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StorageListing {
+    pub directories: Vec<String>,
+    pub objects: Vec<StorageObject>,
+}
+
+/// Part of an object to read, as in an HTTP `Range` header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteRange {
+    /// `bytes=start-end` (end inclusive) or `bytes=start-`
+    From { start: u64, end: Option<u64> },
+    /// `bytes=-n`
+    Last(u64),
+}
+
+impl ByteRange {
+    /// Parse a single-range `Range` header (`bytes=0-99`, `bytes=100-`, `bytes=-500`).
+    /// Multiple ranges, other units and invalid ranges such as `bytes=5-2` give `None`,
+    /// i.e. the whole object is sent; only valid ranges outside the object are a 416.
+    pub fn parse(header: &str) -> Option<Self> {
+        let spec = header.trim().strip_prefix("bytes=")?.trim();
+        if spec.contains(',') {
+            return None;
+        }
+        let (start, end) = spec.split_once('-')?;
+        match (start.trim(), end.trim()) {
+            ("", length) => length.parse().ok().map(ByteRange::Last),
+            (start, "") => start.parse().ok().map(|start| ByteRange::From { start, end: None }),
+            (start, end) => {
+                let (start, end) = (start.parse().ok()?, end.parse().ok()?);
+                (start <= end).then_some(ByteRange::From { start, end: Some(end) })
+            }
+        }
+    }
+
+    /// The byte offsets this range covers in an object of `size` bytes
+    pub fn resolve(&self, size: u64) -> Result<Range<u64>, StorageError> {
+        let range = match *self {
+            ByteRange::From { start, end } => start..end.map_or(size, |end| end.saturating_add(1).min(size)),
+            ByteRange::Last(length) => size.saturating_sub(length)..size,
+        };
+
+        if range.start >= range.end {
+            return Err(StorageError::RangeNotSatisfiable { size });
+        }
+        Ok(range)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum StorageError {
+    #[error("Object not found: {0}")]
+    NotFound(String),
+    #[error("Invalid storage key: {0}")]
+    InvalidKey(String),
+    #[error("Requested range is outside the object ({size} bytes)")]
+    RangeNotSatisfiable { size: u64 },
+}
@@ -0,0 +1,77 @@
+use serde::{Deserialize, Serialize};
+
+use super::NowPlaying;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StreamServer {
+    #[default]
+    Icecast,   // `GET /admin/metadata?mount=&mode=updinfo&song=` with basic auth
+    Shoutcast, // `GET /admin.cgi?mode=updinfo&song=&pass=`, as served by SHOUTcast and Icecast's compatibility port
+}
+
+/// A room's stream whose title follows the set being played
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StreamMount {
+    pub room: String,
+    #[serde(default)]
+    pub server: StreamServer,
+    pub url: String, // e.g. "http://icecast:8000"
+    #[serde(default = "default_mount")]
+    pub mount: String,
+    #[serde(default = "default_username")]
+    pub username: String,
+    #[serde(skip_serializing)]
+    pub password: String,
+    #[serde(default = "default_true")]
+    pub include_track: bool, // Otherwise the title is just the DJ
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+fn default_mount() -> String {
+    "/stream".to_string()
+}
+
+fn default_username() -> String {
+    "admin".to_string()
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Stream mounts read from `STREAM_METADATA_FILE`, a JSON array of `StreamMount`
+#[derive(Clone, Debug)]
+pub struct StreamMetadataConfig {
+    pub mounts: Vec<StreamMount>,
+    pub interval_seconds: u64, // How often the set and track are checked for changes
+}
+
+impl Default for StreamMetadataConfig {
+    fn default() -> Self {
+        Self {
+            mounts: Vec::new(),
+            interval_seconds: 2,
+        }
+    }
+}
+
+impl StreamMetadataConfig {
+    pub fn load_mounts(path: &std::path::Path) -> anyhow::Result<Vec<StreamMount>> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Cannot read stream mounts from {}: {}", path.display(), e))?;
+        serde_json::from_str(&json)
+            .map_err(|e| anyhow::anyhow!("Invalid stream mounts in {}: {}", path.display(), e))
+    }
+}
+
+impl StreamMount {
+    /// "DJ", or "DJ - Artist - Title" once a track is known and the mount shows tracks
+    pub fn title(&self, now_playing: &NowPlaying) -> String {
+        match &now_playing.track {
+            Some(track) if self.include_track => format!("{} - {}", now_playing.dj_name, track.display()),
+            _ => now_playing.dj_name.clone(),
+        }
+    }
+}
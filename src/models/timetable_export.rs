@@ -0,0 +1,195 @@
+use crate::models::event_session::{Timetable, TimetableEntryStatus};
+use crate::models::ReservedSlotKind;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Version of the exported JSON; bumped only when fields change meaning or go away
+pub const TIMETABLE_SCHEMA_VERSION: u32 = 1;
+
+/// `?format=` on `GET /api/event/:id/timetable` and `--format` of `cli timetable`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimetableFormat {
+    Csv,
+    #[default]
+    Json,
+    #[serde(alias = "md")]
+    Markdown,
+    Html,
+}
+
+impl TimetableFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Json => "json",
+            Self::Markdown => "md",
+            Self::Html => "html",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Csv => "text/csv; charset=utf-8",
+            Self::Json => "application/json",
+            Self::Markdown => "text/markdown; charset=utf-8",
+            Self::Html => "text/html; charset=utf-8",
+        }
+    }
+}
+
+impl std::str::FromStr for TimetableFormat {
+    type Err = TimetableExportError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "csv" => Ok(Self::Csv),
+            "json" => Ok(Self::Json),
+            "markdown" | "md" => Ok(Self::Markdown),
+            "html" => Ok(Self::Html),
+            _ => Err(TimetableExportError::UnknownFormat(s.to_string())),
+        }
+    }
+}
+
+/// Look of the printable timetable and the share images
+#[derive(Clone, Debug)]
+pub struct BrandingConfig {
+    pub name: String, // Shown next to the event name, e.g. the collective or venue
+    pub accent_color: String, // `#rrggbb`
+    pub logo_url: Option<String>,
+    pub footer: Option<String>,
+    pub font_path: Option<PathBuf>, // TrueType/OpenType font of the share images; a built-in one when unset
+    pub registration_url: Option<String>, // Where guests sign up; QR code target on the lineup and now playing images
+}
+
+impl Default for BrandingConfig {
+    fn default() -> Self {
+        Self {
+            name: "DA Slotify".to_string(),
+            accent_color: "#ff2e63".to_string(),
+            logo_url: None,
+            footer: None,
+            font_path: None,
+            registration_url: None,
+        }
+    }
+}
+
+impl BrandingConfig {
+    /// `#rrggbb`; anything else falls back to the default, as it ends up in CSS
+    pub fn parse_color(color: &str) -> Option<String> {
+        let hex = color.trim().strip_prefix('#')?;
+        (hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit())).then(|| format!("#{}", hex.to_lowercase()))
+    }
+}
+
+/// An event's timetable as exported: the JSON schema is versioned and has no volatile fields,
+/// so exporting the same night twice gives the same file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimetableExport {
+    pub schema_version: u32,
+    pub event: ExportedEvent,
+    pub slots: Vec<ExportedSlot>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportedEvent {
+    pub id: String,
+    pub name: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub slot_duration_minutes: i32,
+    pub timezone: String, // IANA name the other formats show local times in
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportedSlot {
+    pub position: i32,
+    pub dj_id: String,
+    pub dj_name: String,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>, // Planned from the slot length until the set has ended
+    pub status: ExportedSlotStatus,
+    pub booking: Option<ReservedSlotKind>, // Set for booked slots, None for lottery slots
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportedSlotStatus {
+    Played,
+    Playing,
+    Upcoming,
+}
+
+impl ExportedSlotStatus {
+    /// Same as the serialized value
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Played => "played",
+            Self::Playing => "playing",
+            Self::Upcoming => "upcoming",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Played => "Played",
+            Self::Playing => "Playing now",
+            Self::Upcoming => "Upcoming",
+        }
+    }
+}
+
+impl TimetableExport {
+    pub fn new(timetable: &Timetable, timezone: &str) -> Self {
+        let slot_length = Duration::minutes(timetable.slot_duration_minutes as i64);
+        let slots = timetable
+            .entries
+            .iter()
+            .map(|entry| ExportedSlot {
+                position: entry.position,
+                dj_id: entry.dj_id.clone(),
+                dj_name: entry.dj_name.clone(),
+                starts_at: entry.started_at,
+                ends_at: entry.ended_at.unwrap_or(entry.started_at + slot_length),
+                status: match entry.status {
+                    TimetableEntryStatus::Completed => ExportedSlotStatus::Played,
+                    TimetableEntryStatus::InProgress => ExportedSlotStatus::Playing,
+                    TimetableEntryStatus::Upcoming => ExportedSlotStatus::Upcoming,
+                },
+                booking: entry.reserved_kind,
+                label: entry.reserved_label.clone(),
+            })
+            .collect();
+
+        Self {
+            schema_version: TIMETABLE_SCHEMA_VERSION,
+            event: ExportedEvent {
+                id: timetable.event_id.clone(),
+                name: timetable.event_name.clone(),
+                started_at: timetable.event_started_at,
+                slot_duration_minutes: timetable.slot_duration_minutes,
+                timezone: timezone.to_string(),
+            },
+            slots,
+        }
+    }
+}
+
+/// An export ready to serve or write to disk
+#[derive(Debug, Clone)]
+pub struct RenderedTimetable {
+    pub filename: String, // `<event name>_<date>_timetable.<ext>`
+    pub content_type: &'static str,
+    pub body: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum TimetableExportError {
+    #[error("Event not found")]
+    EventNotFound,
+    #[error("Unknown timetable format '{0}', expected csv, json, markdown or html")]
+    UnknownFormat(String),
+}
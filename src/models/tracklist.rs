@@ -0,0 +1,384 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// Longest tracklist accepted for one set
+pub const MAX_TRACKS: usize = 500;
+/// Latest start time accepted for a track, far beyond any set
+pub const MAX_TRACK_OFFSET_SECONDS: f64 = 48.0 * 3600.0;
+
+/// One track of a set
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRow)]
+pub struct Track {
+    #[serde(default)]
+    pub position: i64, // 1-based; assigned from the order tracks are submitted in
+    pub offset_seconds: Option<f64>, // From the start of the set
+    pub artist: Option<String>,
+    pub title: String,
+}
+
+impl Track {
+    pub fn new(artist: Option<&str>, title: &str, offset_seconds: Option<f64>) -> Self {
+        Self {
+            position: 0,
+            offset_seconds,
+            artist: artist.map(str::to_string),
+            title: title.to_string(),
+        }
+    }
+
+    /// Read a track back from "Artist - Title", as players put it in a single field
+    pub fn from_display(text: &str, offset_seconds: Option<f64>) -> Self {
+        let (artist, title) = split_artist_title(text.trim());
+        Self::new(artist, title, offset_seconds)
+    }
+
+    /// "Artist - Title", or just the title
+    pub fn display(&self) -> String {
+        match &self.artist {
+            Some(artist) => format!("{} - {}", artist, self.title),
+            None => self.title.clone(),
+        }
+    }
+}
+
+/// How a pasted or uploaded tracklist is written
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TracklistFormat {
+    #[default]
+    Auto,      // Guessed from the contents
+    Text,      // One track per line, e.g. "12:40 Artist - Title"
+    Cue,       // Cue sheet
+    M3u,       // M3U/M3U8 playlist, as exported by most DJ software
+    Rekordbox, // rekordbox history exported as TXT, tab separated with a header row
+}
+
+impl std::str::FromStr for TracklistFormat {
+    type Err = TracklistError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "auto" => Ok(Self::Auto),
+            "text" | "txt" => Ok(Self::Text),
+            "cue" => Ok(Self::Cue),
+            "m3u" | "m3u8" => Ok(Self::M3u),
+            "rekordbox" => Ok(Self::Rekordbox),
+            _ => Err(TracklistError::UnknownFormat(s.to_string())),
+        }
+    }
+}
+
+impl TracklistFormat {
+    /// Guess the format of a tracklist from its contents
+    pub fn detect(text: &str) -> Self {
+        let mut lines = text.lines().map(str::trim).filter(|line| !line.is_empty()).peekable();
+        let Some(first) = lines.peek().copied() else {
+            return Self::Text;
+        };
+        if first.contains('\t') && (first.contains("Track Title") || first.contains("Artist")) {
+            return Self::Rekordbox;
+        }
+
+        let mut has_track = false;
+        let mut has_index = false;
+        for line in lines {
+            if line.starts_with("#EXTM3U") || line.starts_with("#EXTINF") {
+                return Self::M3u;
+            }
+            let upper = line.to_ascii_uppercase();
+            has_track |= upper.starts_with("TRACK ");
+            has_index |= upper.starts_with("INDEX ");
+        }
+        if has_track && has_index {
+            Self::Cue
+        } else {
+            Self::Text
+        }
+    }
+
+    /// Read the tracks out of a tracklist. Start times stay `None` where the format has none,
+    /// which is the case for M3U and rekordbox exports.
+    pub fn parse(self, text: &str) -> Result<Vec<Track>, TracklistError> {
+        let format = match self {
+            Self::Auto => Self::detect(text),
+            format => format,
+        };
+        let tracks = match format {
+            Self::Auto | Self::Text => text.lines().filter_map(parse_text_line).collect(),
+            Self::Cue => parse_cue(text),
+            Self::M3u => parse_m3u(text),
+            Self::Rekordbox => parse_rekordbox(text)?,
+        };
+        normalize_tracks(tracks)
+    }
+}
+
+/// Decode an uploaded tracklist: UTF-8, or UTF-16 with a byte order mark as rekordbox
+/// writes its TXT exports
+pub fn decode_tracklist(bytes: &[u8]) -> Result<String, TracklistError> {
+    let utf16 = |bytes: &[u8], from_bytes: fn([u8; 2]) -> u16| {
+        let units = bytes.chunks_exact(2).map(|pair| from_bytes([pair[0], pair[1]]));
+        char::decode_utf16(units)
+            .collect::<Result<String, _>>()
+            .map_err(|_| TracklistError::Invalid("file is not valid UTF-16".to_string()))
+    };
+    match bytes {
+        [0xFF, 0xFE, rest @ ..] => utf16(rest, u16::from_le_bytes),
+        [0xFE, 0xFF, rest @ ..] => utf16(rest, u16::from_be_bytes),
+        [0xEF, 0xBB, 0xBF, rest @ ..] => String::from_utf8(rest.to_vec())
+            .map_err(|_| TracklistError::Invalid("file is not valid UTF-8".to_string())),
+        _ => String::from_utf8(bytes.to_vec())
+            .map_err(|_| TracklistError::Invalid("file is not valid UTF-8".to_string())),
+    }
+}
+
+/// Trim and number the tracks and check that they are in order
+pub fn normalize_tracks(tracks: Vec<Track>) -> Result<Vec<Track>, TracklistError> {
+    if tracks.is_empty() {
+        return Err(TracklistError::Invalid("tracklist has no tracks".to_string()));
+    }
+    if tracks.len() > MAX_TRACKS {
+        return Err(TracklistError::Invalid(format!("tracklist has more than {} tracks", MAX_TRACKS)));
+    }
+
+    let mut previous_offset: Option<f64> = None;
+    let mut normalized = Vec::with_capacity(tracks.len());
+    for (index, track) in tracks.into_iter().enumerate() {
+        let position = index as i64 + 1;
+        let title = track.title.trim().to_string();
+        if title.is_empty() {
+            return Err(TracklistError::Invalid(format!("track {} has no title", position)));
+        }
+        if let Some(offset) = track.offset_seconds {
+            if !(0.0..=MAX_TRACK_OFFSET_SECONDS).contains(&offset) {
+                return Err(TracklistError::Invalid(format!("track {} has an invalid start time", position)));
+            }
+            if previous_offset.is_some_and(|previous| offset < previous) {
+                return Err(TracklistError::Invalid(format!("track {} starts before the track before it", position)));
+            }
+            previous_offset = Some(offset);
+        }
+        normalized.push(Track {
+            position,
+            offset_seconds: track.offset_seconds,
+            artist: track.artist.map(|artist| artist.trim().to_string()).filter(|artist| !artist.is_empty()),
+            title,
+        });
+    }
+    Ok(normalized)
+}
+
+/// Plain text tracklist for emails and archives, one "1. [12:40] Artist - Title" per line
+pub fn format_tracklist(tracks: &[Track]) -> String {
+    let mut text = String::new();
+    for track in tracks {
+        let time = track
+            .offset_seconds
+            .and_then(|offset| chrono::TimeDelta::try_seconds(offset as i64))
+            .map(|offset| format!("[{}] ", crate::utils::format_duration(offset)))
+            .unwrap_or_default();
+        text.push_str(&format!("{:>2}. {}{}\n", track.position, time, track.display()));
+    }
+    text
+}
+
+/// A line of a pasted tracklist. Numbering and a start time may come in either order
+/// before the track: "3. 12:40 Artist - Title", "[1:02:03] Artist - Title", "Artist - Title".
+fn parse_text_line(line: &str) -> Option<Track> {
+    let mut rest = line.trim();
+    if rest.is_empty() || rest.starts_with('#') || rest.starts_with("//") {
+        return None;
+    }
+
+    let mut offset = None;
+    for _ in 0..2 {
+        if offset.is_none() {
+            if let Some((seconds, after)) = leading_time(rest) {
+                offset = Some(seconds);
+                rest = after;
+                continue;
+            }
+        }
+        if let Some(after) = leading_number(rest) {
+            rest = after;
+        }
+    }
+
+    let rest = rest.trim_start_matches([' ', '\t', '-', '–', '—', '|']).trim();
+    if rest.is_empty() {
+        return None;
+    }
+    let (artist, title) = split_artist_title(rest);
+    Some(Track::new(artist, title, offset))
+}
+
+/// `m:ss` or `h:mm:ss`, optionally in brackets, followed by whitespace or the end of the line
+fn leading_time(text: &str) -> Option<(f64, &str)> {
+    let (inner, bracketed) = match text.strip_prefix(['[', '(']) {
+        Some(inner) => (inner, true),
+        None => (text, false),
+    };
+    let end = inner.find(|c: char| !c.is_ascii_digit() && c != ':').unwrap_or(inner.len());
+    let parts: Vec<&str> = inner[..end].split(':').collect();
+    if parts.len() < 2 || parts.len() > 3 || parts[0].is_empty() || parts[0].len() > 3 || parts[1..].iter().any(|part| part.len() != 2) {
+        return None;
+    }
+    let values: Vec<u64> = parts.iter().map(|part| part.parse().ok()).collect::<Option<_>>()?;
+    if values[1..].iter().any(|&value| value >= 60) {
+        return None;
+    }
+    let seconds = values.iter().fold(0, |total, value| total * 60 + value) as f64;
+
+    let mut after = &inner[end..];
+    if bracketed {
+        after = after.strip_prefix([']', ')'])?;
+    }
+    if !after.is_empty() && !after.starts_with(char::is_whitespace) {
+        return None;
+    }
+    Some((seconds, after.trim_start()))
+}
+
+/// "3." or "3)" followed by whitespace
+fn leading_number(text: &str) -> Option<&str> {
+    let end = text.find(|c: char| !c.is_ascii_digit())?;
+    if end == 0 || end > 3 {
+        return None;
+    }
+    let after = text[end..].strip_prefix(['.', ')'])?;
+    if !after.is_empty() && !after.starts_with(char::is_whitespace) {
+        return None;
+    }
+    Some(after.trim_start())
+}
+
+fn split_artist_title(text: &str) -> (Option<&str>, &str) {
+    [" - ", " – ", " — "]
+        .iter()
+        .filter_map(|separator| text.find(separator).map(|at| (at, separator.len())))
+        .min()
+        .map(|(at, len)| (Some(text[..at].trim()), text[at + len..].trim()))
+        .unwrap_or((None, text))
+}
+
+fn parse_cue(text: &str) -> Vec<Track> {
+    let unquote = |value: &str| value.trim().trim_matches('"').to_string();
+    let mut tracks: Vec<Track> = Vec::new();
+    let mut current: Option<Track> = None;
+    let mut album_performer: Option<String> = None;
+
+    for line in text.lines() {
+        let line = line.trim();
+        let (command, value) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        match command.to_ascii_uppercase().as_str() {
+            "TRACK" => {
+                tracks.extend(current.take());
+                current = Some(Track::new(None, "", None));
+            }
+            "TITLE" => {
+                if let Some(track) = current.as_mut() {
+                    track.title = unquote(value);
+                }
+            }
+            "PERFORMER" => match current.as_mut() {
+                Some(track) => track.artist = Some(unquote(value)),
+                None => album_performer = Some(unquote(value)),
+            },
+            "INDEX" => {
+                let mut fields = value.split_whitespace();
+                if let (Some(track), Some("01"), Some(time)) = (current.as_mut(), fields.next(), fields.next()) {
+                    track.offset_seconds = crate::utils::parse_cue_timestamp(time);
+                }
+            }
+            _ => {}
+        }
+    }
+    tracks.extend(current);
+
+    for track in &mut tracks {
+        if track.artist.is_none() {
+            track.artist = album_performer.clone();
+        }
+    }
+    tracks
+}
+
+fn parse_m3u(text: &str) -> Vec<Track> {
+    let mut tracks = Vec::new();
+    let mut pending: Option<Track> = None;
+
+    for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        if let Some(info) = line.strip_prefix("#EXTINF:") {
+            // "#EXTINF:<seconds> <attributes>,Artist - Title"
+            let display = info.split_once(',').map(|(_, display)| display.trim()).unwrap_or("");
+            let (artist, title) = split_artist_title(display);
+            pending = Some(Track::new(artist, title, None));
+        } else if !line.starts_with('#') {
+            let track = match pending.take() {
+                Some(track) if !track.title.is_empty() => track,
+                _ => {
+                    // Only a path: the file name usually reads "Artist - Title.mp3"
+                    let file = line.rsplit(['/', '\\']).next().unwrap_or(line);
+                    let stem = file.rsplit_once('.').map(|(stem, _)| stem).unwrap_or(file);
+                    let (artist, title) = split_artist_title(stem);
+                    Track::new(artist, title, None)
+                }
+            };
+            tracks.push(track);
+        }
+    }
+    tracks
+}
+
+fn parse_rekordbox(text: &str) -> Result<Vec<Track>, TracklistError> {
+    let mut lines = text.lines().filter(|line| !line.trim().is_empty());
+    let header: Vec<&str> = lines.next().unwrap_or("").split('\t').map(str::trim).collect();
+    let column = |names: &[&str]| header.iter().position(|field| names.contains(field));
+    let title_column = column(&["Track Title", "Title"])
+        .ok_or_else(|| TracklistError::Invalid("rekordbox export has no Track Title column".to_string()))?;
+    let artist_column = column(&["Artist"]);
+
+    Ok(lines
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split('\t').map(str::trim).collect();
+            let title = fields.get(title_column).copied().filter(|title| !title.is_empty())?;
+            let artist = artist_column.and_then(|column| fields.get(column).copied());
+            Some(Track::new(artist, title, None))
+        })
+        .collect())
+}
+
+/// Body of `PUT /api/sessions/:id/tracklist`: either the tracks themselves or a tracklist
+/// as text in one of the `TracklistFormat`s
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SubmitTracklistRequest {
+    #[serde(default)]
+    pub tracks: Vec<Track>,
+    pub text: Option<String>,
+    #[serde(default)]
+    pub format: TracklistFormat,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TracklistResponse {
+    pub session_id: String,
+    pub dj_name: String,
+    pub session_started_at: DateTime<Utc>,
+    pub updated_at: Option<DateTime<Utc>>, // None until a tracklist is submitted
+    pub tracks: Vec<Track>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum TracklistError {
+    #[error("Session not found")]
+    SessionNotFound,
+    #[error("Session has no tracklist")]
+    NoTracklist,
+    #[error("Tracklist has no start times")]
+    NoStartTimes,
+    #[error("Invalid tracklist: {0}")]
+    Invalid(String),
+    #[error("Unknown tracklist format '{0}', expected auto, text, cue, m3u or rekordbox")]
+    UnknownFormat(String),
+}
@@ -0,0 +1,48 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Default and upper bound for `?points=` on `GET /api/sessions/:id/waveform`
+pub const DEFAULT_WAVEFORM_POINTS: usize = 1000;
+pub const MAX_WAVEFORM_POINTS: usize = 10_000;
+
+/// Waveform of the recording a session is linked to. It covers the whole recording, which
+/// can hold several sets; `slots` says where each of them starts and ends.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionWaveformResponse {
+    pub session_id: String,
+    pub recorder_id: String,
+    pub recorder_session_id: String,
+    pub recording_started_at: DateTime<Utc>,
+    pub duration_seconds: f64,
+    pub seconds_per_peak: f64,
+    pub peaks: Vec<WaveformPeak>,
+    pub slots: Vec<WaveformSlot>,
+}
+
+/// Lowest and highest sample in a stretch of audio, from -1.0 to 1.0
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WaveformPeak {
+    pub min: f32,
+    pub max: f32,
+}
+
+/// A set within the recording, in seconds from its start
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WaveformSlot {
+    pub session_id: String,
+    pub dj_name: String,
+    pub start_seconds: f64,
+    pub end_seconds: Option<f64>, // None while the set is running
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum WaveformError {
+    #[error("Session not found")]
+    SessionNotFound,
+    #[error("Session is not linked to a recording")]
+    NoRecording,
+    #[error("Recording has no waveform")]
+    NoWaveform,
+    #[error("Invalid waveform data: {0}")]
+    Invalid(String),
+}
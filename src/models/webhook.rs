@@ -0,0 +1,180 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// Headers sent with every delivery, next to the `sha256=<hex>` signature in
+/// `WEBHOOK_SIGNATURE_HEADER`, which is computed the same way as for incoming recorder webhooks
+pub const WEBHOOK_EVENT_HEADER: &str = "x-slotify-event";
+pub const WEBHOOK_DELIVERY_HEADER: &str = "x-slotify-delivery";
+
+/// Domain events third parties can subscribe to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum WebhookEventType {
+    #[serde(rename = "dj.registered")]
+    DjRegistered,
+    #[serde(rename = "lottery.draw")]
+    Draw,
+    #[serde(rename = "slot.started")]
+    SlotStarted,
+    #[serde(rename = "slot.ended")]
+    SlotEnded,
+    #[serde(rename = "recording.ready")]
+    RecordingReady,
+    #[serde(rename = "event.ended")]
+    EventEnded,
+    #[serde(rename = "ping")]
+    Ping, // Sent on request to test a subscription; never subscribed to
+}
+
+impl WebhookEventType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::DjRegistered => "dj.registered",
+            Self::Draw => "lottery.draw",
+            Self::SlotStarted => "slot.started",
+            Self::SlotEnded => "slot.ended",
+            Self::RecordingReady => "recording.ready",
+            Self::EventEnded => "event.ended",
+            Self::Ping => "ping",
+        }
+    }
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct WebhookSubscription {
+    pub id: String,
+    pub url: String,
+    pub secret: String,
+    pub event_types: String, // JSON Vec<WebhookEventType>; empty for all
+    pub description: Option<String>,
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+impl WebhookSubscription {
+    pub fn event_types(&self) -> Vec<WebhookEventType> {
+        serde_json::from_str(&self.event_types).unwrap_or_default()
+    }
+
+    pub fn wants(&self, event_type: WebhookEventType) -> bool {
+        let event_types = self.event_types();
+        event_types.is_empty() || event_types.contains(&event_type)
+    }
+}
+
+/// Body of `POST /api/admin/webhooks`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CreateWebhookRequest {
+    pub url: String,
+    pub secret: Option<String>, // Generated when omitted
+    #[serde(default)]
+    pub event_types: Vec<WebhookEventType>, // Empty for all
+    pub description: Option<String>,
+}
+
+/// Body of `PUT /api/admin/webhooks/:id`; omitted fields stay as they are
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UpdateWebhookRequest {
+    pub url: Option<String>,
+    pub event_types: Option<Vec<WebhookEventType>>,
+    pub description: Option<String>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookSubscriptionResponse {
+    pub id: String,
+    pub url: String,
+    pub event_types: Vec<WebhookEventType>,
+    pub description: Option<String>,
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>, // Only in the answer to creating the subscription
+}
+
+impl From<WebhookSubscription> for WebhookSubscriptionResponse {
+    fn from(subscription: WebhookSubscription) -> Self {
+        Self {
+            event_types: subscription.event_types(),
+            id: subscription.id,
+            url: subscription.url,
+            description: subscription.description,
+            enabled: subscription.enabled,
+            created_at: subscription.created_at,
+            secret: None,
+        }
+    }
+}
+
+/// What every delivery carries; `id` is shared by the deliveries of one event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookEnvelope {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub event_type: WebhookEventType,
+    pub created_at: DateTime<Utc>,
+    pub data: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct WebhookDelivery {
+    pub id: String,
+    pub subscription_id: String,
+    pub event_type: String,
+    pub payload: String,
+    pub status: String, // pending, delivered or failed
+    pub attempts: i64,
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    pub last_attempt_at: Option<DateTime<Utc>>,
+    pub response_status: Option<i64>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+/// Retry policy for deliveries
+#[derive(Clone, Debug)]
+pub struct WebhookConfig {
+    pub max_attempts: i64,
+    pub retry_seconds: i64, // First retry delay; doubled after every failed attempt
+    pub timeout_seconds: u64,
+}
+
+/// Retries never wait longer than a day, however large `WEBHOOK_RETRY_SECONDS` is
+pub const MAX_RETRY_DELAY_SECONDS: i64 = 24 * 60 * 60;
+
+impl WebhookConfig {
+    /// Wait before the next try after `attempts` failed ones
+    pub fn retry_delay(&self, attempts: i64) -> chrono::Duration {
+        let seconds = self.retry_seconds.max(0).saturating_mul(1 << (attempts - 1).clamp(0, 16));
+        chrono::Duration::try_seconds(seconds.min(MAX_RETRY_DELAY_SECONDS)).unwrap_or_else(chrono::Duration::zero)
+    }
+
+    /// How long an attempt holds its delivery against concurrent ones: twice the request
+    /// timeout, at most a day
+    pub fn attempt_lease(&self) -> chrono::Duration {
+        let seconds = i64::try_from(self.timeout_seconds).unwrap_or(i64::MAX).saturating_mul(2);
+        chrono::Duration::try_seconds(seconds.min(MAX_RETRY_DELAY_SECONDS)).unwrap_or_else(chrono::Duration::zero)
+    }
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 6,
+            retry_seconds: 30,
+            timeout_seconds: 10,
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum WebhookError {
+    #[error("Webhook subscription not found")]
+    NotFound,
+    #[error("Invalid webhook URL: {0}")]
+    InvalidUrl(String),
+    #[error("Subscriptions can't include ping")]
+    PingSubscription,
+}
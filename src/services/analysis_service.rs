@@ -0,0 +1,262 @@
+use crate::audio::{analyze_recording, SilenceSettings};
+use crate::models::{
+    AnalysisError, AppState, RecordingAnalysis, RecordingAnalysisResponse, SilenceSegment,
+};
+use crate::services::{SessionRecorderConfig, SessionRecorderService, SessionService};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sqlx::{FromRow, Row, SqlitePool};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+const ANALYSIS_COLUMNS: &str = "a.session_id, a.analyzed_at, a.duration_seconds, a.integrated_lufs, a.true_peak_dbtp, \
+                                a.clipping_ratio, a.silent_seconds, a.silence, a.flags, a.flagged";
+
+/// Measures loudness, true peak, clipping and silence of each session's set and holds back
+/// the download link of sets that fail the configured thresholds
+pub struct AnalysisService {
+    db: SqlitePool,
+    app_state: Arc<AppState>,
+}
+
+impl AnalysisService {
+    pub fn new(app_state: Arc<AppState>) -> Self {
+        Self {
+            db: app_state.db.clone(),
+            app_state,
+        }
+    }
+
+    /// Decode the session's set and store the result, replacing an earlier analysis. A local
+    /// per-DJ file is analysed whole; otherwise the slot is cut out of the linked recording.
+    pub async fn analyze_session(&self, session_id: &str) -> Result<RecordingAnalysisResponse> {
+        self.analyze(session_id, None).await
+    }
+
+    /// `analyze_session`, cutting the slot out of `recording` if the linked recorder recording
+    /// was already fetched instead of downloading it again
+    async fn analyze(&self, session_id: &str, recording: Option<&Path>) -> Result<RecordingAnalysisResponse> {
+        let row = sqlx::query(
+            r#"
+            SELECT s.started_at, s.ended_at, s.file_path, s.recorder_id, s.recorder_session_id,
+                   s.recorder_flac_url, s.recorder_ogg_url, r.start_time AS recorder_start_time
+            FROM sessions s
+            LEFT JOIN recorder_sessions r ON r.recorder_id = s.recorder_id AND r.id = s.recorder_session_id
+            WHERE s.id = ?
+            "#,
+        )
+        .bind(session_id)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or(AnalysisError::SessionNotFound)?;
+
+        // Keeps a downloaded recording alive until it is analysed
+        let mut _download: Option<tempfile::TempDir> = None;
+
+        let local_file = row
+            .get::<Option<String>, _>("file_path")
+            .map(PathBuf::from)
+            .filter(|path| path.is_file());
+        let (source, from_ms, to_ms) = match local_file {
+            Some(path) => (path, 0, None),
+            None => {
+                let (path, recording_started_at) = match recording {
+                    Some(path) => (path.to_path_buf(), self.recording_start(&row).await?),
+                    None => {
+                        let (dir, path, recording_started_at) = self.download_recording(&row).await?;
+                        _download = Some(dir);
+                        (path, recording_started_at)
+                    }
+                };
+                let started_at: DateTime<Utc> = row.get("started_at");
+                let ended_at: Option<DateTime<Utc>> = row.get("ended_at");
+                let offset = |at: DateTime<Utc>| (at - recording_started_at).num_milliseconds().max(0) as u64;
+                (path, offset(started_at), ended_at.map(offset))
+            }
+        };
+
+        let config = self.app_state.config.analysis_config.clone();
+        let silence = SilenceSettings {
+            threshold_dbfs: config.silence_threshold_dbfs,
+            min_seconds: config.min_silence_seconds,
+        };
+        let report = tokio::task::spawn_blocking(move || analyze_recording(&source, from_ms, to_ms, silence)).await??;
+
+        let flags = config.flags(&report);
+        let silence: Vec<SilenceSegment> = report.silence.iter().copied().map(SilenceSegment::from).collect();
+        sqlx::query(
+            r#"
+            INSERT INTO recording_analyses (session_id, analyzed_at, duration_seconds, integrated_lufs, true_peak_dbtp,
+                                            clipping_ratio, silent_seconds, silence, flags, flagged)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT(session_id) DO UPDATE SET
+                analyzed_at = excluded.analyzed_at, duration_seconds = excluded.duration_seconds,
+                integrated_lufs = excluded.integrated_lufs, true_peak_dbtp = excluded.true_peak_dbtp,
+                clipping_ratio = excluded.clipping_ratio, silent_seconds = excluded.silent_seconds,
+                silence = excluded.silence, flags = excluded.flags, flagged = excluded.flagged
+            "#,
+        )
+        .bind(session_id)
+        .bind(Utc::now())
+        .bind(report.duration_seconds)
+        .bind(report.integrated_lufs)
+        .bind(report.true_peak_dbtp)
+        .bind(report.clipping_ratio)
+        .bind(report.silent_seconds())
+        .bind(serde_json::to_string(&silence)?)
+        .bind(serde_json::to_string(&flags)?)
+        .bind(!flags.is_empty())
+        .execute(&self.db)
+        .await?;
+
+        if flags.is_empty() {
+            tracing::info!("Recording of session {} passed analysis", session_id);
+        } else {
+            let reasons: Vec<&str> = flags.iter().map(|flag| flag.description()).collect();
+            tracing::warn!("Recording of session {} is flagged: {}", session_id, reasons.join(", "));
+        }
+
+        self.get_analysis(session_id).await?.ok_or_else(|| AnalysisError::SessionNotFound.into())
+    }
+
+    pub async fn get_analysis(&self, session_id: &str) -> Result<Option<RecordingAnalysisResponse>> {
+        let rows = self.query_analyses("WHERE a.session_id = ?", Some(session_id)).await?;
+        Ok(rows.into_iter().next())
+    }
+
+    /// Newest sets first
+    pub async fn list_analyses(&self, flagged_only: bool) -> Result<Vec<RecordingAnalysisResponse>> {
+        let filter = if flagged_only { "WHERE a.flagged = TRUE" } else { "" };
+        self.query_analyses(filter, None).await
+    }
+
+    async fn query_analyses(&self, filter: &str, session_id: Option<&str>) -> Result<Vec<RecordingAnalysisResponse>> {
+        let sql = format!(
+            r#"
+            SELECT {}, d.name AS dj_name, s.started_at AS session_started_at, s.delivery_held_at
+            FROM recording_analyses a
+            JOIN sessions s ON s.id = a.session_id
+            JOIN djs d ON d.id = s.dj_id
+            {}
+            ORDER BY s.started_at DESC
+            "#,
+            ANALYSIS_COLUMNS, filter
+        );
+        let mut query = sqlx::query(&sql);
+        if let Some(session_id) = session_id {
+            query = query.bind(session_id);
+        }
+
+        let mut analyses = Vec::new();
+        for row in query.fetch_all(&self.db).await? {
+            let analysis = RecordingAnalysis::from_row(&row)?;
+            let held = row.get::<Option<DateTime<Utc>>, _>("delivery_held_at").is_some();
+            analyses.push(analysis.to_response(row.get("dj_name"), row.get("session_started_at"), held));
+        }
+        Ok(analyses)
+    }
+
+    /// Analyse a recording about to be delivered, using `recording` if the linked recorder
+    /// recording was already fetched. Returns `true` if the download link has to wait for an
+    /// admin; a failed analysis doesn't stop the delivery.
+    pub async fn check_delivery(&self, session_id: &str, recording: Option<&Path>) -> bool {
+        let config = &self.app_state.config.analysis_config;
+        if !config.enabled {
+            return false;
+        }
+        let analysis = match self.analyze(session_id, recording).await {
+            Ok(analysis) => analysis,
+            Err(e) => {
+                tracing::warn!("Could not analyse recording of session {}: {}", session_id, e);
+                return false;
+            }
+        };
+        if !analysis.flagged || !config.hold_flagged {
+            return false;
+        }
+
+        let held = sqlx::query("UPDATE sessions SET delivery_held_at = ? WHERE id = ?")
+            .bind(Utc::now())
+            .bind(session_id)
+            .execute(&self.db)
+            .await;
+        match held {
+            Ok(_) => {
+                tracing::warn!("Download link for session {} is held until an admin releases it", session_id);
+                true
+            }
+            Err(e) => {
+                tracing::error!("Could not hold delivery of session {}: {}", session_id, e);
+                false
+            }
+        }
+    }
+
+    /// Issue and send a held download link after all; returns whether the DJ had an address
+    /// to send to
+    pub async fn release_delivery(&self, session_id: &str) -> Result<bool> {
+        let released = sqlx::query(
+            "UPDATE sessions SET delivery_held_at = NULL WHERE id = ? AND delivery_held_at IS NOT NULL"
+        )
+        .bind(session_id)
+        .execute(&self.db)
+        .await?;
+        if released.rows_affected() == 0 {
+            let exists = sqlx::query("SELECT id FROM sessions WHERE id = ?")
+                .bind(session_id)
+                .fetch_optional(&self.db)
+                .await?
+                .is_some();
+            return Err(if exists { AnalysisError::NotHeld } else { AnalysisError::SessionNotFound }.into());
+        }
+
+        tracing::info!("Delivery of session {} released", session_id);
+        let session_service = SessionService::new(self.app_state.clone());
+        session_service.issue_delivery_link(session_id).await?;
+        session_service.send_download_email(session_id).await
+    }
+
+    /// Fetch the linked recorder recording into a temporary directory, with the time it started
+    async fn download_recording(&self, row: &sqlx::sqlite::SqliteRow) -> Result<(tempfile::TempDir, PathBuf, DateTime<Utc>)> {
+        let settings = &self.app_state.config.session_recorder_config;
+        let (Some(recorder_id), Some(recorder_session_id)) = (
+            row.get::<Option<String>, _>("recorder_id"),
+            row.get::<Option<String>, _>("recorder_session_id"),
+        ) else {
+            return Err(AnalysisError::NoRecording.into());
+        };
+        let filename = match (row.get::<Option<String>, _>("recorder_flac_url"), row.get::<Option<String>, _>("recorder_ogg_url")) {
+            (Some(_), _) => "data.flac",
+            (None, Some(_)) => "data.ogg",
+            (None, None) => return Err(AnalysisError::NoRecording.into()),
+        };
+        if !settings.enabled {
+            return Err(AnalysisError::NoRecording.into());
+        }
+
+        let recorder = SessionRecorderService::new(SessionRecorderConfig::from(settings)).await?;
+        let recording_started_at = self.recording_start(row).await?;
+
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join(filename);
+        recorder.download_session_file(&recorder_id, &recorder_session_id, filename, &path).await?;
+        Ok((dir, path, recording_started_at))
+    }
+
+    /// When the linked recorder recording started, asking the recorder if it isn't indexed
+    async fn recording_start(&self, row: &sqlx::sqlite::SqliteRow) -> Result<DateTime<Utc>> {
+        if let Some(start) = row.get::<Option<DateTime<Utc>>, _>("recorder_start_time") {
+            return Ok(start);
+        }
+        let (Some(recorder_id), Some(recorder_session_id)) = (
+            row.get::<Option<String>, _>("recorder_id"),
+            row.get::<Option<String>, _>("recorder_session_id"),
+        ) else {
+            return Err(AnalysisError::NoRecording.into());
+        };
+
+        let settings = &self.app_state.config.session_recorder_config;
+        let recorder = SessionRecorderService::new(SessionRecorderConfig::from(settings)).await?;
+        Ok(recorder.get_session_details(&recorder_id, &recorder_session_id).await?.start_time)
+    }
+}
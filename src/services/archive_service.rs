@@ -0,0 +1,333 @@
+use crate::models::{event_session::DEFAULT_EVENT_NAME, format_tracklist, ArchiveError, ArchiveFormat, ArchiveTimetableEntry, AppState, EventSession, Track};
+use crate::services::{storage_from_config, SessionRecorderConfig, SessionRecorderService, StorageStream, TracklistService};
+use crate::utils::{csv_field, cue_text, sanitize_filename, write_cue_track, ArchiveWriter};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sqlx::{Row, SqlitePool};
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::sync::Arc;
+use tokio::io::AsyncWrite;
+
+/// Bundles everything recorded at an event into one ZIP or tar: the recordings, the
+/// timetable as JSON and CSV, the DJs' tracklists, a cue sheet over the whole night and a README
+pub struct ArchiveService {
+    db: SqlitePool,
+    app_state: Arc<AppState>,
+}
+
+/// An event's archive, worked out but not written yet. Checking the event up front lets the
+/// API answer 404 before it starts streaming.
+pub struct EventArchive {
+    pub event: EventSession,
+    pub name: String, // Top level folder inside the archive and the download's file name
+    pub timetable: Vec<ArchiveTimetableEntry>,
+    tracklists: HashMap<String, Vec<Track>>, // By session, for sets that have one
+    recordings: Vec<ArchiveRecording>,
+    app_state: Arc<AppState>,
+}
+
+/// A file under `recordings/`; a recorder session spanning several sets is included once
+struct ArchiveRecording {
+    path: String,
+    source: RecordingSource,
+}
+
+enum RecordingSource {
+    Storage(String),
+    Recorder { recorder_id: String, session_id: String, filename: String },
+}
+
+impl ArchiveService {
+    pub fn new(app_state: Arc<AppState>) -> Self {
+        Self {
+            db: app_state.db.clone(),
+            app_state,
+        }
+    }
+
+    pub async fn prepare(&self, event_id: &str) -> Result<EventArchive> {
+        let event = sqlx::query_as::<_, EventSession>("SELECT * FROM event_sessions WHERE id = ?")
+            .bind(event_id)
+            .fetch_optional(&self.db)
+            .await?
+            .ok_or(ArchiveError::EventNotFound)?;
+
+        let rows = sqlx::query(
+            r#"
+            SELECT s.id, s.started_at, s.ended_at, s.duration_minutes, s.storage_key,
+                   s.recorder_id, s.recorder_session_id, s.recorder_flac_url,
+                   d.name, r.start_time AS recorder_start_time
+            FROM sessions s
+            JOIN djs d ON d.id = s.dj_id
+            LEFT JOIN recorder_sessions r ON r.recorder_id = s.recorder_id AND r.id = s.recorder_session_id
+            WHERE s.started_at >= ? AND (? IS NULL OR s.started_at <= ?)
+            ORDER BY s.started_at ASC
+            "#,
+        )
+        .bind(event.started_at)
+        .bind(event.ended_at)
+        .bind(event.ended_at)
+        .fetch_all(&self.db)
+        .await?;
+
+        let recorder_enabled = self.app_state.config.session_recorder_config.enabled;
+        let tracklist_service = TracklistService::new(self.app_state.clone());
+        let mut tracklists = HashMap::new();
+        let mut timetable = Vec::new();
+        let mut recordings: Vec<ArchiveRecording> = Vec::new();
+
+        for (index, row) in rows.iter().enumerate() {
+            let started_at: DateTime<Utc> = row.get("started_at");
+            let storage_key: Option<String> = row.get("storage_key");
+            let recorder_id: Option<String> = row.get("recorder_id");
+            let recorder_session_id: Option<String> = row.get("recorder_session_id");
+
+            let (recording, offset) = match (storage_key, recorder_id, recorder_session_id) {
+                (Some(key), _, _) => {
+                    let filename = key.rsplit('/').next().unwrap_or(&key).to_string();
+                    let path = format!("recordings/{}", filename);
+                    recordings.push(ArchiveRecording {
+                        path: path.clone(),
+                        source: RecordingSource::Storage(key),
+                    });
+                    (Some(path), Some(0.0))
+                }
+                (None, Some(recorder_id), Some(session_id)) if recorder_enabled => {
+                    let extension = if row.get::<Option<String>, _>("recorder_flac_url").is_some() { "flac" } else { "ogg" };
+                    let path = format!("recordings/{}_{}.{}", sanitize_filename(&recorder_id), sanitize_filename(&session_id), extension);
+                    if !recordings.iter().any(|r| r.path == path) {
+                        recordings.push(ArchiveRecording {
+                            path: path.clone(),
+                            source: RecordingSource::Recorder {
+                                recorder_id,
+                                session_id,
+                                filename: format!("data.{}", extension),
+                            },
+                        });
+                    }
+                    let offset = row
+                        .get::<Option<DateTime<Utc>>, _>("recorder_start_time")
+                        .map(|start| ((started_at - start).num_milliseconds() as f64 / 1000.0).max(0.0));
+                    (Some(path), offset)
+                }
+                _ => (None, None),
+            };
+
+            let session_id: String = row.get("id");
+            let tracks = tracklist_service.get_tracks(&session_id).await?;
+            if !tracks.is_empty() {
+                tracklists.insert(session_id.clone(), tracks);
+            }
+
+            timetable.push(ArchiveTimetableEntry {
+                position: index + 1,
+                session_id,
+                dj_name: row.get("name"),
+                started_at,
+                ended_at: row.get("ended_at"),
+                duration_minutes: row.get("duration_minutes"),
+                recording,
+                recording_offset_seconds: offset,
+            });
+        }
+
+        let name = format!(
+            "{}_{}",
+            sanitize_filename(event.name.as_deref().unwrap_or(DEFAULT_EVENT_NAME)).replace(' ', "_"),
+            event.started_at.format("%Y-%m-%d")
+        );
+
+        Ok(EventArchive {
+            event,
+            name,
+            timetable,
+            tracklists,
+            recordings,
+            app_state: self.app_state.clone(),
+        })
+    }
+}
+
+impl EventArchive {
+    pub fn filename(&self, format: ArchiveFormat) -> String {
+        format!("{}.{}", self.name, format.extension())
+    }
+
+    /// Stream the archive into `writer`. Recordings that can't be opened are left out and
+    /// listed in the README rather than failing the whole archive.
+    pub async fn write<W: AsyncWrite + Unpin>(&self, format: ArchiveFormat, writer: W) -> Result<W> {
+        let mut archive = ArchiveWriter::new(writer, format.kind(), Utc::now());
+        let mut missing = Vec::new();
+
+        for recording in &self.recordings {
+            match self.open(&recording.source).await {
+                Ok(stream) => {
+                    let name = format!("{}/{}", self.name, recording.path);
+                    archive.add_stream(&name, stream.size, stream.body).await?;
+                }
+                Err(e) => {
+                    tracing::warn!("Leaving {} out of the archive of event {}: {}", recording.path, self.event.id, e);
+                    missing.push(recording.path.clone());
+                }
+            }
+        }
+
+        let timetable: Vec<ArchiveTimetableEntry> = self
+            .timetable
+            .iter()
+            .cloned()
+            .map(|mut entry| {
+                if entry.recording.as_ref().is_some_and(|path| missing.contains(path)) {
+                    entry.recording = None;
+                    entry.recording_offset_seconds = None;
+                }
+                entry
+            })
+            .collect();
+
+        archive.add_bytes(&format!("{}/README.txt", self.name), self.readme(&timetable, &missing).as_bytes()).await?;
+        archive.add_bytes(&format!("{}/timetable.json", self.name), &serde_json::to_vec_pretty(&timetable)?).await?;
+        archive.add_bytes(&format!("{}/timetable.csv", self.name), timetable_csv(&timetable).as_bytes()).await?;
+        for entry in &timetable {
+            if let Some(tracks) = self.tracklists.get(&entry.session_id) {
+                let text = format!("{} ({} UTC)\n\n{}", entry.dj_name, slot_times(entry), format_tracklist(tracks));
+                archive.add_bytes(&format!("{}/{}", self.name, tracklist_path(entry)), text.as_bytes()).await?;
+            }
+        }
+        archive.add_bytes(&format!("{}/{}.cue", self.name, self.name), self.cue_sheet(&timetable).as_bytes()).await?;
+
+        archive.finish().await
+    }
+
+    async fn open(&self, source: &RecordingSource) -> Result<StorageStream> {
+        match source {
+            RecordingSource::Storage(key) => {
+                let storage = storage_from_config(&self.app_state.config.storage_config)?;
+                storage.stream(key, None).await
+            }
+            RecordingSource::Recorder { recorder_id, session_id, filename } => {
+                let config = SessionRecorderConfig::from(&self.app_state.config.session_recorder_config);
+                let recorder = SessionRecorderService::new(config).await?;
+                recorder.stream_session_file(recorder_id, session_id, filename, None).await
+            }
+        }
+    }
+
+    fn title(&self) -> String {
+        format!(
+            "{} {}",
+            self.event.name.as_deref().unwrap_or(DEFAULT_EVENT_NAME),
+            self.event.started_at.format("%Y-%m-%d")
+        )
+    }
+
+    fn readme(&self, timetable: &[ArchiveTimetableEntry], missing: &[String]) -> String {
+        let mut readme = String::new();
+        let _ = writeln!(readme, "{}", self.title());
+        let _ = writeln!(
+            readme,
+            "{} - {} UTC",
+            self.event.started_at.format("%Y-%m-%d %H:%M"),
+            self.event.ended_at.map(|t| t.format("%Y-%m-%d %H:%M").to_string()).unwrap_or_else(|| "still running".to_string())
+        );
+        let _ = writeln!(readme);
+        let _ = writeln!(readme, "- timetable.json, timetable.csv: who played when, and where each set starts in its recording");
+        let _ = writeln!(readme, "- {}.cue: cue sheet over all recordings, one track per set, or per song for tracklists with start times", self.name);
+        if !self.tracklists.is_empty() {
+            let _ = writeln!(readme, "- tracklists/: tracklists the DJs submitted");
+        }
+        let _ = writeln!(readme, "- recordings/: the audio files");
+        let _ = writeln!(readme);
+        let _ = writeln!(readme, "Timetable");
+        for entry in timetable {
+            let _ = writeln!(
+                readme,
+                "{:>3}. {}  {:<24} {}",
+                entry.position,
+                slot_times(entry),
+                entry.dj_name,
+                entry.recording.as_deref().unwrap_or("(not recorded)")
+            );
+        }
+        if !missing.is_empty() {
+            let _ = writeln!(readme);
+            let _ = writeln!(readme, "Recordings that could not be read when this archive was made:");
+            for path in missing {
+                let _ = writeln!(readme, "  {}", path);
+            }
+        }
+        let _ = writeln!(readme);
+        let _ = writeln!(readme, "Generated by Slotify on {} UTC", Utc::now().format("%Y-%m-%d %H:%M"));
+        readme
+    }
+
+    /// One track per recorded set, in the order they were played. Sets whose tracklist has
+    /// start times get one track per song instead.
+    fn cue_sheet(&self, timetable: &[ArchiveTimetableEntry]) -> String {
+        let mut cue = String::new();
+        let _ = writeln!(cue, "REM DATE {}", self.event.started_at.format("%Y-%m-%d"));
+        let _ = writeln!(cue, "PERFORMER \"Various Artists\"");
+        let _ = writeln!(cue, "TITLE \"{}\"", cue_text(&self.title()));
+
+        let mut current_file: Option<&str> = None;
+        let mut track = 0;
+        for entry in timetable {
+            let Some(recording) = entry.recording.as_deref() else {
+                continue;
+            };
+            if current_file != Some(recording) {
+                let _ = writeln!(cue, "FILE \"{}\" WAVE", cue_text(recording));
+                current_file = Some(recording);
+            }
+            let set_offset = entry.recording_offset_seconds.unwrap_or(0.0);
+            let songs: Vec<(&Track, f64)> = self
+                .tracklists
+                .get(&entry.session_id)
+                .map(|tracks| tracks.iter().filter_map(|t| t.offset_seconds.map(|offset| (t, offset))).collect())
+                .unwrap_or_default();
+            if songs.is_empty() {
+                track += 1;
+                let title = format!("{} ({} UTC)", entry.dj_name, slot_times(entry));
+                write_cue_track(&mut cue, track, &title, &entry.dj_name, set_offset);
+            }
+            for (song, offset) in songs {
+                track += 1;
+                let performer = song.artist.as_deref().unwrap_or(&entry.dj_name);
+                write_cue_track(&mut cue, track, &song.title, performer, set_offset + offset);
+            }
+        }
+        cue
+    }
+}
+
+/// `tracklists/03_DJ_Name.txt`
+fn tracklist_path(entry: &ArchiveTimetableEntry) -> String {
+    format!("tracklists/{:02}_{}.txt", entry.position, sanitize_filename(&entry.dj_name).replace(' ', "_"))
+}
+
+fn slot_times(entry: &ArchiveTimetableEntry) -> String {
+    match entry.ended_at {
+        Some(ended_at) => format!("{}-{}", entry.started_at.format("%H:%M"), ended_at.format("%H:%M")),
+        None => format!("{}-", entry.started_at.format("%H:%M")),
+    }
+}
+
+fn timetable_csv(timetable: &[ArchiveTimetableEntry]) -> String {
+    let mut csv = String::from("position,dj_name,started_at,ended_at,duration_minutes,recording,recording_offset_seconds\n");
+    for entry in timetable {
+        let fields = [
+            entry.position.to_string(),
+            entry.dj_name.clone(),
+            entry.started_at.to_rfc3339(),
+            entry.ended_at.map(|t| t.to_rfc3339()).unwrap_or_default(),
+            entry.duration_minutes.map(|d| d.to_string()).unwrap_or_default(),
+            entry.recording.clone().unwrap_or_default(),
+            entry.recording_offset_seconds.map(|o| format!("{:.3}", o)).unwrap_or_default(),
+        ];
+        let line: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+        csv.push_str(&line.join(","));
+        csv.push('\n');
+    }
+    csv
+}
@@ -0,0 +1,143 @@
+use crate::models::{
+    event_session::{Timetable, TimetableEntry, TimetableEntryStatus, DEFAULT_EVENT_NAME},
+    AppState, CalendarError, CalendarFeedResponse,
+};
+use crate::services::EventService;
+use crate::utils::{write_calendar, IcsEvent};
+use anyhow::Result;
+use chrono::{Duration, Utc};
+use sqlx::SqlitePool;
+use std::sync::Arc;
+
+/// How many of the latest events a DJ's feed covers
+const DJ_FEED_EVENTS: i64 = 20;
+
+/// iCalendar feeds of event timetables and of each DJ's slots. Feeds are built from the
+/// timetable on every request, so subscribed calendars follow queue moves and redraws.
+pub struct CalendarService {
+    db: SqlitePool,
+    app_state: Arc<AppState>,
+}
+
+impl CalendarService {
+    pub fn new(app_state: Arc<AppState>) -> Self {
+        Self {
+            db: app_state.db.clone(),
+            app_state,
+        }
+    }
+
+    /// The whole timetable of an event, one entry per set
+    pub async fn event_feed(&self, event_id: &str) -> Result<String> {
+        let timetable = EventService::new(self.app_state.clone())
+            .get_event_timetable(event_id)
+            .await?
+            .ok_or(CalendarError::EventNotFound)?;
+
+        let event_name = timetable.event_name.as_deref().unwrap_or(DEFAULT_EVENT_NAME);
+        let events: Vec<IcsEvent> = timetable
+            .entries
+            .iter()
+            .map(|entry| self.ics_event(&timetable, entry, entry_summary(entry)))
+            .collect();
+        Ok(self.write(event_name, &events))
+    }
+
+    /// Feed URL of a DJ
+    pub async fn dj_feed_url(&self, dj_id: &str) -> Result<CalendarFeedResponse> {
+        let exists = sqlx::query("SELECT id FROM djs WHERE id = ?")
+            .bind(dj_id)
+            .fetch_optional(&self.db)
+            .await?
+            .is_some();
+        if !exists {
+            return Err(CalendarError::DjNotFound.into());
+        }
+
+        let url = format!("{}/api/calendar/djs/{}.ics", self.app_state.config.download_config.base_url, dj_id);
+        let webcal_url = match url.split_once("://") {
+            Some((_, rest)) => format!("webcal://{}", rest),
+            None => url.clone(),
+        };
+        Ok(CalendarFeedResponse { url, webcal_url })
+    }
+
+    /// The DJ's slots across the latest events
+    pub async fn dj_feed(&self, dj_id: &str) -> Result<String> {
+        let dj_name: String = sqlx::query_scalar("SELECT name FROM djs WHERE id = ?")
+            .bind(dj_id)
+            .fetch_optional(&self.db)
+            .await?
+            .ok_or(CalendarError::DjNotFound)?;
+
+        let event_ids: Vec<String> = sqlx::query_scalar("SELECT id FROM event_sessions ORDER BY started_at DESC LIMIT ?")
+            .bind(DJ_FEED_EVENTS)
+            .fetch_all(&self.db)
+            .await?;
+
+        let event_service = EventService::new(self.app_state.clone());
+        let mut events = Vec::new();
+        for event_id in event_ids.iter().rev() {
+            let Some(timetable) = event_service.get_event_timetable(event_id).await? else {
+                continue;
+            };
+            let event_name = timetable.event_name.as_deref().unwrap_or(DEFAULT_EVENT_NAME);
+            for entry in timetable.entries.iter().filter(|entry| entry.dj_id == dj_id) {
+                events.push(self.ics_event(&timetable, entry, format!("Your set at {}", event_name)));
+            }
+        }
+        Ok(self.write(&format!("{} on Slotify", dj_name), &events))
+    }
+
+    fn ics_event(&self, timetable: &Timetable, entry: &TimetableEntry, summary: String) -> IcsEvent {
+        // Upcoming and running sets take the slot length; the slot after them starts then
+        let end = entry
+            .ended_at
+            .unwrap_or(entry.started_at + Duration::minutes(timetable.slot_duration_minutes as i64));
+
+        let status = match (&entry.status, entry.reserved_kind) {
+            (TimetableEntryStatus::Completed, _) => "Played",
+            (TimetableEntryStatus::InProgress, _) => "Playing now",
+            (TimetableEntryStatus::Upcoming, Some(_)) => "Booked",
+            (TimetableEntryStatus::Upcoming, None) => "Planned; moves with the queue",
+        };
+        let mut description = format!("Slot {} · {}", entry.position, status);
+        if let Some(label) = &entry.reserved_label {
+            description.push_str(&format!("\n{}", label));
+        }
+
+        // A DJ can play twice in a night; later sets are numbered, which unlike the position
+        // doesn't change when the queue moves
+        let set_number = timetable
+            .entries
+            .iter()
+            .filter(|other| other.dj_id == entry.dj_id && other.position <= entry.position)
+            .count();
+        let uid = match set_number {
+            0 | 1 => format!("{}-{}@slotify", timetable.event_id, entry.dj_id),
+            n => format!("{}-{}-{}@slotify", timetable.event_id, entry.dj_id, n),
+        };
+
+        IcsEvent {
+            uid,
+            summary,
+            description: Some(description),
+            location: self.app_state.config.calendar_config.location.clone(),
+            start: entry.started_at,
+            end,
+            tentative: entry.status == TimetableEntryStatus::Upcoming && entry.reserved_kind.is_none(),
+        }
+    }
+
+    fn write(&self, name: &str, events: &[IcsEvent]) -> String {
+        write_calendar(name, self.app_state.config.calendar_config.timezone, events, Utc::now())
+    }
+}
+
+/// `DJ Name`, or `DJ Name (Headliner)` for booked slots
+fn entry_summary(entry: &TimetableEntry) -> String {
+    match entry.reserved_kind {
+        Some(kind) => format!("{} ({})", entry.dj_name, kind.label()),
+        None => entry.dj_name.clone(),
+    }
+}
@@ -1,7 +1,8 @@
 use crate::models::{
     dj::{Dj, DjResponse, CreateDjRequest, UpdateDjRequest, DjPool, GuestRequest},
-    AppState,
+    AppState, WebhookEventType,
 };
+use crate::services::{LotteryService, WebhookService};
 use anyhow::Result;
 use sqlx::{SqlitePool, Row};
 use std::sync::Arc;
@@ -9,12 +10,14 @@ use uuid::Uuid;
 
 pub struct DjService {
     db: SqlitePool,
+    app_state: Arc<AppState>,
 }
 
 impl DjService {
     pub fn new(app_state: Arc<AppState>) -> Self {
         Self {
             db: app_state.db.clone(),
+            app_state,
         }
     }
 
@@ -37,12 +40,20 @@ impl DjService {
         .execute(&self.db)
         .await?;
 
+        WebhookService::new(self.app_state.clone())
+            .emit(WebhookEventType::DjRegistered, serde_json::json!({
+                "dj_id": dj.id,
+                "dj_name": dj.name,
+                "registered_at": dj.registered_at,
+            }))
+            .await;
+
         Ok(dj.into())
     }
 
     pub async fn get_all_djs(&self) -> Result<Vec<DjResponse>> {
         let djs = sqlx::query_as::<_, Dj>(
-            "SELECT id, name, email, registered_at, weight, is_active, position_in_queue FROM djs ORDER BY registered_at ASC"
+            "SELECT id, name, email, registered_at, weight, is_active, position_in_queue, keep_recording FROM djs ORDER BY registered_at ASC"
         )
         .fetch_all(&self.db)
         .await?;
@@ -52,7 +63,7 @@ impl DjService {
 
     pub async fn get_active_djs(&self) -> Result<Vec<DjResponse>> {
         let djs = sqlx::query_as::<_, Dj>(
-            "SELECT id, name, email, registered_at, weight, is_active, position_in_queue FROM djs WHERE is_active = true ORDER BY registered_at ASC"
+            "SELECT id, name, email, registered_at, weight, is_active, position_in_queue, keep_recording FROM djs WHERE is_active = true ORDER BY registered_at ASC"
         )
         .fetch_all(&self.db)
         .await?;
@@ -62,7 +73,7 @@ impl DjService {
 
     pub async fn get_dj_by_id(&self, id: &str) -> Result<Option<DjResponse>> {
         let dj = sqlx::query_as::<_, Dj>(
-            "SELECT id, name, email, registered_at, weight, is_active, position_in_queue FROM djs WHERE id = ?"
+            "SELECT id, name, email, registered_at, weight, is_active, position_in_queue, keep_recording FROM djs WHERE id = ?"
         )
         .bind(id)
         .fetch_optional(&self.db)
@@ -76,7 +87,8 @@ impl DjService {
         let mut _updates: Vec<String> = Vec::new();
         
         if request.name.is_some() || request.email.is_some() || request.weight.is_some() || 
-           request.is_active.is_some() || request.position_in_queue.is_some() {
+           request.is_active.is_some() || request.position_in_queue.is_some() ||
+           request.keep_recording.is_some() {
             
             if let Some(name) = &request.name {
                 sqlx::query("UPDATE djs SET name = ? WHERE id = ?")
@@ -110,9 +122,14 @@ impl DjService {
                     .await?;
             }
             
+            // Through the lottery, so reserved slots keep their positions
             if let Some(position) = request.position_in_queue {
-                sqlx::query("UPDATE djs SET position_in_queue = ? WHERE id = ?")
-                    .bind(position)
+                LotteryService::new(self.app_state.clone()).move_dj_position(id, position).await?;
+            }
+
+            if let Some(keep_recording) = request.keep_recording {
+                sqlx::query("UPDATE djs SET keep_recording = ? WHERE id = ?")
+                    .bind(keep_recording)
                     .bind(id)
                     .execute(&self.db)
                     .await?;
@@ -136,7 +153,7 @@ impl DjService {
         
         let current_dj = sqlx::query_as::<_, Dj>(
             r#"
-            SELECT d.id, d.name, d.email, d.registered_at, d.weight, d.is_active, d.position_in_queue
+            SELECT d.id, d.name, d.email, d.registered_at, d.weight, d.is_active, d.position_in_queue, d.keep_recording
             FROM djs d
             INNER JOIN sessions s ON d.id = s.dj_id
             WHERE s.ended_at IS NULL
@@ -148,7 +165,7 @@ impl DjService {
         .await?;
 
         let next_dj = sqlx::query_as::<_, Dj>(
-            "SELECT id, name, email, registered_at, weight, is_active, position_in_queue FROM djs WHERE position_in_queue = 1"
+            "SELECT id, name, email, registered_at, weight, is_active, position_in_queue, keep_recording FROM djs WHERE position_in_queue = 1"
         )
         .fetch_optional(&self.db)
         .await?;
@@ -0,0 +1,306 @@
+use crate::models::{
+    download::{
+        CreateDownloadTokenRequest, DownloadClient, DownloadError, DownloadLogEntry, DownloadToken,
+        DownloadTokenResponse, DOWNLOAD_CONTINUATION_MINUTES, MAX_LINK_TTL_DAYS,
+    },
+    storage::{ByteRange, StorageError},
+    AppState,
+};
+use crate::services::{storage_from_config, SessionRecorderConfig, SessionRecorderService, StorageStream};
+use crate::utils::{content_type_for, sanitize_filename};
+use anyhow::Result;
+use chrono::{DateTime, TimeDelta, Utc};
+use rand::RngCore;
+use sqlx::{Row, SqliteConnection, SqlitePool};
+use std::sync::Arc;
+
+/// A recording opened for `GET /dl/:token`
+pub struct DownloadFile {
+    pub filename: String,
+    pub content_type: &'static str,
+    pub partial: bool, // Answer with 206 and Content-Range
+    pub stream: StorageStream,
+}
+
+/// Issues the download links DJs get and serves them with expiry, download limits,
+/// revocation and an access log
+pub struct DownloadService {
+    db: SqlitePool,
+    app_state: Arc<AppState>,
+}
+
+impl DownloadService {
+    pub fn new(app_state: Arc<AppState>) -> Self {
+        Self {
+            db: app_state.db.clone(),
+            app_state,
+        }
+    }
+
+    /// New link for the session's DJ
+    pub async fn issue_token(&self, session_id: &str, request: CreateDownloadTokenRequest) -> Result<DownloadTokenResponse> {
+        let mut conn = self.db.acquire().await?;
+        self.issue_token_with(&mut conn, session_id, request).await
+    }
+
+    /// `issue_token` on the given connection, so callers can issue a link inside their transaction
+    pub(crate) async fn issue_token_with(
+        &self,
+        conn: &mut SqliteConnection,
+        session_id: &str,
+        request: CreateDownloadTokenRequest,
+    ) -> Result<DownloadTokenResponse> {
+        let dj_id: String = sqlx::query("SELECT dj_id FROM sessions WHERE id = ?")
+            .bind(session_id)
+            .fetch_optional(&mut *conn)
+            .await?
+            .ok_or(DownloadError::SessionNotFound)?
+            .get("dj_id");
+
+        let config = &self.app_state.config.download_config;
+        let ttl_days = request.expires_in_days.unwrap_or(config.link_ttl_days);
+        let now = Utc::now();
+        let expires_at = Some(ttl_days)
+            .filter(|days| (1..=MAX_LINK_TTL_DAYS).contains(days))
+            .and_then(TimeDelta::try_days)
+            .and_then(|ttl| now.checked_add_signed(ttl))
+            .ok_or(DownloadError::InvalidExpiry)?;
+
+        let mut secret = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut secret);
+
+        let token = DownloadToken {
+            token: hex::encode(secret),
+            session_id: session_id.to_string(),
+            dj_id,
+            created_at: now,
+            expires_at,
+            max_downloads: request.max_downloads.or(config.max_downloads),
+            download_count: 0,
+            revoked_at: None,
+        };
+
+        sqlx::query(
+            r#"
+            INSERT INTO download_tokens (token, session_id, dj_id, created_at, expires_at, max_downloads, download_count)
+            VALUES (?, ?, ?, ?, ?, ?, 0)
+            "#,
+        )
+        .bind(&token.token)
+        .bind(&token.session_id)
+        .bind(&token.dj_id)
+        .bind(token.created_at)
+        .bind(token.expires_at)
+        .bind(token.max_downloads)
+        .execute(&mut *conn)
+        .await?;
+
+        tracing::info!("Issued download link for session {} valid until {}", session_id, token.expires_at);
+        Ok(self.to_response(token))
+    }
+
+    pub fn token_url(&self, token: &str) -> String {
+        format!("{}/dl/{}", self.app_state.config.download_config.base_url, token)
+    }
+
+    pub async fn get_token(&self, token: &str) -> Result<Option<DownloadToken>> {
+        let token = sqlx::query_as::<_, DownloadToken>("SELECT * FROM download_tokens WHERE token = ?")
+            .bind(token)
+            .fetch_optional(&self.db)
+            .await?;
+
+        Ok(token)
+    }
+
+    pub async fn get_session_tokens(&self, session_id: &str) -> Result<Vec<DownloadTokenResponse>> {
+        let tokens = sqlx::query_as::<_, DownloadToken>(
+            "SELECT * FROM download_tokens WHERE session_id = ? ORDER BY created_at ASC",
+        )
+        .bind(session_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(tokens.into_iter().map(|token| self.to_response(token)).collect())
+    }
+
+    /// Returns `false` if there is no such link or it was already revoked
+    pub async fn revoke_token(&self, token: &str) -> Result<bool> {
+        let result = sqlx::query("UPDATE download_tokens SET revoked_at = ? WHERE token = ? AND revoked_at IS NULL")
+            .bind(Utc::now())
+            .bind(token)
+            .execute(&self.db)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Revoke every live link of a session; returns how many were revoked
+    pub async fn revoke_session_tokens(&self, session_id: &str) -> Result<u64> {
+        let result = sqlx::query("UPDATE download_tokens SET revoked_at = ? WHERE session_id = ? AND revoked_at IS NULL")
+            .bind(Utc::now())
+            .bind(session_id)
+            .execute(&self.db)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    pub async fn get_access_log(&self, token: &str) -> Result<Vec<DownloadLogEntry>> {
+        let entries = sqlx::query_as::<_, DownloadLogEntry>(
+            "SELECT * FROM download_log WHERE token = ? ORDER BY id ASC",
+        )
+        .bind(token)
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(entries)
+    }
+
+    /// Check the token and open its recording. Every attempt is logged, refused or not.
+    pub async fn open_download(&self, token: &str, range_header: Option<&str>, client: &DownloadClient) -> Result<DownloadFile> {
+        let result = self.try_open_download(token, range_header, client).await;
+
+        let (status, counted) = match &result {
+            Ok((file, counted)) => (if file.partial { 206 } else { 200 }, *counted),
+            Err(e) => (download_status(e), false),
+        };
+        sqlx::query(
+            r#"
+            INSERT INTO download_log (token, accessed_at, status, range_header, client_ip, user_agent, counted)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(token)
+        .bind(Utc::now())
+        .bind(status as i64)
+        .bind(range_header)
+        .bind(&client.ip)
+        .bind(&client.user_agent)
+        .bind(counted)
+        .execute(&self.db)
+        .await?;
+
+        result.map(|(file, _)| file)
+    }
+
+    /// The opened file and whether the request used up a download
+    async fn try_open_download(&self, token: &str, range_header: Option<&str>, client: &DownloadClient) -> Result<(DownloadFile, bool)> {
+        let download_token = self.get_token(token).await?.ok_or(DownloadError::NotFound)?;
+        download_token.check_usable(Utc::now())?;
+
+        let range = range_header.and_then(ByteRange::parse);
+        let (filename, stream) = self.open_session_file(&download_token.session_id, range).await?;
+
+        // Players fetch a file in many ranges. A later range is free only as the continuation
+        // of a download the same client started shortly before; anything else counts.
+        let continuation = stream.range.start > 0 && self.recently_counted(token, client).await?;
+        if !continuation {
+            let counted = sqlx::query(
+                r#"
+                UPDATE download_tokens SET download_count = download_count + 1
+                WHERE token = ? AND revoked_at IS NULL AND (max_downloads IS NULL OR download_count < max_downloads)
+                "#,
+            )
+            .bind(token)
+            .execute(&self.db)
+            .await?;
+            if counted.rows_affected() == 0 {
+                return Err(DownloadError::LimitReached.into());
+            }
+        }
+
+        let file = DownloadFile {
+            content_type: content_type_for(&filename),
+            filename,
+            partial: range.is_some(),
+            stream,
+        };
+        Ok((file, !continuation))
+    }
+
+    /// Whether the client used up a download of this link within the continuation window
+    async fn recently_counted(&self, token: &str, client: &DownloadClient) -> Result<bool> {
+        let Some(ip) = &client.ip else {
+            return Ok(false);
+        };
+        let since = Utc::now() - TimeDelta::minutes(DOWNLOAD_CONTINUATION_MINUTES);
+        let row = sqlx::query(
+            "SELECT 1 FROM download_log WHERE token = ? AND client_ip = ? AND counted AND accessed_at >= ? LIMIT 1",
+        )
+        .bind(token)
+        .bind(ip)
+        .bind(since)
+        .fetch_optional(&self.db)
+        .await?;
+
+        Ok(row.is_some())
+    }
+
+    /// The session's published file, or else its linked recorder recording
+    async fn open_session_file(&self, session_id: &str, range: Option<ByteRange>) -> Result<(String, StorageStream)> {
+        let row = sqlx::query(
+            r#"
+            SELECT s.storage_key, s.recorder_id, s.recorder_session_id, s.recorder_flac_url, s.started_at, d.name
+            FROM sessions s JOIN djs d ON d.id = s.dj_id
+            WHERE s.id = ?
+            "#,
+        )
+        .bind(session_id)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or(DownloadError::SessionNotFound)?;
+
+        if let Some(key) = row.get::<Option<String>, _>("storage_key") {
+            let storage = storage_from_config(&self.app_state.config.storage_config)?;
+            let stream = storage.stream(&key, range).await?;
+            let filename = key.rsplit('/').next().unwrap_or(&key).to_string();
+            return Ok((filename, stream));
+        }
+
+        let recorder_config = &self.app_state.config.session_recorder_config;
+        let recorder_id: Option<String> = row.get("recorder_id");
+        let recorder_session_id: Option<String> = row.get("recorder_session_id");
+        let (Some(recorder_id), Some(recorder_session_id), true) = (recorder_id, recorder_session_id, recorder_config.enabled) else {
+            return Err(DownloadError::NoFile.into());
+        };
+
+        let extension = if row.get::<Option<String>, _>("recorder_flac_url").is_some() { "flac" } else { "ogg" };
+        let recorder = SessionRecorderService::new(SessionRecorderConfig::from(recorder_config)).await?;
+        let stream = recorder
+            .stream_session_file(&recorder_id, &recorder_session_id, &format!("data.{}", extension), range)
+            .await?;
+
+        let started_at: DateTime<Utc> = row.get("started_at");
+        let filename = format!(
+            "{}_{}.{}",
+            sanitize_filename(&row.get::<String, _>("name")),
+            started_at.format("%Y%m%d"),
+            extension
+        );
+        Ok((filename, stream))
+    }
+
+    pub fn to_response(&self, token: DownloadToken) -> DownloadTokenResponse {
+        DownloadTokenResponse {
+            url: self.token_url(&token.token),
+            token,
+        }
+    }
+}
+
+/// HTTP status a failed download is answered (and logged) with
+pub fn download_status(error: &anyhow::Error) -> u16 {
+    if let Some(error) = error.downcast_ref::<DownloadError>() {
+        return match error {
+            DownloadError::NotFound | DownloadError::SessionNotFound | DownloadError::NoFile => 404,
+            DownloadError::Expired | DownloadError::Revoked => 410,
+            DownloadError::LimitReached => 403,
+            DownloadError::InvalidExpiry => 400,
+        };
+    }
+    match error.downcast_ref::<StorageError>() {
+        Some(StorageError::RangeNotSatisfiable { .. }) => 416,
+        Some(StorageError::NotFound(_)) => 404,
+        _ => 500,
+    }
+}
@@ -1,4 +1,4 @@
-use crate::models::{AppState, dj::GuestRequest};
+use crate::models::{AppState, DownloadTokenResponse, dj::GuestRequest};
 use anyhow::Result;
 use lettre::{
     message::{header::ContentType, Mailbox},
@@ -86,9 +86,17 @@ DJ Session Recorder System
         dj_email: &str,
         dj_name: &str,
         session_id: &str,
-        download_link: &str,
+        link: &DownloadTokenResponse,
+        tracklist: Option<&str>,
     ) -> Result<()> {
         let subject = format!("Your DJ Set Recording is Ready - {}", session_id);
+        let limit = match link.token.max_downloads {
+            Some(max) => format!("It can be used for {} download(s).", max),
+            None => "You can download the file as many times as needed within this period.".to_string(),
+        };
+        let tracklist = tracklist
+            .map(|tracklist| format!("\nTracklist\n\n{}", tracklist))
+            .unwrap_or_default();
         
         let body = format!(
             r#"Hello {},
@@ -98,8 +106,8 @@ Your DJ set recording is now ready for download!
 Session ID: {}
 Download Link: {}
 
-The link will be available for 30 days. You can download the file as many times as needed within this period.
-
+The link is valid until {}. {}
+{}
 Thank you for using our DJ Session Recorder system!
 
 Best regards,
@@ -107,7 +115,10 @@ DJ Session Recorder System
 "#,
             dj_name,
             session_id,
-            download_link
+            link.url,
+            link.token.expires_at.format("%Y-%m-%d %H:%M UTC"),
+            limit,
+            tracklist
         );
 
         let email = Message::builder()
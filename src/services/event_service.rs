@@ -1,13 +1,16 @@
 use crate::models::{
     event_session::{EventSession, EventSessionResponse, StartEventRequest, Timetable, TimetableEntry, TimetableEntryStatus},
+    late_penalty::{LatePenaltyCurve, PenaltyPreview, PenaltyPreviewRequest},
     dj::Dj,
     session::Session,
-    AppState,
+    reserved_slot::ReservedSlot,
+    slot_recording::SlotRecording,
+    AppState, HookTrigger, WebhookEventType,
 };
-use crate::services::LotteryService;
+use crate::services::{HttpRecorderControl, LotteryService, OutputService, RecorderControl, ReservedSlotService, SlotRecordingService, WebhookService};
 use anyhow::{Result, anyhow};
 use chrono::Utc;
-use sqlx::SqlitePool;
+use sqlx::{Row, SqlitePool};
 use std::sync::Arc;
 
 pub struct EventService {
@@ -15,6 +18,8 @@ pub struct EventService {
     app_state: Arc<AppState>,
     default_slot_duration: i32,
     default_late_arrival_cutoff: i32,
+    default_late_penalty: f64,
+    recorder_control: Option<Arc<dyn RecorderControl>>,
 }
 
 impl EventService {
@@ -24,9 +29,24 @@ impl EventService {
             app_state: app_state.clone(),
             default_slot_duration: app_state.config.lottery_config.max_session_duration_minutes as i32,
             default_late_arrival_cutoff: app_state.config.lottery_config.time_block_hours as i32,
+            default_late_penalty: app_state.config.lottery_config.late_arrival_penalty,
+            recorder_control: HttpRecorderControl::from_config(&app_state.config.session_recorder_config)
+                .map(|control| Arc::new(control) as Arc<dyn RecorderControl>),
         }
     }
 
+    /// Record slots through the given recorder instead of the configured one
+    pub fn with_recorder_control(mut self, control: Arc<dyn RecorderControl>) -> Self {
+        self.recorder_control = Some(control);
+        self
+    }
+
+    fn slot_recordings(&self) -> Option<SlotRecordingService> {
+        self.recorder_control
+            .as_ref()
+            .map(|control| SlotRecordingService::new(self.app_state.clone(), control.clone()))
+    }
+
     pub async fn start_event(&self, request: StartEventRequest) -> Result<EventSessionResponse> {
         // Check if there's already an active event
         if let Some(_) = self.get_active_event().await? {
@@ -36,14 +56,20 @@ impl EventService {
         let slot_duration = request.slot_duration_minutes.unwrap_or(self.default_slot_duration);
         let late_arrival_cutoff = request.late_arrival_cutoff_hours.unwrap_or(self.default_late_arrival_cutoff);
 
-        let event = EventSession::new(slot_duration, late_arrival_cutoff, request.started_at);
+        let mut event = EventSession::new(slot_duration, late_arrival_cutoff, request.started_at);
+        event.name = request.name.filter(|name| !name.trim().is_empty());
+
+        if let Some(curve) = &request.late_penalty_curve {
+            curve.validate()?;
+            event.late_penalty_curve = Some(serde_json::to_string(curve)?);
+        }
 
         sqlx::query(
             r#"
             INSERT INTO event_sessions (id, started_at, ended_at, slot_duration_minutes,
                                        late_arrival_cutoff_hours, is_active, current_dj_id,
-                                       current_slot_started_at, next_draw_at)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+                                       current_slot_started_at, next_draw_at, late_penalty_curve, name)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
         )
         .bind(&event.id)
@@ -55,12 +81,16 @@ impl EventService {
         .bind(&event.current_dj_id)
         .bind(&event.current_slot_started_at)
         .bind(&event.next_draw_at)
+        .bind(&event.late_penalty_curve)
+        .bind(&event.name)
         .execute(&self.db)
         .await?;
 
-        // Automatically draw the first DJ
+        // Automatically draw the first DJ, unless the opening slot is booked
         let lottery_service = LotteryService::new(self.app_state.clone());
-        if let Ok(Some(draw)) = lottery_service.draw_next_dj().await {
+        if let Some(slot) = self.reserved_slot_at(1).await? {
+            tracing::info!("Opening slot is reserved ({:?}), skipping first draw", slot.kind);
+        } else if let Ok(Some(draw)) = lottery_service.draw_next_dj().await {
             tracing::info!("Automatically drew first DJ for new event: {}", draw.winner.name);
         } else {
             tracing::warn!("No DJs available to draw for the new event");
@@ -112,6 +142,24 @@ impl EventService {
         ended_event.ended_at = Some(Utc::now());
         ended_event.is_active = false;
 
+        if let Some(slot_recordings) = self.slot_recordings() {
+            if let Err(e) = slot_recordings.stop_all().await {
+                tracing::warn!("Failed to stop slot recordings: {}", e);
+            }
+        }
+
+        OutputService::new(self.app_state.clone())
+            .notify(HookTrigger::EventEnd, Some(&ended_event), ended_event.current_dj_id.as_deref())
+            .await;
+        WebhookService::new(self.app_state.clone())
+            .emit(WebhookEventType::EventEnded, serde_json::json!({
+                "event_id": ended_event.id,
+                "event_name": ended_event.name,
+                "started_at": ended_event.started_at,
+                "ended_at": ended_event.ended_at,
+            }))
+            .await;
+
         self.to_response(ended_event).await
     }
 
@@ -137,13 +185,55 @@ impl EventService {
         .await?;
 
         let mut updated_event = event;
-        updated_event.current_dj_id = Some(dj_id);
+        updated_event.current_dj_id = Some(dj_id.clone());
         updated_event.current_slot_started_at = Some(slot_start);
         updated_event.next_draw_at = Some(next_draw);
 
+        if let Some(slot_recordings) = self.slot_recordings() {
+            if let Err(e) = slot_recordings.start_slot(&updated_event, &dj_id).await {
+                tracing::warn!("Failed to start slot recording: {}", e);
+            }
+        }
+
+        OutputService::new(self.app_state.clone())
+            .notify(HookTrigger::SlotStart, Some(&updated_event), Some(&dj_id))
+            .await;
+        let dj_name: Option<String> = sqlx::query_scalar("SELECT name FROM djs WHERE id = ?")
+            .bind(&dj_id)
+            .fetch_optional(&self.db)
+            .await?;
+        WebhookService::new(self.app_state.clone())
+            .emit(WebhookEventType::SlotStarted, serde_json::json!({
+                "event_id": updated_event.id,
+                "dj_id": dj_id,
+                "dj_name": dj_name,
+                "slot_started_at": slot_start,
+                "slot_ends_at": slot_start + chrono::Duration::minutes(updated_event.slot_duration_minutes as i64),
+            }))
+            .await;
+
         self.to_response(updated_event).await
     }
 
+    /// The DJ's set is over; stops their recording. The slot itself runs until the next one starts.
+    pub async fn end_dj_slot(&self, dj_id: &str) -> Result<()> {
+        if let Some(slot_recordings) = self.slot_recordings() {
+            slot_recordings.stop_dj(dj_id).await?;
+        }
+        Ok(())
+    }
+
+    /// Recordings started for the active event's slots
+    pub async fn get_slot_recordings(&self) -> Result<Vec<SlotRecording>> {
+        let Some(event) = self.get_active_event().await? else {
+            return Ok(Vec::new());
+        };
+        match self.slot_recordings() {
+            Some(slot_recordings) => slot_recordings.get_event_recordings(&event.id).await,
+            None => Ok(Vec::new()),
+        }
+    }
+
     pub async fn check_and_trigger_auto_draw(&self) -> Result<bool> {
         let event = match self.get_active_event().await? {
             Some(e) => e,
@@ -163,18 +253,101 @@ impl EventService {
             .execute(&self.db)
             .await?;
 
+            // A booked DJ plays next, so there is nothing to draw for
+            let current_position = match &event.current_dj_id {
+                Some(dj_id) => sqlx::query_scalar::<_, Option<i32>>("SELECT position_in_queue FROM djs WHERE id = ?")
+                    .bind(dj_id)
+                    .fetch_optional(&self.db)
+                    .await?
+                    .flatten()
+                    .unwrap_or(0),
+                None => 0,
+            };
+            if let Some(slot) = self.reserved_slot_at(current_position + 1).await? {
+                tracing::info!("Slot {} is reserved ({:?}), skipping draw", slot.position, slot.kind);
+                return Ok(false);
+            }
+
             return Ok(true);
         }
 
         Ok(false)
     }
 
+    async fn reserved_slot_at(&self, position: i32) -> Result<Option<ReservedSlot>> {
+        ReservedSlotService::new(self.app_state.clone())
+            .get_reserved_slot_by_position(position)
+            .await
+    }
+
     pub async fn get_timetable(&self) -> Result<Option<Timetable>> {
-        let event = match self.get_active_event().await? {
-            Some(e) => e,
-            None => return Ok(None),
-        };
+        match self.get_active_event().await? {
+            Some(event) => Ok(Some(self.queue_timetable(event).await?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Timetable of any event: the queue while the event runs, the sets played once it ended
+    pub async fn get_event_timetable(&self, event_id: &str) -> Result<Option<Timetable>> {
+        let event = sqlx::query_as::<_, EventSession>("SELECT * FROM event_sessions WHERE id = ?")
+            .bind(event_id)
+            .fetch_optional(&self.db)
+            .await?;
 
+        match event {
+            Some(event) if event.is_active() => Ok(Some(self.queue_timetable(event).await?)),
+            Some(event) => Ok(Some(self.played_timetable(event).await?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn played_timetable(&self, event: EventSession) -> Result<Timetable> {
+        let rows = sqlx::query(
+            r#"
+            SELECT s.dj_id, d.name, s.started_at, s.ended_at, s.duration_minutes
+            FROM sessions s
+            JOIN djs d ON d.id = s.dj_id
+            WHERE s.started_at >= ? AND (? IS NULL OR s.started_at <= ?)
+            ORDER BY s.started_at ASC
+            "#,
+        )
+        .bind(event.started_at)
+        .bind(event.ended_at)
+        .bind(event.ended_at)
+        .fetch_all(&self.db)
+        .await?;
+
+        let entries: Vec<TimetableEntry> = rows
+            .iter()
+            .enumerate()
+            .map(|(index, row)| {
+                let ended_at: Option<chrono::DateTime<Utc>> = row.get("ended_at");
+                TimetableEntry {
+                    position: (index + 1) as i32,
+                    dj_id: row.get("dj_id"),
+                    dj_name: row.get("name"),
+                    started_at: row.get("started_at"),
+                    ended_at,
+                    duration_minutes: row.get("duration_minutes"),
+                    status: if ended_at.is_some() { TimetableEntryStatus::Completed } else { TimetableEntryStatus::InProgress },
+                    reserved_kind: None,
+                    reserved_label: None,
+                }
+            })
+            .collect();
+
+        Ok(Timetable {
+            event_id: event.id,
+            event_name: event.name,
+            event_started_at: event.started_at,
+            slot_duration_minutes: event.slot_duration_minutes,
+            total_djs: entries.len(),
+            completed_sets: entries.iter().filter(|entry| matches!(entry.status, TimetableEntryStatus::Completed)).count(),
+            entries,
+        })
+    }
+
+    async fn queue_timetable(&self, event: EventSession) -> Result<Timetable> {
         // Get all queued DJs (ordered by position_in_queue)
         let queued_djs = sqlx::query_as::<_, Dj>(
             r#"
@@ -186,12 +359,21 @@ impl EventService {
         .fetch_all(&self.db)
         .await?;
 
+        let reserved_slots: std::collections::HashMap<String, ReservedSlot> =
+            sqlx::query_as::<_, ReservedSlot>("SELECT * FROM reserved_slots")
+                .fetch_all(&self.db)
+                .await?
+                .into_iter()
+                .map(|slot| (slot.dj_id.clone(), slot))
+                .collect();
+
         let mut entries = Vec::new();
         let mut completed_sets = 0;
 
-        // Build timetable entries in queue order
+        // Build timetable entries in queue order; positions keep the gaps of open lottery slots
         for (index, dj) in queued_djs.iter().enumerate() {
-            let position = (index + 1) as i32;
+            let position = dj.position_in_queue.unwrap_or((index + 1) as i32);
+            let reserved = reserved_slots.get(&dj.id);
 
             // Check if this DJ has a session
             let session = sqlx::query_as::<_, Session>(
@@ -218,8 +400,11 @@ impl EventService {
                 };
                 (session.started_at, session.ended_at, session.duration_minutes, status)
             } else {
-                // DJ is queued but hasn't started yet
-                (event.started_at, None, None, TimetableEntryStatus::Upcoming)
+                // DJ is queued but hasn't started yet: booked time, or planned from the slot grid
+                let planned_start = reserved.and_then(|slot| slot.starts_at).unwrap_or_else(|| {
+                    event.started_at + chrono::Duration::minutes((position - 1) as i64 * event.slot_duration_minutes as i64)
+                });
+                (planned_start, None, None, TimetableEntryStatus::Upcoming)
             };
 
             entries.push(TimetableEntry {
@@ -230,18 +415,49 @@ impl EventService {
                 ended_at,
                 duration_minutes,
                 status,
+                reserved_kind: reserved.map(|slot| slot.kind),
+                reserved_label: reserved.and_then(|slot| slot.label.clone()),
             });
         }
 
         let total_djs = entries.len();
 
-        Ok(Some(Timetable {
+        Ok(Timetable {
             event_id: event.id,
+            event_name: event.name,
             event_started_at: event.started_at,
+            slot_duration_minutes: event.slot_duration_minutes,
             entries,
             total_djs,
             completed_sets,
-        }))
+        })
+    }
+
+    /// Sample a penalty curve; without an explicit curve the active event's curve is used
+    pub async fn preview_late_penalty(&self, request: PenaltyPreviewRequest) -> Result<PenaltyPreview> {
+        let (until_minutes, step_minutes) = request.range()?;
+        let curve = match request.curve {
+            Some(curve) => {
+                curve.validate()?;
+                curve
+            }
+            None => match self.get_active_event().await? {
+                Some(event) => event.penalty_curve(self.default_late_penalty),
+                None => LatePenaltyCurve::step_after_hours(
+                    self.default_late_arrival_cutoff,
+                    self.default_late_penalty,
+                ),
+            },
+        };
+
+        let base_weight = request.base_weight.unwrap_or(self.app_state.config.lottery_config.base_weight);
+        let points = curve.preview(base_weight, until_minutes, step_minutes);
+
+        Ok(PenaltyPreview {
+            curve,
+            base_weight,
+            points,
+        })
     }
 
     async fn to_response(&self, event: EventSession) -> Result<EventSessionResponse> {
@@ -261,13 +477,16 @@ impl EventService {
         let is_active = event.is_active();
         let elapsed_minutes = event.elapsed_minutes();
         let current_slot_progress_percent = event.current_slot_progress_percent();
+        let late_penalty_curve = event.penalty_curve(self.default_late_penalty);
 
         Ok(EventSessionResponse {
             id: event.id,
+            name: event.name,
             started_at: event.started_at,
             ended_at: event.ended_at,
             slot_duration_minutes: event.slot_duration_minutes,
             late_arrival_cutoff_hours: event.late_arrival_cutoff_hours,
+            late_penalty_curve,
             is_active,
             current_dj_id: event.current_dj_id,
             current_dj_name,
@@ -0,0 +1,157 @@
+use crate::models::storage::{ByteRange, StorageError, StorageListing, StorageObject};
+use crate::services::{StorageBackend, StorageFuture, StorageStream};
+use anyhow::Result;
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+use std::pin::Pin;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+
+/// Storage in a directory tree; keys map to paths below `root`
+pub struct LocalStorage {
+    root: PathBuf,
+    public_url: Option<String>,
+}
+
+impl LocalStorage {
+    pub fn new(root: &Path, public_url: Option<String>) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            public_url: public_url.map(|url| url.trim_end_matches('/').to_string()),
+        }
+    }
+
+    /// Path for a key; keys cannot climb out of the root
+    fn path_for(&self, key: &str) -> Result<PathBuf> {
+        let relative = Path::new(key.trim_end_matches('/'));
+        if !relative.components().all(|c| matches!(c, Component::Normal(_))) {
+            return Err(StorageError::InvalidKey(key.to_string()).into());
+        }
+        Ok(self.root.join(relative))
+    }
+
+    /// Write through a temporary sibling so readers never see half a file
+    async fn write_atomically(&self, key: &str, write: impl AsyncFnOnce(&Path) -> std::io::Result<()>) -> Result<()> {
+        let path = self.path_for(key)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let mut partial = path.clone().into_os_string();
+        partial.push(".part");
+        let partial = PathBuf::from(partial);
+        write(&partial).await?;
+        tokio::fs::rename(&partial, &path).await?;
+        Ok(())
+    }
+}
+
+fn not_found(key: &str, error: std::io::Error) -> anyhow::Error {
+    if error.kind() == std::io::ErrorKind::NotFound {
+        StorageError::NotFound(key.to_string()).into()
+    } else {
+        error.into()
+    }
+}
+
+impl StorageBackend for LocalStorage {
+    fn list<'a>(&'a self, prefix: &'a str) -> StorageFuture<'a, StorageListing> {
+        Box::pin(async move {
+            let dir = self.path_for(prefix)?;
+            let mut listing = StorageListing::default();
+            if !dir.is_dir() {
+                return Ok(listing);
+            }
+
+            let key_prefix = match prefix.trim_end_matches('/') {
+                "" => String::new(),
+                trimmed => format!("{}/", trimmed),
+            };
+            let mut entries = tokio::fs::read_dir(&dir).await?;
+            while let Some(entry) = entries.next_entry().await? {
+                let name = entry.file_name().to_string_lossy().to_string();
+                let metadata = entry.metadata().await?;
+                if metadata.is_dir() {
+                    listing.directories.push(name);
+                } else if !name.ends_with(".part") {
+                    listing.objects.push(StorageObject {
+                        key: format!("{}{}", key_prefix, name),
+                        size: metadata.len(),
+                    });
+                }
+            }
+
+            listing.directories.sort();
+            listing.objects.sort_by(|a, b| a.key.cmp(&b.key));
+            Ok(listing)
+        })
+    }
+
+    fn get<'a>(&'a self, key: &'a str) -> StorageFuture<'a, Vec<u8>> {
+        Box::pin(async move {
+            let path = self.path_for(key)?;
+            tokio::fs::read(&path).await.map_err(|e| not_found(key, e))
+        })
+    }
+
+    fn put<'a>(&'a self, key: &'a str, data: Vec<u8>) -> StorageFuture<'a, ()> {
+        Box::pin(async move {
+            self.write_atomically(key, async |partial| tokio::fs::write(partial, &data).await).await
+        })
+    }
+
+    fn put_file<'a>(&'a self, key: &'a str, path: &'a Path) -> StorageFuture<'a, ()> {
+        Box::pin(async move {
+            self.write_atomically(key, async |partial| tokio::fs::copy(path, partial).await.map(|_| ())).await
+        })
+    }
+
+    fn put_stream<'a>(&'a self, key: &'a str, mut body: Pin<Box<dyn AsyncRead + Send>>) -> StorageFuture<'a, ()> {
+        Box::pin(async move {
+            self.write_atomically(key, async |partial| {
+                let mut file = tokio::fs::File::create(partial).await?;
+                tokio::io::copy(&mut body, &mut file).await?;
+                file.flush().await
+            })
+            .await
+        })
+    }
+
+    fn stream<'a>(&'a self, key: &'a str, range: Option<ByteRange>) -> StorageFuture<'a, StorageStream> {
+        Box::pin(async move {
+            let path = self.path_for(key)?;
+            let mut file = tokio::fs::File::open(&path).await.map_err(|e| not_found(key, e))?;
+            let size = file.metadata().await?.len();
+            let range = match range {
+                Some(range) => range.resolve(size)?,
+                None => 0..size,
+            };
+
+            file.seek(std::io::SeekFrom::Start(range.start)).await?;
+            Ok(StorageStream {
+                body: Box::pin(file.take(range.end - range.start)),
+                size,
+                range,
+            })
+        })
+    }
+
+    fn presign<'a>(&'a self, _key: &'a str, _expires_in: Duration) -> StorageFuture<'a, Option<String>> {
+        Box::pin(async move { Ok(None) })
+    }
+
+    fn delete<'a>(&'a self, key: &'a str) -> StorageFuture<'a, ()> {
+        Box::pin(async move {
+            let path = self.path_for(key)?;
+            match tokio::fs::remove_file(&path).await {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+                _ => Ok(()),
+            }
+        })
+    }
+
+    fn public_url(&self, key: &str) -> String {
+        match &self.public_url {
+            Some(base) => format!("{}/{}", base, key),
+            None => format!("file://{}", self.root.join(key).display()),
+        }
+    }
+}
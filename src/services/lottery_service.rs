@@ -1,16 +1,27 @@
 use crate::models::{
     dj::{Dj, DjResponse},
-    lottery::{LotteryDraw, LotteryEngine, LotteryConfig, LotteryParticipant, LotteryStatistics},
+    lottery::{
+        DjDrawHistoryEntry, DjLotteryStatus, DjOdds, DrawRecord, DrawStatus, LotteryConfig, LotteryDraw,
+        LotteryEngine, LotteryStatistics, VoidDrawError, VoidDrawRequest, VoidDrawResponse, WeightComponent,
+    },
+    simulation::{
+        FairnessReport, HistoricalDjFairness, HistoricalFairness, LotterySimulator, SimulationRequest,
+        DEFAULT_SIMULATED_EVENT_MINUTES, DEFAULT_SIMULATION_RUNS,
+    },
     event_session::EventSession,
-    AppState,
+    reserved_slot::assign_queue_positions,
+    AppState, HookTrigger, WebhookEventType,
 };
-use anyhow::Result;
-use chrono::Utc;
-use sqlx::{Row, SqlitePool};
+use crate::services::{OutputService, WebhookService};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use sqlx::{Row, SqliteConnection, SqlitePool};
+use std::collections::HashMap;
 use std::sync::Arc;
 
 pub struct LotteryService {
     db: SqlitePool,
+    app_state: Arc<AppState>,
     engine: LotteryEngine,
 }
 
@@ -21,54 +32,107 @@ impl LotteryService {
             late_arrival_penalty: app_state.config.lottery_config.late_arrival_penalty,
             time_block_hours: app_state.config.lottery_config.time_block_hours,
             enable_time_blocking: true,
+            carry_over_bonus: app_state.config.lottery_config.carry_over_bonus,
         };
 
         Self {
             db: app_state.db.clone(),
+            app_state,
             engine: LotteryEngine::new(config),
         }
     }
 
     pub async fn get_eligible_djs(&self) -> Result<Vec<Dj>> {
+        Self::eligible_djs_with(&mut *self.db.acquire().await?).await
+    }
+
+    async fn eligible_djs_with(conn: &mut SqliteConnection) -> Result<Vec<Dj>> {
         let djs = sqlx::query_as::<_, Dj>(
             r#"
-            SELECT id, name, email, registered_at, weight, is_active, position_in_queue
+            SELECT id, name, email, registered_at, weight, is_active, position_in_queue, keep_recording
             FROM djs 
             WHERE is_active = true 
             AND position_in_queue IS NULL
             ORDER BY registered_at ASC
             "#,
         )
-        .fetch_all(&self.db)
+        .fetch_all(&mut *conn)
         .await?;
 
         Ok(djs)
     }
 
     pub async fn draw_next_dj(&self) -> Result<Option<LotteryDraw>> {
-        let eligible_djs = self.get_eligible_djs().await?;
+        let mut tx = self.db.begin().await?;
+        let drawn = self.draw_with(&mut tx, None, None, None).await?;
+        tx.commit().await?;
+
+        Ok(match drawn {
+            Some((draw, event)) => {
+                self.announce_draw(&draw, event.as_ref(), None).await;
+                Some(draw)
+            }
+            None => None,
+        })
+    }
+
+    /// Draw a winner among the pool, leaving out `excluded_dj_id`, and place them at
+    /// `position`, or at the end of the queue when None. Returns the draw with the event it
+    /// was made for; announcing it is left to the caller once the transaction is committed.
+    async fn draw_with(
+        &self,
+        conn: &mut SqliteConnection,
+        position: Option<i32>,
+        replaces_draw_id: Option<&str>,
+        excluded_dj_id: Option<&str>,
+    ) -> Result<Option<(LotteryDraw, Option<EventSession>)>> {
+        let mut eligible_djs = Self::eligible_djs_with(conn).await?;
+        eligible_djs.retain(|dj| Some(dj.id.as_str()) != excluded_dj_id);
 
         if eligible_djs.is_empty() {
             return Ok(None);
         }
 
         // Get active event for late arrival penalty calculation
-        let event = self.get_active_event().await?;
+        let event = Self::active_event_with(conn).await?;
+        let lost_draws = Self::lost_draw_counts_with(conn, event.as_ref()).await?;
 
-        let draw_result = self.engine.draw_winner(&eligible_djs, event.as_ref());
+        let Some(draw) = self.engine.draw_winner_with_carry_over(&eligible_djs, event.as_ref(), &lost_draws) else {
+            return Ok(None);
+        };
 
-        if let Some(ref draw) = draw_result {
-            // Save the draw to database
-            self.save_lottery_draw(draw).await?;
+        Self::save_draw_with(conn, &draw, event.as_ref().map(|e| e.id.as_str()), replaces_draw_id).await?;
 
-            // Update the winner's position in queue
-            self.assign_next_position(&draw.winner.id).await?;
+        // Update the winner's position in queue
+        match position {
+            Some(position) => Self::insert_at_position_with(conn, &draw.winner.id, position).await?,
+            None => Self::assign_next_position_with(conn, &draw.winner.id).await?,
         }
 
-        Ok(draw_result)
+        Ok(Some((draw, event)))
+    }
+
+    async fn announce_draw(&self, draw: &LotteryDraw, event: Option<&EventSession>, position: Option<i32>) {
+        OutputService::new(self.app_state.clone())
+            .notify(HookTrigger::Draw, event, Some(&draw.winner.id))
+            .await;
+        WebhookService::new(self.app_state.clone())
+            .emit(WebhookEventType::Draw, serde_json::json!({
+                "draw_id": draw.id,
+                "event_id": event.map(|e| &e.id),
+                "dj_id": draw.winner.id,
+                "dj_name": draw.winner.name,
+                "position": position,
+                "drawn_at": draw.drawn_at,
+            }))
+            .await;
     }
 
     async fn get_active_event(&self) -> Result<Option<EventSession>> {
+        Self::active_event_with(&mut *self.db.acquire().await?).await
+    }
+
+    async fn active_event_with(conn: &mut SqliteConnection) -> Result<Option<EventSession>> {
         let event = sqlx::query_as::<_, EventSession>(
             r#"
             SELECT * FROM event_sessions
@@ -77,55 +141,395 @@ impl LotteryService {
             LIMIT 1
             "#,
         )
-        .fetch_optional(&self.db)
+        .fetch_optional(&mut *conn)
         .await?;
 
         Ok(event)
     }
 
-    pub async fn save_lottery_draw(&self, draw: &LotteryDraw) -> Result<()> {
+    /// Number of draws each DJ took part in but lost during the given event
+    async fn get_lost_draw_counts(&self, event: Option<&EventSession>) -> Result<HashMap<String, u32>> {
+        Self::lost_draw_counts_with(&mut *self.db.acquire().await?, event).await
+    }
+
+    async fn lost_draw_counts_with(conn: &mut SqliteConnection, event: Option<&EventSession>) -> Result<HashMap<String, u32>> {
+        let event = match event {
+            Some(e) => e,
+            None => return Ok(HashMap::new()),
+        };
+
+        let rows = sqlx::query(
+            r#"
+            SELECT p.dj_id, COUNT(*) as lost
+            FROM lottery_draw_participants p
+            INNER JOIN lottery_draws d ON d.id = p.draw_id
+            WHERE d.event_id = ? AND d.status = 'completed' AND p.was_winner = false
+            GROUP BY p.dj_id
+            "#,
+        )
+        .bind(&event.id)
+        .fetch_all(&mut *conn)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| (row.get::<String, _>("dj_id"), row.get::<i64, _>("lost") as u32))
+            .collect())
+    }
+
+    pub async fn save_lottery_draw(&self, draw: &LotteryDraw, event_id: Option<&str>) -> Result<()> {
+        let mut tx = self.db.begin().await?;
+        Self::save_draw_with(&mut tx, draw, event_id, None).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn save_draw_with(
+        conn: &mut SqliteConnection,
+        draw: &LotteryDraw,
+        event_id: Option<&str>,
+        replaces_draw_id: Option<&str>,
+    ) -> Result<()> {
         let participants_json = serde_json::to_string(&draw.participants)?;
-        
+
         sqlx::query(
             r#"
-            INSERT INTO lottery_draws (id, winner_dj_id, drawn_at, algorithm_used, participants_data)
-            VALUES (?, ?, ?, ?, ?)
+            INSERT INTO lottery_draws (id, winner_dj_id, drawn_at, algorithm_used, participants_data, event_id,
+                                       replaces_draw_id)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
             "#,
         )
-        .bind(&uuid::Uuid::new_v4().to_string())
+        .bind(&draw.id)
         .bind(&draw.winner.id)
         .bind(&draw.drawn_at)
         .bind(&draw.algorithm_used)
         .bind(&participants_json)
-        .execute(&self.db)
+        .bind(event_id)
+        .bind(replaces_draw_id)
+        .execute(&mut *conn)
         .await?;
 
+        for participant in &draw.participants {
+            sqlx::query(
+                r#"
+                INSERT INTO lottery_draw_participants (draw_id, dj_id, calculated_weight,
+                                                       selection_probability, weight_components, was_winner)
+                VALUES (?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(&draw.id)
+            .bind(&participant.dj.id)
+            .bind(participant.calculated_weight)
+            .bind(participant.selection_probability)
+            .bind(serde_json::to_string(&participant.weight_components)?)
+            .bind(participant.dj.id == draw.winner.id)
+            .execute(&mut *conn)
+            .await?;
+        }
+
         Ok(())
     }
 
-    pub async fn assign_next_position(&self, dj_id: &str) -> Result<()> {
-        // Get the highest position number currently assigned
-        let max_position = sqlx::query("SELECT COALESCE(MAX(position_in_queue), 0) as max_pos FROM djs")
-            .fetch_one(&self.db)
-            .await?
-            .get::<i32, _>("max_pos");
+    /// Current odds and full draw history for one DJ
+    pub async fn get_dj_odds(&self, dj_id: &str) -> Result<Option<DjOdds>> {
+        let dj = sqlx::query_as::<_, Dj>(
+            "SELECT id, name, email, registered_at, weight, is_active, position_in_queue, keep_recording FROM djs WHERE id = ?"
+        )
+        .bind(dj_id)
+        .fetch_optional(&self.db)
+        .await?;
 
-        // Assign the next position
-        sqlx::query(
-            "UPDATE djs SET position_in_queue = ? WHERE id = ?"
+        let dj = match dj {
+            Some(dj) => dj,
+            None => return Ok(None),
+        };
+
+        let lottery_status = match dj.position_in_queue {
+            Some(position) => DjLotteryStatus::Queued { position },
+            None if dj.is_active => DjLotteryStatus::InPool,
+            None => DjLotteryStatus::Inactive,
+        };
+
+        // Odds are only meaningful while the DJ is still waiting in the pool
+        let eligible_djs = self.get_eligible_djs().await?;
+        let pool_size = eligible_djs.len();
+        let current = if matches!(lottery_status, DjLotteryStatus::InPool) {
+            let event = self.get_active_event().await?;
+            let lost_draws = self.get_lost_draw_counts(event.as_ref()).await?;
+            self.engine
+                .calculate_weights_with_carry_over(&eligible_djs, event.as_ref(), &lost_draws)
+                .into_iter()
+                .find(|p| p.dj.id == dj.id)
+        } else {
+            None
+        };
+
+        let rows = sqlx::query(
+            r#"
+            SELECT d.id as draw_id, d.drawn_at, d.status, d.winner_dj_id, w.name as winner_name,
+                   p.was_winner, p.calculated_weight, p.selection_probability, p.weight_components,
+                   (SELECT COUNT(*) FROM lottery_draw_participants c WHERE c.draw_id = d.id) as participant_count
+            FROM lottery_draw_participants p
+            INNER JOIN lottery_draws d ON d.id = p.draw_id
+            LEFT JOIN djs w ON w.id = d.winner_dj_id
+            WHERE p.dj_id = ?
+            ORDER BY d.drawn_at DESC
+            "#,
         )
-        .bind(max_position + 1)
         .bind(dj_id)
-        .execute(&self.db)
+        .fetch_all(&self.db)
         .await?;
 
+        let mut history = Vec::new();
+        for row in rows {
+            let components_json: String = row.get("weight_components");
+            history.push(DjDrawHistoryEntry {
+                draw_id: row.get("draw_id"),
+                drawn_at: row.get::<DateTime<Utc>, _>("drawn_at"),
+                status: row.get("status"),
+                winner_dj_id: row.get("winner_dj_id"),
+                winner_name: row.get("winner_name"),
+                was_winner: row.get("was_winner"),
+                participant_count: row.get("participant_count"),
+                calculated_weight: row.get("calculated_weight"),
+                selection_probability: row.get("selection_probability"),
+                weight_components: serde_json::from_str::<Vec<WeightComponent>>(&components_json)?,
+            });
+        }
+
+        Ok(Some(DjOdds {
+            dj: dj.into(),
+            lottery_status,
+            current,
+            pool_size,
+            history,
+        }))
+    }
+
+    /// Lottery DJs in queue order and the booked (dj_id, position) pairs
+    async fn queue_layout_with(conn: &mut SqliteConnection) -> Result<(Vec<String>, Vec<(String, i32)>)> {
+        let floating = sqlx::query(
+            r#"
+            SELECT id FROM djs
+            WHERE position_in_queue IS NOT NULL
+            AND id NOT IN (SELECT dj_id FROM reserved_slots)
+            ORDER BY position_in_queue ASC
+            "#,
+        )
+        .fetch_all(&mut *conn)
+        .await?
+        .into_iter()
+        .map(|row| row.get::<String, _>("id"))
+        .collect();
+
+        let reserved = sqlx::query(
+            "SELECT r.dj_id, r.position FROM reserved_slots r INNER JOIN djs d ON d.id = r.dj_id ORDER BY r.position ASC"
+        )
+        .fetch_all(&mut *conn)
+        .await?
+        .into_iter()
+        .map(|row| (row.get::<String, _>("dj_id"), row.get::<i32, _>("position")))
+        .collect();
+
+        Ok((floating, reserved))
+    }
+
+    /// Rewrite every queue position: reserved DJs stay on their booking, lottery DJs
+    /// (in the given order) fill the free positions around them
+    pub async fn rebuild_queue(&self, floating: &[String]) -> Result<()> {
+        let mut tx = self.db.begin().await?;
+        Self::rebuild_queue_with(&mut tx, floating).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn rebuild_queue_with(conn: &mut SqliteConnection, floating: &[String]) -> Result<()> {
+        let (_, reserved) = Self::queue_layout_with(conn).await?;
+        let positions = assign_queue_positions(floating, &reserved);
+
+        sqlx::query("UPDATE djs SET position_in_queue = NULL WHERE position_in_queue IS NOT NULL")
+            .execute(&mut *conn)
+            .await?;
+
+        for (dj_id, position) in &positions {
+            sqlx::query("UPDATE djs SET position_in_queue = ? WHERE id = ?")
+                .bind(position)
+                .bind(dj_id)
+                .execute(&mut *conn)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Re-apply reserved slots after bookings changed, keeping the lottery order
+    pub async fn compact_queue(&self) -> Result<()> {
+        let mut tx = self.db.begin().await?;
+        let (floating, _) = Self::queue_layout_with(&mut tx).await?;
+        Self::rebuild_queue_with(&mut tx, &floating).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Index in the lottery order that ends up on queue `position`
+    fn floating_index(position: i32, reserved: &[(String, i32)], floating_len: usize) -> usize {
+        let booked_before = reserved.iter().filter(|(_, p)| *p < position).count() as i32;
+        ((position - 1 - booked_before).max(0) as usize).min(floating_len)
+    }
+
+    pub async fn assign_next_position(&self, dj_id: &str) -> Result<()> {
+        let mut tx = self.db.begin().await?;
+        Self::assign_next_position_with(&mut tx, dj_id).await?;
+        tx.commit().await?;
+
         Ok(())
     }
 
+    async fn assign_next_position_with(conn: &mut SqliteConnection, dj_id: &str) -> Result<()> {
+        let (mut floating, _) = Self::queue_layout_with(conn).await?;
+        floating.retain(|id| id != dj_id);
+        floating.push(dj_id.to_string());
+
+        Self::rebuild_queue_with(conn, &floating).await
+    }
+
+    /// Put a DJ at `position`, moving the lottery DJs from there onwards back by one
+    pub async fn insert_at_position(&self, dj_id: &str, position: i32) -> Result<()> {
+        let mut tx = self.db.begin().await?;
+        Self::insert_at_position_with(&mut tx, dj_id, position).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn insert_at_position_with(conn: &mut SqliteConnection, dj_id: &str, position: i32) -> Result<()> {
+        let (mut floating, reserved) = Self::queue_layout_with(conn).await?;
+        floating.retain(|id| id != dj_id);
+
+        let index = Self::floating_index(position, &reserved, floating.len());
+        floating.insert(index, dj_id.to_string());
+
+        Self::rebuild_queue_with(conn, &floating).await
+    }
+
+    pub async fn get_draw(&self, draw_id: &str) -> Result<Option<DrawRecord>> {
+        let row = sqlx::query(
+            r#"
+            SELECT d.id, d.winner_dj_id, w.name as winner_name, d.drawn_at, d.event_id, d.status,
+                   d.voided_at, d.void_reason, d.replaces_draw_id
+            FROM lottery_draws d
+            LEFT JOIN djs w ON w.id = d.winner_dj_id
+            WHERE d.id = ?
+            "#,
+        )
+        .bind(draw_id)
+        .fetch_optional(&self.db)
+        .await?;
+
+        Ok(row.map(|row| DrawRecord {
+            id: row.get("id"),
+            winner_dj_id: row.get("winner_dj_id"),
+            winner_name: row.get("winner_name"),
+            drawn_at: row.get("drawn_at"),
+            event_id: row.get("event_id"),
+            status: row.get("status"),
+            voided_at: row.get("voided_at"),
+            void_reason: row.get("void_reason"),
+            replaces_draw_id: row.get("replaces_draw_id"),
+        }))
+    }
+
+    /// Undo a mistaken draw: the winner goes back to the pool and the queue closes the gap.
+    /// The draw itself is kept with status `voided`. A redraw leaves out the voided winner
+    /// unless `include_voided_winner` is set.
+    pub async fn void_draw(&self, draw_id: &str, request: VoidDrawRequest) -> Result<VoidDrawResponse> {
+        let reason = request.reason.trim();
+        if reason.is_empty() {
+            return Err(VoidDrawError::MissingReason.into());
+        }
+
+        let draw = self.get_draw(draw_id).await?.ok_or(VoidDrawError::NotFound)?;
+        if draw.status == DrawStatus::Voided {
+            return Err(VoidDrawError::AlreadyVoided.into());
+        }
+
+        // Voiding, closing the gap and the redraw succeed or fail together
+        let mut tx = self.db.begin().await?;
+
+        // Once the winner has played the draw can no longer be taken back
+        let played = sqlx::query(
+            r#"
+            SELECT
+                (SELECT COUNT(*) FROM sessions WHERE dj_id = ? AND started_at >= ?) +
+                (SELECT COUNT(*) FROM event_sessions WHERE is_active = true AND current_dj_id = ?) as count
+            "#,
+        )
+        .bind(&draw.winner_dj_id)
+        .bind(draw.drawn_at)
+        .bind(&draw.winner_dj_id)
+        .fetch_one(&mut *tx)
+        .await?
+        .get::<i64, _>("count");
+        if played > 0 {
+            return Err(VoidDrawError::WinnerAlreadyPlayed.into());
+        }
+
+        let freed_position = sqlx::query("SELECT position_in_queue FROM djs WHERE id = ?")
+            .bind(&draw.winner_dj_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .and_then(|row| row.get::<Option<i32>, _>("position_in_queue"));
+
+        // Only one of two concurrent voids gets to free the position and redraw
+        let voided = sqlx::query(
+            r#"
+            UPDATE lottery_draws
+            SET status = ?, voided_at = ?, void_reason = ?
+            WHERE id = ? AND status = ?
+            "#,
+        )
+        .bind(DrawStatus::Voided)
+        .bind(Utc::now())
+        .bind(reason)
+        .bind(&draw.id)
+        .bind(DrawStatus::Completed)
+        .execute(&mut *tx)
+        .await?;
+        if voided.rows_affected() == 0 {
+            return Err(VoidDrawError::AlreadyVoided.into());
+        }
+
+        Self::remove_from_queue_with(&mut tx, &draw.winner_dj_id).await?;
+
+        let redraw = if request.redraw {
+            let excluded = if request.include_voided_winner { None } else { Some(draw.winner_dj_id.as_str()) };
+            self.draw_with(&mut tx, freed_position, Some(&draw.id), excluded).await?
+        } else {
+            None
+        };
+
+        tx.commit().await?;
+
+        if let Some((redraw, event)) = &redraw {
+            self.announce_draw(redraw, event.as_ref(), freed_position).await;
+        }
+
+        let voided = self.get_draw(&draw.id).await?.ok_or(VoidDrawError::NotFound)?;
+
+        Ok(VoidDrawResponse {
+            voided,
+            freed_position,
+            redraw: redraw.map(|(redraw, _)| redraw),
+        })
+    }
+
     pub async fn get_current_queue(&self) -> Result<Vec<DjResponse>> {
         let djs = sqlx::query_as::<_, Dj>(
             r#"
-            SELECT id, name, email, registered_at, weight, is_active, position_in_queue
+            SELECT id, name, email, registered_at, weight, is_active, position_in_queue, keep_recording
             FROM djs 
             WHERE position_in_queue IS NOT NULL 
             ORDER BY position_in_queue ASC
@@ -140,7 +544,7 @@ impl LotteryService {
     pub async fn get_next_dj(&self) -> Result<Option<DjResponse>> {
         let dj = sqlx::query_as::<_, Dj>(
             r#"
-            SELECT id, name, email, registered_at, weight, is_active, position_in_queue
+            SELECT id, name, email, registered_at, weight, is_active, position_in_queue, keep_recording
             FROM djs 
             WHERE position_in_queue IS NOT NULL 
             ORDER BY position_in_queue ASC 
@@ -154,98 +558,65 @@ impl LotteryService {
     }
 
     pub async fn remove_from_queue(&self, dj_id: &str) -> Result<()> {
-        // Get the position of the DJ being removed
-        let removed_position = sqlx::query(
-            "SELECT position_in_queue FROM djs WHERE id = ?"
-        )
-        .bind(dj_id)
-        .fetch_optional(&self.db)
-        .await?;
+        let mut tx = self.db.begin().await?;
+        Self::remove_from_queue_with(&mut tx, dj_id).await?;
+        tx.commit().await?;
 
-        if let Some(row) = removed_position {
-            let position: Option<i32> = row.get("position_in_queue");
-            
-            // Remove the DJ from queue
-            sqlx::query("UPDATE djs SET position_in_queue = NULL WHERE id = ?")
-                .bind(dj_id)
-                .execute(&self.db)
-                .await?;
+        Ok(())
+    }
 
-            // Shift everyone after this position down by 1
-            if let Some(pos) = position {
-                sqlx::query(
-                    "UPDATE djs SET position_in_queue = position_in_queue - 1 WHERE position_in_queue > ?"
-                )
-                .bind(pos)
-                .execute(&self.db)
-                .await?;
-            }
-        }
+    async fn remove_from_queue_with(conn: &mut SqliteConnection, dj_id: &str) -> Result<()> {
+        // Removing a booked DJ gives up the booking as well
+        sqlx::query("DELETE FROM reserved_slots WHERE dj_id = ?")
+            .bind(dj_id)
+            .execute(&mut *conn)
+            .await?;
 
-        Ok(())
+        let (mut floating, _) = Self::queue_layout_with(conn).await?;
+        floating.retain(|id| id != dj_id);
+
+        // Everyone after the removed DJ moves up
+        Self::rebuild_queue_with(conn, &floating).await
     }
 
     pub async fn move_dj_position(&self, dj_id: &str, new_position: i32) -> Result<()> {
-        // Get current position
-        let current_row = sqlx::query(
-            "SELECT position_in_queue FROM djs WHERE id = ?"
-        )
-        .bind(dj_id)
-        .fetch_optional(&self.db)
-        .await?;
+        let mut tx = self.db.begin().await?;
+        let (mut floating, reserved) = Self::queue_layout_with(&mut tx).await?;
 
-        if let Some(row) = current_row {
-            let current_position: Option<i32> = row.get("position_in_queue");
-            
-            if let Some(current_pos) = current_position {
-                // Temporarily set to -1 to avoid conflicts
-                sqlx::query("UPDATE djs SET position_in_queue = -1 WHERE id = ?")
-                    .bind(dj_id)
-                    .execute(&self.db)
-                    .await?;
-
-                if new_position < current_pos {
-                    // Moving up: shift others down
-                    sqlx::query(
-                        "UPDATE djs SET position_in_queue = position_in_queue + 1 
-                         WHERE position_in_queue >= ? AND position_in_queue < ?"
-                    )
-                    .bind(new_position)
-                    .bind(current_pos)
-                    .execute(&self.db)
-                    .await?;
-                } else if new_position > current_pos {
-                    // Moving down: shift others up
-                    sqlx::query(
-                        "UPDATE djs SET position_in_queue = position_in_queue - 1 
-                         WHERE position_in_queue > ? AND position_in_queue <= ?"
-                    )
-                    .bind(current_pos)
-                    .bind(new_position)
-                    .execute(&self.db)
-                    .await?;
-                }
-
-                // Set the new position
-                sqlx::query("UPDATE djs SET position_in_queue = ? WHERE id = ?")
-                    .bind(new_position)
-                    .bind(dj_id)
-                    .execute(&self.db)
-                    .await?;
-            }
+        if reserved.iter().any(|(id, _)| id == dj_id) {
+            return Err(anyhow!("DJ has a reserved slot; change the reservation instead"));
+        }
+        if reserved.iter().any(|(_, position)| *position == new_position) {
+            return Err(anyhow!("Position {} is reserved", new_position));
+        }
+        if !floating.iter().any(|id| id == dj_id) {
+            // Not in the queue, nothing to move
+            return Ok(());
         }
 
+        floating.retain(|id| id != dj_id);
+        let index = Self::floating_index(new_position, &reserved, floating.len());
+        floating.insert(index, dj_id.to_string());
+
+        Self::rebuild_queue_with(&mut tx, &floating).await?;
+        tx.commit().await?;
+
         Ok(())
     }
 
     pub async fn get_lottery_statistics(&self) -> Result<LotteryStatistics> {
-        let total_draws = sqlx::query("SELECT COUNT(*) as count FROM lottery_draws")
+        let total_draws = sqlx::query("SELECT COUNT(*) as count FROM lottery_draws WHERE status = 'completed'")
+            .fetch_one(&self.db)
+            .await?
+            .get::<i64, _>("count") as usize;
+
+        let voided_draws = sqlx::query("SELECT COUNT(*) as count FROM lottery_draws WHERE status = 'voided'")
             .fetch_one(&self.db)
             .await?
             .get::<i64, _>("count") as usize;
 
         let unique_winners = sqlx::query(
-            "SELECT COUNT(DISTINCT winner_dj_id) as count FROM lottery_draws"
+            "SELECT COUNT(DISTINCT winner_dj_id) as count FROM lottery_draws WHERE status = 'completed'"
         )
         .fetch_one(&self.db)
         .await?
@@ -267,18 +638,95 @@ impl LotteryService {
 
         Ok(LotteryStatistics {
             total_draws,
+            voided_draws,
             unique_winners,
             average_weight,
             fairness_score,
         })
     }
 
-    pub async fn reset_lottery(&self) -> Result<()> {
-        // Clear all queue positions
-        sqlx::query("UPDATE djs SET position_in_queue = NULL")
-            .execute(&self.db)
-            .await?;
+    /// Simulate the current pool and compare recorded draws against their probabilities
+    pub async fn get_fairness_report(&self, request: SimulationRequest) -> Result<FairnessReport> {
+        let eligible_djs = self.get_eligible_djs().await?;
+        let event = self.get_active_event().await?;
+        let lost_draws = self.get_lost_draw_counts(event.as_ref()).await?;
+
+        let slot_duration_minutes = event
+            .as_ref()
+            .map(|e| e.slot_duration_minutes as i64)
+            .unwrap_or(60)
+            .max(1);
+        let slots = request
+            .slots
+            .unwrap_or((DEFAULT_SIMULATED_EVENT_MINUTES / slot_duration_minutes).max(1) as usize);
+        let runs = request.runs.unwrap_or(DEFAULT_SIMULATION_RUNS);
+        let seed = request.seed.unwrap_or_else(rand::random);
+
+        // Up to millions of weighted picks; keep them off the async workers
+        let engine = self.engine.clone();
+        let simulation = tokio::task::spawn_blocking(move || {
+            LotterySimulator::new(&engine).run(&eligible_djs, event.as_ref(), &lost_draws, runs, slots, seed)
+        })
+        .await??;
+        let historical = self.get_historical_fairness(request.event_id.as_deref()).await?;
 
-        Ok(())
+        Ok(FairnessReport {
+            generated_at: Utc::now(),
+            simulation,
+            historical,
+        })
+    }
+
+    /// Observed wins against the probabilities recorded in `lottery_draw_participants`
+    pub async fn get_historical_fairness(&self, event_id: Option<&str>) -> Result<HistoricalFairness> {
+        let rows = sqlx::query(
+            r#"
+            SELECT p.dj_id, dj.name as dj_name, COUNT(*) as draws_entered,
+                   SUM(CASE WHEN p.was_winner THEN 1 ELSE 0 END) as observed_wins,
+                   SUM(p.selection_probability) as expected_wins
+            FROM lottery_draw_participants p
+            INNER JOIN lottery_draws d ON d.id = p.draw_id
+            LEFT JOIN djs dj ON dj.id = p.dj_id
+            WHERE ? IS NULL OR d.event_id = ?
+            GROUP BY p.dj_id
+            ORDER BY expected_wins DESC
+            "#,
+        )
+        .bind(event_id)
+        .bind(event_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        let total_draws = sqlx::query(
+            r#"
+            SELECT COUNT(DISTINCT d.id) as count
+            FROM lottery_draws d
+            INNER JOIN lottery_draw_participants p ON p.draw_id = d.id
+            WHERE ? IS NULL OR d.event_id = ?
+            "#,
+        )
+        .bind(event_id)
+        .bind(event_id)
+        .fetch_one(&self.db)
+        .await?
+        .get::<i64, _>("count");
+
+        let djs = rows
+            .into_iter()
+            .map(|row| HistoricalDjFairness {
+                dj_id: row.get("dj_id"),
+                dj_name: row.get("dj_name"),
+                draws_entered: row.get("draws_entered"),
+                observed_wins: row.get("observed_wins"),
+                expected_wins: row.get("expected_wins"),
+            })
+            .collect();
+
+        Ok(HistoricalFairness::from_djs(total_draws, djs))
+    }
+
+    pub async fn reset_lottery(&self) -> Result<()> {
+        // Clear all queue positions; reserved slots keep their DJs
+        self.rebuild_queue(&[]).await
     }
 }
\ No newline at end of file
@@ -4,10 +4,54 @@ pub mod lottery_service;
 pub mod email_service;
 pub mod session_recorder_service;
 pub mod event_service;
+pub mod reserved_slot_service;
+pub mod segmentation_service;
+pub mod recorder_index_service;
+pub mod recorder_webhook_service;
+pub mod recorder_control;
+pub mod slot_recording_service;
+pub mod storage_backend;
+pub mod local_storage;
+pub mod s3_storage;
+pub mod download_service;
+pub mod tagging_service;
+pub mod archive_service;
+pub mod waveform_service;
+pub mod analysis_service;
+pub mod tracklist_service;
+pub mod now_playing_service;
+pub mod output_service;
+pub mod stream_metadata_service;
+pub mod webhook_service;
+pub mod calendar_service;
+pub mod timetable_export_service;
+pub mod share_image_service;
 
 pub use dj_service::*;
 pub use session_service::*;
 pub use lottery_service::*;
 pub use email_service::*;
 pub use session_recorder_service::*;
-pub use event_service::*;
\ No newline at end of file
+pub use event_service::*;
+pub use reserved_slot_service::*;
+pub use segmentation_service::*;
+pub use recorder_index_service::*;
+pub use recorder_webhook_service::*;
+pub use recorder_control::*;
+pub use slot_recording_service::*;
+pub use storage_backend::*;
+pub use local_storage::*;
+pub use s3_storage::*;
+pub use download_service::*;
+pub use tagging_service::*;
+pub use archive_service::*;
+pub use waveform_service::*;
+pub use analysis_service::*;
+pub use tracklist_service::*;
+pub use now_playing_service::*;
+pub use output_service::*;
+pub use stream_metadata_service::*;
+pub use webhook_service::*;
+pub use calendar_service::*;
+pub use timetable_export_service::*;
+pub use share_image_service::*;
\ No newline at end of file
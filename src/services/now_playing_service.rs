@@ -0,0 +1,152 @@
+use crate::models::{AppState, NowPlaying, Track, MAX_TRACKS};
+use crate::utils::OscMessage;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sqlx::{Row, SqlitePool};
+use std::sync::Arc;
+use tokio::net::UdpSocket;
+
+/// Takes the tracks DJ software reports over OSC into the tracklist of the set being played
+pub struct NowPlayingService {
+    db: SqlitePool,
+    app_state: Arc<AppState>,
+}
+
+impl NowPlayingService {
+    pub fn new(app_state: Arc<AppState>) -> Self {
+        Self {
+            db: app_state.db.clone(),
+            app_state,
+        }
+    }
+
+    /// The open session and its latest track; `None` between sets
+    pub async fn now_playing(&self) -> Result<Option<NowPlaying>> {
+        let Some(mut now_playing) = self.current_session().await? else {
+            return Ok(None);
+        };
+        now_playing.track = self.last_track(&now_playing.session_id).await?;
+        Ok(Some(now_playing))
+    }
+
+    /// Append a played track to the open session's tracklist, timed from the start of the set.
+    /// Players repeat a track when it is loaded or cued again, so a repeat of the latest track
+    /// is not added twice. Returns `None` when no session is open.
+    pub async fn record_track(&self, artist: Option<&str>, title: &str, played_at: DateTime<Utc>) -> Result<Option<NowPlaying>> {
+        let Some(mut now_playing) = self.current_session().await? else {
+            tracing::debug!("Ignoring track '{}' reported between sets", title);
+            return Ok(None);
+        };
+        let title = title.trim();
+        let artist = artist.map(str::trim).filter(|artist| !artist.is_empty());
+        let last = self.last_track(&now_playing.session_id).await?;
+        if title.is_empty() || last.as_ref().is_some_and(|last| last.title == title && last.artist.as_deref() == artist) {
+            now_playing.track = last;
+            return Ok(Some(now_playing));
+        }
+
+        let position = last.as_ref().map_or(0, |last| last.position) + 1;
+        if position as usize > MAX_TRACKS {
+            tracing::warn!("Tracklist of session {} is full; not adding '{}'", now_playing.session_id, title);
+            now_playing.track = last;
+            return Ok(Some(now_playing));
+        }
+        // Clocks of a bridge and this server can disagree a little; keep the tracklist in order
+        let since_start = (played_at - now_playing.session_started_at).num_milliseconds() as f64 / 1000.0;
+        let offset = last
+            .as_ref()
+            .and_then(|last| last.offset_seconds)
+            .unwrap_or(0.0)
+            .max(since_start);
+
+        let mut track = Track::new(artist, title, Some(offset));
+        track.position = position;
+        let mut tx = self.db.begin().await?;
+        sqlx::query(
+            "INSERT INTO tracklist_tracks (session_id, position, offset_seconds, artist, title) VALUES (?, ?, ?, ?, ?)"
+        )
+        .bind(&now_playing.session_id)
+        .bind(track.position)
+        .bind(track.offset_seconds)
+        .bind(&track.artist)
+        .bind(&track.title)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query("UPDATE sessions SET tracklist_updated_at = ? WHERE id = ?")
+            .bind(Utc::now())
+            .bind(&now_playing.session_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        tracing::info!("Now playing in session {}: {}", now_playing.session_id, track.display());
+        now_playing.track = Some(track);
+        Ok(Some(now_playing))
+    }
+
+    /// Record the tracks in an OSC packet. Track messages carry either artist and title as
+    /// their first two strings or "Artist - Title" as one; other arguments are ignored.
+    pub async fn handle_packet(&self, data: &[u8], received_at: DateTime<Utc>) -> Result<usize> {
+        let track_address = &self.app_state.config.osc_config.track_address;
+        let mut recorded = 0;
+        for message in OscMessage::parse_packet(data)? {
+            if &message.address != track_address {
+                continue;
+            }
+            let strings: Vec<&str> = message.args.iter().filter_map(|arg| arg.as_str()).collect();
+            let track = match strings.as_slice() {
+                [] => continue,
+                [display] => Track::from_display(display, None),
+                [artist, title, ..] => Track::new(Some(artist), title, None),
+            };
+            if self.record_track(track.artist.as_deref(), &track.title, received_at).await?.is_some() {
+                recorded += 1;
+            }
+        }
+        Ok(recorded)
+    }
+
+    async fn current_session(&self) -> Result<Option<NowPlaying>> {
+        let row = sqlx::query(
+            "SELECT s.id, s.started_at, d.name FROM sessions s JOIN djs d ON d.id = s.dj_id WHERE s.ended_at IS NULL ORDER BY s.started_at DESC LIMIT 1"
+        )
+        .fetch_optional(&self.db)
+        .await?;
+
+        Ok(row.map(|row| NowPlaying {
+            session_id: row.get("id"),
+            dj_name: row.get("name"),
+            session_started_at: row.get("started_at"),
+            track: None,
+        }))
+    }
+
+    async fn last_track(&self, session_id: &str) -> Result<Option<Track>> {
+        let track = sqlx::query_as::<_, Track>(
+            "SELECT position, offset_seconds, artist, title FROM tracklist_tracks WHERE session_id = ? ORDER BY position DESC LIMIT 1"
+        )
+        .bind(session_id)
+        .fetch_optional(&self.db)
+        .await?;
+        Ok(track)
+    }
+}
+
+/// Receive OSC packets on `socket` until the task is dropped
+pub async fn run_osc_listener(app_state: Arc<AppState>, socket: UdpSocket) {
+    let service = NowPlayingService::new(app_state);
+    let mut buffer = vec![0u8; 65536];
+
+    loop {
+        let (len, peer) = match socket.recv_from(&mut buffer).await {
+            Ok(received) => received,
+            Err(e) => {
+                tracing::error!("Error receiving OSC packet: {}", e);
+                continue;
+            }
+        };
+        if let Err(e) = service.handle_packet(&buffer[..len], Utc::now()).await {
+            tracing::warn!("Ignoring OSC packet from {}: {}", peer, e);
+        }
+    }
+}
@@ -0,0 +1,234 @@
+use crate::models::{
+    event_session::EventSession, AppState, HookContext, HookResult, HookTarget, HookTrigger, OutputHook,
+};
+use crate::utils::{OscArg, OscMessage};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Utc};
+use sqlx::SqlitePool;
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tokio::net::UdpSocket;
+
+/// Tells lights, visuals and displays about slot changes through the configured output hooks
+pub struct OutputService {
+    db: SqlitePool,
+    app_state: Arc<AppState>,
+    client: reqwest::Client,
+}
+
+impl OutputService {
+    pub fn new(app_state: Arc<AppState>) -> Self {
+        Self {
+            db: app_state.db.clone(),
+            app_state,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    fn hooks_for(&self, trigger: HookTrigger) -> Vec<OutputHook> {
+        self.app_state
+            .config
+            .output_config
+            .hooks
+            .iter()
+            .filter(|hook| hook.enabled && hook.triggers.contains(&trigger))
+            .cloned()
+            .collect()
+    }
+
+    /// Fire the hooks for `trigger` in the background; failures are logged, never returned,
+    /// so a missing lighting desk can't hold up the lottery
+    pub async fn notify(&self, trigger: HookTrigger, event: Option<&EventSession>, dj_id: Option<&str>) {
+        let hooks = self.hooks_for(trigger);
+        if hooks.is_empty() {
+            return;
+        }
+        let context = match self.context(trigger, event, dj_id).await {
+            Ok(context) => context,
+            Err(e) => {
+                tracing::warn!("Not firing {} output hooks: {}", trigger.as_str(), e);
+                return;
+            }
+        };
+
+        let client = self.client.clone();
+        let timeout = self.timeout();
+        tokio::spawn(async move {
+            send_hooks(&client, &hooks, &context, timeout).await;
+        });
+    }
+
+    /// Fire the hooks for `trigger` now, for the active event and its current DJ, and report
+    /// how each one went
+    pub async fn test_hooks(&self, trigger: HookTrigger) -> Result<Vec<HookResult>> {
+        let event = self.active_event().await?;
+        let dj_id = event.as_ref().and_then(|event| event.current_dj_id.clone());
+        let context = self.context(trigger, event.as_ref(), dj_id.as_deref()).await?;
+        Ok(send_hooks(&self.client, &self.hooks_for(trigger), &context, self.timeout()).await)
+    }
+
+    /// Fire `slot_ending` once per slot when the active slot gets within the warning time of its
+    /// end. `warned_slot` holds the start of the slot last warned about between calls.
+    pub async fn check_slot_ending(&self, warned_slot: &mut Option<DateTime<Utc>>) -> Result<bool> {
+        let Some(event) = self.active_event().await? else {
+            return Ok(false);
+        };
+        let Some(slot_start) = event.current_slot_started_at else {
+            return Ok(false);
+        };
+        if *warned_slot == Some(slot_start) {
+            return Ok(false);
+        }
+
+        let slot_end = slot_start + Duration::minutes(event.slot_duration_minutes as i64);
+        let warning = Duration::minutes(self.app_state.config.output_config.slot_warning_minutes);
+        let now = Utc::now();
+        if now < slot_end - warning || now >= slot_end {
+            return Ok(false);
+        }
+
+        *warned_slot = Some(slot_start);
+        self.notify(HookTrigger::SlotEnding, Some(&event), event.current_dj_id.as_deref()).await;
+        Ok(true)
+    }
+
+    /// Template values for a trigger. `dj_id` is the DJ the trigger is about; the rest comes
+    /// from the event's current slot.
+    pub async fn context(&self, trigger: HookTrigger, event: Option<&EventSession>, dj_id: Option<&str>) -> Result<HookContext> {
+        let dj_name = match dj_id {
+            Some(dj_id) => self.dj_name(dj_id).await?,
+            None => String::new(),
+        };
+        let current_dj_id = event.and_then(|event| event.current_dj_id.as_deref());
+        let next_dj_name = sqlx::query_scalar::<_, String>(
+            r#"
+            SELECT name FROM djs
+            WHERE position_in_queue > COALESCE((SELECT position_in_queue FROM djs WHERE id = ?), 0)
+            ORDER BY position_in_queue ASC
+            LIMIT 1
+            "#,
+        )
+        .bind(current_dj_id)
+        .fetch_optional(&self.db)
+        .await?
+        .unwrap_or_default();
+
+        let slot_start = event.and_then(|event| event.current_slot_started_at);
+        let slot_end = event
+            .zip(slot_start)
+            .map(|(event, start)| start + Duration::minutes(event.slot_duration_minutes as i64));
+        // Rounded up, so the last minute reads 1 rather than 0
+        let minutes_left = slot_end.map(|end| ((end - Utc::now()).num_seconds().max(0) + 59) / 60);
+
+        Ok(HookContext {
+            trigger: trigger.as_str().to_string(),
+            event_name: event.and_then(|event| event.name.clone()).unwrap_or_default(),
+            dj_name,
+            next_dj_name,
+            slot_start,
+            slot_end,
+            minutes_left,
+        })
+    }
+
+    async fn dj_name(&self, dj_id: &str) -> Result<String> {
+        sqlx::query_scalar::<_, String>("SELECT name FROM djs WHERE id = ?")
+            .bind(dj_id)
+            .fetch_optional(&self.db)
+            .await?
+            .ok_or_else(|| anyhow!("DJ {} not found", dj_id))
+    }
+
+    async fn active_event(&self) -> Result<Option<EventSession>> {
+        let event = sqlx::query_as::<_, EventSession>(
+            "SELECT * FROM event_sessions WHERE is_active = true AND ended_at IS NULL ORDER BY started_at DESC LIMIT 1"
+        )
+        .fetch_optional(&self.db)
+        .await?;
+        Ok(event)
+    }
+
+    fn timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.app_state.config.output_config.timeout_seconds)
+    }
+}
+
+/// Send to every hook at once, each under its own timeout
+async fn send_hooks(client: &reqwest::Client, hooks: &[OutputHook], context: &HookContext, timeout: std::time::Duration) -> Vec<HookResult> {
+    let sends = hooks.iter().map(|hook| async move {
+        let result = match tokio::time::timeout(timeout, send_hook(client, &hook.target, context)).await {
+            Ok(result) => result,
+            Err(_) => Err(anyhow!("timed out after {}s", timeout.as_secs())),
+        };
+        match &result {
+            Ok(()) => tracing::debug!("Output hook '{}' fired for {}", hook.name, context.trigger),
+            Err(e) => tracing::warn!("Output hook '{}' failed for {}: {}", hook.name, context.trigger, e),
+        }
+        HookResult {
+            name: hook.name.clone(),
+            ok: result.is_ok(),
+            error: result.err().map(|e| e.to_string()),
+        }
+    });
+    futures_util::future::join_all(sends).await
+}
+
+async fn send_hook(client: &reqwest::Client, target: &HookTarget, context: &HookContext) -> Result<()> {
+    match target {
+        HookTarget::Osc { host, address, args } => {
+            let args = args
+                .iter()
+                .map(|arg| match arg {
+                    serde_json::Value::String(text) => OscArg::String(context.render(text)),
+                    serde_json::Value::Number(number) => match number.as_i64().and_then(|n| i32::try_from(n).ok()) {
+                        Some(int) => OscArg::Int(int),
+                        None => OscArg::Float(number.as_f64().unwrap_or_default() as f32),
+                    },
+                    serde_json::Value::Bool(value) => OscArg::Bool(*value),
+                    serde_json::Value::Null => OscArg::Nil,
+                    other => OscArg::String(other.to_string()),
+                })
+                .collect();
+            let packet = OscMessage::new(&context.render(address), args).encode();
+            let target = tokio::net::lookup_host(host.as_str())
+                .await?
+                .next()
+                .ok_or_else(|| anyhow!("'{}' has no address", host))?;
+            // The socket has to be of the target's address family
+            let socket = UdpSocket::bind(if target.is_ipv6() { "[::]:0" } else { "0.0.0.0:0" }).await?;
+            socket.send_to(&packet, target).await?;
+        }
+        HookTarget::Http { url, method, headers, body } => {
+            let method = reqwest::Method::from_bytes(method.to_uppercase().as_bytes())
+                .map_err(|_| anyhow!("invalid HTTP method '{}'", method))?;
+            let mut request = client.request(method, context.render(url));
+            for (name, value) in headers {
+                request = request.header(name, context.render(value));
+            }
+            request = match body {
+                Some(body) => request.body(context.render_json(body)),
+                None => request.json(context),
+            };
+            request.send().await?.error_for_status()?;
+        }
+        HookTarget::File { path, template, append } => {
+            let mut options = tokio::fs::OpenOptions::new();
+            options.write(true).create(true).append(*append).truncate(!*append);
+            // Opening a named pipe nobody reads would block a runtime thread for good; the
+            // timeout only drops the future
+            #[cfg(unix)]
+            options.custom_flags(libc::O_NONBLOCK);
+            let mut file = match options.open(path).await {
+                Ok(file) => file,
+                #[cfg(unix)]
+                Err(e) if e.raw_os_error() == Some(libc::ENXIO) => {
+                    return Err(anyhow!("no reader on {}", path.display()));
+                }
+                Err(e) => return Err(e.into()),
+            };
+            file.write_all(format!("{}\n", context.render(template)).as_bytes()).await?;
+            file.flush().await?;
+        }
+    }
+    Ok(())
+}
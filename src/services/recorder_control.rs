@@ -0,0 +1,131 @@
+use crate::models::{
+    slot_recording::{StartRecordingRequest, StartRecordingResponse},
+    SessionRecorderIntegrationConfig,
+};
+use anyhow::{anyhow, Result};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Mutex;
+
+pub type ControlFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T>> + Send + 'a>>;
+
+/// Starts and stops recordings on the session recorder. Object safe so the event service
+/// can hold whichever implementation is configured.
+pub trait RecorderControl: Send + Sync {
+    /// Start a named recording and return the recorder's id for it
+    fn start_recording<'a>(&'a self, request: &'a StartRecordingRequest) -> ControlFuture<'a, String>;
+    fn stop_recording<'a>(&'a self, recording_id: &'a str) -> ControlFuture<'a, ()>;
+}
+
+/// Talks to the recorder's control API:
+/// `POST {url}/api/recordings` with `{"name", "keep"}` answering `{"id"}`, and
+/// `POST {url}/api/recordings/{id}/stop`
+pub struct HttpRecorderControl {
+    client: reqwest::Client,
+    base_url: String,
+    token: Option<String>,
+}
+
+impl HttpRecorderControl {
+    pub fn new(base_url: &str, token: Option<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token,
+        }
+    }
+
+    /// `None` unless the integration is enabled and a control URL is set
+    pub fn from_config(config: &SessionRecorderIntegrationConfig) -> Option<Self> {
+        if !config.enabled {
+            return None;
+        }
+        config.control_url.as_deref().map(|url| Self::new(url, config.control_token.clone()))
+    }
+
+    fn post(&self, path: &str) -> reqwest::RequestBuilder {
+        let request = self.client.post(format!("{}{}", self.base_url, path));
+        match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+}
+
+impl RecorderControl for HttpRecorderControl {
+    fn start_recording<'a>(&'a self, request: &'a StartRecordingRequest) -> ControlFuture<'a, String> {
+        Box::pin(async move {
+            let response = self.post("/api/recordings").json(request).send().await?.error_for_status()?;
+            let started: StartRecordingResponse = response.json().await?;
+            Ok(started.id)
+        })
+    }
+
+    fn stop_recording<'a>(&'a self, recording_id: &'a str) -> ControlFuture<'a, ()> {
+        Box::pin(async move {
+            self.post(&format!("/api/recordings/{}/stop", recording_id)).send().await?.error_for_status()?;
+            Ok(())
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RecorderCommand {
+    Start { recording_id: String, request: StartRecordingRequest },
+    Stop { recording_id: String },
+}
+
+/// In-memory recorder for running the slot lifecycle without hardware. Records every
+/// command; `set_offline` makes it fail like an unreachable recorder.
+#[derive(Default)]
+pub struct MockRecorderControl {
+    commands: Mutex<Vec<RecorderCommand>>,
+    offline: Mutex<bool>,
+}
+
+impl MockRecorderControl {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn commands(&self) -> Vec<RecorderCommand> {
+        self.commands.lock().unwrap().clone()
+    }
+
+    pub fn set_offline(&self, offline: bool) {
+        *self.offline.lock().unwrap() = offline;
+    }
+
+    fn check_online(&self) -> Result<()> {
+        if *self.offline.lock().unwrap() {
+            return Err(anyhow!("Recorder is offline"));
+        }
+        Ok(())
+    }
+}
+
+impl RecorderControl for MockRecorderControl {
+    fn start_recording<'a>(&'a self, request: &'a StartRecordingRequest) -> ControlFuture<'a, String> {
+        Box::pin(async move {
+            self.check_online()?;
+            let mut commands = self.commands.lock().unwrap();
+            let started = commands.iter().filter(|c| matches!(c, RecorderCommand::Start { .. })).count();
+            let recording_id = format!("mock-{}", started + 1);
+            commands.push(RecorderCommand::Start {
+                recording_id: recording_id.clone(),
+                request: request.clone(),
+            });
+            Ok(recording_id)
+        })
+    }
+
+    fn stop_recording<'a>(&'a self, recording_id: &'a str) -> ControlFuture<'a, ()> {
+        Box::pin(async move {
+            self.check_online()?;
+            self.commands.lock().unwrap().push(RecorderCommand::Stop {
+                recording_id: recording_id.to_string(),
+            });
+            Ok(())
+        })
+    }
+}
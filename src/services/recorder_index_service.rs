@@ -0,0 +1,335 @@
+use crate::models::{
+    recorder_index::{RecorderSyncError, RecorderSyncStatus, RecorderSyncSummary, STALE_SYNC_MINUTES},
+    AppState,
+};
+use crate::services::{RecorderSession, SessionFiles, SessionRecorderConfig, SessionRecorderService};
+use anyhow::Result;
+use chrono::{Duration, Utc};
+use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::sync::Arc;
+
+/// Where the index sync reads recorder sessions from. Implemented by the MinIO client;
+/// tests plug in a fixed listing.
+pub trait RecorderCatalog {
+    fn list_recorders(&self) -> impl Future<Output = Result<Vec<String>>> + Send;
+    fn list_session_ids(&self, recorder_id: &str) -> impl Future<Output = Result<Vec<String>>> + Send;
+    fn session_details(&self, recorder_id: &str, session_id: &str) -> impl Future<Output = Result<RecorderSession>> + Send;
+}
+
+impl RecorderCatalog for SessionRecorderService {
+    fn list_recorders(&self) -> impl Future<Output = Result<Vec<String>>> + Send {
+        self.get_recorders()
+    }
+
+    fn list_session_ids(&self, recorder_id: &str) -> impl Future<Output = Result<Vec<String>>> + Send {
+        SessionRecorderService::list_session_ids(self, recorder_id)
+    }
+
+    fn session_details(&self, recorder_id: &str, session_id: &str) -> impl Future<Output = Result<RecorderSession>> + Send {
+        self.get_session_details(recorder_id, session_id)
+    }
+}
+
+const SESSION_COLUMNS: &str = "recorder_id, id, name, start_time, end_time, duration_seconds, is_closed, keep, ogg_url, flac_url, waveform_url, overview_png_url, metadata_url";
+
+/// Local copy of the recorder bucket's session listing. Lookups answer from here instead
+/// of walking MinIO; a background job keeps it current.
+pub struct RecorderIndexService {
+    db: SqlitePool,
+    app_state: Arc<AppState>,
+}
+
+impl RecorderIndexService {
+    pub fn new(app_state: Arc<AppState>) -> Self {
+        Self {
+            db: app_state.db.clone(),
+            app_state,
+        }
+    }
+
+    /// Sync against the configured recorder bucket and wait for it to finish
+    pub async fn sync_recorder_index(&self, full: bool) -> Result<RecorderSyncSummary> {
+        let catalog = self.recorder_client().await?;
+        self.sync_from(&catalog, full).await
+    }
+
+    /// Start a sync in the background. Fails straight away if one is already running.
+    pub async fn trigger_sync(&self, full: bool) -> Result<()> {
+        let catalog = self.recorder_client().await?;
+        self.claim_sync().await?;
+
+        let service = Self::new(self.app_state.clone());
+        tokio::spawn(async move {
+            if let Err(e) = service.run_claimed_sync(&catalog, full).await {
+                tracing::error!("Recorder index sync failed: {}", e);
+            }
+        });
+
+        Ok(())
+    }
+
+    /// Bring the index in line with the catalog. Incremental by default: closed sessions
+    /// already in the index are not fetched again, `full` re-fetches everything.
+    pub async fn sync_from<C: RecorderCatalog>(&self, catalog: &C, full: bool) -> Result<RecorderSyncSummary> {
+        self.claim_sync().await?;
+        self.run_claimed_sync(catalog, full).await
+    }
+
+    async fn recorder_client(&self) -> Result<SessionRecorderService> {
+        let config = &self.app_state.config.session_recorder_config;
+        if !config.enabled {
+            return Err(RecorderSyncError::Disabled.into());
+        }
+        SessionRecorderService::new(SessionRecorderConfig::from(config)).await
+    }
+
+    /// Mark a sync as running, unless another one is. A run that never finished (the
+    /// process died mid-sync) stops blocking new ones after `STALE_SYNC_MINUTES`.
+    async fn claim_sync(&self) -> Result<()> {
+        let now = Utc::now();
+        let claimed = sqlx::query(
+            r#"
+            UPDATE recorder_sync_status
+            SET running = TRUE, last_started_at = ?
+            WHERE id = 1 AND (running = FALSE OR last_started_at IS NULL OR last_started_at < ?)
+            "#,
+        )
+        .bind(now)
+        .bind(now - Duration::minutes(STALE_SYNC_MINUTES))
+        .execute(&self.db)
+        .await?;
+
+        if claimed.rows_affected() == 0 {
+            return Err(RecorderSyncError::AlreadyRunning.into());
+        }
+        Ok(())
+    }
+
+    async fn run_claimed_sync<C: RecorderCatalog>(&self, catalog: &C, full: bool) -> Result<RecorderSyncSummary> {
+        let result = self.sync_sessions(catalog, full).await;
+
+        match &result {
+            Ok(summary) => {
+                sqlx::query(
+                    r#"
+                    UPDATE recorder_sync_status
+                    SET running = FALSE, last_finished_at = ?, last_error = NULL, recorders = ?,
+                        sessions_added = ?, sessions_updated = ?, sessions_removed = ?
+                    WHERE id = 1
+                    "#,
+                )
+                .bind(Utc::now())
+                .bind(summary.recorders)
+                .bind(summary.sessions_added)
+                .bind(summary.sessions_updated)
+                .bind(summary.sessions_removed)
+                .execute(&self.db)
+                .await?;
+
+                tracing::info!(
+                    "Recorder index synced: {} added, {} updated, {} removed, {} failed",
+                    summary.sessions_added,
+                    summary.sessions_updated,
+                    summary.sessions_removed,
+                    summary.sessions_failed
+                );
+            }
+            Err(e) => {
+                sqlx::query("UPDATE recorder_sync_status SET running = FALSE, last_finished_at = ?, last_error = ? WHERE id = 1")
+                    .bind(Utc::now())
+                    .bind(e.to_string())
+                    .execute(&self.db)
+                    .await?;
+            }
+        }
+
+        result
+    }
+
+    async fn sync_sessions<C: RecorderCatalog>(&self, catalog: &C, full: bool) -> Result<RecorderSyncSummary> {
+        let mut indexed: HashMap<(String, String), RecorderSession> = self
+            .get_sessions()
+            .await?
+            .into_iter()
+            .map(|s| ((s.recorder_id.clone(), s.id.clone()), s))
+            .collect();
+
+        let mut summary = RecorderSyncSummary::default();
+        let mut listed = HashSet::new();
+
+        for recorder_id in catalog.list_recorders().await? {
+            summary.recorders += 1;
+
+            for session_id in catalog.list_session_ids(&recorder_id).await? {
+                let key = (recorder_id.clone(), session_id.clone());
+                let known = indexed.get(&key);
+                listed.insert(key.clone());
+
+                // A closed session's files and times no longer change
+                if !full && known.is_some_and(|s| s.is_closed) {
+                    summary.sessions_unchanged += 1;
+                    continue;
+                }
+
+                let mut session = match catalog.session_details(&recorder_id, &session_id).await {
+                    Ok(session) => session,
+                    Err(e) => {
+                        tracing::warn!("Could not fetch recorder session {}/{}: {}", recorder_id, session_id, e);
+                        summary.sessions_failed += 1;
+                        continue;
+                    }
+                };
+                // Stored under the ids it was listed with; the metadata's own can disagree
+                session.recorder_id = recorder_id.clone();
+                session.id = session_id;
+
+                match known {
+                    Some(existing) if *existing == session => summary.sessions_unchanged += 1,
+                    Some(_) => {
+                        self.upsert_session(&session).await?;
+                        summary.sessions_updated += 1;
+                    }
+                    None => {
+                        self.upsert_session(&session).await?;
+                        summary.sessions_added += 1;
+                    }
+                }
+            }
+        }
+
+        indexed.retain(|key, _| !listed.contains(key));
+        for (recorder_id, session_id) in indexed.keys() {
+            sqlx::query("DELETE FROM recorder_sessions WHERE recorder_id = ? AND id = ?")
+                .bind(recorder_id)
+                .bind(session_id)
+                .execute(&self.db)
+                .await?;
+            summary.sessions_removed += 1;
+        }
+
+        Ok(summary)
+    }
+
+    /// Fetch one session from the catalog and store it, e.g. when the recorder reports a change
+    pub async fn refresh_session<C: RecorderCatalog>(&self, catalog: &C, recorder_id: &str, session_id: &str) -> Result<RecorderSession> {
+        let mut session = catalog.session_details(recorder_id, session_id).await?;
+        session.recorder_id = recorder_id.to_string();
+        session.id = session_id.to_string();
+
+        self.upsert_session(&session).await?;
+        Ok(session)
+    }
+
+    async fn upsert_session(&self, session: &RecorderSession) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO recorder_sessions (recorder_id, id, name, start_time, end_time, duration_seconds, is_closed, keep,
+                                           ogg_url, flac_url, waveform_url, overview_png_url, metadata_url, synced_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT (recorder_id, id) DO UPDATE SET
+                name = excluded.name, start_time = excluded.start_time, end_time = excluded.end_time,
+                duration_seconds = excluded.duration_seconds, is_closed = excluded.is_closed, keep = excluded.keep,
+                ogg_url = excluded.ogg_url, flac_url = excluded.flac_url, waveform_url = excluded.waveform_url,
+                overview_png_url = excluded.overview_png_url, metadata_url = excluded.metadata_url,
+                synced_at = excluded.synced_at
+            "#,
+        )
+        .bind(&session.recorder_id)
+        .bind(&session.id)
+        .bind(&session.name)
+        .bind(session.start_time)
+        .bind(session.end_time)
+        .bind(session.duration_seconds)
+        .bind(session.is_closed)
+        .bind(session.keep)
+        .bind(&session.files.ogg_url)
+        .bind(&session.files.flac_url)
+        .bind(&session.files.waveform_url)
+        .bind(&session.files.overview_png_url)
+        .bind(&session.files.metadata_url)
+        .bind(Utc::now())
+        .execute(&self.db)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_sync_status(&self) -> Result<RecorderSyncStatus> {
+        let row = sqlx::query(
+            r#"
+            SELECT running, last_started_at, last_finished_at, last_error, recorders,
+                   sessions_added, sessions_updated, sessions_removed,
+                   (SELECT COUNT(*) FROM recorder_sessions) AS indexed_sessions
+            FROM recorder_sync_status WHERE id = 1
+            "#,
+        )
+        .fetch_one(&self.db)
+        .await?;
+
+        Ok(RecorderSyncStatus {
+            running: row.get("running"),
+            last_started_at: row.get("last_started_at"),
+            last_finished_at: row.get("last_finished_at"),
+            last_error: row.get("last_error"),
+            recorders: row.get("recorders"),
+            indexed_sessions: row.get("indexed_sessions"),
+            sessions_added: row.get("sessions_added"),
+            sessions_updated: row.get("sessions_updated"),
+            sessions_removed: row.get("sessions_removed"),
+        })
+    }
+
+    /// Every indexed session, most recent first
+    pub async fn get_sessions(&self) -> Result<Vec<RecorderSession>> {
+        let rows = sqlx::query(&format!("SELECT {} FROM recorder_sessions ORDER BY start_time DESC", SESSION_COLUMNS))
+            .fetch_all(&self.db)
+            .await?;
+
+        Ok(rows.iter().map(session_from_row).collect())
+    }
+
+    /// Indexed sessions started within the last `hours`, most recent first
+    pub async fn get_recent_sessions(&self, hours: i64) -> Result<Vec<RecorderSession>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM recorder_sessions WHERE start_time >= ? ORDER BY start_time DESC",
+            SESSION_COLUMNS
+        ))
+        .bind(Utc::now() - Duration::hours(hours))
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(rows.iter().map(session_from_row).collect())
+    }
+
+    pub async fn get_session(&self, recorder_id: &str, session_id: &str) -> Result<Option<RecorderSession>> {
+        let row = sqlx::query(&format!("SELECT {} FROM recorder_sessions WHERE recorder_id = ? AND id = ?", SESSION_COLUMNS))
+            .bind(recorder_id)
+            .bind(session_id)
+            .fetch_optional(&self.db)
+            .await?;
+
+        Ok(row.as_ref().map(session_from_row))
+    }
+}
+
+fn session_from_row(row: &SqliteRow) -> RecorderSession {
+    RecorderSession {
+        id: row.get("id"),
+        recorder_id: row.get("recorder_id"),
+        name: row.get("name"),
+        start_time: row.get("start_time"),
+        end_time: row.get("end_time"),
+        duration_seconds: row.get("duration_seconds"),
+        is_closed: row.get("is_closed"),
+        keep: row.get("keep"),
+        files: SessionFiles {
+            ogg_url: row.get("ogg_url"),
+            flac_url: row.get("flac_url"),
+            waveform_url: row.get("waveform_url"),
+            overview_png_url: row.get("overview_png_url"),
+            metadata_url: row.get("metadata_url"),
+        },
+    }
+}
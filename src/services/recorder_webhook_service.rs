@@ -0,0 +1,172 @@
+use crate::models::{
+    recorder_index::RecorderSyncError,
+    recorder_match::{MatchOutcome, RecorderMatcher},
+    recorder_webhook::{RecorderWebhookError, RecorderWebhookPayload, RecorderWebhookReport},
+    AppState,
+};
+use crate::services::{RecorderCatalog, RecorderIndexService, RecorderSession, SessionRecorderConfig, SessionRecorderService, SessionService};
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use sqlx::{Row, SqlitePool};
+use std::sync::Arc;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Handles notifications from the session recorder (or MinIO) so new recordings are
+/// indexed, linked and delivered without waiting for the next poll
+pub struct RecorderWebhookService {
+    db: SqlitePool,
+    app_state: Arc<AppState>,
+}
+
+impl RecorderWebhookService {
+    pub fn new(app_state: Arc<AppState>) -> Self {
+        Self {
+            db: app_state.db.clone(),
+            app_state,
+        }
+    }
+
+    /// Check `sha256=<hex>` against the HMAC-SHA256 of the raw body
+    pub fn verify_signature(&self, body: &[u8], signature: Option<&str>) -> Result<()> {
+        let secret = self
+            .app_state
+            .config
+            .session_recorder_config
+            .webhook_secret
+            .as_ref()
+            .ok_or(RecorderWebhookError::NotConfigured)?;
+
+        let signature = signature
+            .and_then(|s| s.trim().strip_prefix("sha256="))
+            .and_then(|hex_digest| hex::decode(hex_digest).ok())
+            .ok_or(RecorderWebhookError::InvalidSignature)?;
+
+        let mut mac = HmacSha256::new_from_slice(secret.as_bytes())?;
+        mac.update(body);
+        // Constant-time comparison
+        mac.verify_slice(&signature).map_err(|_| RecorderWebhookError::InvalidSignature)?;
+
+        Ok(())
+    }
+
+    /// Verify, parse and process one webhook call against the configured recorder bucket
+    pub async fn handle_webhook(&self, body: &[u8], signature: Option<&str>) -> Result<RecorderWebhookReport> {
+        self.verify_signature(body, signature)?;
+
+        let payload: RecorderWebhookPayload =
+            serde_json::from_slice(body).map_err(|e| RecorderWebhookError::InvalidPayload(e.to_string()))?;
+
+        let config = &self.app_state.config.session_recorder_config;
+        if !config.enabled {
+            return Err(RecorderSyncError::Disabled.into());
+        }
+        let catalog = SessionRecorderService::new(SessionRecorderConfig::from(config)).await?;
+
+        self.process_payload(&catalog, &payload).await
+    }
+
+    /// Re-index every recorder session the payload mentions, auto-link the DJ sessions it
+    /// overlaps and start delivery for those whose recording is complete
+    pub async fn process_payload<C: RecorderCatalog>(
+        &self,
+        catalog: &C,
+        payload: &RecorderWebhookPayload,
+    ) -> Result<RecorderWebhookReport> {
+        let index = RecorderIndexService::new(self.app_state.clone());
+        let session_service = SessionService::new(self.app_state.clone());
+        let tolerance = Duration::minutes(self.app_state.config.session_recorder_config.auto_link_tolerance_minutes);
+
+        match payload {
+            RecorderWebhookPayload::Recorder(event) => tracing::info!(
+                "Recorder webhook: {:?} for {}/{}", event.event, event.recorder_id, event.session_id
+            ),
+            RecorderWebhookPayload::BucketNotification(notification) => tracing::info!(
+                "Bucket notification with {} record(s)", notification.records.len()
+            ),
+        }
+
+        let mut report = RecorderWebhookReport::default();
+
+        for (recorder_id, session_id) in payload.affected_sessions() {
+            let recording = index.refresh_session(catalog, &recorder_id, &session_id).await?;
+            report.indexed.push(format!("{}/{}", recorder_id, session_id));
+
+            let window = recording.window();
+            let window_end = window.ended_at.unwrap_or_else(Utc::now);
+            for dj_session_id in self.unlinked_sessions_between(window.started_at - tolerance, window_end + tolerance).await? {
+                if let Some(result) = session_service.auto_link_recorder_session(&dj_session_id, None).await? {
+                    if matches!(result.outcome, MatchOutcome::Matched { .. }) {
+                        report.linked.push(result);
+                    }
+                }
+            }
+
+            for dj_session_id in self.deliverable_sessions(&recording).await? {
+                // Picks up files that landed after the session was linked
+                session_service.link_to_recorder_session(&dj_session_id, &session_id, &recorder_id).await?;
+                if session_service.deliver_recording(&dj_session_id).await? {
+                    report.deliveries_started.push(dj_session_id);
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    async fn unlinked_sessions_between(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<Vec<String>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id FROM sessions
+            WHERE recorder_session_id IS NULL AND started_at <= ? AND (ended_at IS NULL OR ended_at >= ?)
+            ORDER BY started_at ASC
+            "#,
+        )
+        .bind(to)
+        .bind(from)
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(rows.iter().map(|row| row.get("id")).collect())
+    }
+
+    /// Finished DJ sessions linked to a closed recording that holds just their set. An
+    /// all-night recording is split first (see segmentation), not sent whole to every DJ.
+    async fn deliverable_sessions(&self, recording: &RecorderSession) -> Result<Vec<String>> {
+        if !recording.is_closed {
+            return Ok(Vec::new());
+        }
+
+        let rows = sqlx::query(
+            r#"
+            SELECT id, started_at, ended_at FROM sessions
+            WHERE recorder_id = ? AND recorder_session_id = ? AND ended_at IS NOT NULL
+            ORDER BY started_at ASC
+            "#,
+        )
+        .bind(&recording.recorder_id)
+        .bind(&recording.id)
+        .fetch_all(&self.db)
+        .await?;
+
+        let matcher = RecorderMatcher::new(self.app_state.config.session_recorder_config.auto_link_tolerance_minutes);
+        let windows = [recording.window()];
+        let mut session_ids = Vec::new();
+
+        for row in rows {
+            let session_id: String = row.get("id");
+            let outcome = matcher.match_session(row.get("started_at"), row.get("ended_at"), &windows, Utc::now());
+            match outcome {
+                MatchOutcome::Matched { candidate } if candidate.split_required => {
+                    tracing::info!("Recording {}/{} holds more than session {}; segment it before delivery",
+                                  recording.recorder_id, recording.id, session_id);
+                }
+                _ => session_ids.push(session_id),
+            }
+        }
+
+        Ok(session_ids)
+    }
+}
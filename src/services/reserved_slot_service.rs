@@ -0,0 +1,167 @@
+use crate::models::{
+    dj::{CreateDjRequest, Dj},
+    reserved_slot::{CreateReservedSlotRequest, ReservedSlot, ReservedSlotError, ReservedSlotResponse},
+    AppState,
+};
+use crate::services::{DjService, EventService, LotteryService};
+use anyhow::Result;
+use sqlx::{Row, SqlitePool};
+use std::sync::Arc;
+
+pub struct ReservedSlotService {
+    db: SqlitePool,
+    app_state: Arc<AppState>,
+}
+
+impl ReservedSlotService {
+    pub fn new(app_state: Arc<AppState>) -> Self {
+        Self {
+            db: app_state.db.clone(),
+            app_state,
+        }
+    }
+
+    pub async fn get_reserved_slots(&self) -> Result<Vec<ReservedSlotResponse>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT r.id, r.dj_id, d.name as dj_name, r.position, r.kind, r.label, r.starts_at, r.created_at
+            FROM reserved_slots r
+            LEFT JOIN djs d ON d.id = r.dj_id
+            ORDER BY r.position ASC
+            "#,
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| ReservedSlotResponse {
+                id: row.get("id"),
+                dj_id: row.get("dj_id"),
+                dj_name: row.get("dj_name"),
+                position: row.get("position"),
+                kind: row.get("kind"),
+                label: row.get("label"),
+                starts_at: row.get("starts_at"),
+                created_at: row.get("created_at"),
+            })
+            .collect())
+    }
+
+    pub async fn get_reserved_slot_by_position(&self, position: i32) -> Result<Option<ReservedSlot>> {
+        let slot = sqlx::query_as::<_, ReservedSlot>("SELECT * FROM reserved_slots WHERE position = ?")
+            .bind(position)
+            .fetch_optional(&self.db)
+            .await?;
+
+        Ok(slot)
+    }
+
+    /// Book a queue position for a DJ. Lottery DJs already queued at or after that
+    /// position move back to make room.
+    pub async fn create_reserved_slot(&self, request: CreateReservedSlotRequest) -> Result<ReservedSlotResponse> {
+        let event = EventService::new(self.app_state.clone()).get_active_event().await?;
+
+        let position = match (request.position, request.starts_at) {
+            (Some(position), _) => position,
+            (None, Some(starts_at)) => {
+                let event = event.as_ref().ok_or(ReservedSlotError::NoActiveEvent)?;
+                ReservedSlot::position_for_time(event.started_at, event.slot_duration_minutes, starts_at)
+            }
+            (None, None) => return Err(ReservedSlotError::MissingPosition.into()),
+        };
+        if position < 1 {
+            return Err(ReservedSlotError::InvalidPosition.into());
+        }
+        if self.get_reserved_slot_by_position(position).await?.is_some() {
+            return Err(ReservedSlotError::PositionTaken(position).into());
+        }
+
+        // Slots up to the one currently playing can't be booked any more
+        if let Some(current_dj_id) = event.as_ref().and_then(|e| e.current_dj_id.as_ref()) {
+            let current_position = sqlx::query("SELECT position_in_queue FROM djs WHERE id = ?")
+                .bind(current_dj_id)
+                .fetch_optional(&self.db)
+                .await?
+                .and_then(|row| row.get::<Option<i32>, _>("position_in_queue"));
+            if matches!(current_position, Some(current) if position <= current) {
+                return Err(ReservedSlotError::PositionPassed(position).into());
+            }
+        }
+
+        let dj_id = match (request.dj_id, request.dj_name) {
+            (Some(dj_id), _) => {
+                let exists = sqlx::query_as::<_, Dj>("SELECT * FROM djs WHERE id = ?")
+                    .bind(&dj_id)
+                    .fetch_optional(&self.db)
+                    .await?;
+                exists.ok_or(ReservedSlotError::DjNotFound)?.id
+            }
+            (None, Some(name)) => {
+                let dj_service = DjService::new(self.app_state.clone());
+                dj_service.register_dj(CreateDjRequest { name, email: None }).await?.id
+            }
+            (None, None) => return Err(ReservedSlotError::MissingDj.into()),
+        };
+
+        let already_reserved = sqlx::query("SELECT id FROM reserved_slots WHERE dj_id = ?")
+            .bind(&dj_id)
+            .fetch_optional(&self.db)
+            .await?;
+        if already_reserved.is_some() {
+            return Err(ReservedSlotError::AlreadyReserved.into());
+        }
+
+        let slot = ReservedSlot::new(dj_id, position, request.kind, request.label, request.starts_at);
+
+        sqlx::query(
+            r#"
+            INSERT INTO reserved_slots (id, dj_id, position, kind, label, starts_at, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&slot.id)
+        .bind(&slot.dj_id)
+        .bind(slot.position)
+        .bind(slot.kind)
+        .bind(&slot.label)
+        .bind(slot.starts_at)
+        .bind(slot.created_at)
+        .execute(&self.db)
+        .await?;
+
+        LotteryService::new(self.app_state.clone()).compact_queue().await?;
+
+        self.get_reserved_slots()
+            .await?
+            .into_iter()
+            .find(|s| s.id == slot.id)
+            .ok_or_else(|| ReservedSlotError::NotFound.into())
+    }
+
+    /// Cancel a booking; the DJ goes back into the lottery pool
+    pub async fn delete_reserved_slot(&self, id: &str) -> Result<()> {
+        let dj_id = sqlx::query("SELECT dj_id FROM reserved_slots WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await?
+            .ok_or(ReservedSlotError::NotFound)?
+            .get::<String, _>("dj_id");
+
+        let mut tx = self.db.begin().await?;
+
+        sqlx::query("DELETE FROM reserved_slots WHERE id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("UPDATE djs SET position_in_queue = NULL WHERE id = ?")
+            .bind(&dj_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        LotteryService::new(self.app_state.clone()).compact_queue().await
+    }
+}
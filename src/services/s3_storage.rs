@@ -0,0 +1,284 @@
+use crate::models::storage::{ByteRange, S3StorageConfig, StorageError, StorageListing, StorageObject};
+use crate::services::{StorageBackend, StorageFuture, StorageStream};
+use aws_config::{BehaviorVersion, Region};
+use aws_credential_types::Credentials;
+use anyhow::{anyhow, Result};
+use aws_sdk_s3::{
+    primitives::ByteStream,
+    types::{CompletedMultipartUpload, CompletedPart},
+    Client as S3Client, Config as S3Config,
+};
+use std::path::Path;
+use std::pin::Pin;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// Streams are uploaded in parts of this size; S3 wants at least 5 MiB for all but the last
+const MULTIPART_PART_SIZE: usize = 8 * 1024 * 1024;
+
+/// Storage in an S3 bucket (MinIO or AWS)
+pub struct S3Storage {
+    client: S3Client,
+    bucket: String,
+    public_endpoint: String,
+}
+
+impl S3Storage {
+    pub fn new(config: &S3StorageConfig) -> Self {
+        let credentials = Credentials::new(
+            &config.access_key,
+            &config.secret_key,
+            None,
+            None,
+            "slotify-storage",
+        );
+
+        let s3_config = S3Config::builder()
+            .behavior_version(BehaviorVersion::latest())
+            .region(Region::new(config.region.clone()))
+            .endpoint_url(&config.endpoint)
+            .credentials_provider(credentials)
+            .force_path_style(true)
+            .build();
+
+        Self {
+            client: S3Client::from_conf(s3_config),
+            bucket: config.bucket.clone(),
+            public_endpoint: config.public_endpoint.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Upload `first` and the rest of `body` as parts of a multipart upload
+    async fn upload_parts(
+        &self,
+        key: &str,
+        upload_id: &str,
+        first: Vec<u8>,
+        body: &mut Pin<Box<dyn AsyncRead + Send>>,
+    ) -> Result<Vec<CompletedPart>> {
+        let mut parts = Vec::new();
+        let mut part = first;
+        while !part.is_empty() {
+            let part_number = parts.len() as i32 + 1;
+            let response = self
+                .client
+                .upload_part()
+                .bucket(&self.bucket)
+                .key(key)
+                .upload_id(upload_id)
+                .part_number(part_number)
+                .body(ByteStream::from(part))
+                .send()
+                .await?;
+            parts.push(
+                CompletedPart::builder()
+                    .part_number(part_number)
+                    .set_e_tag(response.e_tag().map(str::to_string))
+                    .build(),
+            );
+            part = read_part(body).await?;
+        }
+        Ok(parts)
+    }
+}
+
+/// Up to one part's worth of `body`; shorter only at the end of the stream
+async fn read_part(body: &mut Pin<Box<dyn AsyncRead + Send>>) -> Result<Vec<u8>> {
+    let mut part = Vec::with_capacity(MULTIPART_PART_SIZE);
+    body.take(MULTIPART_PART_SIZE as u64).read_to_end(&mut part).await?;
+    Ok(part)
+}
+
+impl StorageBackend for S3Storage {
+    fn list<'a>(&'a self, prefix: &'a str) -> StorageFuture<'a, StorageListing> {
+        Box::pin(async move {
+            let prefix = match prefix.trim_end_matches('/') {
+                "" => String::new(),
+                trimmed => format!("{}/", trimmed),
+            };
+            let mut listing = StorageListing::default();
+            let mut continuation_token: Option<String> = None;
+
+            // Listings are paged at 1000 keys
+            loop {
+                let response = self
+                    .client
+                    .list_objects_v2()
+                    .bucket(&self.bucket)
+                    .prefix(&prefix)
+                    .delimiter("/")
+                    .set_continuation_token(continuation_token.take())
+                    .send()
+                    .await?;
+
+                for common_prefix in response.common_prefixes() {
+                    if let Some(dir) = common_prefix.prefix() {
+                        let name = dir.strip_prefix(prefix.as_str()).unwrap_or(dir).trim_end_matches('/');
+                        if !name.is_empty() {
+                            listing.directories.push(name.to_string());
+                        }
+                    }
+                }
+                for object in response.contents() {
+                    if let Some(key) = object.key().filter(|key| !key.ends_with('/')) {
+                        listing.objects.push(StorageObject {
+                            key: key.to_string(),
+                            size: object.size().unwrap_or(0).max(0) as u64,
+                        });
+                    }
+                }
+
+                match response.next_continuation_token() {
+                    Some(token) if response.is_truncated().unwrap_or(false) => continuation_token = Some(token.to_string()),
+                    _ => break,
+                }
+            }
+
+            Ok(listing)
+        })
+    }
+
+    fn get<'a>(&'a self, key: &'a str) -> StorageFuture<'a, Vec<u8>> {
+        Box::pin(async move {
+            let response = self
+                .client
+                .get_object()
+                .bucket(&self.bucket)
+                .key(key)
+                .send()
+                .await
+                .map_err(|e| match e.as_service_error() {
+                    Some(service_error) if service_error.is_no_such_key() => StorageError::NotFound(key.to_string()).into(),
+                    _ => anyhow::Error::from(e),
+                })?;
+
+            Ok(response.body.collect().await?.into_bytes().to_vec())
+        })
+    }
+
+    fn put<'a>(&'a self, key: &'a str, data: Vec<u8>) -> StorageFuture<'a, ()> {
+        Box::pin(async move {
+            self.client
+                .put_object()
+                .bucket(&self.bucket)
+                .key(key)
+                .body(ByteStream::from(data))
+                .send()
+                .await?;
+            Ok(())
+        })
+    }
+
+    fn put_file<'a>(&'a self, key: &'a str, path: &'a Path) -> StorageFuture<'a, ()> {
+        Box::pin(async move {
+            self.client
+                .put_object()
+                .bucket(&self.bucket)
+                .key(key)
+                .body(ByteStream::from_path(path).await?)
+                .send()
+                .await?;
+            Ok(())
+        })
+    }
+
+    fn put_stream<'a>(&'a self, key: &'a str, mut body: Pin<Box<dyn AsyncRead + Send>>) -> StorageFuture<'a, ()> {
+        Box::pin(async move {
+            // Anything that fits in one part goes up as a plain PUT
+            let first = read_part(&mut body).await?;
+            if first.len() < MULTIPART_PART_SIZE {
+                return self.put(key, first).await;
+            }
+
+            let upload = self.client.create_multipart_upload().bucket(&self.bucket).key(key).send().await?;
+            let upload_id = upload.upload_id().ok_or_else(|| anyhow!("S3 returned no upload id for {}", key))?;
+
+            match self.upload_parts(key, upload_id, first, &mut body).await {
+                Ok(parts) => {
+                    self.client
+                        .complete_multipart_upload()
+                        .bucket(&self.bucket)
+                        .key(key)
+                        .upload_id(upload_id)
+                        .multipart_upload(CompletedMultipartUpload::builder().set_parts(Some(parts)).build())
+                        .send()
+                        .await?;
+                    Ok(())
+                }
+                Err(e) => {
+                    // Don't leave orphaned parts in the bucket
+                    if let Err(abort_error) = self
+                        .client
+                        .abort_multipart_upload()
+                        .bucket(&self.bucket)
+                        .key(key)
+                        .upload_id(upload_id)
+                        .send()
+                        .await
+                    {
+                        tracing::warn!("Could not abort upload of {}: {}", key, abort_error);
+                    }
+                    Err(e)
+                }
+            }
+        })
+    }
+
+    fn stream<'a>(&'a self, key: &'a str, range: Option<ByteRange>) -> StorageFuture<'a, StorageStream> {
+        Box::pin(async move {
+            // The size is needed to resolve the range (and by callers for Content-Range)
+            let head = self
+                .client
+                .head_object()
+                .bucket(&self.bucket)
+                .key(key)
+                .send()
+                .await
+                .map_err(|e| match e.as_service_error() {
+                    Some(service_error) if service_error.is_not_found() => StorageError::NotFound(key.to_string()).into(),
+                    _ => anyhow::Error::from(e),
+                })?;
+            let size = head.content_length().unwrap_or(0).max(0) as u64;
+            let range = match range {
+                Some(range) => range.resolve(size)?,
+                None => 0..size,
+            };
+
+            let mut request = self.client.get_object().bucket(&self.bucket).key(key);
+            if range.start > 0 || range.end < size {
+                request = request.range(format!("bytes={}-{}", range.start, range.end - 1));
+            }
+            let response = request.send().await?;
+
+            Ok(StorageStream {
+                body: Box::pin(response.body.into_async_read()),
+                size,
+                range,
+            })
+        })
+    }
+
+    fn presign<'a>(&'a self, key: &'a str, expires_in: Duration) -> StorageFuture<'a, Option<String>> {
+        Box::pin(async move {
+            let presigned = self
+                .client
+                .get_object()
+                .bucket(&self.bucket)
+                .key(key)
+                .presigned(aws_sdk_s3::presigning::PresigningConfig::expires_in(expires_in)?)
+                .await?;
+            Ok(Some(presigned.uri().to_string()))
+        })
+    }
+
+    fn delete<'a>(&'a self, key: &'a str) -> StorageFuture<'a, ()> {
+        Box::pin(async move {
+            self.client.delete_object().bucket(&self.bucket).key(key).send().await?;
+            Ok(())
+        })
+    }
+
+    fn public_url(&self, key: &str) -> String {
+        format!("{}/{}", self.public_endpoint, key)
+    }
+}
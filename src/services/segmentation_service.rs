@@ -0,0 +1,254 @@
+use crate::audio::{split_recording, AudioDecoder, SegmentRange};
+use crate::models::{
+    segmentation::{SegmentRecordingRequest, SegmentationError, SegmentationReport, SessionSegment},
+    AppState,
+};
+use crate::services::{SessionRecorderConfig, SessionRecorderService, SessionService};
+use crate::utils::sanitize_filename;
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use sqlx::{Row, SqlitePool};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+
+struct SessionWindow {
+    session_id: String,
+    dj_id: String,
+    dj_name: String,
+    started_at: DateTime<Utc>,
+    ended_at: Option<DateTime<Utc>>,
+}
+
+pub struct SegmentationService {
+    db: SqlitePool,
+    app_state: Arc<AppState>,
+}
+
+impl SegmentationService {
+    pub fn new(app_state: Arc<AppState>) -> Self {
+        Self {
+            db: app_state.db.clone(),
+            app_state,
+        }
+    }
+
+    /// Split one continuous recording at the DJ sessions' boundaries and attach each
+    /// segment to its session as `file_path`
+    pub async fn segment_recording(&self, request: SegmentRecordingRequest) -> Result<SegmentationReport> {
+        let config = &self.app_state.config.segmentation_config;
+        let pre_roll_seconds = request.pre_roll_seconds.unwrap_or(config.pre_roll_seconds);
+        let post_roll_seconds = request.post_roll_seconds.unwrap_or(config.post_roll_seconds);
+
+        // Keeps a downloaded recording alive until the split is done
+        let mut _download: Option<tempfile::NamedTempFile> = None;
+        let mut recorder_ended_at = None;
+
+        let (source, recording_started_at) = match (&request.file_path, &request.recorder_id, &request.recorder_session_id) {
+            (Some(file_path), _, _) => {
+                let path = PathBuf::from(file_path);
+                if !path.is_file() {
+                    return Err(SegmentationError::FileNotFound.into());
+                }
+                let started_at = request.recording_started_at.ok_or(SegmentationError::MissingStartTime)?;
+                (path, started_at)
+            }
+            (None, Some(recorder_id), Some(recorder_session_id)) => {
+                let (file, started_at, ended_at) = self.download_recording(recorder_id, recorder_session_id).await?;
+                let path = file.path().to_path_buf();
+                _download = Some(file);
+                recorder_ended_at = ended_at;
+                (path, request.recording_started_at.unwrap_or(started_at))
+            }
+            _ => return Err(SegmentationError::MissingSource.into()),
+        };
+
+        // The file's own length is exact; the recorder's end time is the fallback
+        let length = {
+            let source = source.clone();
+            tokio::task::spawn_blocking(move || -> Result<Option<f64>> {
+                let decoder = AudioDecoder::open(&source)?;
+                Ok(decoder.frames().map(|frames| decoder.spec().frames_to_seconds(frames)))
+            })
+            .await??
+        };
+        let recording_ended_at = length
+            .map(|seconds| recording_started_at + Duration::milliseconds((seconds * 1000.0) as i64))
+            .or(recorder_ended_at);
+
+        let windows = self.sessions_during(recording_started_at, recording_ended_at).await?;
+        let ranges: Vec<SegmentRange> = windows
+            .iter()
+            .map(|window| SegmentRange {
+                id: window.session_id.clone(),
+                start_ms: offset_ms(recording_started_at, window.started_at, -(pre_roll_seconds as i64)),
+                end_ms: window
+                    .ended_at
+                    .map(|ended_at| offset_ms(recording_started_at, ended_at, post_roll_seconds as i64)),
+                output_path: config.output_dir.join(segment_filename(window)),
+            })
+            .collect();
+
+        let split = tokio::task::spawn_blocking(move || split_recording(&source, &ranges)).await??;
+
+        let by_id: HashMap<&str, &SessionWindow> = windows.iter().map(|w| (w.session_id.as_str(), w)).collect();
+        let session_service = SessionService::new(self.app_state.clone());
+        let mut segments = Vec::new();
+        for written in &split.segments {
+            let window = by_id[written.id.as_str()];
+            let file_path = written.output_path.to_string_lossy().to_string();
+
+            sqlx::query(
+                r#"
+                UPDATE sessions
+                SET file_path = ?,
+                    recorder_id = COALESCE(?, recorder_id),
+                    recorder_session_id = COALESCE(?, recorder_session_id)
+                WHERE id = ?
+                "#,
+            )
+            .bind(&file_path)
+            .bind(&request.recorder_id)
+            .bind(&request.recorder_session_id)
+            .bind(&written.id)
+            .execute(&self.db)
+            .await?;
+
+            // Finished sets go out right away; a running one is split again once it ends
+            if window.ended_at.is_some() {
+                session_service.publish_session_file(&written.id).await?;
+            }
+
+            segments.push(SessionSegment {
+                session_id: written.id.clone(),
+                dj_id: window.dj_id.clone(),
+                dj_name: window.dj_name.clone(),
+                file_path,
+                offset_seconds: split.spec.frames_to_seconds(written.start_frame),
+                duration_seconds: split.spec.frames_to_seconds(written.frames),
+            });
+        }
+
+        let skipped_session_ids = windows
+            .iter()
+            .filter(|w| !split.segments.iter().any(|s| s.id == w.session_id))
+            .map(|w| w.session_id.clone())
+            .collect();
+
+        tracing::info!(
+            "Split recording from {} into {} session files",
+            recording_started_at,
+            segments.len()
+        );
+
+        Ok(SegmentationReport {
+            recording_started_at,
+            recording_duration_seconds: split.spec.frames_to_seconds(split.total_frames),
+            sample_rate: split.spec.sample_rate,
+            channels: split.spec.channels,
+            pre_roll_seconds,
+            post_roll_seconds,
+            segments,
+            skipped_session_ids,
+        })
+    }
+
+    /// Sessions overlapping the recording, in play order. A recording still running ends now.
+    /// Sessions from before the earliest event the recording overlaps are left out, so a set
+    /// never ended on an earlier night isn't cut out of this one.
+    async fn sessions_during(
+        &self,
+        recording_started_at: DateTime<Utc>,
+        recording_ended_at: Option<DateTime<Utc>>,
+    ) -> Result<Vec<SessionWindow>> {
+        let recording_ended_at = recording_ended_at.unwrap_or_else(Utc::now);
+        let rows = sqlx::query(
+            r#"
+            SELECT s.id, s.dj_id, d.name as dj_name, s.started_at, s.ended_at
+            FROM sessions s
+            LEFT JOIN djs d ON d.id = s.dj_id
+            WHERE (s.ended_at IS NULL OR s.ended_at > ?)
+            AND s.started_at < ?
+            AND s.started_at >= COALESCE(
+                (SELECT MIN(e.started_at) FROM event_sessions e
+                 WHERE e.started_at < ? AND (e.ended_at IS NULL OR e.ended_at > ?)),
+                s.started_at
+            )
+            ORDER BY s.started_at ASC
+            "#,
+        )
+        .bind(recording_started_at)
+        .bind(recording_ended_at)
+        .bind(recording_ended_at)
+        .bind(recording_started_at)
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| SessionWindow {
+                session_id: row.get("id"),
+                dj_id: row.get("dj_id"),
+                dj_name: row
+                    .get::<Option<String>, _>("dj_name")
+                    .unwrap_or_else(|| "Unknown DJ".to_string()),
+                started_at: row.get("started_at"),
+                ended_at: row.get("ended_at"),
+            })
+            .collect())
+    }
+
+    /// Fetch the recorder's FLAC (preferred) or OGG into a temporary file, with the times the
+    /// recording started and, once it is closed, ended
+    async fn download_recording(
+        &self,
+        recorder_id: &str,
+        recorder_session_id: &str,
+    ) -> Result<(tempfile::NamedTempFile, DateTime<Utc>, Option<DateTime<Utc>>)> {
+        let settings = &self.app_state.config.session_recorder_config;
+        if !settings.enabled {
+            return Err(SegmentationError::RecorderUnavailable.into());
+        }
+        let recorder = SessionRecorderService::new(SessionRecorderConfig::from(settings))
+            .await
+            .map_err(|_| SegmentationError::RecorderUnavailable)?;
+        let details = recorder
+            .get_session_details(recorder_id, recorder_session_id)
+            .await
+            .map_err(|_| SegmentationError::RecorderUnavailable)?;
+
+        let filename = match (&details.files.flac_url, &details.files.ogg_url) {
+            (Some(_), _) => "data.flac",
+            (None, Some(_)) => "data.ogg",
+            (None, None) => return Err(SegmentationError::NoRecording.into()),
+        };
+        let mut recording = recorder.stream_session_file(recorder_id, recorder_session_id, filename, None).await?;
+
+        let extension = Path::new(filename).extension().and_then(|e| e.to_str()).unwrap_or("flac");
+        let file = tempfile::Builder::new()
+            .suffix(&format!(".{}", extension))
+            .tempfile()?;
+        // All-night recordings run to gigabytes; copy without holding them in memory
+        let mut writer = tokio::fs::File::from_std(file.reopen()?);
+        tokio::io::copy(&mut recording.body, &mut writer).await?;
+        writer.flush().await?;
+
+        Ok((file, details.start_time, details.end_time))
+    }
+}
+
+/// Milliseconds from the recording start to `at` shifted by `shift_seconds`, clamped at zero
+fn offset_ms(recording_started_at: DateTime<Utc>, at: DateTime<Utc>, shift_seconds: i64) -> u64 {
+    let offset = at.signed_duration_since(recording_started_at).num_milliseconds() + shift_seconds * 1000;
+    offset.max(0) as u64
+}
+
+fn segment_filename(window: &SessionWindow) -> String {
+    format!(
+        "{}_{}_session_{}.flac",
+        sanitize_filename(&window.dj_name),
+        window.started_at.format("%Y%m%d_%H%M%S"),
+        window.session_id.chars().take(8).collect::<String>()
+    )
+}
@@ -1,15 +1,17 @@
 use anyhow::Result;
-use aws_config::{BehaviorVersion, Region};
-use aws_credential_types::Credentials;
-use aws_sdk_s3::{Client as S3Client, Config as S3Config};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use uuid::Uuid;
+use tokio::io::AsyncWriteExt;
 
-use crate::models::AppState;
+use crate::models::{
+    storage::{ByteRange, S3StorageConfig},
+    MatchOutcome, RecorderMatcher, RecordingWindow, SessionRecorderIntegrationConfig,
+};
+use crate::services::{copy_object, LocalStorage, S3Storage, StorageBackend, StorageStream};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct RecorderSession {
     pub id: String,
     pub recorder_id: String,
@@ -22,7 +24,7 @@ pub struct RecorderSession {
     pub files: SessionFiles,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SessionFiles {
     pub ogg_url: Option<String>,
     pub flac_url: Option<String>,
@@ -50,6 +52,7 @@ pub struct SessionRecorderConfig {
     pub minio_secret_key: String,
     pub bucket_name: String,
     pub public_endpoint: String,
+    pub local_dir: Option<PathBuf>, // Read recordings from this directory instead of MinIO
 }
 
 impl Default for SessionRecorderConfig {
@@ -60,97 +63,102 @@ impl Default for SessionRecorderConfig {
             minio_secret_key: "password123".to_string(),
             bucket_name: "session-recorder".to_string(),
             public_endpoint: "http://localhost:9000".to_string(),
+            local_dir: None,
+        }
+    }
+}
+
+impl From<&SessionRecorderIntegrationConfig> for SessionRecorderConfig {
+    fn from(config: &SessionRecorderIntegrationConfig) -> Self {
+        Self {
+            minio_endpoint: config.minio_endpoint.clone(),
+            minio_access_key: config.minio_access_key.clone(),
+            minio_secret_key: config.minio_secret_key.clone(),
+            bucket_name: config.bucket_name.clone(),
+            public_endpoint: config.public_endpoint.clone(),
+            local_dir: config.local_dir.clone(),
+        }
+    }
+}
+
+impl RecorderSession {
+    /// Recording interval for matching; open while the recorder is still running
+    pub fn window(&self) -> RecordingWindow {
+        let ended_at = self.end_time.or_else(|| {
+            self.duration_seconds
+                .filter(|_| self.is_closed)
+                .map(|seconds| self.start_time + chrono::Duration::seconds(seconds))
+        });
+
+        RecordingWindow {
+            recorder_id: self.recorder_id.clone(),
+            recorder_session_id: self.id.clone(),
+            started_at: self.start_time,
+            ended_at,
         }
     }
 }
 
 pub struct SessionRecorderService {
-    s3_client: S3Client,
-    config: SessionRecorderConfig,
+    storage: Arc<dyn StorageBackend>,
 }
 
 impl SessionRecorderService {
+    /// Reads the recorder's MinIO bucket, or `local_dir` if the recordings are on this machine
     pub async fn new(config: SessionRecorderConfig) -> Result<Self> {
-        let credentials = Credentials::new(
-            &config.minio_access_key,
-            &config.minio_secret_key,
-            None,
-            None,
-            "session-recorder-integration",
-        );
-
-        let s3_config = S3Config::builder()
-            .behavior_version(BehaviorVersion::latest())
-            .region(Region::new("us-east-1"))
-            .endpoint_url(&config.minio_endpoint)
-            .credentials_provider(credentials)
-            .force_path_style(true)
-            .build();
-
-        let s3_client = S3Client::from_conf(s3_config);
-
-        Ok(Self { s3_client, config })
+        let storage: Arc<dyn StorageBackend> = match &config.local_dir {
+            Some(dir) => Arc::new(LocalStorage::new(dir, None)),
+            None => Arc::new(S3Storage::new(&S3StorageConfig {
+                endpoint: config.minio_endpoint.clone(),
+                region: "us-east-1".to_string(),
+                access_key: config.minio_access_key.clone(),
+                secret_key: config.minio_secret_key.clone(),
+                bucket: config.bucket_name.clone(),
+                public_endpoint: config.public_endpoint.clone(),
+            })),
+        };
+
+        Ok(Self::with_storage(storage))
     }
 
-    /// Get all available recorders from MinIO
-    pub async fn get_recorders(&self) -> Result<Vec<String>> {
-        let response = self
-            .s3_client
-            .list_objects_v2()
-            .bucket(&self.config.bucket_name)
-            .delimiter("/")
-            .send()
-            .await?;
-
-        let mut recorders = Vec::new();
-        
-        for prefix in response.common_prefixes() {
-            if let Some(prefix_str) = prefix.prefix() {
-                // Remove trailing slash and add to recorders
-                let recorder_id = prefix_str.trim_end_matches('/');
-                // Skip metadata.json at root level
-                if recorder_id != "metadata.json" && !recorder_id.is_empty() {
-                    recorders.push(recorder_id.to_string());
-                }
-            }
-        }
+    pub fn with_storage(storage: Arc<dyn StorageBackend>) -> Self {
+        Self { storage }
+    }
 
-        Ok(recorders)
+    /// Get all available recorders
+    pub async fn get_recorders(&self) -> Result<Vec<String>> {
+        let listing = self.storage.list("").await?;
+
+        Ok(listing
+            .directories
+            .into_iter()
+            // Skip metadata.json at root level
+            .filter(|recorder_id| recorder_id != "metadata.json" && !recorder_id.is_empty())
+            .collect())
     }
 
     /// Get all sessions for a specific recorder
     pub async fn get_sessions(&self, recorder_id: &str) -> Result<Vec<RecorderSession>> {
-        let sessions_prefix = format!("{}/sessions/", recorder_id);
-        
-        let response = self
-            .s3_client
-            .list_objects_v2()
-            .bucket(&self.config.bucket_name)
-            .prefix(&sessions_prefix)
-            .delimiter("/")
-            .send()
-            .await?;
-
         let mut sessions = Vec::new();
 
-        for prefix in response.common_prefixes() {
-            if let Some(prefix_str) = prefix.prefix() {
-                // Extract session ID from path like "recorder-id/sessions/session-id/"
-                let session_path = prefix_str.trim_end_matches('/');
-                if let Some(session_id) = session_path.split('/').last() {
-                    if let Ok(session) = self.get_session_details(recorder_id, session_id).await {
-                        sessions.push(session);
-                    }
-                }
+        for session_id in self.list_session_ids(recorder_id).await? {
+            if let Ok(session) = self.get_session_details(recorder_id, &session_id).await {
+                sessions.push(session);
             }
         }
 
         Ok(sessions)
     }
 
+    /// Ids of a recorder's sessions, without fetching their metadata
+    pub async fn list_session_ids(&self, recorder_id: &str) -> Result<Vec<String>> {
+        // Session directories look like "recorder-id/sessions/session-id/"
+        Ok(self.storage.list(&format!("{}/sessions/", recorder_id)).await?.directories)
+    }
+
     /// Get detailed information about a specific session
     pub async fn get_session_details(&self, recorder_id: &str, session_id: &str) -> Result<RecorderSession> {
-        let metadata_key = format!("{}/sessions/{}/metadata.json", recorder_id, session_id);
+        let metadata_key = session_file_key(recorder_id, session_id, "metadata.json");
         
         // Try to get metadata
         let metadata = match self.get_session_metadata(&metadata_key).await {
@@ -195,17 +203,9 @@ impl SessionRecorderService {
         })
     }
 
-    /// Get session metadata from MinIO
+    /// Get session metadata from the recorder's storage
     async fn get_session_metadata(&self, metadata_key: &str) -> Result<SessionMetadata> {
-        let response = self
-            .s3_client
-            .get_object()
-            .bucket(&self.config.bucket_name)
-            .key(metadata_key)
-            .send()
-            .await?;
-
-        let body = response.body.collect().await?.into_bytes();
+        let body = self.storage.get(metadata_key).await?;
         let metadata: SessionMetadata = serde_json::from_slice(&body)?;
         
         Ok(metadata)
@@ -213,15 +213,7 @@ impl SessionRecorderService {
 
     /// Get available file URLs for a session
     async fn get_session_files(&self, recorder_id: &str, session_id: &str) -> Result<SessionFiles> {
-        let session_prefix = format!("{}/sessions/{}/", recorder_id, session_id);
-        
-        let response = self
-            .s3_client
-            .list_objects_v2()
-            .bucket(&self.config.bucket_name)
-            .prefix(&session_prefix)
-            .send()
-            .await?;
+        let listing = self.storage.list(&format!("{}/sessions/{}/", recorder_id, session_id)).await?;
 
         let mut files = SessionFiles {
             ogg_url: None,
@@ -231,79 +223,83 @@ impl SessionRecorderService {
             metadata_url: None,
         };
 
-        for object in response.contents() {
-            if let Some(key) = object.key() {
-                if let Some(filename) = key.split('/').last() {
-                    let url = format!("{}/{}", self.config.public_endpoint, key);
-                    
-                    match filename {
-                        "data.ogg" => files.ogg_url = Some(url),
-                        "data.flac" => files.flac_url = Some(url),
-                        "waveform.dat" => files.waveform_url = Some(url),
-                        "overview.png" => files.overview_png_url = Some(url),
-                        "metadata.json" => files.metadata_url = Some(url),
-                        _ => {}
-                    }
-                }
+        for object in &listing.objects {
+            let url = self.storage.public_url(&object.key);
+
+            match object.name() {
+                "data.ogg" => files.ogg_url = Some(url),
+                "data.flac" => files.flac_url = Some(url),
+                "waveform.dat" => files.waveform_url = Some(url),
+                "overview.png" => files.overview_png_url = Some(url),
+                "metadata.json" => files.metadata_url = Some(url),
+                _ => {}
             }
         }
 
         Ok(files)
     }
 
-    /// Download a session file (OGG, FLAC, etc.)
-    pub async fn download_session_file(&self, recorder_id: &str, session_id: &str, filename: &str) -> Result<Vec<u8>> {
-        let key = format!("{}/sessions/{}/{}", recorder_id, session_id, filename);
-        
-        let response = self
-            .s3_client
-            .get_object()
-            .bucket(&self.config.bucket_name)
-            .key(&key)
-            .send()
-            .await?;
-
-        let body = response.body.collect().await?.into_bytes();
-        Ok(body.to_vec())
+    /// Save a session file (OGG, FLAC, etc.) to `destination`, streamed so memory use doesn't
+    /// grow with the recording; returns the number of bytes written
+    pub async fn download_session_file(&self, recorder_id: &str, session_id: &str, filename: &str, destination: &Path) -> Result<u64> {
+        let mut stream = self.stream_session_file(recorder_id, session_id, filename, None).await?;
+        let mut file = tokio::fs::File::create(destination).await?;
+        let written = tokio::io::copy(&mut stream.body, &mut file).await?;
+        file.flush().await?;
+        Ok(written)
+    }
+
+    /// Copy a session file into another storage, e.g. from the recorder's bucket into Slotify's
+    pub async fn copy_session_file(
+        &self,
+        recorder_id: &str,
+        session_id: &str,
+        filename: &str,
+        target: &dyn StorageBackend,
+        key: &str,
+    ) -> Result<u64> {
+        copy_object(self.storage.as_ref(), &session_file_key(recorder_id, session_id, filename), target, key).await
     }
 
-    /// Get presigned URL for direct access to a file
+    /// Read a session file, or part of it, without loading it into memory
+    pub async fn stream_session_file(
+        &self,
+        recorder_id: &str,
+        session_id: &str,
+        filename: &str,
+        range: Option<ByteRange>,
+    ) -> Result<StorageStream> {
+        self.storage.stream(&session_file_key(recorder_id, session_id, filename), range).await
+    }
+
+    /// Get presigned URL for direct access to a file; the plain file URL if the storage can't sign
     pub async fn get_presigned_url(&self, recorder_id: &str, session_id: &str, filename: &str, expires_in_secs: u64) -> Result<String> {
-        let key = format!("{}/sessions/{}/{}", recorder_id, session_id, filename);
+        let key = session_file_key(recorder_id, session_id, filename);
         
-        let presigned_request = self
-            .s3_client
-            .get_object()
-            .bucket(&self.config.bucket_name)
-            .key(&key)
-            .presigned(
-                aws_sdk_s3::presigning::PresigningConfig::expires_in(
-                    std::time::Duration::from_secs(expires_in_secs)
-                ).unwrap()
-            )
-            .await?;
-
-        Ok(presigned_request.uri().to_string())
+        let presigned = self.storage.presign(&key, std::time::Duration::from_secs(expires_in_secs)).await?;
+
+        Ok(presigned.unwrap_or_else(|| self.storage.public_url(&key)))
     }
 
-    /// Map a DJ session to a recorder session based on timing
-    pub async fn find_matching_session(&self, dj_session_start: DateTime<Utc>, tolerance_minutes: i64) -> Result<Option<RecorderSession>> {
-        let recorders = self.get_recorders().await?;
-        
-        for recorder_id in recorders {
-            let sessions = self.get_sessions(&recorder_id).await?;
-            
-            for session in sessions {
-                // Check if the session start time is within tolerance
-                let time_diff = (session.start_time - dj_session_start).num_minutes().abs();
-                
-                if time_diff <= tolerance_minutes {
-                    return Ok(Some(session));
-                }
-            }
+    /// Score every recorder session against a DJ session's interval. See `RecorderMatcher`.
+    pub async fn find_matching_session(
+        &self,
+        dj_session_start: DateTime<Utc>,
+        dj_session_end: Option<DateTime<Utc>>,
+        tolerance_minutes: i64,
+    ) -> Result<MatchOutcome> {
+        let windows: Vec<RecordingWindow> = self.get_all_sessions().await?.iter().map(|s| s.window()).collect();
+
+        Ok(RecorderMatcher::new(tolerance_minutes).match_session(dj_session_start, dj_session_end, &windows, Utc::now()))
+    }
+
+    /// Sessions of every recorder
+    pub async fn get_all_sessions(&self) -> Result<Vec<RecorderSession>> {
+        let mut all_sessions = Vec::new();
+        for recorder_id in self.get_recorders().await? {
+            all_sessions.extend(self.get_sessions(&recorder_id).await?);
         }
-        
-        Ok(None)
+        Ok(all_sessions)
     }
 
     /// Get all recent sessions (useful for automatic mapping)
@@ -328,4 +324,9 @@ impl SessionRecorderService {
         
         Ok(recent_sessions)
     }
-}
\ No newline at end of file
+}
+
+/// Key of a file in a session's directory, e.g. `main/sessions/night/data.flac`
+fn session_file_key(recorder_id: &str, session_id: &str, filename: &str) -> String {
+    format!("{}/sessions/{}/{}", recorder_id, session_id, filename)
+}
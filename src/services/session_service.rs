@@ -1,10 +1,14 @@
 use crate::models::{
     session::{Session, SessionResponse, StartSessionRequest, SessionStats, SessionType, SessionUploadStatus, B2BSessionRequest},
-    AppState,
+    recorder_match::{EventLinkReport, MatchOutcome, RecorderMatcher, RecordingWindow, SessionLinkResult},
+    download::{CreateDownloadTokenRequest, DownloadTokenResponse},
+    AppState, EventSession, WebhookEventType,
 };
-use crate::services::{SessionRecorderService, SessionRecorderConfig, EventService};
+use crate::services::{storage_from_config, AnalysisService, DownloadService, EmailService, TaggingService, TracklistService, RecorderIndexService, RecorderSession, SessionRecorderService, SessionRecorderConfig, EventService, WebhookService};
+use crate::utils::generate_cloud_upload_path;
 use anyhow::Result;
 use sqlx::{SqlitePool, Row};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use uuid::Uuid;
 
@@ -24,7 +28,7 @@ impl SessionService {
     }
 
     pub async fn new_with_recorder(app_state: Arc<AppState>) -> Result<Self> {
-        let recorder_config = SessionRecorderConfig::default(); // TODO: Load from config
+        let recorder_config = SessionRecorderConfig::from(&app_state.config.session_recorder_config);
         let session_recorder = SessionRecorderService::new(recorder_config).await.ok();
 
         Ok(Self {
@@ -93,7 +97,7 @@ impl SessionService {
     pub async fn end_session(&self, session_id: &str) -> Result<Option<SessionResponse>> {
         // Get the session
         let mut session = sqlx::query_as::<_, Session>(
-            "SELECT id, dj_id, started_at, ended_at, duration_minutes, file_path, download_link, upload_status, session_type FROM sessions WHERE id = ?"
+            "SELECT id, dj_id, started_at, ended_at, duration_minutes, file_path, download_link, upload_status, session_type, recorder_session_id, recorder_id, recorder_ogg_url, recorder_flac_url, recorder_waveform_url FROM sessions WHERE id = ?"
         )
         .bind(session_id)
         .fetch_optional(&self.db)
@@ -113,12 +117,29 @@ impl SessionService {
             .execute(&self.db)
             .await?;
 
+            // Stop the recorder for this set
+            let event_service = EventService::new(self.app_state.clone());
+            if let Err(e) = event_service.end_dj_slot(&session.dj_id).await {
+                tracing::warn!("Failed to stop slot recording: {}", e);
+            }
+
             // Start file processing and cloud upload
             self.process_session_recording(session).await?;
 
             // Get DJ name for response
             let dj_name = self.get_dj_name(&session.dj_id).await?;
 
+            WebhookService::new(self.app_state.clone())
+                .emit(WebhookEventType::SlotEnded, serde_json::json!({
+                    "session_id": session.id,
+                    "dj_id": session.dj_id,
+                    "dj_name": dj_name,
+                    "started_at": session.started_at,
+                    "ended_at": session.ended_at,
+                    "duration_minutes": session.duration_minutes,
+                }))
+                .await;
+
             Ok(Some(SessionResponse {
                 id: session.id.clone(),
                 dj_id: session.dj_id.clone(),
@@ -137,7 +158,7 @@ impl SessionService {
 
     pub async fn get_session_by_id(&self, id: &str) -> Result<Option<SessionResponse>> {
         let session = sqlx::query_as::<_, Session>(
-            "SELECT id, dj_id, started_at, ended_at, duration_minutes, file_path, download_link, upload_status, session_type FROM sessions WHERE id = ?"
+            "SELECT id, dj_id, started_at, ended_at, duration_minutes, file_path, download_link, upload_status, session_type, recorder_session_id, recorder_id, recorder_ogg_url, recorder_flac_url, recorder_waveform_url FROM sessions WHERE id = ?"
         )
         .bind(id)
         .fetch_optional(&self.db)
@@ -164,7 +185,7 @@ impl SessionService {
 
     pub async fn get_all_sessions(&self) -> Result<Vec<SessionResponse>> {
         let sessions = sqlx::query_as::<_, Session>(
-            "SELECT id, dj_id, started_at, ended_at, duration_minutes, file_path, download_link, upload_status, session_type FROM sessions ORDER BY started_at DESC"
+            "SELECT id, dj_id, started_at, ended_at, duration_minutes, file_path, download_link, upload_status, session_type, recorder_session_id, recorder_id, recorder_ogg_url, recorder_flac_url, recorder_waveform_url FROM sessions ORDER BY started_at DESC"
         )
         .fetch_all(&self.db)
         .await?;
@@ -190,7 +211,7 @@ impl SessionService {
 
     pub async fn get_current_session(&self) -> Result<Option<SessionResponse>> {
         let session = sqlx::query_as::<_, Session>(
-            "SELECT id, dj_id, started_at, ended_at, duration_minutes, file_path, download_link, upload_status, session_type FROM sessions WHERE ended_at IS NULL ORDER BY started_at DESC LIMIT 1"
+            "SELECT id, dj_id, started_at, ended_at, duration_minutes, file_path, download_link, upload_status, session_type, recorder_session_id, recorder_id, recorder_ogg_url, recorder_flac_url, recorder_waveform_url FROM sessions WHERE ended_at IS NULL ORDER BY started_at DESC LIMIT 1"
         )
         .fetch_optional(&self.db)
         .await?;
@@ -301,6 +322,26 @@ impl SessionService {
         })
     }
 
+    /// Tell webhook subscribers a set can be downloaded. The link itself is the DJ's and
+    /// stays out of the payload.
+    async fn emit_recording_ready(&self, session_id: &str) -> Result<()> {
+        let row = sqlx::query("SELECT s.dj_id, d.name, s.started_at FROM sessions s JOIN djs d ON d.id = s.dj_id WHERE s.id = ?")
+            .bind(session_id)
+            .fetch_optional(&self.db)
+            .await?;
+        if let Some(row) = row {
+            WebhookService::new(self.app_state.clone())
+                .emit(WebhookEventType::RecordingReady, serde_json::json!({
+                    "session_id": session_id,
+                    "dj_id": row.get::<String, _>("dj_id"),
+                    "dj_name": row.get::<String, _>("name"),
+                    "started_at": row.get::<chrono::DateTime<chrono::Utc>, _>("started_at"),
+                }))
+                .await;
+        }
+        Ok(())
+    }
+
     async fn get_dj_name(&self, dj_id: &str) -> Result<String> {
         let name = sqlx::query("SELECT name FROM djs WHERE id = ?")
             .bind(dj_id)
@@ -313,78 +354,467 @@ impl SessionService {
     }
 
     async fn process_session_recording(&self, session: &Session) -> Result<()> {
-        // This would be where we:
-        // 1. Process the audio recording
-        // 2. Upload to cloud storage
-        // 3. Generate download links
-        // 4. Send email to DJ with download link
+        // A set recorded by Slotify itself is published right away; recorder
+        // recordings arrive later through the webhook or segmentation
+        if self.publish_session_file(&session.id).await?.is_none() {
+            tracing::info!("Session {} has no download link yet", session.id);
+        }
+
+        Ok(())
+    }
 
-        // For now, simulate the process
-        let session_id = session.id.clone();
-        let download_link = format!("https://cloud-storage.example.com/sessions/{}.mp3", session_id);
+    /// Copy the session's `file_path` into storage, tagged and renamed for the DJ, and make
+    /// it the download link once it passed analysis.
+    /// Returns the link, or `None` if the session has no file on disk or its link is held
+    /// until an admin releases it.
+    pub async fn publish_session_file(&self, session_id: &str) -> Result<Option<String>> {
+        let file_path: Option<String> = sqlx::query("SELECT file_path FROM sessions WHERE id = ?")
+            .bind(session_id)
+            .fetch_optional(&self.db)
+            .await?
+            .and_then(|row| row.get("file_path"));
+        let Some(path) = file_path.map(PathBuf::from).filter(|path| path.is_file()) else {
+            return Ok(None);
+        };
+
+        let held = AnalysisService::new(self.app_state.clone()).check_delivery(session_id, Some(&path)).await;
+        let key = self.store_delivery(session_id, &path).await?;
+        if held {
+            // Releasing the delivery issues the link and announces the recording
+            sqlx::query("UPDATE sessions SET storage_key = ?, upload_status = 'uploaded' WHERE id = ?")
+                .bind(&key)
+                .bind(session_id)
+                .execute(&self.db)
+                .await?;
+            return Ok(None);
+        }
+        let download_link = storage_from_config(&self.app_state.config.storage_config)?.public_url(&key);
 
-        // Update session with download link
         sqlx::query(
-            "UPDATE sessions SET download_link = ?, upload_status = 'uploaded' WHERE id = ?"
+            "UPDATE sessions SET storage_key = ?, download_link = ?, upload_status = 'uploaded' WHERE id = ?"
         )
+        .bind(&key)
         .bind(&download_link)
-        .bind(&session_id)
+        .bind(session_id)
         .execute(&self.db)
         .await?;
 
-        // TODO: Send email to DJ with download link
-        tracing::info!("Session {} processed and uploaded: {}", session_id, download_link);
+        tracing::info!("Session {} published: {}", session_id, download_link);
+        self.emit_recording_ready(session_id).await?;
+        Ok(Some(download_link))
+    }
 
-        Ok(())
+    /// Download a session's linked recorder recording into a temporary directory, or `None`
+    /// if it has none
+    pub async fn fetch_recorder_recording(&self, session_id: &str) -> Result<Option<(tempfile::TempDir, PathBuf)>> {
+        let Some((recorder_id, recorder_session_id, filename)) = self.recorder_file(session_id).await? else {
+            return Ok(None);
+        };
+
+        let recorder = SessionRecorderService::new(SessionRecorderConfig::from(&self.app_state.config.session_recorder_config)).await?;
+        let download_dir = tempfile::tempdir()?;
+        let download = download_dir.path().join(filename);
+        recorder.download_session_file(&recorder_id, &recorder_session_id, filename, &download).await?;
+        Ok(Some((download_dir, download)))
+    }
+
+    /// Copy a session's linked recorder recording into storage as it is, streamed from the
+    /// recorder's bucket without a local copy, and renamed for the DJ. For deliveries that
+    /// neither analyse nor tag the file. Returns the storage key, or `None` if it has none.
+    pub async fn copy_recorder_recording(&self, session_id: &str) -> Result<Option<String>> {
+        let Some((recorder_id, recorder_session_id, filename)) = self.recorder_file(session_id).await? else {
+            return Ok(None);
+        };
+
+        let extension = filename.rsplit('.').next().unwrap_or("bin");
+        let delivered_name = TaggingService::new(self.app_state.clone()).delivery_filename(session_id, extension).await?;
+        let key = generate_cloud_upload_path(session_id, &delivered_name);
+        let recorder = SessionRecorderService::new(SessionRecorderConfig::from(&self.app_state.config.session_recorder_config)).await?;
+        let storage = storage_from_config(&self.app_state.config.storage_config)?;
+        recorder.copy_session_file(&recorder_id, &recorder_session_id, filename, storage.as_ref(), &key).await?;
+
+        sqlx::query("UPDATE sessions SET storage_key = ? WHERE id = ?")
+            .bind(&key)
+            .bind(session_id)
+            .execute(&self.db)
+            .await?;
+
+        tracing::info!("Recording of session {} copied as {}", session_id, key);
+        Ok(Some(key))
+    }
+
+    /// Recorder, recorder session and file name of a session's linked recording, FLAC
+    /// preferred; `None` if it has none or the integration is off
+    async fn recorder_file(&self, session_id: &str) -> Result<Option<(String, String, &'static str)>> {
+        let row = sqlx::query(
+            "SELECT recorder_id, recorder_session_id, recorder_flac_url, recorder_ogg_url FROM sessions WHERE id = ?"
+        )
+        .bind(session_id)
+        .fetch_optional(&self.db)
+        .await?;
+        let Some(row) = row.filter(|_| self.app_state.config.session_recorder_config.enabled) else {
+            return Ok(None);
+        };
+        let (Some(recorder_id), Some(recorder_session_id)) =
+            (row.get::<Option<String>, _>("recorder_id"), row.get::<Option<String>, _>("recorder_session_id"))
+        else {
+            return Ok(None);
+        };
+        let filename = match (row.get::<Option<String>, _>("recorder_flac_url"), row.get::<Option<String>, _>("recorder_ogg_url")) {
+            (Some(_), _) => "data.flac",
+            (None, Some(_)) => "data.ogg",
+            (None, None) => return Ok(None),
+        };
+        Ok(Some((recorder_id, recorder_session_id, filename)))
+    }
+
+    /// Copy a fetched recorder recording into storage, tagged and renamed for the DJ. Its
+    /// download links serve the copy from then on. Returns the storage key.
+    pub async fn publish_recorder_file(&self, session_id: &str, recording: &Path) -> Result<String> {
+        let key = self.store_delivery(session_id, recording).await?;
+        sqlx::query("UPDATE sessions SET storage_key = ? WHERE id = ?")
+            .bind(&key)
+            .bind(session_id)
+            .execute(&self.db)
+            .await?;
+
+        tracing::info!("Recording of session {} published as {}", session_id, key);
+        Ok(key)
+    }
+
+    /// Tag and rename a recording for its DJ, then put it in storage; returns the key
+    async fn store_delivery(&self, session_id: &str, source: &Path) -> Result<String> {
+        let dir = tempfile::tempdir()?;
+        let prepared = TaggingService::new(self.app_state.clone())
+            .prepare_delivery(session_id, source, dir.path())
+            .await?;
+
+        let filename = prepared.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+        let key = generate_cloud_upload_path(session_id, &filename);
+        let storage = storage_from_config(&self.app_state.config.storage_config)?;
+        storage.put_file(&key, &prepared).await?;
+        Ok(key)
     }
 
     /// Link a DJ session to a session-recorder session
     pub async fn link_to_recorder_session(&self, session_id: &str, recorder_session_id: &str, recorder_id: &str) -> Result<()> {
+        let index = RecorderIndexService::new(self.app_state.clone());
+        if let Some(recorder_session) = index.get_session(recorder_id, recorder_session_id).await? {
+            return self.store_recorder_link(session_id, &recorder_session).await;
+        }
+
+        // Not indexed yet (e.g. recorded since the last sync); ask the recorder directly
         if let Some(ref recorder) = self.session_recorder {
-            // Get session details from recorder
             if let Ok(recorder_session) = recorder.get_session_details(recorder_id, recorder_session_id).await {
-                sqlx::query(
-                    r#"
-                    UPDATE sessions 
-                    SET recorder_session_id = ?, recorder_id = ?, 
-                        recorder_ogg_url = ?, recorder_flac_url = ?, recorder_waveform_url = ?
-                    WHERE id = ?
-                    "#,
-                )
-                .bind(recorder_session_id)
-                .bind(recorder_id)
-                .bind(&recorder_session.files.ogg_url)
-                .bind(&recorder_session.files.flac_url)
-                .bind(&recorder_session.files.waveform_url)
-                .bind(session_id)
-                .execute(&self.db)
-                .await?;
+                self.store_recorder_link(session_id, &recorder_session).await?;
             }
         }
         Ok(())
     }
 
-    /// Try to automatically find and link a matching recorder session
-    pub async fn auto_link_recorder_session(&self, session_id: &str, session_start_time: chrono::DateTime<chrono::Utc>) -> Result<bool> {
-        if let Some(ref recorder) = self.session_recorder {
-            // Look for sessions within 5 minutes of the DJ session start
-            if let Ok(Some(recorder_session)) = recorder.find_matching_session(session_start_time, 5).await {
-                self.link_to_recorder_session(session_id, &recorder_session.id, &recorder_session.recorder_id).await?;
-                tracing::info!("Auto-linked DJ session {} to recorder session {}/{}", 
-                              session_id, recorder_session.recorder_id, recorder_session.id);
-                return Ok(true);
+    async fn store_recorder_link(&self, session_id: &str, recorder_session: &RecorderSession) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE sessions 
+            SET recorder_session_id = ?, recorder_id = ?, 
+                recorder_ogg_url = ?, recorder_flac_url = ?, recorder_waveform_url = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(&recorder_session.id)
+        .bind(&recorder_session.recorder_id)
+        .bind(&recorder_session.files.ogg_url)
+        .bind(&recorder_session.files.flac_url)
+        .bind(&recorder_session.files.waveform_url)
+        .bind(session_id)
+        .execute(&self.db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Match a DJ session against the indexed recorder sessions and link it if exactly one
+    /// recording covers it clearly. Ambiguous matches are returned, not linked.
+    pub async fn auto_link_recorder_session(&self, session_id: &str, tolerance_minutes: Option<i64>) -> Result<Option<SessionLinkResult>> {
+        let Some(session) = self.get_session_by_id(session_id).await? else {
+            return Ok(None);
+        };
+        let tolerance = tolerance_minutes.unwrap_or(self.app_state.config.session_recorder_config.auto_link_tolerance_minutes);
+
+        let recordings = RecorderIndexService::new(self.app_state.clone()).get_sessions().await?;
+        let result = self.match_and_link(&session, &recordings, &self.recorder_matcher(tolerance)).await?;
+
+        Ok(Some(result))
+    }
+
+    /// Auto-link every DJ session of an event against the indexed recorder sessions
+    pub async fn auto_link_event(&self, event_id: &str, tolerance_minutes: Option<i64>) -> Result<Option<EventLinkReport>> {
+        let recordings = RecorderIndexService::new(self.app_state.clone()).get_sessions().await?;
+
+        self.link_event_sessions(event_id, &recordings, tolerance_minutes).await
+    }
+
+    /// Batch linking against an already fetched list of recorder sessions. Sessions that
+    /// are linked already are left alone; `None` if the event does not exist.
+    pub async fn link_event_sessions(
+        &self,
+        event_id: &str,
+        recordings: &[RecorderSession],
+        tolerance_minutes: Option<i64>,
+    ) -> Result<Option<EventLinkReport>> {
+        let event = sqlx::query_as::<_, EventSession>("SELECT * FROM event_sessions WHERE id = ?")
+            .bind(event_id)
+            .fetch_optional(&self.db)
+            .await?;
+        let Some(event) = event else {
+            return Ok(None);
+        };
+
+        let sessions = sqlx::query(
+            r#"
+            SELECT id, recorder_session_id FROM sessions
+            WHERE started_at >= ? AND (? IS NULL OR started_at <= ?)
+            ORDER BY started_at ASC
+            "#,
+        )
+        .bind(event.started_at)
+        .bind(event.ended_at)
+        .bind(event.ended_at)
+        .fetch_all(&self.db)
+        .await?;
+
+        let tolerance = tolerance_minutes.unwrap_or(self.app_state.config.session_recorder_config.auto_link_tolerance_minutes);
+        let matcher = self.recorder_matcher(tolerance);
+
+        let mut report = EventLinkReport {
+            event_id: event.id,
+            linked: Vec::new(),
+            ambiguous: Vec::new(),
+            unmatched: Vec::new(),
+            already_linked: Vec::new(),
+        };
+
+        for row in sessions {
+            let session_id: String = row.get("id");
+            if row.get::<Option<String>, _>("recorder_session_id").is_some() {
+                report.already_linked.push(session_id);
+                continue;
+            }
+            let Some(session) = self.get_session_by_id(&session_id).await? else {
+                continue;
+            };
+
+            let result = self.match_and_link(&session, recordings, &matcher).await?;
+            match result.outcome {
+                MatchOutcome::Matched { .. } => report.linked.push(result),
+                MatchOutcome::Ambiguous { .. } => report.ambiguous.push(result),
+                MatchOutcome::NoMatch => report.unmatched.push(result),
+            }
+        }
+
+        tracing::info!(
+            "Auto-linked event {}: {} linked, {} ambiguous, {} unmatched",
+            event_id,
+            report.linked.len(),
+            report.ambiguous.len(),
+            report.unmatched.len()
+        );
+
+        Ok(Some(report))
+    }
+
+    fn recorder_matcher(&self, tolerance_minutes: i64) -> RecorderMatcher {
+        RecorderMatcher::new(tolerance_minutes)
+            .with_preferred_recorder(self.app_state.config.session_recorder_config.preferred_recorder_id.clone())
+    }
+
+    async fn match_and_link(
+        &self,
+        session: &SessionResponse,
+        recordings: &[RecorderSession],
+        matcher: &RecorderMatcher,
+    ) -> Result<SessionLinkResult> {
+        let windows: Vec<RecordingWindow> = recordings.iter().map(|r| r.window()).collect();
+        let outcome = matcher.match_session(session.started_at, session.ended_at, &windows, chrono::Utc::now());
+
+        match &outcome {
+            MatchOutcome::Matched { candidate } => {
+                let recording = recordings
+                    .iter()
+                    .find(|r| r.id == candidate.recorder_session_id && r.recorder_id == candidate.recorder_id)
+                    .expect("candidates come from the recording list");
+                self.store_recorder_link(&session.id, recording).await?;
+                tracing::info!("Auto-linked DJ session {} to recorder session {}/{} ({:.0}% coverage)",
+                              session.id, candidate.recorder_id, candidate.recorder_session_id, candidate.coverage * 100.0);
             }
+            MatchOutcome::Ambiguous { candidates } => {
+                tracing::warn!("DJ session {} matches {} recordings equally well; needs admin review",
+                              session.id, candidates.len());
+            }
+            MatchOutcome::NoMatch => {}
         }
-        Ok(false)
+
+        Ok(SessionLinkResult {
+            session_id: session.id.clone(),
+            dj_name: session.dj_name.clone(),
+            started_at: session.started_at,
+            ended_at: session.ended_at,
+            outcome,
+        })
+    }
+
+    /// Hand a linked recorder recording to the DJ: once it passed analysis, a download link
+    /// (see `DownloadService`) becomes the session's download link and the DJ is emailed if
+    /// they left an address. Happens once per session; returns `false` if there is nothing to
+    /// deliver or it was delivered before.
+    pub async fn deliver_recording(&self, session_id: &str) -> Result<bool> {
+        let row = sqlx::query(
+            r#"
+            SELECT s.recorder_flac_url, s.recorder_ogg_url, s.recording_delivered_at, d.name, d.email
+            FROM sessions s JOIN djs d ON d.id = s.dj_id
+            WHERE s.id = ?
+            "#,
+        )
+        .bind(session_id)
+        .fetch_optional(&self.db)
+        .await?;
+        let Some(row) = row else {
+            return Ok(false);
+        };
+
+        let has_recording = row.get::<Option<String>, _>("recorder_flac_url").is_some()
+            || row.get::<Option<String>, _>("recorder_ogg_url").is_some();
+        if !has_recording {
+            return Ok(false);
+        }
+        if row.get::<Option<chrono::DateTime<chrono::Utc>>, _>("recording_delivered_at").is_some() {
+            return Ok(false);
+        }
+
+        // Concurrent notifications for the same recording race here; only one wins
+        let claimed = sqlx::query(
+            r#"
+            UPDATE sessions SET recording_delivered_at = ?, upload_status = 'uploaded'
+            WHERE id = ? AND recording_delivered_at IS NULL
+            "#,
+        )
+        .bind(chrono::Utc::now())
+        .bind(session_id)
+        .execute(&self.db)
+        .await?;
+        if claimed.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        // The recording is fetched once, analysed and tagged; until the tagged copy is in
+        // storage the link serves the recorder's file. Flagged sets get no link until an
+        // admin releases them. With neither analysis nor tagging on, nothing has to read the
+        // file, so it is copied bucket to bucket instead.
+        let app_state = self.app_state.clone();
+        let delivered_id = session_id.to_string();
+        tokio::spawn(async move {
+            let session_service = SessionService::new(app_state.clone());
+            let config = &app_state.config;
+            let held = if config.analysis_config.enabled || config.tagging_config.enabled {
+                let recording = session_service.fetch_recorder_recording(&delivered_id).await.unwrap_or_else(|e| {
+                    tracing::warn!("Could not fetch recording of session {}: {}", delivered_id, e);
+                    None
+                });
+                let recording_path = recording.as_ref().map(|(_, path)| path.as_path());
+
+                let held = AnalysisService::new(app_state.clone()).check_delivery(&delivered_id, recording_path).await;
+                if let Some(path) = recording_path {
+                    if let Err(e) = session_service.publish_recorder_file(&delivered_id, path).await {
+                        tracing::warn!("Recording of session {} is delivered untagged: {}", delivered_id, e);
+                    }
+                }
+                held
+            } else {
+                if let Err(e) = session_service.copy_recorder_recording(&delivered_id).await {
+                    tracing::warn!("Recording of session {} is served from the recorder: {}", delivered_id, e);
+                }
+                false
+            };
+            if held {
+                return;
+            }
+
+            if let Err(e) = session_service.issue_delivery_link(&delivered_id).await {
+                tracing::error!("Failed to deliver recording of session {}: {}", delivered_id, e);
+                // Undo the claim so the next notification for the recording retries
+                let released = sqlx::query("UPDATE sessions SET recording_delivered_at = NULL WHERE id = ?")
+                    .bind(&delivered_id)
+                    .execute(&session_service.db)
+                    .await;
+                if let Err(e) = released {
+                    tracing::error!("Could not release delivery of session {}: {}", delivered_id, e);
+                }
+                return;
+            }
+            if let Err(e) = session_service.send_download_email(&delivered_id).await {
+                tracing::error!("Failed to email recording of session {}: {}", delivered_id, e);
+            }
+        });
+
+        tracing::info!("Delivery started for DJ session {}", session_id);
+        Ok(true)
+    }
+
+    /// Issue a download link for a delivered recording and make it the session's download link
+    pub async fn issue_delivery_link(&self, session_id: &str) -> Result<DownloadTokenResponse> {
+        let mut tx = self.db.begin().await?;
+        let link = DownloadService::new(self.app_state.clone())
+            .issue_token_with(&mut tx, session_id, CreateDownloadTokenRequest::default())
+            .await?;
+        sqlx::query("UPDATE sessions SET download_link = ? WHERE id = ?")
+            .bind(&link.url)
+            .bind(session_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        self.emit_recording_ready(session_id).await?;
+        Ok(link)
+    }
+
+    /// Email the DJ their newest usable download link, issuing one if none is left. Returns
+    /// `false` if the DJ left no address.
+    pub async fn send_download_email(&self, session_id: &str) -> Result<bool> {
+        let row = sqlx::query("SELECT d.name, d.email FROM sessions s JOIN djs d ON d.id = s.dj_id WHERE s.id = ?")
+            .bind(session_id)
+            .fetch_optional(&self.db)
+            .await?;
+        let Some(row) = row else {
+            return Ok(false);
+        };
+        let dj_name: String = row.get("name");
+        let Some(email) = row.get::<Option<String>, _>("email") else {
+            return Ok(false);
+        };
+
+        let download_service = DownloadService::new(self.app_state.clone());
+        let now = chrono::Utc::now();
+        let usable = download_service
+            .get_session_tokens(session_id)
+            .await?
+            .into_iter()
+            .filter(|link| link.token.check_usable(now).is_ok())
+            .max_by_key(|link| link.token.created_at);
+        let link = match usable {
+            Some(link) => link,
+            None => download_service.issue_token(session_id, CreateDownloadTokenRequest::default()).await?,
+        };
+
+        let tracklist = TracklistService::new(self.app_state.clone()).formatted_tracklist(session_id).await?;
+        EmailService::new(self.app_state.clone())?
+            .send_session_download_link(&email, &dj_name, session_id, &link, tracklist.as_deref())
+            .await?;
+        Ok(true)
     }
 
     /// Get all available recorder sessions
     pub async fn get_available_recorder_sessions(&self) -> Result<Vec<crate::services::RecorderSession>> {
-        if let Some(ref recorder) = self.session_recorder {
-            let recent_sessions = recorder.get_recent_sessions(24).await?;
-            return Ok(recent_sessions);
-        }
-        Ok(Vec::new())
+        RecorderIndexService::new(self.app_state.clone()).get_recent_sessions(24).await
     }
 
     /// Get recording download URL for a session
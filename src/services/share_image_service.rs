@@ -0,0 +1,316 @@
+use crate::models::{
+    event_session::{Timetable, TimetableEntry, TimetableEntryStatus, DEFAULT_EVENT_NAME},
+    AppState, BrandingConfig, ShareImageError, ShareImageRequest, ShareTemplate, Track,
+};
+use crate::services::{DownloadService, EventService, NowPlayingService};
+use crate::utils::{hex_color, load_font, Canvas};
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use chrono_tz::Tz;
+use image::Rgb;
+use sqlx::SqlitePool;
+use std::sync::Arc;
+
+const BACKGROUND: Rgb<u8> = Rgb([17, 17, 17]);
+const TEXT: Rgb<u8> = Rgb([255, 255, 255]);
+const MUTED: Rgb<u8> = Rgb([160, 160, 160]);
+const FALLBACK_ACCENT: Rgb<u8> = Rgb([255, 46, 99]);
+const MARGIN: f32 = 80.0;
+const QR_SIZE: u32 = 240;
+
+/// PNG share images of an event: the lineup poster, a now playing story card and the card
+/// announcing a DJ's recording. Times are local to the calendar time zone, like the exports.
+pub struct ShareImageService {
+    db: SqlitePool,
+    app_state: Arc<AppState>,
+}
+
+/// What a template shows beyond the event itself
+enum Card {
+    Lineup,
+    NowPlaying {
+        current: TimetableEntry,
+        next: Option<TimetableEntry>,
+        track: Option<Track>,
+    },
+    SetOnline {
+        set: TimetableEntry,
+    },
+}
+
+impl ShareImageService {
+    pub fn new(app_state: Arc<AppState>) -> Self {
+        Self {
+            db: app_state.db.clone(),
+            app_state,
+        }
+    }
+
+    pub async fn render(&self, event_id: &str, request: ShareImageRequest) -> Result<Vec<u8>> {
+        let timetable = EventService::new(self.app_state.clone())
+            .get_event_timetable(event_id)
+            .await?
+            .ok_or(ShareImageError::EventNotFound)?;
+        let branding = self.app_state.config.branding_config.clone();
+
+        let (card, qr_url) = match request.template {
+            ShareTemplate::Lineup => (Card::Lineup, branding.registration_url.clone()),
+            ShareTemplate::NowPlaying => (self.now_playing(&timetable).await?, branding.registration_url.clone()),
+            ShareTemplate::SetOnline => {
+                let token = request.token.ok_or(ShareImageError::TokenRequired)?;
+                let card = self.set_online(&timetable, &token).await?;
+                (card, Some(DownloadService::new(self.app_state.clone()).token_url(&token)))
+            }
+        };
+
+        let poster = Poster {
+            template: request.template,
+            timetable,
+            timezone: self.app_state.config.calendar_config.timezone.unwrap_or(Tz::UTC),
+            branding,
+            qr_url: qr_url.filter(|_| request.qr.unwrap_or(true)),
+        };
+        // Rasterizing and PNG encoding take a while for a story-sized image
+        tokio::task::spawn_blocking(move || poster.draw(card)).await?
+    }
+
+    async fn now_playing(&self, timetable: &Timetable) -> Result<Card> {
+        let current = timetable
+            .entries
+            .iter()
+            .find(|entry| entry.status == TimetableEntryStatus::InProgress)
+            .cloned()
+            .ok_or(ShareImageError::NothingPlaying)?;
+        let next = timetable
+            .entries
+            .iter()
+            .find(|entry| entry.status == TimetableEntryStatus::Upcoming)
+            .cloned();
+
+        // The latest track only belongs to this set if the open session is the one playing
+        let track = NowPlayingService::new(self.app_state.clone())
+            .now_playing()
+            .await?
+            .filter(|now_playing| now_playing.session_started_at == current.started_at)
+            .and_then(|now_playing| now_playing.track);
+
+        Ok(Card::NowPlaying { current, next, track })
+    }
+
+    /// The set the download token belongs to; the token is what keeps the card's QR code private
+    async fn set_online(&self, timetable: &Timetable, token: &str) -> Result<Card> {
+        let download = DownloadService::new(self.app_state.clone())
+            .get_token(token)
+            .await?
+            .ok_or(ShareImageError::UnknownToken)?;
+        download.check_usable(Utc::now()).map_err(|_| ShareImageError::UnknownToken)?;
+
+        let started_at: DateTime<Utc> = sqlx::query_scalar("SELECT started_at FROM sessions WHERE id = ?")
+            .bind(&download.session_id)
+            .fetch_optional(&self.db)
+            .await?
+            .ok_or(ShareImageError::UnknownToken)?;
+        let set = timetable
+            .entries
+            .iter()
+            .find(|entry| entry.dj_id == download.dj_id && entry.started_at == started_at)
+            .cloned()
+            .ok_or(ShareImageError::UnknownToken)?;
+
+        Ok(Card::SetOnline { set })
+    }
+}
+
+/// Everything needed to draw, moved onto a blocking thread
+struct Poster {
+    template: ShareTemplate,
+    timetable: Timetable,
+    timezone: Tz,
+    branding: BrandingConfig,
+    qr_url: Option<String>,
+}
+
+impl Poster {
+    fn draw(&self, card: Card) -> Result<Vec<u8>> {
+        let (width, height) = self.template.size();
+        let accent = hex_color(&self.branding.accent_color).unwrap_or(FALLBACK_ACCENT);
+        let mut canvas = Canvas::new(width, height, BACKGROUND, load_font(self.branding.font_path.as_deref())?);
+
+        canvas.fill_rect(0, 0, width, 16, accent);
+        self.line(&mut canvas, &self.branding.name.to_uppercase(), 64.0, 34.0, accent, true);
+        if let Some(footer) = &self.branding.footer {
+            self.line(&mut canvas, footer, height as f32 - 62.0, 26.0, MUTED, false);
+        }
+
+        match card {
+            Card::Lineup => {
+                self.draw_lineup(&mut canvas, accent);
+                self.draw_qr_band(&mut canvas, "Want a slot?", "Scan to sign up for the lottery")?;
+            }
+            Card::NowPlaying { current, next, track } => {
+                self.draw_now_playing(&mut canvas, accent, &current, next.as_ref(), track.as_ref());
+                self.draw_qr_band(&mut canvas, "Want to play?", "Scan to sign up for the lottery")?;
+            }
+            Card::SetOnline { set } => {
+                self.draw_set_online(&mut canvas, accent, &set);
+                self.draw_qr_band(&mut canvas, "Listen back", "Scan for the recording")?;
+            }
+        }
+
+        canvas.into_png()
+    }
+
+    fn draw_lineup(&self, canvas: &mut Canvas, accent: Rgb<u8>) {
+        let mut y = self.line(canvas, self.event_name(), 130.0, 96.0, TEXT, true);
+        let subtitle = format!(
+            "{} · from {}",
+            self.local(self.timetable.event_started_at).format("%A %-d %B %Y"),
+            self.local(self.timetable.event_started_at).format("%H:%M")
+        );
+        y = self.line(canvas, &subtitle, y + 8.0, 36.0, MUTED, false) + 56.0;
+
+        let entries = &self.timetable.entries;
+        if entries.is_empty() {
+            self.line(canvas, "Lineup drawn live at the venue", y, 44.0, TEXT, false);
+            return;
+        }
+
+        let bottom = canvas.height() as f32 - MARGIN - if self.qr_url.is_some() { QR_SIZE as f32 + 40.0 } else { 40.0 };
+        let (row, shown) = lineup_rows(bottom - y, entries.len());
+        let size = row * 0.6;
+        let name_x = MARGIN + canvas.text_width("00:00", size) + size;
+        let right = canvas.width() as f32 - MARGIN;
+
+        for entry in &entries[..shown] {
+            canvas.draw_text(&self.local(entry.started_at).format("%H:%M").to_string(), MARGIN, y, size, MUTED, false);
+
+            let mut name_width = right - name_x;
+            if let Some(booking) = booking_label(entry) {
+                let booking_size = size * 0.7;
+                let booking_width = canvas.text_width(&booking, booking_size);
+                let baseline_offset = (canvas.line_height(size) - canvas.line_height(booking_size)) / 2.0;
+                canvas.draw_text(&booking, right - booking_width, y + baseline_offset, booking_size, accent, false);
+                name_width -= booking_width + size / 2.0;
+            }
+            let color = if entry.status == TimetableEntryStatus::InProgress { accent } else { TEXT };
+            let (name, name_size) = canvas.fit_text(&entry.dj_name, size, name_width);
+            canvas.draw_text(&name, name_x, y + (size - name_size) / 2.0, name_size, color, true);
+            y += row;
+        }
+        if shown < entries.len() {
+            canvas.draw_text(&format!("+ {} more", entries.len() - shown), name_x, y, size, MUTED, false);
+        }
+    }
+
+    fn draw_now_playing(
+        &self,
+        canvas: &mut Canvas,
+        accent: Rgb<u8>,
+        current: &TimetableEntry,
+        next: Option<&TimetableEntry>,
+        track: Option<&Track>,
+    ) {
+        let mut y = self.line(canvas, "NOW PLAYING", 560.0, 56.0, accent, true) + 12.0;
+        y = self.line(canvas, &current.dj_name, y, 160.0, TEXT, true) + 16.0;
+        if let Some(track) = track {
+            let text = match &track.artist {
+                Some(artist) => format!("{} – {}", artist, track.title),
+                None => track.title.clone(),
+            };
+            y = self.line(canvas, &text, y, 44.0, MUTED, false);
+        }
+
+        y = self.line(canvas, &format!("at {}", self.event_name()), y + 80.0, 48.0, TEXT, false);
+        y = self.line(canvas, &format!("since {}", self.local(current.started_at).format("%H:%M")), y + 8.0, 40.0, MUTED, false);
+        if let Some(next) = next {
+            let text = format!("Up next: {} · {}", next.dj_name, self.local(next.started_at).format("%H:%M"));
+            self.line(canvas, &text, y + 48.0, 40.0, MUTED, false);
+        }
+    }
+
+    fn draw_set_online(&self, canvas: &mut Canvas, accent: Rgb<u8>, set: &TimetableEntry) {
+        let mut y = self.line(canvas, "SET NOW ONLINE", 220.0, 52.0, accent, true) + 12.0;
+        y = self.line(canvas, &set.dj_name, y, 130.0, TEXT, true) + 24.0;
+        y = self.line(canvas, self.event_name(), y, 48.0, TEXT, false);
+
+        let ended_at = set
+            .ended_at
+            .unwrap_or(set.started_at + Duration::minutes(self.timetable.slot_duration_minutes as i64));
+        let times = format!(
+            "{} · {}–{}",
+            self.local(set.started_at).format("%-d %B %Y"),
+            self.local(set.started_at).format("%H:%M"),
+            self.local(ended_at).format("%H:%M")
+        );
+        self.line(canvas, &times, y + 8.0, 36.0, MUTED, false);
+    }
+
+    /// QR code in the bottom right corner with a call to action beside it
+    fn draw_qr_band(&self, canvas: &mut Canvas, title: &str, caption: &str) -> Result<()> {
+        let Some(url) = &self.qr_url else {
+            return Ok(());
+        };
+        let x = canvas.width() - MARGIN as u32 - QR_SIZE;
+        let y = canvas.height() - MARGIN as u32 - QR_SIZE;
+        canvas.draw_qr(url, x, y, QR_SIZE)?;
+
+        let max_width = x as f32 - 2.0 * MARGIN;
+        let middle = y as f32 + QR_SIZE as f32 / 2.0;
+        let (title, title_size) = canvas.fit_text(title, 44.0, max_width);
+        let (caption, caption_size) = canvas.fit_text(caption, 30.0, max_width);
+        canvas.draw_text(&title, MARGIN, middle - canvas.line_height(title_size), title_size, TEXT, true);
+        canvas.draw_text(&caption, MARGIN, middle + 6.0, caption_size, MUTED, false);
+        Ok(())
+    }
+
+    /// One line across the content width at `y`, shrunk to fit; returns where the next line starts
+    fn line(&self, canvas: &mut Canvas, text: &str, y: f32, size: f32, color: Rgb<u8>, bold: bool) -> f32 {
+        let (text, size) = canvas.fit_text(text, size, canvas.width() as f32 - 2.0 * MARGIN);
+        canvas.draw_text(&text, MARGIN, y, size, color, bold);
+        y + canvas.line_height(size)
+    }
+
+    fn event_name(&self) -> &str {
+        self.timetable.event_name.as_deref().unwrap_or(DEFAULT_EVENT_NAME)
+    }
+
+    fn local(&self, time: DateTime<Utc>) -> DateTime<Tz> {
+        time.with_timezone(&self.timezone)
+    }
+}
+
+/// `Headliner`, or the booking's own label
+fn booking_label(entry: &TimetableEntry) -> Option<String> {
+    entry
+        .reserved_label
+        .clone()
+        .or_else(|| entry.reserved_kind.map(|kind| kind.label().to_string()))
+}
+
+/// Row height and how many of `count` sets fit in `space` pixels; when they don't all fit,
+/// the last row is left for a "+ n more" line
+fn lineup_rows(space: f32, count: usize) -> (f32, usize) {
+    let row = (space / count as f32).clamp(40.0, 84.0);
+    let rows = ((space / row).floor() as usize).max(1);
+    (row, if rows < count { rows - 1 } else { count })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lineup_rows() {
+        // A short lineup gets the tallest rows
+        assert_eq!(lineup_rows(660.0, 3), (84.0, 3));
+        // Rows shrink until every set fits...
+        assert_eq!(lineup_rows(660.0, 12), (55.0, 12));
+        assert_eq!(lineup_rows(660.0, 16), (41.25, 16));
+        // ...then the rest go into the "+ n more" row
+        assert_eq!(lineup_rows(660.0, 17), (40.0, 15));
+        assert_eq!(lineup_rows(660.0, 30), (40.0, 15));
+        // Room for one row holds the only set, or just the count of them
+        assert_eq!(lineup_rows(20.0, 1), (40.0, 1));
+        assert_eq!(lineup_rows(20.0, 5), (40.0, 0));
+    }
+}
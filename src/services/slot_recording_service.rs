@@ -0,0 +1,141 @@
+use crate::models::{
+    slot_recording::{recording_name, SlotRecording, StartRecordingRequest},
+    AppState, EventSession,
+};
+use crate::services::RecorderControl;
+use anyhow::Result;
+use chrono::Utc;
+use sqlx::{Row, SqlitePool};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Keeps the session recorder in step with the slot timetable: one named recording per
+/// DJ slot, started when the slot starts and stopped when it ends
+pub struct SlotRecordingService {
+    db: SqlitePool,
+    control: Arc<dyn RecorderControl>,
+}
+
+impl SlotRecordingService {
+    pub fn new(app_state: Arc<AppState>, control: Arc<dyn RecorderControl>) -> Self {
+        Self {
+            db: app_state.db.clone(),
+            control,
+        }
+    }
+
+    /// Start recording a DJ's slot, stopping whatever is still being recorded first.
+    /// A recorder that cannot be reached is noted on the returned recording, not raised.
+    pub async fn start_slot(&self, event: &EventSession, dj_id: &str) -> Result<SlotRecording> {
+        self.stop_all().await?;
+
+        let dj = sqlx::query("SELECT name, keep_recording FROM djs WHERE id = ?")
+            .bind(dj_id)
+            .fetch_one(&self.db)
+            .await?;
+        let request = StartRecordingRequest {
+            name: recording_name(&dj.get::<String, _>("name"), event.name.as_deref(), event.started_at),
+            keep: dj.get("keep_recording"),
+        };
+
+        let (recording_id, error) = match self.control.start_recording(&request).await {
+            Ok(recording_id) => (Some(recording_id), None),
+            Err(e) => {
+                tracing::error!("Could not start recording \"{}\": {}", request.name, e);
+                (None, Some(e.to_string()))
+            }
+        };
+
+        let recording = SlotRecording {
+            id: Uuid::new_v4().to_string(),
+            event_id: event.id.clone(),
+            dj_id: dj_id.to_string(),
+            recording_id,
+            name: request.name,
+            keep: request.keep,
+            started_at: Utc::now(),
+            // Nothing to stop later if the recorder never started
+            stopped_at: error.as_ref().map(|_| Utc::now()),
+            error,
+        };
+
+        sqlx::query(
+            r#"
+            INSERT INTO slot_recordings (id, event_id, dj_id, recording_id, name, keep, started_at, stopped_at, error)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&recording.id)
+        .bind(&recording.event_id)
+        .bind(&recording.dj_id)
+        .bind(&recording.recording_id)
+        .bind(&recording.name)
+        .bind(recording.keep)
+        .bind(recording.started_at)
+        .bind(recording.stopped_at)
+        .bind(&recording.error)
+        .execute(&self.db)
+        .await?;
+
+        Ok(recording)
+    }
+
+    /// Stop the DJ's running recording, if any
+    pub async fn stop_dj(&self, dj_id: &str) -> Result<Vec<SlotRecording>> {
+        let open = sqlx::query_as::<_, SlotRecording>(
+            "SELECT * FROM slot_recordings WHERE dj_id = ? AND stopped_at IS NULL ORDER BY started_at ASC",
+        )
+        .bind(dj_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        self.stop(open).await
+    }
+
+    /// Stop every running recording, e.g. when the event ends
+    pub async fn stop_all(&self) -> Result<Vec<SlotRecording>> {
+        let open = sqlx::query_as::<_, SlotRecording>(
+            "SELECT * FROM slot_recordings WHERE stopped_at IS NULL ORDER BY started_at ASC",
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        self.stop(open).await
+    }
+
+    async fn stop(&self, recordings: Vec<SlotRecording>) -> Result<Vec<SlotRecording>> {
+        let mut stopped = Vec::new();
+
+        for mut recording in recordings {
+            if let Some(recording_id) = &recording.recording_id {
+                // Marked stopped either way; retrying against a dead recorder would block every later slot
+                if let Err(e) = self.control.stop_recording(recording_id).await {
+                    tracing::error!("Could not stop recording \"{}\": {}", recording.name, e);
+                    recording.error = Some(e.to_string());
+                }
+            }
+            recording.stopped_at = Some(Utc::now());
+
+            sqlx::query("UPDATE slot_recordings SET stopped_at = ?, error = ? WHERE id = ?")
+                .bind(recording.stopped_at)
+                .bind(&recording.error)
+                .bind(&recording.id)
+                .execute(&self.db)
+                .await?;
+            stopped.push(recording);
+        }
+
+        Ok(stopped)
+    }
+
+    pub async fn get_event_recordings(&self, event_id: &str) -> Result<Vec<SlotRecording>> {
+        let recordings = sqlx::query_as::<_, SlotRecording>(
+            "SELECT * FROM slot_recordings WHERE event_id = ? ORDER BY started_at ASC",
+        )
+        .bind(event_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(recordings)
+    }
+}
@@ -0,0 +1,56 @@
+use crate::models::storage::{ByteRange, StorageConfig, StorageListing};
+use crate::services::{LocalStorage, S3Storage};
+use anyhow::Result;
+use std::future::Future;
+use std::ops::Range;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncRead;
+
+pub type StorageFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T>> + Send + 'a>>;
+
+/// An object being read; `range` is the part `body` yields
+pub struct StorageStream {
+    pub body: Pin<Box<dyn AsyncRead + Send>>,
+    pub size: u64,
+    pub range: Range<u64>,
+}
+
+/// Keyed file storage. Keys are `/`-separated paths such as `main/sessions/night/data.flac`.
+/// Object safe so services can hold whichever backend is configured.
+pub trait StorageBackend: Send + Sync {
+    /// Direct children of `prefix` (`""` for the top level)
+    fn list<'a>(&'a self, prefix: &'a str) -> StorageFuture<'a, StorageListing>;
+    /// The whole object in memory; for small files such as `metadata.json`, use `stream` for recordings
+    fn get<'a>(&'a self, key: &'a str) -> StorageFuture<'a, Vec<u8>>;
+    fn put<'a>(&'a self, key: &'a str, data: Vec<u8>) -> StorageFuture<'a, ()>;
+    /// Store a local file without reading it into memory
+    fn put_file<'a>(&'a self, key: &'a str, path: &'a Path) -> StorageFuture<'a, ()>;
+    /// Store everything `body` yields, holding at most one upload part in memory
+    fn put_stream<'a>(&'a self, key: &'a str, body: Pin<Box<dyn AsyncRead + Send>>) -> StorageFuture<'a, ()>;
+    fn stream<'a>(&'a self, key: &'a str, range: Option<ByteRange>) -> StorageFuture<'a, StorageStream>;
+    /// A time-limited direct URL, or `None` if the backend can't issue one and the file
+    /// has to be served through Slotify
+    fn presign<'a>(&'a self, key: &'a str, expires_in: Duration) -> StorageFuture<'a, Option<String>>;
+    /// Removing a missing object is not an error
+    fn delete<'a>(&'a self, key: &'a str) -> StorageFuture<'a, ()>;
+    /// Permanent URL of an object
+    fn public_url(&self, key: &str) -> String;
+}
+
+/// The backend selected by `STORAGE_BACKEND`
+pub fn storage_from_config(config: &StorageConfig) -> Result<Arc<dyn StorageBackend>> {
+    Ok(match config {
+        StorageConfig::Local { root, public_url } => Arc::new(LocalStorage::new(root, public_url.clone())),
+        StorageConfig::S3(s3) => Arc::new(S3Storage::new(s3)),
+    })
+}
+
+/// Copy an object between backends or buckets without loading it into memory; returns its size
+pub async fn copy_object(from: &dyn StorageBackend, from_key: &str, to: &dyn StorageBackend, to_key: &str) -> Result<u64> {
+    let stream = from.stream(from_key, None).await?;
+    to.put_stream(to_key, stream.body).await?;
+    Ok(stream.size)
+}
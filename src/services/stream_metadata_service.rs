@@ -0,0 +1,83 @@
+use crate::models::{AppState, StreamMount, StreamServer};
+use crate::services::NowPlayingService;
+use anyhow::Result;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Keeps the titles of the night's Icecast/SHOUTcast streams on the DJ and track being played
+pub struct StreamMetadataService {
+    app_state: Arc<AppState>,
+    client: reqwest::Client,
+}
+
+impl StreamMetadataService {
+    pub fn new(app_state: Arc<AppState>) -> Self {
+        Self {
+            app_state,
+            client: reqwest::Client::builder()
+                .timeout(std::time::Duration::from_secs(5))
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Push the current title to every mount whose title differs from the last one it took.
+    /// `sent` maps rooms to the title last pushed; a failed push is retried on the next call.
+    /// Titles are left alone between sets. Returns the number of mounts updated.
+    pub async fn sync(&self, sent: &mut HashMap<String, String>) -> Result<usize> {
+        let Some(now_playing) = NowPlayingService::new(self.app_state.clone()).now_playing().await? else {
+            return Ok(0);
+        };
+
+        let mut updated = 0;
+        for mount in self.app_state.config.stream_metadata_config.mounts.iter().filter(|mount| mount.enabled) {
+            let title = mount.title(&now_playing);
+            if sent.get(&mount.room) == Some(&title) {
+                continue;
+            }
+            match self.update_mount(mount, &title).await {
+                Ok(()) => {
+                    tracing::info!("Stream title of {} set to '{}'", mount.room, title);
+                    sent.insert(mount.room.clone(), title);
+                    updated += 1;
+                }
+                Err(e) => tracing::warn!("Failed to update stream title of {}: {}", mount.room, e),
+            }
+        }
+        Ok(updated)
+    }
+
+    pub async fn update_mount(&self, mount: &StreamMount, title: &str) -> Result<()> {
+        let url = mount.url.trim_end_matches('/');
+        let request = match mount.server {
+            StreamServer::Icecast => self
+                .client
+                .get(format!("{}/admin/metadata", url))
+                .basic_auth(&mount.username, Some(&mount.password))
+                .query(&[("mount", mount.mount.as_str()), ("mode", "updinfo"), ("song", title), ("charset", "UTF-8")]),
+            // SHOUTcast 1 answers admin.cgi only to browser-like user agents
+            StreamServer::Shoutcast => self
+                .client
+                .get(format!("{}/admin.cgi", url))
+                .header(reqwest::header::USER_AGENT, "Mozilla/5.0 (compatible; DA Slotify)")
+                .query(&[("pass", mount.password.as_str()), ("mode", "updinfo"), ("song", title)]),
+        };
+        request.send().await?.error_for_status()?;
+        Ok(())
+    }
+}
+
+/// Check for set and track changes every `interval_seconds` until the task is dropped
+pub async fn run_stream_metadata_sync(app_state: Arc<AppState>, interval_seconds: u64) {
+    let service = StreamMetadataService::new(app_state);
+    let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(interval_seconds.max(1)));
+    let mut sent = HashMap::new();
+
+    loop {
+        interval.tick().await;
+
+        if let Err(e) = service.sync(&mut sent).await {
+            tracing::error!("Error updating stream titles: {}", e);
+        }
+    }
+}
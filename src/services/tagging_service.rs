@@ -0,0 +1,129 @@
+use crate::audio::{write_tags, CoverArt, RecordingTags, TaggedFormat};
+use crate::models::{event_session::DEFAULT_EVENT_NAME, AppState, EventSession};
+use crate::utils::generate_session_filename;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use sqlx::{Row, SqlitePool};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Prepares the files DJs receive: tagged with DJ, event and slot, and named after the DJ
+/// instead of the recorder's `data.flac`
+pub struct TaggingService {
+    db: SqlitePool,
+    app_state: Arc<AppState>,
+}
+
+impl TaggingService {
+    pub fn new(app_state: Arc<AppState>) -> Self {
+        Self {
+            db: app_state.db.clone(),
+            app_state,
+        }
+    }
+
+    /// Artist is the DJ, album the event and its date, comment the timetable slot
+    pub async fn session_tags(&self, session_id: &str) -> Result<RecordingTags> {
+        let (tags, _) = self.session_details(session_id).await?;
+        Ok(tags)
+    }
+
+    /// The name the DJ receives a recording of the session under
+    pub async fn delivery_filename(&self, session_id: &str, extension: &str) -> Result<String> {
+        let (tags, started_at) = self.session_details(session_id).await?;
+        Ok(generate_session_filename(&tags.artist, session_id, started_at, extension))
+    }
+
+    /// Copy `source` into `dir` with tags written in, under the name
+    /// `generate_session_filename` gives it. Files that can't be tagged are copied as they are.
+    pub async fn prepare_delivery(&self, session_id: &str, source: &Path, dir: &Path) -> Result<PathBuf> {
+        let (tags, started_at) = self.session_details(session_id).await?;
+
+        let mut header = [0u8; 4];
+        let format = std::fs::File::open(source)?
+            .read_exact(&mut header)
+            .ok()
+            .and_then(|_| TaggedFormat::detect(&header));
+        let extension = match format {
+            Some(format) => format.extension().to_string(),
+            None => source.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_else(|| "bin".to_string()),
+        };
+        let target = dir.join(generate_session_filename(&tags.artist, session_id, started_at, &extension));
+
+        if format.is_some() && self.app_state.config.tagging_config.enabled {
+            let (input, output) = (source.to_path_buf(), target.clone());
+            let tagged = tokio::task::spawn_blocking(move || write_tags(&input, &output, &tags)).await?;
+            match tagged {
+                Ok(_) => return Ok(target),
+                Err(e) => tracing::warn!("Could not tag recording of session {}, delivering it untagged: {}", session_id, e),
+            }
+        }
+
+        tokio::fs::copy(source, &target).await?;
+        Ok(target)
+    }
+
+    async fn session_details(&self, session_id: &str) -> Result<(RecordingTags, DateTime<Utc>)> {
+        let row = sqlx::query(
+            r#"
+            SELECT s.started_at, s.ended_at, d.name, d.position_in_queue
+            FROM sessions s JOIN djs d ON d.id = s.dj_id
+            WHERE s.id = ?
+            "#,
+        )
+        .bind(session_id)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| anyhow!("Session not found"))?;
+        let started_at: DateTime<Utc> = row.get("started_at");
+        let ended_at: Option<DateTime<Utc>> = row.get("ended_at");
+
+        // The event the set was played at
+        let event = sqlx::query_as::<_, EventSession>(
+            r#"
+            SELECT * FROM event_sessions
+            WHERE started_at <= ? AND (ended_at IS NULL OR ended_at >= ?)
+            ORDER BY started_at DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(started_at)
+        .bind(started_at)
+        .fetch_optional(&self.db)
+        .await?;
+        let (event_name, event_date) = match &event {
+            Some(event) => (event.name.as_deref().unwrap_or(DEFAULT_EVENT_NAME), event.started_at),
+            None => (DEFAULT_EVENT_NAME, started_at),
+        };
+
+        let times = match ended_at {
+            Some(ended_at) => format!("{}-{} UTC", started_at.format("%H:%M"), ended_at.format("%H:%M")),
+            None => format!("from {} UTC", started_at.format("%H:%M")),
+        };
+        let comment = match row.get::<Option<i32>, _>("position_in_queue").filter(|_| event.is_some()) {
+            Some(position) => format!("Slot {}, {}", position, times),
+            None => times,
+        };
+
+        let tags = RecordingTags {
+            artist: row.get("name"),
+            album: format!("{} {}", event_name, event_date.format("%Y-%m-%d")),
+            date: started_at.format("%Y-%m-%d").to_string(),
+            comment: Some(comment),
+            cover: self.cover_art(),
+        };
+        Ok((tags, started_at))
+    }
+
+    fn cover_art(&self) -> Option<CoverArt> {
+        let path = self.app_state.config.tagging_config.cover_art_path.as_ref()?;
+        match CoverArt::from_file(path) {
+            Ok(cover) => Some(cover),
+            Err(e) => {
+                tracing::warn!("Ignoring cover art {:?}: {}", path, e);
+                None
+            }
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use crate::models::{
+    event_session::DEFAULT_EVENT_NAME, AppState, BrandingConfig, ExportedSlot, RenderedTimetable, TimetableExport,
+    TimetableExportError, TimetableFormat,
+};
+use crate::services::EventService;
+use crate::utils::{csv_field, html_escape, markdown_cell, sanitize_filename};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use std::fmt::Write as _;
+use std::sync::Arc;
+
+/// The timetable of any event, past or running, as a file to publish or print. Times are shown
+/// in the calendar time zone, so the venue's export matches its calendar feed.
+pub struct TimetableExportService {
+    app_state: Arc<AppState>,
+}
+
+impl TimetableExportService {
+    pub fn new(app_state: Arc<AppState>) -> Self {
+        Self { app_state }
+    }
+
+    pub async fn export(&self, event_id: &str, format: TimetableFormat) -> Result<RenderedTimetable> {
+        let timetable = EventService::new(self.app_state.clone())
+            .get_event_timetable(event_id)
+            .await?
+            .ok_or(TimetableExportError::EventNotFound)?;
+
+        let timezone = self.timezone();
+        let export = TimetableExport::new(&timetable, timezone.name());
+        let body = match format {
+            TimetableFormat::Csv => timetable_csv(&export, timezone),
+            TimetableFormat::Json => serde_json::to_string_pretty(&export)? + "\n",
+            TimetableFormat::Markdown => timetable_markdown(&export, timezone),
+            TimetableFormat::Html => timetable_html(&export, timezone, &self.app_state.config.branding_config),
+        };
+
+        Ok(RenderedTimetable {
+            filename: format!(
+                "{}_{}_timetable.{}",
+                sanitize_filename(event_name(&export)).replace(' ', "_"),
+                export.event.started_at.with_timezone(&timezone).format("%Y-%m-%d"),
+                format.extension()
+            ),
+            content_type: format.content_type(),
+            body,
+        })
+    }
+
+    fn timezone(&self) -> Tz {
+        self.app_state.config.calendar_config.timezone.unwrap_or(Tz::UTC)
+    }
+}
+
+fn event_name(export: &TimetableExport) -> &str {
+    export.event.name.as_deref().unwrap_or(DEFAULT_EVENT_NAME)
+}
+
+fn local_time(time: DateTime<Utc>, timezone: Tz) -> String {
+    time.with_timezone(&timezone).format("%H:%M").to_string()
+}
+
+/// `22:00–23:00` in the venue's time zone
+fn slot_times(slot: &ExportedSlot, timezone: Tz) -> String {
+    format!("{}–{}", local_time(slot.starts_at, timezone), local_time(slot.ends_at, timezone))
+}
+
+/// `Headliner · Played`; lottery slots only show their status
+fn slot_note(slot: &ExportedSlot) -> String {
+    let booking = slot
+        .label
+        .clone()
+        .or_else(|| slot.booking.map(|kind| kind.label().to_string()));
+    match booking {
+        Some(booking) => format!("{} · {}", booking, slot.status.label()),
+        None => slot.status.label().to_string(),
+    }
+}
+
+/// `Friday 15 March 2024 · times in Europe/Berlin · 60 min slots`
+fn event_subtitle(export: &TimetableExport, timezone: Tz) -> String {
+    format!(
+        "{} · times in {} · {} min slots",
+        export.event.started_at.with_timezone(&timezone).format("%A %-d %B %Y"),
+        timezone.name(),
+        export.event.slot_duration_minutes
+    )
+}
+
+fn timetable_csv(export: &TimetableExport, timezone: Tz) -> String {
+    let mut csv = String::from("position,dj_name,starts_at,ends_at,local_start,local_end,status,booking,label\n");
+    for slot in &export.slots {
+        let fields = [
+            slot.position.to_string(),
+            slot.dj_name.clone(),
+            slot.starts_at.to_rfc3339(),
+            slot.ends_at.to_rfc3339(),
+            local_time(slot.starts_at, timezone),
+            local_time(slot.ends_at, timezone),
+            slot.status.as_str().to_string(),
+            slot.booking.map(|kind| kind.label().to_string()).unwrap_or_default(),
+            slot.label.clone().unwrap_or_default(),
+        ];
+        let line: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+        csv.push_str(&line.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+fn timetable_markdown(export: &TimetableExport, timezone: Tz) -> String {
+    let mut markdown = String::new();
+    let _ = writeln!(markdown, "# {}", markdown_cell(event_name(export)));
+    let _ = writeln!(markdown);
+    let _ = writeln!(markdown, "{}", event_subtitle(export, timezone));
+    let _ = writeln!(markdown);
+    let _ = writeln!(markdown, "| # | Time | DJ | Note |");
+    let _ = writeln!(markdown, "|--:|------|----|------|");
+    for slot in &export.slots {
+        let _ = writeln!(
+            markdown,
+            "| {} | {} | {} | {} |",
+            slot.position,
+            slot_times(slot, timezone),
+            markdown_cell(&slot.dj_name),
+            markdown_cell(&slot_note(slot))
+        );
+    }
+    markdown
+}
+
+/// A self-contained page that prints on one sheet for the door or the booth
+fn timetable_html(export: &TimetableExport, timezone: Tz, branding: &BrandingConfig) -> String {
+    let name = html_escape(event_name(export));
+    let accent = html_escape(&branding.accent_color);
+
+    let mut html = String::new();
+    let _ = writeln!(html, "<!DOCTYPE html>");
+    let _ = writeln!(html, "<html lang=\"en\">");
+    let _ = writeln!(html, "<head>");
+    let _ = writeln!(html, "<meta charset=\"utf-8\">");
+    let _ = writeln!(html, "<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">");
+    let _ = writeln!(html, "<title>{} – Timetable</title>", name);
+    let _ = writeln!(html, "<style>");
+    let _ = writeln!(html, ":root {{ --accent: {}; }}", accent);
+    let _ = writeln!(html, "body {{ font-family: -apple-system, 'Helvetica Neue', Arial, sans-serif; color: #111; max-width: 48rem; margin: 2rem auto; padding: 0 1rem; }}");
+    let _ = writeln!(html, "header {{ display: flex; align-items: center; gap: 1rem; border-bottom: 4px solid var(--accent); padding-bottom: 1rem; }}");
+    let _ = writeln!(html, "header img {{ max-height: 4rem; }}");
+    let _ = writeln!(html, "h1 {{ margin: 0; font-size: 2rem; }}");
+    let _ = writeln!(html, ".brand {{ color: var(--accent); font-weight: 600; text-transform: uppercase; letter-spacing: .08em; font-size: .8rem; }}");
+    let _ = writeln!(html, ".subtitle {{ color: #555; margin: .25rem 0 0; }}");
+    let _ = writeln!(html, "table {{ width: 100%; border-collapse: collapse; margin-top: 1.5rem; font-size: 1.1rem; }}");
+    let _ = writeln!(html, "th, td {{ text-align: left; padding: .5rem .75rem; border-bottom: 1px solid #ddd; }}");
+    let _ = writeln!(html, "th {{ font-size: .75rem; text-transform: uppercase; color: #555; }}");
+    let _ = writeln!(html, "td.time {{ font-variant-numeric: tabular-nums; white-space: nowrap; }}");
+    let _ = writeln!(html, "td.dj {{ font-weight: 600; }}");
+    let _ = writeln!(html, "tr.playing td {{ background: var(--accent); color: #fff; }}");
+    let _ = writeln!(html, "tr.played td {{ color: #777; }}");
+    let _ = writeln!(html, "footer {{ margin-top: 2rem; color: #777; font-size: .85rem; }}");
+    let _ = writeln!(html, "@media print {{ body {{ margin: 0; max-width: none; }} tr {{ break-inside: avoid; }} tr.playing td {{ -webkit-print-color-adjust: exact; print-color-adjust: exact; }} }}");
+    let _ = writeln!(html, "</style>");
+    let _ = writeln!(html, "</head>");
+    let _ = writeln!(html, "<body>");
+    let _ = writeln!(html, "<header>");
+    if let Some(logo_url) = &branding.logo_url {
+        let _ = writeln!(html, "<img src=\"{}\" alt=\"{}\">", html_escape(logo_url), html_escape(&branding.name));
+    }
+    let _ = writeln!(html, "<div>");
+    let _ = writeln!(html, "<div class=\"brand\">{}</div>", html_escape(&branding.name));
+    let _ = writeln!(html, "<h1>{}</h1>", name);
+    let _ = writeln!(html, "<p class=\"subtitle\">{}</p>", html_escape(&event_subtitle(export, timezone)));
+    let _ = writeln!(html, "</div>");
+    let _ = writeln!(html, "</header>");
+    let _ = writeln!(html, "<table>");
+    let _ = writeln!(html, "<thead><tr><th>#</th><th>Time</th><th>DJ</th><th>Note</th></tr></thead>");
+    let _ = writeln!(html, "<tbody>");
+    for slot in &export.slots {
+        let _ = writeln!(
+            html,
+            "<tr class=\"{}\"><td>{}</td><td class=\"time\">{}</td><td class=\"dj\">{}</td><td>{}</td></tr>",
+            slot.status.as_str(),
+            slot.position,
+            slot_times(slot, timezone),
+            html_escape(&slot.dj_name),
+            html_escape(&slot_note(slot))
+        );
+    }
+    let _ = writeln!(html, "</tbody>");
+    let _ = writeln!(html, "</table>");
+    if let Some(footer) = &branding.footer {
+        let _ = writeln!(html, "<footer>{}</footer>", html_escape(footer));
+    }
+    let _ = writeln!(html, "</body>");
+    let _ = writeln!(html, "</html>");
+    html
+}
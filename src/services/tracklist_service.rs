@@ -0,0 +1,199 @@
+use crate::models::{
+    decode_tracklist, format_tracklist, normalize_tracks, AppState, SubmitTracklistRequest, Track,
+    TracklistError, TracklistFormat, TracklistResponse,
+};
+use crate::utils::{cue_text, sanitize_filename, write_cue_track};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sqlx::{Row, SqlitePool};
+use std::sync::Arc;
+
+/// Keeps the tracklist of each set, submitted as tracks or as a pasted or exported
+/// tracklist, and renders it as a cue sheet or as text for emails and archives
+pub struct TracklistService {
+    db: SqlitePool,
+}
+
+/// A session's tracklist rendered as a cue sheet over its delivered recording
+#[derive(Debug, Clone)]
+pub struct TracklistCueSheet {
+    pub filename: String, // `<recording name>.cue`
+    pub content: String,
+}
+
+impl TracklistService {
+    pub fn new(app_state: Arc<AppState>) -> Self {
+        Self {
+            db: app_state.db.clone(),
+        }
+    }
+
+    pub async fn get_tracklist(&self, session_id: &str) -> Result<TracklistResponse> {
+        let row = sqlx::query(
+            "SELECT s.started_at, s.tracklist_updated_at, d.name FROM sessions s JOIN djs d ON d.id = s.dj_id WHERE s.id = ?"
+        )
+        .bind(session_id)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or(TracklistError::SessionNotFound)?;
+
+        Ok(TracklistResponse {
+            session_id: session_id.to_string(),
+            dj_name: row.get("name"),
+            session_started_at: row.get("started_at"),
+            updated_at: row.get("tracklist_updated_at"),
+            tracks: self.get_tracks(session_id).await?,
+        })
+    }
+
+    /// Tracks of a session in order; empty if none were submitted
+    pub async fn get_tracks(&self, session_id: &str) -> Result<Vec<Track>> {
+        let tracks = sqlx::query_as::<_, Track>(
+            "SELECT position, offset_seconds, artist, title FROM tracklist_tracks WHERE session_id = ? ORDER BY position"
+        )
+        .bind(session_id)
+        .fetch_all(&self.db)
+        .await?;
+        Ok(tracks)
+    }
+
+    /// Replace the session's tracklist with the submitted tracks or parsed text
+    pub async fn set_tracklist(&self, session_id: &str, request: SubmitTracklistRequest) -> Result<TracklistResponse> {
+        let tracks = match request.text {
+            Some(text) => request.format.parse(&text)?,
+            None => normalize_tracks(request.tracks)?,
+        };
+        self.store(session_id, tracks).await
+    }
+
+    /// Replace the session's tracklist with an uploaded export
+    pub async fn import_tracklist(&self, session_id: &str, data: &[u8], format: TracklistFormat) -> Result<TracklistResponse> {
+        let text = decode_tracklist(data)?;
+        let tracks = format.parse(&text)?;
+        self.store(session_id, tracks).await
+    }
+
+    pub async fn delete_tracklist(&self, session_id: &str) -> Result<()> {
+        let mut tx = self.db.begin().await?;
+        let cleared = sqlx::query("UPDATE sessions SET tracklist_updated_at = NULL WHERE id = ?")
+            .bind(session_id)
+            .execute(&mut *tx)
+            .await?;
+        if cleared.rows_affected() == 0 {
+            return Err(TracklistError::SessionNotFound.into());
+        }
+        sqlx::query("DELETE FROM tracklist_tracks WHERE session_id = ?")
+            .bind(session_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn store(&self, session_id: &str, tracks: Vec<Track>) -> Result<TracklistResponse> {
+        let mut tx = self.db.begin().await?;
+        let updated = sqlx::query("UPDATE sessions SET tracklist_updated_at = ? WHERE id = ?")
+            .bind(Utc::now())
+            .bind(session_id)
+            .execute(&mut *tx)
+            .await?;
+        if updated.rows_affected() == 0 {
+            return Err(TracklistError::SessionNotFound.into());
+        }
+
+        sqlx::query("DELETE FROM tracklist_tracks WHERE session_id = ?")
+            .bind(session_id)
+            .execute(&mut *tx)
+            .await?;
+        for track in &tracks {
+            sqlx::query(
+                "INSERT INTO tracklist_tracks (session_id, position, offset_seconds, artist, title) VALUES (?, ?, ?, ?, ?)"
+            )
+            .bind(session_id)
+            .bind(track.position)
+            .bind(track.offset_seconds)
+            .bind(&track.artist)
+            .bind(&track.title)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        tracing::info!("Stored tracklist of {} tracks for session {}", tracks.len(), session_id);
+        self.get_tracklist(session_id).await
+    }
+
+    /// The tracklist as text, headed by the DJ and set times; `None` without a tracklist
+    pub async fn formatted_tracklist(&self, session_id: &str) -> Result<Option<String>> {
+        let tracklist = self.get_tracklist(session_id).await?;
+        if tracklist.tracks.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(format!(
+            "{} - {} UTC\n\n{}",
+            tracklist.dj_name,
+            tracklist.session_started_at.format("%Y-%m-%d %H:%M"),
+            format_tracklist(&tracklist.tracks)
+        )))
+    }
+
+    /// Cue sheet over the session's recording. Per-DJ files start with the set; a recorder
+    /// recording can hold several sets, so track times are moved by where the set starts in it.
+    pub async fn cue_sheet(&self, session_id: &str) -> Result<TracklistCueSheet> {
+        let row = sqlx::query(
+            r#"
+            SELECT s.started_at, s.file_path, s.storage_key, s.recorder_flac_url, s.recorder_ogg_url,
+                   d.name, r.start_time AS recorder_start_time
+            FROM sessions s
+            JOIN djs d ON d.id = s.dj_id
+            LEFT JOIN recorder_sessions r ON r.recorder_id = s.recorder_id AND r.id = s.recorder_session_id
+            WHERE s.id = ?
+            "#,
+        )
+        .bind(session_id)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or(TracklistError::SessionNotFound)?;
+
+        let tracks = self.get_tracks(session_id).await?;
+        if tracks.is_empty() {
+            return Err(TracklistError::NoTracklist.into());
+        }
+        if tracks.iter().all(|track| track.offset_seconds.is_none()) {
+            return Err(TracklistError::NoStartTimes.into());
+        }
+
+        let dj_name: String = row.get("name");
+        let started_at: DateTime<Utc> = row.get("started_at");
+        let file_path: Option<String> = row.get("file_path");
+        let file_name = |path: &str| path.rsplit(['/', '\\']).next().unwrap_or(path).to_string();
+        let recording = match (row.get::<Option<String>, _>("storage_key"), &file_path) {
+            (Some(key), _) => file_name(&key),
+            (None, Some(path)) => file_name(path),
+            (None, None) if row.get::<Option<String>, _>("recorder_flac_url").is_some() => "data.flac".to_string(),
+            (None, None) if row.get::<Option<String>, _>("recorder_ogg_url").is_some() => "data.ogg".to_string(),
+            (None, None) => format!("{}.flac", sanitize_filename(&dj_name).replace(' ', "_")),
+        };
+        let set_offset = match (&file_path, row.get::<Option<DateTime<Utc>>, _>("recorder_start_time")) {
+            (None, Some(recording_started_at)) => ((started_at - recording_started_at).num_milliseconds() as f64 / 1000.0).max(0.0),
+            _ => 0.0,
+        };
+
+        let mut cue = String::new();
+        cue.push_str(&format!("REM DATE {}\n", started_at.format("%Y-%m-%d")));
+        cue.push_str(&format!("PERFORMER \"{}\"\n", cue_text(&dj_name)));
+        cue.push_str(&format!("TITLE \"{}\"\n", cue_text(&format!("{} {} UTC", dj_name, started_at.format("%Y-%m-%d %H:%M")))));
+        cue.push_str(&format!("FILE \"{}\" WAVE\n", cue_text(&recording)));
+        let timed = tracks.iter().filter_map(|track| track.offset_seconds.map(|offset| (track, offset)));
+        for (number, (track, offset)) in timed.enumerate() {
+            let performer = track.artist.as_deref().unwrap_or(&dj_name);
+            write_cue_track(&mut cue, number + 1, &track.title, performer, set_offset + offset);
+        }
+
+        let stem = recording.rsplit_once('.').map(|(stem, _)| stem).unwrap_or(&recording);
+        Ok(TracklistCueSheet {
+            filename: format!("{}.cue", stem),
+            content: cue,
+        })
+    }
+}
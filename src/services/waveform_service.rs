@@ -0,0 +1,117 @@
+use crate::audio::WaveformData;
+use crate::models::{AppState, SessionWaveformResponse, WaveformError, WaveformPeak, WaveformSlot, MAX_WAVEFORM_POINTS};
+use crate::services::{SessionRecorderConfig, SessionRecorderService};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sqlx::{Row, SqlitePool};
+use std::sync::Arc;
+use tokio::io::AsyncReadExt;
+
+/// File the recorder writes next to each recording
+const WAVEFORM_FILE: &str = "waveform.dat";
+
+/// Serves the recorder's `waveform.dat` of a session's recording as downsampled peaks,
+/// with the sets played on that recording marked in it
+pub struct WaveformService {
+    db: SqlitePool,
+    app_state: Arc<AppState>,
+}
+
+impl WaveformService {
+    pub fn new(app_state: Arc<AppState>) -> Self {
+        Self {
+            db: app_state.db.clone(),
+            app_state,
+        }
+    }
+
+    pub async fn session_waveform(&self, session_id: &str, points: usize) -> Result<SessionWaveformResponse> {
+        let row = sqlx::query(
+            r#"
+            SELECT s.recorder_id, s.recorder_session_id, r.start_time
+            FROM sessions s
+            LEFT JOIN recorder_sessions r ON r.recorder_id = s.recorder_id AND r.id = s.recorder_session_id
+            WHERE s.id = ?
+            "#,
+        )
+        .bind(session_id)
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or(WaveformError::SessionNotFound)?;
+
+        let (Some(recorder_id), Some(recorder_session_id)) = (
+            row.get::<Option<String>, _>("recorder_id"),
+            row.get::<Option<String>, _>("recorder_session_id"),
+        ) else {
+            return Err(WaveformError::NoRecording.into());
+        };
+        if !self.app_state.config.session_recorder_config.enabled {
+            return Err(WaveformError::NoRecording.into());
+        }
+        let indexed_start: Option<DateTime<Utc>> = row.get("start_time");
+
+        let config = SessionRecorderConfig::from(&self.app_state.config.session_recorder_config);
+        let recorder = SessionRecorderService::new(config).await?;
+        let recording_started_at = match indexed_start {
+            Some(start) => start,
+            None => recorder.get_session_details(&recorder_id, &recorder_session_id).await?.start_time,
+        };
+
+        let mut stream = recorder
+            .stream_session_file(&recorder_id, &recorder_session_id, WAVEFORM_FILE, None)
+            .await
+            .map_err(|_| WaveformError::NoWaveform)?;
+        let mut data = Vec::with_capacity(stream.size as usize);
+        stream.body.read_to_end(&mut data).await?;
+        let waveform = WaveformData::parse(&data).map_err(|e| WaveformError::Invalid(e.to_string()))?;
+
+        let slots = self.slots(&recorder_id, &recorder_session_id, recording_started_at).await?;
+        let points = points.clamp(1, MAX_WAVEFORM_POINTS);
+        let peaks: Vec<WaveformPeak> = waveform
+            .peaks(0..waveform.len(), points)
+            .into_iter()
+            .map(|(min, max)| WaveformPeak { min, max })
+            .collect();
+        let duration_seconds = waveform.duration_seconds();
+        let seconds_per_peak = if peaks.is_empty() { 0.0 } else { duration_seconds / peaks.len() as f64 };
+
+        Ok(SessionWaveformResponse {
+            session_id: session_id.to_string(),
+            recorder_id,
+            recorder_session_id,
+            recording_started_at,
+            duration_seconds,
+            seconds_per_peak,
+            peaks,
+            slots,
+        })
+    }
+
+    /// Every set linked to the recording, in the order they were played
+    async fn slots(&self, recorder_id: &str, recorder_session_id: &str, recording_started_at: DateTime<Utc>) -> Result<Vec<WaveformSlot>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT s.id, s.started_at, s.ended_at, d.name
+            FROM sessions s
+            JOIN djs d ON d.id = s.dj_id
+            WHERE s.recorder_id = ? AND s.recorder_session_id = ?
+            ORDER BY s.started_at ASC
+            "#,
+        )
+        .bind(recorder_id)
+        .bind(recorder_session_id)
+        .fetch_all(&self.db)
+        .await?;
+
+        let offset = |at: DateTime<Utc>| ((at - recording_started_at).num_milliseconds() as f64 / 1000.0).max(0.0);
+        Ok(rows
+            .iter()
+            .map(|row| WaveformSlot {
+                session_id: row.get("id"),
+                dj_name: row.get("name"),
+                start_seconds: offset(row.get("started_at")),
+                end_seconds: row.get::<Option<DateTime<Utc>>, _>("ended_at").map(offset),
+            })
+            .collect())
+    }
+}
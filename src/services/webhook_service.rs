@@ -0,0 +1,394 @@
+use crate::models::{
+    recorder_webhook::WEBHOOK_SIGNATURE_HEADER,
+    webhook::{WEBHOOK_DELIVERY_HEADER, WEBHOOK_EVENT_HEADER},
+    AppState, CreateWebhookRequest, UpdateWebhookRequest, WebhookDelivery, WebhookEnvelope, WebhookError,
+    WebhookEventType, WebhookSubscription, WebhookSubscriptionResponse,
+};
+use anyhow::Result;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha2::Sha256;
+use sqlx::SqlitePool;
+use std::sync::Arc;
+use uuid::Uuid;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Most deliveries listed per subscription
+const DELIVERY_LOG_LIMIT: i64 = 100;
+
+/// Sends domain events to the webhook subscriptions admins registered, signed, retried
+/// with backoff and logged per delivery
+pub struct WebhookService {
+    db: SqlitePool,
+    app_state: Arc<AppState>,
+    client: reqwest::Client,
+}
+
+impl WebhookService {
+    pub fn new(app_state: Arc<AppState>) -> Self {
+        let timeout = std::time::Duration::from_secs(app_state.config.webhook_config.timeout_seconds);
+        Self {
+            db: app_state.db.clone(),
+            app_state,
+            client: reqwest::Client::builder().timeout(timeout).build().unwrap_or_default(),
+        }
+    }
+
+    pub async fn create_subscription(&self, request: CreateWebhookRequest) -> Result<WebhookSubscriptionResponse> {
+        validate_url(&request.url)?;
+        if request.event_types.contains(&WebhookEventType::Ping) {
+            return Err(WebhookError::PingSubscription.into());
+        }
+        let secret = match request.secret.filter(|secret| !secret.is_empty()) {
+            Some(secret) => secret,
+            None => {
+                let mut bytes = [0u8; 32];
+                rand::thread_rng().fill_bytes(&mut bytes);
+                hex::encode(bytes)
+            }
+        };
+
+        let subscription = WebhookSubscription {
+            id: Uuid::new_v4().to_string(),
+            url: request.url,
+            secret,
+            event_types: serde_json::to_string(&request.event_types)?,
+            description: request.description,
+            enabled: true,
+            created_at: Utc::now(),
+        };
+        sqlx::query(
+            r#"
+            INSERT INTO webhook_subscriptions (id, url, secret, event_types, description, enabled, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&subscription.id)
+        .bind(&subscription.url)
+        .bind(&subscription.secret)
+        .bind(&subscription.event_types)
+        .bind(&subscription.description)
+        .bind(subscription.enabled)
+        .bind(subscription.created_at)
+        .execute(&self.db)
+        .await?;
+
+        tracing::info!("Webhook subscription {} created for {}", subscription.id, subscription.url);
+        let secret = subscription.secret.clone();
+        let mut response = WebhookSubscriptionResponse::from(subscription);
+        response.secret = Some(secret);
+        Ok(response)
+    }
+
+    pub async fn list_subscriptions(&self) -> Result<Vec<WebhookSubscriptionResponse>> {
+        let subscriptions = sqlx::query_as::<_, WebhookSubscription>(
+            "SELECT * FROM webhook_subscriptions ORDER BY created_at ASC"
+        )
+        .fetch_all(&self.db)
+        .await?;
+        Ok(subscriptions.into_iter().map(WebhookSubscriptionResponse::from).collect())
+    }
+
+    pub async fn get_subscription(&self, id: &str) -> Result<WebhookSubscriptionResponse> {
+        Ok(self.find_subscription(id).await?.into())
+    }
+
+    async fn find_subscription(&self, id: &str) -> Result<WebhookSubscription> {
+        let subscription = sqlx::query_as::<_, WebhookSubscription>("SELECT * FROM webhook_subscriptions WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await?
+            .ok_or(WebhookError::NotFound)?;
+        Ok(subscription)
+    }
+
+    pub async fn update_subscription(&self, id: &str, request: UpdateWebhookRequest) -> Result<WebhookSubscriptionResponse> {
+        let mut subscription = self.find_subscription(id).await?;
+        if let Some(url) = request.url {
+            validate_url(&url)?;
+            subscription.url = url;
+        }
+        if let Some(event_types) = request.event_types {
+            if event_types.contains(&WebhookEventType::Ping) {
+                return Err(WebhookError::PingSubscription.into());
+            }
+            subscription.event_types = serde_json::to_string(&event_types)?;
+        }
+        if let Some(description) = request.description {
+            subscription.description = Some(description).filter(|description| !description.is_empty());
+        }
+        if let Some(enabled) = request.enabled {
+            subscription.enabled = enabled;
+        }
+
+        sqlx::query("UPDATE webhook_subscriptions SET url = ?, event_types = ?, description = ?, enabled = ? WHERE id = ?")
+            .bind(&subscription.url)
+            .bind(&subscription.event_types)
+            .bind(&subscription.description)
+            .bind(subscription.enabled)
+            .bind(id)
+            .execute(&self.db)
+            .await?;
+        Ok(subscription.into())
+    }
+
+    /// Remove a subscription along with its delivery log
+    pub async fn delete_subscription(&self, id: &str) -> Result<()> {
+        let deleted = sqlx::query("DELETE FROM webhook_subscriptions WHERE id = ?")
+            .bind(id)
+            .execute(&self.db)
+            .await?;
+        if deleted.rows_affected() == 0 {
+            return Err(WebhookError::NotFound.into());
+        }
+        Ok(())
+    }
+
+    /// Newest deliveries of a subscription first
+    pub async fn get_deliveries(&self, subscription_id: &str) -> Result<Vec<WebhookDelivery>> {
+        self.find_subscription(subscription_id).await?;
+        let deliveries = sqlx::query_as::<_, WebhookDelivery>(
+            "SELECT * FROM webhook_deliveries WHERE subscription_id = ? ORDER BY created_at DESC LIMIT ?"
+        )
+        .bind(subscription_id)
+        .bind(DELIVERY_LOG_LIMIT)
+        .fetch_all(&self.db)
+        .await?;
+        Ok(deliveries)
+    }
+
+    /// Queue an event for every enabled subscription that wants it and start delivering in the
+    /// background. Failures are logged; the action that raised the event goes ahead regardless.
+    pub async fn emit(&self, event_type: WebhookEventType, data: serde_json::Value) {
+        match self.enqueue(event_type, data).await {
+            Ok(queued) if !queued.is_empty() => {
+                let app_state = self.app_state.clone();
+                tokio::spawn(async move {
+                    let service = WebhookService::new(app_state);
+                    for delivery_id in queued {
+                        if let Err(e) = service.deliver(&delivery_id).await {
+                            tracing::error!("Error delivering webhook {}: {}", delivery_id, e);
+                        }
+                    }
+                });
+            }
+            Ok(_) => {}
+            Err(e) => tracing::error!("Failed to queue {} webhooks: {}", event_type.as_str(), e),
+        }
+    }
+
+    async fn enqueue(&self, event_type: WebhookEventType, data: serde_json::Value) -> Result<Vec<String>> {
+        let subscriptions = sqlx::query_as::<_, WebhookSubscription>("SELECT * FROM webhook_subscriptions WHERE enabled = TRUE")
+            .fetch_all(&self.db)
+            .await?;
+        let subscriptions: Vec<WebhookSubscription> = subscriptions.into_iter().filter(|s| s.wants(event_type)).collect();
+        if subscriptions.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let envelope = WebhookEnvelope {
+            id: Uuid::new_v4().to_string(),
+            event_type,
+            created_at: Utc::now(),
+            data,
+        };
+        let payload = serde_json::to_string(&envelope)?;
+        let mut queued = Vec::with_capacity(subscriptions.len());
+        for subscription in subscriptions {
+            queued.push(self.insert_delivery(&subscription.id, event_type, &payload).await?);
+        }
+        Ok(queued)
+    }
+
+    async fn insert_delivery(&self, subscription_id: &str, event_type: WebhookEventType, payload: &str) -> Result<String> {
+        let id = Uuid::new_v4().to_string();
+        let now = Utc::now();
+        sqlx::query(
+            r#"
+            INSERT INTO webhook_deliveries (id, subscription_id, event_type, payload, status, attempts, next_attempt_at, created_at)
+            VALUES (?, ?, ?, ?, 'pending', 0, ?, ?)
+            "#,
+        )
+        .bind(&id)
+        .bind(subscription_id)
+        .bind(event_type.as_str())
+        .bind(payload)
+        .bind(now)
+        .bind(now)
+        .execute(&self.db)
+        .await?;
+        Ok(id)
+    }
+
+    /// Send a `ping` to one subscription right away and return how it went
+    pub async fn ping(&self, subscription_id: &str) -> Result<WebhookDelivery> {
+        let subscription = self.find_subscription(subscription_id).await?;
+        let envelope = WebhookEnvelope {
+            id: Uuid::new_v4().to_string(),
+            event_type: WebhookEventType::Ping,
+            created_at: Utc::now(),
+            data: serde_json::json!({ "subscription_id": subscription.id }),
+        };
+        let delivery_id = self
+            .insert_delivery(&subscription.id, WebhookEventType::Ping, &serde_json::to_string(&envelope)?)
+            .await?;
+        self.deliver(&delivery_id).await?;
+        self.get_delivery(&delivery_id).await
+    }
+
+    async fn get_delivery(&self, id: &str) -> Result<WebhookDelivery> {
+        let delivery = sqlx::query_as::<_, WebhookDelivery>("SELECT * FROM webhook_deliveries WHERE id = ?")
+            .bind(id)
+            .fetch_one(&self.db)
+            .await?;
+        Ok(delivery)
+    }
+
+    /// Attempt every pending delivery whose retry is due. Returns how many were attempted.
+    pub async fn deliver_due(&self) -> Result<usize> {
+        let due: Vec<String> = sqlx::query_scalar(
+            "SELECT id FROM webhook_deliveries WHERE status = 'pending' AND next_attempt_at <= ? ORDER BY created_at ASC"
+        )
+        .bind(Utc::now())
+        .fetch_all(&self.db)
+        .await?;
+
+        let mut attempted = 0;
+        for delivery_id in due {
+            if self.deliver(&delivery_id).await? {
+                attempted += 1;
+            }
+        }
+        Ok(attempted)
+    }
+
+    /// Make one attempt at a delivery if it is due. Returns `false` if it wasn't, e.g. because
+    /// a concurrent attempt holds it.
+    pub async fn deliver(&self, delivery_id: &str) -> Result<bool> {
+        let config = &self.app_state.config.webhook_config;
+        let now = Utc::now();
+        // Claim the delivery by moving its next attempt past the request timeout
+        let claimed = sqlx::query(
+            "UPDATE webhook_deliveries SET next_attempt_at = ? WHERE id = ? AND status = 'pending' AND next_attempt_at <= ?"
+        )
+        .bind(now + config.attempt_lease())
+        .bind(delivery_id)
+        .bind(now)
+        .execute(&self.db)
+        .await?;
+        if claimed.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        let delivery = self.get_delivery(delivery_id).await?;
+        let subscription = self.find_subscription(&delivery.subscription_id).await?;
+        let attempts = delivery.attempts + 1;
+
+        let outcome = if subscription.enabled {
+            self.send(&subscription, &delivery).await
+        } else {
+            Err((None, "Subscription is disabled".to_string()))
+        };
+        let now = Utc::now();
+        match outcome {
+            Ok(status) => {
+                sqlx::query(
+                    r#"
+                    UPDATE webhook_deliveries
+                    SET status = 'delivered', attempts = ?, next_attempt_at = NULL, last_attempt_at = ?,
+                        response_status = ?, last_error = NULL, delivered_at = ?
+                    WHERE id = ?
+                    "#,
+                )
+                .bind(attempts)
+                .bind(now)
+                .bind(status as i64)
+                .bind(now)
+                .bind(delivery_id)
+                .execute(&self.db)
+                .await?;
+                tracing::debug!("Webhook {} delivered to {}", delivery.event_type, subscription.url);
+            }
+            Err((status, error)) => {
+                let gives_up = attempts >= config.max_attempts || !subscription.enabled;
+                let next_attempt_at = (!gives_up)
+                    .then(|| now + config.retry_delay(attempts));
+                sqlx::query(
+                    r#"
+                    UPDATE webhook_deliveries
+                    SET status = ?, attempts = ?, next_attempt_at = ?, last_attempt_at = ?, response_status = ?, last_error = ?
+                    WHERE id = ?
+                    "#,
+                )
+                .bind(if gives_up { "failed" } else { "pending" })
+                .bind(attempts)
+                .bind(next_attempt_at)
+                .bind(now)
+                .bind(status.map(i64::from))
+                .bind(&error)
+                .bind(delivery_id)
+                .execute(&self.db)
+                .await?;
+                tracing::warn!(
+                    "Webhook {} to {} failed (attempt {}): {}{}",
+                    delivery.event_type, subscription.url, attempts, error,
+                    if gives_up { "; giving up" } else { "" }
+                );
+            }
+        }
+        Ok(true)
+    }
+
+    /// POST the payload; the HTTP status on success, or the status (if any) and error
+    async fn send(&self, subscription: &WebhookSubscription, delivery: &WebhookDelivery) -> Result<u16, (Option<u16>, String)> {
+        let response = self
+            .client
+            .post(&subscription.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(WEBHOOK_EVENT_HEADER, &delivery.event_type)
+            .header(WEBHOOK_DELIVERY_HEADER, &delivery.id)
+            .header(WEBHOOK_SIGNATURE_HEADER, sign(&subscription.secret, delivery.payload.as_bytes()))
+            .body(delivery.payload.clone())
+            .send()
+            .await
+            .map_err(|e| (None, e.to_string()))?;
+
+        let status = response.status();
+        if status.is_success() {
+            Ok(status.as_u16())
+        } else {
+            Err((Some(status.as_u16()), format!("HTTP {}", status)))
+        }
+    }
+}
+
+/// `sha256=<hex HMAC-SHA256 of the body>`, as receivers check it
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+fn validate_url(url: &str) -> Result<(), WebhookError> {
+    match reqwest::Url::parse(url) {
+        Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => Ok(()),
+        Ok(_) => Err(WebhookError::InvalidUrl(format!("{} is not an http(s) URL", url))),
+        Err(e) => Err(WebhookError::InvalidUrl(format!("{}: {}", url, e))),
+    }
+}
+
+/// Retry due deliveries every `interval_seconds` until the task is dropped
+pub async fn run_webhook_retries(app_state: Arc<AppState>, interval_seconds: u64) {
+    let service = WebhookService::new(app_state);
+    let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(interval_seconds));
+
+    loop {
+        interval.tick().await;
+
+        if let Err(e) = service.deliver_due().await {
+            tracing::error!("Error retrying webhooks: {}", e);
+        }
+    }
+}
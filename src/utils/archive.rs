@@ -0,0 +1,349 @@
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Datelike, Timelike, Utc};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Container format of an archive written by `ArchiveWriter`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveKind {
+    Zip,
+    Tar,
+}
+
+/// Streams files into a ZIP or tar archive as they are read. Nothing is buffered beyond one
+/// copy chunk, so an archive of a whole night of recordings can go straight to a socket.
+///
+/// ZIP entries are stored uncompressed (audio doesn't compress) with data descriptors, since
+/// the CRC is only known once the entry has been written; ZIP64 records are added for entries
+/// and offsets past 4 GiB. Tar entries are POSIX ustar.
+pub struct ArchiveWriter<W> {
+    writer: W,
+    kind: ArchiveKind,
+    modified: DateTime<Utc>,
+    position: u64,
+    entries: Vec<ZipEntry>,
+}
+
+/// What the ZIP central directory needs to know about a written entry
+struct ZipEntry {
+    name: String,
+    crc: u32,
+    size: u64,
+    offset: u64,
+}
+
+const COPY_CHUNK: usize = 64 * 1024;
+const TAR_BLOCK: usize = 512;
+const ZIP_MAX_32: u64 = 0xFFFF_FFFF;
+const ZIP_MAX_16: u64 = 0xFFFF;
+const ZIP_FLAGS: u16 = 0x0808; // Sizes in data descriptor, UTF-8 names
+const ZIP_VERSION: u16 = 20;
+const ZIP64_VERSION: u16 = 45;
+
+impl<W: AsyncWrite + Unpin> ArchiveWriter<W> {
+    /// `modified` is the timestamp given to every entry
+    pub fn new(writer: W, kind: ArchiveKind, modified: DateTime<Utc>) -> Self {
+        Self {
+            writer,
+            kind,
+            modified,
+            position: 0,
+            entries: Vec::new(),
+        }
+    }
+
+    pub async fn add_bytes(&mut self, name: &str, data: &[u8]) -> Result<()> {
+        self.add_stream(name, data.len() as u64, data).await
+    }
+
+    /// Add an entry of exactly `size` bytes read from `reader`
+    pub async fn add_stream<R: AsyncRead + Unpin>(&mut self, name: &str, size: u64, reader: R) -> Result<()> {
+        match self.kind {
+            ArchiveKind::Zip => self.add_zip_entry(name, size, reader).await,
+            ArchiveKind::Tar => self.add_tar_entry(name, size, reader).await,
+        }
+    }
+
+    /// Write the trailer and hand back the underlying writer
+    pub async fn finish(mut self) -> Result<W> {
+        match self.kind {
+            ArchiveKind::Zip => self.write_zip_central_directory().await?,
+            ArchiveKind::Tar => self.write(&[0u8; 2 * TAR_BLOCK]).await?,
+        }
+        self.writer.flush().await?;
+        Ok(self.writer)
+    }
+
+    async fn write(&mut self, data: &[u8]) -> Result<()> {
+        self.writer.write_all(data).await?;
+        self.position += data.len() as u64;
+        Ok(())
+    }
+
+    /// Copy `size` bytes from `reader`, returning their CRC-32
+    async fn copy_exact<R: AsyncRead + Unpin>(&mut self, name: &str, size: u64, mut reader: R) -> Result<u32> {
+        let mut buffer = vec![0u8; COPY_CHUNK];
+        let mut crc = Crc32::new();
+        let mut remaining = size;
+        while remaining > 0 {
+            let want = remaining.min(COPY_CHUNK as u64) as usize;
+            let read = reader.read(&mut buffer[..want]).await?;
+            if read == 0 {
+                bail!("{} ended {} bytes short of its {} byte size", name, remaining, size);
+            }
+            crc.update(&buffer[..read]);
+            self.write(&buffer[..read]).await?;
+            remaining -= read as u64;
+        }
+        Ok(crc.finish())
+    }
+
+    async fn add_zip_entry<R: AsyncRead + Unpin>(&mut self, name: &str, size: u64, reader: R) -> Result<()> {
+        if name.len() > ZIP_MAX_16 as usize {
+            bail!("Archive entry name too long: {}", name);
+        }
+        let offset = self.position;
+        let zip64 = size >= ZIP_MAX_32;
+        let (time, date) = dos_date_time(self.modified);
+
+        let mut header = Vec::with_capacity(30 + name.len() + 20);
+        header.extend_from_slice(&0x0403_4b50u32.to_le_bytes());
+        header.extend_from_slice(&(if zip64 { ZIP64_VERSION } else { ZIP_VERSION }).to_le_bytes());
+        header.extend_from_slice(&ZIP_FLAGS.to_le_bytes());
+        header.extend_from_slice(&0u16.to_le_bytes()); // Stored
+        header.extend_from_slice(&time.to_le_bytes());
+        header.extend_from_slice(&date.to_le_bytes());
+        header.extend_from_slice(&0u32.to_le_bytes()); // CRC and sizes follow the data
+        let local_size = if zip64 { ZIP_MAX_32 as u32 } else { 0 };
+        header.extend_from_slice(&local_size.to_le_bytes());
+        header.extend_from_slice(&local_size.to_le_bytes());
+        header.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        header.extend_from_slice(&(if zip64 { 20u16 } else { 0 }).to_le_bytes());
+        header.extend_from_slice(name.as_bytes());
+        if zip64 {
+            header.extend_from_slice(&0x0001u16.to_le_bytes());
+            header.extend_from_slice(&16u16.to_le_bytes());
+            header.extend_from_slice(&[0u8; 16]);
+        }
+        self.write(&header).await?;
+
+        let crc = self.copy_exact(name, size, reader).await?;
+
+        let mut descriptor = Vec::with_capacity(24);
+        descriptor.extend_from_slice(&0x0807_4b50u32.to_le_bytes());
+        descriptor.extend_from_slice(&crc.to_le_bytes());
+        if zip64 {
+            descriptor.extend_from_slice(&size.to_le_bytes());
+            descriptor.extend_from_slice(&size.to_le_bytes());
+        } else {
+            descriptor.extend_from_slice(&(size as u32).to_le_bytes());
+            descriptor.extend_from_slice(&(size as u32).to_le_bytes());
+        }
+        self.write(&descriptor).await?;
+
+        self.entries.push(ZipEntry {
+            name: name.to_string(),
+            crc,
+            size,
+            offset,
+        });
+        Ok(())
+    }
+
+    async fn write_zip_central_directory(&mut self) -> Result<()> {
+        let (time, date) = dos_date_time(self.modified);
+        let directory_offset = self.position;
+        let mut needs_zip64 = self.entries.len() as u64 >= ZIP_MAX_16;
+
+        for i in 0..self.entries.len() {
+            let entry = &self.entries[i];
+            // Fields that don't fit in 32 bits move to the ZIP64 extra field, in this order
+            let mut extra = Vec::new();
+            if entry.size >= ZIP_MAX_32 {
+                extra.extend_from_slice(&entry.size.to_le_bytes());
+                extra.extend_from_slice(&entry.size.to_le_bytes());
+            }
+            if entry.offset >= ZIP_MAX_32 {
+                extra.extend_from_slice(&entry.offset.to_le_bytes());
+            }
+            let zip64 = !extra.is_empty();
+            needs_zip64 |= zip64;
+
+            let mut record = Vec::with_capacity(46 + entry.name.len() + 4 + extra.len());
+            record.extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+            record.extend_from_slice(&(ZIP64_VERSION | 3 << 8).to_le_bytes()); // Made by Unix
+            record.extend_from_slice(&(if zip64 { ZIP64_VERSION } else { ZIP_VERSION }).to_le_bytes());
+            record.extend_from_slice(&ZIP_FLAGS.to_le_bytes());
+            record.extend_from_slice(&0u16.to_le_bytes());
+            record.extend_from_slice(&time.to_le_bytes());
+            record.extend_from_slice(&date.to_le_bytes());
+            record.extend_from_slice(&entry.crc.to_le_bytes());
+            let size = entry.size.min(ZIP_MAX_32) as u32;
+            record.extend_from_slice(&size.to_le_bytes());
+            record.extend_from_slice(&size.to_le_bytes());
+            record.extend_from_slice(&(entry.name.len() as u16).to_le_bytes());
+            record.extend_from_slice(&(if zip64 { extra.len() as u16 + 4 } else { 0 }).to_le_bytes());
+            record.extend_from_slice(&0u16.to_le_bytes()); // Comment
+            record.extend_from_slice(&0u16.to_le_bytes()); // Disk
+            record.extend_from_slice(&0u16.to_le_bytes()); // Internal attributes
+            record.extend_from_slice(&(0o100644u32 << 16).to_le_bytes());
+            record.extend_from_slice(&(entry.offset.min(ZIP_MAX_32) as u32).to_le_bytes());
+            record.extend_from_slice(entry.name.as_bytes());
+            if zip64 {
+                record.extend_from_slice(&0x0001u16.to_le_bytes());
+                record.extend_from_slice(&(extra.len() as u16).to_le_bytes());
+                record.extend_from_slice(&extra);
+            }
+            self.write(&record).await?;
+        }
+
+        let directory_size = self.position - directory_offset;
+        let count = self.entries.len() as u64;
+        needs_zip64 |= directory_offset >= ZIP_MAX_32 || directory_size >= ZIP_MAX_32;
+
+        let mut trailer = Vec::with_capacity(98);
+        if needs_zip64 {
+            let record_offset = self.position;
+            trailer.extend_from_slice(&0x0606_4b50u32.to_le_bytes());
+            trailer.extend_from_slice(&44u64.to_le_bytes());
+            trailer.extend_from_slice(&(ZIP64_VERSION | 3 << 8).to_le_bytes());
+            trailer.extend_from_slice(&ZIP64_VERSION.to_le_bytes());
+            trailer.extend_from_slice(&0u32.to_le_bytes());
+            trailer.extend_from_slice(&0u32.to_le_bytes());
+            trailer.extend_from_slice(&count.to_le_bytes());
+            trailer.extend_from_slice(&count.to_le_bytes());
+            trailer.extend_from_slice(&directory_size.to_le_bytes());
+            trailer.extend_from_slice(&directory_offset.to_le_bytes());
+
+            trailer.extend_from_slice(&0x0706_4b50u32.to_le_bytes());
+            trailer.extend_from_slice(&0u32.to_le_bytes());
+            trailer.extend_from_slice(&record_offset.to_le_bytes());
+            trailer.extend_from_slice(&1u32.to_le_bytes());
+        }
+        trailer.extend_from_slice(&0x0605_4b50u32.to_le_bytes());
+        trailer.extend_from_slice(&0u16.to_le_bytes());
+        trailer.extend_from_slice(&0u16.to_le_bytes());
+        trailer.extend_from_slice(&(count.min(ZIP_MAX_16) as u16).to_le_bytes());
+        trailer.extend_from_slice(&(count.min(ZIP_MAX_16) as u16).to_le_bytes());
+        trailer.extend_from_slice(&(directory_size.min(ZIP_MAX_32) as u32).to_le_bytes());
+        trailer.extend_from_slice(&(directory_offset.min(ZIP_MAX_32) as u32).to_le_bytes());
+        trailer.extend_from_slice(&0u16.to_le_bytes());
+        self.write(&trailer).await
+    }
+
+    async fn add_tar_entry<R: AsyncRead + Unpin>(&mut self, name: &str, size: u64, reader: R) -> Result<()> {
+        let header = tar_header(name, size, self.modified.timestamp().max(0) as u64)?;
+        self.write(&header).await?;
+        self.copy_exact(name, size, reader).await?;
+
+        let padding = (TAR_BLOCK - (size % TAR_BLOCK as u64) as usize) % TAR_BLOCK;
+        self.write(&[0u8; TAR_BLOCK][..padding]).await
+    }
+}
+
+fn tar_header(name: &str, size: u64, mtime: u64) -> Result<[u8; TAR_BLOCK]> {
+    let mut header = [0u8; TAR_BLOCK];
+
+    // Names over 100 bytes are split at a `/` into the 155 byte prefix field
+    let (prefix, name) = if name.len() <= 100 {
+        ("", name)
+    } else {
+        name.char_indices()
+            .filter(|&(i, c)| c == '/' && i <= 155 && name.len() - i - 1 <= 100)
+            .map(|(i, _)| (&name[..i], &name[i + 1..]))
+            .next()
+            .ok_or_else(|| anyhow!("Archive entry name too long for tar: {}", name))?
+    };
+    header[..name.len()].copy_from_slice(name.as_bytes());
+    header[100..108].copy_from_slice(b"0000644\0");
+    header[108..116].copy_from_slice(b"0000000\0");
+    header[116..124].copy_from_slice(b"0000000\0");
+    if size < 1 << 33 {
+        header[124..136].copy_from_slice(format!("{:011o}\0", size).as_bytes());
+    } else {
+        // GNU base-256 for sizes past the 8 GiB octal limit
+        header[124] = 0x80;
+        header[128..136].copy_from_slice(&size.to_be_bytes());
+    }
+    header[136..148].copy_from_slice(format!("{:011o}\0", mtime.min((1 << 33) - 1)).as_bytes());
+    header[148..156].copy_from_slice(b"        ");
+    header[156] = b'0';
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+    header[345..345 + prefix.len()].copy_from_slice(prefix.as_bytes());
+
+    let checksum: u32 = header.iter().map(|&b| b as u32).sum();
+    header[148..156].copy_from_slice(format!("{:06o}\0 ", checksum).as_bytes());
+    Ok(header)
+}
+
+/// MS-DOS time and date words; ZIP can't represent anything before 1980
+fn dos_date_time(at: DateTime<Utc>) -> (u16, u16) {
+    if at.year() < 1980 {
+        return (0, 1 << 5 | 1);
+    }
+    let time = (at.hour() << 11 | at.minute() << 5 | (at.second() / 2)) as u16;
+    let date = (((at.year() - 1980) as u32) << 9 | at.month() << 5 | at.day()) as u16;
+    (time, date)
+}
+
+/// CRC-32 as used by ZIP (reflected, polynomial 0xEDB88320)
+pub struct Crc32(u32);
+
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 { crc >> 1 ^ 0xEDB8_8320 } else { crc >> 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+impl Crc32 {
+    pub fn new() -> Self {
+        Self(0xFFFF_FFFF)
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        for &byte in data {
+            self.0 = CRC32_TABLE[((self.0 ^ byte as u32) & 0xFF) as usize] ^ (self.0 >> 8);
+        }
+    }
+
+    pub fn finish(&self) -> u32 {
+        !self.0
+    }
+}
+
+impl Default for Crc32 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crc32() {
+        let mut crc = Crc32::new();
+        crc.update(b"123456789");
+        assert_eq!(crc.finish(), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn test_tar_header_splits_long_names() {
+        let name = format!("{}/{}", "a".repeat(80), "b".repeat(90));
+        let header = tar_header(&name, 10, 0).unwrap();
+        assert_eq!(&header[..90], "b".repeat(90).as_bytes());
+        assert_eq!(&header[345..425], "a".repeat(80).as_bytes());
+        assert!(tar_header(&"c".repeat(120), 10, 0).is_err());
+    }
+}
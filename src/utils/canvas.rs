@@ -0,0 +1,223 @@
+use ab_glyph::{point, Font, FontArc, PxScale, ScaleFont};
+use anyhow::{anyhow, Result};
+use image::{ImageFormat, Rgb, RgbImage};
+use qrcode::{Color, QrCode};
+use std::path::Path;
+
+/// Smallest text size `fit_text` shrinks to before it cuts the text short
+const MIN_FIT_SIZE: f32 = 24.0;
+/// Characters `fit_text` looks at; no line holds more, so longer text is cut right away
+const MAX_FIT_CHARS: usize = 200;
+
+/// The font at `path`, or the built-in Ubuntu Light
+pub fn load_font(path: Option<&Path>) -> Result<FontArc> {
+    match path {
+        Some(path) => {
+            let data = std::fs::read(path).map_err(|e| anyhow!("Failed to read font {}: {}", path.display(), e))?;
+            FontArc::try_from_vec(data).map_err(|_| anyhow!("{} is not a TrueType or OpenType font", path.display()))
+        }
+        None => Ok(FontArc::try_from_slice(epaint_default_fonts::UBUNTU_LIGHT)?),
+    }
+}
+
+/// `#rrggbb` as a pixel colour
+pub fn hex_color(color: &str) -> Option<Rgb<u8>> {
+    let hex = color.trim().strip_prefix('#')?;
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some(Rgb([channel(0)?, channel(2)?, channel(4)?]))
+}
+
+/// An opaque RGB image to draw share images on
+pub struct Canvas {
+    image: RgbImage,
+    font: FontArc,
+}
+
+impl Canvas {
+    pub fn new(width: u32, height: u32, background: Rgb<u8>, font: FontArc) -> Self {
+        Self {
+            image: RgbImage::from_pixel(width, height, background),
+            font,
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.image.width()
+    }
+
+    pub fn height(&self) -> u32 {
+        self.image.height()
+    }
+
+    pub fn fill_rect(&mut self, x: u32, y: u32, width: u32, height: u32, color: Rgb<u8>) {
+        for py in y..(y + height).min(self.height()) {
+            for px in x..(x + width).min(self.width()) {
+                self.image.put_pixel(px, py, color);
+            }
+        }
+    }
+
+    /// Advance width of `text` in pixels
+    pub fn text_width(&self, text: &str, size: f32) -> f32 {
+        let font = self.font.as_scaled(PxScale::from(size));
+        let mut width = 0.0;
+        let mut previous = None;
+        for c in text.chars() {
+            let id = font.glyph_id(c);
+            if let Some(previous) = previous {
+                width += font.kern(previous, id);
+            }
+            width += font.h_advance(id);
+            previous = Some(id);
+        }
+        width
+    }
+
+    /// Height of a line of text, ascent to descent
+    pub fn line_height(&self, size: f32) -> f32 {
+        let font = self.font.as_scaled(PxScale::from(size));
+        font.ascent() - font.descent()
+    }
+
+    /// `text` and the size it fits into `max_width` at: shrunk from `size`, then cut short with `…`
+    pub fn fit_text(&self, text: &str, size: f32, max_width: f32) -> (String, f32) {
+        let mut chars: Vec<char> = text.chars().take(MAX_FIT_CHARS + 1).collect();
+        let too_long = chars.len() > MAX_FIT_CHARS;
+        chars.truncate(MAX_FIT_CHARS);
+        let text: String = chars.iter().collect();
+
+        let width = self.text_width(&text, size);
+        if width <= max_width && !too_long {
+            return (text, size);
+        }
+        // Widths scale linearly with the size; allow for rounding
+        let size = (size * max_width / width).min(size).max(MIN_FIT_SIZE.min(size));
+        if self.text_width(&text, size) <= max_width + 0.5 && !too_long {
+            return (text, size);
+        }
+
+        // The longest start of the text that still fits with the ellipsis
+        let cut = |len: usize| format!("{}…", chars[..len].iter().collect::<String>().trim_end());
+        let (mut fits, mut too_wide) = (0, chars.len() + 1);
+        while too_wide - fits > 1 {
+            let len = (fits + too_wide) / 2;
+            if self.text_width(&cut(len), size) <= max_width {
+                fits = len;
+            } else {
+                too_wide = len;
+            }
+        }
+        (cut(fits), size)
+    }
+
+    /// Draw `text` with its line box starting at `y`; bold is faked by overdrawing, as the
+    /// built-in font only comes in one weight. Returns the width drawn.
+    pub fn draw_text(&mut self, text: &str, x: f32, y: f32, size: f32, color: Rgb<u8>, bold: bool) -> f32 {
+        let offsets = if bold { (size / 28.0).ceil().max(1.0) as u32 } else { 1 };
+        for offset in 0..offsets {
+            self.draw_glyphs(text, x + offset as f32 * 0.75, y, size, color);
+        }
+        self.text_width(text, size)
+    }
+
+    fn draw_glyphs(&mut self, text: &str, x: f32, y: f32, size: f32, color: Rgb<u8>) {
+        let scale = PxScale::from(size);
+        let font = self.font.as_scaled(scale);
+        let baseline = y + font.ascent();
+        let mut caret = x;
+        let mut previous = None;
+        let mut outlines = Vec::new();
+        for c in text.chars() {
+            let id = font.glyph_id(c);
+            if let Some(previous) = previous {
+                caret += font.kern(previous, id);
+            }
+            outlines.extend(self.font.outline_glyph(id.with_scale_and_position(scale, point(caret, baseline))));
+            caret += font.h_advance(id);
+            previous = Some(id);
+        }
+
+        let (width, height) = (self.width() as i64, self.height() as i64);
+        for outline in outlines {
+            let bounds = outline.px_bounds();
+            outline.draw(|gx, gy, coverage| {
+                let px = bounds.min.x as i64 + gx as i64;
+                let py = bounds.min.y as i64 + gy as i64;
+                if px < 0 || py < 0 || px >= width || py >= height {
+                    return;
+                }
+                let pixel = self.image.get_pixel_mut(px as u32, py as u32);
+                let coverage = coverage.clamp(0.0, 1.0);
+                for (channel, target) in pixel.0.iter_mut().zip(color.0) {
+                    *channel = (*channel as f32 * (1.0 - coverage) + target as f32 * coverage).round() as u8;
+                }
+            });
+        }
+    }
+
+    /// A QR code of `data` with its quiet zone, centred in the `size` square at `x`, `y`
+    pub fn draw_qr(&mut self, data: &str, x: u32, y: u32, size: u32) -> Result<()> {
+        let code = QrCode::new(data.as_bytes())?;
+        let modules = code.width() as u32 + 8;
+        let module_size = (size / modules).max(1);
+        let offset = size.saturating_sub(modules * module_size) / 2;
+        let (x, y) = (x + offset, y + offset);
+
+        self.fill_rect(x, y, modules * module_size, modules * module_size, Rgb([255, 255, 255]));
+        for (index, color) in code.to_colors().into_iter().enumerate() {
+            if color == Color::Dark {
+                let column = index as u32 % code.width() as u32 + 4;
+                let row = index as u32 / code.width() as u32 + 4;
+                self.fill_rect(x + column * module_size, y + row * module_size, module_size, module_size, Rgb([0, 0, 0]));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn into_png(self) -> Result<Vec<u8>> {
+        let mut png = std::io::Cursor::new(Vec::new());
+        self.image.write_to(&mut png, ImageFormat::Png)?;
+        Ok(png.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex_color() {
+        assert_eq!(hex_color("#ff2e63"), Some(Rgb([255, 46, 99])));
+        assert_eq!(hex_color("ff2e63"), None);
+        assert_eq!(hex_color("#ff2e6"), None);
+        assert_eq!(hex_color("#ééé"), None);
+    }
+
+    #[test]
+    fn test_fit_text_shrinks_then_cuts() {
+        let canvas = Canvas::new(10, 10, Rgb([0, 0, 0]), load_font(None).unwrap());
+        let (text, size) = canvas.fit_text("Alice", 100.0, 10_000.0);
+        assert_eq!((text.as_str(), size), ("Alice", 100.0));
+
+        let width = canvas.text_width("Alice", 100.0);
+        let (text, size) = canvas.fit_text("Alice", 100.0, width / 2.0);
+        assert_eq!(text, "Alice");
+        assert!(size < 100.0 && canvas.text_width(&text, size) <= width / 2.0 + 0.5);
+
+        let (text, size) = canvas.fit_text(&"Very Long DJ Name ".repeat(10), 100.0, 400.0);
+        assert_eq!(size, MIN_FIT_SIZE);
+        assert!(text.ends_with('…'));
+        assert!(canvas.text_width(&text, size) <= 400.0);
+        // Nothing more fits: one more character would overflow
+        let longer: String = "Very Long DJ Name ".repeat(10).chars().take(text.chars().count()).collect();
+        assert!(canvas.text_width(&format!("{}…", longer.trim_end()), size) > 400.0);
+
+        // Huge input is cut to a line's worth before measuring, even where it would fit
+        let (text, size) = canvas.fit_text(&"i".repeat(1_000_000), 100.0, 1_000_000.0);
+        assert_eq!((text.chars().count(), size), (MAX_FIT_CHARS + 1, 100.0));
+        assert!(text.ends_with('…'));
+    }
+}
@@ -0,0 +1,30 @@
+/// `mm:ss:ff` with 75 frames per second; minutes go past 99 for long recordings
+pub fn cue_timestamp(seconds: f64) -> String {
+    let frames = (seconds.max(0.0) * 75.0).round() as u64;
+    format!("{:02}:{:02}:{:02}", frames / (75 * 60), frames / 75 % 60, frames % 75)
+}
+
+/// Seconds of an `mm:ss:ff` cue sheet index
+pub fn parse_cue_timestamp(text: &str) -> Option<f64> {
+    let parts: Vec<u64> = text.trim().split(':').map(|part| part.parse().ok()).collect::<Option<_>>()?;
+    match parts[..] {
+        [minutes, seconds, frames] if seconds < 60 && frames < 75 => {
+            Some(minutes.checked_mul(60)?.checked_add(seconds)? as f64 + frames as f64 / 75.0)
+        }
+        _ => None,
+    }
+}
+
+/// Cue sheets have no escaping, so quotes inside strings become single quotes and line
+/// breaks, which would start a new command, become spaces
+pub fn cue_text(text: &str) -> String {
+    text.replace('"', "'").replace(['\r', '\n'], " ")
+}
+
+/// A `TRACK` entry of a cue sheet starting `seconds` into the current `FILE`
+pub fn write_cue_track(cue: &mut String, number: usize, title: &str, performer: &str, seconds: f64) {
+    cue.push_str(&format!("  TRACK {:02} AUDIO\n", number));
+    cue.push_str(&format!("    TITLE \"{}\"\n", cue_text(title)));
+    cue.push_str(&format!("    PERFORMER \"{}\"\n", cue_text(performer)));
+    cue.push_str(&format!("    INDEX 01 {}\n", cue_timestamp(seconds)));
+}
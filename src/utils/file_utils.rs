@@ -2,10 +2,17 @@ use std::path::{Path, PathBuf};
 use anyhow::Result;
 use uuid::Uuid;
 
-pub fn generate_session_filename(dj_name: &str, session_id: &str) -> String {
-    let sanitized_name = crate::utils::sanitize_filename(dj_name);
-    let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S");
-    format!("{}_{}_session_{}.mp3", sanitized_name, timestamp, &session_id[..8])
+/// `DJ_Name_YYYYMMDD_HHMMSS_session_<first 8 of id>.<extension>`, timed from the set's start
+pub fn generate_session_filename(
+    dj_name: &str,
+    session_id: &str,
+    started_at: chrono::DateTime<chrono::Utc>,
+    extension: &str,
+) -> String {
+    let sanitized_name = crate::utils::sanitize_filename(dj_name).replace(' ', "_");
+    let timestamp = started_at.format("%Y%m%d_%H%M%S");
+    let short_id: String = session_id.chars().take(8).collect();
+    format!("{}_{}_session_{}.{}", sanitized_name, timestamp, short_id, extension)
 }
 
 pub fn get_recordings_directory() -> PathBuf {
@@ -19,9 +26,9 @@ pub fn ensure_recordings_directory() -> Result<PathBuf> {
     Ok(dir)
 }
 
-pub fn get_session_file_path(session_id: &str, dj_name: &str) -> Result<PathBuf> {
+pub fn get_session_file_path(session_id: &str, dj_name: &str, extension: &str) -> Result<PathBuf> {
     let recordings_dir = ensure_recordings_directory()?;
-    let filename = generate_session_filename(dj_name, session_id);
+    let filename = generate_session_filename(dj_name, session_id, chrono::Utc::now(), extension);
     Ok(recordings_dir.join(filename))
 }
 
@@ -82,6 +89,21 @@ pub fn generate_cloud_upload_path(session_id: &str, filename: &str) -> String {
     format!("sessions/{}/{}/{}", date, session_id, filename)
 }
 
+/// MIME type to serve a file under, by extension
+pub fn content_type_for(filename: &str) -> &'static str {
+    let extension = Path::new(filename).extension().and_then(|e| e.to_str()).unwrap_or("");
+    match extension.to_lowercase().as_str() {
+        "flac" => "audio/flac",
+        "ogg" => "audio/ogg",
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "m4a" | "aac" => "audio/aac",
+        "zip" => "application/zip",
+        "json" => "application/json",
+        _ => "application/octet-stream",
+    }
+}
+
 pub fn is_audio_file(path: &Path) -> bool {
     if let Some(extension) = path.extension() {
         if let Some(ext_str) = extension.to_str() {
@@ -103,10 +125,12 @@ mod tests {
 
     #[test]
     fn test_generate_session_filename() {
-        let filename = generate_session_filename("DJ Test", "session-123-456");
-        assert!(filename.contains("DJ_Test"));
-        assert!(filename.contains("session_123"));
-        assert!(filename.ends_with(".mp3"));
+        let started_at = chrono::TimeZone::with_ymd_and_hms(&chrono::Utc, 2024, 1, 5, 22, 30, 0).unwrap();
+        let filename = generate_session_filename("DJ Test", "3f2a9c1e-77aa-4b1c", started_at, "flac");
+        assert_eq!(filename, "DJ_Test_20240105_223000_session_3f2a9c1e.flac");
+
+        // Short ids don't panic; the extension follows the format
+        assert_eq!(generate_session_filename("Bob", "abc", started_at, "ogg"), "Bob_20240105_223000_session_abc.ogg");
     }
 
     #[test]
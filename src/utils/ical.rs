@@ -0,0 +1,214 @@
+use chrono::{DateTime, Duration, DurationRound, NaiveDateTime, Offset, TimeZone, Utc};
+use chrono_tz::{OffsetComponents, OffsetName, Tz};
+
+/// A `VEVENT` of an iCalendar feed
+#[derive(Debug, Clone, PartialEq)]
+pub struct IcsEvent {
+    pub uid: String, // Stable across feed refreshes, so calendars update the entry in place
+    pub summary: String,
+    pub description: Option<String>,
+    pub location: Option<String>,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub tentative: bool, // Times may still move, e.g. planned slots of the lottery
+}
+
+/// An RFC 5545 calendar. With a time zone, times are written in it along with its
+/// `VTIMEZONE`; without, they are written in UTC.
+pub fn write_calendar(name: &str, timezone: Option<Tz>, events: &[IcsEvent], now: DateTime<Utc>) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//DA Slotify//Timetable//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+        "METHOD:PUBLISH".to_string(),
+        format!("X-WR-CALNAME:{}", ics_text(name)),
+        // Subscribed calendars pick up queue changes within minutes
+        "REFRESH-INTERVAL;VALUE=DURATION:PT5M".to_string(),
+        "X-PUBLISHED-TTL:PT5M".to_string(),
+    ];
+
+    if let Some(tz) = timezone {
+        lines.push(format!("X-WR-TIMEZONE:{}", tz.name()));
+        let first = events.iter().map(|event| event.start).min();
+        let last = events.iter().map(|event| event.end).max();
+        if let (Some(first), Some(last)) = (first, last) {
+            lines.extend(vtimezone(tz, first, last));
+        }
+    }
+
+    for event in events {
+        lines.push("BEGIN:VEVENT".to_string());
+        lines.push(format!("UID:{}", ics_text(&event.uid)));
+        lines.push(format!("DTSTAMP:{}", utc_time(now)));
+        lines.push(ics_time("DTSTART", event.start, timezone));
+        lines.push(ics_time("DTEND", event.end.max(event.start), timezone));
+        lines.push(format!("SUMMARY:{}", ics_text(&event.summary)));
+        if let Some(description) = &event.description {
+            lines.push(format!("DESCRIPTION:{}", ics_text(description)));
+        }
+        if let Some(location) = &event.location {
+            lines.push(format!("LOCATION:{}", ics_text(location)));
+        }
+        lines.push(format!("STATUS:{}", if event.tentative { "TENTATIVE" } else { "CONFIRMED" }));
+        lines.push("TRANSP:OPAQUE".to_string());
+        lines.push("END:VEVENT".to_string());
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    lines.iter().map(|line| fold_line(line)).collect()
+}
+
+/// Escape a TEXT value
+pub fn ics_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace(['\n', '\r'], "\\n")
+}
+
+/// Split a content line into lines of at most 75 octets, ending each with CRLF
+fn fold_line(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + 8);
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            width = 1;
+        }
+        folded.push(c);
+        width += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+fn utc_time(time: DateTime<Utc>) -> String {
+    time.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+fn ics_time(property: &str, time: DateTime<Utc>, timezone: Option<Tz>) -> String {
+    match timezone {
+        Some(tz) => format!("{};TZID={}:{}", property, tz.name(), time.with_timezone(&tz).format("%Y%m%dT%H%M%S")),
+        None => format!("{}:{}", property, utc_time(time)),
+    }
+}
+
+/// `VTIMEZONE` listing the offsets in effect from a year before `from` until `until`
+fn vtimezone(tz: Tz, from: DateTime<Utc>, until: DateTime<Utc>) -> Vec<String> {
+    let offset_at = |time: DateTime<Utc>| tz.offset_from_utc_datetime(&time.naive_utc());
+
+    let start = from - Duration::days(366);
+    let start = start.duration_trunc(Duration::days(1)).unwrap_or(start);
+    let mut lines = vec!["BEGIN:VTIMEZONE".to_string(), format!("TZID:{}", tz.name())];
+    lines.extend(observance(start, offset_at(start), offset_at(start)));
+
+    // Offsets change at most a few times a year; find each change by the day, then the minute
+    let mut day = start;
+    while day < until {
+        let next_day = day + Duration::days(1);
+        let (before, after) = (offset_at(day), offset_at(next_day));
+        if before.fix() != after.fix() || before.abbreviation() != after.abbreviation() {
+            let (mut low, mut high) = (day, next_day);
+            while high - low > Duration::minutes(1) {
+                let middle = low + (high - low) / 2;
+                if offset_at(middle).fix() == before.fix() && offset_at(middle).abbreviation() == before.abbreviation() {
+                    low = middle;
+                } else {
+                    high = middle;
+                }
+            }
+            let transition = high.duration_trunc(Duration::minutes(1)).unwrap_or(high);
+            lines.extend(observance(transition, before, offset_at(transition)));
+        }
+        day = next_day;
+    }
+
+    lines.push("END:VTIMEZONE".to_string());
+    lines
+}
+
+/// `STANDARD` or `DAYLIGHT` block for the offset `to` taking over from `from` at `at`
+fn observance(at: DateTime<Utc>, from: <Tz as TimeZone>::Offset, to: <Tz as TimeZone>::Offset) -> Vec<String> {
+    let kind = if to.dst_offset().is_zero() { "STANDARD" } else { "DAYLIGHT" };
+    // Onsets are local times in the offset being replaced
+    let onset: NaiveDateTime = at.naive_utc() + Duration::seconds(from.fix().local_minus_utc() as i64);
+    let mut lines = vec![
+        format!("BEGIN:{}", kind),
+        format!("DTSTART:{}", onset.format("%Y%m%dT%H%M%S")),
+        format!("TZOFFSETFROM:{}", utc_offset(from.fix().local_minus_utc())),
+        format!("TZOFFSETTO:{}", utc_offset(to.fix().local_minus_utc())),
+    ];
+    if let Some(abbreviation) = to.abbreviation() {
+        lines.push(format!("TZNAME:{}", ics_text(abbreviation)));
+    }
+    lines.push(format!("END:{}", kind));
+    lines
+}
+
+/// `+0100`, or `+053000` for offsets with seconds
+fn utc_offset(seconds: i32) -> String {
+    let sign = if seconds < 0 { '-' } else { '+' };
+    let seconds = seconds.abs();
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if seconds == 0 {
+        format!("{}{:02}{:02}", sign, hours, minutes)
+    } else {
+        format!("{}{:02}{:02}{:02}", sign, hours, minutes, seconds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(start: DateTime<Utc>) -> IcsEvent {
+        IcsEvent {
+            uid: "event-dj@slotify".to_string(),
+            summary: "DJ Élodie, Headliner; all night".to_string(),
+            description: Some("Slot 3\nPlanned".to_string()),
+            location: None,
+            start,
+            end: start + Duration::hours(1),
+            tentative: true,
+        }
+    }
+
+    #[test]
+    fn test_text_is_escaped_and_lines_folded() {
+        assert_eq!(ics_text("a,b;c\\d\ne"), "a\\,b\\;c\\\\d\\ne");
+
+        let line = format!("SUMMARY:{}", "é".repeat(60));
+        let folded = fold_line(&line);
+        assert!(folded.split("\r\n").all(|part| part.len() <= 75));
+        assert_eq!(folded.replace("\r\n ", "").trim_end(), line);
+    }
+
+    #[test]
+    fn test_utc_calendar() {
+        let start = Utc.with_ymd_and_hms(2024, 1, 15, 22, 0, 0).unwrap();
+        let calendar = write_calendar("Friday", None, &[event(start)], start);
+        assert!(calendar.contains("DTSTART:20240115T220000Z\r\n"));
+        assert!(calendar.contains("DTEND:20240115T230000Z\r\n"));
+        assert!(calendar.contains("SUMMARY:DJ Élodie\\, Headliner\\; all night\r\n"));
+        assert!(calendar.contains("STATUS:TENTATIVE\r\n"));
+        assert!(!calendar.contains("VTIMEZONE"));
+    }
+
+    #[test]
+    fn test_time_zone_transitions() {
+        // The night summer time ends in Berlin
+        let start = Utc.with_ymd_and_hms(2024, 10, 27, 0, 30, 0).unwrap();
+        let calendar = write_calendar("Friday", Some(chrono_tz::Europe::Berlin), &[event(start)], start);
+        assert!(calendar.contains("DTSTART;TZID=Europe/Berlin:20241027T023000\r\n"));
+        assert!(calendar.contains("DTEND;TZID=Europe/Berlin:20241027T023000\r\n"));
+        assert!(calendar.contains(
+            "BEGIN:STANDARD\r\nDTSTART:20241027T030000\r\nTZOFFSETFROM:+0200\r\nTZOFFSETTO:+0100\r\nTZNAME:CET\r\nEND:STANDARD\r\n"
+        ));
+        assert!(calendar.contains(
+            "BEGIN:DAYLIGHT\r\nDTSTART:20240331T020000\r\nTZOFFSETFROM:+0100\r\nTZOFFSETTO:+0200\r\nTZNAME:CEST\r\nEND:DAYLIGHT\r\n"
+        ));
+        assert_eq!(utc_offset(-(5 * 3600 + 30 * 60)), "-0530");
+    }
+}
@@ -1,7 +1,19 @@
+pub mod archive;
+pub mod canvas;
+pub mod cue;
 pub mod file_utils;
+pub mod ical;
+pub mod markup;
+pub mod osc;
 pub mod time_utils;
 pub mod validation;
 
+pub use archive::*;
+pub use canvas::*;
+pub use cue::*;
 pub use file_utils::*;
+pub use ical::*;
+pub use markup::*;
+pub use osc::*;
 pub use time_utils::*;
 pub use validation::*;
\ No newline at end of file
@@ -0,0 +1,211 @@
+use anyhow::{anyhow, bail, Result};
+
+/// Bundles start with this padded string instead of an address
+const BUNDLE_TAG: &[u8] = b"#bundle\0";
+
+/// An argument of an OSC 1.0 message
+#[derive(Debug, Clone, PartialEq)]
+pub enum OscArg {
+    Int(i32),
+    Float(f32),
+    String(String),
+    Blob(Vec<u8>),
+    Long(i64),
+    Double(f64),
+    Bool(bool),
+    Nil,
+}
+
+impl OscArg {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OscMessage {
+    pub address: String,
+    pub args: Vec<OscArg>,
+}
+
+impl OscMessage {
+    pub fn new(address: &str, args: Vec<OscArg>) -> Self {
+        Self {
+            address: address.to_string(),
+            args,
+        }
+    }
+
+    /// The messages in a UDP datagram; bundles are flattened and their time tags ignored
+    pub fn parse_packet(data: &[u8]) -> Result<Vec<OscMessage>> {
+        let mut messages = Vec::new();
+        parse_element(data, &mut messages, 0)?;
+        Ok(messages)
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+        push_string(&mut data, &self.address);
+
+        let mut tags = String::from(",");
+        for arg in &self.args {
+            tags.push(match arg {
+                OscArg::Int(_) => 'i',
+                OscArg::Float(_) => 'f',
+                OscArg::String(_) => 's',
+                OscArg::Blob(_) => 'b',
+                OscArg::Long(_) => 'h',
+                OscArg::Double(_) => 'd',
+                OscArg::Bool(true) => 'T',
+                OscArg::Bool(false) => 'F',
+                OscArg::Nil => 'N',
+            });
+        }
+        push_string(&mut data, &tags);
+
+        for arg in &self.args {
+            match arg {
+                OscArg::Int(value) => data.extend_from_slice(&value.to_be_bytes()),
+                OscArg::Float(value) => data.extend_from_slice(&value.to_be_bytes()),
+                OscArg::String(value) => push_string(&mut data, value),
+                OscArg::Blob(value) => {
+                    data.extend_from_slice(&(value.len() as i32).to_be_bytes());
+                    data.extend_from_slice(value);
+                    data.resize(padded(data.len()), 0);
+                }
+                OscArg::Long(value) => data.extend_from_slice(&value.to_be_bytes()),
+                OscArg::Double(value) => data.extend_from_slice(&value.to_be_bytes()),
+                OscArg::Bool(_) | OscArg::Nil => {}
+            }
+        }
+        data
+    }
+}
+
+/// Bundles can nest; a limit keeps a hostile packet from recursing deeply
+fn parse_element(data: &[u8], messages: &mut Vec<OscMessage>, depth: usize) -> Result<()> {
+    if depth > 8 {
+        bail!("OSC bundles nested too deeply");
+    }
+    if let Some(rest) = data.strip_prefix(BUNDLE_TAG) {
+        let mut rest = rest.get(8..).ok_or_else(|| anyhow!("OSC bundle without time tag"))?;
+        while !rest.is_empty() {
+            let size = read_i32(&mut rest)?;
+            let size = usize::try_from(size).map_err(|_| anyhow!("negative OSC bundle element size"))?;
+            if size > rest.len() {
+                bail!("OSC bundle element runs past the packet");
+            }
+            let (element, after) = rest.split_at(size);
+            parse_element(element, messages, depth + 1)?;
+            rest = after;
+        }
+        return Ok(());
+    }
+
+    let mut rest = data;
+    let address = read_string(&mut rest)?;
+    if !address.starts_with('/') {
+        bail!("OSC address '{}' does not start with '/'", address);
+    }
+    // Type tags are optional in old senders; without them there are no arguments
+    let tags = if rest.first() == Some(&b',') { read_string(&mut rest)? } else { String::new() };
+
+    let mut args = Vec::new();
+    for tag in tags.chars().skip(1) {
+        let arg = match tag {
+            'i' => OscArg::Int(read_i32(&mut rest)?),
+            'f' => OscArg::Float(f32::from_bits(read_i32(&mut rest)? as u32)),
+            's' | 'S' => OscArg::String(read_string(&mut rest)?),
+            'b' => {
+                let size = usize::try_from(read_i32(&mut rest)?).map_err(|_| anyhow!("negative OSC blob size"))?;
+                let blob = take(&mut rest, padded(size))?;
+                OscArg::Blob(blob[..size].to_vec())
+            }
+            'h' => OscArg::Long(i64::from_be_bytes(take(&mut rest, 8)?.try_into()?)),
+            'd' => OscArg::Double(f64::from_be_bytes(take(&mut rest, 8)?.try_into()?)),
+            't' => OscArg::Long(i64::from_be_bytes(take(&mut rest, 8)?.try_into()?)), // Time tag
+            'c' | 'r' | 'm' => OscArg::Int(read_i32(&mut rest)?),
+            'T' => OscArg::Bool(true),
+            'F' => OscArg::Bool(false),
+            'N' | 'I' => OscArg::Nil,
+            other => bail!("unsupported OSC type tag '{}'", other),
+        };
+        args.push(arg);
+    }
+
+    messages.push(OscMessage { address, args });
+    Ok(())
+}
+
+/// Lengths in OSC are padded to a multiple of four bytes
+fn padded(len: usize) -> usize {
+    len.div_ceil(4) * 4
+}
+
+fn take<'a>(data: &mut &'a [u8], len: usize) -> Result<&'a [u8]> {
+    if data.len() < len {
+        bail!("OSC packet is truncated");
+    }
+    let (taken, rest) = data.split_at(len);
+    *data = rest;
+    Ok(taken)
+}
+
+fn read_i32(data: &mut &[u8]) -> Result<i32> {
+    Ok(i32::from_be_bytes(take(data, 4)?.try_into()?))
+}
+
+/// NUL terminated and padded with NULs to four bytes
+fn read_string(data: &mut &[u8]) -> Result<String> {
+    let end = data.iter().position(|&b| b == 0).ok_or_else(|| anyhow!("unterminated OSC string"))?;
+    let bytes = take(data, padded(end + 1))?;
+    String::from_utf8(bytes[..end].to_vec()).map_err(|_| anyhow!("OSC string is not valid UTF-8"))
+}
+
+fn push_string(data: &mut Vec<u8>, value: &str) {
+    data.extend_from_slice(value.as_bytes());
+    data.push(0);
+    data.resize(padded(data.len()), 0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_round_trip() {
+        let message = OscMessage::new("/slotify/track", vec![
+            OscArg::String("Robert Hood".to_string()),
+            OscArg::String("Minus".to_string()),
+            OscArg::Int(2),
+            OscArg::Float(0.5),
+            OscArg::Blob(vec![1, 2, 3]),
+            OscArg::Bool(true),
+        ]);
+        let data = message.encode();
+        assert_eq!(data.len() % 4, 0);
+        assert_eq!(&data[..16], b"/slotify/track\0\0");
+        assert_eq!(OscMessage::parse_packet(&data).unwrap(), vec![message]);
+    }
+
+    #[test]
+    fn test_bundles_are_flattened() {
+        let first = OscMessage::new("/a", vec![OscArg::Int(1)]).encode();
+        let second = OscMessage::new("/b", vec![]).encode();
+        let mut bundle = BUNDLE_TAG.to_vec();
+        bundle.extend_from_slice(&1u64.to_be_bytes());
+        for element in [&first, &second] {
+            bundle.extend_from_slice(&(element.len() as i32).to_be_bytes());
+            bundle.extend_from_slice(element);
+        }
+        let messages = OscMessage::parse_packet(&bundle).unwrap();
+        assert_eq!(messages.iter().map(|m| m.address.as_str()).collect::<Vec<_>>(), vec!["/a", "/b"]);
+
+        bundle.truncate(bundle.len() - 2);
+        assert!(OscMessage::parse_packet(&bundle).is_err());
+        assert!(OscMessage::parse_packet(b"no address\0\0").is_err());
+    }
+}
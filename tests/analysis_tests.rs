@@ -0,0 +1,268 @@
+use session_recorder_addon::{
+    audio::{AudioSpec, FlacEncoder, LoudnessReport, SilentSpan},
+    models::{
+        AppState,
+        analysis::{AnalysisConfig, AnalysisError, AnalysisFlag},
+        dj::CreateDjRequest,
+    },
+    services::{AnalysisService, DjService, DownloadService, SessionService},
+};
+use chrono::{DateTime, TimeZone, Utc};
+use sqlx::{Row, SqlitePool};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use std::sync::Arc;
+
+mod common;
+
+#[cfg(test)]
+mod analysis_tests {
+    use super::*;
+
+    const SPEC: AudioSpec = AudioSpec {
+        sample_rate: 8_000,
+        channels: 1,
+        bits_per_sample: 16,
+    };
+
+    async fn setup_test_db(dir: &Path) -> Arc<AppState> {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+
+        let config = session_recorder_addon::models::AppConfig {
+            storage_config: common::local_storage(dir),
+            session_recorder_config: common::local_recorder(dir),
+            analysis_config: AnalysisConfig {
+                min_silence_seconds: 5.0,
+                max_silent_seconds: 8.0,
+                ..AnalysisConfig::default()
+            },
+            ..common::test_config()
+        };
+
+        Arc::new(AppState { db: pool, config })
+    }
+
+    fn at(minute: u32, second: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 5, 22, minute, second).unwrap()
+    }
+
+    /// A 997 Hz tone at `amplitude` of full scale; above 1.0 it is clipped
+    fn tone(amplitude: f64, seconds: u32) -> Vec<i32> {
+        (0..seconds * SPEC.sample_rate)
+            .map(|i| {
+                let value = amplitude * 32767.0 * (2.0 * std::f64::consts::PI * 997.0 * i as f64 / SPEC.sample_rate as f64).sin();
+                value.round().clamp(-32768.0, 32767.0) as i32
+            })
+            .collect()
+    }
+
+    fn write_flac(path: &Path, samples: &[i32]) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let mut encoder = FlacEncoder::new(BufWriter::new(File::create(path).unwrap()), SPEC).unwrap();
+        encoder.write_interleaved(samples).unwrap();
+        encoder.finish().unwrap();
+    }
+
+    async fn add_session(
+        app_state: &Arc<AppState>,
+        dj_name: &str,
+        started_at: DateTime<Utc>,
+        ended_at: DateTime<Utc>,
+        file_path: Option<&Path>,
+        recorder_session_id: Option<&str>,
+    ) -> String {
+        let dj = DjService::new(app_state.clone()).register_dj(CreateDjRequest {
+            name: dj_name.to_string(),
+            email: None,
+        }).await.unwrap();
+        let session_id = uuid::Uuid::new_v4().to_string();
+        sqlx::query(
+            "INSERT INTO sessions (id, dj_id, started_at, ended_at, upload_status, session_type, file_path, recorder_id, recorder_session_id, recorder_flac_url) \
+             VALUES (?, ?, ?, ?, 'processing', 'solo', ?, ?, ?, ?)"
+        )
+        .bind(&session_id)
+        .bind(&dj.id)
+        .bind(started_at)
+        .bind(ended_at)
+        .bind(file_path.map(|path| path.to_string_lossy().to_string()))
+        .bind(recorder_session_id.map(|_| "main"))
+        .bind(recorder_session_id)
+        .bind(recorder_session_id.map(|id| format!("file:///recorder/main/sessions/{}/data.flac", id)))
+        .execute(&app_state.db)
+        .await
+        .unwrap();
+        session_id
+    }
+
+    #[test]
+    fn test_flags_follow_thresholds() {
+        let config = AnalysisConfig::default();
+        let report = |lufs: Option<f64>, peak: Option<f64>, clipping_ratio: f64, silence: Vec<SilentSpan>| LoudnessReport {
+            duration_seconds: 3600.0,
+            integrated_lufs: lufs,
+            true_peak_dbtp: peak,
+            clipped_samples: 0,
+            clipping_ratio,
+            silence,
+        };
+
+        assert!(config.flags(&report(Some(-14.0), Some(-1.0), 0.0, vec![])).is_empty());
+        assert_eq!(
+            config.flags(&report(None, None, 0.0, vec![SilentSpan { start_seconds: 0.0, end_seconds: 3600.0 }])),
+            vec![AnalysisFlag::Silent, AnalysisFlag::LongSilence]
+        );
+        assert_eq!(config.flags(&report(Some(-45.0), Some(-30.0), 0.0, vec![])), vec![AnalysisFlag::TooQuiet]);
+        assert_eq!(
+            config.flags(&report(Some(-6.0), Some(3.1), 0.02, vec![])),
+            vec![AnalysisFlag::Clipping, AnalysisFlag::TruePeak]
+        );
+        // Breaks shorter than the allowance pass
+        let breaks = vec![
+            SilentSpan { start_seconds: 600.0, end_seconds: 660.0 },
+            SilentSpan { start_seconds: 1200.0, end_seconds: 1250.0 },
+        ];
+        assert!(config.flags(&report(Some(-14.0), Some(-1.0), 0.0, breaks)).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_analysis_cuts_the_slot_out_of_the_recording() {
+        let dir = tempfile::tempdir().unwrap();
+        let app_state = setup_test_db(dir.path()).await;
+
+        // Alice plays a clean tone for 20 s, Bob clips for 20 s
+        let mut recording = tone(0.25, 20);
+        recording.extend(tone(2.0, 20));
+        write_flac(&dir.path().join("recorder/main/sessions/night/data.flac"), &recording);
+        sqlx::query(
+            "INSERT INTO recorder_sessions (recorder_id, id, name, start_time, is_closed, keep, synced_at) \
+             VALUES ('main', 'night', 'night', ?, TRUE, FALSE, ?)"
+        )
+        .bind(at(0, 0))
+        .bind(Utc::now())
+        .execute(&app_state.db)
+        .await
+        .unwrap();
+
+        let alice = add_session(&app_state, "Alice", at(0, 0), at(0, 20), None, Some("night")).await;
+        let bob = add_session(&app_state, "Bob", at(0, 20), at(0, 40), None, Some("night")).await;
+        let service = AnalysisService::new(app_state.clone());
+
+        let clean = service.analyze_session(&alice).await.unwrap();
+        assert_eq!(clean.dj_name, "Alice");
+        assert!((clean.duration_seconds - 20.0).abs() < 0.01);
+        // -12 dBFS in one channel is 3 dB quieter than in both
+        let lufs = clean.integrated_lufs.unwrap();
+        assert!((lufs + 15.05).abs() < 0.3, "{}", lufs);
+        assert_eq!(clean.clipping_ratio, 0.0);
+        assert!(clean.flags.is_empty());
+        assert!(!clean.flagged);
+
+        let clipped = service.analyze_session(&bob).await.unwrap();
+        assert!((clipped.duration_seconds - 20.0).abs() < 0.01);
+        assert!(clipped.clipping_ratio > 0.3, "{}", clipped.clipping_ratio);
+        assert!(clipped.flags.contains(&AnalysisFlag::Clipping));
+        assert!(clipped.flagged);
+
+        let flagged = service.list_analyses(true).await.unwrap();
+        assert_eq!(flagged.len(), 1);
+        assert_eq!(flagged[0].session_id, bob);
+        assert_eq!(service.list_analyses(false).await.unwrap().len(), 2);
+
+        // Running it again replaces the stored result
+        service.analyze_session(&bob).await.unwrap();
+        let stored: i64 = sqlx::query("SELECT COUNT(*) AS count FROM recording_analyses")
+            .fetch_one(&app_state.db)
+            .await
+            .unwrap()
+            .get("count");
+        assert_eq!(stored, 2);
+
+        // A recording already fetched for delivery is not downloaded again
+        let fetched = dir.path().join("fetched/data.flac");
+        std::fs::create_dir_all(fetched.parent().unwrap()).unwrap();
+        std::fs::rename(dir.path().join("recorder/main/sessions/night/data.flac"), &fetched).unwrap();
+        assert!(service.analyze_session(&alice).await.is_err());
+        assert!(service.check_delivery(&bob, Some(&fetched)).await);
+        assert!(service.get_analysis(&bob).await.unwrap().unwrap().delivery_held);
+    }
+
+    #[tokio::test]
+    async fn test_silent_set_is_held_until_released() {
+        let dir = tempfile::tempdir().unwrap();
+        let app_state = setup_test_db(dir.path()).await;
+
+        // A muted mixer: a local per-DJ file that is silent apart from a click
+        let mut samples = vec![0; 12 * SPEC.sample_rate as usize];
+        samples[SPEC.sample_rate as usize] = 100;
+        let file = dir.path().join("segments/carol.flac");
+        write_flac(&file, &samples);
+        let carol = add_session(&app_state, "Carol", at(1, 0), at(1, 12), Some(&file), None).await;
+        let service = AnalysisService::new(app_state.clone());
+
+        assert!(service.check_delivery(&carol, None).await);
+        let analysis = service.get_analysis(&carol).await.unwrap().unwrap();
+        assert_eq!(analysis.flags, vec![AnalysisFlag::Silent, AnalysisFlag::LongSilence]);
+        assert_eq!(analysis.integrated_lufs, None);
+        assert!(analysis.silence[0].end_seconds - analysis.silence[0].start_seconds >= 10.0);
+        assert!(analysis.delivery_held);
+        let download_service = DownloadService::new(app_state.clone());
+        assert!(download_service.get_session_tokens(&carol).await.unwrap().is_empty());
+
+        // Carol left no address, so there is nobody to email; her link is issued all the same
+        assert!(!service.release_delivery(&carol).await.unwrap());
+        assert_eq!(download_service.get_session_tokens(&carol).await.unwrap().len(), 1);
+        assert!(!service.get_analysis(&carol).await.unwrap().unwrap().delivery_held);
+        let again = service.release_delivery(&carol).await.unwrap_err();
+        assert!(matches!(again.downcast_ref::<AnalysisError>(), Some(AnalysisError::NotHeld)));
+    }
+
+    #[tokio::test]
+    async fn test_flagged_local_file_is_published_without_a_link() {
+        let dir = tempfile::tempdir().unwrap();
+        let app_state = setup_test_db(dir.path()).await;
+
+        // A segment cut from the recorder's file, silent because the mixer was muted
+        let file = dir.path().join("segments/erin.flac");
+        write_flac(&file, &vec![0; 12 * SPEC.sample_rate as usize]);
+        let erin = add_session(&app_state, "Erin", at(3, 0), at(3, 12), Some(&file), None).await;
+        let session_service = SessionService::new(app_state.clone());
+
+        assert_eq!(session_service.publish_session_file(&erin).await.unwrap(), None);
+        let row = sqlx::query("SELECT storage_key, download_link FROM sessions WHERE id = ?")
+            .bind(&erin)
+            .fetch_one(&app_state.db)
+            .await
+            .unwrap();
+        let key: String = row.get("storage_key");
+        assert!(dir.path().join("storage").join(&key).is_file());
+        assert_eq!(row.get::<Option<String>, _>("download_link"), None);
+        assert!(AnalysisService::new(app_state.clone()).get_analysis(&erin).await.unwrap().unwrap().delivery_held);
+
+        // Released, it gets a link to the stored copy
+        AnalysisService::new(app_state.clone()).release_delivery(&erin).await.unwrap();
+        let link = session_service.get_download_link(&erin).await.unwrap().unwrap();
+        assert!(link.contains("/dl/"));
+    }
+
+    #[tokio::test]
+    async fn test_analysis_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let app_state = setup_test_db(dir.path()).await;
+        let service = AnalysisService::new(app_state.clone());
+
+        let missing = service.analyze_session("missing").await.unwrap_err();
+        assert!(matches!(missing.downcast_ref::<AnalysisError>(), Some(AnalysisError::SessionNotFound)));
+        let missing = service.release_delivery("missing").await.unwrap_err();
+        assert!(matches!(missing.downcast_ref::<AnalysisError>(), Some(AnalysisError::SessionNotFound)));
+
+        let unrecorded = add_session(&app_state, "Dave", at(2, 0), at(2, 30), None, None).await;
+        let no_recording = service.analyze_session(&unrecorded).await.unwrap_err();
+        assert!(matches!(no_recording.downcast_ref::<AnalysisError>(), Some(AnalysisError::NoRecording)));
+        assert!(service.get_analysis(&unrecorded).await.unwrap().is_none());
+
+        // A failed analysis never holds a delivery back
+        assert!(!service.check_delivery(&unrecorded, None).await);
+    }
+}
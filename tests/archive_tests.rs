@@ -0,0 +1,361 @@
+use session_recorder_addon::{
+    models::{
+        AppState,
+        archive::{ArchiveError, ArchiveFormat, ArchiveTimetableEntry},
+        dj::CreateDjRequest,
+        event_session::StartEventRequest,
+        tracklist::{SubmitTracklistRequest, TracklistFormat},
+    },
+    services::{ArchiveService, DjService, EventService, TracklistService},
+    utils::Crc32,
+};
+use chrono::{DateTime, TimeZone, Utc};
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+mod common;
+
+#[cfg(test)]
+mod archive_tests {
+    use super::*;
+
+    async fn setup_test_db(dir: &Path) -> Arc<AppState> {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+
+        let config = session_recorder_addon::models::AppConfig {
+            storage_config: common::local_storage(dir),
+            session_recorder_config: common::local_recorder(dir),
+            ..common::test_config()
+        };
+
+        Arc::new(AppState { db: pool, config })
+    }
+
+    fn at(day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, day, hour, minute, 0).unwrap()
+    }
+
+    async fn add_session(
+        app_state: &Arc<AppState>,
+        dj_name: &str,
+        started_at: DateTime<Utc>,
+        ended_at: DateTime<Utc>,
+        storage_key: Option<&str>,
+        recorder_session_id: Option<&str>,
+    ) -> String {
+        let dj = DjService::new(app_state.clone()).register_dj(CreateDjRequest {
+            name: dj_name.to_string(),
+            email: None,
+        }).await.unwrap();
+        let session_id = uuid::Uuid::new_v4().to_string();
+        sqlx::query(
+            "INSERT INTO sessions (id, dj_id, started_at, ended_at, duration_minutes, upload_status, session_type, storage_key, recorder_id, recorder_session_id, recorder_flac_url) \
+             VALUES (?, ?, ?, ?, 60, 'uploaded', 'solo', ?, ?, ?, ?)"
+        )
+        .bind(&session_id)
+        .bind(&dj.id)
+        .bind(started_at)
+        .bind(ended_at)
+        .bind(storage_key)
+        .bind(recorder_session_id.map(|_| "main"))
+        .bind(recorder_session_id)
+        .bind(recorder_session_id.map(|id| format!("file:///recorder/main/sessions/{}/data.flac", id)))
+        .execute(&app_state.db)
+        .await
+        .unwrap();
+        session_id
+    }
+
+    /// Friday Night: Alice published to storage, Bob and Carol on one recorder session that
+    /// started at 22:55, Dave not recorded, and a set from the next week that isn't part of it
+    async fn seed_event(dir: &Path, app_state: &Arc<AppState>) -> (String, Vec<u8>, Vec<u8>) {
+        let event = EventService::new(app_state.clone()).start_event(StartEventRequest {
+            slot_duration_minutes: Some(60),
+            late_arrival_cutoff_hours: None,
+            started_at: Some(at(5, 21, 0)),
+            late_penalty_curve: None,
+            name: Some("Friday Night".to_string()),
+        }).await.unwrap();
+        sqlx::query("UPDATE event_sessions SET ended_at = ?, is_active = FALSE WHERE id = ?")
+            .bind(at(6, 4, 0))
+            .bind(&event.id)
+            .execute(&app_state.db)
+            .await
+            .unwrap();
+
+        let alice_audio: Vec<u8> = (0..70_000u32).map(|i| (i * 7 % 251) as u8).collect();
+        std::fs::create_dir_all(dir.join("storage/published")).unwrap();
+        std::fs::write(dir.join("storage/published/alice.flac"), &alice_audio).unwrap();
+
+        let night_audio: Vec<u8> = (0..1_000u32).map(|i| (i % 13) as u8).collect();
+        std::fs::create_dir_all(dir.join("recorder/main/sessions/night")).unwrap();
+        std::fs::write(dir.join("recorder/main/sessions/night/data.flac"), &night_audio).unwrap();
+        sqlx::query(
+            "INSERT INTO recorder_sessions (recorder_id, id, name, start_time, is_closed, keep, synced_at) \
+             VALUES ('main', 'night', 'night', ?, TRUE, FALSE, ?)"
+        )
+        .bind(at(5, 22, 55))
+        .bind(Utc::now())
+        .execute(&app_state.db)
+        .await
+        .unwrap();
+
+        add_session(app_state, "Alice", at(5, 22, 0), at(5, 23, 0), Some("published/alice.flac"), None).await;
+        add_session(app_state, "Bob", at(5, 23, 0), at(6, 0, 0), None, Some("night")).await;
+        add_session(app_state, "Carol \"CC\"", at(6, 0, 0), at(6, 1, 0), None, Some("night")).await;
+        add_session(app_state, "Dave", at(6, 1, 0), at(6, 2, 0), None, None).await;
+        add_session(app_state, "Next Week", at(12, 22, 0), at(12, 23, 0), None, None).await;
+
+        (event.id, alice_audio, night_audio)
+    }
+
+    fn u16_at(data: &[u8], at: usize) -> usize {
+        u16::from_le_bytes(data[at..at + 2].try_into().unwrap()) as usize
+    }
+
+    fn u32_at(data: &[u8], at: usize) -> u32 {
+        u32::from_le_bytes(data[at..at + 4].try_into().unwrap())
+    }
+
+    fn crc32(data: &[u8]) -> u32 {
+        let mut crc = Crc32::new();
+        crc.update(data);
+        crc.finish()
+    }
+
+    /// Entries of a ZIP read through its central directory; checks local headers,
+    /// data descriptors and CRCs along the way
+    fn unzip(data: &[u8]) -> Vec<(String, Vec<u8>)> {
+        let end = data.len() - 22;
+        assert_eq!(u32_at(data, end), 0x0605_4b50);
+        let count = u16_at(data, end + 10);
+        let mut at = u32_at(data, end + 16) as usize;
+
+        let mut entries = Vec::new();
+        for _ in 0..count {
+            assert_eq!(u32_at(data, at), 0x0201_4b50);
+            let crc = u32_at(data, at + 16);
+            let size = u32_at(data, at + 24) as usize;
+            let name_len = u16_at(data, at + 28);
+            let extra_len = u16_at(data, at + 30);
+            let offset = u32_at(data, at + 42) as usize;
+            let name = String::from_utf8(data[at + 46..at + 46 + name_len].to_vec()).unwrap();
+            at += 46 + name_len + extra_len;
+
+            assert_eq!(u32_at(data, offset), 0x0403_4b50);
+            assert_eq!(u16_at(data, offset + 8), 0); // Stored
+            assert_eq!(&data[offset + 30..offset + 30 + name_len], name.as_bytes());
+            let start = offset + 30 + name_len + u16_at(data, offset + 28);
+            let content = data[start..start + size].to_vec();
+            assert_eq!(crc32(&content), crc, "CRC of {}", name);
+
+            let descriptor = start + size;
+            assert_eq!(u32_at(data, descriptor), 0x0807_4b50);
+            assert_eq!(u32_at(data, descriptor + 4), crc);
+            assert_eq!(u32_at(data, descriptor + 8) as usize, size);
+            entries.push((name, content));
+        }
+        assert_eq!(at, end);
+        entries
+    }
+
+    /// Entries of a ustar archive; checks header checksums and block padding
+    fn untar(data: &[u8]) -> Vec<(String, Vec<u8>)> {
+        assert_eq!(data.len() % 512, 0);
+        let mut entries = Vec::new();
+        let mut at = 0;
+        while data[at..at + 512].iter().any(|&b| b != 0) {
+            let header = &data[at..at + 512];
+            assert_eq!(&header[257..263], b"ustar\0");
+            let field = |range: std::ops::Range<usize>| {
+                String::from_utf8(header[range].iter().take_while(|&&b| b != 0).copied().collect()).unwrap()
+            };
+            let checksum = u32::from_str_radix(field(148..154).trim(), 8).unwrap();
+            let computed: u32 = header.iter().enumerate().map(|(i, &b)| if (148..156).contains(&i) { 32 } else { b as u32 }).sum();
+            assert_eq!(checksum, computed);
+
+            let prefix = field(345..500);
+            let name = if prefix.is_empty() { field(0..100) } else { format!("{}/{}", prefix, field(0..100)) };
+            let size = usize::from_str_radix(&field(124..135), 8).unwrap();
+            at += 512;
+            entries.push((name, data[at..at + size].to_vec()));
+            at += size.div_ceil(512) * 512;
+        }
+        assert!(data[at..].iter().all(|&b| b == 0));
+        assert_eq!(data.len() - at, 1024);
+        entries
+    }
+
+    #[tokio::test]
+    async fn test_event_archive_zip() {
+        let dir = tempfile::tempdir().unwrap();
+        let app_state = setup_test_db(dir.path()).await;
+        let (event_id, alice_audio, night_audio) = seed_event(dir.path(), &app_state).await;
+
+        let archive = ArchiveService::new(app_state.clone()).prepare(&event_id).await.unwrap();
+        assert_eq!(archive.name, "Friday_Night_2024-01-05");
+        assert_eq!(archive.filename(ArchiveFormat::Zip), "Friday_Night_2024-01-05.zip");
+
+        let zip = archive.write(ArchiveFormat::Zip, Vec::new()).await.unwrap();
+        let entries = unzip(&zip);
+        let names: Vec<&str> = entries.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec![
+            "Friday_Night_2024-01-05/recordings/alice.flac",
+            "Friday_Night_2024-01-05/recordings/main_night.flac",
+            "Friday_Night_2024-01-05/README.txt",
+            "Friday_Night_2024-01-05/timetable.json",
+            "Friday_Night_2024-01-05/timetable.csv",
+            "Friday_Night_2024-01-05/Friday_Night_2024-01-05.cue",
+        ]);
+        let files: HashMap<&str, &[u8]> = entries.iter().map(|(name, data)| (name.rsplit('/').next().unwrap(), data.as_slice())).collect();
+        assert_eq!(files["alice.flac"], alice_audio.as_slice());
+        assert_eq!(files["main_night.flac"], night_audio.as_slice()); // Shared by two sets, included once
+
+        let timetable: Vec<ArchiveTimetableEntry> = serde_json::from_slice(files["timetable.json"]).unwrap();
+        let summary: Vec<(usize, &str, Option<&str>, Option<f64>)> = timetable
+            .iter()
+            .map(|e| (e.position, e.dj_name.as_str(), e.recording.as_deref(), e.recording_offset_seconds))
+            .collect();
+        assert_eq!(summary, vec![
+            (1, "Alice", Some("recordings/alice.flac"), Some(0.0)),
+            (2, "Bob", Some("recordings/main_night.flac"), Some(300.0)),
+            (3, "Carol \"CC\"", Some("recordings/main_night.flac"), Some(3900.0)),
+            (4, "Dave", None, None),
+        ]);
+
+        let csv = std::str::from_utf8(files["timetable.csv"]).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[0], "position,dj_name,started_at,ended_at,duration_minutes,recording,recording_offset_seconds");
+        assert_eq!(
+            lines[3],
+            "3,\"Carol \"\"CC\"\"\",2024-01-06T00:00:00+00:00,2024-01-06T01:00:00+00:00,60,recordings/main_night.flac,3900.000"
+        );
+        assert_eq!(lines[4], "4,Dave,2024-01-06T01:00:00+00:00,2024-01-06T02:00:00+00:00,60,,");
+
+        let cue = std::str::from_utf8(files["Friday_Night_2024-01-05.cue"]).unwrap();
+        assert_eq!(cue, "\
+REM DATE 2024-01-05
+PERFORMER \"Various Artists\"
+TITLE \"Friday Night 2024-01-05\"
+FILE \"recordings/alice.flac\" WAVE
+  TRACK 01 AUDIO
+    TITLE \"Alice (22:00-23:00 UTC)\"
+    PERFORMER \"Alice\"
+    INDEX 01 00:00:00
+FILE \"recordings/main_night.flac\" WAVE
+  TRACK 02 AUDIO
+    TITLE \"Bob (23:00-00:00 UTC)\"
+    PERFORMER \"Bob\"
+    INDEX 01 05:00:00
+  TRACK 03 AUDIO
+    TITLE \"Carol 'CC' (00:00-01:00 UTC)\"
+    PERFORMER \"Carol 'CC'\"
+    INDEX 01 65:00:00
+");
+
+        let readme = std::str::from_utf8(files["README.txt"]).unwrap();
+        assert!(readme.starts_with("Friday Night 2024-01-05\n2024-01-05 21:00 - 2024-01-06 04:00 UTC\n"));
+        assert!(readme.contains("  4. 01:00-02:00  Dave                     (not recorded)"));
+        assert!(!readme.contains("Next Week"));
+    }
+
+    #[tokio::test]
+    async fn test_event_archive_tar_leaves_out_unreadable_recordings() {
+        let dir = tempfile::tempdir().unwrap();
+        let app_state = setup_test_db(dir.path()).await;
+        let (event_id, _, night_audio) = seed_event(dir.path(), &app_state).await;
+        std::fs::remove_file(dir.path().join("storage/published/alice.flac")).unwrap();
+
+        let archive = ArchiveService::new(app_state.clone()).prepare(&event_id).await.unwrap();
+        let tar = archive.write(ArchiveFormat::Tar, Vec::new()).await.unwrap();
+        let entries = untar(&tar);
+        let names: Vec<&str> = entries.iter().map(|(name, _)| name.rsplit('/').next().unwrap()).collect();
+        assert_eq!(names, vec!["main_night.flac", "README.txt", "timetable.json", "timetable.csv", "Friday_Night_2024-01-05.cue"]);
+        assert_eq!(entries[0].1, night_audio);
+
+        let readme = std::str::from_utf8(&entries[1].1).unwrap();
+        assert!(readme.contains("could not be read when this archive was made:\n  recordings/alice.flac\n"));
+        let timetable: Vec<ArchiveTimetableEntry> = serde_json::from_slice(&entries[2].1).unwrap();
+        assert_eq!(timetable[0].recording, None);
+        let cue = std::str::from_utf8(&entries[4].1).unwrap();
+        assert!(!cue.contains("alice.flac"));
+        assert!(cue.contains("  TRACK 01 AUDIO\n    TITLE \"Bob"));
+    }
+
+    #[tokio::test]
+    async fn test_event_archive_includes_tracklists() {
+        let dir = tempfile::tempdir().unwrap();
+        let app_state = setup_test_db(dir.path()).await;
+        let (event_id, _, _) = seed_event(dir.path(), &app_state).await;
+
+        let session_of = |name: &'static str| {
+            let db = app_state.db.clone();
+            async move {
+                sqlx::query_scalar::<_, String>("SELECT s.id FROM sessions s JOIN djs d ON d.id = s.dj_id WHERE d.name = ?")
+                    .bind(name)
+                    .fetch_one(&db)
+                    .await
+                    .unwrap()
+            }
+        };
+        let tracklists = TracklistService::new(app_state.clone());
+        let text = |text: &str| SubmitTracklistRequest { text: Some(text.to_string()), format: TracklistFormat::Text, ..Default::default() };
+        // Bob's tracklist has start times, Alice's doesn't
+        tracklists.set_tracklist(&session_of("Bob").await, text("0:00 Artist A - First\n30:00 Artist B - Second")).await.unwrap();
+        tracklists.set_tracklist(&session_of("Alice").await, text("Opener\nCloser")).await.unwrap();
+
+        let archive = ArchiveService::new(app_state.clone()).prepare(&event_id).await.unwrap();
+        let zip = archive.write(ArchiveFormat::Zip, Vec::new()).await.unwrap();
+        let entries = unzip(&zip);
+        let files: HashMap<&str, &[u8]> = entries.iter().map(|(name, data)| (name.rsplit('/').next().unwrap(), data.as_slice())).collect();
+        assert!(entries.iter().any(|(name, _)| name == "Friday_Night_2024-01-05/tracklists/02_Bob.txt"));
+
+        assert_eq!(
+            std::str::from_utf8(files["01_Alice.txt"]).unwrap(),
+            "Alice (22:00-23:00 UTC)\n\n 1. Opener\n 2. Closer\n"
+        );
+        assert_eq!(
+            std::str::from_utf8(files["02_Bob.txt"]).unwrap(),
+            "Bob (23:00-00:00 UTC)\n\n 1. [0:00] Artist A - First\n 2. [30:00] Artist B - Second\n"
+        );
+
+        let cue = std::str::from_utf8(files["Friday_Night_2024-01-05.cue"]).unwrap();
+        assert!(cue.contains("\
+  TRACK 01 AUDIO
+    TITLE \"Alice (22:00-23:00 UTC)\"
+    PERFORMER \"Alice\"
+    INDEX 01 00:00:00
+FILE \"recordings/main_night.flac\" WAVE
+  TRACK 02 AUDIO
+    TITLE \"First\"
+    PERFORMER \"Artist A\"
+    INDEX 01 05:00:00
+  TRACK 03 AUDIO
+    TITLE \"Second\"
+    PERFORMER \"Artist B\"
+    INDEX 01 35:00:00
+  TRACK 04 AUDIO
+    TITLE \"Carol 'CC' (00:00-01:00 UTC)\"
+"));
+
+        let readme = std::str::from_utf8(files["README.txt"]).unwrap();
+        assert!(readme.contains("- tracklists/: tracklists the DJs submitted"));
+    }
+
+    #[tokio::test]
+    async fn test_event_archive_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let app_state = setup_test_db(dir.path()).await;
+
+        let err = ArchiveService::new(app_state).prepare("missing").await.err().unwrap();
+        assert!(matches!(err.downcast_ref::<ArchiveError>(), Some(ArchiveError::EventNotFound)));
+
+        assert_eq!("TAR".parse::<ArchiveFormat>().unwrap(), ArchiveFormat::Tar);
+        assert_eq!(ArchiveFormat::default(), ArchiveFormat::Zip);
+        assert!(matches!("rar".parse::<ArchiveFormat>(), Err(ArchiveError::UnknownFormat(_))));
+    }
+}
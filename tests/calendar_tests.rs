@@ -0,0 +1,187 @@
+use session_recorder_addon::{
+    models::{
+        AppState,
+        dj::CreateDjRequest,
+        event_session::StartEventRequest,
+        lottery::VoidDrawRequest,
+        CalendarConfig, CalendarError,
+    },
+    services::{CalendarService, DjService, EventService, LotteryService},
+};
+use chrono::{TimeZone, Utc};
+use sqlx::sqlite::SqlitePoolOptions;
+use std::path::Path;
+use std::sync::Arc;
+
+mod common;
+
+#[cfg(test)]
+mod calendar_tests {
+    use super::*;
+
+    async fn setup_test_db(dir: &Path) -> Arc<AppState> {
+        let pool = SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+
+        let config = session_recorder_addon::models::AppConfig {
+            storage_config: common::local_storage(dir),
+            calendar_config: CalendarConfig {
+                timezone: Some(chrono_tz::Europe::Berlin),
+                location: Some("Club Zukunft, Berlin".to_string()),
+            },
+            ..common::test_config()
+        };
+
+        Arc::new(AppState { db: pool, config })
+    }
+
+    async fn register(app_state: &Arc<AppState>, name: &str) -> String {
+        DjService::new(app_state.clone()).register_dj(CreateDjRequest {
+            name: name.to_string(),
+            email: None,
+        }).await.unwrap().id
+    }
+
+    /// Starts at 22:00 in Berlin and draws the opening DJ
+    async fn start_event(app_state: &Arc<AppState>) -> String {
+        EventService::new(app_state.clone()).start_event(StartEventRequest {
+            slot_duration_minutes: Some(60),
+            late_arrival_cutoff_hours: None,
+            started_at: Some(Utc.with_ymd_and_hms(2024, 1, 19, 21, 0, 0).unwrap()),
+            late_penalty_curve: None,
+            name: Some("Friday Night".to_string()),
+        }).await.unwrap().id
+    }
+
+    /// `(UID, DTSTART, SUMMARY)` of each entry
+    fn entries(calendar: &str) -> Vec<(String, String, String)> {
+        calendar
+            .replace("\r\n ", "")
+            .split("BEGIN:VEVENT\r\n")
+            .skip(1)
+            .map(|event| {
+                let property = |name: &str| {
+                    event
+                        .lines()
+                        .find_map(|line| line.strip_prefix(name))
+                        .unwrap()
+                        .to_string()
+                };
+                (property("UID:"), property("DTSTART;TZID=Europe/Berlin:"), property("SUMMARY:"))
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_event_feed_follows_queue() {
+        let dir = tempfile::tempdir().unwrap();
+        let app_state = setup_test_db(dir.path()).await;
+        let alice = register(&app_state, "Alice").await;
+        let bob = register(&app_state, "Bob").await;
+        let event_id = start_event(&app_state).await;
+        let lottery_service = LotteryService::new(app_state.clone());
+        lottery_service.draw_next_dj().await.unwrap().unwrap();
+
+        let calendar_service = CalendarService::new(app_state.clone());
+        let calendar = calendar_service.event_feed(&event_id).await.unwrap();
+        assert!(calendar.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(calendar.contains("X-WR-CALNAME:Friday Night\r\n"));
+        assert!(calendar.contains("BEGIN:VTIMEZONE\r\nTZID:Europe/Berlin\r\n"));
+        assert!(calendar.contains("LOCATION:Club Zukunft\\, Berlin\r\n"));
+        assert!(calendar.contains("STATUS:TENTATIVE\r\n"));
+
+        let first = entries(&calendar);
+        assert_eq!(first.len(), 2);
+        assert_eq!(first[0].1, "20240119T220000");
+        assert_eq!(first[1].1, "20240119T230000");
+
+        // Swapping the two moves the entries, which keep their UIDs
+        let second_dj = if first[1].0.contains(&alice) { &alice } else { &bob };
+        lottery_service.move_dj_position(second_dj, 1).await.unwrap();
+        let moved = entries(&calendar_service.event_feed(&event_id).await.unwrap());
+        assert_eq!(moved[0], (first[1].0.clone(), first[0].1.clone(), first[1].2.clone()));
+        assert_eq!(moved[1], (first[0].0.clone(), first[1].1.clone(), first[0].2.clone()));
+
+        let missing = calendar_service.event_feed("missing").await.unwrap_err();
+        assert!(matches!(missing.downcast_ref::<CalendarError>(), Some(CalendarError::EventNotFound)));
+    }
+
+    #[tokio::test]
+    async fn test_dj_feed_follows_redraw() {
+        let dir = tempfile::tempdir().unwrap();
+        let app_state = setup_test_db(dir.path()).await;
+        let alice = register(&app_state, "Alice").await;
+        let bob = register(&app_state, "Bob").await;
+        start_event(&app_state).await;
+
+        let calendar_service = CalendarService::new(app_state.clone());
+        let feed = calendar_service.dj_feed_url(&alice).await.unwrap();
+        assert_eq!(feed.url, format!("http://localhost:3000/api/calendar/djs/{}.ics", alice));
+        assert_eq!(feed.webcal_url, feed.url.replace("http://", "webcal://"));
+
+        let (opening_id, opening_dj): (String, String) = sqlx::query_as("SELECT id, winner_dj_id FROM lottery_draws")
+            .fetch_one(&app_state.db)
+            .await
+            .unwrap();
+        let other_dj = if opening_dj == alice { &bob } else { &alice };
+
+        let calendar = calendar_service.dj_feed(&opening_dj).await.unwrap();
+        let slots = entries(&calendar);
+        assert_eq!(slots.len(), 1);
+        assert_eq!((slots[0].1.as_str(), slots[0].2.as_str()), ("20240119T220000", "Your set at Friday Night"));
+        let calendar = calendar_service.dj_feed(other_dj).await.unwrap();
+        assert!(calendar.ends_with("END:VCALENDAR\r\n"));
+        assert!(entries(&calendar).is_empty());
+
+        // The redrawn winner takes over the slot; a voided DJ's feed loses it
+        let response = LotteryService::new(app_state.clone()).void_draw(&opening_id, VoidDrawRequest {
+            reason: "Not at the venue".to_string(),
+            redraw: true,
+            include_voided_winner: false,
+        }).await.unwrap();
+        let redrawn = response.redraw.unwrap().winner.id;
+        let slots = entries(&calendar_service.dj_feed(&redrawn).await.unwrap());
+        assert_eq!(slots.len(), 1);
+        assert_eq!(slots[0].1, "20240119T220000");
+        assert!(entries(&calendar_service.dj_feed(&opening_dj).await.unwrap()).is_empty());
+
+        let unknown = calendar_service.dj_feed("nope").await.unwrap_err();
+        assert!(matches!(unknown.downcast_ref::<CalendarError>(), Some(CalendarError::DjNotFound)));
+        let missing = calendar_service.dj_feed_url("missing").await.unwrap_err();
+        assert!(matches!(missing.downcast_ref::<CalendarError>(), Some(CalendarError::DjNotFound)));
+    }
+
+    #[tokio::test]
+    async fn test_ended_event_lists_played_sets() {
+        let dir = tempfile::tempdir().unwrap();
+        let app_state = setup_test_db(dir.path()).await;
+        let alice = register(&app_state, "Alice").await;
+        let event_id = start_event(&app_state).await;
+        sqlx::query("INSERT INTO sessions (id, dj_id, started_at, ended_at, duration_minutes, upload_status, session_type) VALUES ('s1', ?, ?, ?, 95, 'uploaded', 'solo')")
+            .bind(&alice)
+            .bind(Utc.with_ymd_and_hms(2024, 1, 19, 21, 5, 0).unwrap())
+            .bind(Utc.with_ymd_and_hms(2024, 1, 19, 22, 40, 0).unwrap())
+            .execute(&app_state.db)
+            .await
+            .unwrap();
+        // Alice came back for a closing set, which needs a UID of its own
+        sqlx::query("INSERT INTO sessions (id, dj_id, started_at, ended_at, duration_minutes, upload_status, session_type) VALUES ('s2', ?, ?, ?, 30, 'uploaded', 'solo')")
+            .bind(&alice)
+            .bind(Utc.with_ymd_and_hms(2024, 1, 19, 23, 0, 0).unwrap())
+            .bind(Utc.with_ymd_and_hms(2024, 1, 19, 23, 30, 0).unwrap())
+            .execute(&app_state.db)
+            .await
+            .unwrap();
+        EventService::new(app_state.clone()).end_event().await.unwrap();
+
+        let calendar = CalendarService::new(app_state.clone()).event_feed(&event_id).await.unwrap();
+        let sets = entries(&calendar);
+        assert_eq!(sets, vec![
+            (format!("{}-{}@slotify", event_id, alice), "20240119T220500".to_string(), "Alice".to_string()),
+            (format!("{}-{}-2@slotify", event_id, alice), "20240120T000000".to_string(), "Alice".to_string()),
+        ]);
+        assert!(calendar.contains("DTEND;TZID=Europe/Berlin:20240119T234000\r\n"));
+        assert!(calendar.contains("STATUS:CONFIRMED\r\n"));
+        assert!(calendar.contains("DESCRIPTION:Slot 1 · Played\r\n"));
+    }
+}
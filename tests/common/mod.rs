@@ -0,0 +1,74 @@
+//! Shared setup of the integration tests. Each test file only spells out the config sections
+//! it exercises, e.g. `AppConfig { storage_config: common::local_storage(dir), ..common::test_config() }`,
+//! so a new section only needs a default here.
+#![allow(dead_code)] // Every test file is its own crate and uses only some of these
+
+use session_recorder_addon::models::{
+    AnalysisConfig, AppConfig, BrandingConfig, CalendarConfig, DownloadConfig, EmailConfig, LotteryConfig, OscConfig,
+    OutputConfig, SegmentationConfig, SessionRecorderIntegrationConfig, StorageConfig, StreamMetadataConfig,
+    TaggingConfig, WebhookConfig,
+};
+use std::path::Path;
+
+/// Every integration off, default storage and a placeholder mail server
+pub fn test_config() -> AppConfig {
+    AppConfig {
+        database_url: "sqlite::memory:".to_string(),
+        storage_config: StorageConfig::default(),
+        email_config: EmailConfig {
+            smtp_server: "localhost".to_string(),
+            smtp_port: 587,
+            username: "test".to_string(),
+            password: "test".to_string(),
+            from_address: "test@example.com".to_string(),
+        },
+        lottery_config: LotteryConfig::default(),
+        session_recorder_config: recorder_config(),
+        segmentation_config: SegmentationConfig::default(),
+        download_config: DownloadConfig::default(),
+        tagging_config: TaggingConfig::default(),
+        analysis_config: AnalysisConfig::default(),
+        osc_config: OscConfig::default(),
+        output_config: OutputConfig::default(),
+        stream_metadata_config: StreamMetadataConfig::default(),
+        webhook_config: WebhookConfig::default(),
+        calendar_config: CalendarConfig::default(),
+        branding_config: BrandingConfig::default(),
+    }
+}
+
+/// Local storage under `dir/storage`
+pub fn local_storage(dir: &Path) -> StorageConfig {
+    StorageConfig::Local {
+        root: dir.join("storage"),
+        public_url: None,
+    }
+}
+
+/// The session recorder integration, disabled
+pub fn recorder_config() -> SessionRecorderIntegrationConfig {
+    SessionRecorderIntegrationConfig {
+        enabled: false,
+        minio_endpoint: "http://localhost:9000".to_string(),
+        minio_access_key: "test".to_string(),
+        minio_secret_key: "test".to_string(),
+        bucket_name: "session-recorder".to_string(),
+        public_endpoint: "http://localhost:9000".to_string(),
+        auto_link_tolerance_minutes: 5,
+        sync_interval_seconds: 0,
+        webhook_secret: None,
+        control_url: None,
+        control_token: None,
+        local_dir: None,
+        preferred_recorder_id: None,
+    }
+}
+
+/// The session recorder integration, reading recordings from `dir/recorder`
+pub fn local_recorder(dir: &Path) -> SessionRecorderIntegrationConfig {
+    SessionRecorderIntegrationConfig {
+        enabled: true,
+        local_dir: Some(dir.join("recorder")),
+        ..recorder_config()
+    }
+}
@@ -8,6 +8,8 @@ use session_recorder_addon::{
 use sqlx::SqlitePool;
 use std::sync::Arc;
 
+mod common;
+
 #[cfg(test)]
 mod dj_service_tests {
     use super::*;
@@ -20,16 +22,7 @@ mod dj_service_tests {
         sqlx::migrate!("./migrations").run(&pool).await.unwrap();
         
         let config = session_recorder_addon::models::AppConfig {
-            database_url: "sqlite::memory:".to_string(),
-            cloud_storage_url: None,
-            email_config: session_recorder_addon::models::EmailConfig {
-                smtp_server: "localhost".to_string(),
-                smtp_port: 587,
-                username: "test".to_string(),
-                password: "test".to_string(),
-                from_address: "test@example.com".to_string(),
-            },
-            lottery_config: session_recorder_addon::models::LotteryConfig::default(),
+            ..common::test_config()
         };
         
         Arc::new(AppState { db: pool, config })
@@ -107,6 +100,7 @@ mod dj_service_tests {
             weight: None,
             is_active: Some(false),
             position_in_queue: None,
+            keep_recording: None,
         }).await.unwrap();
         
         let active_djs = dj_service.get_active_djs().await.unwrap();
@@ -130,6 +124,7 @@ mod dj_service_tests {
             weight: Some(2.0),
             is_active: None,
             position_in_queue: None,
+            keep_recording: None,
         };
         
         let updated_dj = dj_service.update_dj(&dj.id, update_request).await.unwrap();
@@ -0,0 +1,257 @@
+use session_recorder_addon::{
+    models::{
+        AppState,
+        dj::CreateDjRequest,
+        download::{CreateDownloadTokenRequest, DownloadClient, DownloadError},
+        session::StartSessionRequest,
+        storage::{ByteRange, StorageConfig, StorageError},
+    },
+    services::{download_status, DjService, DownloadService, DownloadFile, SessionService, StorageBackend, LocalStorage},
+};
+use sqlx::SqlitePool;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::io::AsyncReadExt;
+
+mod common;
+
+#[cfg(test)]
+mod download_tests {
+    use super::*;
+
+    async fn setup_test_db(storage_root: &Path) -> Arc<AppState> {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+
+        let config = session_recorder_addon::models::AppConfig {
+            storage_config: StorageConfig::Local {
+                root: storage_root.to_path_buf(),
+                public_url: Some("https://files.example.com/".to_string()),
+            },
+            ..common::test_config()
+        };
+
+        Arc::new(AppState { db: pool, config })
+    }
+
+    /// A session whose set is published under `sets/alice.flac`
+    async fn published_session(app_state: &Arc<AppState>, storage_root: &Path) -> String {
+        let dj = DjService::new(app_state.clone()).register_dj(CreateDjRequest {
+            name: "Alice".to_string(),
+            email: None,
+        }).await.unwrap();
+        let session = SessionService::new(app_state.clone()).start_session(StartSessionRequest {
+            dj_id: dj.id,
+            session_type: None,
+        }).await.unwrap();
+
+        LocalStorage::new(storage_root, None).put("sets/alice.flac", b"0123456789".to_vec()).await.unwrap();
+        sqlx::query("UPDATE sessions SET storage_key = 'sets/alice.flac' WHERE id = ?")
+            .bind(&session.id)
+            .execute(&app_state.db)
+            .await
+            .unwrap();
+        session.id
+    }
+
+    async fn read_body(file: DownloadFile) -> Vec<u8> {
+        let mut body = Vec::new();
+        let mut stream = file.stream.body;
+        stream.read_to_end(&mut body).await.unwrap();
+        body
+    }
+
+    fn client() -> DownloadClient {
+        DownloadClient {
+            ip: Some("203.0.113.7".to_string()),
+            user_agent: Some("curl/8.0".to_string()),
+        }
+    }
+
+    fn download_error(error: &anyhow::Error) -> Option<&DownloadError> {
+        error.downcast_ref::<DownloadError>()
+    }
+
+    #[test]
+    fn test_range_header_parsing() {
+        assert_eq!(ByteRange::parse("bytes=0-99"), Some(ByteRange::From { start: 0, end: Some(99) }));
+        assert_eq!(ByteRange::parse("bytes=100-"), Some(ByteRange::From { start: 100, end: None }));
+        assert_eq!(ByteRange::parse("bytes=-500"), Some(ByteRange::Last(500)));
+        assert_eq!(ByteRange::parse("bytes=0-1,5-6"), None); // Multiple ranges get the whole file
+        assert_eq!(ByteRange::parse("items=0-1"), None);
+        assert_eq!(ByteRange::parse("bytes=a-b"), None);
+        assert_eq!(ByteRange::parse("bytes=5-2"), None); // Invalid, not unsatisfiable
+        assert_eq!(ByteRange::parse("bytes=5-5"), Some(ByteRange::From { start: 5, end: Some(5) }));
+    }
+
+    #[test]
+    fn test_forwarded_for_only_trusted_from_proxies() {
+        let proxy: std::net::IpAddr = "10.0.0.2".parse().unwrap();
+        let peer = |ip: &str| Some(ip.parse().unwrap());
+
+        // Anyone else could put any address in the header
+        assert_eq!(DownloadClient::resolve_ip(peer("198.51.100.1"), Some("203.0.113.7"), &[proxy]).as_deref(), Some("198.51.100.1"));
+        assert_eq!(DownloadClient::resolve_ip(peer("10.0.0.2"), Some("203.0.113.7"), &[]).as_deref(), Some("10.0.0.2"));
+
+        // The client may have sent its own header, which the proxy appended to
+        assert_eq!(DownloadClient::resolve_ip(peer("10.0.0.2"), Some("1.2.3.4, 203.0.113.7"), &[proxy]).as_deref(), Some("203.0.113.7"));
+        assert_eq!(DownloadClient::resolve_ip(peer("10.0.0.2"), Some("203.0.113.7, 10.0.0.2"), &[proxy]).as_deref(), Some("203.0.113.7"));
+        assert_eq!(DownloadClient::resolve_ip(peer("10.0.0.2"), Some("garbage"), &[proxy]).as_deref(), Some("10.0.0.2"));
+        assert_eq!(DownloadClient::resolve_ip(peer("10.0.0.2"), None, &[proxy]).as_deref(), Some("10.0.0.2"));
+        assert_eq!(DownloadClient::resolve_ip(None, Some("203.0.113.7"), &[proxy]), None);
+    }
+
+    #[tokio::test]
+    async fn test_download_link_serves_file_with_ranges() {
+        let dir = tempfile::tempdir().unwrap();
+        let app_state = setup_test_db(dir.path()).await;
+        let session_id = published_session(&app_state, dir.path()).await;
+        let download_service = DownloadService::new(app_state.clone());
+
+        let issued = download_service.issue_token(&session_id, CreateDownloadTokenRequest::default()).await.unwrap();
+        assert_eq!(issued.token.token.len(), 64);
+        assert_eq!(issued.url, format!("http://localhost:3000/dl/{}", issued.token.token));
+        assert_eq!(issued.token.max_downloads, None);
+        assert_eq!((issued.token.expires_at - issued.token.created_at).num_days(), 30);
+        let token = issued.token.token;
+
+        let full = download_service.open_download(&token, None, &client()).await.unwrap();
+        assert!(!full.partial);
+        assert_eq!(full.filename, "alice.flac");
+        assert_eq!(full.content_type, "audio/flac");
+        assert_eq!(read_body(full).await, b"0123456789");
+
+        // Seeking in a player doesn't use up downloads
+        let part = download_service.open_download(&token, Some("bytes=2-4"), &client()).await.unwrap();
+        assert!(part.partial);
+        assert_eq!((part.stream.range.clone(), part.stream.size), (2..5, 10));
+        assert_eq!(read_body(part).await, b"234");
+        assert_eq!(download_service.get_token(&token).await.unwrap().unwrap().download_count, 1);
+
+        let error = download_service.open_download(&token, Some("bytes=20-"), &client()).await.err().unwrap();
+        assert!(matches!(error.downcast_ref::<StorageError>(), Some(StorageError::RangeNotSatisfiable { size: 10 })));
+        assert_eq!(download_status(&error), 416);
+
+        let log = download_service.get_access_log(&token).await.unwrap();
+        let statuses: Vec<i64> = log.iter().map(|entry| entry.status).collect();
+        assert_eq!(statuses, vec![200, 206, 416]);
+        assert_eq!(log[1].range_header.as_deref(), Some("bytes=2-4"));
+        assert_eq!(log[0].client_ip.as_deref(), Some("203.0.113.7"));
+        assert_eq!(log[0].user_agent.as_deref(), Some("curl/8.0"));
+        assert_eq!(log.iter().map(|entry| entry.counted).collect::<Vec<_>>(), vec![true, false, false]);
+    }
+
+    #[tokio::test]
+    async fn test_ranged_downloads_count_unless_continuing() {
+        let dir = tempfile::tempdir().unwrap();
+        let app_state = setup_test_db(dir.path()).await;
+        let session_id = published_session(&app_state, dir.path()).await;
+        let download_service = DownloadService::new(app_state.clone());
+
+        let token = download_service.issue_token(&session_id, CreateDownloadTokenRequest {
+            expires_in_days: None,
+            max_downloads: Some(2),
+        }).await.unwrap().token.token;
+        let other = DownloadClient {
+            ip: Some("198.51.100.1".to_string()),
+            user_agent: None,
+        };
+
+        // Skipping the first byte doesn't get around the limit
+        download_service.open_download(&token, Some("bytes=1-"), &client()).await.unwrap();
+        download_service.open_download(&token, Some("bytes=5-"), &client()).await.unwrap();
+        assert_eq!(download_service.get_token(&token).await.unwrap().unwrap().download_count, 1);
+
+        download_service.open_download(&token, Some("bytes=1-"), &other).await.unwrap();
+        let error = download_service.open_download(&token, Some("bytes=1-"), &DownloadClient::default()).await.err().unwrap();
+        assert!(matches!(download_error(&error), Some(DownloadError::LimitReached)));
+
+        // An old download no longer covers later ranges
+        sqlx::query("UPDATE download_log SET accessed_at = ?")
+            .bind(chrono::Utc::now() - chrono::Duration::hours(1))
+            .execute(&app_state.db)
+            .await
+            .unwrap();
+        let error = download_service.open_download(&token, Some("bytes=5-"), &client()).await.err().unwrap();
+        assert!(matches!(download_error(&error), Some(DownloadError::LimitReached)));
+    }
+
+    #[tokio::test]
+    async fn test_download_link_limits() {
+        let dir = tempfile::tempdir().unwrap();
+        let app_state = setup_test_db(dir.path()).await;
+        let session_id = published_session(&app_state, dir.path()).await;
+        let download_service = DownloadService::new(app_state.clone());
+
+        let once = download_service.issue_token(&session_id, CreateDownloadTokenRequest {
+            expires_in_days: None,
+            max_downloads: Some(1),
+        }).await.unwrap().token.token;
+        download_service.open_download(&once, None, &client()).await.unwrap();
+        let error = download_service.open_download(&once, None, &client()).await.err().unwrap();
+        assert!(matches!(download_error(&error), Some(DownloadError::LimitReached)));
+        assert_eq!(download_status(&error), 403);
+
+        let expired = download_service.issue_token(&session_id, CreateDownloadTokenRequest {
+            expires_in_days: Some(1),
+            max_downloads: None,
+        }).await.unwrap().token.token;
+        sqlx::query("UPDATE download_tokens SET expires_at = ? WHERE token = ?")
+            .bind(chrono::Utc::now() - chrono::Duration::minutes(1))
+            .bind(&expired)
+            .execute(&app_state.db)
+            .await
+            .unwrap();
+        let error = download_service.open_download(&expired, None, &client()).await.err().unwrap();
+        assert!(matches!(download_error(&error), Some(DownloadError::Expired)));
+        assert_eq!(download_status(&error), 410);
+
+        let revoked = download_service.issue_token(&session_id, CreateDownloadTokenRequest::default()).await.unwrap().token.token;
+        assert!(download_service.revoke_token(&revoked).await.unwrap());
+        assert!(!download_service.revoke_token(&revoked).await.unwrap());
+        let error = download_service.open_download(&revoked, None, &client()).await.err().unwrap();
+        assert!(matches!(download_error(&error), Some(DownloadError::Revoked)));
+
+        // Used-up and expired links are revoked too; the already revoked one is left alone
+        let fresh = download_service.issue_token(&session_id, CreateDownloadTokenRequest::default()).await.unwrap().token.token;
+        assert_eq!(download_service.revoke_session_tokens(&session_id).await.unwrap(), 3);
+        assert!(download_service.open_download(&fresh, None, &client()).await.is_err());
+        assert_eq!(download_service.get_session_tokens(&session_id).await.unwrap().len(), 4);
+
+        let error = download_service.open_download("no-such-token", None, &client()).await.err().unwrap();
+        assert!(matches!(download_error(&error), Some(DownloadError::NotFound)));
+        assert_eq!(download_service.get_access_log("no-such-token").await.unwrap().len(), 1);
+
+        let error = download_service.issue_token("no-such-session", CreateDownloadTokenRequest::default()).await.err().unwrap();
+        assert!(matches!(download_error(&error), Some(DownloadError::SessionNotFound)));
+
+        // Lifetimes outside 1..=365 days are refused instead of overflowing the expiry date
+        for days in [0, -1, 366, i64::MAX] {
+            let error = download_service.issue_token(&session_id, CreateDownloadTokenRequest {
+                expires_in_days: Some(days),
+                max_downloads: None,
+            }).await.err().unwrap();
+            assert!(matches!(download_error(&error), Some(DownloadError::InvalidExpiry)));
+            assert_eq!(download_status(&error), 400);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_download_link_without_recording() {
+        let dir = tempfile::tempdir().unwrap();
+        let app_state = setup_test_db(dir.path()).await;
+        let session_id = published_session(&app_state, dir.path()).await;
+        sqlx::query("UPDATE sessions SET storage_key = NULL WHERE id = ?")
+            .bind(&session_id)
+            .execute(&app_state.db)
+            .await
+            .unwrap();
+        let download_service = DownloadService::new(app_state.clone());
+
+        let token = download_service.issue_token(&session_id, CreateDownloadTokenRequest::default()).await.unwrap().token.token;
+        let error = download_service.open_download(&token, None, &client()).await.err().unwrap();
+        assert!(matches!(download_error(&error), Some(DownloadError::NoFile)));
+        assert_eq!(download_status(&error), 404);
+        assert_eq!(download_service.get_token(&token).await.unwrap().unwrap().download_count, 0);
+    }
+}
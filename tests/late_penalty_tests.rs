@@ -0,0 +1,139 @@
+use session_recorder_addon::models::{
+    event_session::EventSession,
+    late_penalty::{LatePenaltyCurve, PenaltyBreakpoint, PenaltyCurveError, PenaltyPreviewRequest},
+};
+use chrono::Utc;
+
+#[cfg(test)]
+mod late_penalty_tests {
+    use super::*;
+
+    fn approx(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn test_step_curve() {
+        let curve = LatePenaltyCurve::step_after_hours(2, 0.5);
+
+        assert_eq!(curve.multiplier(0), 1.0);
+        assert_eq!(curve.multiplier(120), 1.0);
+        assert_eq!(curve.multiplier(121), 0.5);
+        // Registrations before the event start count as on time
+        assert_eq!(curve.multiplier(-30), 1.0);
+    }
+
+    #[test]
+    fn test_linear_decay() {
+        let curve = LatePenaltyCurve::Linear {
+            grace_minutes: 60,
+            decay_minutes: 120,
+            floor: 0.4,
+        };
+
+        assert_eq!(curve.multiplier(60), 1.0);
+        assert!(approx(curve.multiplier(120), 0.7));
+        assert!(approx(curve.multiplier(180), 0.4));
+        assert!(approx(curve.multiplier(600), 0.4));
+    }
+
+    #[test]
+    fn test_exponential_decay() {
+        let curve = LatePenaltyCurve::Exponential {
+            grace_minutes: 0,
+            half_life_minutes: 60.0,
+            floor: 0.0,
+        };
+
+        assert_eq!(curve.multiplier(0), 1.0);
+        assert!(approx(curve.multiplier(60), 0.5));
+        assert!(approx(curve.multiplier(120), 0.25));
+    }
+
+    #[test]
+    fn test_breakpoint_table() {
+        let breakpoints = vec![
+            PenaltyBreakpoint { from_minutes: 60, multiplier: 0.8 },
+            PenaltyBreakpoint { from_minutes: 120, multiplier: 0.4 },
+        ];
+        let stepped = LatePenaltyCurve::Table { breakpoints: breakpoints.clone(), interpolate: false };
+        let interpolated = LatePenaltyCurve::Table { breakpoints, interpolate: true };
+
+        assert_eq!(stepped.multiplier(30), 1.0);
+        assert_eq!(stepped.multiplier(90), 0.8);
+        assert_eq!(stepped.multiplier(500), 0.4);
+        assert!(approx(interpolated.multiplier(90), 0.6));
+        assert_eq!(interpolated.multiplier(500), 0.4);
+    }
+
+    #[test]
+    fn test_validation() {
+        assert!(LatePenaltyCurve::step_after_hours(2, 0.5).validate().is_ok());
+        assert!(LatePenaltyCurve::step_after_hours(2, 1.5).validate().is_err());
+        assert!(LatePenaltyCurve::Linear { grace_minutes: 0, decay_minutes: 0, floor: 0.5 }.validate().is_err());
+        assert!(LatePenaltyCurve::Table { breakpoints: vec![], interpolate: false }.validate().is_err());
+
+        let unsorted = LatePenaltyCurve::Table {
+            breakpoints: vec![
+                PenaltyBreakpoint { from_minutes: 120, multiplier: 0.5 },
+                PenaltyBreakpoint { from_minutes: 60, multiplier: 0.8 },
+            ],
+            interpolate: false,
+        };
+        assert!(unsorted.validate().is_err());
+    }
+
+    #[test]
+    fn test_json_representation() {
+        let curve: LatePenaltyCurve = serde_json::from_str(
+            r#"{"type": "linear", "grace_minutes": 30, "decay_minutes": 90, "floor": 0.25}"#,
+        ).unwrap();
+
+        assert_eq!(curve, LatePenaltyCurve::Linear { grace_minutes: 30, decay_minutes: 90, floor: 0.25 });
+    }
+
+    #[test]
+    fn test_preview() {
+        let curve = LatePenaltyCurve::step_after_hours(1, 0.5);
+        let points = curve.preview(2.0, 120, 30);
+
+        assert_eq!(points.len(), 5);
+        assert_eq!(points[0].weight, 2.0);
+        assert_eq!(points[4].minutes_after_start, 120);
+        assert_eq!(points[4].weight, 1.0);
+    }
+
+    #[test]
+    fn test_preview_range_is_bounded() {
+        let request = |until_minutes, step_minutes| PenaltyPreviewRequest {
+            until_minutes,
+            step_minutes,
+            ..Default::default()
+        };
+
+        assert_eq!(request(None, None).range().unwrap(), (360, 15));
+        assert_eq!(request(Some(1440), Some(5)).range().unwrap(), (1440, 5));
+        assert!(matches!(request(Some(1441), None).range(), Err(PenaltyCurveError::PreviewTooLong(1441))));
+        assert!(matches!(request(Some(-1), None).range(), Err(PenaltyCurveError::PreviewTooLong(-1))));
+        assert!(matches!(request(None, Some(0)).range(), Err(PenaltyCurveError::NotPositive("step_minutes"))));
+        assert!(matches!(request(Some(1440), Some(1)).range(), Err(PenaltyCurveError::TooManyPoints)));
+    }
+
+    #[test]
+    fn test_event_curve_fallback_and_override() {
+        let started_at = Utc::now() - chrono::Duration::hours(3);
+        let mut event = EventSession::new(60, 2, Some(started_at));
+        let registered = started_at + chrono::Duration::minutes(150);
+
+        // Without a configured curve the cutoff hours act as a step
+        assert_eq!(event.late_penalty_multiplier(registered, 0.5), 0.5);
+
+        event.late_penalty_curve = Some(serde_json::to_string(&LatePenaltyCurve::Linear {
+            grace_minutes: 0,
+            decay_minutes: 300,
+            floor: 0.0,
+        }).unwrap());
+        assert!(approx(event.late_penalty_multiplier(registered, 0.5), 0.5));
+        assert!(approx(event.late_penalty_multiplier(started_at + chrono::Duration::minutes(60), 0.5), 0.8));
+    }
+}
@@ -0,0 +1,298 @@
+use session_recorder_addon::{
+    models::{
+        AppState,
+        dj::CreateDjRequest,
+        event_session::StartEventRequest,
+        lottery::{DjLotteryStatus, DrawStatus, VoidDrawError, VoidDrawRequest, VoidDrawResponse},
+        session::StartSessionRequest,
+        simulation::SimulationRequest,
+    },
+    services::{DjService, EventService, LotteryService, SessionService},
+};
+use sqlx::SqlitePool;
+use std::sync::Arc;
+
+mod common;
+
+#[cfg(test)]
+mod lottery_service_tests {
+    use super::*;
+
+    async fn setup_test_db() -> Arc<AppState> {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+
+        let config = session_recorder_addon::models::AppConfig {
+            ..common::test_config()
+        };
+
+        Arc::new(AppState { db: pool, config })
+    }
+
+    async fn register(app_state: &Arc<AppState>, name: &str) -> String {
+        let dj_service = DjService::new(app_state.clone());
+        dj_service.register_dj(CreateDjRequest {
+            name: name.to_string(),
+            email: None,
+        }).await.unwrap().id
+    }
+
+    #[tokio::test]
+    async fn test_odds_for_dj_in_pool() {
+        let app_state = setup_test_db().await;
+        let lottery_service = LotteryService::new(app_state.clone());
+
+        let alice = register(&app_state, "Alice").await;
+        register(&app_state, "Bob").await;
+
+        let odds = lottery_service.get_dj_odds(&alice).await.unwrap().unwrap();
+
+        assert!(matches!(odds.lottery_status, DjLotteryStatus::InPool));
+        assert_eq!(odds.pool_size, 2);
+        let current = odds.current.unwrap();
+        assert!((current.selection_probability - 0.5).abs() < 1e-9);
+        assert!(!current.weight_components.is_empty());
+        assert!(odds.history.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_draw_history_is_recorded_per_participant() {
+        let app_state = setup_test_db().await;
+        let lottery_service = LotteryService::new(app_state.clone());
+
+        let alice = register(&app_state, "Alice").await;
+        let bob = register(&app_state, "Bob").await;
+
+        let draw = lottery_service.draw_next_dj().await.unwrap().unwrap();
+        let (winner, loser) = if draw.winner.id == alice { (&alice, &bob) } else { (&bob, &alice) };
+
+        let winner_odds = lottery_service.get_dj_odds(winner).await.unwrap().unwrap();
+        assert!(matches!(winner_odds.lottery_status, DjLotteryStatus::Queued { position: 1 }));
+        assert!(winner_odds.current.is_none());
+        assert_eq!(winner_odds.history.len(), 1);
+        assert!(winner_odds.history[0].was_winner);
+        assert_eq!(winner_odds.history[0].participant_count, 2);
+
+        let loser_odds = lottery_service.get_dj_odds(loser).await.unwrap().unwrap();
+        assert_eq!(loser_odds.history.len(), 1);
+        assert!(!loser_odds.history[0].was_winner);
+        assert_eq!(loser_odds.history[0].draw_id, draw.id);
+        assert_eq!(loser_odds.history[0].winner_dj_id, draw.winner.id);
+        assert!(!loser_odds.history[0].weight_components.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_odds_for_unknown_dj() {
+        let app_state = setup_test_db().await;
+        let lottery_service = LotteryService::new(app_state);
+
+        assert!(lottery_service.get_dj_odds("missing").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_draws_are_linked_to_active_event() {
+        let app_state = setup_test_db().await;
+        register(&app_state, "Alice").await;
+        register(&app_state, "Bob").await;
+
+        let event_service = EventService::new(app_state.clone());
+        let event = event_service.start_event(StartEventRequest {
+            slot_duration_minutes: None,
+            late_arrival_cutoff_hours: None,
+            started_at: None,
+            late_penalty_curve: None,
+            name: None,
+        }).await.unwrap();
+
+        let event_draws: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM lottery_draws WHERE event_id = ?")
+            .bind(&event.id)
+            .fetch_one(&app_state.db)
+            .await
+            .unwrap();
+        assert_eq!(event_draws, 1);
+    }
+
+    #[tokio::test]
+    async fn test_fairness_report_simulates_current_pool() {
+        let app_state = setup_test_db().await;
+        let lottery_service = LotteryService::new(app_state.clone());
+
+        for name in ["Alice", "Bob", "Carol"] {
+            register(&app_state, name).await;
+        }
+
+        let request = SimulationRequest {
+            runs: Some(2_000),
+            seed: Some(5),
+            slots: Some(2),
+            event_id: None,
+        };
+        let report = lottery_service.get_fairness_report(request.clone()).await.unwrap();
+        let again = lottery_service.get_fairness_report(request).await.unwrap();
+
+        assert_eq!(report.simulation.pool_size, 3);
+        assert_eq!(report.simulation.seed, 5);
+        let total: f64 = report.simulation.djs.iter().map(|d| d.play_probability).sum();
+        assert!((total - 2.0).abs() < 1e-9);
+        for (a, b) in report.simulation.djs.iter().zip(again.simulation.djs.iter()) {
+            assert_eq!(a.play_probability, b.play_probability);
+        }
+        assert_eq!(report.historical.total_draws, 0);
+        assert!(report.historical.p_value.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_historical_fairness_uses_recorded_probabilities() {
+        let app_state = setup_test_db().await;
+        let lottery_service = LotteryService::new(app_state.clone());
+
+        for name in ["Alice", "Bob", "Carol", "Dave"] {
+            register(&app_state, name).await;
+        }
+        for _ in 0..3 {
+            lottery_service.draw_next_dj().await.unwrap().unwrap();
+        }
+
+        let historical = lottery_service.get_historical_fairness(None).await.unwrap();
+
+        assert_eq!(historical.total_draws, 3);
+        assert_eq!(historical.djs.len(), 4);
+        let wins: i64 = historical.djs.iter().map(|d| d.observed_wins).sum();
+        let expected: f64 = historical.djs.iter().map(|d| d.expected_wins).sum();
+        assert_eq!(wins, 3);
+        assert!((expected - 3.0).abs() < 1e-9);
+        assert_eq!(historical.degrees_of_freedom, 3);
+        assert!(historical.p_value.is_some());
+
+        let other_event = lottery_service.get_historical_fairness(Some("no-such-event")).await.unwrap();
+        assert_eq!(other_event.total_draws, 0);
+    }
+
+    fn void(reason: &str, redraw: bool) -> VoidDrawRequest {
+        VoidDrawRequest {
+            reason: reason.to_string(),
+            redraw,
+            include_voided_winner: false,
+        }
+    }
+
+    fn void_error(result: anyhow::Result<VoidDrawResponse>) -> VoidDrawError {
+        result.unwrap_err().downcast::<VoidDrawError>().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_void_restores_winner_and_compacts_queue() {
+        let app_state = setup_test_db().await;
+        let lottery_service = LotteryService::new(app_state.clone());
+
+        for name in ["Alice", "Bob", "Carol"] {
+            register(&app_state, name).await;
+        }
+        let mut draws = Vec::new();
+        for _ in 0..3 {
+            draws.push(lottery_service.draw_next_dj().await.unwrap().unwrap());
+        }
+
+        let response = lottery_service.void_draw(&draws[1].id, void("Wrong DJ announced", false)).await.unwrap();
+
+        assert_eq!(response.freed_position, Some(2));
+        assert!(response.redraw.is_none());
+        assert_eq!(response.voided.status, DrawStatus::Voided);
+        assert_eq!(response.voided.void_reason.as_deref(), Some("Wrong DJ announced"));
+        assert!(response.voided.voided_at.is_some());
+
+        let queue = lottery_service.get_current_queue().await.unwrap();
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue[0].id, draws[0].winner.id);
+        assert_eq!(queue[1].id, draws[2].winner.id);
+        assert_eq!(queue[1].position_in_queue, Some(2));
+
+        let pool = lottery_service.get_eligible_djs().await.unwrap();
+        assert_eq!(pool.len(), 1);
+        assert_eq!(pool[0].id, draws[1].winner.id);
+
+        // The draw is kept for the audit trail but no longer counts as completed
+        let stats = lottery_service.get_lottery_statistics().await.unwrap();
+        assert_eq!(stats.total_draws, 2);
+        assert_eq!(stats.voided_draws, 1);
+        let odds = lottery_service.get_dj_odds(&draws[1].winner.id).await.unwrap().unwrap();
+        assert!(odds.history.iter().any(|h| h.draw_id == draws[1].id && h.status == DrawStatus::Voided));
+    }
+
+    #[tokio::test]
+    async fn test_void_with_redraw_fills_same_position() {
+        let app_state = setup_test_db().await;
+        let lottery_service = LotteryService::new(app_state.clone());
+
+        for name in ["Alice", "Bob", "Carol"] {
+            register(&app_state, name).await;
+        }
+        let first = lottery_service.draw_next_dj().await.unwrap().unwrap();
+        let second = lottery_service.draw_next_dj().await.unwrap().unwrap();
+
+        let response = lottery_service.void_draw(&first.id, void("Drawn twice by mistake", true)).await.unwrap();
+        let redraw = response.redraw.unwrap();
+
+        let queue = lottery_service.get_current_queue().await.unwrap();
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue[0].id, redraw.winner.id);
+        assert_eq!(queue[1].id, second.winner.id);
+
+        let record = lottery_service.get_draw(&redraw.id).await.unwrap().unwrap();
+        assert_eq!(record.status, DrawStatus::Completed);
+        assert_eq!(record.replaces_draw_id.as_deref(), Some(first.id.as_str()));
+    }
+
+    #[tokio::test]
+    async fn test_redraw_leaves_out_the_voided_winner() {
+        let app_state = setup_test_db().await;
+        let lottery_service = LotteryService::new(app_state.clone());
+
+        // With only the voided winner in the pool, there is no one to redraw unless they are let back in
+        let alice = register(&app_state, "Alice").await;
+        let first = lottery_service.draw_next_dj().await.unwrap().unwrap();
+        let response = lottery_service.void_draw(&first.id, void("Not at the venue", true)).await.unwrap();
+        assert!(response.redraw.is_none());
+        assert!(lottery_service.get_current_queue().await.unwrap().is_empty());
+
+        let again = lottery_service.draw_next_dj().await.unwrap().unwrap();
+        let response = lottery_service.void_draw(&again.id, VoidDrawRequest {
+            include_voided_winner: true,
+            ..void("Announced before the recount", true)
+        }).await.unwrap();
+        assert_eq!(response.redraw.unwrap().winner.id, alice);
+
+        register(&app_state, "Bob").await;
+        register(&app_state, "Carol").await;
+        let wrong = lottery_service.draw_next_dj().await.unwrap().unwrap();
+        let response = lottery_service.void_draw(&wrong.id, void("Wrong DJ announced", true)).await.unwrap();
+        let redraw = response.redraw.unwrap();
+        assert_ne!(redraw.winner.id, wrong.winner.id);
+        assert!(redraw.participants.iter().all(|p| p.dj.id != wrong.winner.id));
+        let queue: Vec<String> = lottery_service.get_current_queue().await.unwrap().into_iter().map(|dj| dj.id).collect();
+        assert_eq!(queue, vec![alice, redraw.winner.id]);
+    }
+
+    #[tokio::test]
+    async fn test_void_rejections() {
+        let app_state = setup_test_db().await;
+        let lottery_service = LotteryService::new(app_state.clone());
+
+        register(&app_state, "Alice").await;
+        register(&app_state, "Bob").await;
+        let first = lottery_service.draw_next_dj().await.unwrap().unwrap();
+        let second = lottery_service.draw_next_dj().await.unwrap().unwrap();
+
+        assert!(matches!(void_error(lottery_service.void_draw(&first.id, void("  ", false)).await), VoidDrawError::MissingReason));
+        assert!(matches!(void_error(lottery_service.void_draw("missing", void("Typo", false)).await), VoidDrawError::NotFound));
+
+        lottery_service.void_draw(&first.id, void("Typo", false)).await.unwrap();
+        assert!(matches!(void_error(lottery_service.void_draw(&first.id, void("Typo", false)).await), VoidDrawError::AlreadyVoided));
+
+        SessionService::new(app_state.clone()).start_session(StartSessionRequest {
+            dj_id: second.winner.id.clone(),
+            session_type: None,
+        }).await.unwrap();
+        assert!(matches!(void_error(lottery_service.void_draw(&second.id, void("Too late", false)).await), VoidDrawError::WinnerAlreadyPlayed));
+    }
+}
@@ -1,7 +1,9 @@
 use session_recorder_addon::models::{
     dj::Dj,
-    lottery::{LotteryEngine, LotteryConfig},
+    event_session::EventSession,
+    lottery::{LotteryEngine, LotteryConfig, WeightComponentKind, MINIMUM_WEIGHT},
 };
+use std::collections::HashMap;
 use chrono::Utc;
 
 #[cfg(test)]
@@ -28,7 +30,7 @@ mod lottery_tests {
         let engine = LotteryEngine::new(config);
         let djs = vec![];
         
-        let result = engine.draw_winner(&djs);
+        let result = engine.draw_winner(&djs, None);
         assert!(result.is_none());
     }
 
@@ -38,7 +40,7 @@ mod lottery_tests {
         let engine = LotteryEngine::new(config);
         let djs = vec![create_test_dj("Alice", 1)];
         
-        let result = engine.draw_winner(&djs);
+        let result = engine.draw_winner(&djs, None);
         assert!(result.is_some());
         
         let draw = result.unwrap();
@@ -56,7 +58,7 @@ mod lottery_tests {
             create_test_dj("Charlie", 1),
         ];
         
-        let result = engine.draw_winner(&djs);
+        let result = engine.draw_winner(&djs, None);
         assert!(result.is_some());
         
         let draw = result.unwrap();
@@ -85,7 +87,7 @@ mod lottery_tests {
         let late_dj = create_test_dj("Late", 1);
         
         let djs = vec![early_dj, late_dj];
-        let participants = engine.calculate_weights(&djs);
+        let participants = engine.calculate_weights(&djs, None);
         
         // Early DJ should have higher weight due to time bonus
         let early_weight = participants.iter()
@@ -108,14 +110,21 @@ mod lottery_tests {
             ..Default::default()
         };
         let engine = LotteryEngine::new(config);
-        
-        // This test would need to mock the current time to properly test late arrival
-        // For now, we'll just ensure the engine handles the config correctly
-        let djs = vec![create_test_dj("TestDJ", 1)];
-        let participants = engine.calculate_weights(&djs);
-        
-        assert_eq!(participants.len(), 1);
-        assert!(participants[0].calculated_weight > 0.0);
+
+        // Event started 4 hours ago with a 2 hour cutoff
+        let event = EventSession::new(60, 2, Some(Utc::now() - chrono::Duration::hours(4)));
+        let on_time_dj = create_test_dj("OnTime", 3);
+        let late_dj = create_test_dj("Late", 1);
+
+        assert_eq!(event.late_penalty_multiplier(on_time_dj.registered_at, 0.5), 1.0);
+        assert_eq!(event.late_penalty_multiplier(late_dj.registered_at, 0.5), 0.5);
+
+        let djs = vec![on_time_dj, late_dj];
+        let participants = engine.calculate_weights(&djs, Some(&event));
+
+        let on_time_weight = participants.iter().find(|p| p.dj.name == "OnTime").unwrap().calculated_weight;
+        let late_weight = participants.iter().find(|p| p.dj.name == "Late").unwrap().calculated_weight;
+        assert!(late_weight < on_time_weight * 0.6);
     }
 
     #[test]
@@ -128,7 +137,7 @@ mod lottery_tests {
         low_weight_dj.weight = 0.001;
         
         let djs = vec![low_weight_dj];
-        let participants = engine.calculate_weights(&djs);
+        let participants = engine.calculate_weights(&djs, None);
         
         // Should enforce minimum weight of 0.1
         assert!(participants[0].calculated_weight >= 0.1);
@@ -150,7 +159,7 @@ mod lottery_tests {
         
         // Run multiple draws and count winners
         for _ in 0..num_draws {
-            if let Some(draw) = engine.draw_winner(&djs) {
+            if let Some(draw) = engine.draw_winner(&djs, None) {
                 *winner_counts.entry(draw.winner.name).or_insert(0) += 1;
             }
         }
@@ -163,4 +172,63 @@ mod lottery_tests {
             assert!(*count < 80);
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_weight_components_explain_weight() {
+        let config = LotteryConfig::default();
+        let engine = LotteryEngine::new(config);
+
+        let event = EventSession::new(60, 2, Some(Utc::now() - chrono::Duration::hours(4)));
+        let mut dj = create_test_dj("Adjusted", 1);
+        dj.weight = 2.0;
+
+        let participants = engine.calculate_weights(&[dj], Some(&event));
+        let participant = &participants[0];
+
+        let product: f64 = participant.weight_components.iter().map(|c| c.factor).product();
+        assert!((product - participant.calculated_weight).abs() < 1e-9);
+
+        let factor = |kind| participant.weight_components.iter()
+            .find(|c| c.kind == kind)
+            .unwrap()
+            .factor;
+        assert_eq!(factor(WeightComponentKind::ManualAdjustment), 2.0);
+        assert_eq!(factor(WeightComponentKind::LatePenalty), 0.5);
+        assert_eq!(factor(WeightComponentKind::CarryOver), 1.0);
+    }
+
+    #[test]
+    fn test_carry_over_bonus() {
+        let config = LotteryConfig {
+            carry_over_bonus: 0.25,
+            ..Default::default()
+        };
+        let engine = LotteryEngine::new(config);
+
+        let unlucky = create_test_dj("Unlucky", 2);
+        let fresh = create_test_dj("Fresh", 2);
+        let lost_draws = HashMap::from([(unlucky.id.clone(), 2)]);
+
+        let participants = engine.calculate_weights_with_carry_over(&[unlucky, fresh], None, &lost_draws);
+        let weight = |name: &str| participants.iter().find(|p| p.dj.name == name).unwrap().calculated_weight;
+
+        assert!((weight("Unlucky") / weight("Fresh") - 1.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_minimum_weight_is_recorded() {
+        let engine = LotteryEngine::new(LotteryConfig::default());
+
+        let mut low_weight_dj = create_test_dj("LowWeight", 1);
+        low_weight_dj.weight = 0.01;
+
+        let mut zero_weight_dj = create_test_dj("ZeroWeight", 1);
+        zero_weight_dj.weight = 0.0;
+
+        let participants = engine.calculate_weights(&[low_weight_dj, zero_weight_dj], None);
+        for participant in &participants {
+            assert_eq!(participant.calculated_weight, MINIMUM_WEIGHT);
+            assert!(participant.weight_components.iter().any(|c| c.kind == WeightComponentKind::MinimumWeight));
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use session_recorder_addon::{
+    models::{AppState, dj::CreateDjRequest},
+    services::{run_osc_listener, DjService, NowPlayingService, TracklistService},
+    utils::{OscArg, OscMessage},
+};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use sqlx::sqlite::SqlitePoolOptions;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::net::UdpSocket;
+
+mod common;
+
+#[cfg(test)]
+mod now_playing_tests {
+    use super::*;
+
+    async fn setup_test_db(dir: &Path) -> Arc<AppState> {
+        // One connection, so the listener task and the test see the same in-memory database
+        let pool = SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+
+        let config = session_recorder_addon::models::AppConfig {
+            storage_config: common::local_storage(dir),
+            session_recorder_config: common::local_recorder(dir),
+            ..common::test_config()
+        };
+
+        Arc::new(AppState { db: pool, config })
+    }
+
+    async fn start_session(app_state: &Arc<AppState>, dj_name: &str, started_at: DateTime<Utc>) -> String {
+        let dj = DjService::new(app_state.clone()).register_dj(CreateDjRequest {
+            name: dj_name.to_string(),
+            email: None,
+        }).await.unwrap();
+        let session_id = uuid::Uuid::new_v4().to_string();
+        sqlx::query(
+            "INSERT INTO sessions (id, dj_id, started_at, upload_status, session_type) VALUES (?, ?, ?, 'recording', 'solo')"
+        )
+        .bind(&session_id)
+        .bind(&dj.id)
+        .bind(started_at)
+        .execute(&app_state.db)
+        .await
+        .unwrap();
+        session_id
+    }
+
+    fn string(value: &str) -> OscArg {
+        OscArg::String(value.to_string())
+    }
+
+    #[tokio::test]
+    async fn test_tracks_are_timed_against_the_open_session() {
+        let dir = tempfile::tempdir().unwrap();
+        let app_state = setup_test_db(dir.path()).await;
+        let service = NowPlayingService::new(app_state.clone());
+        let started_at = Utc.with_ymd_and_hms(2024, 1, 5, 22, 0, 0).unwrap();
+
+        // Nothing is recorded between sets
+        assert!(service.record_track(Some("Floorplan"), "Never Grow Old", started_at).await.unwrap().is_none());
+        assert!(service.now_playing().await.unwrap().is_none());
+
+        let session_id = start_session(&app_state, "Alice", started_at).await;
+        let now_playing = service.now_playing().await.unwrap().unwrap();
+        assert_eq!(now_playing.session_id, session_id);
+        assert_eq!(now_playing.dj_name, "Alice");
+        assert!(now_playing.track.is_none());
+
+        service.record_track(Some("Floorplan"), "Never Grow Old", started_at + Duration::seconds(5)).await.unwrap();
+        // Reported again when cued on the other deck
+        service.record_track(Some(" Floorplan "), "Never Grow Old", started_at + Duration::seconds(90)).await.unwrap();
+        service.record_track(None, "Untitled dubplate", started_at + Duration::seconds(272)).await.unwrap();
+        // A bridge clock running behind still lands after the track before it
+        let now_playing = service
+            .record_track(Some("Robert Hood"), "Minus", started_at + Duration::seconds(200))
+            .await
+            .unwrap()
+            .unwrap();
+        let track = now_playing.track.unwrap();
+        assert_eq!((track.position, track.offset_seconds), (3, Some(272.0)));
+
+        let tracklist = TracklistService::new(app_state.clone()).get_tracklist(&session_id).await.unwrap();
+        assert!(tracklist.updated_at.is_some());
+        let tracks: Vec<(Option<f64>, String)> = tracklist.tracks.iter().map(|t| (t.offset_seconds, t.display())).collect();
+        assert_eq!(tracks, vec![
+            (Some(5.0), "Floorplan - Never Grow Old".to_string()),
+            (Some(272.0), "Untitled dubplate".to_string()),
+            (Some(272.0), "Robert Hood - Minus".to_string()),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_osc_listener_records_tracks() {
+        let dir = tempfile::tempdir().unwrap();
+        let app_state = setup_test_db(dir.path()).await;
+        let session_id = start_session(&app_state, "Bob", Utc::now() - Duration::minutes(10)).await;
+
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let listen_addr = socket.local_addr().unwrap();
+        let listener = tokio::spawn(run_osc_listener(app_state.clone(), socket));
+
+        let sender = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let packets = [
+            OscMessage::new("/slotify/track", vec![string("Jeff Mills"), string("The Bells"), OscArg::Int(1)]).encode(),
+            OscMessage::new("/mixxx/deck1/volume", vec![OscArg::Float(0.8)]).encode(),
+            b"not osc".to_vec(),
+            OscMessage::new("/slotify/track", vec![OscArg::Int(2), string("DJ Koze - Pick Up")]).encode(),
+        ];
+        for packet in &packets {
+            sender.send_to(packet, listen_addr).await.unwrap();
+        }
+
+        let service = TracklistService::new(app_state.clone());
+        let mut tracks = Vec::new();
+        for _ in 0..100 {
+            tracks = service.get_tracks(&session_id).await.unwrap();
+            if tracks.len() == 2 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        listener.abort();
+
+        assert_eq!(tracks.iter().map(|t| t.display()).collect::<Vec<_>>(), vec!["Jeff Mills - The Bells", "DJ Koze - Pick Up"]);
+        let offset = tracks[0].offset_seconds.unwrap();
+        assert!((595.0..=660.0).contains(&offset), "offset {}", offset);
+    }
+}
@@ -0,0 +1,331 @@
+use session_recorder_addon::{
+    models::{
+        AppState,
+        dj::CreateDjRequest,
+        event_session::StartEventRequest,
+        HookContext, HookTarget, HookTrigger, OutputConfig, OutputHook,
+    },
+    services::{DjService, EventService, OutputService},
+    utils::{OscArg, OscMessage},
+};
+use chrono::{Duration, TimeZone, Utc};
+use sqlx::SqlitePool;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, UdpSocket};
+
+mod common;
+
+#[cfg(test)]
+mod output_hook_tests {
+    use super::*;
+
+    async fn setup_test_db(dir: &Path, hooks: Vec<OutputHook>) -> Arc<AppState> {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+
+        let config = session_recorder_addon::models::AppConfig {
+            storage_config: common::local_storage(dir),
+            output_config: OutputConfig {
+                hooks,
+                timeout_seconds: 2,
+                ..OutputConfig::default()
+            },
+            ..common::test_config()
+        };
+
+        Arc::new(AppState { db: pool, config })
+    }
+
+    async fn register(app_state: &Arc<AppState>, name: &str) -> String {
+        DjService::new(app_state.clone()).register_dj(CreateDjRequest {
+            name: name.to_string(),
+            email: None,
+        }).await.unwrap().id
+    }
+
+    async fn start_event(app_state: &Arc<AppState>) {
+        EventService::new(app_state.clone()).start_event(StartEventRequest {
+            slot_duration_minutes: Some(60),
+            late_arrival_cutoff_hours: None,
+            started_at: None,
+            late_penalty_curve: None,
+            name: Some("Friday Night".to_string()),
+        }).await.unwrap();
+    }
+
+    async fn receive_osc(socket: &UdpSocket) -> OscMessage {
+        let mut buffer = [0u8; 1024];
+        let (len, _) = tokio::time::timeout(std::time::Duration::from_secs(5), socket.recv_from(&mut buffer))
+            .await
+            .expect("no OSC message")
+            .unwrap();
+        OscMessage::parse_packet(&buffer[..len]).unwrap().remove(0)
+    }
+
+    #[test]
+    fn test_hooks_file_and_templates() {
+        let json = r#"[
+            { "name": "Lights", "triggers": ["slot_start", "slot_ending"], "type": "osc",
+              "host": "10.0.0.5:8000", "address": "/dj/{trigger}", "args": ["{dj_name}", 1, 0.5] },
+            { "name": "Display", "triggers": ["draw"], "type": "http", "url": "http://display.local/text",
+              "headers": { "Authorization": "Bearer display-key" }, "body": "Next up: {dj_name}", "enabled": false },
+            { "name": "OBS", "triggers": ["slot_start"], "type": "file", "path": "/tmp/dj.txt",
+              "template": "{dj_name} until {slot_end}" }
+        ]"#;
+        let hooks: Vec<OutputHook> = serde_json::from_str(json).unwrap();
+        assert_eq!(hooks.len(), 3);
+        assert_eq!(hooks[0].triggers, vec![HookTrigger::SlotStart, HookTrigger::SlotEnding]);
+        assert!(matches!(&hooks[0].target, HookTarget::Osc { args, .. } if args.len() == 3));
+        assert!(matches!(&hooks[1].target, HookTarget::Http { method, headers, .. } if method == "POST" && headers.len() == 1));
+        // Listing the hooks doesn't give the credentials away
+        let listed = serde_json::to_string(&hooks).unwrap();
+        assert!(!listed.contains("headers") && !listed.contains("display-key"));
+        assert!(!hooks[1].enabled);
+        assert!(hooks[2].enabled);
+
+        let start = Utc.with_ymd_and_hms(2024, 1, 5, 22, 0, 0).unwrap();
+        let context = HookContext {
+            trigger: "slot_ending".to_string(),
+            event_name: "Friday Night".to_string(),
+            dj_name: "Alice".to_string(),
+            next_dj_name: "Bob".to_string(),
+            slot_start: Some(start),
+            slot_end: Some(start + Duration::minutes(60)),
+            minutes_left: Some(10),
+        };
+        let rendered = context.render_at(
+            "{event_name}: {dj_name} {slot_start}-{slot_end}, {countdown} left ({minutes_left} min), then {next_dj_name} {unknown}",
+            start + Duration::seconds(50 * 60 + 15),
+        );
+        assert_eq!(rendered, "Friday Night: Alice 22:00-23:00, 9:45 left (10 min), then Bob {unknown}");
+
+        // Values are not expanded again, and are escaped for JSON bodies
+        let tricky = HookContext {
+            dj_name: "{countdown} \"DJ\" \\o/".to_string(),
+            ..context
+        };
+        assert_eq!(tricky.render_at("{{dj_name}} {", start), "{{countdown} \"DJ\" \\o/} {");
+        let body = tricky.render_json(r#"{"text": "{dj_name} next"}"#);
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&body).unwrap()["text"], "{countdown} \"DJ\" \\o/ next");
+    }
+
+    #[tokio::test]
+    async fn test_slot_start_and_draw_fire_hooks() {
+        let dir = tempfile::tempdir().unwrap();
+        let osc_receiver = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let http_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let display_file = dir.path().join("now.txt");
+        let hooks = vec![
+            OutputHook {
+                name: "Lights".to_string(),
+                triggers: vec![HookTrigger::SlotStart],
+                enabled: true,
+                target: HookTarget::Osc {
+                    host: osc_receiver.local_addr().unwrap().to_string(),
+                    address: "/slotify/{trigger}".to_string(),
+                    args: vec![serde_json::json!("{dj_name}"), serde_json::json!(60)],
+                },
+            },
+            OutputHook {
+                name: "VJ".to_string(),
+                triggers: vec![HookTrigger::Draw],
+                enabled: true,
+                target: HookTarget::Http {
+                    url: format!("http://{}/draw", http_listener.local_addr().unwrap()),
+                    method: "PUT".to_string(),
+                    headers: Default::default(),
+                    body: Some("{dj_name} is in".to_string()),
+                },
+            },
+            OutputHook {
+                name: "Stream overlay".to_string(),
+                triggers: vec![HookTrigger::SlotStart],
+                enabled: true,
+                target: HookTarget::File {
+                    path: display_file.clone(),
+                    template: "{event_name}: {dj_name} until {slot_end}".to_string(),
+                    append: false,
+                },
+            },
+        ];
+        let app_state = setup_test_db(dir.path(), hooks).await;
+        let alice = register(&app_state, "Alice").await;
+
+        // Starting the event draws Alice, which goes to the VJ desk
+        start_event(&app_state).await;
+        let (mut connection, _) = tokio::time::timeout(std::time::Duration::from_secs(5), http_listener.accept())
+            .await
+            .expect("no HTTP request")
+            .unwrap();
+        let mut request = Vec::new();
+        let mut buffer = [0u8; 1024];
+        while !String::from_utf8_lossy(&request).contains("Alice is in") {
+            let len = connection.read(&mut buffer).await.unwrap();
+            assert!(len > 0, "request ended early: {}", String::from_utf8_lossy(&request));
+            request.extend_from_slice(&buffer[..len]);
+        }
+        assert!(String::from_utf8_lossy(&request).starts_with("PUT /draw HTTP/1.1"));
+        connection.write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n").await.unwrap();
+
+        let event = EventService::new(app_state.clone()).start_next_dj_slot(alice).await.unwrap();
+        let message = receive_osc(&osc_receiver).await;
+        assert_eq!(message.address, "/slotify/slot_start");
+        assert_eq!(message.args, vec![OscArg::String("Alice".to_string()), OscArg::Int(60)]);
+
+        let slot_end = event.current_slot_started_at.unwrap() + Duration::minutes(60);
+        let expected = format!("Friday Night: Alice until {}\n", slot_end.format("%H:%M"));
+        for _ in 0..100 {
+            if std::fs::read_to_string(&display_file).is_ok_and(|text| text == expected) {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        assert_eq!(std::fs::read_to_string(&display_file).unwrap(), expected);
+    }
+
+    #[tokio::test]
+    async fn test_slot_ending_fires_once_per_slot() {
+        let dir = tempfile::tempdir().unwrap();
+        let osc_receiver = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let hooks = vec![OutputHook {
+            name: "Countdown".to_string(),
+            triggers: vec![HookTrigger::SlotEnding],
+            enabled: true,
+            target: HookTarget::Osc {
+                host: osc_receiver.local_addr().unwrap().to_string(),
+                address: "/countdown".to_string(),
+                args: vec![serde_json::json!("{dj_name}"), serde_json::json!("{next_dj_name}"), serde_json::json!("{minutes_left}")],
+            },
+        }];
+        let app_state = setup_test_db(dir.path(), hooks).await;
+        let alice = register(&app_state, "Alice").await;
+        register(&app_state, "Bob").await;
+        start_event(&app_state).await;
+        let service = OutputService::new(app_state.clone());
+        let mut warned_slot = None;
+
+        // No slot is running yet
+        assert!(!service.check_slot_ending(&mut warned_slot).await.unwrap());
+
+        let event = EventService::new(app_state.clone()).start_next_dj_slot(alice.clone()).await.unwrap();
+        assert!(!service.check_slot_ending(&mut warned_slot).await.unwrap());
+
+        // Fifty-two minutes into a sixty minute slot; Bob is queued after Alice
+        let slot_start = event.current_slot_started_at.unwrap() - Duration::minutes(52);
+        sqlx::query("UPDATE event_sessions SET current_slot_started_at = ? WHERE id = ?")
+            .bind(slot_start)
+            .bind(&event.id)
+            .execute(&app_state.db)
+            .await
+            .unwrap();
+        sqlx::query("UPDATE djs SET position_in_queue = 2 WHERE name = 'Bob'")
+            .execute(&app_state.db)
+            .await
+            .unwrap();
+        assert!(service.check_slot_ending(&mut warned_slot).await.unwrap());
+        assert!(!service.check_slot_ending(&mut warned_slot).await.unwrap());
+        assert_eq!(warned_slot, Some(slot_start));
+
+        let message = receive_osc(&osc_receiver).await;
+        assert_eq!(message.address, "/countdown");
+        assert_eq!(message.args, vec![
+            OscArg::String("Alice".to_string()),
+            OscArg::String("Bob".to_string()),
+            OscArg::String("8".to_string()),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_hook_failures_are_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        // Bound and dropped, so nothing listens there
+        let closed_port = TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap();
+        let hooks = vec![
+            OutputHook {
+                name: "Display".to_string(),
+                triggers: vec![HookTrigger::EventEnd],
+                enabled: true,
+                target: HookTarget::Http {
+                    url: format!("http://{}/", closed_port),
+                    method: "POST".to_string(),
+                    headers: Default::default(),
+                    body: None,
+                },
+            },
+            OutputHook {
+                name: "Log".to_string(),
+                triggers: vec![HookTrigger::EventEnd],
+                enabled: true,
+                target: HookTarget::File {
+                    path: dir.path().join("events.log"),
+                    template: "{trigger} {event_name}".to_string(),
+                    append: true,
+                },
+            },
+        ];
+        let app_state = setup_test_db(dir.path(), hooks).await;
+        start_event(&app_state).await;
+        let service = OutputService::new(app_state.clone());
+
+        let results = service.test_hooks(HookTrigger::EventEnd).await.unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(!results[0].ok && results[0].error.is_some());
+        assert!(results[1].ok);
+        service.test_hooks(HookTrigger::EventEnd).await.unwrap();
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("events.log")).unwrap(),
+            "event_end Friday Night\nevent_end Friday Night\n"
+        );
+        assert!(service.test_hooks(HookTrigger::Draw).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_osc_reaches_ipv6_targets() {
+        let dir = tempfile::tempdir().unwrap();
+        let osc_receiver = UdpSocket::bind("[::1]:0").await.unwrap();
+        let hooks = vec![OutputHook {
+            name: "Lights".to_string(),
+            triggers: vec![HookTrigger::EventEnd],
+            enabled: true,
+            target: HookTarget::Osc {
+                host: osc_receiver.local_addr().unwrap().to_string(),
+                address: "/slotify/{trigger}".to_string(),
+                args: Vec::new(),
+            },
+        }];
+        let app_state = setup_test_db(dir.path(), hooks).await;
+        start_event(&app_state).await;
+
+        let results = OutputService::new(app_state).test_hooks(HookTrigger::EventEnd).await.unwrap();
+        assert!(results[0].ok, "{:?}", results[0].error);
+        assert_eq!(receive_osc(&osc_receiver).await.address, "/slotify/event_end");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_pipe_without_reader_fails_at_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let pipe = dir.path().join("obs.pipe");
+        let path = std::ffi::CString::new(pipe.to_str().unwrap()).unwrap();
+        assert_eq!(unsafe { libc::mkfifo(path.as_ptr(), 0o600) }, 0);
+        let hooks = vec![OutputHook {
+            name: "OBS".to_string(),
+            triggers: vec![HookTrigger::EventEnd],
+            enabled: true,
+            target: HookTarget::File {
+                path: pipe,
+                template: "{dj_name}".to_string(),
+                append: false,
+            },
+        }];
+        let app_state = setup_test_db(dir.path(), hooks).await;
+        start_event(&app_state).await;
+
+        let started = std::time::Instant::now();
+        let results = OutputService::new(app_state).test_hooks(HookTrigger::EventEnd).await.unwrap();
+        assert!(results[0].error.as_deref().unwrap().starts_with("no reader on"));
+        assert!(started.elapsed() < std::time::Duration::from_secs(1));
+    }
+}
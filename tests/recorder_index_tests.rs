@@ -0,0 +1,288 @@
+use session_recorder_addon::{
+    models::{
+        AppState,
+        dj::CreateDjRequest,
+        recorder_index::RecorderSyncError,
+        recorder_match::MatchOutcome,
+    },
+    services::{DjService, RecorderCatalog, RecorderIndexService, RecorderSession, SessionFiles, SessionService},
+};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Utc};
+use sqlx::{Row, SqlitePool};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+mod common;
+
+#[cfg(test)]
+mod recorder_index_tests {
+    use super::*;
+
+    async fn setup_test_db() -> Arc<AppState> {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+
+        let config = session_recorder_addon::models::AppConfig {
+            ..common::test_config()
+        };
+
+        Arc::new(AppState { db: pool, config })
+    }
+
+    /// In-memory stand-in for the recorder bucket that counts detail fetches
+    #[derive(Default)]
+    struct FakeCatalog {
+        sessions: Mutex<BTreeMap<String, Vec<RecorderSession>>>,
+        unreadable: Mutex<Vec<String>>,
+        fetches: AtomicUsize,
+    }
+
+    impl FakeCatalog {
+        fn put(&self, session: RecorderSession) {
+            let mut sessions = self.sessions.lock().unwrap();
+            let recorder = sessions.entry(session.recorder_id.clone()).or_default();
+            recorder.retain(|s| s.id != session.id);
+            recorder.push(session);
+        }
+
+        fn remove(&self, recorder_id: &str, session_id: &str) {
+            if let Some(recorder) = self.sessions.lock().unwrap().get_mut(recorder_id) {
+                recorder.retain(|s| s.id != session_id);
+            }
+        }
+
+        fn take_fetches(&self) -> usize {
+            self.fetches.swap(0, Ordering::SeqCst)
+        }
+    }
+
+    impl RecorderCatalog for FakeCatalog {
+        async fn list_recorders(&self) -> Result<Vec<String>> {
+            Ok(self.sessions.lock().unwrap().keys().cloned().collect())
+        }
+
+        async fn list_session_ids(&self, recorder_id: &str) -> Result<Vec<String>> {
+            let sessions = self.sessions.lock().unwrap();
+            Ok(sessions.get(recorder_id).map(|s| s.iter().map(|s| s.id.clone()).collect()).unwrap_or_default())
+        }
+
+        async fn session_details(&self, recorder_id: &str, session_id: &str) -> Result<RecorderSession> {
+            self.fetches.fetch_add(1, Ordering::SeqCst);
+            if self.unreadable.lock().unwrap().iter().any(|id| id == session_id) {
+                return Err(anyhow!("metadata.json is corrupt"));
+            }
+            self.sessions
+                .lock()
+                .unwrap()
+                .get(recorder_id)
+                .and_then(|s| s.iter().find(|s| s.id == session_id).cloned())
+                .ok_or_else(|| anyhow!("no such session"))
+        }
+    }
+
+    fn recording(recorder: &str, id: &str, start: DateTime<Utc>, end: Option<DateTime<Utc>>) -> RecorderSession {
+        RecorderSession {
+            id: id.to_string(),
+            recorder_id: recorder.to_string(),
+            name: id.to_string(),
+            start_time: start,
+            end_time: end,
+            duration_seconds: end.map(|end| (end - start).num_seconds()),
+            is_closed: end.is_some(),
+            keep: false,
+            files: SessionFiles {
+                ogg_url: Some(format!("http://minio/{}/sessions/{}/data.ogg", recorder, id)),
+                flac_url: None,
+                waveform_url: None,
+                overview_png_url: None,
+                metadata_url: None,
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sync_adds_updates_and_removes_sessions() {
+        let app_state = setup_test_db().await;
+        let index = RecorderIndexService::new(app_state.clone());
+        let catalog = FakeCatalog::default();
+        let start = Utc::now() - Duration::hours(3);
+
+        catalog.put(recording("main", "a", start, Some(start + Duration::hours(1))));
+        catalog.put(recording("main", "b", start + Duration::hours(1), None));
+        catalog.put(recording("side", "c", start, Some(start + Duration::hours(2))));
+
+        let summary = index.sync_from(&catalog, false).await.unwrap();
+        assert_eq!(summary.recorders, 2);
+        assert_eq!((summary.sessions_added, summary.sessions_updated, summary.sessions_removed), (3, 0, 0));
+
+        // "b" finishes, "c" is deleted on the recorder, "d" is new
+        catalog.put(recording("main", "b", start + Duration::hours(1), Some(start + Duration::hours(2))));
+        catalog.remove("side", "c");
+        catalog.put(recording("side", "d", start + Duration::hours(2), None));
+
+        let summary = index.sync_from(&catalog, false).await.unwrap();
+        assert_eq!((summary.sessions_added, summary.sessions_updated, summary.sessions_removed), (1, 1, 1));
+        assert_eq!(summary.sessions_unchanged, 1);
+
+        let sessions = index.get_sessions().await.unwrap();
+        let ids: Vec<&str> = sessions.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, vec!["d", "b", "a"]);
+
+        let b = index.get_session("main", "b").await.unwrap().unwrap();
+        assert!(b.is_closed);
+        assert_eq!(b.end_time, Some(start + Duration::hours(2)));
+        assert_eq!(b.files.ogg_url.as_deref(), Some("http://minio/main/sessions/b/data.ogg"));
+        assert!(index.get_session("side", "c").await.unwrap().is_none());
+
+        // Only the last 24 hours are offered for linking
+        catalog.put(recording("main", "old", start - Duration::days(3), Some(start - Duration::days(3) + Duration::hours(1))));
+        index.sync_from(&catalog, false).await.unwrap();
+        assert_eq!(index.get_recent_sessions(24).await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_incremental_sync_only_refetches_open_sessions() {
+        let app_state = setup_test_db().await;
+        let index = RecorderIndexService::new(app_state.clone());
+        let catalog = FakeCatalog::default();
+        let start = Utc::now() - Duration::hours(2);
+
+        catalog.put(recording("main", "closed", start, Some(start + Duration::hours(1))));
+        catalog.put(recording("main", "open", start + Duration::hours(1), None));
+        index.sync_from(&catalog, false).await.unwrap();
+        assert_eq!(catalog.take_fetches(), 2);
+
+        let summary = index.sync_from(&catalog, false).await.unwrap();
+        assert_eq!(catalog.take_fetches(), 1);
+        assert_eq!(summary.sessions_unchanged, 2);
+        assert_eq!(summary.sessions_updated, 0);
+
+        let summary = index.sync_from(&catalog, true).await.unwrap();
+        assert_eq!(catalog.take_fetches(), 2);
+        assert_eq!(summary.sessions_unchanged, 2);
+
+        // An unreadable session is reported but neither indexed nor dropped
+        catalog.put(recording("main", "broken", start, None));
+        catalog.unreadable.lock().unwrap().push("broken".to_string());
+        catalog.unreadable.lock().unwrap().push("open".to_string());
+        let summary = index.sync_from(&catalog, false).await.unwrap();
+        assert_eq!(summary.sessions_failed, 2);
+        assert_eq!(summary.sessions_removed, 0);
+        assert!(index.get_session("main", "open").await.unwrap().is_some());
+        assert!(index.get_session("main", "broken").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_sync_status_and_concurrent_runs() {
+        let app_state = setup_test_db().await;
+        let index = RecorderIndexService::new(app_state.clone());
+        let catalog = FakeCatalog::default();
+        let start = Utc::now() - Duration::hours(1);
+
+        let status = index.get_sync_status().await.unwrap();
+        assert!(!status.running);
+        assert!(status.last_finished_at.is_none());
+
+        catalog.put(recording("main", "a", start, None));
+        index.sync_from(&catalog, false).await.unwrap();
+
+        let status = index.get_sync_status().await.unwrap();
+        assert!(!status.running);
+        assert_eq!((status.recorders, status.indexed_sessions, status.sessions_added), (1, 1, 1));
+        assert!(status.last_finished_at.unwrap() >= status.last_started_at.unwrap());
+        assert!(status.last_error.is_none());
+
+        // Another run holds the claim
+        sqlx::query("UPDATE recorder_sync_status SET running = TRUE, last_started_at = ? WHERE id = 1")
+            .bind(Utc::now())
+            .execute(&app_state.db)
+            .await
+            .unwrap();
+        let error = index.sync_from(&catalog, false).await.unwrap_err();
+        assert!(matches!(error.downcast_ref::<RecorderSyncError>(), Some(RecorderSyncError::AlreadyRunning)));
+
+        // ...until it looks like it died with the process
+        sqlx::query("UPDATE recorder_sync_status SET last_started_at = ? WHERE id = 1")
+            .bind(Utc::now() - Duration::hours(2))
+            .execute(&app_state.db)
+            .await
+            .unwrap();
+        index.sync_from(&catalog, false).await.unwrap();
+        assert!(!index.get_sync_status().await.unwrap().running);
+
+        // Without the recorder integration there is nothing to sync from
+        let error = index.trigger_sync(false).await.unwrap_err();
+        assert!(matches!(error.downcast_ref::<RecorderSyncError>(), Some(RecorderSyncError::Disabled)));
+        let row = sqlx::query("SELECT running FROM recorder_sync_status WHERE id = 1")
+            .fetch_one(&app_state.db)
+            .await
+            .unwrap();
+        assert!(!row.get::<bool, _>("running"));
+    }
+
+    #[tokio::test]
+    async fn test_auto_link_reads_from_index() {
+        let app_state = setup_test_db().await;
+        let index = RecorderIndexService::new(app_state.clone());
+        let session_service = SessionService::new(app_state.clone());
+        let catalog = FakeCatalog::default();
+
+        let dj = DjService::new(app_state.clone())
+            .register_dj(CreateDjRequest { name: "Alice".to_string(), email: None })
+            .await
+            .unwrap();
+        let start = Utc::now() - Duration::hours(2);
+        let session_id = uuid::Uuid::new_v4().to_string();
+        sqlx::query("INSERT INTO sessions (id, dj_id, started_at, ended_at, upload_status, session_type) VALUES (?, ?, ?, ?, 'uploaded', 'solo')")
+            .bind(&session_id)
+            .bind(&dj.id)
+            .bind(start)
+            .bind(start + Duration::hours(1))
+            .execute(&app_state.db)
+            .await
+            .unwrap();
+
+        // Nothing indexed yet
+        let result = session_service.auto_link_recorder_session(&session_id, None).await.unwrap().unwrap();
+        assert!(matches!(result.outcome, MatchOutcome::NoMatch));
+
+        catalog.put(recording("main", "set", start, Some(start + Duration::hours(1))));
+        index.sync_from(&catalog, false).await.unwrap();
+
+        let result = session_service.auto_link_recorder_session(&session_id, None).await.unwrap().unwrap();
+        assert!(matches!(result.outcome, MatchOutcome::Matched { .. }));
+
+        let row = sqlx::query("SELECT recorder_session_id, recorder_id, recorder_ogg_url FROM sessions WHERE id = ?")
+            .bind(&session_id)
+            .fetch_one(&app_state.db)
+            .await
+            .unwrap();
+        assert_eq!(row.get::<Option<String>, _>("recorder_session_id").as_deref(), Some("set"));
+        assert_eq!(row.get::<Option<String>, _>("recorder_id").as_deref(), Some("main"));
+        assert_eq!(
+            row.get::<Option<String>, _>("recorder_ogg_url").as_deref(),
+            Some("http://minio/main/sessions/set/data.ogg")
+        );
+
+        // Manual links resolve from the index without a recorder client too
+        let other = uuid::Uuid::new_v4().to_string();
+        sqlx::query("INSERT INTO sessions (id, dj_id, started_at, upload_status, session_type) VALUES (?, ?, ?, 'uploaded', 'solo')")
+            .bind(&other)
+            .bind(&dj.id)
+            .bind(start)
+            .execute(&app_state.db)
+            .await
+            .unwrap();
+        session_service.link_to_recorder_session(&other, "set", "main").await.unwrap();
+        let linked: Option<String> = sqlx::query("SELECT recorder_session_id FROM sessions WHERE id = ?")
+            .bind(&other)
+            .fetch_one(&app_state.db)
+            .await
+            .unwrap()
+            .get("recorder_session_id");
+        assert_eq!(linked.as_deref(), Some("set"));
+    }
+}
@@ -0,0 +1,262 @@
+use session_recorder_addon::{
+    models::{
+        AppState,
+        dj::CreateDjRequest,
+        event_session::StartEventRequest,
+        recorder_match::{MatchOutcome, RecorderMatcher, RecordingWindow},
+    },
+    services::{DjService, EventService, RecorderSession, SessionFiles, SessionService},
+};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use sqlx::{Row, SqlitePool};
+use std::sync::Arc;
+
+mod common;
+
+#[cfg(test)]
+mod recorder_match_tests {
+    use super::*;
+
+    async fn setup_test_db() -> Arc<AppState> {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+
+        let config = session_recorder_addon::models::AppConfig {
+            ..common::test_config()
+        };
+
+        Arc::new(AppState { db: pool, config })
+    }
+
+    fn t(hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 1, hour, minute, 0).unwrap()
+    }
+
+    fn window(recorder: &str, id: &str, start: DateTime<Utc>, end: Option<DateTime<Utc>>) -> RecordingWindow {
+        RecordingWindow {
+            recorder_id: recorder.to_string(),
+            recorder_session_id: id.to_string(),
+            started_at: start,
+            ended_at: end,
+        }
+    }
+
+    fn recording(recorder: &str, id: &str, start: DateTime<Utc>, end: DateTime<Utc>) -> RecorderSession {
+        RecorderSession {
+            id: id.to_string(),
+            recorder_id: recorder.to_string(),
+            name: id.to_string(),
+            start_time: start,
+            end_time: Some(end),
+            duration_seconds: Some((end - start).num_seconds()),
+            is_closed: true,
+            keep: true,
+            files: SessionFiles {
+                ogg_url: Some(format!("http://minio/{}/{}/data.ogg", recorder, id)),
+                flac_url: None,
+                waveform_url: None,
+                overview_png_url: None,
+                metadata_url: None,
+            },
+        }
+    }
+
+    fn matched_id(outcome: &MatchOutcome) -> &str {
+        match outcome {
+            MatchOutcome::Matched { candidate } => &candidate.recorder_session_id,
+            other => panic!("expected a match, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_best_overlap_wins_over_earliest_start() {
+        let matcher = RecorderMatcher::new(5);
+        // A short sound check starts right at the DJ's start; the real recording a little later
+        let recordings = vec![
+            window("rec", "soundcheck", t(20, 0), Some(t(20, 10))),
+            window("rec", "set", t(20, 8), Some(t(21, 5))),
+        ];
+
+        let outcome = matcher.match_session(t(20, 0), Some(t(21, 0)), &recordings, t(23, 0));
+
+        assert_eq!(matched_id(&outcome), "set");
+        if let MatchOutcome::Matched { candidate } = outcome {
+            assert!(candidate.coverage > 0.9);
+            assert!(!candidate.split_required);
+        }
+    }
+
+    #[test]
+    fn test_all_night_recording_matches_every_set() {
+        let matcher = RecorderMatcher::new(5);
+        let recordings = vec![window("rec", "night", t(19, 55), Some(t(23, 59)))];
+
+        for (start, end) in [(t(20, 0), t(21, 0)), (t(21, 0), t(22, 0)), (t(22, 0), t(23, 0))] {
+            let outcome = matcher.match_session(start, Some(end), &recordings, t(23, 59));
+            assert_eq!(matched_id(&outcome), "night");
+            if let MatchOutcome::Matched { candidate } = outcome {
+                assert_eq!(candidate.coverage, 1.0);
+                assert!(candidate.split_required);
+            }
+        }
+    }
+
+    #[test]
+    fn test_two_recorders_covering_the_set_are_ambiguous() {
+        let matcher = RecorderMatcher::new(5);
+        let recordings = vec![
+            window("main-room", "a", t(20, 0), Some(t(23, 0))),
+            window("side-room", "b", t(19, 30), Some(t(22, 30))),
+            window("side-room", "c", t(22, 30), Some(t(23, 0))),
+        ];
+
+        let outcome = matcher.match_session(t(21, 0), Some(t(22, 0)), &recordings, t(23, 0));
+
+        match outcome {
+            MatchOutcome::Ambiguous { candidates } => {
+                let ids: Vec<&str> = candidates.iter().map(|c| c.recorder_session_id.as_str()).collect();
+                assert_eq!(ids.len(), 2);
+                assert!(ids.contains(&"a") && ids.contains(&"b"));
+            }
+            other => panic!("expected ambiguity, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_preferred_recorder_settles_a_tie() {
+        let recordings = vec![
+            window("main-room", "a", t(20, 0), Some(t(23, 0))),
+            window("backup", "b", t(20, 0), Some(t(23, 0))),
+        ];
+
+        let matcher = RecorderMatcher::new(5).with_preferred_recorder(Some("main-room".to_string()));
+        let outcome = matcher.match_session(t(21, 0), Some(t(22, 0)), &recordings, t(23, 0));
+        assert_eq!(matched_id(&outcome), "a");
+
+        // A preferred recorder that is not among the candidates leaves the tie for an admin
+        let matcher = RecorderMatcher::new(5).with_preferred_recorder(Some("side-room".to_string()));
+        let outcome = matcher.match_session(t(21, 0), Some(t(22, 0)), &recordings, t(23, 0));
+        assert!(matches!(outcome, MatchOutcome::Ambiguous { .. }));
+    }
+
+    #[test]
+    fn test_recording_of_the_set_beats_an_all_night_one() {
+        let recordings = vec![
+            window("main-room", "night", t(20, 0), Some(t(23, 0))),
+            window("booth", "set", t(21, 0), Some(t(22, 0))),
+        ];
+
+        let outcome = RecorderMatcher::new(5).match_session(t(21, 0), Some(t(22, 0)), &recordings, t(23, 0));
+        assert_eq!(matched_id(&outcome), "set");
+
+        // Two recordings that both need splitting still go to an admin
+        let recordings = vec![
+            window("main-room", "night", t(20, 0), Some(t(23, 0))),
+            window("backup", "night", t(20, 30), Some(t(23, 30))),
+        ];
+        let outcome = RecorderMatcher::new(5).match_session(t(21, 0), Some(t(22, 0)), &recordings, t(23, 30));
+        assert!(matches!(outcome, MatchOutcome::Ambiguous { .. }));
+    }
+
+    #[test]
+    fn test_small_overlap_and_open_intervals() {
+        let matcher = RecorderMatcher::new(0);
+
+        // 15 of 60 minutes is below the minimum coverage
+        let partial = vec![window("rec", "tail", t(20, 45), Some(t(22, 0)))];
+        assert!(matches!(matcher.match_session(t(20, 0), Some(t(21, 0)), &partial, t(23, 0)), MatchOutcome::NoMatch));
+
+        // A running set matches a recording that is still going
+        let running = vec![window("rec", "live", t(20, 0), None)];
+        let outcome = matcher.match_session(t(21, 0), None, &running, t(21, 30));
+        assert_eq!(matched_id(&outcome), "live");
+
+        // The tolerance absorbs a recorder clock a few minutes behind
+        let skewed = vec![window("rec", "late", t(20, 4), Some(t(20, 58)))];
+        assert!(matches!(matcher.match_session(t(20, 0), Some(t(21, 0)), &skewed, t(23, 0)), MatchOutcome::Matched { .. }));
+        let tolerant = RecorderMatcher::new(5);
+        if let MatchOutcome::Matched { candidate } = tolerant.match_session(t(20, 0), Some(t(21, 0)), &skewed, t(23, 0)) {
+            assert_eq!(candidate.coverage, 1.0);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_link_event_sessions() {
+        let app_state = setup_test_db().await;
+        let dj_service = DjService::new(app_state.clone());
+        let session_service = SessionService::new(app_state.clone());
+
+        let event_start = Utc::now() - Duration::hours(4);
+        let event = EventService::new(app_state.clone())
+            .start_event(StartEventRequest {
+                slot_duration_minutes: Some(60),
+                late_arrival_cutoff_hours: None,
+                started_at: Some(event_start),
+                late_penalty_curve: None,
+                name: None,
+            })
+            .await
+            .unwrap();
+
+        let mut session_ids = Vec::new();
+        for (i, name) in ["Alice", "Bob", "Carol", "Dave"].iter().enumerate() {
+            let dj = dj_service.register_dj(CreateDjRequest { name: name.to_string(), email: None }).await.unwrap();
+            let id = uuid::Uuid::new_v4().to_string();
+            let start = event_start + Duration::hours(i as i64);
+            sqlx::query("INSERT INTO sessions (id, dj_id, started_at, ended_at, upload_status, session_type) VALUES (?, ?, ?, ?, 'uploaded', 'solo')")
+                .bind(&id)
+                .bind(&dj.id)
+                .bind(start)
+                .bind(start + Duration::hours(1))
+                .execute(&app_state.db)
+                .await
+                .unwrap();
+            session_ids.push(id);
+        }
+        // Dave was linked by hand earlier
+        sqlx::query("UPDATE sessions SET recorder_session_id = 'manual', recorder_id = 'rec' WHERE id = ?")
+            .bind(&session_ids[3])
+            .execute(&app_state.db)
+            .await
+            .unwrap();
+
+        let recordings = vec![
+            // Alice and Bob share one recording
+            recording("main", "first-half", event_start, event_start + Duration::hours(2)),
+            // Two recorders both caught Carol along with Dave
+            recording("main", "second-half", event_start + Duration::hours(2), event_start + Duration::hours(4)),
+            recording("backup", "second-half", event_start + Duration::hours(2), event_start + Duration::hours(4)),
+        ];
+
+        let report = session_service
+            .link_event_sessions(&event.id, &recordings, None)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let linked: Vec<&str> = report.linked.iter().map(|r| r.session_id.as_str()).collect();
+        assert_eq!(linked, vec![session_ids[0].as_str(), session_ids[1].as_str()]);
+        assert_eq!(report.ambiguous.len(), 1);
+        assert_eq!(report.ambiguous[0].session_id, session_ids[2]);
+        assert_eq!(report.ambiguous[0].dj_name, "Carol");
+        assert!(report.unmatched.is_empty());
+        assert_eq!(report.already_linked, vec![session_ids[3].clone()]);
+
+        for (session_id, expected) in [(&session_ids[0], Some("first-half")), (&session_ids[1], Some("first-half")), (&session_ids[2], None), (&session_ids[3], Some("manual"))] {
+            let row = sqlx::query("SELECT recorder_session_id, recorder_ogg_url FROM sessions WHERE id = ?")
+                .bind(session_id)
+                .fetch_one(&app_state.db)
+                .await
+                .unwrap();
+            assert_eq!(row.get::<Option<String>, _>("recorder_session_id").as_deref(), expected);
+            if expected == Some("first-half") {
+                assert_eq!(
+                    row.get::<Option<String>, _>("recorder_ogg_url").as_deref(),
+                    Some("http://minio/main/first-half/data.ogg")
+                );
+            }
+        }
+
+        assert!(session_service.link_event_sessions("missing", &recordings, None).await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,296 @@
+use session_recorder_addon::{
+    models::{
+        AppState,
+        dj::CreateDjRequest,
+        recorder_webhook::{RecorderWebhookError, RecorderWebhookPayload},
+    },
+    services::{DjService, DownloadService, RecorderCatalog, RecorderIndexService, RecorderSession, RecorderWebhookService, SessionFiles},
+};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Utc};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use sqlx::{Row, SqlitePool};
+use std::sync::Mutex;
+use std::sync::Arc;
+
+mod common;
+
+#[cfg(test)]
+mod recorder_webhook_tests {
+    use super::*;
+
+    const SECRET: &str = "shared-secret";
+
+    async fn setup_test_db(webhook_secret: Option<&str>) -> Arc<AppState> {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+
+        let config = session_recorder_addon::models::AppConfig {
+            session_recorder_config: session_recorder_addon::models::SessionRecorderIntegrationConfig {
+                webhook_secret: webhook_secret.map(str::to_string),
+                ..common::recorder_config()
+            },
+            ..common::test_config()
+        };
+
+        Arc::new(AppState { db: pool, config })
+    }
+
+    /// Recorder bucket holding whatever the test put there
+    #[derive(Default)]
+    struct FakeCatalog {
+        sessions: Mutex<Vec<RecorderSession>>,
+    }
+
+    impl FakeCatalog {
+        fn put(&self, session: RecorderSession) {
+            let mut sessions = self.sessions.lock().unwrap();
+            sessions.retain(|s| !(s.recorder_id == session.recorder_id && s.id == session.id));
+            sessions.push(session);
+        }
+    }
+
+    impl RecorderCatalog for FakeCatalog {
+        async fn list_recorders(&self) -> Result<Vec<String>> {
+            Ok(self.sessions.lock().unwrap().iter().map(|s| s.recorder_id.clone()).collect())
+        }
+
+        async fn list_session_ids(&self, recorder_id: &str) -> Result<Vec<String>> {
+            Ok(self.sessions.lock().unwrap().iter().filter(|s| s.recorder_id == recorder_id).map(|s| s.id.clone()).collect())
+        }
+
+        async fn session_details(&self, recorder_id: &str, session_id: &str) -> Result<RecorderSession> {
+            self.sessions
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|s| s.recorder_id == recorder_id && s.id == session_id)
+                .cloned()
+                .ok_or_else(|| anyhow!("no such session"))
+        }
+    }
+
+    fn recording(recorder: &str, id: &str, start: DateTime<Utc>, end: Option<DateTime<Utc>>) -> RecorderSession {
+        RecorderSession {
+            id: id.to_string(),
+            recorder_id: recorder.to_string(),
+            name: id.to_string(),
+            start_time: start,
+            end_time: end,
+            duration_seconds: end.map(|end| (end - start).num_seconds()),
+            is_closed: end.is_some(),
+            keep: false,
+            files: SessionFiles {
+                ogg_url: Some(format!("http://minio/{}/sessions/{}/data.ogg", recorder, id)),
+                flac_url: end.map(|_| format!("http://minio/{}/sessions/{}/data.flac", recorder, id)),
+                waveform_url: None,
+                overview_png_url: None,
+                metadata_url: None,
+            },
+        }
+    }
+
+    fn sign(body: &[u8]) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(SECRET.as_bytes()).unwrap();
+        mac.update(body);
+        format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+    }
+
+    fn event(kind: &str, recorder: &str, session: &str) -> RecorderWebhookPayload {
+        serde_json::from_value(serde_json::json!({
+            "event": kind,
+            "recorder_id": recorder,
+            "session_id": session,
+        }))
+        .unwrap()
+    }
+
+    async fn insert_session(app_state: &Arc<AppState>, name: &str, start: DateTime<Utc>, end: Option<DateTime<Utc>>) -> String {
+        let dj = DjService::new(app_state.clone())
+            .register_dj(CreateDjRequest { name: name.to_string(), email: None })
+            .await
+            .unwrap();
+        let id = uuid::Uuid::new_v4().to_string();
+        sqlx::query("INSERT INTO sessions (id, dj_id, started_at, ended_at, upload_status, session_type) VALUES (?, ?, ?, ?, 'recording', 'solo')")
+            .bind(&id)
+            .bind(&dj.id)
+            .bind(start)
+            .bind(end)
+            .execute(&app_state.db)
+            .await
+            .unwrap();
+        id
+    }
+
+    #[tokio::test]
+    async fn test_signature_verification() {
+        let body = br#"{"event":"session_closed","recorder_id":"main","session_id":"a"}"#;
+
+        let service = RecorderWebhookService::new(setup_test_db(Some(SECRET)).await);
+        assert!(service.verify_signature(body, Some(&sign(body))).is_ok());
+
+        let error = |result: Result<()>| -> RecorderWebhookError { result.unwrap_err().downcast().unwrap() };
+        assert!(matches!(error(service.verify_signature(body, None)), RecorderWebhookError::InvalidSignature));
+        assert!(matches!(error(service.verify_signature(body, Some("sha256=zz"))), RecorderWebhookError::InvalidSignature));
+        let tampered = br#"{"event":"session_closed","recorder_id":"main","session_id":"b"}"#;
+        assert!(matches!(error(service.verify_signature(tampered, Some(&sign(body)))), RecorderWebhookError::InvalidSignature));
+        let bare = sign(body).trim_start_matches("sha256=").to_string();
+        assert!(matches!(error(service.verify_signature(body, Some(&bare))), RecorderWebhookError::InvalidSignature));
+
+        // Without a secret the endpoint refuses everything
+        let unconfigured = RecorderWebhookService::new(setup_test_db(None).await);
+        assert!(matches!(error(unconfigured.verify_signature(body, Some(&sign(body)))), RecorderWebhookError::NotConfigured));
+
+        // A bad payload is only reported once the signature checks out
+        let garbage = b"not json";
+        let result = service.handle_webhook(garbage, Some(&sign(garbage))).await;
+        assert!(matches!(result.unwrap_err().downcast().unwrap(), RecorderWebhookError::InvalidPayload(_)));
+    }
+
+    #[test]
+    fn test_payload_formats() {
+        let recorder = event("file_uploaded", "main", "2024-01-01T20-00");
+        assert_eq!(recorder.affected_sessions(), vec![("main".to_string(), "2024-01-01T20-00".to_string())]);
+
+        let bucket: RecorderWebhookPayload = serde_json::from_value(serde_json::json!({
+            "EventName": "s3:ObjectCreated:Put",
+            "Key": "session-recorder/main/sessions/night/data.flac",
+            "Records": [
+                { "s3": { "bucket": { "name": "session-recorder" }, "object": { "key": "main%2Fsessions%2Fnight%2Fdata.flac", "size": 1024 } } },
+                { "s3": { "object": { "key": "main/sessions/night/waveform.dat" } } },
+                { "s3": { "object": { "key": "side/sessions/my+set/data.ogg" } } },
+                { "s3": { "object": { "key": "metadata.json" } } },
+                { "s3": { "object": { "key": "main/sessions/" } } }
+            ]
+        }))
+        .unwrap();
+        assert_eq!(
+            bucket.affected_sessions(),
+            vec![("main".to_string(), "night".to_string()), ("side".to_string(), "my set".to_string())]
+        );
+
+        assert!(serde_json::from_value::<RecorderWebhookPayload>(serde_json::json!({ "event": "exploded" })).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_closed_recording_is_indexed_linked_and_delivered() {
+        let app_state = setup_test_db(Some(SECRET)).await;
+        let service = RecorderWebhookService::new(app_state.clone());
+        let index = RecorderIndexService::new(app_state.clone());
+        let catalog = FakeCatalog::default();
+
+        let start = Utc::now() - Duration::hours(2);
+        let session_id = insert_session(&app_state, "Alice", start, Some(start + Duration::hours(1))).await;
+        let unrelated = insert_session(&app_state, "Bob", start - Duration::hours(5), Some(start - Duration::hours(4))).await;
+
+        // Recording starts: indexed and linked, but nothing to deliver yet
+        catalog.put(recording("main", "set", start, None));
+        let report = service.process_payload(&catalog, &event("session_started", "main", "set")).await.unwrap();
+        assert_eq!(report.indexed, vec!["main/set".to_string()]);
+        assert_eq!(report.linked.len(), 1);
+        assert_eq!(report.linked[0].session_id, session_id);
+        assert!(report.deliveries_started.is_empty());
+        assert!(!index.get_session("main", "set").await.unwrap().unwrap().is_closed);
+
+        // Recording closes with its FLAC in place
+        catalog.put(recording("main", "set", start, Some(start + Duration::hours(1))));
+        let report = service.process_payload(&catalog, &event("session_closed", "main", "set")).await.unwrap();
+        assert!(report.linked.is_empty());
+        assert_eq!(report.deliveries_started, vec![session_id.clone()]);
+
+        // The link is issued in the background once the recording passed analysis
+        let mut row = None;
+        for _ in 0..100 {
+            let current = sqlx::query("SELECT download_link, upload_status, recorder_flac_url, recording_delivered_at FROM sessions WHERE id = ?")
+                .bind(&session_id)
+                .fetch_one(&app_state.db)
+                .await
+                .unwrap();
+            if current.get::<Option<String>, _>("download_link").is_some() {
+                row = Some(current);
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        let row = row.unwrap();
+        let flac = "http://minio/main/sessions/set/data.flac";
+        assert_eq!(row.get::<Option<String>, _>("recorder_flac_url").as_deref(), Some(flac));
+        // The DJ gets a link to our download proxy, not the bucket URL
+        let download_link: String = row.get("download_link");
+        assert!(download_link.starts_with("http://localhost:3000/dl/"));
+        let tokens = DownloadService::new(app_state.clone()).get_session_tokens(&session_id).await.unwrap();
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(tokens[0].url, download_link);
+        assert_eq!(row.get::<String, _>("upload_status"), "uploaded");
+        assert!(row.get::<Option<DateTime<Utc>>, _>("recording_delivered_at").is_some());
+
+        // Retried notifications don't deliver twice
+        let report = service.process_payload(&catalog, &event("file_uploaded", "main", "set")).await.unwrap();
+        assert!(report.deliveries_started.is_empty());
+
+        let untouched: Option<String> = sqlx::query("SELECT recorder_session_id FROM sessions WHERE id = ?")
+            .bind(&unrelated)
+            .fetch_one(&app_state.db)
+            .await
+            .unwrap()
+            .get("recorder_session_id");
+        assert!(untouched.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_failed_delivery_can_be_retried() {
+        let healthy = setup_test_db(Some(SECRET)).await;
+        // A link lifetime the download service refuses makes issuing the link fail
+        let mut config = healthy.config.clone();
+        config.download_config.link_ttl_days = 0;
+        let broken = Arc::new(AppState { db: healthy.db.clone(), config });
+        let catalog = FakeCatalog::default();
+
+        let start = Utc::now() - Duration::hours(2);
+        let session_id = insert_session(&healthy, "Alice", start, Some(start + Duration::hours(1))).await;
+        catalog.put(recording("main", "set", start, Some(start + Duration::hours(1))));
+
+        let service = RecorderWebhookService::new(broken);
+        let report = service.process_payload(&catalog, &event("session_closed", "main", "set")).await.unwrap();
+        assert_eq!(report.deliveries_started, vec![session_id.clone()]);
+
+        // The claim is given up in the background once the link could not be issued
+        let mut delivered_at: Option<DateTime<Utc>> = Some(Utc::now());
+        for _ in 0..100 {
+            delivered_at = sqlx::query("SELECT recording_delivered_at FROM sessions WHERE id = ?")
+                .bind(&session_id)
+                .fetch_one(&healthy.db)
+                .await
+                .unwrap()
+                .get("recording_delivered_at");
+            if delivered_at.is_none() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        assert!(delivered_at.is_none());
+
+        let service = RecorderWebhookService::new(healthy);
+        let report = service.process_payload(&catalog, &event("file_uploaded", "main", "set")).await.unwrap();
+        assert_eq!(report.deliveries_started, vec![session_id]);
+    }
+
+    #[tokio::test]
+    async fn test_all_night_recording_is_not_delivered_whole() {
+        let app_state = setup_test_db(Some(SECRET)).await;
+        let service = RecorderWebhookService::new(app_state.clone());
+        let catalog = FakeCatalog::default();
+
+        let start = Utc::now() - Duration::hours(4);
+        let first = insert_session(&app_state, "Alice", start, Some(start + Duration::hours(1))).await;
+        let second = insert_session(&app_state, "Bob", start + Duration::hours(1), Some(start + Duration::hours(2))).await;
+
+        catalog.put(recording("main", "night", start, Some(start + Duration::hours(3))));
+        let report = service.process_payload(&catalog, &event("session_closed", "main", "night")).await.unwrap();
+
+        let linked: Vec<&str> = report.linked.iter().map(|r| r.session_id.as_str()).collect();
+        assert_eq!(linked, vec![first.as_str(), second.as_str()]);
+        assert!(report.deliveries_started.is_empty());
+    }
+}
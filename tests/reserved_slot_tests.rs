@@ -0,0 +1,295 @@
+use session_recorder_addon::{
+    models::{
+        AppState,
+        dj::{CreateDjRequest, UpdateDjRequest},
+        event_session::StartEventRequest,
+        reserved_slot::{
+            assign_queue_positions, CreateReservedSlotRequest, ReservedSlotError, ReservedSlotKind, ReservedSlotResponse,
+        },
+    },
+    services::{DjService, EventService, LotteryService, ReservedSlotService},
+};
+use chrono::{Duration, Utc};
+use sqlx::SqlitePool;
+use std::sync::Arc;
+
+mod common;
+
+#[cfg(test)]
+mod reserved_slot_tests {
+    use super::*;
+
+    async fn setup_test_db() -> Arc<AppState> {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+
+        let config = session_recorder_addon::models::AppConfig {
+            ..common::test_config()
+        };
+
+        Arc::new(AppState { db: pool, config })
+    }
+
+    async fn register(app_state: &Arc<AppState>, name: &str) -> String {
+        let dj_service = DjService::new(app_state.clone());
+        dj_service.register_dj(CreateDjRequest {
+            name: name.to_string(),
+            email: None,
+        }).await.unwrap().id
+    }
+
+    fn booking(dj_name: &str, position: i32, kind: ReservedSlotKind) -> CreateReservedSlotRequest {
+        CreateReservedSlotRequest {
+            dj_id: None,
+            dj_name: Some(dj_name.to_string()),
+            position: Some(position),
+            starts_at: None,
+            kind,
+            label: None,
+        }
+    }
+
+    fn error(result: anyhow::Result<ReservedSlotResponse>) -> ReservedSlotError {
+        result.unwrap_err().downcast().unwrap()
+    }
+
+    async fn queue_names(app_state: &Arc<AppState>) -> Vec<(String, i32)> {
+        LotteryService::new(app_state.clone())
+            .get_current_queue()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|dj| (dj.name, dj.position_in_queue.unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn test_assign_queue_positions_skips_bookings() {
+        let floating = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        let reserved = vec![("h".to_string(), 2), ("w".to_string(), 1)];
+
+        let positions = assign_queue_positions(&floating, &reserved);
+
+        assert_eq!(positions, vec![
+            ("w".to_string(), 1),
+            ("h".to_string(), 2),
+            ("a".to_string(), 3),
+            ("b".to_string(), 4),
+            ("c".to_string(), 5),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_lottery_fills_open_slots_around_booking() {
+        let app_state = setup_test_db().await;
+        let lottery_service = LotteryService::new(app_state.clone());
+        let reserved_slot_service = ReservedSlotService::new(app_state.clone());
+
+        for name in ["Alice", "Bob", "Carol"] {
+            register(&app_state, name).await;
+        }
+        let slot = reserved_slot_service
+            .create_reserved_slot(booking("Headliner", 2, ReservedSlotKind::Headliner))
+            .await
+            .unwrap();
+        assert_eq!(slot.dj_name.as_deref(), Some("Headliner"));
+
+        // The booked DJ is not part of the lottery
+        assert_eq!(lottery_service.get_eligible_djs().await.unwrap().len(), 3);
+
+        let mut winners = Vec::new();
+        for _ in 0..3 {
+            winners.push(lottery_service.draw_next_dj().await.unwrap().unwrap().winner.name);
+        }
+
+        let queue = queue_names(&app_state).await;
+        assert_eq!(queue, vec![
+            (winners[0].clone(), 1),
+            ("Headliner".to_string(), 2),
+            (winners[1].clone(), 3),
+            (winners[2].clone(), 4),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_booking_moves_queued_djs_back() {
+        let app_state = setup_test_db().await;
+        let lottery_service = LotteryService::new(app_state.clone());
+        let reserved_slot_service = ReservedSlotService::new(app_state.clone());
+
+        for name in ["Alice", "Bob", "Carol"] {
+            let id = register(&app_state, name).await;
+            lottery_service.assign_next_position(&id).await.unwrap();
+        }
+
+        reserved_slot_service
+            .create_reserved_slot(booking("Closer", 2, ReservedSlotKind::Closing))
+            .await
+            .unwrap();
+
+        assert_eq!(queue_names(&app_state).await, vec![
+            ("Alice".to_string(), 1),
+            ("Closer".to_string(), 2),
+            ("Bob".to_string(), 3),
+            ("Carol".to_string(), 4),
+        ]);
+
+        // Removing a lottery DJ compacts around the booking
+        let alice = lottery_service.get_current_queue().await.unwrap()[0].id.clone();
+        lottery_service.remove_from_queue(&alice).await.unwrap();
+        assert_eq!(queue_names(&app_state).await, vec![
+            ("Bob".to_string(), 1),
+            ("Closer".to_string(), 2),
+            ("Carol".to_string(), 3),
+        ]);
+
+        // Moves keep the booking in place and cannot target it
+        let carol = lottery_service.get_current_queue().await.unwrap()[2].id.clone();
+        assert!(lottery_service.move_dj_position(&carol, 2).await.is_err());
+        lottery_service.move_dj_position(&carol, 1).await.unwrap();
+        assert_eq!(queue_names(&app_state).await, vec![
+            ("Carol".to_string(), 1),
+            ("Closer".to_string(), 2),
+            ("Bob".to_string(), 3),
+        ]);
+
+        // Updating a DJ's position moves them the same way
+        let dj_service = DjService::new(app_state.clone());
+        let update = |position| UpdateDjRequest {
+            name: None,
+            email: None,
+            weight: None,
+            is_active: None,
+            position_in_queue: Some(position),
+            keep_recording: None,
+        };
+        assert!(dj_service.update_dj(&carol, update(2)).await.is_err());
+        dj_service.update_dj(&carol, update(3)).await.unwrap();
+        assert_eq!(queue_names(&app_state).await, vec![
+            ("Bob".to_string(), 1),
+            ("Closer".to_string(), 2),
+            ("Carol".to_string(), 3),
+        ]);
+
+        // A reset clears the lottery slots only
+        lottery_service.reset_lottery().await.unwrap();
+        assert_eq!(queue_names(&app_state).await, vec![("Closer".to_string(), 2)]);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_booking_returns_dj_to_pool() {
+        let app_state = setup_test_db().await;
+        let lottery_service = LotteryService::new(app_state.clone());
+        let reserved_slot_service = ReservedSlotService::new(app_state.clone());
+
+        let alice = register(&app_state, "Alice").await;
+        lottery_service.assign_next_position(&alice).await.unwrap();
+        let slot = reserved_slot_service
+            .create_reserved_slot(booking("Warm-up", 1, ReservedSlotKind::WarmUp))
+            .await
+            .unwrap();
+        assert_eq!(queue_names(&app_state).await[1], ("Alice".to_string(), 2));
+
+        reserved_slot_service.delete_reserved_slot(&slot.id).await.unwrap();
+
+        assert_eq!(queue_names(&app_state).await, vec![("Alice".to_string(), 1)]);
+        let pool = lottery_service.get_eligible_djs().await.unwrap();
+        assert_eq!(pool.len(), 1);
+        assert_eq!(pool[0].name, "Warm-up");
+        assert!(reserved_slot_service.get_reserved_slots().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_booking_validation() {
+        let app_state = setup_test_db().await;
+        let reserved_slot_service = ReservedSlotService::new(app_state.clone());
+
+        reserved_slot_service.create_reserved_slot(booking("Headliner", 3, ReservedSlotKind::Headliner)).await.unwrap();
+
+        let taken = reserved_slot_service.create_reserved_slot(booking("Other", 3, ReservedSlotKind::Other)).await;
+        assert!(matches!(error(taken), ReservedSlotError::PositionTaken(3)));
+
+        let zero = reserved_slot_service.create_reserved_slot(booking("Other", 0, ReservedSlotKind::Other)).await;
+        assert!(matches!(error(zero), ReservedSlotError::InvalidPosition));
+
+        let mut no_dj = booking("Other", 4, ReservedSlotKind::Other);
+        no_dj.dj_name = None;
+        assert!(matches!(error(reserved_slot_service.create_reserved_slot(no_dj).await), ReservedSlotError::MissingDj));
+
+        let mut by_time = booking("Other", 4, ReservedSlotKind::Other);
+        by_time.position = None;
+        by_time.starts_at = Some(Utc::now());
+        assert!(matches!(error(reserved_slot_service.create_reserved_slot(by_time).await), ReservedSlotError::NoActiveEvent));
+    }
+
+    #[tokio::test]
+    async fn test_booking_by_time_and_event_scheduling() {
+        let app_state = setup_test_db().await;
+        let event_service = EventService::new(app_state.clone());
+        let reserved_slot_service = ReservedSlotService::new(app_state.clone());
+
+        let warm_up = register(&app_state, "Warm-up").await;
+        register(&app_state, "Alice").await;
+        reserved_slot_service.create_reserved_slot(CreateReservedSlotRequest {
+            dj_id: Some(warm_up.clone()),
+            dj_name: None,
+            position: Some(1),
+            starts_at: None,
+            kind: ReservedSlotKind::WarmUp,
+            label: Some("Opening".to_string()),
+        }).await.unwrap();
+
+        let started_at = Utc::now() - Duration::minutes(10);
+        event_service.start_event(StartEventRequest {
+            slot_duration_minutes: Some(60),
+            late_arrival_cutoff_hours: None,
+            started_at: Some(started_at),
+            late_penalty_curve: None,
+            name: None,
+        }).await.unwrap();
+
+        // The opening slot is booked, so no first draw happened
+        assert_eq!(queue_names(&app_state).await, vec![("Warm-up".to_string(), 1)]);
+
+        // 22:00 at a 20:00 start with one hour slots is the third slot
+        let headliner = reserved_slot_service.create_reserved_slot(CreateReservedSlotRequest {
+            dj_id: None,
+            dj_name: Some("Headliner".to_string()),
+            position: None,
+            starts_at: Some(started_at + Duration::hours(2)),
+            kind: ReservedSlotKind::Headliner,
+            label: None,
+        }).await.unwrap();
+        assert_eq!(headliner.position, 3);
+
+        let timetable = event_service.get_timetable().await.unwrap().unwrap();
+        let booked: Vec<i32> = timetable.entries.iter().filter(|e| e.reserved_kind.is_some()).map(|e| e.position).collect();
+        assert_eq!(booked, vec![1, 3]);
+        assert_eq!(timetable.entries[0].reserved_label.as_deref(), Some("Opening"));
+        assert_eq!(timetable.entries[1].started_at, started_at + Duration::hours(2));
+
+        // Warm-up plays; the next slot is open so the scheduler draws
+        event_service.start_next_dj_slot(warm_up.clone()).await.unwrap();
+        sqlx::query("UPDATE event_sessions SET next_draw_at = ?")
+            .bind(Utc::now() - Duration::minutes(1))
+            .execute(&app_state.db)
+            .await
+            .unwrap();
+        assert!(event_service.check_and_trigger_auto_draw().await.unwrap());
+        LotteryService::new(app_state.clone()).draw_next_dj().await.unwrap().unwrap();
+
+        // Alice plays slot 2; slot 3 is the headliner's, so no draw
+        let alice = LotteryService::new(app_state.clone()).get_current_queue().await.unwrap()[1].id.clone();
+        event_service.start_next_dj_slot(alice).await.unwrap();
+        sqlx::query("UPDATE event_sessions SET next_draw_at = ?")
+            .bind(Utc::now() - Duration::minutes(1))
+            .execute(&app_state.db)
+            .await
+            .unwrap();
+        assert!(!event_service.check_and_trigger_auto_draw().await.unwrap());
+
+        // Booking a slot that has already been played is refused
+        let passed = reserved_slot_service.create_reserved_slot(booking("Late", 2, ReservedSlotKind::Other)).await;
+        assert!(matches!(error(passed), ReservedSlotError::PositionPassed(2)));
+    }
+}
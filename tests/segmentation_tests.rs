@@ -0,0 +1,320 @@
+use session_recorder_addon::{
+    audio::{split_recording, AudioDecoder, AudioSpec, FlacEncoder, SegmentRange},
+    models::{
+        AppState,
+        dj::CreateDjRequest,
+        event_session::StartEventRequest,
+        segmentation::{SegmentRecordingRequest, SegmentationError},
+    },
+    services::{DjService, EventService, SegmentationService},
+};
+use chrono::{DateTime, Duration, Utc};
+use sqlx::{Row, SqlitePool};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+mod common;
+
+#[cfg(test)]
+mod segmentation_tests {
+    use super::*;
+
+    const RATE: u32 = 8_000;
+
+    async fn setup_test_db(output_dir: &Path) -> Arc<AppState> {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+
+        let config = session_recorder_addon::models::AppConfig {
+            storage_config: session_recorder_addon::models::StorageConfig::Local {
+                root: output_dir.with_file_name("storage"),
+                public_url: None,
+            },
+            segmentation_config: session_recorder_addon::models::SegmentationConfig {
+                pre_roll_seconds: 1,
+                post_roll_seconds: 1,
+                output_dir: output_dir.to_path_buf(),
+            },
+            ..common::test_config()
+        };
+
+        Arc::new(AppState { db: pool, config })
+    }
+
+    async fn register(app_state: &Arc<AppState>, name: &str) -> String {
+        let dj_service = DjService::new(app_state.clone());
+        dj_service.register_dj(CreateDjRequest {
+            name: name.to_string(),
+            email: None,
+        }).await.unwrap().id
+    }
+
+    async fn insert_session(
+        app_state: &Arc<AppState>,
+        dj_id: &str,
+        started_at: DateTime<Utc>,
+        ended_at: Option<DateTime<Utc>>,
+    ) -> String {
+        let id = uuid::Uuid::new_v4().to_string();
+        sqlx::query("INSERT INTO sessions (id, dj_id, started_at, ended_at, upload_status, session_type) VALUES (?, ?, ?, ?, 'uploaded', 'solo')")
+            .bind(&id)
+            .bind(dj_id)
+            .bind(started_at)
+            .bind(ended_at)
+            .execute(&app_state.db)
+            .await
+            .unwrap();
+        id
+    }
+
+    /// A sine per channel with a little deterministic noise, silence in the middle
+    fn synthetic_samples(spec: AudioSpec, frames: usize) -> Vec<i32> {
+        let amplitude = ((1i64 << (spec.bits_per_sample - 1)) - 1) as f64 * 0.5;
+        let mut noise = 12345u32;
+        let mut samples = Vec::with_capacity(frames * spec.channels as usize);
+        for frame in 0..frames {
+            for channel in 0..spec.channels {
+                noise = noise.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                let silent = (frames / 2..frames / 2 + 1_000).contains(&frame);
+                let value = if silent {
+                    0.0
+                } else {
+                    let freq = 440.0 * (channel + 1) as f64;
+                    let t = frame as f64 / spec.sample_rate as f64;
+                    amplitude * (2.0 * std::f64::consts::PI * freq * t).sin() + ((noise >> 16) % 64) as f64 - 32.0
+                };
+                samples.push(value.round() as i32);
+            }
+        }
+        samples
+    }
+
+    fn write_flac(path: &Path, spec: AudioSpec, samples: &[i32]) {
+        let mut encoder = FlacEncoder::new(BufWriter::new(File::create(path).unwrap()), spec).unwrap();
+        // Odd chunk sizes exercise the block buffering
+        for chunk in samples.chunks(spec.channels as usize * 1_234) {
+            encoder.write_interleaved(chunk).unwrap();
+        }
+        encoder.finish().unwrap();
+    }
+
+    fn decode_all(path: &Path) -> (AudioSpec, Vec<i32>) {
+        let mut decoder = AudioDecoder::open(path).unwrap();
+        let mut samples = Vec::new();
+        while let Some(chunk) = decoder.next_chunk().unwrap() {
+            samples.extend(chunk);
+        }
+        (decoder.spec(), samples)
+    }
+
+    fn stereo() -> AudioSpec {
+        AudioSpec { sample_rate: RATE, channels: 2, bits_per_sample: 16 }
+    }
+
+    #[test]
+    fn test_flac_round_trip_is_lossless() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tone.flac");
+        let spec = stereo();
+        let samples = synthetic_samples(spec, 10_000);
+
+        write_flac(&path, spec, &samples);
+        let (decoded_spec, decoded) = decode_all(&path);
+
+        assert_eq!(decoded_spec, spec);
+        assert_eq!(decoded, samples);
+    }
+
+    #[test]
+    fn test_flac_round_trip_24_bit_noise() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("noise.flac");
+        let spec = AudioSpec { sample_rate: 44_100, channels: 1, bits_per_sample: 24 };
+
+        // Full-scale noise doesn't predict, so it ends up in verbatim subframes
+        let mut state = 99u64;
+        let samples: Vec<i32> = (0..5_000)
+            .map(|_| {
+                state = state.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+                ((state >> 40) as i32) - (1 << 23)
+            })
+            .collect();
+
+        write_flac(&path, spec, &samples);
+        let (decoded_spec, decoded) = decode_all(&path);
+
+        assert_eq!(decoded_spec, spec);
+        assert_eq!(decoded, samples);
+    }
+
+    #[test]
+    fn test_split_recording_cuts_overlapping_ranges() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("night.flac");
+        let spec = stereo();
+        let samples = synthetic_samples(spec, 10 * RATE as usize);
+        write_flac(&source, spec, &samples);
+
+        let range = |id: &str, start_ms: u64, end_ms: Option<u64>| SegmentRange {
+            id: id.to_string(),
+            start_ms,
+            end_ms,
+            output_path: dir.path().join(format!("{}.flac", id)),
+        };
+        let ranges = vec![
+            range("first", 0, Some(4_000)),
+            range("second", 3_500, None),
+            range("late", 20_000, Some(25_000)),
+        ];
+
+        let result = split_recording(&source, &ranges).unwrap();
+
+        assert_eq!(result.total_frames, 10 * RATE as u64);
+        let ids: Vec<&str> = result.segments.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, vec!["first", "second"]);
+
+        let first = &result.segments[0];
+        assert_eq!((first.start_frame, first.frames), (0, 4 * RATE as u64));
+        let second = &result.segments[1];
+        assert_eq!((second.start_frame, second.frames), (3_500 * RATE as u64 / 1000, 6_500 * RATE as u64 / 1000));
+
+        let (_, decoded) = decode_all(&second.output_path);
+        let offset = second.start_frame as usize * 2;
+        assert_eq!(decoded, samples[offset..].to_vec());
+        assert!(!dir.path().join("late.flac").exists());
+    }
+
+    #[tokio::test]
+    async fn test_segment_recording_attaches_files_to_sessions() {
+        let dir = tempfile::tempdir().unwrap();
+        let output_dir = dir.path().join("segments");
+        let app_state = setup_test_db(&output_dir).await;
+
+        let source = dir.path().join("data.flac");
+        let spec = stereo();
+        write_flac(&source, spec, &synthetic_samples(spec, 10 * RATE as usize));
+
+        let recording_started_at = Utc::now() - Duration::hours(1);
+        let at = |seconds: i64| recording_started_at + Duration::seconds(seconds);
+
+        let alice = register(&app_state, "Alice").await;
+        let bob = register(&app_state, "Bob").await;
+        let carol = register(&app_state, "Carol").await;
+
+        // A set from last night that was never ended
+        insert_session(&app_state, &bob, at(-86_400), None).await;
+        EventService::new(app_state.clone()).start_event(StartEventRequest {
+            slot_duration_minutes: None,
+            late_arrival_cutoff_hours: None,
+            started_at: Some(at(-900)),
+            late_penalty_curve: None,
+            name: None,
+        }).await.unwrap();
+
+        insert_session(&app_state, &carol, at(-600), Some(at(-300))).await; // before the recording
+        let alice_session = insert_session(&app_state, &alice, at(2), Some(at(5))).await;
+        let bob_session = insert_session(&app_state, &bob, at(5), None).await;
+        insert_session(&app_state, &carol, at(60), Some(at(120))).await; // after the recording
+
+        let service = SegmentationService::new(app_state.clone());
+        let report = service.segment_recording(SegmentRecordingRequest {
+            file_path: Some(source.to_string_lossy().to_string()),
+            recording_started_at: Some(recording_started_at),
+            ..Default::default()
+        }).await.unwrap();
+
+        assert_eq!(report.recording_duration_seconds, 10.0);
+        assert_eq!((report.pre_roll_seconds, report.post_roll_seconds), (1, 1));
+        assert!(report.skipped_session_ids.is_empty());
+        assert_eq!(report.segments.len(), 2);
+
+        // Alice 00:02-00:05 with a second of roll either side
+        let alice_segment = &report.segments[0];
+        assert_eq!(alice_segment.session_id, alice_session);
+        assert_eq!(alice_segment.dj_name, "Alice");
+        assert_eq!((alice_segment.offset_seconds, alice_segment.duration_seconds), (1.0, 5.0));
+
+        // Bob is still playing, so his segment runs to the end of the recording
+        let bob_segment = &report.segments[1];
+        assert_eq!(bob_segment.session_id, bob_session);
+        assert_eq!((bob_segment.offset_seconds, bob_segment.duration_seconds), (4.0, 6.0));
+
+        for segment in &report.segments {
+            let path = PathBuf::from(&segment.file_path);
+            assert!(path.starts_with(&output_dir));
+            assert_eq!(path.extension().unwrap(), "flac");
+
+            let (_, decoded) = decode_all(&path);
+            assert_eq!(decoded.len() as f64, segment.duration_seconds * RATE as f64 * 2.0);
+
+            let stored: Option<String> = sqlx::query("SELECT file_path FROM sessions WHERE id = ?")
+                .bind(&segment.session_id)
+                .fetch_one(&app_state.db)
+                .await
+                .unwrap()
+                .get("file_path");
+            assert_eq!(stored.as_deref(), Some(segment.file_path.as_str()));
+        }
+
+        // Alice's finished set is published to storage; Bob's is still being played
+        let links: Vec<Option<String>> = sqlx::query("SELECT download_link FROM sessions WHERE id IN (?, ?) ORDER BY started_at ASC")
+            .bind(&alice_session)
+            .bind(&bob_session)
+            .fetch_all(&app_state.db)
+            .await
+            .unwrap()
+            .iter()
+            .map(|row| row.get("download_link"))
+            .collect();
+        assert!(links[0].as_ref().unwrap().starts_with(&format!("file://{}", dir.path().join("storage").display())));
+        assert!(links[1].is_none());
+
+        // Per-request roll overrides the configured default
+        let report = service.segment_recording(SegmentRecordingRequest {
+            file_path: Some(source.to_string_lossy().to_string()),
+            recording_started_at: Some(recording_started_at),
+            pre_roll_seconds: Some(0),
+            post_roll_seconds: Some(0),
+            ..Default::default()
+        }).await.unwrap();
+        assert_eq!((report.segments[0].offset_seconds, report.segments[0].duration_seconds), (2.0, 3.0));
+    }
+
+    #[tokio::test]
+    async fn test_segment_recording_validation() {
+        let dir = tempfile::tempdir().unwrap();
+        let app_state = setup_test_db(dir.path()).await;
+        let service = SegmentationService::new(app_state);
+
+        let error = |result: anyhow::Result<_>| -> SegmentationError { result.unwrap_err().downcast().unwrap() };
+
+        let missing = service.segment_recording(SegmentRecordingRequest::default()).await;
+        assert!(matches!(error(missing), SegmentationError::MissingSource));
+
+        let source = dir.path().join("data.flac");
+        write_flac(&source, stereo(), &synthetic_samples(stereo(), RATE as usize));
+        let no_start = service.segment_recording(SegmentRecordingRequest {
+            file_path: Some(source.to_string_lossy().to_string()),
+            ..Default::default()
+        }).await;
+        assert!(matches!(error(no_start), SegmentationError::MissingStartTime));
+
+        let not_found = service.segment_recording(SegmentRecordingRequest {
+            file_path: Some(dir.path().join("missing.flac").to_string_lossy().to_string()),
+            recording_started_at: Some(Utc::now()),
+            ..Default::default()
+        }).await;
+        assert!(matches!(error(not_found), SegmentationError::FileNotFound));
+
+        // A path in an HTTP request body is dropped, so the server's files cannot be read
+        let from_http: SegmentRecordingRequest = serde_json::from_value(serde_json::json!({
+            "file_path": source.to_string_lossy(),
+            "recording_started_at": Utc::now(),
+        })).unwrap();
+        assert!(from_http.file_path.is_none());
+        let from_http = service.segment_recording(from_http).await;
+        assert!(matches!(error(from_http), SegmentationError::MissingSource));
+    }
+}
@@ -9,6 +9,8 @@ use session_recorder_addon::{
 use sqlx::SqlitePool;
 use std::sync::Arc;
 
+mod common;
+
 #[cfg(test)]
 mod session_tests {
     use super::*;
@@ -18,16 +20,7 @@ mod session_tests {
         sqlx::migrate!("./migrations").run(&pool).await.unwrap();
         
         let config = session_recorder_addon::models::AppConfig {
-            database_url: "sqlite::memory:".to_string(),
-            cloud_storage_url: None,
-            email_config: session_recorder_addon::models::EmailConfig {
-                smtp_server: "localhost".to_string(),
-                smtp_port: 587,
-                username: "test".to_string(),
-                password: "test".to_string(),
-                from_address: "test@example.com".to_string(),
-            },
-            lottery_config: session_recorder_addon::models::LotteryConfig::default(),
+            ..common::test_config()
         };
         
         Arc::new(AppState { db: pool, config })
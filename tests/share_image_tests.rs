@@ -0,0 +1,192 @@
+use session_recorder_addon::{
+    models::{
+        AppState,
+        dj::CreateDjRequest,
+        event_session::StartEventRequest,
+        BrandingConfig, CalendarConfig, CreateDownloadTokenRequest, ShareImageError, ShareImageRequest, ShareTemplate,
+    },
+    services::{DjService, DownloadService, EventService, LotteryService, NowPlayingService, ShareImageService},
+};
+use chrono::{DateTime, Duration, Utc};
+use image::RgbImage;
+use sqlx::sqlite::SqlitePoolOptions;
+use std::path::Path;
+use std::sync::Arc;
+
+mod common;
+
+#[cfg(test)]
+mod share_image_tests {
+    use super::*;
+
+    async fn setup_test_db(dir: &Path) -> Arc<AppState> {
+        let pool = SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+
+        let config = session_recorder_addon::models::AppConfig {
+            storage_config: common::local_storage(dir),
+            calendar_config: CalendarConfig {
+                timezone: Some(chrono_tz::Europe::Berlin),
+                location: None,
+            },
+            branding_config: BrandingConfig {
+                accent_color: "#00aaff".to_string(),
+                footer: Some("Doors close at 5".to_string()),
+                registration_url: Some("https://slotify.example/register".to_string()),
+                ..BrandingConfig::default()
+            },
+            ..common::test_config()
+        };
+
+        Arc::new(AppState { db: pool, config })
+    }
+
+    async fn register(app_state: &Arc<AppState>, name: &str) -> String {
+        DjService::new(app_state.clone()).register_dj(CreateDjRequest {
+            name: name.to_string(),
+            email: None,
+        }).await.unwrap().id
+    }
+
+    /// Started half an hour ago; draws the opening DJ
+    async fn start_event(app_state: &Arc<AppState>) -> String {
+        EventService::new(app_state.clone()).start_event(StartEventRequest {
+            slot_duration_minutes: Some(60),
+            late_arrival_cutoff_hours: None,
+            started_at: Some(Utc::now() - Duration::minutes(30)),
+            late_penalty_curve: None,
+            name: Some("Friday Night".to_string()),
+        }).await.unwrap().id
+    }
+
+    async fn start_session(app_state: &Arc<AppState>, dj_id: &str, started_at: DateTime<Utc>) -> String {
+        let session_id = uuid::Uuid::new_v4().to_string();
+        sqlx::query(
+            "INSERT INTO sessions (id, dj_id, started_at, upload_status, session_type) VALUES (?, ?, ?, 'recording', 'solo')"
+        )
+        .bind(&session_id)
+        .bind(dj_id)
+        .bind(started_at)
+        .execute(&app_state.db)
+        .await
+        .unwrap();
+        session_id
+    }
+
+    async fn render(app_state: &Arc<AppState>, event_id: &str, request: ShareImageRequest) -> anyhow::Result<RgbImage> {
+        let png = ShareImageService::new(app_state.clone()).render(event_id, request).await?;
+        assert!(png.starts_with(b"\x89PNG"));
+        Ok(image::load_from_memory(&png).unwrap().to_rgb8())
+    }
+
+    /// Share of white pixels in the bottom right square the QR code goes in
+    fn qr_coverage(image: &RgbImage) -> f32 {
+        let (width, height) = image.dimensions();
+        let (mut white, mut total) = (0, 0);
+        for y in height - 320..height - 80 {
+            for x in width - 320..width - 80 {
+                total += 1;
+                if image.get_pixel(x, y).0 == [255, 255, 255] {
+                    white += 1;
+                }
+            }
+        }
+        white as f32 / total as f32
+    }
+
+    fn assert_error(error: anyhow::Error, expected: ShareImageError) {
+        let actual = error.downcast_ref::<ShareImageError>().unwrap();
+        assert_eq!(std::mem::discriminant(actual), std::mem::discriminant(&expected));
+    }
+
+    #[tokio::test]
+    async fn test_lineup_poster() {
+        let dir = tempfile::tempdir().unwrap();
+        let app_state = setup_test_db(dir.path()).await;
+        // How many rows fit is covered by the layout's unit test; a short lineup keeps this quick
+        for i in 0..4 {
+            register(&app_state, &format!("DJ Number {}", i)).await;
+        }
+        let event_id = start_event(&app_state).await;
+        let lottery_service = LotteryService::new(app_state.clone());
+        for _ in 0..3 {
+            lottery_service.draw_next_dj().await.unwrap().unwrap();
+        }
+
+        let poster = render(&app_state, &event_id, ShareImageRequest::default()).await.unwrap();
+        assert_eq!(poster.dimensions(), (1080, 1350));
+        assert_eq!(poster.get_pixel(540, 4).0, [0, 170, 255]);
+        assert!(qr_coverage(&poster) > 0.3);
+
+        let without_qr = render(&app_state, &event_id, ShareImageRequest {
+            qr: Some(false),
+            ..ShareImageRequest::default()
+        }).await.unwrap();
+        assert_eq!(qr_coverage(&without_qr), 0.0);
+
+        let missing = render(&app_state, "missing", ShareImageRequest::default()).await.unwrap_err();
+        assert_error(missing, ShareImageError::EventNotFound);
+    }
+
+    #[tokio::test]
+    async fn test_now_playing_story() {
+        let dir = tempfile::tempdir().unwrap();
+        let app_state = setup_test_db(dir.path()).await;
+        let alice = register(&app_state, "Alice").await;
+        let event_id = start_event(&app_state).await;
+        let request = ShareImageRequest {
+            template: ShareTemplate::NowPlaying,
+            ..ShareImageRequest::default()
+        };
+
+        let nothing = render(&app_state, &event_id, request.clone()).await.unwrap_err();
+        assert_error(nothing, ShareImageError::NothingPlaying);
+
+        let started_at = Utc::now() - Duration::minutes(5);
+        EventService::new(app_state.clone()).start_next_dj_slot(alice.clone()).await.unwrap();
+        start_session(&app_state, &alice, started_at).await;
+        NowPlayingService::new(app_state.clone())
+            .record_track(Some("Floorplan"), "Never Grow Old", started_at + Duration::minutes(1))
+            .await
+            .unwrap();
+
+        let story = render(&app_state, &event_id, request).await.unwrap();
+        assert_eq!(story.dimensions(), (1080, 1920));
+        assert!(qr_coverage(&story) > 0.3);
+    }
+
+    #[tokio::test]
+    async fn test_set_online_card_needs_the_sets_token() {
+        let dir = tempfile::tempdir().unwrap();
+        let app_state = setup_test_db(dir.path()).await;
+        let alice = register(&app_state, "Alice").await;
+        let earlier = start_session(&app_state, &alice, Utc::now() - Duration::days(7)).await;
+        let event_id = start_event(&app_state).await;
+        let session_id = start_session(&app_state, &alice, Utc::now() - Duration::minutes(20)).await;
+
+        let download_service = DownloadService::new(app_state.clone());
+        let token = download_service.issue_token(&session_id, CreateDownloadTokenRequest::default()).await.unwrap();
+        let set_online = |token: Option<String>| ShareImageRequest {
+            template: ShareTemplate::SetOnline,
+            token,
+            qr: None,
+        };
+
+        let card = render(&app_state, &event_id, set_online(Some(token.token.token.clone()))).await.unwrap();
+        assert_eq!(card.dimensions(), (1080, 1080));
+        assert!(qr_coverage(&card) > 0.3);
+
+        let required = render(&app_state, &event_id, set_online(None)).await.unwrap_err();
+        assert_error(required, ShareImageError::TokenRequired);
+        let unknown = render(&app_state, &event_id, set_online(Some("nope".to_string()))).await.unwrap_err();
+        assert_error(unknown, ShareImageError::UnknownToken);
+
+        // A set from another night, or a revoked link, doesn't make a card for this event
+        let other_night = download_service.issue_token(&earlier, CreateDownloadTokenRequest::default()).await.unwrap();
+        let other = render(&app_state, &event_id, set_online(Some(other_night.token.token))).await.unwrap_err();
+        assert_error(other, ShareImageError::UnknownToken);
+        download_service.revoke_token(&token.token.token).await.unwrap();
+        let revoked = render(&app_state, &event_id, set_online(Some(token.token.token))).await.unwrap_err();
+        assert_error(revoked, ShareImageError::UnknownToken);
+    }
+}
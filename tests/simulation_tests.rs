@@ -0,0 +1,168 @@
+use session_recorder_addon::models::{
+    dj::Dj,
+    lottery::{LotteryConfig, LotteryEngine},
+    simulation::{
+        chi_square_p_value, gini_coefficient, HistoricalDjFairness, HistoricalFairness, LotterySimulator,
+        SimulationError,
+    },
+};
+use std::collections::HashMap;
+use chrono::Utc;
+
+#[cfg(test)]
+mod simulation_tests {
+    use super::*;
+
+    fn create_test_dj(name: &str, weight: f64) -> Dj {
+        let mut dj = Dj::new(name.to_string(), None);
+        dj.registered_at = Utc::now();
+        dj.weight = weight;
+        dj
+    }
+
+    fn historical(name: &str, observed_wins: i64, expected_wins: f64) -> HistoricalDjFairness {
+        HistoricalDjFairness {
+            dj_id: name.to_string(),
+            dj_name: Some(name.to_string()),
+            draws_entered: 10,
+            observed_wins,
+            expected_wins,
+        }
+    }
+
+    #[test]
+    fn test_same_seed_gives_same_result() {
+        let engine = LotteryEngine::new(LotteryConfig::default());
+        let simulator = LotterySimulator::new(&engine);
+        let djs = vec![create_test_dj("Alice", 1.0), create_test_dj("Bob", 2.0), create_test_dj("Carol", 0.5)];
+
+        let first = simulator.run(&djs, None, &HashMap::new(), 500, 2, 42).unwrap();
+        let second = simulator.run(&djs, None, &HashMap::new(), 500, 2, 42).unwrap();
+
+        for (a, b) in first.djs.iter().zip(second.djs.iter()) {
+            assert_eq!(a.play_probability, b.play_probability);
+            assert_eq!(a.expected_wait_slots, b.expected_wait_slots);
+        }
+    }
+
+    #[test]
+    fn test_equal_weights_give_equal_odds() {
+        let engine = LotteryEngine::new(LotteryConfig::default());
+        let simulator = LotterySimulator::new(&engine);
+        let djs: Vec<Dj> = (0..4).map(|i| create_test_dj(&format!("DJ{}", i), 1.0)).collect();
+
+        let result = simulator.run(&djs, None, &HashMap::new(), 20_000, 2, 7).unwrap();
+
+        for dj in &result.djs {
+            assert!((dj.play_probability - 0.5).abs() < 0.02, "{}", dj.play_probability);
+            assert!((dj.expected_wait_slots.unwrap() - 0.5).abs() < 0.03);
+        }
+        assert!(result.gini_coefficient < 0.02);
+    }
+
+    #[test]
+    fn test_heavier_dj_plays_more_often() {
+        let engine = LotteryEngine::new(LotteryConfig::default());
+        let simulator = LotterySimulator::new(&engine);
+        let djs = vec![create_test_dj("Heavy", 3.0), create_test_dj("Light", 1.0), create_test_dj("Other", 1.0)];
+
+        let result = simulator.run(&djs, None, &HashMap::new(), 10_000, 1, 1).unwrap();
+
+        // Single slot: probability equals the weight share
+        assert!((result.djs[0].play_probability - 0.6).abs() < 0.02);
+        assert!((result.djs[1].play_probability - 0.2).abs() < 0.02);
+        assert!(result.gini_coefficient > 0.1);
+    }
+
+    #[test]
+    fn test_every_dj_plays_when_slots_cover_pool() {
+        let engine = LotteryEngine::new(LotteryConfig::default());
+        let simulator = LotterySimulator::new(&engine);
+        let djs = vec![create_test_dj("Alice", 5.0), create_test_dj("Bob", 1.0)];
+
+        let result = simulator.run(&djs, None, &HashMap::new(), 1_000, 10, 3).unwrap();
+
+        assert!(result.djs.iter().all(|dj| dj.play_probability == 1.0));
+        let alice_wait = result.djs[0].expected_wait_slots.unwrap();
+        let bob_wait = result.djs[1].expected_wait_slots.unwrap();
+        assert!(alice_wait < bob_wait);
+    }
+
+    #[test]
+    fn test_carry_over_improves_odds() {
+        let engine = LotteryEngine::new(LotteryConfig {
+            carry_over_bonus: 1.0,
+            ..LotteryConfig::default()
+        });
+        let simulator = LotterySimulator::new(&engine);
+        let djs = vec![create_test_dj("Waiting", 1.0), create_test_dj("Fresh", 1.0)];
+
+        let mut lost_draws = HashMap::new();
+        lost_draws.insert(djs[0].id.clone(), 3);
+
+        let result = simulator.run(&djs, None, &lost_draws, 10_000, 1, 11).unwrap();
+
+        // Weight 4.0 against 1.0
+        assert!((result.djs[0].play_probability - 0.8).abs() < 0.02);
+    }
+
+    #[test]
+    fn test_invalid_run_count_is_rejected() {
+        let engine = LotteryEngine::new(LotteryConfig::default());
+        let simulator = LotterySimulator::new(&engine);
+        let djs = vec![create_test_dj("Alice", 1.0)];
+
+        assert!(simulator.run(&djs, None, &HashMap::new(), 0, 1, 1).is_err());
+        assert!(simulator.run(&djs, None, &HashMap::new(), 1_000_000, 1, 1).is_err());
+    }
+
+    #[test]
+    fn test_oversized_simulation_is_rejected() {
+        let engine = LotteryEngine::new(LotteryConfig::default());
+        let simulator = LotterySimulator::new(&engine);
+        let djs: Vec<Dj> = (0..50).map(|i| create_test_dj(&format!("DJ {}", i), 1.0)).collect();
+
+        // 100 000 runs × 50 slots × 50 DJs is far beyond the limit; the check fails before drawing
+        let error = simulator.run(&djs, None, &HashMap::new(), 100_000, 50, 1).unwrap_err();
+        assert!(matches!(error.downcast_ref::<SimulationError>(), Some(SimulationError::TooLarge)));
+        assert!(simulator.run(&djs, None, &HashMap::new(), 100, 6, 1).is_ok());
+    }
+
+    #[test]
+    fn test_gini_coefficient() {
+        assert_eq!(gini_coefficient(&[]), 0.0);
+        assert!(gini_coefficient(&[0.5, 0.5, 0.5]).abs() < 1e-12);
+        // One of four holds everything: (n - 1) / n
+        assert!((gini_coefficient(&[0.0, 0.0, 0.0, 1.0]) - 0.75).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_chi_square_p_value_matches_tables() {
+        // Critical values at p = 0.05
+        assert!((chi_square_p_value(3.841, 1) - 0.05).abs() < 1e-3);
+        assert!((chi_square_p_value(11.070, 5) - 0.05).abs() < 1e-3);
+        assert!((chi_square_p_value(2.0, 2) - (-1.0f64).exp()).abs() < 1e-9);
+        assert_eq!(chi_square_p_value(0.0, 3), 1.0);
+    }
+
+    #[test]
+    fn test_historical_fairness() {
+        let fair = HistoricalFairness::from_djs(
+            20,
+            vec![historical("Alice", 10, 10.0), historical("Bob", 10, 10.0)],
+        );
+        assert_eq!(fair.chi_square, 0.0);
+        assert_eq!(fair.degrees_of_freedom, 1);
+        assert_eq!(fair.p_value, Some(1.0));
+
+        let skewed = HistoricalFairness::from_djs(
+            20,
+            vec![historical("Alice", 20, 10.0), historical("Bob", 0, 10.0)],
+        );
+        assert!((skewed.chi_square - 20.0).abs() < 1e-9);
+        assert!(skewed.p_value.unwrap() < 0.001);
+
+        let single = HistoricalFairness::from_djs(1, vec![historical("Alice", 1, 1.0)]);
+        assert!(single.p_value.is_none());
+    }
+}
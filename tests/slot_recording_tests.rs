@@ -0,0 +1,163 @@
+use session_recorder_addon::{
+    models::{
+        AppState,
+        dj::{CreateDjRequest, UpdateDjRequest},
+        event_session::StartEventRequest,
+        slot_recording::StartRecordingRequest,
+    },
+    services::{DjService, EventService, MockRecorderControl, RecorderCommand},
+};
+use chrono::{TimeZone, Utc};
+use sqlx::SqlitePool;
+use std::sync::Arc;
+
+mod common;
+
+#[cfg(test)]
+mod slot_recording_tests {
+    use super::*;
+
+    async fn setup_test_db() -> Arc<AppState> {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+
+        let config = session_recorder_addon::models::AppConfig {
+            ..common::test_config()
+        };
+
+        Arc::new(AppState { db: pool, config })
+    }
+
+    /// Event started on 2024-01-01 with the mock recorder attached
+    async fn start_event(app_state: &Arc<AppState>, recorder: &Arc<MockRecorderControl>) -> EventService {
+        let event_service = EventService::new(app_state.clone()).with_recorder_control(recorder.clone());
+        event_service.start_event(StartEventRequest {
+            slot_duration_minutes: Some(60),
+            late_arrival_cutoff_hours: None,
+            started_at: Some(Utc.with_ymd_and_hms(2024, 1, 1, 20, 0, 0).unwrap()),
+            late_penalty_curve: None,
+            name: None,
+        }).await.unwrap();
+        event_service
+    }
+
+    async fn create_dj(app_state: &Arc<AppState>, name: &str, keep_recording: bool) -> String {
+        let dj_service = DjService::new(app_state.clone());
+        let dj = dj_service.register_dj(CreateDjRequest {
+            name: name.to_string(),
+            email: None,
+        }).await.unwrap();
+
+        if keep_recording {
+            dj_service.update_dj(&dj.id, UpdateDjRequest {
+                name: None,
+                email: None,
+                weight: None,
+                is_active: None,
+                position_in_queue: None,
+                keep_recording: Some(true),
+            }).await.unwrap();
+        }
+        dj.id
+    }
+
+    fn start(recording_id: &str, name: &str, keep: bool) -> RecorderCommand {
+        RecorderCommand::Start {
+            recording_id: recording_id.to_string(),
+            request: StartRecordingRequest { name: name.to_string(), keep },
+        }
+    }
+
+    fn stop(recording_id: &str) -> RecorderCommand {
+        RecorderCommand::Stop { recording_id: recording_id.to_string() }
+    }
+
+    #[tokio::test]
+    async fn test_slot_transitions_drive_the_recorder() {
+        let app_state = setup_test_db().await;
+        let recorder = Arc::new(MockRecorderControl::new());
+        let event_service = start_event(&app_state, &recorder).await;
+
+        let alice = create_dj(&app_state, "Alice", true).await;
+        let bob = create_dj(&app_state, "Bob", false).await;
+
+        event_service.start_next_dj_slot(alice.clone()).await.unwrap();
+        // Next slot starts while Alice's recording is still running
+        event_service.start_next_dj_slot(bob.clone()).await.unwrap();
+        event_service.end_event().await.unwrap();
+
+        assert_eq!(recorder.commands(), vec![
+            start("mock-1", "Alice @ 2024-01-01", true),
+            stop("mock-1"),
+            start("mock-2", "Bob @ 2024-01-01", false),
+            stop("mock-2"),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_recording_name_includes_the_event() {
+        let app_state = setup_test_db().await;
+        let recorder = Arc::new(MockRecorderControl::new());
+        let event_service = EventService::new(app_state.clone()).with_recorder_control(recorder.clone());
+        event_service.start_event(StartEventRequest {
+            slot_duration_minutes: Some(60),
+            late_arrival_cutoff_hours: None,
+            started_at: Some(Utc.with_ymd_and_hms(2024, 1, 1, 20, 0, 0).unwrap()),
+            late_penalty_curve: None,
+            name: Some("Friday Night Sessions".to_string()),
+        }).await.unwrap();
+
+        let alice = create_dj(&app_state, "Alice", true).await;
+        assert!(DjService::new(app_state.clone()).get_dj_by_id(&alice).await.unwrap().unwrap().keep_recording);
+
+        event_service.start_next_dj_slot(alice).await.unwrap();
+        assert_eq!(recorder.commands(), vec![start("mock-1", "Alice @ Friday Night Sessions 2024-01-01", true)]);
+    }
+
+    #[tokio::test]
+    async fn test_ending_a_set_stops_only_that_recording() {
+        let app_state = setup_test_db().await;
+        let recorder = Arc::new(MockRecorderControl::new());
+        let event_service = start_event(&app_state, &recorder).await;
+
+        let alice = create_dj(&app_state, "Alice", false).await;
+        let bob = create_dj(&app_state, "Bob", false).await;
+
+        event_service.start_next_dj_slot(alice.clone()).await.unwrap();
+        event_service.end_dj_slot(&bob).await.unwrap();
+        assert_eq!(recorder.commands().len(), 1);
+
+        event_service.end_dj_slot(&alice).await.unwrap();
+        // Already stopped, nothing left for the event end
+        event_service.end_event().await.unwrap();
+        assert_eq!(recorder.commands(), vec![start("mock-1", "Alice @ 2024-01-01", false), stop("mock-1")]);
+    }
+
+    #[tokio::test]
+    async fn test_offline_recorder_does_not_block_slots() {
+        let app_state = setup_test_db().await;
+        let recorder = Arc::new(MockRecorderControl::new());
+        let event_service = start_event(&app_state, &recorder).await;
+
+        let alice = create_dj(&app_state, "Alice", false).await;
+        let bob = create_dj(&app_state, "Bob", false).await;
+
+        recorder.set_offline(true);
+        let event = event_service.start_next_dj_slot(alice.clone()).await.unwrap();
+        assert_eq!(event.current_dj_id.as_deref(), Some(alice.as_str()));
+
+        recorder.set_offline(false);
+        event_service.start_next_dj_slot(bob.clone()).await.unwrap();
+        // The failed recording is not stopped again
+        assert_eq!(recorder.commands(), vec![start("mock-1", "Bob @ 2024-01-01", false)]);
+
+        let recordings = event_service.get_slot_recordings().await.unwrap();
+        assert_eq!(recordings.len(), 2);
+        assert_eq!(recordings[0].dj_id, alice);
+        assert!(recordings[0].recording_id.is_none());
+        assert!(recordings[0].error.is_some());
+        assert!(recordings[0].stopped_at.is_some());
+        assert_eq!(recordings[1].recording_id.as_deref(), Some("mock-1"));
+        assert!(recordings[1].stopped_at.is_none());
+    }
+}
@@ -0,0 +1,293 @@
+use session_recorder_addon::{
+    models::{
+        AppState,
+        dj::CreateDjRequest,
+        session::StartSessionRequest,
+        storage::{ByteRange, StorageConfig, StorageError, StorageObject},
+    },
+    services::{copy_object, DjService, LocalStorage, SessionRecorderConfig, SessionRecorderService, SessionService, StorageBackend},
+};
+use anyhow::Result;
+use sqlx::{Row, SqlitePool};
+use std::path::Path;
+use std::sync::Arc;
+use tokio::io::AsyncReadExt;
+
+mod common;
+
+#[cfg(test)]
+mod storage_tests {
+    use super::*;
+
+    async fn setup_test_db(storage_root: &Path) -> Arc<AppState> {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+
+        let config = session_recorder_addon::models::AppConfig {
+            storage_config: StorageConfig::Local {
+                root: storage_root.to_path_buf(),
+                public_url: Some("https://files.example.com/".to_string()),
+            },
+            ..common::test_config()
+        };
+
+        Arc::new(AppState { db: pool, config })
+    }
+
+    async fn read_all(storage: &dyn StorageBackend, key: &str, range: Option<ByteRange>) -> Result<(Vec<u8>, u64)> {
+        let mut stream = storage.stream(key, range).await?;
+        let mut body = Vec::new();
+        stream.body.read_to_end(&mut body).await?;
+        assert_eq!(body.len() as u64, stream.range.end - stream.range.start);
+        Ok((body, stream.size))
+    }
+
+    #[test]
+    fn test_byte_range_resolution() {
+        let from = |start, end| ByteRange::From { start, end };
+        assert_eq!(from(0, Some(3)).resolve(10).unwrap(), 0..4);
+        assert_eq!(from(4, None).resolve(10).unwrap(), 4..10);
+        assert_eq!(from(8, Some(100)).resolve(10).unwrap(), 8..10); // Clamped to the object
+        assert_eq!(ByteRange::Last(3).resolve(10).unwrap(), 7..10);
+        assert_eq!(ByteRange::Last(30).resolve(10).unwrap(), 0..10);
+
+        assert!(matches!(from(10, None).resolve(10), Err(StorageError::RangeNotSatisfiable { size: 10 })));
+        assert!(matches!(from(5, Some(2)).resolve(10), Err(StorageError::RangeNotSatisfiable { .. })));
+        assert!(matches!(ByteRange::Last(0).resolve(10), Err(StorageError::RangeNotSatisfiable { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_local_storage_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = LocalStorage::new(dir.path(), None);
+
+        storage.put("main/sessions/night/metadata.json", b"{}".to_vec()).await.unwrap();
+        storage.put("main/sessions/night/data.flac", b"0123456789".to_vec()).await.unwrap();
+        storage.put("side/sessions/warmup/data.ogg", b"ogg".to_vec()).await.unwrap();
+
+        let root = storage.list("").await.unwrap();
+        assert_eq!(root.directories, vec!["main".to_string(), "side".to_string()]);
+        assert!(root.objects.is_empty());
+
+        let session = storage.list("main/sessions/night/").await.unwrap();
+        assert!(session.directories.is_empty());
+        assert_eq!(session.objects, vec![
+            StorageObject { key: "main/sessions/night/data.flac".to_string(), size: 10 },
+            StorageObject { key: "main/sessions/night/metadata.json".to_string(), size: 2 },
+        ]);
+        assert_eq!(session.objects[0].name(), "data.flac");
+        assert!(storage.list("nothing/here").await.unwrap().objects.is_empty());
+
+        assert_eq!(storage.get("side/sessions/warmup/data.ogg").await.unwrap(), b"ogg");
+        assert_eq!(read_all(&storage, "main/sessions/night/data.flac", None).await.unwrap(), (b"0123456789".to_vec(), 10));
+        assert_eq!(
+            read_all(&storage, "main/sessions/night/data.flac", Some(ByteRange::From { start: 2, end: Some(4) })).await.unwrap(),
+            (b"234".to_vec(), 10)
+        );
+        assert_eq!(read_all(&storage, "main/sessions/night/data.flac", Some(ByteRange::Last(2))).await.unwrap().0, b"89");
+
+        // Files can be stored from disk, and overwritten
+        let source = dir.path().join("upload.bin");
+        std::fs::write(&source, b"replaced").unwrap();
+        storage.put_file("main/sessions/night/data.flac", &source).await.unwrap();
+        assert_eq!(storage.get("main/sessions/night/data.flac").await.unwrap(), b"replaced");
+
+        storage.delete("main/sessions/night/data.flac").await.unwrap();
+        storage.delete("main/sessions/night/data.flac").await.unwrap();
+        let missing = storage.get("main/sessions/night/data.flac").await.unwrap_err();
+        assert!(matches!(missing.downcast_ref::<StorageError>(), Some(StorageError::NotFound(_))));
+
+        assert_eq!(storage.presign("side/sessions/warmup/data.ogg", std::time::Duration::from_secs(60)).await.unwrap(), None);
+        assert_eq!(
+            LocalStorage::new(dir.path(), Some("https://files.example.com/".to_string())).public_url("a/b.flac"),
+            "https://files.example.com/a/b.flac"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_streamed_copies() {
+        let dir = tempfile::tempdir().unwrap();
+        let recorder_root = dir.path().join("recorder");
+        let recorder_storage = LocalStorage::new(&recorder_root, None);
+        let slotify = LocalStorage::new(&dir.path().join("slotify"), None);
+
+        // Larger than any copy buffer, so it has to go through in pieces
+        let recording: Vec<u8> = (0..3_000_000u32).map(|i| (i % 251) as u8).collect();
+        recorder_storage.put("main/sessions/night/data.flac", recording.clone()).await.unwrap();
+
+        let size = copy_object(&recorder_storage, "main/sessions/night/data.flac", &slotify, "sets/night.flac").await.unwrap();
+        assert_eq!(size, recording.len() as u64);
+        assert_eq!(slotify.get("sets/night.flac").await.unwrap(), recording);
+
+        slotify.put_stream("sets/short.ogg", Box::pin(&b"ogg"[..])).await.unwrap();
+        assert_eq!(slotify.get("sets/short.ogg").await.unwrap(), b"ogg");
+
+        let recorder = SessionRecorderService::new(SessionRecorderConfig {
+            local_dir: Some(recorder_root),
+            ..SessionRecorderConfig::default()
+        }).await.unwrap();
+        recorder.copy_session_file("main", "night", "data.flac", &slotify, "sets/copy.flac").await.unwrap();
+        assert_eq!(slotify.get("sets/copy.flac").await.unwrap(), recording);
+
+        let missing = copy_object(&recorder_storage, "main/sessions/none/data.flac", &slotify, "sets/none.flac").await.unwrap_err();
+        assert!(matches!(missing.downcast_ref::<StorageError>(), Some(StorageError::NotFound(_))));
+        assert!(!dir.path().join("slotify/sets/none.flac").exists());
+    }
+
+    #[tokio::test]
+    async fn test_local_storage_rejects_keys_outside_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = LocalStorage::new(&dir.path().join("root"), None);
+
+        for key in ["../escape.txt", "a/../../escape.txt", "/etc/passwd", "./a"] {
+            let error = storage.put(key, b"x".to_vec()).await.unwrap_err();
+            assert!(matches!(error.downcast_ref::<StorageError>(), Some(StorageError::InvalidKey(_))), "{}", key);
+        }
+        assert!(!dir.path().join("escape.txt").exists());
+    }
+
+    #[tokio::test]
+    async fn test_recorder_reads_local_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let session_dir = dir.path().join("main/sessions/night");
+        std::fs::create_dir_all(&session_dir).unwrap();
+        std::fs::write(session_dir.join("metadata.json"), serde_json::json!({
+            "id": "night",
+            "recorder_id": "main",
+            "name": "Friday night",
+            "start_time": "2024-01-01T20:00:00Z",
+            "end_time": "2024-01-02T02:00:00Z",
+            "duration": 21600,
+            "keep": true,
+            "is_closed": true
+        }).to_string()).unwrap();
+        std::fs::write(session_dir.join("data.flac"), b"flac-bytes").unwrap();
+        std::fs::write(dir.path().join("metadata.json"), b"{}").unwrap();
+
+        let recorder = SessionRecorderService::new(SessionRecorderConfig {
+            local_dir: Some(dir.path().to_path_buf()),
+            ..SessionRecorderConfig::default()
+        }).await.unwrap();
+
+        assert_eq!(recorder.get_recorders().await.unwrap(), vec!["main".to_string()]);
+        assert_eq!(recorder.list_session_ids("main").await.unwrap(), vec!["night".to_string()]);
+
+        let session = recorder.get_session_details("main", "night").await.unwrap();
+        assert_eq!(session.name, "Friday night");
+        assert_eq!(session.duration_seconds, Some(21600));
+        assert!(session.keep && session.is_closed);
+        let flac_url = session.files.flac_url.unwrap();
+        assert!(flac_url.starts_with("file://") && flac_url.ends_with("main/sessions/night/data.flac"));
+        assert!(session.files.ogg_url.is_none());
+
+        let download = dir.path().join("download.flac");
+        assert_eq!(recorder.download_session_file("main", "night", "data.flac", &download).await.unwrap(), 10);
+        assert_eq!(std::fs::read(&download).unwrap(), b"flac-bytes");
+        let url = recorder.get_presigned_url("main", "night", "data.flac", 60).await.unwrap();
+        assert_eq!(url, flac_url);
+    }
+
+    #[tokio::test]
+    async fn test_ended_session_file_is_published() {
+        let dir = tempfile::tempdir().unwrap();
+        let app_state = setup_test_db(&dir.path().join("storage")).await;
+        let session_service = SessionService::new(app_state.clone());
+
+        let dj = DjService::new(app_state.clone()).register_dj(CreateDjRequest {
+            name: "Alice".to_string(),
+            email: None,
+        }).await.unwrap();
+        let session = session_service.start_session(StartSessionRequest {
+            dj_id: dj.id.clone(),
+            session_type: None,
+        }).await.unwrap();
+
+        let recording = dir.path().join("alice.flac");
+        std::fs::write(&recording, b"alice-set").unwrap();
+        sqlx::query("UPDATE sessions SET file_path = ? WHERE id = ?")
+            .bind(recording.to_string_lossy().to_string())
+            .bind(&session.id)
+            .execute(&app_state.db)
+            .await
+            .unwrap();
+
+        session_service.end_session(&session.id).await.unwrap();
+
+        let row = sqlx::query("SELECT storage_key, download_link, upload_status FROM sessions WHERE id = ?")
+            .bind(&session.id)
+            .fetch_one(&app_state.db)
+            .await
+            .unwrap();
+        let key: String = row.get("storage_key");
+        // Not an audio file, so it can't be tagged, but it is still renamed for the DJ
+        let filename = format!("Alice_{}_session_{}.flac", session.started_at.format("%Y%m%d_%H%M%S"), &session.id[..8]);
+        assert!(key.starts_with("sessions/") && key.ends_with(&format!("{}/{}", session.id, filename)));
+        assert_eq!(row.get::<String, _>("download_link"), format!("https://files.example.com/{}", key));
+        assert_eq!(row.get::<String, _>("upload_status"), "uploaded");
+        assert_eq!(std::fs::read(dir.path().join("storage").join(&key)).unwrap(), b"alice-set");
+
+        // Without a file there is nothing to publish
+        let empty = session_service.start_session(StartSessionRequest {
+            dj_id: dj.id.clone(),
+            session_type: None,
+        }).await.unwrap();
+        session_service.end_session(&empty.id).await.unwrap();
+        assert_eq!(session_service.get_download_link(&empty.id).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_recorder_recording_is_copied_as_is() {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+        let app_state = Arc::new(AppState {
+            db: pool,
+            config: session_recorder_addon::models::AppConfig {
+                storage_config: common::local_storage(dir.path()),
+                session_recorder_config: common::local_recorder(dir.path()),
+                ..common::test_config()
+            },
+        });
+        let session_dir = dir.path().join("recorder/main/sessions/night");
+        std::fs::create_dir_all(&session_dir).unwrap();
+        std::fs::write(session_dir.join("data.ogg"), b"ogg-set").unwrap();
+
+        let dj = DjService::new(app_state.clone()).register_dj(CreateDjRequest {
+            name: "Bob".to_string(),
+            email: None,
+        }).await.unwrap();
+        let session_id = uuid::Uuid::new_v4().to_string();
+        let started_at = chrono::Utc::now();
+        sqlx::query(
+            "INSERT INTO sessions (id, dj_id, started_at, upload_status, session_type, recorder_id, recorder_session_id, recorder_ogg_url) \
+             VALUES (?, ?, ?, 'processing', 'solo', 'main', 'night', 'file:///recorder/main/sessions/night/data.ogg')"
+        )
+        .bind(&session_id)
+        .bind(&dj.id)
+        .bind(started_at)
+        .execute(&app_state.db)
+        .await
+        .unwrap();
+
+        let session_service = SessionService::new(app_state.clone());
+        let key = session_service.copy_recorder_recording(&session_id).await.unwrap().unwrap();
+        let filename = format!("Bob_{}_session_{}.ogg", started_at.format("%Y%m%d_%H%M%S"), &session_id[..8]);
+        assert!(key.ends_with(&format!("{}/{}", session_id, filename)));
+        assert_eq!(std::fs::read(dir.path().join("storage").join(&key)).unwrap(), b"ogg-set");
+        let stored: String = sqlx::query("SELECT storage_key FROM sessions WHERE id = ?")
+            .bind(&session_id)
+            .fetch_one(&app_state.db)
+            .await
+            .unwrap()
+            .get("storage_key");
+        assert_eq!(stored, key);
+
+        // Nothing to copy without a linked recording
+        sqlx::query("UPDATE sessions SET recorder_id = NULL WHERE id = ?")
+            .bind(&session_id)
+            .execute(&app_state.db)
+            .await
+            .unwrap();
+        assert_eq!(session_service.copy_recorder_recording(&session_id).await.unwrap(), None);
+    }
+}
@@ -0,0 +1,175 @@
+use session_recorder_addon::{
+    models::{
+        AppState,
+        dj::CreateDjRequest,
+        StreamMetadataConfig, StreamMount, StreamServer,
+    },
+    services::{DjService, NowPlayingService, StreamMetadataService},
+};
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    routing::get,
+    Router,
+};
+use chrono::Utc;
+use sqlx::sqlite::SqlitePoolOptions;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+mod common;
+
+#[cfg(test)]
+mod stream_metadata_tests {
+    use super::*;
+
+    type Requests = Arc<Mutex<Vec<(String, HashMap<String, String>, Option<String>)>>>;
+
+    async fn setup_test_db(dir: &Path, mounts: Vec<StreamMount>) -> Arc<AppState> {
+        let pool = SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+
+        let config = session_recorder_addon::models::AppConfig {
+            storage_config: common::local_storage(dir),
+            stream_metadata_config: StreamMetadataConfig {
+                mounts,
+                ..StreamMetadataConfig::default()
+            },
+            ..common::test_config()
+        };
+
+        Arc::new(AppState { db: pool, config })
+    }
+
+    /// Stub of an Icecast and a SHOUTcast admin endpoint, recording each request
+    async fn start_stub_server() -> (String, Requests) {
+        async fn record(
+            path: &'static str,
+            State(requests): State<Requests>,
+            Query(query): Query<HashMap<String, String>>,
+            headers: HeaderMap,
+        ) -> StatusCode {
+            let auth = headers.get("authorization").map(|value| value.to_str().unwrap().to_string());
+            requests.lock().unwrap().push((path.to_string(), query, auth));
+            StatusCode::OK
+        }
+
+        let requests = Requests::default();
+        let app = Router::new()
+            .route("/admin/metadata", get(|state, query, headers| record("/admin/metadata", state, query, headers)))
+            .route("/admin.cgi", get(|state, query, headers| record("/admin.cgi", state, query, headers)))
+            .with_state(requests.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        (url, requests)
+    }
+
+    fn mount(room: &str, server: StreamServer, url: &str, include_track: bool) -> StreamMount {
+        StreamMount {
+            room: room.to_string(),
+            server,
+            url: url.to_string(),
+            mount: "/live".to_string(),
+            username: "admin".to_string(),
+            password: "hackme".to_string(),
+            include_track,
+            enabled: true,
+        }
+    }
+
+    #[test]
+    fn test_mounts_file_defaults() {
+        let mounts: Vec<StreamMount> = serde_json::from_str(
+            r#"[{ "room": "Main", "url": "http://icecast:8000", "password": "hackme" },
+                { "room": "Garden", "server": "shoutcast", "url": "http://radio:8000", "password": "x", "include_track": false }]"#,
+        ).unwrap();
+        assert_eq!((mounts[0].server, mounts[0].mount.as_str(), mounts[0].username.as_str()), (StreamServer::Icecast, "/stream", "admin"));
+        assert!(mounts[0].include_track && mounts[0].enabled);
+        assert_eq!(mounts[1].server, StreamServer::Shoutcast);
+        assert!(!mounts[1].include_track);
+        // Credentials never leave the server
+        assert!(!serde_json::to_string(&mounts[0]).unwrap().contains("hackme"));
+    }
+
+    #[tokio::test]
+    async fn test_titles_follow_set_and_track() {
+        let dir = tempfile::tempdir().unwrap();
+        let (url, requests) = start_stub_server().await;
+        let app_state = setup_test_db(dir.path(), vec![
+            mount("Main", StreamServer::Icecast, &url, true),
+            mount("Garden", StreamServer::Shoutcast, &url, false),
+        ]).await;
+        let service = StreamMetadataService::new(app_state.clone());
+        let mut sent = HashMap::new();
+
+        // Nothing to announce between sets
+        assert_eq!(service.sync(&mut sent).await.unwrap(), 0);
+
+        let dj = DjService::new(app_state.clone()).register_dj(CreateDjRequest {
+            name: "DJ Élodie".to_string(),
+            email: None,
+        }).await.unwrap();
+        sqlx::query("INSERT INTO sessions (id, dj_id, started_at, upload_status, session_type) VALUES ('s1', ?, ?, 'recording', 'solo')")
+            .bind(&dj.id)
+            .bind(Utc::now())
+            .execute(&app_state.db)
+            .await
+            .unwrap();
+        assert_eq!(service.sync(&mut sent).await.unwrap(), 2);
+        assert_eq!(service.sync(&mut sent).await.unwrap(), 0);
+
+        NowPlayingService::new(app_state.clone())
+            .record_track(Some("Robert Hood"), "Minus & More", Utc::now())
+            .await
+            .unwrap();
+        // Only the mount showing tracks changes
+        assert_eq!(service.sync(&mut sent).await.unwrap(), 1);
+
+        let requests = requests.lock().unwrap().clone();
+        let songs: Vec<(&str, &str)> = requests.iter().map(|(path, query, _)| (path.as_str(), query["song"].as_str())).collect();
+        assert_eq!(songs, vec![
+            ("/admin/metadata", "DJ Élodie"),
+            ("/admin.cgi", "DJ Élodie"),
+            ("/admin/metadata", "DJ Élodie - Robert Hood - Minus & More"),
+        ]);
+
+        let (_, icecast, auth) = &requests[0];
+        assert_eq!((icecast["mount"].as_str(), icecast["mode"].as_str()), ("/live", "updinfo"));
+        assert_eq!(auth.as_deref(), Some("Basic YWRtaW46aGFja21l")); // admin:hackme
+        let (_, shoutcast, auth) = &requests[1];
+        assert_eq!(shoutcast["pass"], "hackme");
+        assert!(auth.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_failed_updates_are_retried() {
+        let dir = tempfile::tempdir().unwrap();
+        let (url, requests) = start_stub_server().await;
+        let app_state = setup_test_db(dir.path(), vec![
+            mount("Main", StreamServer::Icecast, &format!("{}/missing", url), true),
+            mount("Garden", StreamServer::Icecast, &url, true),
+        ]).await;
+        let dj = DjService::new(app_state.clone()).register_dj(CreateDjRequest {
+            name: "Alice".to_string(),
+            email: None,
+        }).await.unwrap();
+        sqlx::query("INSERT INTO sessions (id, dj_id, started_at, upload_status, session_type) VALUES ('s1', ?, ?, 'recording', 'solo')")
+            .bind(&dj.id)
+            .bind(Utc::now())
+            .execute(&app_state.db)
+            .await
+            .unwrap();
+
+        let service = StreamMetadataService::new(app_state.clone());
+        let mut sent = HashMap::new();
+        assert_eq!(service.sync(&mut sent).await.unwrap(), 1);
+        assert_eq!(service.sync(&mut sent).await.unwrap(), 0);
+        assert!(!sent.contains_key("Main"));
+        assert_eq!(sent["Garden"], "Alice");
+        assert_eq!(requests.lock().unwrap().len(), 1);
+    }
+}
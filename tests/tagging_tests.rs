@@ -0,0 +1,320 @@
+use session_recorder_addon::{
+    audio::{read_tags, write_tags, AudioDecoder, AudioSpec, CoverArt, FlacEncoder, RecordingTags, TaggedFormat},
+    models::{
+        AppState,
+        dj::CreateDjRequest,
+        download::{CreateDownloadTokenRequest, DownloadClient},
+        event_session::StartEventRequest,
+    },
+    services::{DjService, DownloadService, EventService, SessionService, TaggingService},
+};
+use chrono::{TimeZone, Utc};
+use sqlx::{Row, SqlitePool};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+mod common;
+
+#[cfg(test)]
+mod tagging_tests {
+    use super::*;
+
+    async fn setup_test_db(dir: &Path, cover_art_path: Option<PathBuf>) -> Arc<AppState> {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+
+        let config = session_recorder_addon::models::AppConfig {
+            storage_config: common::local_storage(dir),
+            session_recorder_config: common::local_recorder(dir),
+            tagging_config: session_recorder_addon::models::TaggingConfig {
+                enabled: true,
+                cover_art_path,
+            },
+            ..common::test_config()
+        };
+
+        Arc::new(AppState { db: pool, config })
+    }
+
+    fn write_flac(path: &Path) -> Vec<i32> {
+        let spec = AudioSpec { sample_rate: 8_000, channels: 2, bits_per_sample: 16 };
+        let samples: Vec<i32> = (0..20_000).map(|i| ((i * 37) % 2_000) - 1_000).collect();
+        let mut encoder = FlacEncoder::new(BufWriter::new(File::create(path).unwrap()), spec).unwrap();
+        encoder.write_interleaved(&samples).unwrap();
+        encoder.finish().unwrap();
+        samples
+    }
+
+    fn decode_all(path: &Path) -> Vec<i32> {
+        let mut decoder = AudioDecoder::open(path).unwrap();
+        let mut samples = Vec::new();
+        while let Some(chunk) = decoder.next_chunk().unwrap() {
+            samples.extend(chunk);
+        }
+        samples
+    }
+
+    fn cover_png(size: u32) -> Vec<u8> {
+        let image = image::RgbImage::from_fn(size, size, |x, y| image::Rgb([x as u8, y as u8, (x ^ y) as u8]));
+        let mut png = std::io::Cursor::new(Vec::new());
+        image.write_to(&mut png, image::ImageFormat::Png).unwrap();
+        png.into_inner()
+    }
+
+    fn tags(artist: &str, cover: Option<CoverArt>) -> RecordingTags {
+        RecordingTags {
+            artist: artist.to_string(),
+            album: "Friday Night 2024-01-05".to_string(),
+            date: "2024-01-05".to_string(),
+            comment: Some("Slot 2, 23:00-00:00 UTC".to_string()),
+            cover,
+        }
+    }
+
+    /// Ogg page with a valid checksum
+    fn ogg_page(header_type: u8, granule: u64, sequence: u32, packets: &[&[u8]]) -> Vec<u8> {
+        let mut lacing = Vec::new();
+        for packet in packets {
+            lacing.extend(std::iter::repeat_n(255u8, packet.len() / 255));
+            lacing.push((packet.len() % 255) as u8);
+        }
+        let mut page = b"OggS\0".to_vec();
+        page.push(header_type);
+        page.extend_from_slice(&granule.to_le_bytes());
+        page.extend_from_slice(&7u32.to_le_bytes());
+        page.extend_from_slice(&sequence.to_le_bytes());
+        page.extend_from_slice(&[0; 4]);
+        page.push(lacing.len() as u8);
+        page.extend_from_slice(&lacing);
+        for packet in packets {
+            page.extend_from_slice(packet);
+        }
+        let crc = ogg_crc(&page);
+        page[22..26].copy_from_slice(&crc.to_le_bytes());
+        page
+    }
+
+    fn ogg_crc(data: &[u8]) -> u32 {
+        data.iter().fold(0u32, |mut crc, &byte| {
+            crc ^= (byte as u32) << 24;
+            for _ in 0..8 {
+                crc = if crc & 0x8000_0000 != 0 { (crc << 1) ^ 0x04C1_1DB7 } else { crc << 1 };
+            }
+            crc
+        })
+    }
+
+    /// Sequence numbers and payloads of an Ogg file's pages; checks every CRC
+    fn ogg_pages(data: &[u8]) -> Vec<(u32, Vec<u8>)> {
+        let mut pages = Vec::new();
+        let mut rest = data;
+        while !rest.is_empty() {
+            assert_eq!(&rest[..4], b"OggS");
+            let segments = rest[26] as usize;
+            let length = 27 + segments + rest[27..27 + segments].iter().map(|&l| l as usize).sum::<usize>();
+            let mut page = rest[..length].to_vec();
+            let crc = u32::from_le_bytes(page[22..26].try_into().unwrap());
+            page[22..26].copy_from_slice(&[0; 4]);
+            assert_eq!(ogg_crc(&page), crc);
+            pages.push((u32::from_le_bytes(page[18..22].try_into().unwrap()), page[27 + segments..].to_vec()));
+            rest = &rest[length..];
+        }
+        pages
+    }
+
+    #[test]
+    fn test_flac_tags_keep_audio_intact() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("data.flac");
+        let samples = write_flac(&source);
+        let cover = CoverArt { mime_type: "image/png".to_string(), data: cover_png(4) };
+
+        let tagged = dir.path().join("tagged.flac");
+        assert_eq!(write_tags(&source, &tagged, &tags("Alice", Some(cover.clone()))).unwrap(), TaggedFormat::Flac);
+        assert_eq!(read_tags(&tagged).unwrap(), tags("Alice", Some(cover.clone())));
+        assert_eq!(decode_all(&tagged), samples);
+
+        // Tagging again replaces the fields instead of adding to them
+        let retagged = dir.path().join("retagged.flac");
+        write_tags(&tagged, &retagged, &tags("Alice B", None)).unwrap();
+        assert_eq!(read_tags(&retagged).unwrap(), tags("Alice B", Some(cover)));
+        assert_eq!(
+            std::fs::metadata(&retagged).unwrap().len(),
+            std::fs::metadata(&tagged).unwrap().len() + 2
+        );
+        assert_eq!(decode_all(&retagged), samples);
+    }
+
+    #[test]
+    fn test_ogg_comment_header_is_rewritten() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("data.ogg");
+        let opus_head = b"OpusHead\x01\x02\x38\x01\x80\xbb\0\0\0\0\0";
+        let opus_tags = b"OpusTags\x08\0\0\0recorder\x01\0\0\0\x0c\0\0\0ENCODER=test";
+        let audio: Vec<Vec<u8>> = (0..3u8).map(|i| vec![i; 300]).collect();
+        let mut ogg = ogg_page(0x02, 0, 0, &[opus_head]);
+        ogg.extend(ogg_page(0, 0, 1, &[opus_tags]));
+        for (i, packet) in audio.iter().enumerate() {
+            ogg.extend(ogg_page(if i == 2 { 0x04 } else { 0 }, 960 * (i as u64 + 1), 2 + i as u32, &[packet]));
+        }
+        std::fs::write(&source, &ogg).unwrap();
+
+        // A cover this size spreads the comments over several pages
+        let cover = CoverArt { mime_type: "image/png".to_string(), data: (0..100_000u32).map(|i| (i % 251) as u8).collect() };
+        let tagged = dir.path().join("tagged.ogg");
+        assert_eq!(write_tags(&source, &tagged, &tags("Alice", Some(cover.clone()))).unwrap(), TaggedFormat::Ogg);
+        assert_eq!(read_tags(&tagged).unwrap(), tags("Alice", Some(cover)));
+
+        let pages = ogg_pages(&std::fs::read(&tagged).unwrap());
+        assert!(pages.len() > 6);
+        let sequences: Vec<u32> = pages.iter().map(|(sequence, _)| *sequence).collect();
+        assert_eq!(sequences, (0..pages.len() as u32).collect::<Vec<_>>());
+        assert_eq!(pages[0].1, opus_head.to_vec());
+        let audio_pages: Vec<Vec<u8>> = pages[pages.len() - 3..].iter().map(|(_, data)| data.clone()).collect();
+        assert_eq!(audio_pages, audio);
+
+        // Fields we don't set survive
+        let comments = String::from_utf8_lossy(&pages[1].1).to_string();
+        assert!(comments.starts_with("OpusTags") && comments.contains("recorder") && comments.contains("ENCODER=test"));
+    }
+
+    #[test]
+    fn test_mp3_gets_id3_tag() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("set.mp3");
+        let frames = [0xFFu8, 0xFB, 0x90, 0x64, 1, 2, 3, 4];
+        // An old ID3v2.3 tag with a title, which is replaced
+        let mut mp3 = b"ID3\x03\0\0\0\0\0\x11TIT2\0\0\0\x07\0\0\0Old set".to_vec();
+        mp3.extend_from_slice(&frames);
+        std::fs::write(&source, &mp3).unwrap();
+
+        let cover = CoverArt { mime_type: "image/jpeg".to_string(), data: vec![0xFF, 0xD8, 0xFF, 0xE0, 0, 0] };
+        let tagged = dir.path().join("tagged.mp3");
+        assert_eq!(write_tags(&source, &tagged, &tags("Alice", Some(cover.clone()))).unwrap(), TaggedFormat::Mp3);
+        assert_eq!(read_tags(&tagged).unwrap(), tags("Alice", Some(cover)));
+
+        let bytes = std::fs::read(&tagged).unwrap();
+        assert_eq!(&bytes[..4], b"ID3\x04");
+        assert!(bytes.ends_with(&frames));
+        assert!(!bytes.windows(4).any(|window| window == b"TIT2"));
+
+        let untaggable = dir.path().join("notes.txt");
+        std::fs::write(&untaggable, b"not audio").unwrap();
+        assert!(write_tags(&untaggable, &dir.path().join("out.txt"), &tags("Alice", None)).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_session_tags_describe_the_slot() {
+        let dir = tempfile::tempdir().unwrap();
+        let cover_path = dir.path().join("logo.png");
+        std::fs::write(&cover_path, cover_png(8)).unwrap();
+        let app_state = setup_test_db(dir.path(), Some(cover_path)).await;
+
+        EventService::new(app_state.clone()).start_event(StartEventRequest {
+            slot_duration_minutes: Some(60),
+            late_arrival_cutoff_hours: None,
+            started_at: Some(Utc.with_ymd_and_hms(2024, 1, 5, 21, 0, 0).unwrap()),
+            late_penalty_curve: None,
+            name: Some("Friday Night".to_string()),
+        }).await.unwrap();
+        let dj = DjService::new(app_state.clone()).register_dj(CreateDjRequest {
+            name: "DJ Alice".to_string(),
+            email: None,
+        }).await.unwrap();
+        let session_id = uuid::Uuid::new_v4().to_string();
+        sqlx::query("UPDATE djs SET position_in_queue = 2 WHERE id = ?")
+            .bind(&dj.id)
+            .execute(&app_state.db)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO sessions (id, dj_id, started_at, ended_at, upload_status, session_type) VALUES (?, ?, ?, ?, 'processing', 'solo')")
+            .bind(&session_id)
+            .bind(&dj.id)
+            .bind(Utc.with_ymd_and_hms(2024, 1, 5, 23, 0, 0).unwrap())
+            .bind(Utc.with_ymd_and_hms(2024, 1, 6, 0, 0, 0).unwrap())
+            .execute(&app_state.db)
+            .await
+            .unwrap();
+
+        let tags = TaggingService::new(app_state.clone()).session_tags(&session_id).await.unwrap();
+        assert_eq!(tags.artist, "DJ Alice");
+        assert_eq!(tags.album, "Friday Night 2024-01-05");
+        assert_eq!(tags.date, "2024-01-05");
+        assert_eq!(tags.comment.as_deref(), Some("Slot 2, 23:00-00:00 UTC"));
+        assert_eq!(tags.cover.unwrap().mime_type, "image/png");
+
+        // A file on disk is published tagged and renamed
+        let recording = dir.path().join("set.flac");
+        write_flac(&recording);
+        sqlx::query("UPDATE sessions SET file_path = ? WHERE id = ?")
+            .bind(recording.to_string_lossy().to_string())
+            .bind(&session_id)
+            .execute(&app_state.db)
+            .await
+            .unwrap();
+        SessionService::new(app_state.clone()).publish_session_file(&session_id).await.unwrap().unwrap();
+
+        let key: String = sqlx::query("SELECT storage_key FROM sessions WHERE id = ?")
+            .bind(&session_id)
+            .fetch_one(&app_state.db)
+            .await
+            .unwrap()
+            .get("storage_key");
+        assert!(key.ends_with(&format!("/DJ_Alice_20240105_230000_session_{}.flac", &session_id[..8])));
+        let published = read_tags(&dir.path().join("storage").join(&key)).unwrap();
+        assert_eq!(published.artist, "DJ Alice");
+        assert_eq!(published.comment.as_deref(), Some("Slot 2, 23:00-00:00 UTC"));
+        assert!(published.cover.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_recorder_recording_is_published_tagged() {
+        let dir = tempfile::tempdir().unwrap();
+        let app_state = setup_test_db(dir.path(), None).await;
+        let session_dir = dir.path().join("recorder/main/sessions/night");
+        std::fs::create_dir_all(&session_dir).unwrap();
+        let samples = write_flac(&session_dir.join("data.flac"));
+
+        let dj = DjService::new(app_state.clone()).register_dj(CreateDjRequest {
+            name: "Bob".to_string(),
+            email: None,
+        }).await.unwrap();
+        let session_id = uuid::Uuid::new_v4().to_string();
+        sqlx::query(
+            "INSERT INTO sessions (id, dj_id, started_at, ended_at, upload_status, session_type, recorder_id, recorder_session_id, recorder_flac_url) \
+             VALUES (?, ?, ?, ?, 'processing', 'solo', 'main', 'night', 'file:///recorder/main/sessions/night/data.flac')"
+        )
+        .bind(&session_id)
+        .bind(&dj.id)
+        .bind(Utc.with_ymd_and_hms(2024, 1, 5, 22, 0, 0).unwrap())
+        .bind(Utc.with_ymd_and_hms(2024, 1, 5, 23, 0, 0).unwrap())
+        .execute(&app_state.db)
+        .await
+        .unwrap();
+
+        let session_service = SessionService::new(app_state.clone());
+        let (_fetched, recording) = session_service.fetch_recorder_recording(&session_id).await.unwrap().unwrap();
+        let key = session_service.publish_recorder_file(&session_id, &recording).await.unwrap();
+        let published = dir.path().join("storage").join(&key);
+        assert_eq!(read_tags(&published).unwrap().artist, "Bob");
+        assert_eq!(read_tags(&published).unwrap().album, "Slotify 2024-01-05"); // No event
+        assert_eq!(decode_all(&published), samples);
+
+        // Download links now serve the tagged copy under the DJ's file name
+        let download_service = DownloadService::new(app_state.clone());
+        let token = download_service.issue_token(&session_id, CreateDownloadTokenRequest::default()).await.unwrap().token.token;
+        let file = download_service.open_download(&token, None, &DownloadClient::default()).await.unwrap();
+        assert_eq!(file.filename, format!("Bob_20240105_220000_session_{}.flac", &session_id[..8]));
+        assert_eq!(file.stream.size, std::fs::metadata(&published).unwrap().len());
+
+        // Nothing to publish without a linked recording
+        sqlx::query("UPDATE sessions SET recorder_id = NULL WHERE id = ?")
+            .bind(&session_id)
+            .execute(&app_state.db)
+            .await
+            .unwrap();
+        assert!(session_service.fetch_recorder_recording(&session_id).await.unwrap().is_none());
+    }
+}
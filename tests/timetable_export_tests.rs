@@ -0,0 +1,154 @@
+use session_recorder_addon::{
+    models::{
+        AppState,
+        dj::CreateDjRequest,
+        event_session::StartEventRequest,
+        BrandingConfig, CalendarConfig, CreateReservedSlotRequest, ExportedSlotStatus, ReservedSlotKind,
+        TimetableExport, TimetableExportError, TimetableFormat,
+    },
+    services::{DjService, EventService, ReservedSlotService, TimetableExportService},
+    utils::csv_field,
+};
+use chrono::{TimeZone, Utc};
+use sqlx::sqlite::SqlitePoolOptions;
+use std::path::Path;
+use std::sync::Arc;
+
+mod common;
+
+#[cfg(test)]
+mod timetable_export_tests {
+    use super::*;
+
+    async fn setup_test_db(dir: &Path) -> Arc<AppState> {
+        let pool = SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+
+        let config = session_recorder_addon::models::AppConfig {
+            storage_config: common::local_storage(dir),
+            calendar_config: CalendarConfig {
+                timezone: Some(chrono_tz::Europe::Berlin),
+                location: None,
+            },
+            branding_config: BrandingConfig {
+                name: "Kollektiv <Nord>".to_string(),
+                accent_color: "#00aaff".to_string(),
+                logo_url: Some("https://example.com/logo.png".to_string()),
+                footer: Some("Doors close at 5".to_string()),
+                ..BrandingConfig::default()
+            },
+            ..common::test_config()
+        };
+
+        Arc::new(AppState { db: pool, config })
+    }
+
+    async fn register(app_state: &Arc<AppState>, name: &str) -> String {
+        DjService::new(app_state.clone()).register_dj(CreateDjRequest {
+            name: name.to_string(),
+            email: None,
+        }).await.unwrap().id
+    }
+
+    /// Starts at 22:00 in Berlin and draws the opening DJ; a headliner is booked for the second slot
+    async fn start_event(app_state: &Arc<AppState>) -> String {
+        let event_id = EventService::new(app_state.clone()).start_event(StartEventRequest {
+            slot_duration_minutes: Some(60),
+            late_arrival_cutoff_hours: None,
+            started_at: Some(Utc.with_ymd_and_hms(2024, 1, 19, 21, 0, 0).unwrap()),
+            late_penalty_curve: None,
+            name: Some("Friday Night".to_string()),
+        }).await.unwrap().id;
+        ReservedSlotService::new(app_state.clone()).create_reserved_slot(CreateReservedSlotRequest {
+            dj_id: None,
+            dj_name: Some("Lee \"Scratch\" | Perry, Jr.".to_string()),
+            position: Some(2),
+            starts_at: None,
+            kind: ReservedSlotKind::Headliner,
+            label: None,
+        }).await.unwrap();
+        event_id
+    }
+
+    #[tokio::test]
+    async fn test_json_export_is_versioned_and_stable() {
+        let dir = tempfile::tempdir().unwrap();
+        let app_state = setup_test_db(dir.path()).await;
+        let alice = register(&app_state, "Alice").await;
+        let event_id = start_event(&app_state).await;
+
+        let service = TimetableExportService::new(app_state.clone());
+        let export = service.export(&event_id, TimetableFormat::Json).await.unwrap();
+        assert_eq!(export.filename, "Friday_Night_2024-01-19_timetable.json");
+        assert_eq!(export.content_type, "application/json");
+        // Nothing changed in between, so neither did the file
+        assert_eq!(service.export(&event_id, TimetableFormat::Json).await.unwrap().body, export.body);
+
+        let parsed: TimetableExport = serde_json::from_str(&export.body).unwrap();
+        assert_eq!(parsed.schema_version, 1);
+        assert_eq!(parsed.event.name.as_deref(), Some("Friday Night"));
+        assert_eq!(parsed.event.timezone, "Europe/Berlin");
+        assert_eq!(parsed.slots.len(), 2);
+        assert_eq!((parsed.slots[0].dj_id.as_str(), parsed.slots[0].status), (alice.as_str(), ExportedSlotStatus::Upcoming));
+        assert_eq!(parsed.slots[0].starts_at, Utc.with_ymd_and_hms(2024, 1, 19, 21, 0, 0).unwrap());
+        assert_eq!(parsed.slots[0].ends_at, Utc.with_ymd_and_hms(2024, 1, 19, 22, 0, 0).unwrap());
+        assert_eq!(parsed.slots[1].booking, Some(ReservedSlotKind::Headliner));
+        assert!(parsed.slots[0].booking.is_none());
+
+        let missing = service.export("missing", TimetableFormat::Json).await.unwrap_err();
+        assert!(matches!(missing.downcast_ref::<TimetableExportError>(), Some(TimetableExportError::EventNotFound)));
+    }
+
+    #[tokio::test]
+    async fn test_csv_and_markdown_escape_names() {
+        let dir = tempfile::tempdir().unwrap();
+        let app_state = setup_test_db(dir.path()).await;
+        register(&app_state, "Alice").await;
+        let event_id = start_event(&app_state).await;
+        let service = TimetableExportService::new(app_state.clone());
+
+        let csv = service.export(&event_id, TimetableFormat::Csv).await.unwrap();
+        assert_eq!(csv.filename, "Friday_Night_2024-01-19_timetable.csv");
+        let lines: Vec<&str> = csv.body.lines().collect();
+        assert_eq!(lines[0], "position,dj_name,starts_at,ends_at,local_start,local_end,status,booking,label");
+        // Names can't smuggle formulas into spreadsheets
+        assert_eq!(csv_field("=HYPERLINK(\"x\")"), "\"'=HYPERLINK(\"\"x\"\")\"");
+        assert_eq!(csv_field("@SUM(A1)"), "'@SUM(A1)");
+        assert_eq!(csv_field("-DJ-"), "'-DJ-");
+        assert_eq!(csv_field("DJ = x"), "DJ = x");
+        assert_eq!(
+            lines[2],
+            "2,\"Lee \"\"Scratch\"\" | Perry, Jr.\",2024-01-19T22:00:00+00:00,2024-01-19T23:00:00+00:00,23:00,00:00,upcoming,Headliner,"
+        );
+
+        let markdown = service.export(&event_id, "md".parse().unwrap()).await.unwrap();
+        assert_eq!(markdown.content_type, "text/markdown; charset=utf-8");
+        assert!(markdown.body.starts_with("# Friday Night\n\nFriday 19 January 2024 · times in Europe/Berlin · 60 min slots\n"));
+        assert!(markdown.body.contains("| 1 | 22:00–23:00 | Alice | Upcoming |\n"));
+        assert!(markdown.body.contains("| 2 | 23:00–00:00 | Lee \"Scratch\" \\| Perry, Jr. | Headliner · Upcoming |\n"));
+    }
+
+    #[tokio::test]
+    async fn test_html_is_branded_and_escaped() {
+        let dir = tempfile::tempdir().unwrap();
+        let app_state = setup_test_db(dir.path()).await;
+        register(&app_state, "<script>alert(1)</script>").await;
+        let event_id = start_event(&app_state).await;
+
+        let html = TimetableExportService::new(app_state.clone())
+            .export(&event_id, TimetableFormat::Html)
+            .await
+            .unwrap();
+        assert_eq!(html.content_type, "text/html; charset=utf-8");
+        assert!(html.body.starts_with("<!DOCTYPE html>"));
+        assert!(html.body.contains(":root { --accent: #00aaff; }"));
+        assert!(html.body.contains("@media print"));
+        assert!(html.body.contains("<img src=\"https://example.com/logo.png\" alt=\"Kollektiv &lt;Nord&gt;\">"));
+        assert!(html.body.contains("<footer>Doors close at 5</footer>"));
+        assert!(html.body.contains("&lt;script&gt;alert(1)&lt;/script&gt;"));
+        assert!(!html.body.contains("<script>"));
+        assert!(html.body.contains("Lee &quot;Scratch&quot; | Perry, Jr."));
+
+        assert!(matches!("pdf".parse::<TimetableFormat>(), Err(TimetableExportError::UnknownFormat(_))));
+    }
+}
@@ -0,0 +1,266 @@
+use session_recorder_addon::{
+    models::{
+        AppState,
+        dj::CreateDjRequest,
+        tracklist::{
+            decode_tracklist, format_tracklist, normalize_tracks, SubmitTracklistRequest, Track, TracklistError, TracklistFormat,
+        },
+    },
+    services::{DjService, TracklistService},
+    utils::{cue_text, parse_cue_timestamp},
+};
+use chrono::{DateTime, TimeZone, Utc};
+use sqlx::SqlitePool;
+use std::path::Path;
+use std::sync::Arc;
+
+mod common;
+
+#[cfg(test)]
+mod tracklist_tests {
+    use super::*;
+
+    async fn setup_test_db(dir: &Path) -> Arc<AppState> {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+
+        let config = session_recorder_addon::models::AppConfig {
+            storage_config: common::local_storage(dir),
+            session_recorder_config: common::local_recorder(dir),
+            ..common::test_config()
+        };
+
+        Arc::new(AppState { db: pool, config })
+    }
+
+    fn at(hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 5, hour, minute, 0).unwrap()
+    }
+
+    async fn add_session(
+        app_state: &Arc<AppState>,
+        dj_name: &str,
+        started_at: DateTime<Utc>,
+        file_path: Option<&str>,
+        recorder_session_id: Option<&str>,
+    ) -> String {
+        let dj = DjService::new(app_state.clone()).register_dj(CreateDjRequest {
+            name: dj_name.to_string(),
+            email: None,
+        }).await.unwrap();
+        let session_id = uuid::Uuid::new_v4().to_string();
+        sqlx::query(
+            "INSERT INTO sessions (id, dj_id, started_at, upload_status, session_type, file_path, recorder_id, recorder_session_id, recorder_flac_url) \
+             VALUES (?, ?, ?, 'recording', 'solo', ?, ?, ?, ?)"
+        )
+        .bind(&session_id)
+        .bind(&dj.id)
+        .bind(started_at)
+        .bind(file_path)
+        .bind(recorder_session_id.map(|_| "main"))
+        .bind(recorder_session_id)
+        .bind(recorder_session_id.map(|id| format!("file:///recorder/main/sessions/{}/data.flac", id)))
+        .execute(&app_state.db)
+        .await
+        .unwrap();
+        session_id
+    }
+
+    fn summary(tracks: &[Track]) -> Vec<(Option<f64>, Option<&str>, &str)> {
+        tracks.iter().map(|t| (t.offset_seconds, t.artist.as_deref(), t.title.as_str())).collect()
+    }
+
+    #[test]
+    fn test_parse_pasted_text() {
+        let text = "\
+# Friday warm-up
+1. 0:00 Floorplan - Never Grow Old
+2) [4:32] Robert Hood – Minus
+03. 1:02:03 Jeff Mills - The Bells (Live)
+
+Untitled dubplate
+1:12:40 - DJ Koze - Pick Up";
+        let tracks = TracklistFormat::Text.parse(text).unwrap();
+        assert_eq!(summary(&tracks), vec![
+            (Some(0.0), Some("Floorplan"), "Never Grow Old"),
+            (Some(272.0), Some("Robert Hood"), "Minus"),
+            (Some(3723.0), Some("Jeff Mills"), "The Bells (Live)"),
+            (None, None, "Untitled dubplate"),
+            (Some(4360.0), Some("DJ Koze"), "Pick Up"),
+        ]);
+        assert_eq!(tracks.iter().map(|t| t.position).collect::<Vec<_>>(), vec![1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn test_parse_exports() {
+        let cue = "\
+PERFORMER \"DJ Name\"
+TITLE \"Friday\"
+FILE \"set.flac\" WAVE
+  TRACK 01 AUDIO
+    TITLE \"Intro\"
+    INDEX 01 00:00:00
+  TRACK 02 AUDIO
+    TITLE \"Minus\"
+    PERFORMER \"Robert Hood\"
+    INDEX 00 04:30:00
+    INDEX 01 04:32:37
+";
+        assert_eq!(TracklistFormat::detect(cue), TracklistFormat::Cue);
+        assert_eq!(parse_cue_timestamp("04:32:37"), Some(272.0 + 37.0 / 75.0));
+        assert_eq!(parse_cue_timestamp("18446744073709551615:00:00"), None);
+        assert_eq!(cue_text("Say \"Hi\"\r\n  INDEX 01 00:00:00"), "Say 'Hi'    INDEX 01 00:00:00");
+        let tracks = TracklistFormat::Auto.parse(cue).unwrap();
+        assert_eq!(summary(&tracks), vec![
+            (Some(0.0), Some("DJ Name"), "Intro"),
+            (Some(272.0 + 37.0 / 75.0), Some("Robert Hood"), "Minus"),
+        ]);
+
+        let m3u = "#EXTM3U\n#EXTINF:312,Floorplan - Never Grow Old\n/music/floorplan.mp3\nC:\\Music\\Robert Hood - Minus.flac\n";
+        assert_eq!(TracklistFormat::detect(m3u), TracklistFormat::M3u);
+        assert_eq!(summary(&TracklistFormat::Auto.parse(m3u).unwrap()), vec![
+            (None, Some("Floorplan"), "Never Grow Old"),
+            (None, Some("Robert Hood"), "Minus"),
+        ]);
+
+        // rekordbox writes its TXT export as UTF-16 with a byte order mark
+        let rekordbox = "#\tArtwork\tTrack Title\tArtist\tBPM\r\n1\t\tNever Grow Old\tFloorplan\t126.00\r\n2\t\tMinus\tRobert Hood\t130.00\r\n";
+        let mut exported = vec![0xFF, 0xFE];
+        exported.extend(rekordbox.encode_utf16().flat_map(u16::to_le_bytes));
+        let decoded = decode_tracklist(&exported).unwrap();
+        assert_eq!(decoded, rekordbox);
+        assert_eq!(TracklistFormat::detect(&decoded), TracklistFormat::Rekordbox);
+        let tracks = TracklistFormat::Auto.parse(&decoded).unwrap();
+        assert_eq!(summary(&tracks), vec![
+            (None, Some("Floorplan"), "Never Grow Old"),
+            (None, Some("Robert Hood"), "Minus"),
+        ]);
+        assert_eq!(tracks.iter().map(|t| t.position).collect::<Vec<_>>(), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_invalid_tracklists() {
+        let invalid = |result: Result<Vec<Track>, TracklistError>| matches!(result, Err(TracklistError::Invalid(_)));
+        assert!(invalid(TracklistFormat::Text.parse("")));
+        assert!(invalid(TracklistFormat::Text.parse("10:00 A - B\n5:00 C - D")));
+        assert!(invalid(TracklistFormat::Rekordbox.parse("Artist\tBPM\nFloorplan\t126")));
+        assert!(decode_tracklist(&[0xC3, 0x28]).is_err());
+        assert!(matches!("wav".parse::<TracklistFormat>(), Err(TracklistError::UnknownFormat(_))));
+        assert_eq!("M3U8".parse::<TracklistFormat>().unwrap(), TracklistFormat::M3u);
+
+        assert!(invalid(normalize_tracks(vec![Track::new(Some("A"), "  ", None)])));
+        assert!(invalid(normalize_tracks(vec![Track::new(None, "A", Some(-1.0))])));
+        assert!(invalid(normalize_tracks(vec![Track::new(None, "A", Some(f64::NAN))])));
+        assert!(invalid(normalize_tracks(vec![Track::new(None, "A", Some(1e300))])));
+        assert!(invalid(TracklistFormat::Text.parse("49:00:00 A - B")));
+        // Tracks that skipped validation still format without panicking
+        let huge = Track { position: 1, ..Track::new(None, "A", Some(1e300)) };
+        assert_eq!(format_tracklist(&[huge]), " 1. A\n");
+    }
+
+    #[tokio::test]
+    async fn test_submit_replace_and_delete() {
+        let dir = tempfile::tempdir().unwrap();
+        let app_state = setup_test_db(dir.path()).await;
+        let session = add_session(&app_state, "Alice", at(22, 0), None, None).await;
+        let service = TracklistService::new(app_state.clone());
+
+        let empty = service.get_tracklist(&session).await.unwrap();
+        assert!(empty.tracks.is_empty());
+        assert_eq!(empty.updated_at, None);
+        assert_eq!(service.formatted_tracklist(&session).await.unwrap(), None);
+
+        let submitted = service.set_tracklist(&session, SubmitTracklistRequest {
+            tracks: vec![
+                Track::new(Some(" Floorplan "), "Never Grow Old", Some(0.0)),
+                Track::new(Some(""), "Untitled", None),
+            ],
+            ..Default::default()
+        }).await.unwrap();
+        assert_eq!(submitted.dj_name, "Alice");
+        assert!(submitted.updated_at.is_some());
+        assert_eq!(summary(&submitted.tracks), vec![
+            (Some(0.0), Some("Floorplan"), "Never Grow Old"),
+            (None, None, "Untitled"),
+        ]);
+        assert_eq!(
+            service.formatted_tracklist(&session).await.unwrap().unwrap(),
+            "Alice - 2024-01-05 22:00 UTC\n\n 1. [0:00] Floorplan - Never Grow Old\n 2. Untitled\n"
+        );
+
+        // An upload replaces the whole list
+        let imported = service
+            .import_tracklist(&session, b"#EXTM3U\n#EXTINF:300,Robert Hood - Minus\nminus.mp3\n", TracklistFormat::Auto)
+            .await
+            .unwrap();
+        assert_eq!(summary(&imported.tracks), vec![(None, Some("Robert Hood"), "Minus")]);
+        assert_eq!(service.get_tracks(&session).await.unwrap().len(), 1);
+
+        service.delete_tracklist(&session).await.unwrap();
+        assert!(service.get_tracklist(&session).await.unwrap().tracks.is_empty());
+
+        let text = SubmitTracklistRequest { text: Some("A - B".to_string()), ..Default::default() };
+        let missing = service.set_tracklist("missing", text).await.unwrap_err();
+        assert!(matches!(missing.downcast_ref::<TracklistError>(), Some(TracklistError::SessionNotFound)));
+        let missing = service.delete_tracklist("missing").await.unwrap_err();
+        assert!(matches!(missing.downcast_ref::<TracklistError>(), Some(TracklistError::SessionNotFound)));
+        let rejected = service.set_tracklist(&session, SubmitTracklistRequest::default()).await.unwrap_err();
+        assert!(matches!(rejected.downcast_ref::<TracklistError>(), Some(TracklistError::Invalid(_))));
+    }
+
+    #[tokio::test]
+    async fn test_cue_sheet_follows_the_recording() {
+        let dir = tempfile::tempdir().unwrap();
+        let app_state = setup_test_db(dir.path()).await;
+        let service = TracklistService::new(app_state.clone());
+        sqlx::query(
+            "INSERT INTO recorder_sessions (recorder_id, id, name, start_time, is_closed, keep, synced_at) \
+             VALUES ('main', 'night', 'night', ?, TRUE, FALSE, ?)"
+        )
+        .bind(at(21, 30))
+        .bind(Utc::now())
+        .execute(&app_state.db)
+        .await
+        .unwrap();
+        let text = "0:00 Floorplan - Never Grow Old\nUntimed \"bootleg\"\n4:32 Robert Hood - Minus";
+        let submit = |text: &str| SubmitTracklistRequest { text: Some(text.to_string()), ..Default::default() };
+
+        // The set starts half an hour into the recorder's recording
+        let bob = add_session(&app_state, "Bob", at(22, 0), None, Some("night")).await;
+        service.set_tracklist(&bob, submit(text)).await.unwrap();
+        let cue = service.cue_sheet(&bob).await.unwrap();
+        assert_eq!(cue.filename, "data.cue");
+        assert_eq!(cue.content, "\
+REM DATE 2024-01-05
+PERFORMER \"Bob\"
+TITLE \"Bob 2024-01-05 22:00 UTC\"
+FILE \"data.flac\" WAVE
+  TRACK 01 AUDIO
+    TITLE \"Never Grow Old\"
+    PERFORMER \"Floorplan\"
+    INDEX 01 30:00:00
+  TRACK 02 AUDIO
+    TITLE \"Minus\"
+    PERFORMER \"Robert Hood\"
+    INDEX 01 34:32:00
+");
+
+        // A per-DJ file starts with the set
+        let carol = add_session(&app_state, "Carol", at(23, 0), Some("/segments/Carol_20240105.flac"), Some("night")).await;
+        service.set_tracklist(&carol, submit(text)).await.unwrap();
+        let cue = service.cue_sheet(&carol).await.unwrap();
+        assert_eq!(cue.filename, "Carol_20240105.cue");
+        assert!(cue.content.contains("FILE \"Carol_20240105.flac\" WAVE\n  TRACK 01 AUDIO\n    TITLE \"Never Grow Old\"\n    PERFORMER \"Floorplan\"\n    INDEX 01 00:00:00\n"));
+
+        let dave = add_session(&app_state, "Dave", at(23, 30), None, None).await;
+        let none = service.cue_sheet(&dave).await.unwrap_err();
+        assert!(matches!(none.downcast_ref::<TracklistError>(), Some(TracklistError::NoTracklist)));
+        service.set_tracklist(&dave, submit("A - B\nC - D")).await.unwrap();
+        let untimed = service.cue_sheet(&dave).await.unwrap_err();
+        assert!(matches!(untimed.downcast_ref::<TracklistError>(), Some(TracklistError::NoStartTimes)));
+        let missing = service.cue_sheet("missing").await.unwrap_err();
+        assert!(matches!(missing.downcast_ref::<TracklistError>(), Some(TracklistError::SessionNotFound)));
+
+        assert_eq!(format_tracklist(&service.get_tracks(&dave).await.unwrap()), " 1. A - B\n 2. C - D\n");
+    }
+}
@@ -0,0 +1,205 @@
+use session_recorder_addon::{
+    audio::WaveformData,
+    models::{
+        AppState,
+        dj::CreateDjRequest,
+        waveform::{WaveformError, WaveformSlot},
+    },
+    services::{DjService, WaveformService},
+};
+use chrono::{DateTime, TimeZone, Utc};
+use sqlx::SqlitePool;
+use std::path::Path;
+use std::sync::Arc;
+
+mod common;
+
+#[cfg(test)]
+mod waveform_tests {
+    use super::*;
+
+    async fn setup_test_db(dir: &Path) -> Arc<AppState> {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+
+        let config = session_recorder_addon::models::AppConfig {
+            storage_config: common::local_storage(dir),
+            session_recorder_config: common::local_recorder(dir),
+            ..common::test_config()
+        };
+
+        Arc::new(AppState { db: pool, config })
+    }
+
+    fn at(hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 5, hour, minute, 0).unwrap()
+    }
+
+    /// audiowaveform version 2 file: header, then a min/max pair per channel per pixel
+    fn waveform_v2(bits: u8, sample_rate: u32, samples_per_pixel: u32, channels: u32, pixels: &[Vec<(i16, i16)>]) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&2u32.to_le_bytes());
+        data.extend_from_slice(&(if bits == 8 { 1u32 } else { 0 }).to_le_bytes());
+        data.extend_from_slice(&sample_rate.to_le_bytes());
+        data.extend_from_slice(&samples_per_pixel.to_le_bytes());
+        data.extend_from_slice(&(pixels.len() as u32).to_le_bytes());
+        data.extend_from_slice(&channels.to_le_bytes());
+        for pixel in pixels {
+            for &(min, max) in pixel {
+                if bits == 8 {
+                    data.push(min as i8 as u8);
+                    data.push(max as i8 as u8);
+                } else {
+                    data.extend_from_slice(&min.to_le_bytes());
+                    data.extend_from_slice(&max.to_le_bytes());
+                }
+            }
+        }
+        data
+    }
+
+    async fn add_session(
+        app_state: &Arc<AppState>,
+        dj_name: &str,
+        started_at: DateTime<Utc>,
+        ended_at: Option<DateTime<Utc>>,
+        recorder_session_id: Option<&str>,
+    ) -> String {
+        let dj = DjService::new(app_state.clone()).register_dj(CreateDjRequest {
+            name: dj_name.to_string(),
+            email: None,
+        }).await.unwrap();
+        let session_id = uuid::Uuid::new_v4().to_string();
+        sqlx::query(
+            "INSERT INTO sessions (id, dj_id, started_at, ended_at, upload_status, session_type, recorder_id, recorder_session_id) \
+             VALUES (?, ?, ?, ?, 'recording', 'solo', ?, ?)"
+        )
+        .bind(&session_id)
+        .bind(&dj.id)
+        .bind(started_at)
+        .bind(ended_at)
+        .bind(recorder_session_id.map(|_| "main"))
+        .bind(recorder_session_id)
+        .execute(&app_state.db)
+        .await
+        .unwrap();
+        session_id
+    }
+
+    #[test]
+    fn test_parse_v1_and_v2() {
+        // Version 1, 16 bit mono, two pixels
+        let mut v1 = Vec::new();
+        for field in [1u32, 0, 44_100, 441, 2] {
+            v1.extend_from_slice(&field.to_le_bytes());
+        }
+        for value in [-1000i16, 2000, -32768, 32767] {
+            v1.extend_from_slice(&value.to_le_bytes());
+        }
+        let waveform = WaveformData::parse(&v1).unwrap();
+        assert_eq!((waveform.channels, waveform.bits, waveform.len()), (1, 16, 2));
+        assert!((waveform.duration_seconds() - 0.02).abs() < 1e-9);
+        assert_eq!(waveform.peaks(0..2, 2), vec![(-1000.0 / 32768.0, 2000.0 / 32768.0), (-1.0, 32767.0 / 32768.0)]);
+
+        // Version 2, 8 bit stereo: channels merge to the widest outline
+        let v2 = waveform_v2(8, 48_000, 480, 2, &[vec![(-10, 20), (-64, 5)], vec![(0, 0), (-1, 127)]]);
+        let waveform = WaveformData::parse(&v2).unwrap();
+        assert_eq!((waveform.channels, waveform.bits, waveform.len()), (2, 8, 2));
+        assert_eq!(waveform.peaks(0..2, 10), vec![(-0.5, 20.0 / 128.0), (-1.0 / 128.0, 127.0 / 128.0)]);
+    }
+
+    #[test]
+    fn test_parse_rejects_broken_files() {
+        assert!(WaveformData::parse(&[0; 10]).is_err());
+
+        let mut unknown = waveform_v2(16, 44_100, 256, 1, &[vec![(0, 0)]]);
+        unknown[0] = 3;
+        assert!(WaveformData::parse(&unknown).is_err());
+
+        let mut truncated = waveform_v2(16, 44_100, 256, 1, &[vec![(0, 0)], vec![(0, 0)]]);
+        truncated.truncate(truncated.len() - 1);
+        assert!(WaveformData::parse(&truncated).is_err());
+
+        let no_rate = waveform_v2(16, 0, 256, 1, &[vec![(0, 0)]]);
+        assert!(WaveformData::parse(&no_rate).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_session_waveform_marks_every_set_on_the_recording() {
+        let dir = tempfile::tempdir().unwrap();
+        let app_state = setup_test_db(dir.path()).await;
+
+        // One pixel per second for 20 minutes; louder in the second half
+        let pixels: Vec<Vec<(i16, i16)>> = (0..1200)
+            .map(|second| if second < 600 { vec![(-8192, 8192)] } else { vec![(-16384, 16384)] })
+            .collect();
+        std::fs::create_dir_all(dir.path().join("recorder/main/sessions/night")).unwrap();
+        std::fs::write(
+            dir.path().join("recorder/main/sessions/night/waveform.dat"),
+            waveform_v2(16, 1000, 1000, 1, &pixels),
+        ).unwrap();
+        sqlx::query(
+            "INSERT INTO recorder_sessions (recorder_id, id, name, start_time, is_closed, keep, synced_at) \
+             VALUES ('main', 'night', 'night', ?, TRUE, FALSE, ?)"
+        )
+        .bind(at(22, 0))
+        .bind(Utc::now())
+        .execute(&app_state.db)
+        .await
+        .unwrap();
+
+        let alice = add_session(&app_state, "Alice", at(22, 0), Some(at(22, 10)), Some("night")).await;
+        let bob = add_session(&app_state, "Bob", at(22, 10), None, Some("night")).await;
+        add_session(&app_state, "Carol", at(22, 5), Some(at(22, 6)), None).await;
+
+        let service = WaveformService::new(app_state.clone());
+        let waveform = service.session_waveform(&bob, 100).await.unwrap();
+        assert_eq!(waveform.session_id, bob);
+        assert_eq!(waveform.recording_started_at, at(22, 0));
+        assert_eq!(waveform.duration_seconds, 1200.0);
+        assert_eq!(waveform.seconds_per_peak, 12.0);
+        assert_eq!(waveform.peaks.len(), 100);
+        assert_eq!((waveform.peaks[0].min, waveform.peaks[0].max), (-0.25, 0.25));
+        assert_eq!((waveform.peaks[99].min, waveform.peaks[99].max), (-0.5, 0.5));
+        assert_eq!(waveform.slots, vec![
+            WaveformSlot { session_id: alice.clone(), dj_name: "Alice".to_string(), start_seconds: 0.0, end_seconds: Some(600.0) },
+            WaveformSlot { session_id: bob, dj_name: "Bob".to_string(), start_seconds: 600.0, end_seconds: None },
+        ]);
+
+        // Fewer pixels than points are returned as they are, and the cap holds
+        assert_eq!(service.session_waveform(&alice, 5_000).await.unwrap().peaks.len(), 1200);
+        assert_eq!(service.session_waveform(&alice, 0).await.unwrap().peaks.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_session_waveform_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let app_state = setup_test_db(dir.path()).await;
+        let service = WaveformService::new(app_state.clone());
+
+        let not_found = service.session_waveform("missing", 100).await.unwrap_err();
+        assert!(matches!(not_found.downcast_ref::<WaveformError>(), Some(WaveformError::SessionNotFound)));
+
+        let unrecorded = add_session(&app_state, "Dave", at(23, 0), None, None).await;
+        let no_recording = service.session_waveform(&unrecorded, 100).await.unwrap_err();
+        assert!(matches!(no_recording.downcast_ref::<WaveformError>(), Some(WaveformError::NoRecording)));
+
+        sqlx::query(
+            "INSERT INTO recorder_sessions (recorder_id, id, name, start_time, is_closed, keep, synced_at) \
+             VALUES ('main', 'quiet', 'quiet', ?, TRUE, FALSE, ?)"
+        )
+        .bind(at(23, 0))
+        .bind(Utc::now())
+        .execute(&app_state.db)
+        .await
+        .unwrap();
+        let linked = add_session(&app_state, "Eve", at(23, 0), None, Some("quiet")).await;
+        let no_waveform = service.session_waveform(&linked, 100).await.unwrap_err();
+        assert!(matches!(no_waveform.downcast_ref::<WaveformError>(), Some(WaveformError::NoWaveform)));
+
+        std::fs::create_dir_all(dir.path().join("recorder/main/sessions/quiet")).unwrap();
+        std::fs::write(dir.path().join("recorder/main/sessions/quiet/waveform.dat"), b"not a waveform").unwrap();
+        let invalid = service.session_waveform(&linked, 100).await.unwrap_err();
+        assert!(matches!(invalid.downcast_ref::<WaveformError>(), Some(WaveformError::Invalid(_))));
+    }
+}
@@ -0,0 +1,216 @@
+use session_recorder_addon::{
+    models::{
+        AppState,
+        dj::CreateDjRequest,
+        CreateWebhookRequest, UpdateWebhookRequest, WebhookConfig, WebhookError, WebhookEventType,
+    },
+    services::{sign, DjService, WebhookService},
+};
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    routing::post,
+    Router,
+};
+use sqlx::sqlite::SqlitePoolOptions;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+mod common;
+
+#[cfg(test)]
+mod webhook_tests {
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct Listener {
+        requests: Arc<Mutex<Vec<(HeaderMap, String)>>>,
+        failures_left: Arc<AtomicUsize>,
+    }
+
+    async fn setup_test_db(dir: &Path) -> Arc<AppState> {
+        let pool = SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+
+        let config = session_recorder_addon::models::AppConfig {
+            storage_config: common::local_storage(dir),
+            webhook_config: WebhookConfig {
+                max_attempts: 3,
+                retry_seconds: 0,
+                timeout_seconds: 2,
+            },
+            ..common::test_config()
+        };
+
+        Arc::new(AppState { db: pool, config })
+    }
+
+    /// Local receiver recording each delivery; answers 500 while `failures_left` lasts
+    async fn start_listener() -> (String, Listener) {
+        async fn receive(State(listener): State<Listener>, headers: HeaderMap, body: String) -> StatusCode {
+            listener.requests.lock().unwrap().push((headers, body));
+            let failing = listener
+                .failures_left
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |left| left.checked_sub(1))
+                .is_ok();
+            if failing { StatusCode::INTERNAL_SERVER_ERROR } else { StatusCode::NO_CONTENT }
+        }
+
+        let listener = Listener::default();
+        let app = Router::new().route("/hook", post(receive)).with_state(listener.clone());
+        let tcp = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", tcp.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(tcp, app).await.unwrap();
+        });
+        (url, listener)
+    }
+
+    fn subscribe(url: &str, event_types: Vec<WebhookEventType>) -> CreateWebhookRequest {
+        CreateWebhookRequest {
+            url: format!("{}/hook", url),
+            event_types,
+            ..CreateWebhookRequest::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_events_are_signed_and_filtered() {
+        let dir = tempfile::tempdir().unwrap();
+        let (url, listener) = start_listener().await;
+        let app_state = setup_test_db(dir.path()).await;
+        let service = WebhookService::new(app_state.clone());
+
+        let registrations = service.create_subscription(subscribe(&url, vec![WebhookEventType::DjRegistered])).await.unwrap();
+        let draws = service.create_subscription(subscribe(&url, vec![WebhookEventType::Draw])).await.unwrap();
+        let secret = registrations.secret.clone().unwrap();
+        assert_eq!(secret.len(), 64);
+
+        DjService::new(app_state.clone()).register_dj(CreateDjRequest {
+            name: "Alice".to_string(),
+            email: None,
+        }).await.unwrap();
+
+        // Delivery runs in the background
+        for _ in 0..50 {
+            if !listener.requests.lock().unwrap().is_empty() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+
+        let requests = listener.requests.lock().unwrap().clone();
+        assert_eq!(requests.len(), 1);
+        let (headers, body) = &requests[0];
+        assert_eq!(headers["x-slotify-event"], "dj.registered");
+        assert_eq!(headers["content-type"], "application/json");
+        assert_eq!(headers["x-slotify-signature"].to_str().unwrap(), sign(&secret, body.as_bytes()));
+        assert!(headers["x-slotify-signature"].to_str().unwrap().starts_with("sha256="));
+
+        let envelope: serde_json::Value = serde_json::from_str(body).unwrap();
+        assert_eq!(envelope["type"], "dj.registered");
+        assert_eq!(envelope["data"]["dj_name"], "Alice");
+
+        let deliveries = service.get_deliveries(&registrations.id).await.unwrap();
+        assert_eq!(deliveries.len(), 1);
+        assert_eq!(deliveries[0].id, headers["x-slotify-delivery"].to_str().unwrap());
+        assert_eq!((deliveries[0].status.as_str(), deliveries[0].attempts, deliveries[0].response_status), ("delivered", 1, Some(204)));
+        assert!(service.get_deliveries(&draws.id).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_failed_deliveries_are_retried_then_given_up() {
+        let dir = tempfile::tempdir().unwrap();
+        let (url, listener) = start_listener().await;
+        let app_state = setup_test_db(dir.path()).await;
+        let service = WebhookService::new(app_state.clone());
+
+        // Recovers on the second attempt
+        listener.failures_left.store(1, Ordering::SeqCst);
+        let flaky = service.create_subscription(subscribe(&url, vec![])).await.unwrap();
+        let delivery = service.ping(&flaky.id).await.unwrap();
+        assert_eq!((delivery.status.as_str(), delivery.attempts, delivery.response_status), ("pending", 1, Some(500)));
+        assert_eq!(delivery.last_error.as_deref(), Some("HTTP 500 Internal Server Error"));
+        assert_eq!(service.deliver_due().await.unwrap(), 1);
+        let delivery = &service.get_deliveries(&flaky.id).await.unwrap()[0];
+        assert_eq!((delivery.status.as_str(), delivery.attempts), ("delivered", 2));
+        assert!(delivery.last_error.is_none());
+        assert_eq!(service.deliver_due().await.unwrap(), 0);
+
+        // Never answers successfully; dropped after max_attempts
+        let broken = service.create_subscription(CreateWebhookRequest {
+            url: format!("{}/gone", url),
+            ..CreateWebhookRequest::default()
+        }).await.unwrap();
+        service.ping(&broken.id).await.unwrap();
+        assert_eq!(service.deliver_due().await.unwrap(), 1);
+        assert_eq!(service.deliver_due().await.unwrap(), 1);
+        assert_eq!(service.deliver_due().await.unwrap(), 0);
+        let delivery = &service.get_deliveries(&broken.id).await.unwrap()[0];
+        assert_eq!((delivery.status.as_str(), delivery.attempts, delivery.response_status), ("failed", 3, Some(404)));
+        assert!(delivery.next_attempt_at.is_none());
+
+        // Both pings of the flaky subscription carried the same body and signature
+        let requests = listener.requests.lock().unwrap().clone();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].1, requests[1].1);
+        assert_eq!(requests[0].0["x-slotify-signature"], requests[1].0["x-slotify-signature"]);
+    }
+
+    #[test]
+    fn test_retry_delays_are_bounded() {
+        let config = WebhookConfig { max_attempts: 6, retry_seconds: 30, timeout_seconds: 10 };
+        assert_eq!(config.retry_delay(1), chrono::Duration::seconds(30));
+        assert_eq!(config.retry_delay(3), chrono::Duration::seconds(120));
+        assert_eq!(config.retry_delay(30), chrono::Duration::days(1));
+        assert_eq!(config.attempt_lease(), chrono::Duration::seconds(20));
+
+        // Absurd settings wait a day instead of overflowing
+        let huge = WebhookConfig { max_attempts: 6, retry_seconds: i64::MAX, timeout_seconds: u64::MAX };
+        assert_eq!(huge.retry_delay(5), chrono::Duration::days(1));
+        assert_eq!(huge.attempt_lease(), chrono::Duration::days(1));
+        let negative = WebhookConfig { retry_seconds: -5, ..huge };
+        assert_eq!(negative.retry_delay(1), chrono::Duration::zero());
+    }
+
+    #[tokio::test]
+    async fn test_manage_subscriptions() {
+        let dir = tempfile::tempdir().unwrap();
+        let app_state = setup_test_db(dir.path()).await;
+        let service = WebhookService::new(app_state.clone());
+
+        let invalid = service.create_subscription(subscribe("ftp://example.com", vec![])).await.unwrap_err();
+        assert!(matches!(invalid.downcast_ref::<WebhookError>(), Some(WebhookError::InvalidUrl(_))));
+        let ping = service.create_subscription(subscribe("https://example.com", vec![WebhookEventType::Ping])).await.unwrap_err();
+        assert!(matches!(ping.downcast_ref::<WebhookError>(), Some(WebhookError::PingSubscription)));
+
+        let created = service.create_subscription(CreateWebhookRequest {
+            url: "https://example.com/hook".to_string(),
+            secret: Some("s3cret".to_string()),
+            event_types: vec![WebhookEventType::SlotStarted, WebhookEventType::SlotEnded],
+            description: Some("Lighting desk".to_string()),
+        }).await.unwrap();
+        assert_eq!(created.secret.as_deref(), Some("s3cret"));
+
+        // The secret is only shown once
+        let listed = service.list_subscriptions().await.unwrap();
+        assert_eq!(listed.len(), 1);
+        assert!(listed[0].secret.is_none());
+        assert!(!serde_json::to_string(&listed).unwrap().contains("s3cret"));
+
+        let updated = service.update_subscription(&created.id, UpdateWebhookRequest {
+            enabled: Some(false),
+            event_types: Some(vec![]),
+            ..UpdateWebhookRequest::default()
+        }).await.unwrap();
+        assert!(!updated.enabled);
+        assert!(updated.event_types.is_empty());
+        assert_eq!(updated.description.as_deref(), Some("Lighting desk"));
+
+        service.delete_subscription(&created.id).await.unwrap();
+        let missing = service.get_subscription(&created.id).await.unwrap_err();
+        assert!(matches!(missing.downcast_ref::<WebhookError>(), Some(WebhookError::NotFound)));
+    }
+}